@@ -0,0 +1,5 @@
+// The templates are embedded with `include_dir!`, which doesn't tell cargo to rebuild when
+// they change.
+fn main() {
+    println!("cargo:rerun-if-changed=templates");
+}
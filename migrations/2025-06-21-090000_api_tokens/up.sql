@@ -0,0 +1,14 @@
+create table api_tokens (
+    id text primary key not null,
+    user_id text not null,
+    name text not null,
+    token_prefix text not null,
+    token_hash text unique not null,
+    scopes text not null,
+    last_used_at timestamp,
+    expires_at timestamp,
+    created_at timestamp not null default current_timestamp,
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+create index idx_api_tokens_user_id on api_tokens(user_id);
@@ -0,0 +1,10 @@
+create table email_suppressions (
+    id text primary key not null,
+    email text unique not null,
+    reason text not null,
+    provider text not null,
+    details text,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+    reactivated_at timestamp
+);
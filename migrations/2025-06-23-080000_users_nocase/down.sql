@@ -0,0 +1,2 @@
+drop index idx_users_name_nocase;
+drop index idx_users_email_nocase;
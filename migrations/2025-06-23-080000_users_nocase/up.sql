@@ -0,0 +1,3 @@
+-- Fails if existing rows differ only by case; run `tsumi dedupe-report` first to find them.
+create unique index idx_users_name_nocase on users(name collate nocase);
+create unique index idx_users_email_nocase on users(email collate nocase);
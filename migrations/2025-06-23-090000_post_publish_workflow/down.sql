@@ -0,0 +1,8 @@
+drop index idx_posts_status_published_at;
+
+alter table posts add column is_published boolean not null default false;
+
+update posts set is_published = true where status = 'published';
+
+alter table posts drop column published_at;
+alter table posts drop column status;
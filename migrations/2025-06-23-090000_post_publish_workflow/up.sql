@@ -0,0 +1,8 @@
+alter table posts add column status text not null default 'draft' check (status in ('draft', 'scheduled', 'published'));
+alter table posts add column published_at timestamp;
+
+update posts set status = 'published', published_at = created_at where is_published;
+
+alter table posts drop column is_published;
+
+create index idx_posts_status_published_at on posts(status, published_at);
@@ -0,0 +1,6 @@
+drop index idx_posts_slug_nocase;
+drop index idx_refresh_tokens_user_id;
+drop index idx_refresh_tokens_ip_address;
+
+alter table refresh_tokens drop column user_agent;
+alter table refresh_tokens drop column ip_address;
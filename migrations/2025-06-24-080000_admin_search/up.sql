@@ -0,0 +1,6 @@
+alter table refresh_tokens add column ip_address text;
+alter table refresh_tokens add column user_agent text;
+
+create index idx_refresh_tokens_ip_address on refresh_tokens(ip_address);
+create index idx_refresh_tokens_user_id on refresh_tokens(user_id);
+create index idx_posts_slug_nocase on posts(slug collate nocase);
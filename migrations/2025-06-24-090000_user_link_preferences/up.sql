@@ -0,0 +1 @@
+alter table users add column canonicalize_links boolean not null default true;
@@ -0,0 +1,20 @@
+create table comments (
+    id text primary key not null,
+    post_id text not null,
+    user_id text not null,
+    parent_id text,
+    root_id text,
+    body text not null,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+    deleted_at timestamp,
+    deleted_by text,
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (user_id) references users(id) on delete cascade,
+    foreign key (parent_id) references comments(id) on delete cascade,
+    foreign key (root_id) references comments(id) on delete cascade
+);
+
+create index idx_comments_post_root on comments(post_id, root_id, created_at);
+create index idx_comments_root_id on comments(root_id);
+create index idx_comments_user_created on comments(user_id, created_at);
@@ -0,0 +1,4 @@
+drop table backfill_jobs;
+drop table post_search_index;
+alter table posts drop column og_image_url;
+alter table posts drop column word_count;
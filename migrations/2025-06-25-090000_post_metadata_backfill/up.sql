@@ -0,0 +1,26 @@
+alter table posts add column word_count integer;
+alter table posts add column og_image_url text;
+
+create table post_search_index (
+    post_id text primary key not null,
+    terms text not null,
+    indexed_at timestamp not null default current_timestamp,
+    foreign key (post_id) references posts(id) on delete cascade
+);
+
+create table backfill_jobs (
+    id text primary key not null,
+    kind text not null check (kind in ('word_count', 'description', 'search_index', 'og_image')),
+    status text not null default 'pending' check (status in ('pending', 'running', 'paused', 'completed', 'failed')),
+    cursor text,
+    total integer not null default 0,
+    processed integer not null default 0,
+    error text,
+    created_by text,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+    finished_at timestamp,
+    foreign key (created_by) references users(id) on delete set null
+);
+
+create index idx_backfill_jobs_status on backfill_jobs(status, created_at);
@@ -0,0 +1,11 @@
+create table post_reactions (
+    post_id text not null,
+    user_id text not null,
+    kind text not null default 'like' check (kind in ('like', 'love', 'celebrate', 'insightful')),
+    created_at timestamp not null default current_timestamp,
+    primary key (post_id, user_id),
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+create index idx_post_reactions_user_created on post_reactions(user_id, created_at);
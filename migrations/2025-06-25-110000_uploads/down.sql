@@ -0,0 +1,2 @@
+alter table posts drop column cover_upload_id;
+drop table uploads;
@@ -0,0 +1,15 @@
+create table uploads (
+    id text primary key not null,
+    user_id text not null,
+    storage_key text not null unique,
+    content_type text not null,
+    byte_size integer not null,
+    sha256 text not null,
+    original_filename text,
+    created_at timestamp not null default current_timestamp,
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+create index idx_uploads_user_created on uploads(user_id, created_at);
+
+alter table posts add column cover_upload_id text references uploads(id) on delete set null;
@@ -0,0 +1,2 @@
+alter table users drop column blog_styles_disabled;
+drop table blog_styles;
@@ -0,0 +1,12 @@
+create table blog_styles (
+    id text primary key not null,
+    user_id text not null,
+    version integer not null,
+    css text not null,
+    head_html text not null,
+    created_at timestamp not null default current_timestamp,
+    foreign key (user_id) references users(id) on delete cascade,
+    unique (user_id, version)
+);
+
+alter table users add column blog_styles_disabled boolean not null default false;
@@ -0,0 +1,5 @@
+drop index idx_uploads_alt_text_pending;
+alter table uploads drop column alt_text_attempts;
+alter table uploads drop column alt_text_status;
+alter table uploads drop column alt_text_suggestion;
+alter table uploads drop column alt_text;
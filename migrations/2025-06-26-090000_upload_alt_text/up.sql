@@ -0,0 +1,7 @@
+alter table uploads add column alt_text text;
+alter table uploads add column alt_text_suggestion text;
+-- null when no suggestion was requested, otherwise 'pending', 'suggested' or 'failed'
+alter table uploads add column alt_text_status text;
+alter table uploads add column alt_text_attempts integer not null default 0;
+
+create index idx_uploads_alt_text_pending on uploads(alt_text_status) where alt_text_status = 'pending';
@@ -0,0 +1,23 @@
+create table post_fingerprints (
+    post_id text primary key not null,
+    user_id text not null,
+    simhash bigint not null,
+    -- The simhash split into four 16-bit bands. Posts within three bits of each other share at
+    -- least one band exactly, so candidates come from the band indexes instead of a full scan.
+    band0 integer not null,
+    band1 integer not null,
+    band2 integer not null,
+    band3 integer not null,
+    -- Set when the post nearly duplicates another author's post, for admins to review.
+    duplicate_of text,
+    updated_at timestamp not null default current_timestamp,
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (user_id) references users(id) on delete cascade,
+    foreign key (duplicate_of) references posts(id) on delete set null
+);
+
+create index post_fingerprints_band0 on post_fingerprints (band0);
+create index post_fingerprints_band1 on post_fingerprints (band1);
+create index post_fingerprints_band2 on post_fingerprints (band2);
+create index post_fingerprints_band3 on post_fingerprints (band3);
+create index post_fingerprints_duplicate_of on post_fingerprints (duplicate_of) where duplicate_of is not null;
@@ -0,0 +1,14 @@
+create table post_locks (
+    post_id text primary key not null,
+    user_id text not null,
+    -- Identifies the editor tab or device holding the lock, chosen by the client.
+    session_id text not null,
+    acquired_at timestamp not null,
+    expires_at timestamp not null,
+    takeover_session_id text,
+    takeover_user_id text,
+    takeover_requested_at timestamp,
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (user_id) references users(id) on delete cascade,
+    foreign key (takeover_user_id) references users(id) on delete set null
+);
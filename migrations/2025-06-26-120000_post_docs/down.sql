@@ -0,0 +1,2 @@
+drop table post_doc_updates;
+drop table post_docs;
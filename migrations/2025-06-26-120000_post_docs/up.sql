@@ -0,0 +1,20 @@
+-- The compacted collaborative document for a post, as a single Yjs update.
+create table post_docs (
+    post_id text primary key not null,
+    state blob not null,
+    compacted_at timestamp not null,
+    foreign key (post_id) references posts(id) on delete cascade
+);
+
+-- Yjs updates received from editors since the last compaction.
+create table post_doc_updates (
+    id text primary key not null,
+    post_id text not null,
+    user_id text,
+    data blob not null,
+    created_at timestamp not null,
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (user_id) references users(id) on delete set null
+);
+
+create index idx_post_doc_updates_post_id on post_doc_updates(post_id);
@@ -0,0 +1,4 @@
+drop table notification_deliveries;
+alter table users drop column quiet_hours_end;
+alter table users drop column quiet_hours_start;
+alter table users drop column timezone;
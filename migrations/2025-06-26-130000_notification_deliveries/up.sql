@@ -0,0 +1,25 @@
+alter table users add column timezone text not null default 'UTC';
+-- Minutes past local midnight; notifications wait until the end of the window. The window
+-- may wrap past midnight (start > end). Both are set or neither is.
+alter table users add column quiet_hours_start integer;
+alter table users add column quiet_hours_end integer;
+
+create table notification_deliveries (
+    id text primary key not null,
+    user_id text not null,
+    channel text not null,
+    -- Events with the same key are folded into one pending delivery.
+    coalesce_key text not null,
+    kind text not null,
+    post_id text not null,
+    -- JSON array of the names behind the events, newest first, deduplicated.
+    actors text not null,
+    event_count integer not null default 1,
+    deliver_after timestamp not null,
+    created_at timestamp not null,
+    foreign key (user_id) references users(id) on delete cascade,
+    foreign key (post_id) references posts(id) on delete cascade
+);
+
+create unique index notification_deliveries_key on notification_deliveries(user_id, channel, coalesce_key);
+create index notification_deliveries_due on notification_deliveries(deliver_after);
@@ -0,0 +1,10 @@
+create table follows (
+    follower_id text not null,
+    followee_id text not null,
+    created_at timestamp not null,
+    primary key (follower_id, followee_id),
+    foreign key (follower_id) references users(id) on delete cascade,
+    foreign key (followee_id) references users(id) on delete cascade
+);
+
+create index follows_followee on follows(followee_id);
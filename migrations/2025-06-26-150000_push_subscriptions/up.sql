@@ -0,0 +1,13 @@
+create table push_subscriptions (
+    id text primary key not null,
+    user_id text not null,
+    endpoint text not null unique,
+    p256dh text not null,
+    auth text not null,
+    expires_at timestamp,
+    user_agent text,
+    created_at timestamp not null,
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+create index push_subscriptions_user on push_subscriptions(user_id);
@@ -0,0 +1,17 @@
+create table notifications (
+    id text primary key not null,
+    user_id text not null,
+    kind text not null,
+    actor_id text,
+    post_id text,
+    comment_id text,
+    read_at timestamp,
+    created_at timestamp not null,
+    foreign key (user_id) references users(id) on delete cascade,
+    foreign key (actor_id) references users(id) on delete cascade,
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (comment_id) references comments(id) on delete cascade
+);
+
+create index notifications_user_created on notifications(user_id, created_at);
+create index notifications_user_unread on notifications(user_id) where read_at is null;
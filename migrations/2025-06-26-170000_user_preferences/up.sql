@@ -0,0 +1,7 @@
+create table user_preferences (
+    user_id text primary key not null,
+    weekly_digest boolean not null default 1,
+    digest_sent_at timestamp,
+    updated_at timestamp not null,
+    foreign key (user_id) references users(id) on delete cascade
+);
@@ -0,0 +1,14 @@
+create table audit_logs (
+    id text primary key not null,
+    event text not null,
+    user_id text,
+    actor_id text,
+    detail text,
+    ip_address text,
+    user_agent text,
+    created_at timestamp not null
+);
+
+create index audit_logs_user_created on audit_logs(user_id, created_at);
+create index audit_logs_event_created on audit_logs(event, created_at);
+create index audit_logs_created on audit_logs(created_at);
@@ -0,0 +1,29 @@
+create table webhooks (
+    id text primary key not null,
+    user_id text not null,
+    url text not null,
+    secret text not null,
+    events text not null,
+    created_at timestamp not null,
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+create index webhooks_user on webhooks(user_id);
+
+create table webhook_deliveries (
+    id text primary key not null,
+    webhook_id text not null,
+    event text not null,
+    payload text not null,
+    status text not null,
+    attempts integer not null default 0,
+    next_attempt_at timestamp not null,
+    response_status integer,
+    last_error text,
+    created_at timestamp not null,
+    delivered_at timestamp,
+    foreign key (webhook_id) references webhooks(id) on delete cascade
+);
+
+create index webhook_deliveries_due on webhook_deliveries(status, next_attempt_at);
+create index webhook_deliveries_webhook on webhook_deliveries(webhook_id, created_at);
@@ -0,0 +1,9 @@
+create table avatar_sources (
+    id text primary key not null,
+    user_id text not null,
+    upstream_url text not null,
+    created_at timestamp not null,
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+create unique index avatar_sources_upstream on avatar_sources(user_id, upstream_url);
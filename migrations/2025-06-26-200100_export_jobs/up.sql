@@ -0,0 +1,16 @@
+create table export_jobs (
+    id text primary key not null,
+    user_id text not null,
+    status text not null,
+    storage_key text,
+    size_bytes bigint,
+    error text,
+    created_at timestamp not null,
+    started_at timestamp,
+    finished_at timestamp,
+    expires_at timestamp,
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+create index export_jobs_user on export_jobs(user_id, created_at);
+create index export_jobs_status on export_jobs(status, created_at);
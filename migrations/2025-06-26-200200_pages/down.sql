@@ -0,0 +1,2 @@
+drop table page_versions;
+drop table pages;
@@ -0,0 +1,28 @@
+create table pages (
+    id text primary key not null,
+    slug text not null unique,
+    title text not null,
+    description text not null default '',
+    content text not null,
+    published_at timestamp,
+    created_by text,
+    created_at timestamp not null,
+    updated_at timestamp not null,
+    foreign key (created_by) references users(id) on delete set null
+);
+
+create table page_versions (
+    id text primary key not null,
+    page_id text not null,
+    user_id text,
+    title text not null,
+    content text not null,
+    description text not null,
+    commit_hash text not null,
+    commit_message text not null,
+    created_at timestamp not null,
+    foreign key (page_id) references pages(id) on delete cascade,
+    foreign key (user_id) references users(id) on delete set null
+);
+
+create index page_versions_page on page_versions(page_id, created_at);
@@ -0,0 +1,3 @@
+alter table user_preferences drop column onboarding_dismissed_at;
+
+drop table onboarding_steps;
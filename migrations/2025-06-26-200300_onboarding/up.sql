@@ -0,0 +1,22 @@
+create table onboarding_steps (
+    user_id text not null,
+    step text not null,
+    completed_at timestamp not null,
+    primary key (user_id, step),
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+alter table user_preferences add column onboarding_dismissed_at timestamp;
+
+-- Existing users keep the progress they've already made.
+insert into onboarding_steps (user_id, step, completed_at)
+select id, 'verify_email', updated_at from users where email_verified;
+
+insert into onboarding_steps (user_id, step, completed_at)
+select distinct user_id, 'set_avatar', current_timestamp from accounts where provider = 'github';
+
+insert into onboarding_steps (user_id, step, completed_at)
+select user_id, 'first_post', min(published_at) from posts where status = 'published' and published_at is not null group by user_id;
+
+insert into onboarding_steps (user_id, step, completed_at)
+select follower_id, 'follow_authors', max(created_at) from follows group by follower_id having count(*) >= 3;
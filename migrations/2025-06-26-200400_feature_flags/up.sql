@@ -0,0 +1,7 @@
+create table feature_flags (
+    name text primary key not null,
+    enabled boolean not null,
+    updated_by text,
+    updated_at timestamp not null,
+    foreign key (updated_by) references users(id) on delete set null
+);
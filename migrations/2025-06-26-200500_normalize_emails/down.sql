@@ -0,0 +1,2 @@
+-- The original spelling of each address isn't kept, so there is nothing to restore.
+select 1;
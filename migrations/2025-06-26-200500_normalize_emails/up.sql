@@ -0,0 +1,2 @@
+-- Addresses are stored trimmed and lowercased from now on; bring existing rows in line.
+update users set email = lower(trim(email)) where email != lower(trim(email));
@@ -0,0 +1,5 @@
+drop index refresh_tokens_family_id;
+
+delete from refresh_tokens where replaced_by is not null;
+alter table refresh_tokens drop column replaced_by;
+alter table refresh_tokens drop column family_id;
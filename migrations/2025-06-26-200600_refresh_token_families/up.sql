@@ -0,0 +1,7 @@
+-- Every sign-in starts a family; each refresh adds the next token to it and points the
+-- previous one at its replacement. Existing sessions become families of one.
+alter table refresh_tokens add column family_id text not null default '';
+alter table refresh_tokens add column replaced_by text;
+update refresh_tokens set family_id = id;
+
+create index refresh_tokens_family_id on refresh_tokens (family_id);
@@ -0,0 +1,3 @@
+drop table user_devices;
+
+alter table refresh_tokens drop column device;
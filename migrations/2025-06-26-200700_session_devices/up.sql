@@ -0,0 +1,10 @@
+alter table refresh_tokens add column device text;
+
+create table user_devices (
+    user_id text not null,
+    label text not null,
+    first_seen_at timestamp not null,
+    last_seen_at timestamp not null,
+    primary key (user_id, label),
+    foreign key (user_id) references users(id) on delete cascade
+);
@@ -0,0 +1 @@
+alter table refresh_tokens add column persistent boolean not null default 1;
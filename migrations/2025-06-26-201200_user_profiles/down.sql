@@ -0,0 +1,5 @@
+alter table users drop column show_email;
+alter table users drop column profile_private;
+alter table users drop column location;
+alter table users drop column website;
+alter table users drop column bio;
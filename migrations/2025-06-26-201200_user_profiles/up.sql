@@ -0,0 +1,5 @@
+alter table users add column bio text not null default '';
+alter table users add column website text;
+alter table users add column location text;
+alter table users add column profile_private boolean not null default false;
+alter table users add column show_email boolean not null default false;
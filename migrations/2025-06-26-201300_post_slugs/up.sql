@@ -0,0 +1,11 @@
+create table post_slugs (
+    user_id text not null,
+    slug text not null,
+    post_id text not null,
+    created_at timestamp not null,
+    primary key (user_id, slug),
+    foreign key (user_id) references users(id) on delete cascade,
+    foreign key (post_id) references posts(id) on delete cascade
+);
+
+create index post_slugs_post on post_slugs(post_id);
@@ -0,0 +1,13 @@
+create table post_collaborators (
+    post_id text not null,
+    user_id text not null,
+    role text not null,
+    invited_by text,
+    created_at timestamp not null,
+    primary key (post_id, user_id),
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (user_id) references users(id) on delete cascade,
+    foreign key (invited_by) references users(id) on delete set null
+);
+
+create index post_collaborators_user on post_collaborators(user_id);
@@ -0,0 +1,2 @@
+drop table series_posts;
+drop table series;
@@ -0,0 +1,21 @@
+create table series (
+    id text primary key not null,
+    user_id text not null,
+    title text not null,
+    slug text not null,
+    description text not null default '',
+    created_at timestamp not null,
+    updated_at timestamp not null,
+    unique (user_id, slug),
+    foreign key (user_id) references users(id) on delete cascade
+);
+
+create table series_posts (
+    post_id text primary key not null,
+    series_id text not null,
+    position integer not null,
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (series_id) references series(id) on delete cascade
+);
+
+create index series_posts_series on series_posts(series_id, position);
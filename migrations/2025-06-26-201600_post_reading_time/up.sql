@@ -0,0 +1,4 @@
+alter table posts add column reading_minutes integer;
+
+-- At 200 words a minute, rounded up, and never under a minute.
+update posts set reading_minutes = max(1, (word_count + 199) / 200) where word_count is not null;
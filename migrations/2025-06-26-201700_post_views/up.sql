@@ -0,0 +1,9 @@
+create table post_views (
+    post_id text not null,
+    day date not null,
+    views integer not null default 0,
+    primary key (post_id, day),
+    foreign key (post_id) references posts(id) on delete cascade
+);
+
+create index post_views_day on post_views(day);
@@ -0,0 +1,16 @@
+create table idempotency_keys (
+    id text primary key not null,
+    -- The user who sent the request, or 'anonymous'.
+    scope text not null,
+    idempotency_key text not null,
+    request_hash text not null,
+    -- Null while the first request is still being handled.
+    status_code integer,
+    content_type text,
+    body blob,
+    created_at timestamp not null,
+    expires_at timestamp not null
+);
+
+create unique index idempotency_keys_scope_key on idempotency_keys(scope, idempotency_key);
+create index idempotency_keys_expires_at on idempotency_keys(expires_at);
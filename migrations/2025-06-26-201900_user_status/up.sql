@@ -0,0 +1,2 @@
+-- 'active' or 'suspended'. Suspended users can't sign in and their credentials stop working.
+alter table users add column status text not null default 'active';
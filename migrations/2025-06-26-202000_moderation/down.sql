@@ -0,0 +1,3 @@
+drop table reports;
+alter table comments drop column moderation_status;
+alter table posts drop column moderation_status;
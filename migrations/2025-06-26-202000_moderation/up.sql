@@ -0,0 +1,26 @@
+-- 'visible', or 'hidden' by a moderator, which keeps it out of everything public.
+alter table posts add column moderation_status text not null default 'visible';
+alter table comments add column moderation_status text not null default 'visible';
+
+create table reports (
+    id text primary key not null,
+    reporter_id text not null,
+    post_id text not null,
+    -- Set when a comment on the post was reported rather than the post itself.
+    comment_id text,
+    reason text not null,
+    details text not null default '',
+    -- 'open' until a moderator hides the content ('actioned') or lets it stand ('dismissed').
+    status text not null default 'open',
+    reviewed_by text,
+    reviewed_at timestamp,
+    created_at timestamp not null,
+    foreign key (reporter_id) references users(id) on delete cascade,
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (comment_id) references comments(id) on delete cascade,
+    foreign key (reviewed_by) references users(id) on delete set null
+);
+
+create index reports_status on reports(status, created_at);
+create index reports_post on reports(post_id);
+create index reports_comment on reports(comment_id);
@@ -0,0 +1,26 @@
+create table reports_old (
+    id text primary key not null,
+    reporter_id text not null,
+    post_id text not null,
+    comment_id text,
+    reason text not null,
+    details text not null default '',
+    status text not null default 'open',
+    reviewed_by text,
+    reviewed_at timestamp,
+    created_at timestamp not null,
+    foreign key (reporter_id) references users(id) on delete cascade,
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (comment_id) references comments(id) on delete cascade,
+    foreign key (reviewed_by) references users(id) on delete set null
+);
+insert into reports_old select * from reports where reporter_id is not null;
+drop table reports;
+alter table reports_old rename to reports;
+
+create index reports_status on reports(status, created_at);
+create index reports_post on reports(post_id);
+create index reports_comment on reports(comment_id);
+
+update comments set moderation_status = 'hidden' where moderation_status in ('pending', 'shadowed');
+alter table users drop column comment_moderation;
@@ -0,0 +1,28 @@
+-- Where new comments by the account start out: 'visible', or 'pending' or 'shadowed' when the
+-- spam check flagged its signup.
+alter table users add column comment_moderation text not null default 'visible';
+
+-- Reports the spam check files have no reporter.
+create table reports_new (
+    id text primary key not null,
+    reporter_id text,
+    post_id text not null,
+    comment_id text,
+    reason text not null,
+    details text not null default '',
+    status text not null default 'open',
+    reviewed_by text,
+    reviewed_at timestamp,
+    created_at timestamp not null,
+    foreign key (reporter_id) references users(id) on delete cascade,
+    foreign key (post_id) references posts(id) on delete cascade,
+    foreign key (comment_id) references comments(id) on delete cascade,
+    foreign key (reviewed_by) references users(id) on delete set null
+);
+insert into reports_new select * from reports;
+drop table reports;
+alter table reports_new rename to reports;
+
+create index reports_status on reports(status, created_at);
+create index reports_post on reports(post_id);
+create index reports_comment on reports(comment_id);
@@ -0,0 +1,16 @@
+create table email_outbox (
+    id text primary key not null,
+    recipient text not null,
+    subject text not null,
+    text_body text not null,
+    html_body text,
+    priority text not null,
+    status text not null,
+    attempts integer not null default 0,
+    next_attempt_at timestamp not null,
+    last_error text,
+    created_at timestamp not null,
+    sent_at timestamp
+);
+
+create index email_outbox_due on email_outbox(status, priority, next_attempt_at);
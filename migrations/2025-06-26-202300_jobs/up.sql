@@ -0,0 +1,9 @@
+create table jobs (
+    name text primary key not null,
+    next_run_at timestamp,
+    locked_by text,
+    locked_until timestamp,
+    last_started_at timestamp,
+    last_finished_at timestamp,
+    last_error text
+);
@@ -0,0 +1,4 @@
+alter table jobs add column locked_by text;
+alter table jobs add column locked_until timestamp;
+
+drop table job_locks;
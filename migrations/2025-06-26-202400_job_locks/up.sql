@@ -0,0 +1,10 @@
+create table job_locks (
+    name text primary key not null,
+    holder text not null,
+    acquired_at timestamp not null,
+    expires_at timestamp not null
+);
+
+-- Scheduled jobs take their locks from job_locks like every other worker.
+alter table jobs drop column locked_by;
+alter table jobs drop column locked_until;
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use crate::config::{Config, ConfigHandle};
+use crate::db::write::WriteLock;
+use crate::http::assets::AssetManifest;
+use crate::services;
+use crate::services::alt_text::AltTextWorker;
+use crate::services::avatars::AvatarProxy;
+use crate::services::backfill::BackfillWorker;
+use crate::services::backups::BackupWorker;
+use crate::services::collab::{CollabCompactor, CollabHub};
+use crate::services::config_watcher::{ConfigWatcher, LogFilter};
+use crate::services::digest::DigestWorker;
+use crate::services::email::EmailService;
+use crate::services::email_queue::EmailQueue;
+use crate::services::exports::ExportWorker;
+use crate::services::http_client::HttpClient;
+use crate::services::job_locks::Leases;
+use crate::services::jwt::JwtService;
+use crate::services::lifecycle::ServiceRegistry;
+use crate::services::links::LinkRules;
+use crate::services::live::LiveHub;
+use crate::services::notifications::NotificationDispatcher;
+use crate::services::push::PushService;
+use crate::services::retention::RetentionPruner;
+use crate::services::scheduled_posts::ScheduledPublisher;
+use crate::services::scheduler::Scheduler;
+use crate::services::sitemap::SitemapCache;
+use crate::services::templates::{TemplateWatcher, Templates};
+use crate::services::token_purge::TokenPurger;
+use crate::services::views::ViewCounter;
+use crate::services::webhooks::WebhookDispatcher;
+use crate::state::{AppState, DbPool};
+
+/// Everything the router needs, with the background services registered in `state.services`
+/// but not yet started. Without a `log_filter` the config watcher is left out, as it would
+/// have no log level to change.
+pub fn build_state(config: &Config, pool: DbPool, log_filter: Option<LogFilter>) -> AppState {
+    let live_config = ConfigHandle::new(config.clone());
+    let assets = Arc::new(AssetManifest::build("static", config.static_asset_hashing()));
+    let templates = Templates::from_config(config, assets.clone());
+
+    let leases = Leases::new(config, pool.clone());
+    let mailer = EmailService::new(config, pool.clone());
+    let email_queue = EmailQueue::new(config, pool.clone(), mailer, leases.clone());
+    let storage = services::storage::from_config(config);
+    let cache = services::cache::from_config(config);
+    let writes = WriteLock::new();
+    let sessions = services::sessions::from_config(config, pool.clone(), writes.clone());
+    let push = PushService::new(config);
+    let http = HttpClient::new(config);
+
+    let mut registry = ServiceRegistry::new();
+    if let Some(log_filter) = log_filter {
+        registry.register(Arc::new(ConfigWatcher::new(live_config.clone(), log_filter)));
+    }
+    if templates.dir().is_some() {
+        registry.register(Arc::new(TemplateWatcher::new(templates.clone())));
+    }
+    registry.register(Arc::new(email_queue.clone()));
+
+    let mut scheduler = Scheduler::new(config, pool.clone(), leases.clone());
+    scheduler.register(Arc::new(ScheduledPublisher::new(config, pool.clone(), cache.clone())));
+    scheduler.register(Arc::new(DigestWorker::new(config, pool.clone(), email_queue.clone(), templates.clone())));
+    scheduler.register(Arc::new(TokenPurger::new(config, pool.clone())));
+    let retention = RetentionPruner::new(config, pool.clone(), writes.clone());
+    let retention_handle = retention.handle();
+    scheduler.register(Arc::new(retention));
+    let backups = BackupWorker::new(config, pool.clone());
+    let backups_handle = backups.handle();
+    scheduler.register(Arc::new(backups));
+    let scheduler = Arc::new(scheduler);
+    registry.register(scheduler.clone());
+
+    registry.register(Arc::new(BackfillWorker::new(config, pool.clone(), writes.clone(), leases.clone())));
+    registry.register(Arc::new(CollabCompactor::new(config, pool.clone(), cache.clone(), leases.clone())));
+    registry.register(Arc::new(NotificationDispatcher::new(config, pool.clone(), email_queue.clone(), push.clone(), leases.clone())));
+    registry.register(Arc::new(WebhookDispatcher::new(config, pool.clone(), http.clone(), leases.clone())));
+    registry.register(Arc::new(ExportWorker::new(config, pool.clone(), storage.clone(), leases.clone())));
+    let views = Arc::new(ViewCounter::new(config, pool.clone(), writes.clone()));
+    registry.register(views.clone());
+    let sitemaps = Arc::new(SitemapCache::new(config, pool.clone()));
+    registry.register(sitemaps.clone());
+    if let Some(captioner) = services::alt_text::from_config(config) {
+        registry.register(Arc::new(AltTextWorker::new(config, pool.clone(), storage.clone(), captioner, leases.clone())));
+    }
+
+    AppState {
+        templates,
+        db_pool: pool,
+        writes,
+        config: live_config,
+        jwt: Arc::new(JwtService::new(config)),
+        email_queue,
+        link_rules: Arc::new(LinkRules::load(config.link_rules_file())),
+        storage,
+        avatars: Arc::new(AvatarProxy::new(config, cache.clone())),
+        captcha: services::captcha::from_config(config, http.clone()),
+        spam: services::spam::from_config(config, http.clone()),
+        http,
+        cache,
+        sessions,
+        collab: Arc::new(CollabHub::new()),
+        live: Arc::new(LiveHub::new()),
+        push,
+        retention: retention_handle,
+        backups: backups_handle,
+        scheduler,
+        views,
+        sitemaps,
+        services: Arc::new(registry),
+        assets,
+    }
+}
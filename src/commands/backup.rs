@@ -0,0 +1,11 @@
+use crate::commands::CommandResult;
+use crate::config::Config;
+use crate::services::backups::{BackupInfo, Backups};
+use crate::state::DbPool;
+
+/// Takes a backup now, alongside the scheduled ones, and rotates out the oldest.
+pub async fn run(config: &Config, pool: &DbPool) -> CommandResult<BackupInfo> {
+    let backup = Backups::from_config(config).create(pool).await?;
+    println!("Backed up to {} ({} bytes)", backup.name, backup.size_bytes);
+    Ok(backup)
+}
@@ -0,0 +1,83 @@
+use diesel::prelude::*;
+use validator::Validate;
+
+use crate::commands::CommandResult;
+use crate::config::Config;
+use crate::db::models::onboarding_step::ONBOARDING_STEP_VERIFY_EMAIL;
+use crate::db::models::user_model::{NewUser, UserModel};
+use crate::db::schema::users;
+use crate::db::write::write;
+use crate::handlers::auth::SignUpRequest;
+use crate::services::normalize::{self, Normalize};
+use crate::services::onboarding;
+use crate::services::passwords::hash_password;
+use crate::utils::generate_token;
+
+/// Makes the user with `email` an administrator, creating them first when nobody has that
+/// address. New accounts need a `name`; without a `password` a random one is generated and
+/// printed once. Either way the address is marked verified, so the admin can sign in at once.
+pub fn run(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    email: &str,
+    name: Option<&str>,
+    password: Option<&str>,
+) -> CommandResult<UserModel> {
+    let email = &normalize::email(email);
+    if let Some(user) = UserModel::by_email(conn, email)? {
+        if user.deleted_at.is_some() {
+            return Err(format!("{} belongs to a deleted account", email).into());
+        }
+        if name.is_some() || password.is_some() {
+            println!("{} already exists; --name and --password are ignored", email);
+        }
+        write(conn, |conn| {
+            UserModel::set_admin(conn, &user.id, true)?;
+            UserModel::mark_email_verified(conn, &user.id)?;
+            onboarding::complete(conn, &user.id, ONBOARDING_STEP_VERIFY_EMAIL)
+        })?;
+        println!("{} ({}) is now an admin", user.name, user.id);
+        return Ok(user);
+    }
+
+    let name = name.ok_or("Creating a new admin needs --name")?;
+    let generated = password.is_none().then(generate_token);
+    let password = password.or(generated.as_deref()).unwrap_or_default();
+
+    let request = SignUpRequest {
+        name: name.to_string(),
+        email: email.to_string(),
+        password: password.to_string(),
+        captcha_token: None,
+    }
+    .normalize()?;
+    request.validate()?;
+    if UserModel::by_name(conn, &request.name)?.is_some() {
+        return Err(format!("Username {} is already taken", request.name).into());
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let hashed = hash_password(config, &id, password)?;
+    let user = write(conn, |conn| {
+        let user = diesel::insert_into(users::table)
+            .values(&NewUser {
+                id: id.clone(),
+                name: request.name,
+                email: request.email,
+                password: hashed,
+                email_verified: true,
+                created_at: chrono::Utc::now().naive_utc(),
+            })
+            .returning(UserModel::as_returning())
+            .get_result(conn)?;
+        UserModel::set_admin(conn, &id, true)?;
+        onboarding::complete(conn, &id, ONBOARDING_STEP_VERIFY_EMAIL)?;
+        Ok::<_, diesel::result::Error>(user)
+    })?;
+
+    println!("Created admin {} ({})", user.name, user.id);
+    if let Some(generated) = generated {
+        println!("Password: {}", generated);
+    }
+    Ok(user)
+}
@@ -0,0 +1,38 @@
+use diesel::SqliteConnection;
+
+use crate::db::models::user_model::UserModel;
+use crate::db::queries::users::DuplicateField;
+
+/// Prints users whose names or emails collide case-insensitively, which would make the
+/// `users_nocase` migration fail. Nothing is modified; each group has to be resolved by hand.
+///
+/// Returns the number of colliding groups found.
+pub fn run(conn: &mut SqliteConnection) -> Result<usize, diesel::result::Error> {
+    let mut groups = 0;
+
+    for (label, field) in [("name", DuplicateField::Name), ("email", DuplicateField::Email)] {
+        let duplicates = UserModel::case_duplicates(conn, field)?;
+        if duplicates.is_empty() {
+            println!("No case-insensitive duplicate {}s", label);
+            continue;
+        }
+
+        println!("{} case-insensitive duplicate {} group(s):", duplicates.len(), label);
+        for (key, users) in &duplicates {
+            println!("  {}", key);
+            for user in users {
+                println!(
+                    "    {}  name={}  email={}  created_at={}{}",
+                    user.id,
+                    user.name,
+                    user.email,
+                    user.created_at,
+                    if user.deleted_at.is_some() { "  (deleted)" } else { "" },
+                );
+            }
+        }
+        groups += duplicates.len();
+    }
+
+    Ok(groups)
+}
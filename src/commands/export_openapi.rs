@@ -0,0 +1,18 @@
+use std::path::Path;
+
+use crate::commands::CommandResult;
+use crate::config::Config;
+use crate::http::openapi;
+
+/// Writes the OpenAPI document to `output`, or to stdout without one.
+pub fn run(config: &Config, output: Option<&Path>) -> CommandResult<()> {
+    let document = serde_json::to_string_pretty(&openapi::document(config.public_url(), config.access_cookie_name()))?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, document + "\n")?;
+            eprintln!("Wrote {}", path.display());
+        }
+        None => println!("{}", document),
+    }
+    Ok(())
+}
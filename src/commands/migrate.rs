@@ -0,0 +1,17 @@
+use diesel::SqliteConnection;
+use diesel_migrations::MigrationHarness;
+
+use crate::commands::CommandResult;
+use crate::MIGRATIONS;
+
+/// Applies every migration the database hasn't seen yet, printing each one.
+pub fn run(conn: &mut SqliteConnection) -> CommandResult<usize> {
+    let applied = conn.run_pending_migrations(MIGRATIONS)?;
+    if applied.is_empty() {
+        println!("Database is up to date");
+    }
+    for version in &applied {
+        println!("Applied {}", version);
+    }
+    Ok(applied.len())
+}
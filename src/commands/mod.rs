@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+pub mod backup;
+pub mod create_admin;
+pub mod dedupe_report;
+pub mod export_openapi;
+pub mod migrate;
+pub mod purge_tokens;
+pub mod restore;
+pub mod seed;
+
+pub type CommandResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Every command reads the same environment and `.env` as the server.
+#[derive(Parser, Debug)]
+#[command(name = "tsumi", version, about = "A blogging platform")]
+pub struct Cli {
+    /// Runs the server when omitted.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run the web server and background services.
+    Serve,
+    /// Apply pending database migrations.
+    Migrate,
+    /// Make a user an administrator, creating the account if needed.
+    CreateAdmin {
+        #[arg(long)]
+        email: String,
+        /// Username for a new account.
+        #[arg(long)]
+        name: Option<String>,
+        /// Password for a new account. A random one is printed when omitted.
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Delete expired refresh, email verification and password reset tokens.
+    PurgeTokens,
+    /// Print the OpenAPI document for the JSON APIs.
+    ExportOpenapi {
+        /// Write to this file instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// List users whose names or emails collide case-insensitively. Exits 1 when any do.
+    DedupeReport,
+    /// Back up the database now, deleting the oldest backups past `BACKUP_KEEP`.
+    Backup,
+    /// Fill a development database with sample users, posts, comments and follows.
+    Seed,
+    /// Replace the database with a backup. Stop the server first.
+    Restore {
+        /// The backup's name, as listed in the backup manifest.
+        #[arg(required_unless_present = "latest")]
+        name: Option<String>,
+        /// Restore the newest backup.
+        #[arg(long, conflicts_with = "name")]
+        latest: bool,
+    },
+}
@@ -0,0 +1,17 @@
+use chrono::Utc;
+use diesel::SqliteConnection;
+
+use crate::commands::CommandResult;
+use crate::services::token_purge;
+
+/// Deletes refresh tokens, email verification links and password reset links past their
+/// expiry, as the server's `token-purge` job does. Sessions kept in Redis expire on their own
+/// and aren't touched.
+pub fn run(conn: &mut SqliteConnection) -> CommandResult<usize> {
+    let purged = token_purge::purge_expired(conn, Utc::now().naive_utc())?;
+
+    println!("Deleted {} refresh token(s)", purged.refresh);
+    println!("Deleted {} email verification token(s)", purged.verification);
+    println!("Deleted {} password reset token(s)", purged.reset);
+    Ok(purged.total())
+}
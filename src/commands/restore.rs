@@ -0,0 +1,94 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use diesel::connection::SimpleConnection;
+use diesel::sql_types::Text;
+use diesel::{sql_query, Connection, QueryableByName, RunQueryDsl, SqliteConnection};
+
+use crate::commands::CommandResult;
+use crate::config::Config;
+use crate::db::connection::is_in_memory;
+use crate::services::backups::Backups;
+
+#[derive(QueryableByName)]
+struct IntegrityCheck {
+    #[diesel(sql_type = Text)]
+    integrity_check: String,
+}
+
+/// Replaces the database with the backup `name`, or the newest backup without one. The
+/// server must be stopped first. The replaced database is kept beside it as
+/// `<file>.before-restore`, and a backup that fails SQLite's integrity check is never put in
+/// place.
+pub async fn run(config: &Config, name: Option<&str>) -> CommandResult<String> {
+    let url = config.db_url();
+    if is_in_memory(url) {
+        return Err("DATABASE_URL is an in-memory database, so there's no file to restore".into());
+    }
+    let target = database_path(url);
+
+    let backups = Backups::from_config(config);
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => backups.list().await?.into_iter().next().map(|backup| backup.name).ok_or("There are no backups to restore")?,
+    };
+    let bytes = backups.fetch(&name).await?.ok_or_else(|| format!("There's no backup named {}", name))?;
+
+    let incoming = with_suffix(&target, ".restoring");
+    std::fs::write(&incoming, bytes)?;
+    if let Err(e) = check_integrity(&incoming) {
+        let _ = std::fs::remove_file(&incoming);
+        return Err(e);
+    }
+
+    if target.exists() {
+        // Folds the write-ahead log into the file, so the copy set aside is complete.
+        SqliteConnection::establish(&target.to_string_lossy())?.batch_execute("PRAGMA wal_checkpoint(TRUNCATE)")?;
+        let previous = with_suffix(&target, ".before-restore");
+        std::fs::rename(&target, &previous)?;
+        println!("Kept the replaced database as {}", previous.display());
+    }
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(with_suffix(&target, suffix));
+    }
+    std::fs::rename(&incoming, &target)?;
+
+    println!("Restored {} to {}", name, target.display());
+    println!("Run `tsumi migrate` if the backup is older than this version of tsumi");
+    Ok(name)
+}
+
+/// The file a `DATABASE_URL` names, which may be a plain path or a `file:` URI.
+fn database_path(url: &str) -> PathBuf {
+    let path = url.strip_prefix("file:").unwrap_or(url);
+    PathBuf::from(path.split('?').next().unwrap_or(path))
+}
+
+fn with_suffix(path: &Path, suffix: impl AsRef<OsStr>) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn check_integrity(path: &Path) -> CommandResult<()> {
+    let mut conn = SqliteConnection::establish(&path.to_string_lossy())?;
+    let results = sql_query("PRAGMA integrity_check").load::<IntegrityCheck>(&mut conn)?;
+    match results.as_slice() {
+        [result] if result.integrity_check == "ok" => Ok(()),
+        _ => {
+            let problems: Vec<_> = results.into_iter().map(|result| result.integrity_check).collect();
+            Err(format!("The backup failed its integrity check: {}", problems.join("; ")).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn database_paths_come_from_paths_and_file_uris() {
+        assert_eq!(database_path("tsumi.db"), PathBuf::from("tsumi.db"));
+        assert_eq!(database_path("file:data/tsumi.db?mode=rwc"), PathBuf::from("data/tsumi.db"));
+    }
+}
@@ -0,0 +1,25 @@
+use diesel::SqliteConnection;
+
+use crate::commands::CommandResult;
+use crate::config::Config;
+use crate::services::seed::{seed, seed_email, SeedReport, SEED_PASSWORD};
+
+/// Fills a development database with sample data. Refuses to run in production, and does
+/// nothing if the database was seeded before.
+pub fn run(conn: &mut SqliteConnection, config: &Config) -> CommandResult<Option<SeedReport>> {
+    if config.environment() == "production" {
+        return Err("Refusing to seed a production database; set APP_ENV=development to seed".into());
+    }
+
+    let Some(report) = seed(conn, config)? else {
+        println!("The database is already seeded");
+        return Ok(None);
+    };
+
+    println!(
+        "Created {} users, {} posts with {} versions, {} comments and {} follows",
+        report.users, report.posts, report.versions, report.comments, report.follows
+    );
+    println!("Sign in as {} (an admin) or {} with the password {}", seed_email("ada"), seed_email("grace"), SEED_PASSWORD);
+    Ok(Some(report))
+}
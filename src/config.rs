@@ -1,55 +1,356 @@
+use std::collections::HashMap;
 use std::env;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use arc_swap::{ArcSwap, Guard};
 use dotenvy::dotenv;
-use tokio::sync::OnceCell;
+use figment::providers::{Format, Toml, Yaml};
+use figment::Figment;
+use tower_cookies::cookie::SameSite;
+use tracing_subscriber::EnvFilter;
 
-#[derive(Debug)]
+use crate::db::models::feature_flag::FEATURE_FLAGS;
+use crate::db::models::onboarding_step::ONBOARDING_STEPS;
+use crate::http::forwarded::IpRange;
+use crate::services::captcha::{CaptchaEndpoint, CaptchaProvider};
+use crate::services::spam::{SpamBackend, SpamCheck, Verdict};
+use crate::services::nodeinfo::NodeInfoStats;
+
+#[derive(Debug, Clone, PartialEq)]
 struct ServerConfig {
     host: String,
     port: u16,
+    public_url: String,
+    canonical_url: String,
+    environment: String,
+    shutdown_timeout_seconds: u64,
+    idempotency_key_ttl_hours: i64,
+    api_body_max_bytes: usize,
+    tls: Option<TlsConfig>,
+    behind_tls_proxy: bool,
+    trusted_proxies: Vec<IpRange>,
+    static_asset_hashing: bool,
+    log_level: String,
+    config_watch_interval_seconds: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+struct TlsConfig {
+    cert_path: String,
+    key_path: String,
+    redirect_http_port: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 struct DatabaseConfig {
     url: String,
+    pool_max_size: u32,
+    /// Unset keeps `pool_max_size` connections open.
+    pool_min_idle: Option<u32>,
+    acquire_timeout_seconds: u64,
+    idle_timeout_seconds: u64,
+    busy_timeout_ms: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct CorsConfig {
     allowed_origins: Vec<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct AccessTokenConfig {
     secret: String,
     expires_at: i64
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct RefreshTokenConfig {
     secret: String,
     expires_at: i64,
-    cookie_name: String,
+    /// How long a session lasts when the user didn't ask to be remembered.
+    short_expires_hours: i64,
 }
 
-#[derive(Debug)]
+/// Attributes shared by every cookie we set, and each cookie's name.
+#[derive(Debug, Clone, PartialEq)]
+struct CookiesConfig {
+    domain: Option<String>,
+    path: String,
+    same_site: SameSite,
+    secure: bool,
+    access_name: String,
+    refresh_name: String,
+    sudo_name: String,
+    csrf_name: String,
+    oauth_state_name: String,
+    flash_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 struct GithubOAuthConfig {
     client_id: String,
     client_secret: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+struct ReauthConfig {
+    window_minutes: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct EmailConfig {
+    smtp_url: Option<String>,
+    from_address: String,
+    webhook_secret: Option<String>,
+    transactional_rate_per_minute: u32,
+    bulk_rate_per_minute: u32,
+    outbox_poll_interval_seconds: u64,
+    max_attempts: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PostsConfig {
+    scheduled_publish_interval_seconds: u64,
+    link_rules_file: Option<String>,
+    edit_lock_ttl_seconds: i64,
+    edit_lock_takeover_grace_seconds: i64,
+    collab_compact_interval_seconds: u64,
+    body_max_bytes: usize,
+    import_max_bytes: usize,
+    import_max_files: usize,
+    view_flush_interval_seconds: u64,
+    view_flush_batch_size: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BlogConfig {
+    styles_enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RetentionConfig {
+    audit_log_days: u32,
+    notification_days: u32,
+    job_history_days: u32,
+    batch_size: i64,
+    run_hour: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BackupsConfig {
+    dir: String,
+    /// Set when `BACKUP_STORAGE=s3`; backups go under `dir` on local disk otherwise.
+    to_s3: bool,
+    interval_hours: u64,
+    keep: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SchedulerConfig {
+    poll_interval_seconds: u64,
+    lease_seconds: u64,
+    token_purge_interval_seconds: u64,
+}
+
+/// The HTTP client outbound integrations share.
+#[derive(Debug, Clone, PartialEq)]
+struct HttpClientConfig {
+    connect_timeout_seconds: u64,
+    timeout_seconds: u64,
+    /// Extra tries for calls that are safe to repeat.
+    retries: u32,
+    retry_base_ms: u64,
+    proxy_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct WebhooksConfig {
+    dispatch_interval_seconds: u64,
+    timeout_seconds: u64,
+    max_attempts: i32,
+    max_per_user: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AvatarsConfig {
+    cache_ttl_seconds: u64,
+    fetch_timeout_seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FeaturesConfig {
+    /// Each flag's `FEATURE_<NAME>` setting, used unless an admin has overridden it.
+    defaults: HashMap<String, bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct OnboardingConfig {
+    steps: Vec<String>,
+    follow_target: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ExportsConfig {
+    ttl_hours: i64,
+    poll_interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PublicApiConfig {
+    rate_limit: u32,
+    rate_window_seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CaptchaConfig {
+    provider: CaptchaProvider,
+    secret: String,
+    /// Handed to clients so they can render the widget.
+    site_key: Option<String>,
+    /// Overrides the provider's verification endpoint, e.g. for a proxy or a test double.
+    verify_url: Option<String>,
+    signup: bool,
+    password_reset: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SpamConfig {
+    backend: SpamBackend,
+    akismet_api_key: Option<String>,
+    /// Overrides Akismet's endpoint, e.g. for a proxy or a test double.
+    akismet_url: Option<String>,
+    /// Words and phrases the heuristic backend counts against content.
+    keywords: Vec<String>,
+    /// Scores from 0 to 1 at which content is queued for review, hidden, or refused.
+    queue_threshold: f64,
+    hide_threshold: f64,
+    reject_threshold: f64,
+    comments: bool,
+    signups: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct NodeInfoConfig {
+    stats: NodeInfoStats,
+    node_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CommentsConfig {
+    rate_limit: i64,
+    rate_window_seconds: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BackfillConfig {
+    batch_size: i64,
+    batch_delay_ms: u64,
+    poll_interval_seconds: u64,
+}
+
+/// Percentage of users moved onto new auth formats. Both formats are always accepted, so
+/// dropping a percentage back to 0 is an instant rollback.
+#[derive(Debug, Clone, PartialEq)]
+struct RolloutConfig {
+    argon2_percent: u8,
+    opaque_refresh_tokens_percent: u8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct S3Config {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct UploadsConfig {
+    dir: String,
+    max_bytes: usize,
+    s3: Option<S3Config>,
+    captioner: Option<CaptionerConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CaptionerConfig {
+    url: String,
+    api_key: Option<String>,
+    poll_interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CacheConfig {
+    redis_url: Option<String>,
+    capacity: usize,
+    ttl_seconds: u64,
+    sitemap_refresh_interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct NotificationsConfig {
+    batch_window_seconds: i64,
+    dispatch_interval_seconds: u64,
+    live_ping_interval_seconds: u64,
+    live_idle_timeout_seconds: u64,
+    /// Raw P-256 private key, base64url without padding. Web Push is off when unset.
+    vapid_private_key: Option<String>,
+    vapid_subject: String,
+    digest_check_interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SessionConfig {
+    /// Set when `SESSION_STORE=redis`; sessions live in the database otherwise.
+    redis_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 struct JWTConfig {
     access_token: AccessTokenConfig,
-    refresh_token: RefreshTokenConfig
+    refresh_token: RefreshTokenConfig,
+    reauth: ReauthConfig,
+    /// Put in the `iss` claim and required of every token when set.
+    issuer: Option<String>,
+    /// Put in the `aud` claim and required of every token when set.
+    audience: Option<String>,
+    /// Seconds of clock skew allowed when checking expiry.
+    leeway_seconds: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
     server: ServerConfig,
     db: DatabaseConfig,
     cors: CorsConfig,
     jwt: JWTConfig,
-    github: GithubOAuthConfig
+    cookies: CookiesConfig,
+    github: GithubOAuthConfig,
+    captcha: Option<CaptchaConfig>,
+    spam: Option<SpamConfig>,
+    http_client: HttpClientConfig,
+    email: EmailConfig,
+    posts: PostsConfig,
+    blog: BlogConfig,
+    nodeinfo: NodeInfoConfig,
+    public_api: PublicApiConfig,
+    retention: RetentionConfig,
+    backups: BackupsConfig,
+    scheduler: SchedulerConfig,
+    webhooks: WebhooksConfig,
+    avatars: AvatarsConfig,
+    exports: ExportsConfig,
+    onboarding: OnboardingConfig,
+    features: FeaturesConfig,
+    comments: CommentsConfig,
+    backfill: BackfillConfig,
+    uploads: UploadsConfig,
+    rollout: RolloutConfig,
+    cache: CacheConfig,
+    sessions: SessionConfig,
+    notifications: NotificationsConfig,
 }
 
 impl Config {
@@ -57,6 +358,31 @@ impl Config {
         &self.db.url
     }
 
+    /// Most connections the pool keeps open at once.
+    pub fn db_pool_max_size(&self) -> u32 {
+        self.db.pool_max_size
+    }
+
+    /// Idle connections the pool keeps ready. All of them when unset.
+    pub fn db_pool_min_idle(&self) -> Option<u32> {
+        self.db.pool_min_idle
+    }
+
+    /// How long a request waits for a free connection before failing.
+    pub fn db_acquire_timeout_seconds(&self) -> u64 {
+        self.db.acquire_timeout_seconds
+    }
+
+    /// Connections idle for longer are closed, down to `db_pool_min_idle`. 0 keeps them open.
+    pub fn db_idle_timeout_seconds(&self) -> u64 {
+        self.db.idle_timeout_seconds
+    }
+
+    /// How long a connection waits on SQLite's write lock before giving up with `SQLITE_BUSY`.
+    pub fn db_busy_timeout_ms(&self) -> u64 {
+        self.db.busy_timeout_ms
+    }
+
     pub fn server_host(&self) -> &str {
         &self.server.host
     }
@@ -65,6 +391,118 @@ impl Config {
         self.server.port
     }
 
+    pub fn public_url(&self) -> &str {
+        &self.server.public_url
+    }
+
+    /// Base URL used in sitemaps and other links meant for crawlers. Defaults to `public_url`.
+    pub fn canonical_url(&self) -> &str {
+        &self.server.canonical_url
+    }
+
+    /// `APP_ENV`, `production` unless set.
+    pub fn environment(&self) -> &str {
+        &self.server.environment
+    }
+
+    /// Debugging routes such as `/dev/whoami` are only mounted in development.
+    pub fn is_development(&self) -> bool {
+        self.server.environment == "development"
+    }
+
+    /// How long in-flight requests get to finish after a shutdown signal before the server
+    /// exits anyway.
+    pub fn shutdown_timeout_seconds(&self) -> u64 {
+        self.server.shutdown_timeout_seconds
+    }
+
+    /// How long a POST's `Idempotency-Key` is remembered, and its response replayed to retries.
+    pub fn idempotency_key_ttl_hours(&self) -> i64 {
+        self.server.idempotency_key_ttl_hours
+    }
+
+    /// Largest request body the API takes, where a route group doesn't set its own limit.
+    pub fn api_body_max_bytes(&self) -> usize {
+        self.server.api_body_max_bytes
+    }
+
+    /// Certificate and private key PEM files, when the server terminates TLS itself.
+    pub fn tls_cert_and_key(&self) -> Option<(&str, &str)> {
+        self.server.tls.as_ref().map(|tls| (tls.cert_path.as_str(), tls.key_path.as_str()))
+    }
+
+    /// Port for a plain HTTP listener that only redirects to HTTPS. Only used with TLS.
+    pub fn http_redirect_port(&self) -> Option<u16> {
+        self.server.tls.as_ref().and_then(|tls| tls.redirect_http_port)
+    }
+
+    /// Whether cookies get the `Secure` flag. `COOKIE_SECURE`, defaulting to true when clients
+    /// reach us over HTTPS, either directly or through a TLS-terminating proxy. Plain-HTTP local
+    /// setups need it off or browsers drop the cookies.
+    pub fn secure_cookies(&self) -> bool {
+        self.cookies.secure
+    }
+
+    /// `COOKIE_DOMAIN`. Unset keeps cookies to the exact host that set them.
+    pub fn cookie_domain(&self) -> Option<&str> {
+        self.cookies.domain.as_deref()
+    }
+
+    pub fn cookie_path(&self) -> &str {
+        &self.cookies.path
+    }
+
+    pub fn cookie_same_site(&self) -> SameSite {
+        self.cookies.same_site
+    }
+
+    pub fn access_cookie_name(&self) -> &str {
+        &self.cookies.access_name
+    }
+
+    pub fn refresh_cookie_name(&self) -> &str {
+        &self.cookies.refresh_name
+    }
+
+    pub fn sudo_cookie_name(&self) -> &str {
+        &self.cookies.sudo_name
+    }
+
+    pub fn csrf_cookie_name(&self) -> &str {
+        &self.cookies.csrf_name
+    }
+
+    pub fn oauth_state_cookie_name(&self) -> &str {
+        &self.cookies.oauth_state_name
+    }
+
+    pub fn flash_cookie_name(&self) -> &str {
+        &self.cookies.flash_name
+    }
+
+    /// Peers allowed to report the client's address and scheme through `X-Forwarded-For` and
+    /// `X-Forwarded-Proto`. Empty unless configured, in which case the headers are ignored.
+    pub fn trusted_proxies(&self) -> &[IpRange] {
+        &self.server.trusted_proxies
+    }
+
+    /// Whether templates link static files by content-hashed names that can be cached forever.
+    /// Off by default in development, where files change under a running server.
+    pub fn static_asset_hashing(&self) -> bool {
+        self.server.static_asset_hashing
+    }
+
+    /// `LOG_LEVEL`, as `tracing` filter directives such as `info` or `info,tsumi=debug`.
+    pub fn log_level(&self) -> &str {
+        &self.server.log_level
+    }
+
+    /// How often the config file is checked for changes to reload. Zero leaves reloads to
+    /// `SIGHUP`.
+    pub fn config_watch_interval_seconds(&self) -> u64 {
+        self.server.config_watch_interval_seconds
+    }
+
     pub fn cors_origin(&self) -> Vec<&str> {
         self.cors.allowed_origins.iter().map(String::as_str).collect()
     }
@@ -85,8 +523,30 @@ impl Config {
         self.jwt.refresh_token.expires_at
     }
 
-    pub fn refresh_token_cookie_name(&self) -> &str {
-        &self.jwt.refresh_token.cookie_name
+    /// How long a new session lasts: the full refresh expiry when the user asked to be
+    /// remembered, a few hours otherwise.
+    pub fn session_lifetime(&self, remember_me: bool) -> chrono::Duration {
+        if remember_me {
+            chrono::Duration::days(self.jwt.refresh_token.expires_at)
+        } else {
+            chrono::Duration::hours(self.jwt.refresh_token.short_expires_hours)
+        }
+    }
+
+    pub fn reauth_window_minutes(&self) -> i64 {
+        self.jwt.reauth.window_minutes
+    }
+
+    pub fn jwt_issuer(&self) -> Option<&str> {
+        self.jwt.issuer.as_deref()
+    }
+
+    pub fn jwt_audience(&self) -> Option<&str> {
+        self.jwt.audience.as_deref()
+    }
+
+    pub fn jwt_leeway_seconds(&self) -> u64 {
+        self.jwt.leeway_seconds
     }
     
     pub fn github_auth_client_id(&self) -> &str {
@@ -95,61 +555,1281 @@ impl Config {
     pub fn github_auth_client_secret(&self) -> &str {
         &self.github.client_secret
     }
+
+    pub fn smtp_url(&self) -> Option<&str> {
+        self.email.smtp_url.as_deref()
+    }
+
+    pub fn email_from_address(&self) -> &str {
+        &self.email.from_address
+    }
+
+    pub fn email_webhook_secret(&self) -> Option<&str> {
+        self.email.webhook_secret.as_deref()
+    }
+
+    pub fn email_transactional_rate_per_minute(&self) -> u32 {
+        self.email.transactional_rate_per_minute
+    }
+
+    pub fn email_bulk_rate_per_minute(&self) -> u32 {
+        self.email.bulk_rate_per_minute
+    }
+
+    /// How often the outbox is checked for mail that's due, on top of the wake-up each
+    /// enqueue sends.
+    pub fn email_outbox_poll_interval_seconds(&self) -> u64 {
+        self.email.outbox_poll_interval_seconds
+    }
+
+    /// Sends tried per message, the first included, before it's marked failed.
+    pub fn email_max_attempts(&self) -> i32 {
+        self.email.max_attempts
+    }
+
+    pub fn scheduled_publish_interval_seconds(&self) -> u64 {
+        self.posts.scheduled_publish_interval_seconds
+    }
+
+    /// Site-wide kill-switch for blog owners' custom CSS and head snippets. Saved styles are
+    /// kept while it's off; they just aren't rendered.
+    pub fn blog_styles_enabled(&self) -> bool {
+        self.blog.styles_enabled
+    }
+
+    /// How much of the instance's usage `/nodeinfo/2.1` reveals.
+    /// The captcha provider, when `CAPTCHA_PROVIDER` is set.
+    pub fn captcha_provider(&self) -> Option<CaptchaProvider> {
+        self.captcha.as_ref().map(|captcha| captcha.provider)
+    }
+
+    pub fn captcha_secret(&self) -> Option<&str> {
+        self.captcha.as_ref().map(|captcha| captcha.secret.as_str())
+    }
+
+    pub fn captcha_site_key(&self) -> Option<&str> {
+        self.captcha.as_ref().and_then(|captcha| captcha.site_key.as_deref())
+    }
+
+    pub fn captcha_verify_url(&self) -> Option<&str> {
+        self.captcha.as_ref().and_then(|captcha| captcha.verify_url.as_deref())
+    }
+
+    /// Whether `endpoint` needs a solved captcha. Never without a provider.
+    pub fn captcha_required(&self, endpoint: CaptchaEndpoint) -> bool {
+        self.captcha.as_ref().is_some_and(|captcha| match endpoint {
+            CaptchaEndpoint::Signup => captcha.signup,
+            CaptchaEndpoint::PasswordReset => captcha.password_reset,
+        })
+    }
+
+    /// The spam check backend, when `SPAM_CHECKER` is set.
+    pub fn spam_backend(&self) -> Option<SpamBackend> {
+        self.spam.as_ref().map(|spam| spam.backend)
+    }
+
+    pub fn spam_akismet_api_key(&self) -> Option<&str> {
+        self.spam.as_ref().and_then(|spam| spam.akismet_api_key.as_deref())
+    }
+
+    pub fn spam_akismet_url(&self) -> Option<&str> {
+        self.spam.as_ref().and_then(|spam| spam.akismet_url.as_deref())
+    }
+
+    pub fn spam_keywords(&self) -> &[String] {
+        self.spam.as_ref().map_or(&[], |spam| spam.keywords.as_slice())
+    }
+
+    /// Whether `check` goes through the spam checker. Never without a backend.
+    pub fn spam_checked(&self, check: SpamCheck) -> bool {
+        self.spam.as_ref().is_some_and(|spam| match check {
+            SpamCheck::Comment => spam.comments,
+            SpamCheck::Signup => spam.signups,
+        })
+    }
+
+    /// What a spam score calls for under the configured thresholds.
+    pub fn spam_verdict(&self, score: f64) -> Verdict {
+        let Some(spam) = &self.spam else { return Verdict::Allow };
+        if score >= spam.reject_threshold {
+            Verdict::Reject
+        } else if score >= spam.hide_threshold {
+            Verdict::Hide
+        } else if score >= spam.queue_threshold {
+            Verdict::Queue
+        } else {
+            Verdict::Allow
+        }
+    }
+
+    pub fn nodeinfo_stats(&self) -> NodeInfoStats {
+        self.nodeinfo.stats
+    }
+
+    pub fn nodeinfo_node_name(&self) -> Option<&str> {
+        self.nodeinfo.node_name.as_deref()
+    }
+
+    /// Requests each client may make to `/api/public/v1` per window.
+    pub fn public_api_rate_limit(&self) -> u32 {
+        self.public_api.rate_limit
+    }
+
+    pub fn public_api_rate_window_seconds(&self) -> u64 {
+        self.public_api.rate_window_seconds
+    }
+
+    /// Days audit log entries are kept; 0 keeps them forever.
+    pub fn audit_log_retention_days(&self) -> u32 {
+        self.retention.audit_log_days
+    }
+
+    /// Days in-app notifications are kept; 0 keeps them forever.
+    pub fn notification_retention_days(&self) -> u32 {
+        self.retention.notification_days
+    }
+
+    /// Days finished backfill jobs, webhook deliveries and exports are kept; 0 keeps them forever.
+    pub fn job_history_retention_days(&self) -> u32 {
+        self.retention.job_history_days
+    }
+
+    /// Rows deleted per statement, so no single delete holds the write lock for long.
+    pub fn retention_batch_size(&self) -> i64 {
+        self.retention.batch_size
+    }
+
+    /// The UTC hour the nightly pruning runs at.
+    pub fn retention_run_hour(&self) -> u32 {
+        self.retention.run_hour
+    }
+
+    /// The directory database backups are written to, unless they go to S3.
+    pub fn backup_dir(&self) -> &str {
+        &self.backups.dir
+    }
+
+    /// Whether backups are kept in the S3 bucket, under `backups/`.
+    pub fn backups_to_s3(&self) -> bool {
+        self.backups.to_s3
+    }
+
+    /// Hours between scheduled backups; 0 only backs up on demand.
+    pub fn backup_interval_hours(&self) -> u64 {
+        self.backups.interval_hours
+    }
+
+    /// How many backups are kept before the oldest are deleted.
+    pub fn backup_keep(&self) -> usize {
+        self.backups.keep
+    }
+
+    /// The longest the scheduler waits before checking the `jobs` table for due jobs.
+    pub fn scheduler_poll_interval_seconds(&self) -> u64 {
+        self.scheduler.poll_interval_seconds
+    }
+
+    /// How long a server holds a lock in `job_locks` without renewing it: for a scheduled job
+    /// while it runs, and for a queue worker while it's the one doing the work. Work whose
+    /// server died moves to another once the lease runs out.
+    pub fn job_lease_seconds(&self) -> u64 {
+        self.scheduler.lease_seconds
+    }
+
+    /// Seconds between purges of expired refresh tokens and verification and reset links.
+    pub fn token_purge_interval_seconds(&self) -> u64 {
+        self.scheduler.token_purge_interval_seconds
+    }
+
+    pub fn webhook_dispatch_interval_seconds(&self) -> u64 {
+        self.webhooks.dispatch_interval_seconds
+    }
+
+    pub fn http_connect_timeout_seconds(&self) -> u64 {
+        self.http_client.connect_timeout_seconds
+    }
+
+    /// How long an outbound request gets in all, unless it sets its own timeout.
+    pub fn http_timeout_seconds(&self) -> u64 {
+        self.http_client.timeout_seconds
+    }
+
+    pub fn http_retries(&self) -> u32 {
+        self.http_client.retries
+    }
+
+    pub fn http_retry_base_ms(&self) -> u64 {
+        self.http_client.retry_base_ms
+    }
+
+    /// Where outbound requests are sent through, when `HTTP_PROXY_URL` is set.
+    pub fn http_proxy_url(&self) -> Option<&str> {
+        self.http_client.proxy_url.as_deref()
+    }
+
+    /// How long an endpoint gets to answer before the attempt counts as failed.
+    pub fn webhook_timeout_seconds(&self) -> u64 {
+        self.webhooks.timeout_seconds
+    }
+
+    /// Attempts per delivery, the first included, before it's marked failed.
+    pub fn webhook_max_attempts(&self) -> i32 {
+        self.webhooks.max_attempts
+    }
+
+    pub fn webhook_max_per_user(&self) -> i64 {
+        self.webhooks.max_per_user
+    }
+
+    /// How long a proxied avatar is served from cache before it's fetched again.
+    pub fn avatar_cache_ttl_seconds(&self) -> u64 {
+        self.avatars.cache_ttl_seconds
+    }
+
+    pub fn avatar_fetch_timeout_seconds(&self) -> u64 {
+        self.avatars.fetch_timeout_seconds
+    }
+
+    /// How long a finished account export can be downloaded before it's deleted.
+    pub fn export_ttl_hours(&self) -> i64 {
+        self.exports.ttl_hours
+    }
+
+    pub fn export_poll_interval_seconds(&self) -> u64 {
+        self.exports.poll_interval_seconds
+    }
+
+    /// The onboarding checklist's steps, in the order they're shown.
+    pub fn onboarding_steps(&self) -> Vec<&str> {
+        self.onboarding.steps.iter().map(String::as_str).collect()
+    }
+
+    /// How many authors a user follows before the follow step is done.
+    pub fn onboarding_follow_target(&self) -> i64 {
+        self.onboarding.follow_target
+    }
+
+    /// Whether the feature flag `name` is on when no admin has overridden it.
+    pub fn feature_flag_default(&self, name: &str) -> bool {
+        self.features.defaults.get(name).copied().unwrap_or(true)
+    }
+
+    pub fn link_rules_file(&self) -> Option<&str> {
+        self.posts.link_rules_file.as_deref()
+    }
+
+    /// How long an edit lock lasts without a heartbeat.
+    pub fn edit_lock_ttl_seconds(&self) -> i64 {
+        self.posts.edit_lock_ttl_seconds
+    }
+
+    /// How long the holder of an edit lock has to save and let go after another session asks
+    /// to take it over.
+    pub fn edit_lock_takeover_grace_seconds(&self) -> i64 {
+        self.posts.edit_lock_takeover_grace_seconds
+    }
+
+    pub fn collab_compact_interval_seconds(&self) -> u64 {
+        self.posts.collab_compact_interval_seconds
+    }
+
+    /// Largest import accepted, counting both the upload and what its archives expand to.
+    /// Largest body for creating or editing a post, which carries the whole text.
+    pub fn post_body_max_bytes(&self) -> usize {
+        self.posts.body_max_bytes
+    }
+
+    pub fn import_max_bytes(&self) -> usize {
+        self.posts.import_max_bytes
+    }
+
+    /// Most markdown files one import may create posts from.
+    pub fn import_max_files(&self) -> usize {
+        self.posts.import_max_files
+    }
+
+    pub fn view_flush_interval_seconds(&self) -> u64 {
+        self.posts.view_flush_interval_seconds
+    }
+
+    /// Post views held in memory before they're written out early, ahead of the next interval.
+    pub fn view_flush_batch_size(&self) -> i64 {
+        self.posts.view_flush_batch_size
+    }
+
+    pub fn comment_rate_limit(&self) -> i64 {
+        self.comments.rate_limit
+    }
+
+    pub fn comment_rate_window_seconds(&self) -> i64 {
+        self.comments.rate_window_seconds
+    }
+
+    pub fn backfill_batch_size(&self) -> i64 {
+        self.backfill.batch_size
+    }
+
+    pub fn backfill_batch_delay_ms(&self) -> u64 {
+        self.backfill.batch_delay_ms
+    }
+
+    pub fn backfill_poll_interval_seconds(&self) -> u64 {
+        self.backfill.poll_interval_seconds
+    }
+
+    pub fn argon2_rollout_percent(&self) -> u8 {
+        self.rollout.argon2_percent
+    }
+
+    pub fn opaque_refresh_tokens_rollout_percent(&self) -> u8 {
+        self.rollout.opaque_refresh_tokens_percent
+    }
+
+    pub fn uploads_dir(&self) -> &str {
+        &self.uploads.dir
+    }
+
+    pub fn upload_max_bytes(&self) -> usize {
+        self.uploads.max_bytes
+    }
+
+    /// Endpoint of the image captioning backend that suggests alt text. Suggestions are off
+    /// when unset.
+    pub fn alt_text_captioner_url(&self) -> Option<&str> {
+        self.uploads.captioner.as_ref().map(|captioner| captioner.url.as_str())
+    }
+
+    pub fn alt_text_captioner_api_key(&self) -> Option<&str> {
+        self.uploads.captioner.as_ref().and_then(|captioner| captioner.api_key.as_deref())
+    }
+
+    pub fn alt_text_poll_interval_seconds(&self) -> u64 {
+        self.uploads.captioner.as_ref().map_or(10, |captioner| captioner.poll_interval_seconds)
+    }
+
+    pub fn s3_endpoint(&self) -> Option<&str> {
+        self.uploads.s3.as_ref().map(|s3| s3.endpoint.as_str())
+    }
+
+    pub fn s3_bucket(&self) -> Option<&str> {
+        self.uploads.s3.as_ref().map(|s3| s3.bucket.as_str())
+    }
+
+    pub fn s3_region(&self) -> Option<&str> {
+        self.uploads.s3.as_ref().map(|s3| s3.region.as_str())
+    }
+
+    pub fn s3_access_key_id(&self) -> Option<&str> {
+        self.uploads.s3.as_ref().map(|s3| s3.access_key_id.as_str())
+    }
+
+    pub fn s3_secret_access_key(&self) -> Option<&str> {
+        self.uploads.s3.as_ref().map(|s3| s3.secret_access_key.as_str())
+    }
+
+    pub fn cache_redis_url(&self) -> Option<&str> {
+        self.cache.redis_url.as_deref()
+    }
+
+    pub fn cache_capacity(&self) -> usize {
+        self.cache.capacity
+    }
+
+    /// How long rendered pages stay cached. Zero turns caching off.
+    pub fn cache_ttl_seconds(&self) -> u64 {
+        self.cache.ttl_seconds
+    }
+
+    /// How often the sitemap worker looks for changed posts and pages. A change is only
+    /// rendered once a check finds nothing newer, so bursts of writes cost one rebuild.
+    pub fn sitemap_refresh_interval_seconds(&self) -> u64 {
+        self.cache.sitemap_refresh_interval_seconds
+    }
+
+    /// How long a notification waits for similar events to fold into it before going out.
+    pub fn notification_batch_window_seconds(&self) -> i64 {
+        self.notifications.batch_window_seconds
+    }
+
+    pub fn notification_dispatch_interval_seconds(&self) -> u64 {
+        self.notifications.dispatch_interval_seconds
+    }
+
+    /// How often `/ws` connections are pinged to keep them alive.
+    pub fn live_ping_interval_seconds(&self) -> u64 {
+        self.notifications.live_ping_interval_seconds
+    }
+
+    /// How long a `/ws` connection may stay silent, pongs included, before it's closed.
+    pub fn live_idle_timeout_seconds(&self) -> u64 {
+        self.notifications.live_idle_timeout_seconds
+    }
+
+    /// The VAPID key signing Web Push requests, or `None` when push notifications are off.
+    pub fn vapid_private_key(&self) -> Option<&str> {
+        self.notifications.vapid_private_key.as_deref()
+    }
+
+    /// Contact push services can reach about our traffic, a `mailto:` or `https:` URL.
+    pub fn vapid_subject(&self) -> &str {
+        &self.notifications.vapid_subject
+    }
+
+    /// How often the digest worker looks for users due their weekly digest.
+    pub fn digest_check_interval_seconds(&self) -> u64 {
+        self.notifications.digest_check_interval_seconds
+    }
+
+    /// The Redis holding refresh-token sessions, or `None` to keep them in the database.
+    pub fn session_redis_url(&self) -> Option<&str> {
+        self.sessions.redis_url.as_deref()
+    }
+}
+
+/// Files looked for in the working directory when `TSUMI_CONFIG` doesn't name one.
+const CONFIG_FILES: [&str; 3] = ["tsumi.toml", "config.yaml", "config.yml"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("couldn't load {path}: {message}")]
+    File { path: String, message: String },
+    #[error("{key} must be set")]
+    Missing { key: String },
+    #[error("{key} must be set when {when} is")]
+    MissingWhen { key: String, when: String },
+    #[error("{key} is invalid: {message}")]
+    Invalid { key: String, message: String },
+}
+
+/// Everything wrong with the configuration, so it can all be fixed in one go.
+#[derive(Debug)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl std::fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid configuration ({} problem(s)):", self.0.len())?;
+        for error in &self.0 {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Flattens a config file into environment variable names: `port = 80` is `PORT`, `ttl_hours`
+/// under `[export]` is `EXPORT_TTL_HOURS`, and lists become comma-separated values.
+fn flatten(value: &serde_json::Value, key: String, out: &mut HashMap<String, String>) {
+    let joined = |name: &str| if key.is_empty() { name.to_uppercase() } else { format!("{}_{}", key, name.to_uppercase()) };
+    match value {
+        serde_json::Value::Object(map) => {
+            for (name, value) in map {
+                flatten(value, joined(name), out);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| match item {
+                    serde_json::Value::String(item) => item.clone(),
+                    item => item.to_string(),
+                })
+                .collect();
+            out.insert(key, items.join(","));
+        }
+        serde_json::Value::String(value) => {
+            out.insert(key, value.clone());
+        }
+        serde_json::Value::Null => {}
+        value => {
+            out.insert(key, value.to_string());
+        }
+    }
+}
+
+/// `TSUMI_CONFIG`, or the first of `CONFIG_FILES` that exists. Having no file is fine.
+pub fn config_file() -> Option<String> {
+    env::var("TSUMI_CONFIG")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .or_else(|| CONFIG_FILES.into_iter().find(|path| Path::new(path).exists()).map(String::from))
+}
+
+fn load_file(errors: &mut Vec<ConfigError>) -> HashMap<String, String> {
+    let Some(path) = config_file() else {
+        return HashMap::new();
+    };
+    if !Path::new(&path).exists() {
+        errors.push(ConfigError::File { path, message: "no such file".to_string() });
+        return HashMap::new();
+    }
+
+    let figment = match Path::new(&path).extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => Figment::from(Toml::file(&path)),
+        Some("yaml" | "yml") => Figment::from(Yaml::file(&path)),
+        _ => {
+            errors.push(ConfigError::File { path, message: "expected a .toml, .yaml or .yml file".to_string() });
+            return HashMap::new();
+        }
+    };
+
+    let mut values = HashMap::new();
+    match figment.extract::<serde_json::Value>() {
+        Ok(document) => flatten(&document, String::new(), &mut values),
+        Err(e) => errors.push(ConfigError::File { path, message: e.to_string() }),
+    }
+    values
+}
+
+/// Settings from the config file overridden by environment variables (including `.env`).
+/// Empty values count as unset. Problems are collected rather than panicking on the first.
+struct Source {
+    file: HashMap<String, String>,
+    env: HashMap<String, String>,
+    errors: Vec<ConfigError>,
 }
 
-pub static CONFIG: OnceCell<Config> = OnceCell::const_new();
+impl Source {
+    fn build(mut self) -> Result<Config, ConfigErrors> {
+        let config = build_config(&mut self);
+        if self.errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigErrors(self.errors))
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        [&self.env, &self.file]
+            .into_iter()
+            .find_map(|values| values.get(key).filter(|value| !value.is_empty()))
+            .cloned()
+    }
+
+    fn string_or(&self, key: &str, default: &str) -> String {
+        self.get(key).unwrap_or_else(|| default.to_string())
+    }
+
+    fn flag(&self, key: &str) -> Option<bool> {
+        self.get(key).map(|value| value == "true" || value == "1")
+    }
+
+    fn list(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    fn invalid(&mut self, key: &str, message: impl std::fmt::Display) {
+        self.errors.push(ConfigError::Invalid { key: key.to_string(), message: message.to_string() });
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        self.get(key).unwrap_or_else(|| {
+            self.errors.push(ConfigError::Missing { key: key.to_string() });
+            String::new()
+        })
+    }
+
+    /// `key`, which must be set because `when` is.
+    fn required_when(&mut self, key: &str, when: &str) -> String {
+        self.get(key).unwrap_or_else(|| {
+            self.errors.push(ConfigError::MissingWhen { key: key.to_string(), when: when.to_string() });
+            String::new()
+        })
+    }
+
+    fn parse<T: FromStr>(&mut self, key: &str, raw: &str) -> Option<T>
+    where
+        T::Err: std::fmt::Display,
+    {
+        match raw.trim().parse::<T>() {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.invalid(key, format!("{:?}: {}", raw, e));
+                None
+            }
+        }
+    }
+
+    fn parse_or<T: FromStr>(&mut self, key: &str, default: T) -> T
+    where
+        T::Err: std::fmt::Display,
+    {
+        match self.get(key) {
+            Some(raw) => self.parse(key, &raw).unwrap_or(default),
+            None => default,
+        }
+    }
 
-async fn init_config() -> Config {
+    fn parse_optional<T: FromStr>(&mut self, key: &str) -> Option<T>
+    where
+        T::Err: std::fmt::Display,
+    {
+        let raw = self.get(key)?;
+        self.parse(key, &raw)
+    }
+
+    fn parse_required<T: FromStr + Default>(&mut self, key: &str) -> T
+    where
+        T::Err: std::fmt::Display,
+    {
+        match self.get(key) {
+            Some(raw) => self.parse(key, &raw).unwrap_or_default(),
+            None => {
+                self.errors.push(ConfigError::Missing { key: key.to_string() });
+                T::default()
+            }
+        }
+    }
+}
+
+fn init_config() -> Result<Config, ConfigErrors> {
     dotenv().ok();
 
+    let mut errors = Vec::new();
+    let file = load_file(&mut errors);
+    Source { file, env: env::vars().collect(), errors }.build()
+}
+
+impl Config {
+    /// A config made from `values` alone, keyed like the environment variables. Neither the
+    /// environment, `.env` nor a config file is read, which keeps tests independent of the
+    /// machine they run on.
+    pub fn from_values<'a>(values: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Config, ConfigErrors> {
+        let env = values.into_iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        Source { file: HashMap::new(), env, errors: Vec::new() }.build()
+    }
+}
+
+fn build_config(source: &mut Source) -> Config {
+    let host = source.string_or("HOST", "127.0.0.1");
+    let port = source.parse_or::<u16>("PORT", 8000);
+
+    let tls_config = source.get("TLS_CERT_PATH").map(|cert_path| TlsConfig {
+        cert_path,
+        key_path: source.required_when("TLS_KEY_PATH", "TLS_CERT_PATH"),
+        redirect_http_port: source.parse_optional::<u16>("HTTP_REDIRECT_PORT"),
+    });
+
+    let scheme = if tls_config.is_some() { "https" } else { "http" };
+    let public_url = source.get("PUBLIC_URL")
+        .unwrap_or_else(|| format!("{}://{}:{}", scheme, host, port))
+        .trim_end_matches('/')
+        .to_string();
+
+    // A proxy terminating TLS in front of us is assumed when the public URL is https.
+    let behind_tls_proxy = source.flag("BEHIND_TLS_PROXY")
+        .unwrap_or_else(|| public_url.starts_with("https://"));
+
+    let environment = source.string_or("APP_ENV", "production");
+    let static_asset_hashing = source.flag("STATIC_ASSET_HASHING")
+        .unwrap_or(environment != "development");
+
+    let trusted_proxies = source.list("TRUSTED_PROXIES")
+        .into_iter()
+        .filter_map(|proxy| source.parse::<IpRange>("TRUSTED_PROXIES", &proxy))
+        .collect();
+
+    let log_level = source.string_or("LOG_LEVEL", "info");
+    if let Err(e) = EnvFilter::try_new(&log_level) {
+        source.invalid("LOG_LEVEL", e);
+    }
+
     let server_config = ServerConfig {
-        host: env::var("HOST").unwrap_or_else(|_| String::from("127.0.0.1")),
-        port: env::var("PORT").unwrap_or_else(|_| String::from("8000")).parse::<u16>().unwrap(),
+        canonical_url: source.get("CANONICAL_URL")
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| public_url.clone()),
+        public_url,
+        host,
+        port,
+        environment,
+        shutdown_timeout_seconds: source.parse_or::<u64>("SHUTDOWN_TIMEOUT_SECONDS", 30),
+        idempotency_key_ttl_hours: source.parse_or::<i64>("IDEMPOTENCY_KEY_TTL_HOURS", 24),
+        api_body_max_bytes: source.parse_or::<usize>("API_BODY_MAX_BYTES", 262144),
+        behind_tls_proxy,
+        tls: tls_config,
+        trusted_proxies,
+        static_asset_hashing,
+        log_level,
+        config_watch_interval_seconds: source.parse_or::<u64>("CONFIG_WATCH_INTERVAL_SECONDS", 5),
     };
 
     let database_config = DatabaseConfig {
-        url: env::var("DATABASE_URL").expect("DATABASE_URL must be set")
+        url: source.required("DATABASE_URL"),
+        pool_max_size: source.parse_or::<u32>("DB_POOL_MAX_SIZE", 10),
+        pool_min_idle: source.parse_optional::<u32>("DB_POOL_MIN_IDLE"),
+        acquire_timeout_seconds: source.parse_or::<u64>("DB_ACQUIRE_TIMEOUT_SECONDS", 30),
+        idle_timeout_seconds: source.parse_or::<u64>("DB_IDLE_TIMEOUT_SECONDS", 600),
+        busy_timeout_ms: source.parse_or::<u64>("DB_BUSY_TIMEOUT_MS", 5000),
     };
 
     let cors_config = CorsConfig {
-        allowed_origins: env::var("CORS_ORIGIN").expect("CORS_ORIGIN must be set").split(",").map(String::from).collect(),
+        allowed_origins: source.required("CORS_ORIGIN").split(",").map(String::from).collect(),
     };
 
     let access_token_config = AccessTokenConfig {
-        secret: env::var("ACCESS_SECRET").expect("ACCESS_SECRET must be set"),
-        expires_at: env::var("ACCESS_EXPIRES").expect("ACCESS_EXPIRES must be set").parse::<i64>
-        ().expect("ACCESS_EXPIRES must be a number"),
+        secret: source.required("ACCESS_SECRET"),
+        expires_at: source.parse_required::<i64>("ACCESS_EXPIRES"),
     };
 
     let refresh_token_config = RefreshTokenConfig {
-        secret: env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN must be set"),
-        expires_at: env::var("REFRESH_EXPIRES").expect("REFRESH_EXPIRES must be set")
-            .parse::<i64>().expect("REFRESH_EXPIRES must be a number"),
-        cookie_name: env::var("COOKIE_NAME").expect("COOKIE_NAME must be set")
+        secret: source.required("REFRESH_TOKEN"),
+        expires_at: source.parse_required::<i64>("REFRESH_EXPIRES"),
+        short_expires_hours: source.parse_or::<i64>("SESSION_EXPIRES_HOURS", 12),
     };
 
+    let same_site = match source.string_or("COOKIE_SAME_SITE", "strict").to_lowercase().as_str() {
+        "strict" => SameSite::Strict,
+        "lax" => SameSite::Lax,
+        "none" => SameSite::None,
+        other => {
+            source.invalid("COOKIE_SAME_SITE", format!("must be strict, lax or none, got {}", other));
+            SameSite::Strict
+        }
+    };
+    let cookies_config = CookiesConfig {
+        domain: source.get("COOKIE_DOMAIN"),
+        path: source.string_or("COOKIE_PATH", "/"),
+        same_site,
+        secure: source.flag("COOKIE_SECURE")
+            .unwrap_or(server_config.tls.is_some() || server_config.behind_tls_proxy),
+        access_name: source.string_or("COOKIE_ACCESS_NAME", "access_token"),
+        refresh_name: source.string_or("COOKIE_REFRESH_NAME", "refresh_token"),
+        sudo_name: source.string_or("COOKIE_SUDO_NAME", "sudo_token"),
+        csrf_name: source.string_or("COOKIE_CSRF_NAME", "csrf_token"),
+        oauth_state_name: source.string_or("COOKIE_OAUTH_STATE_NAME", "oauth_state"),
+        flash_name: source.string_or("COOKIE_FLASH_NAME", "flash"),
+    };
+    // Browsers reject SameSite=None cookies that aren't also Secure.
+    if same_site == SameSite::None && !cookies_config.secure {
+        source.invalid("COOKIE_SAME_SITE", "none needs COOKIE_SECURE");
+    }
+
     let github_oauth_config = GithubOAuthConfig {
-        client_id: env::var("GITHUB_OAUTH_CLIENT_ID").expect("GITHUB_OAUTH_CLIENT_ID muse be \
-        set"),
-        client_secret: env::var("GITHUB_OAUTH_CLIENT_SECRET").expect("GITHUB_OAUTH_CLIENT_SECRET \
-        must be set")
+        client_id: source.required("GITHUB_OAUTH_CLIENT_ID"),
+        client_secret: source.required("GITHUB_OAUTH_CLIENT_SECRET"),
+    };
+
+    let captcha_config = source.parse_optional::<CaptchaProvider>("CAPTCHA_PROVIDER").map(|provider| CaptchaConfig {
+        provider,
+        secret: source.required_when("CAPTCHA_SECRET", "CAPTCHA_PROVIDER"),
+        site_key: source.get("CAPTCHA_SITE_KEY"),
+        verify_url: source.get("CAPTCHA_VERIFY_URL"),
+        signup: source.parse_or::<bool>("CAPTCHA_SIGNUP", true),
+        password_reset: source.parse_or::<bool>("CAPTCHA_PASSWORD_RESET", true),
+    });
+
+    let spam_config = source.parse_optional::<SpamBackend>("SPAM_CHECKER").map(|backend| SpamConfig {
+        backend,
+        akismet_api_key: match backend {
+            SpamBackend::Akismet => Some(source.required_when("SPAM_AKISMET_API_KEY", "SPAM_CHECKER")),
+            SpamBackend::Heuristic => None,
+        },
+        akismet_url: source.get("SPAM_AKISMET_URL"),
+        keywords: source.list("SPAM_KEYWORDS"),
+        queue_threshold: source.parse_or::<f64>("SPAM_QUEUE_THRESHOLD", 0.5),
+        hide_threshold: source.parse_or::<f64>("SPAM_HIDE_THRESHOLD", 0.7),
+        reject_threshold: source.parse_or::<f64>("SPAM_REJECT_THRESHOLD", 0.9),
+        comments: source.parse_or::<bool>("SPAM_CHECK_COMMENTS", true),
+        signups: source.parse_or::<bool>("SPAM_CHECK_SIGNUPS", true),
+    });
+    if let Some(spam) = &spam_config
+        && !(spam.queue_threshold <= spam.hide_threshold && spam.hide_threshold <= spam.reject_threshold)
+    {
+        source.invalid("SPAM_QUEUE_THRESHOLD", "must not be above SPAM_HIDE_THRESHOLD, nor that above SPAM_REJECT_THRESHOLD");
+    }
+
+    let reauth_config = ReauthConfig {
+        window_minutes: source.parse_or::<i64>("REAUTH_WINDOW_MINUTES", 10),
     };
 
     let jwt_config = JWTConfig {
         access_token: access_token_config,
-        refresh_token: refresh_token_config
+        refresh_token: refresh_token_config,
+        reauth: reauth_config,
+        issuer: source.get("JWT_ISSUER"),
+        audience: source.get("JWT_AUDIENCE"),
+        leeway_seconds: source.parse_or::<u64>("JWT_LEEWAY_SECONDS", 60),
+    };
+
+    let email_config = EmailConfig {
+        smtp_url: source.get("SMTP_URL"),
+        from_address: source.string_or("EMAIL_FROM", "tsumi <no-reply@localhost>"),
+        webhook_secret: source.get("EMAIL_WEBHOOK_SECRET"),
+        transactional_rate_per_minute: source.parse_or::<u32>("EMAIL_TRANSACTIONAL_RATE_PER_MINUTE", 120),
+        bulk_rate_per_minute: source.parse_or::<u32>("EMAIL_BULK_RATE_PER_MINUTE", 30),
+        outbox_poll_interval_seconds: source.parse_or::<u64>("EMAIL_OUTBOX_POLL_INTERVAL_SECONDS", 5),
+        max_attempts: source.parse_or::<i32>("EMAIL_MAX_ATTEMPTS", 8).max(1),
+    };
+    if let Some(smtp_url) = &email_config.smtp_url
+        && let Err(e) = lettre::AsyncSmtpTransport::<lettre::Tokio1Executor>::from_url(smtp_url)
+    {
+        source.invalid("SMTP_URL", e);
+    }
+    if let Err(e) = email_config.from_address.parse::<lettre::message::Mailbox>() {
+        source.invalid("EMAIL_FROM", e);
+    }
+
+    let posts_config = PostsConfig {
+        scheduled_publish_interval_seconds: source.parse_or::<u64>("SCHEDULED_PUBLISH_INTERVAL_SECONDS", 30),
+        link_rules_file: source.get("LINK_RULES_FILE"),
+        edit_lock_ttl_seconds: source.parse_or::<i64>("EDIT_LOCK_TTL_SECONDS", 60),
+        edit_lock_takeover_grace_seconds: source.parse_or::<i64>("EDIT_LOCK_TAKEOVER_GRACE_SECONDS", 30),
+        collab_compact_interval_seconds: source.parse_or::<u64>("COLLAB_COMPACT_INTERVAL_SECONDS", 30),
+        body_max_bytes: source.parse_or::<usize>("POST_BODY_MAX_BYTES", 2097152),
+        import_max_bytes: source.parse_or::<usize>("POST_IMPORT_MAX_BYTES", 10485760),
+        import_max_files: source.parse_or::<usize>("POST_IMPORT_MAX_FILES", 200),
+        view_flush_interval_seconds: source.parse_or::<u64>("VIEW_FLUSH_INTERVAL_SECONDS", 30),
+        view_flush_batch_size: source.parse_or::<i64>("VIEW_FLUSH_BATCH_SIZE", 500),
+    };
+
+    let blog_config = BlogConfig {
+        styles_enabled: source.parse_or::<bool>("BLOG_STYLES_ENABLED", true),
+    };
+
+    let nodeinfo_config = NodeInfoConfig {
+        stats: source.parse_or::<NodeInfoStats>("NODEINFO_STATS", NodeInfoStats::Full),
+        node_name: source.get("NODEINFO_NODE_NAME"),
+    };
+
+    let public_api_config = PublicApiConfig {
+        rate_limit: source.parse_or::<u32>("PUBLIC_API_RATE_LIMIT", 60),
+        rate_window_seconds: source.parse_or::<u64>("PUBLIC_API_RATE_WINDOW_SECONDS", 60),
+    };
+
+    let retention_config = RetentionConfig {
+        audit_log_days: source.parse_or::<u32>("AUDIT_LOG_RETENTION_DAYS", 365),
+        notification_days: source.parse_or::<u32>("NOTIFICATION_RETENTION_DAYS", 90),
+        job_history_days: source.parse_or::<u32>("JOB_HISTORY_RETENTION_DAYS", 30),
+        batch_size: source.parse_or::<i64>("RETENTION_BATCH_SIZE", 500).max(1),
+        run_hour: source.parse_or::<u32>("RETENTION_RUN_HOUR", 3).min(23),
+    };
+
+    let http_client_config = HttpClientConfig {
+        connect_timeout_seconds: source.parse_or::<u64>("HTTP_CONNECT_TIMEOUT_SECONDS", 5),
+        timeout_seconds: source.parse_or::<u64>("HTTP_TIMEOUT_SECONDS", 15),
+        retries: source.parse_or::<u32>("HTTP_RETRIES", 2),
+        retry_base_ms: source.parse_or::<u64>("HTTP_RETRY_BASE_MS", 200),
+        proxy_url: source.get("HTTP_PROXY_URL"),
+    };
+    if let Some(proxy_url) = &http_client_config.proxy_url
+        && let Err(e) = reqwest::Proxy::all(proxy_url)
+    {
+        source.invalid("HTTP_PROXY_URL", e);
+    }
+
+    let webhooks_config = WebhooksConfig {
+        dispatch_interval_seconds: source.parse_or::<u64>("WEBHOOK_DISPATCH_INTERVAL_SECONDS", 10),
+        timeout_seconds: source.parse_or::<u64>("WEBHOOK_TIMEOUT_SECONDS", 10),
+        max_attempts: source.parse_or::<i32>("WEBHOOK_MAX_ATTEMPTS", 8).max(1),
+        max_per_user: source.parse_or::<i64>("WEBHOOK_MAX_PER_USER", 10),
+    };
+
+    let avatars_config = AvatarsConfig {
+        cache_ttl_seconds: source.parse_or::<u64>("AVATAR_CACHE_TTL_SECONDS", 86400).max(60),
+        fetch_timeout_seconds: source.parse_or::<u64>("AVATAR_FETCH_TIMEOUT_SECONDS", 5).max(1),
+    };
+
+    let exports_config = ExportsConfig {
+        ttl_hours: source.parse_or::<i64>("EXPORT_TTL_HOURS", 72).max(1),
+        poll_interval_seconds: source.parse_or::<u64>("EXPORT_POLL_INTERVAL_SECONDS", 5),
+    };
+
+    let mut onboarding_steps = source.list("ONBOARDING_STEPS");
+    if source.get("ONBOARDING_STEPS").is_none() {
+        onboarding_steps = ONBOARDING_STEPS.map(String::from).to_vec();
+    }
+    if let Some(unknown) = onboarding_steps.iter().find(|step| !ONBOARDING_STEPS.contains(&step.as_str())) {
+        let message = format!("unknown step {:?}; known steps are {}", unknown, ONBOARDING_STEPS.join(", "));
+        source.invalid("ONBOARDING_STEPS", message);
+    }
+    let onboarding_config = OnboardingConfig {
+        steps: onboarding_steps,
+        follow_target: source.parse_or::<i64>("ONBOARDING_FOLLOW_TARGET", 3).max(1),
+    };
+
+    let features_config = FeaturesConfig {
+        defaults: FEATURE_FLAGS
+            .into_iter()
+            .map(|name| (name.to_string(), source.parse_or::<bool>(&format!("FEATURE_{}", name.to_uppercase()), true)))
+            .collect(),
+    };
+
+    let comments_config = CommentsConfig {
+        rate_limit: source.parse_or::<i64>("COMMENT_RATE_LIMIT", 5),
+        rate_window_seconds: source.parse_or::<i64>("COMMENT_RATE_WINDOW_SECONDS", 60),
+    };
+
+    let backfill_config = BackfillConfig {
+        batch_size: source.parse_or::<i64>("BACKFILL_BATCH_SIZE", 100),
+        batch_delay_ms: source.parse_or::<u64>("BACKFILL_BATCH_DELAY_MS", 250),
+        poll_interval_seconds: source.parse_or::<u64>("BACKFILL_POLL_INTERVAL_SECONDS", 5),
+    };
+
+    let rollout_config = RolloutConfig {
+        argon2_percent: source.parse_or::<u8>("ROLLOUT_ARGON2_PERCENT", 0).min(100),
+        opaque_refresh_tokens_percent: source.parse_or::<u8>("ROLLOUT_OPAQUE_REFRESH_TOKENS_PERCENT", 0).min(100),
+    };
+
+    let s3_config = source.get("S3_BUCKET").map(|bucket| S3Config {
+        endpoint: source.required_when("S3_ENDPOINT", "S3_BUCKET")
+            .trim_end_matches('/')
+            .to_string(),
+        bucket,
+        region: source.string_or("S3_REGION", "us-east-1"),
+        access_key_id: source.required_when("S3_ACCESS_KEY_ID", "S3_BUCKET"),
+        secret_access_key: source.required_when("S3_SECRET_ACCESS_KEY", "S3_BUCKET"),
+    });
+    if let Some(s3) = &s3_config
+        && !s3.endpoint.is_empty()
+    {
+        match url::Url::parse(&s3.endpoint) {
+            Ok(url) if url.host_str().is_some() => {}
+            Ok(_) => source.invalid("S3_ENDPOINT", "must have a host"),
+            Err(e) => source.invalid("S3_ENDPOINT", e),
+        }
+    }
+
+    let backups_config = BackupsConfig {
+        dir: source.string_or("BACKUP_DIR", "backups"),
+        to_s3: match source.string_or("BACKUP_STORAGE", "disk").as_str() {
+            "s3" => {
+                if s3_config.is_none() {
+                    source.errors.push(ConfigError::MissingWhen {
+                        key: "S3_BUCKET".to_string(),
+                        when: "BACKUP_STORAGE=s3".to_string(),
+                    });
+                }
+                true
+            }
+            "disk" => false,
+            other => {
+                source.invalid("BACKUP_STORAGE", format!("must be disk or s3, got {}", other));
+                false
+            }
+        },
+        interval_hours: source.parse_or::<u64>("BACKUP_INTERVAL_HOURS", 24),
+        keep: source.parse_or::<usize>("BACKUP_KEEP", 7).max(1),
+    };
+
+    let scheduler_config = SchedulerConfig {
+        poll_interval_seconds: source.parse_or::<u64>("SCHEDULER_POLL_INTERVAL_SECONDS", 10).max(1),
+        lease_seconds: source.parse_or::<u64>("JOB_LEASE_SECONDS", 300).max(30),
+        token_purge_interval_seconds: source.parse_or::<u64>("TOKEN_PURGE_INTERVAL_SECONDS", 3600).max(1),
+    };
+
+    let captioner_config = source.get("ALT_TEXT_CAPTIONER_URL").map(|url| CaptionerConfig {
+        url,
+        api_key: source.get("ALT_TEXT_CAPTIONER_API_KEY"),
+        poll_interval_seconds: source.parse_or::<u64>("ALT_TEXT_POLL_INTERVAL_SECONDS", 10),
+    });
+
+    let uploads_config = UploadsConfig {
+        dir: source.string_or("UPLOADS_DIR", "uploads"),
+        max_bytes: source.parse_or::<usize>("UPLOAD_MAX_BYTES", 5242880),
+        s3: s3_config,
+        captioner: captioner_config,
     };
 
+    let cache_config = CacheConfig {
+        redis_url: source.get("REDIS_URL"),
+        capacity: source.parse_or::<usize>("CACHE_CAPACITY", 1000),
+        ttl_seconds: source.parse_or::<u64>("CACHE_TTL_SECONDS", 300),
+        sitemap_refresh_interval_seconds: source.parse_or::<u64>("SITEMAP_REFRESH_INTERVAL_SECONDS", 15),
+    };
+    if let Some(redis_url) = &cache_config.redis_url
+        && let Err(e) = redis::Client::open(redis_url.as_str())
+    {
+        source.invalid("REDIS_URL", e);
+    }
+
+    let session_config = SessionConfig {
+        redis_url: match source.string_or("SESSION_STORE", "database").as_str() {
+            "redis" => {
+                if cache_config.redis_url.is_none() {
+                    source.errors.push(ConfigError::MissingWhen {
+                        key: "REDIS_URL".to_string(),
+                        when: "SESSION_STORE=redis".to_string(),
+                    });
+                }
+                cache_config.redis_url.clone()
+            }
+            "database" => None,
+            other => {
+                source.invalid("SESSION_STORE", format!("must be database or redis, got {}", other));
+                None
+            }
+        },
+    };
+
+    let notifications_config = NotificationsConfig {
+        batch_window_seconds: source.parse_or::<i64>("NOTIFICATION_BATCH_WINDOW_SECONDS", 60),
+        dispatch_interval_seconds: source.parse_or::<u64>("NOTIFICATION_DISPATCH_INTERVAL_SECONDS", 15),
+        live_ping_interval_seconds: source.parse_or::<u64>("LIVE_PING_INTERVAL_SECONDS", 30),
+        live_idle_timeout_seconds: source.parse_or::<u64>("LIVE_IDLE_TIMEOUT_SECONDS", 90),
+        vapid_private_key: source.get("VAPID_PRIVATE_KEY"),
+        vapid_subject: source.get("VAPID_SUBJECT").unwrap_or_else(|| server_config.public_url.clone()),
+        digest_check_interval_seconds: source.parse_or::<u64>("DIGEST_CHECK_INTERVAL_SECONDS", 3600),
+    };
+    if let Some(key) = &notifications_config.vapid_private_key
+        && let Err(e) = web_push::VapidSignatureBuilder::from_base64_no_sub(key)
+    {
+        source.invalid("VAPID_PRIVATE_KEY", format!("must be a base64url-encoded P-256 private key: {}", e));
+    }
 
     Config {
         server: server_config,
         db: database_config,
         cors:cors_config,
         jwt: jwt_config,
-        github: github_oauth_config
+        cookies: cookies_config,
+        github: github_oauth_config,
+        captcha: captcha_config,
+        spam: spam_config,
+        http_client: http_client_config,
+        email: email_config,
+        posts: posts_config,
+        blog: blog_config,
+        nodeinfo: nodeinfo_config,
+        public_api: public_api_config,
+        retention: retention_config,
+        backups: backups_config,
+        scheduler: scheduler_config,
+        webhooks: webhooks_config,
+        avatars: avatars_config,
+        exports: exports_config,
+        onboarding: onboarding_config,
+        features: features_config,
+        comments: comments_config,
+        backfill: backfill_config,
+        uploads: uploads_config,
+        rollout: rollout_config,
+        cache: cache_config,
+        sessions: session_config,
+        notifications: notifications_config,
+    }
+}
+
+/// Loads the configuration from the config file, `.env` and the environment, with every
+/// problem found if it's invalid. The server hands it to `AppState`; nothing keeps a global copy.
+pub fn load() -> Result<Config, ConfigErrors> {
+    init_config()
+}
+
+/// The configuration the server is running with. Starts as the loaded config; reloads swap
+/// in a copy with only the settings that are safe to change on a running server updated.
+#[derive(Clone)]
+pub struct ConfigHandle(Arc<ArcSwap<Config>>);
+
+impl ConfigHandle {
+    pub fn new(config: Config) -> Self {
+        Self(Arc::new(ArcSwap::from_pointee(config)))
+    }
+
+    pub fn load(&self) -> Guard<Arc<Config>> {
+        self.0.load()
+    }
+
+    /// Reads the config file and environment again and applies the log level, rate limits,
+    /// CORS origins and feature flags from them, returning the names of the settings that
+    /// changed. Anything else needs a restart. An invalid config changes nothing.
+    pub fn reload(&self) -> Result<Vec<&'static str>, ConfigErrors> {
+        let (next, changed) = with_reloadable(&self.load(), init_config()?);
+        if !changed.is_empty() {
+            self.0.store(Arc::new(next));
+        }
+        Ok(changed)
     }
 }
 
-pub async fn config() -> &'static Config {
-    CONFIG.get_or_init(init_config).await
+/// `current` with the reloadable settings taken from `fresh`, and what changed.
+fn with_reloadable(current: &Config, fresh: Config) -> (Config, Vec<&'static str>) {
+    let mut next = current.clone();
+    let mut changed = Vec::new();
+
+    if next.server.log_level != fresh.server.log_level {
+        next.server.log_level = fresh.server.log_level;
+        changed.push("log level");
+    }
+    if next.public_api != fresh.public_api || next.comments != fresh.comments {
+        next.public_api = fresh.public_api;
+        next.comments = fresh.comments;
+        changed.push("rate limits");
+    }
+    if next.cors != fresh.cors {
+        next.cors = fresh.cors;
+        changed.push("CORS origins");
+    }
+    if next.blog != fresh.blog || next.rollout != fresh.rollout || next.features != fresh.features {
+        next.blog = fresh.blog;
+        next.rollout = fresh.rollout;
+        next.features = fresh.features;
+        changed.push("feature flags");
+    }
+    (next, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(file: &[(&str, &str)], env: &[(&str, &str)]) -> Source {
+        let pairs = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Source { file: pairs(file), env: pairs(env), errors: Vec::new() }
+    }
+
+    #[test]
+    fn file_tables_flatten_into_env_names() {
+        let document = serde_json::json!({
+            "port": 9000,
+            "export": { "ttl_hours": 12 },
+            "trusted_proxies": ["10.0.0.0/8", "127.0.0.1"],
+            "tls": null,
+        });
+        let mut values = HashMap::new();
+        flatten(&document, String::new(), &mut values);
+
+        assert_eq!(values["PORT"], "9000");
+        assert_eq!(values["EXPORT_TTL_HOURS"], "12");
+        assert_eq!(values["TRUSTED_PROXIES"], "10.0.0.0/8,127.0.0.1");
+        assert!(!values.contains_key("TLS"));
+    }
+
+    #[test]
+    fn env_overrides_the_file_and_blank_values_fall_through() {
+        let source = source(&[("PORT", "9000"), ("HOST", "0.0.0.0")], &[("PORT", "9100"), ("HOST", "")]);
+        assert_eq!(source.get("PORT").as_deref(), Some("9100"));
+        assert_eq!(source.get("HOST").as_deref(), Some("0.0.0.0"));
+    }
+
+    #[test]
+    fn every_problem_is_reported_at_once() {
+        let mut source = source(&[("PORT", "eighty")], &[("S3_BUCKET", "media"), ("SESSION_STORE", "memcached")]);
+        build_config(&mut source);
+        let errors: Vec<String> = source.errors.iter().map(ToString::to_string).collect();
+
+        assert!(errors.contains(&"DATABASE_URL must be set".to_string()));
+        assert!(errors.contains(&"ACCESS_EXPIRES must be set".to_string()));
+        assert!(errors.contains(&"S3_ENDPOINT must be set when S3_BUCKET is".to_string()));
+        assert!(errors.iter().any(|e| e.starts_with("PORT is invalid")));
+        assert!(errors.iter().any(|e| e.starts_with("SESSION_STORE is invalid")));
+    }
+
+    #[test]
+    fn captchas_need_a_provider_and_can_be_switched_off_per_endpoint() {
+        assert!(!build(&[]).captcha_required(CaptchaEndpoint::Signup));
+
+        let config = build(&[("CAPTCHA_PROVIDER", "recaptcha"), ("CAPTCHA_SECRET", "s"), ("CAPTCHA_PASSWORD_RESET", "false")]);
+        assert!(config.captcha_required(CaptchaEndpoint::Signup));
+        assert!(!config.captcha_required(CaptchaEndpoint::PasswordReset));
+
+        let mut source = source(&[], &[("CAPTCHA_PROVIDER", "hcaptcha")]);
+        build_config(&mut source);
+        assert!(source.errors.iter().any(|e| e.to_string() == "CAPTCHA_SECRET must be set when CAPTCHA_PROVIDER is"));
+    }
+
+    #[test]
+    fn spam_thresholds_pick_the_most_severe_verdict_reached() {
+        assert_eq!(build(&[]).spam_verdict(1.0), Verdict::Allow);
+
+        let config = build(&[("SPAM_CHECKER", "heuristic"), ("SPAM_CHECK_SIGNUPS", "false")]);
+        assert!(config.spam_checked(SpamCheck::Comment));
+        assert!(!config.spam_checked(SpamCheck::Signup));
+        assert_eq!(config.spam_verdict(0.2), Verdict::Allow);
+        assert_eq!(config.spam_verdict(0.5), Verdict::Queue);
+        assert_eq!(config.spam_verdict(0.8), Verdict::Hide);
+        assert_eq!(config.spam_verdict(0.95), Verdict::Reject);
+
+        let mut source = source(&[], &[("SPAM_CHECKER", "akismet"), ("SPAM_HIDE_THRESHOLD", "0.3")]);
+        build_config(&mut source);
+        assert!(source.errors.iter().any(|e| e.to_string() == "SPAM_AKISMET_API_KEY must be set when SPAM_CHECKER is"));
+        assert!(source.errors.iter().any(|e| e.to_string().starts_with("SPAM_QUEUE_THRESHOLD is invalid")));
+    }
+
+    #[test]
+    fn the_http_proxy_must_be_a_url() {
+        assert_eq!(build(&[("HTTP_PROXY_URL", "http://proxy.internal:3128")]).http_proxy_url(), Some("http://proxy.internal:3128"));
+
+        let mut source = source(&[], &[("HTTP_PROXY_URL", "not a proxy")]);
+        build_config(&mut source);
+        assert!(source.errors.iter().any(|e| e.to_string().starts_with("HTTP_PROXY_URL is invalid")));
+    }
+
+    #[test]
+    fn service_urls_and_keys_are_checked_when_loaded() {
+        let mut source = source(&[], &[
+            ("SMTP_URL", "not a url"),
+            ("EMAIL_FROM", "nobody"),
+            ("REDIS_URL", "http://cache"),
+            ("S3_BUCKET", "media"),
+            ("S3_ENDPOINT", "localhost"),
+            ("VAPID_PRIVATE_KEY", "not a key"),
+        ]);
+        build_config(&mut source);
+        for key in ["SMTP_URL", "EMAIL_FROM", "REDIS_URL", "S3_ENDPOINT", "VAPID_PRIVATE_KEY"] {
+            assert!(
+                source.errors.iter().any(|e| e.to_string().starts_with(&format!("{} is invalid", key))),
+                "{} wasn't reported: {:?}", key, source.errors,
+            );
+        }
+    }
+
+    #[test]
+    fn cookies_follow_tls_unless_configured_and_same_site_none_needs_secure() {
+        let config = build(&[]);
+        assert!(!config.secure_cookies());
+        assert_eq!(config.cookie_same_site(), SameSite::Strict);
+        assert_eq!(config.refresh_cookie_name(), "refresh_token");
+        assert!(build(&[("PUBLIC_URL", "https://tsumi.test")]).secure_cookies());
+
+        let config = build(&[("COOKIE_SAME_SITE", "None"), ("COOKIE_SECURE", "true"), ("COOKIE_REFRESH_NAME", "rt")]);
+        assert_eq!(config.cookie_same_site(), SameSite::None);
+        assert_eq!(config.refresh_cookie_name(), "rt");
+
+        let mut source = source(&[], &[("COOKIE_SAME_SITE", "none")]);
+        build_config(&mut source);
+        assert!(source.errors.iter().any(|e| e.to_string() == "COOKIE_SAME_SITE is invalid: none needs COOKIE_SECURE"));
+    }
+
+    fn build(overrides: &[(&str, &str)]) -> Config {
+        let required = [
+            ("DATABASE_URL", "tsumi.db"),
+            ("CORS_ORIGIN", "http://a.test"),
+            ("ACCESS_SECRET", "a"),
+            ("ACCESS_EXPIRES", "1"),
+            ("REFRESH_TOKEN", "r"),
+            ("REFRESH_EXPIRES", "24"),
+            ("GITHUB_OAUTH_CLIENT_ID", "id"),
+            ("GITHUB_OAUTH_CLIENT_SECRET", "secret"),
+        ];
+        let mut source = source(&required, overrides);
+        let config = build_config(&mut source);
+        assert!(source.errors.is_empty(), "{:?}", source.errors);
+        config
+    }
+
+    #[test]
+    fn reloads_only_take_reloadable_settings() {
+        let current = build(&[]);
+        let fresh = build(&[
+            ("CORS_ORIGIN", "http://b.test"),
+            ("COMMENT_RATE_LIMIT", "50"),
+            ("BLOG_STYLES_ENABLED", "false"),
+            ("PORT", "9999"),
+            ("DATABASE_URL", "other.db"),
+        ]);
+        let (next, changed) = with_reloadable(&current, fresh);
+
+        assert_eq!(changed, ["rate limits", "CORS origins", "feature flags"]);
+        assert_eq!(next.cors_origin(), ["http://b.test"]);
+        assert_eq!(next.comment_rate_limit(), 50);
+        assert!(!next.blog_styles_enabled());
+        assert_eq!(next.server_port(), current.server_port());
+        assert_eq!(next.db_url(), "tsumi.db");
+    }
 }
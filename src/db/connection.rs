@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use diesel::connection::SimpleConnection;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Error, Pool};
+use diesel::{QueryResult, SqliteConnection};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::state::DbPool;
+
+/// The connection pool shared by the server and every CLI command.
+pub fn build_pool(config: &Config) -> DbPool {
+    let url = config.db_url();
+    let manager = ConnectionManager::<SqliteConnection>::new(url.to_string());
+    let idle_timeout = config.db_idle_timeout_seconds();
+    Pool::builder()
+        .max_size(config.db_pool_max_size().max(1))
+        .min_idle(config.db_pool_min_idle())
+        .connection_timeout(Duration::from_secs(config.db_acquire_timeout_seconds().max(1)))
+        .idle_timeout((idle_timeout > 0).then(|| Duration::from_secs(idle_timeout)))
+        .connection_customizer(Box::new(SqliteCustomizer {
+            wal: !is_in_memory(url),
+            busy_timeout_ms: config.db_busy_timeout_ms(),
+        }))
+        .build(manager)
+        .expect("Failed to create pool.")
+}
+
+/// In-memory databases have no journal file to switch to WAL.
+pub fn is_in_memory(url: &str) -> bool {
+    url == ":memory:" || url.contains("mode=memory")
+}
+
+/// Applies per-connection SQLite settings as connections are opened by the pool.
+///
+/// SQLite ignores `foreign key ... on delete cascade` clauses unless `foreign_keys` is enabled
+/// on every connection, so without this the cascades declared in the migrations never fire.
+/// WAL lets readers carry on while a write is in progress, and `busy_timeout` makes a writer
+/// wait its turn for the lock instead of failing straight away.
+#[derive(Debug, Clone, Copy)]
+pub struct SqliteCustomizer {
+    pub wal: bool,
+    pub busy_timeout_ms: u64,
+}
+
+impl Default for SqliteCustomizer {
+    fn default() -> Self {
+        Self { wal: false, busy_timeout_ms: 5000 }
+    }
+}
+
+impl CustomizeConnection<SqliteConnection, Error> for SqliteCustomizer {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), Error> {
+        let mut pragmas = format!("PRAGMA busy_timeout = {}; PRAGMA foreign_keys = ON;", self.busy_timeout_ms);
+        if self.wal {
+            pragmas.push_str(" PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;");
+        }
+        conn.batch_execute(&pragmas)
+            .map_err(Error::QueryError)
+    }
+}
+
+/// How busy the pool is right now.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PoolStats {
+    pub max_size: u32,
+    pub connections: u32,
+    pub idle_connections: u32,
+    /// Connections handed out to requests and workers.
+    pub in_use: u32,
+}
+
+pub fn pool_stats(pool: &DbPool) -> PoolStats {
+    let state = pool.state();
+    PoolStats {
+        max_size: pool.max_size(),
+        connections: state.connections,
+        idle_connections: state.idle_connections,
+        in_use: state.connections.saturating_sub(state.idle_connections),
+    }
+}
+
+/// Runs `f` with a pooled connection on the blocking thread pool, for async code that mustn't
+/// hold up the runtime while it waits on SQLite. Errors come back as text for the caller to log.
+pub(crate) async fn run_blocking<T, F>(pool: &DbPool, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut SqliteConnection) -> QueryResult<T> + Send + 'static,
+{
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| e.to_string())?;
+        f(&mut conn).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::sql_types::{BigInt, Text};
+    use diesel::{sql_query, Connection, QueryableByName, RunQueryDsl};
+
+    use super::*;
+
+    #[derive(QueryableByName)]
+    struct JournalMode {
+        #[diesel(sql_type = Text)]
+        journal_mode: String,
+    }
+
+    #[derive(QueryableByName)]
+    struct BusyTimeout {
+        #[diesel(sql_type = BigInt)]
+        timeout: i64,
+    }
+
+    #[test]
+    fn file_databases_get_wal_and_a_busy_timeout() {
+        let path = std::env::temp_dir().join(format!("tsumi-pool-{}.db", uuid::Uuid::new_v4()));
+        let url = path.to_string_lossy().to_string();
+        assert!(!is_in_memory(&url));
+        assert!(is_in_memory("file:test?mode=memory&cache=shared"));
+
+        let mut conn = SqliteConnection::establish(&url).unwrap();
+        SqliteCustomizer { wal: !is_in_memory(&url), busy_timeout_ms: 1234 }.on_acquire(&mut conn).unwrap();
+
+        let mode = sql_query("PRAGMA journal_mode").get_result::<JournalMode>(&mut conn).unwrap();
+        assert_eq!(mode.journal_mode, "wal");
+        let timeout = sql_query("PRAGMA busy_timeout").get_result::<BusyTimeout>(&mut conn).unwrap();
+        assert_eq!(timeout.timeout, 1234);
+
+        drop(conn);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{url}{suffix}"));
+        }
+    }
+}
@@ -1,3 +1,6 @@
+pub mod connection;
 pub mod models;
+pub mod nocase;
 pub mod schema;
-pub mod queries;
\ No newline at end of file
+pub mod queries;
+pub mod write;
\ No newline at end of file
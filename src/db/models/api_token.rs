@@ -0,0 +1,29 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = crate::db::schema::api_tokens)]
+pub struct ApiTokens {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: String,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Serialize)]
+#[diesel(table_name = crate::db::schema::api_tokens)]
+pub struct NewApiToken {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub token_prefix: String,
+    pub token_hash: String,
+    pub scopes: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
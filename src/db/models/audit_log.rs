@@ -0,0 +1,18 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// One security-sensitive event. `user_id` is the account it concerns and `actor_id` whoever
+/// acted on it, when that's someone else (an admin). Neither references `users`, so the
+/// trail outlives purged accounts.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::audit_logs)]
+pub struct AuditLogs {
+    pub id: String,
+    pub event: String,
+    pub user_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub detail: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
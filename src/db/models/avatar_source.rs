@@ -0,0 +1,13 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// An upstream avatar image that may be fetched through `/avatars/{id}`. The id is random,
+/// so proxied URLs don't give away the email hash Gravatar would otherwise see.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::avatar_sources)]
+pub struct AvatarSources {
+    pub id: String,
+    pub user_id: String,
+    pub upstream_url: String,
+    pub created_at: NaiveDateTime,
+}
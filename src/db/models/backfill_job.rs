@@ -0,0 +1,50 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+pub const BACKFILL_KIND_WORD_COUNT: &str = "word_count";
+pub const BACKFILL_KIND_DESCRIPTION: &str = "description";
+pub const BACKFILL_KIND_SEARCH_INDEX: &str = "search_index";
+pub const BACKFILL_KIND_OG_IMAGE: &str = "og_image";
+
+pub const BACKFILL_KINDS: [&str; 4] = [
+    BACKFILL_KIND_WORD_COUNT,
+    BACKFILL_KIND_DESCRIPTION,
+    BACKFILL_KIND_SEARCH_INDEX,
+    BACKFILL_KIND_OG_IMAGE,
+];
+
+pub const BACKFILL_STATUS_PENDING: &str = "pending";
+pub const BACKFILL_STATUS_RUNNING: &str = "running";
+pub const BACKFILL_STATUS_PAUSED: &str = "paused";
+pub const BACKFILL_STATUS_COMPLETED: &str = "completed";
+pub const BACKFILL_STATUS_FAILED: &str = "failed";
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::backfill_jobs)]
+pub struct BackfillJobs {
+    pub id: String,
+    pub kind: String,
+    pub status: String,
+    /// Id of the last post processed; the job resumes after it.
+    pub cursor: Option<String>,
+    pub total: i32,
+    pub processed: i32,
+    pub error: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::db::schema::backfill_jobs)]
+pub struct NewBackfillJob {
+    pub id: String,
+    pub kind: String,
+    pub status: String,
+    pub total: i32,
+    pub created_by: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+/// One saved version of a blog's custom CSS and head snippets. The highest version is live.
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = crate::db::schema::blog_styles)]
+pub struct BlogStyles {
+    pub id: String,
+    pub user_id: String,
+    pub version: i32,
+    pub css: String,
+    pub head_html: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::db::schema::blog_styles)]
+pub struct NewBlogStyle {
+    pub id: String,
+    pub user_id: String,
+    pub version: i32,
+    pub css: String,
+    pub head_html: String,
+    pub created_at: NaiveDateTime,
+}
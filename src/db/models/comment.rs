@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::comments)]
+pub struct Comments {
+    pub id: String,
+    pub post_id: String,
+    pub user_id: String,
+    pub parent_id: Option<String>,
+    /// The top-level comment of the thread, `None` for top-level comments themselves.
+    pub root_id: Option<String>,
+    pub body: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub deleted_by: Option<String>,
+    /// `visible`, `hidden` by a moderator, or `pending` or `shadowed` by the spam check; see
+    /// [`crate::db::models::report`].
+    pub moderation_status: String,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::db::schema::comments)]
+pub struct NewComment {
+    pub id: String,
+    pub post_id: String,
+    pub user_id: String,
+    pub parent_id: Option<String>,
+    pub root_id: Option<String>,
+    pub body: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub moderation_status: String,
+}
@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+pub const EMAIL_OUTBOX_PENDING: &str = "pending";
+pub const EMAIL_OUTBOX_SENT: &str = "sent";
+/// Dropped by the mailer because the address is suppressed.
+pub const EMAIL_OUTBOX_SUPPRESSED: &str = "suppressed";
+/// Out of attempts; left for an admin to look at or retry.
+pub const EMAIL_OUTBOX_FAILED: &str = "failed";
+
+/// One outgoing email. Written in the same transaction as whatever it's about, so a message
+/// is queued exactly when the change that sent it is kept. The bodies are cleared once sent,
+/// as they can carry one-time links.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::email_outbox)]
+pub struct EmailOutbox {
+    pub id: String,
+    pub recipient: String,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: Option<String>,
+    /// `transactional` or `bulk`, the lane that sends it.
+    pub priority: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+pub const SUPPRESSION_REASON_BOUNCE: &str = "bounce";
+pub const SUPPRESSION_REASON_COMPLAINT: &str = "complaint";
+
+#[derive(Selectable, Queryable, Serialize, Debug)]
+#[diesel(table_name = crate::db::schema::email_suppressions)]
+pub struct EmailSuppressions {
+    pub id: String,
+    pub email: String,
+    pub reason: String,
+    pub provider: String,
+    pub details: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub reactivated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::db::schema::email_suppressions)]
+pub struct NewEmailSuppression {
+    pub id: String,
+    pub email: String,
+    pub reason: String,
+    pub provider: String,
+    pub details: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
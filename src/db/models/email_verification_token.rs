@@ -0,0 +1,19 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = crate::db::schema::email_verification_tokens)]
+pub struct EmailVerificationTokens {
+    pub expires_at: NaiveDateTime,
+    pub user_id: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::db::schema::email_verification_tokens)]
+pub struct NewEmailVerificationToken {
+    pub id: String,
+    pub token: String,
+    pub expires_at: NaiveDateTime,
+    pub user_id: String,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+pub const EXPORT_STATUS_PENDING: &str = "pending";
+pub const EXPORT_STATUS_RUNNING: &str = "running";
+pub const EXPORT_STATUS_READY: &str = "ready";
+pub const EXPORT_STATUS_FAILED: &str = "failed";
+/// The archive was deleted after `expires_at`; the row stays as a record of the export.
+pub const EXPORT_STATUS_EXPIRED: &str = "expired";
+
+/// A request for a zip of everything the user has stored here. `storage_key` points at the
+/// archive once it's ready.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::export_jobs)]
+pub struct ExportJobs {
+    pub id: String,
+    pub user_id: String,
+    pub status: String,
+    pub storage_key: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+}
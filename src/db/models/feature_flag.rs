@@ -0,0 +1,19 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+pub const FEATURE_SIGNUP: &str = "signup_enabled";
+pub const FEATURE_OAUTH_GITHUB: &str = "oauth_github_enabled";
+pub const FEATURE_COMMENTS: &str = "comments_enabled";
+
+/// Every flag there is. Each defaults to its `FEATURE_<NAME>` setting, on unless configured.
+pub const FEATURE_FLAGS: [&str; 3] = [FEATURE_SIGNUP, FEATURE_OAUTH_GITHUB, FEATURE_COMMENTS];
+
+/// An admin's runtime override of a flag's configured default. Deleting it restores the default.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::feature_flags)]
+pub struct FeatureFlags {
+    pub name: String,
+    pub enabled: bool,
+    pub updated_by: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
@@ -0,0 +1,11 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// `follower_id` follows `followee_id` and sees their posts in the feed.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::follows)]
+pub struct Follows {
+    pub follower_id: String,
+    pub followee_id: String,
+    pub created_at: NaiveDateTime,
+}
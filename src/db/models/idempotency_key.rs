@@ -0,0 +1,23 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// Starts the scope of a request with an `Idempotency-Key` sent when nobody was signed in,
+/// followed by a hash of the client's address and user agent.
+pub const ANONYMOUS_SCOPE_PREFIX: &str = "anonymous:";
+
+/// A POST sent with an `Idempotency-Key`, kept so retries of it get the first response back.
+/// Keys are per user, or per client when signed out, and `request_hash` ties each one to the request it was first used for.
+/// `status_code` stays null until the first request has been answered.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::idempotency_keys)]
+pub struct IdempotencyKeys {
+    pub id: String,
+    pub scope: String,
+    pub idempotency_key: String,
+    pub request_hash: String,
+    pub status_code: Option<i32>,
+    pub content_type: Option<String>,
+    pub body: Option<Vec<u8>>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
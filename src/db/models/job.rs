@@ -0,0 +1,15 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// A scheduled job's row: when it next runs and how its last run went. Shared by every
+/// server on the database; whichever holds the job's lock in `job_locks` runs it.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::jobs)]
+pub struct Jobs {
+    pub name: String,
+    /// `None` when the job only runs on demand and nobody has asked.
+    pub next_run_at: Option<NaiveDateTime>,
+    pub last_started_at: Option<NaiveDateTime>,
+    pub last_finished_at: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+}
@@ -0,0 +1,14 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// A lease on a named piece of background work, so that of the servers sharing a database
+/// only its holder does it. A lease past `expires_at` was abandoned and can be taken.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::job_locks)]
+pub struct JobLocks {
+    pub name: String,
+    /// The server holding it, by the id it picked at startup.
+    pub holder: String,
+    pub acquired_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
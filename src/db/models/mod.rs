@@ -1,3 +1,41 @@
 pub mod user_model;
 pub mod refresh_token;
-mod accounts;
\ No newline at end of file
+pub mod api_token;
+pub mod email_verification_token;
+pub mod email_suppression;
+pub mod post;
+pub mod post_version;
+pub mod post_reaction;
+pub mod tag;
+pub mod comment;
+pub mod backfill_job;
+pub mod upload;
+pub mod blog_style;
+pub mod post_fingerprint;
+pub mod post_lock;
+pub mod post_doc;
+pub mod notification_delivery;
+pub mod follow;
+pub mod push_subscription;
+pub mod notification;
+pub mod user_preferences;
+pub mod audit_log;
+pub mod webhook;
+pub mod webhook_delivery;
+pub mod avatar_source;
+pub mod export_job;
+pub mod page;
+pub mod page_version;
+pub mod onboarding_step;
+pub mod feature_flag;
+pub mod reset_token;
+pub mod user_device;
+pub mod post_slug;
+pub mod post_collaborator;
+pub mod series;
+pub mod post_view;
+pub mod idempotency_key;
+pub mod report;
+pub mod email_outbox;
+pub mod job;
+pub mod job_lock;
\ No newline at end of file
@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+pub const NOTIFICATION_KIND_COMMENT: &str = "comment";
+pub const NOTIFICATION_KIND_FOLLOW: &str = "follow";
+pub const NOTIFICATION_KIND_POST_PUBLISHED: &str = "post_published";
+/// A moderator hid the recipient's post, or their comment when `comment_id` is set.
+pub const NOTIFICATION_KIND_CONTENT_HIDDEN: &str = "content_hidden";
+
+/// Something shown in a user's in-app notification list. `actor_id` is whoever caused it;
+/// `post_id` and `comment_id` point at what it's about, when it's about anything.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::notifications)]
+pub struct Notifications {
+    pub id: String,
+    pub user_id: String,
+    pub kind: String,
+    pub actor_id: Option<String>,
+    pub post_id: Option<String>,
+    pub comment_id: Option<String>,
+    pub read_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// A notification with the names needed to show it.
+#[derive(Debug, Clone)]
+pub struct NotificationEntry {
+    pub notification: Notifications,
+    pub actor_name: Option<String>,
+    pub post_title: Option<String>,
+}
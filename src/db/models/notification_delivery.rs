@@ -0,0 +1,19 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// A notification waiting to go out on one channel. Events arriving while it waits are
+/// folded into it by `coalesce_key`, so a burst of reactions becomes a single message.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::notification_deliveries)]
+pub struct NotificationDeliveries {
+    pub id: String,
+    pub user_id: String,
+    pub channel: String,
+    pub coalesce_key: String,
+    pub kind: String,
+    pub post_id: String,
+    pub actors: String,
+    pub event_count: i32,
+    pub deliver_after: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+pub const ONBOARDING_STEP_VERIFY_EMAIL: &str = "verify_email";
+pub const ONBOARDING_STEP_SET_AVATAR: &str = "set_avatar";
+pub const ONBOARDING_STEP_FIRST_POST: &str = "first_post";
+pub const ONBOARDING_STEP_FOLLOW_AUTHORS: &str = "follow_authors";
+
+/// Every step the checklist knows, in the order it's shown by default.
+pub const ONBOARDING_STEPS: [&str; 4] = [
+    ONBOARDING_STEP_VERIFY_EMAIL,
+    ONBOARDING_STEP_SET_AVATAR,
+    ONBOARDING_STEP_FIRST_POST,
+    ONBOARDING_STEP_FOLLOW_AUTHORS,
+];
+
+/// A checklist step the user has completed. Steps are recorded as they happen and never
+/// un-completed, so unpublishing a first post doesn't send the user back.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::onboarding_steps)]
+pub struct OnboardingSteps {
+    pub user_id: String,
+    pub step: String,
+    pub completed_at: NaiveDateTime,
+}
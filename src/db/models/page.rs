@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+/// A site page such as About, Terms or Privacy, served at `/p/{slug}`. Pages belong to the
+/// site rather than an author and are managed by admins. A page without `published_at` is a
+/// draft.
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::pages)]
+pub struct Pages {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub content: String,
+    pub published_at: Option<NaiveDateTime>,
+    pub created_by: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(AsChangeset, Debug, Default)]
+#[diesel(table_name = crate::db::schema::pages)]
+pub struct PageChanges {
+    pub slug: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub content: Option<String>,
+    pub published_at: Option<Option<NaiveDateTime>>,
+    pub updated_at: Option<NaiveDateTime>,
+}
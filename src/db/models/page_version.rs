@@ -0,0 +1,18 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+/// A saved revision of a page, kept the same way as [`PostVersions`](crate::db::models::post_version::PostVersions).
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug)]
+#[diesel(table_name = crate::db::schema::page_versions)]
+pub struct PageVersions {
+    pub id: String,
+    pub page_id: String,
+    pub user_id: Option<String>,
+    pub title: String,
+    pub content: String,
+    pub description: String,
+    pub commit_hash: String,
+    pub commit_message: String,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,61 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+pub const POST_STATUS_DRAFT: &str = "draft";
+pub const POST_STATUS_SCHEDULED: &str = "scheduled";
+pub const POST_STATUS_PUBLISHED: &str = "published";
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::posts)]
+pub struct Posts {
+    pub id: String,
+    pub user_id: String,
+    pub title: String,
+    pub description: String,
+    pub slug: String,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub status: String,
+    pub published_at: Option<NaiveDateTime>,
+    pub word_count: Option<i32>,
+    pub og_image_url: Option<String>,
+    pub cover_upload_id: Option<String>,
+    pub reading_minutes: Option<i32>,
+    /// `visible`, or `hidden` by a moderator; see [`crate::db::models::report`].
+    pub moderation_status: String,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::db::schema::posts)]
+pub struct NewPost {
+    pub id: String,
+    pub user_id: String,
+    pub title: String,
+    pub description: String,
+    pub slug: String,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub status: String,
+    pub published_at: Option<NaiveDateTime>,
+    pub word_count: Option<i32>,
+    pub og_image_url: Option<String>,
+    pub cover_upload_id: Option<String>,
+    pub reading_minutes: Option<i32>,
+}
+
+#[derive(AsChangeset, Debug, Default)]
+#[diesel(table_name = crate::db::schema::posts)]
+pub struct PostChanges {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub slug: Option<String>,
+    pub content: Option<String>,
+    pub word_count: Option<i32>,
+    pub reading_minutes: Option<i32>,
+    pub og_image_url: Option<Option<String>>,
+    pub cover_upload_id: Option<Option<String>>,
+    pub updated_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+pub const COLLABORATOR_ROLE_EDITOR: &str = "editor";
+pub const COLLABORATOR_ROLE_VIEWER: &str = "viewer";
+
+pub const COLLABORATOR_ROLES: [&str; 2] = [COLLABORATOR_ROLE_EDITOR, COLLABORATOR_ROLE_VIEWER];
+
+/// Someone the author invited to a post. Viewers can read it before it's published; editors
+/// can also change it while it's a draft.
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::post_collaborators)]
+pub struct PostCollaborators {
+    pub post_id: String,
+    pub user_id: String,
+    pub role: String,
+    pub invited_by: Option<String>,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,22 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// A post's collaborative document as of the last compaction, encoded as one Yjs update.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::post_docs)]
+pub struct PostDocs {
+    pub post_id: String,
+    pub state: Vec<u8>,
+    pub compacted_at: NaiveDateTime,
+}
+
+/// A Yjs update from an editor, kept until the next compaction folds it into `PostDocs`.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::post_doc_updates)]
+pub struct PostDocUpdates {
+    pub id: String,
+    pub post_id: String,
+    pub user_id: Option<String>,
+    pub data: Vec<u8>,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,19 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// A published post's simhash, kept for near-duplicate lookups.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::post_fingerprints)]
+pub struct PostFingerprints {
+    pub post_id: String,
+    pub user_id: String,
+    /// The `u64` simhash stored bit for bit.
+    pub simhash: i64,
+    pub band0: i32,
+    pub band1: i32,
+    pub band2: i32,
+    pub band3: i32,
+    /// Another author's post this one nearly duplicates.
+    pub duplicate_of: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
@@ -0,0 +1,17 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// An advisory lease on editing a post, held by one editor session until it expires or is
+/// released. Another session may ask to take it over.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::post_locks)]
+pub struct PostLocks {
+    pub post_id: String,
+    pub user_id: String,
+    pub session_id: String,
+    pub acquired_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub takeover_session_id: Option<String>,
+    pub takeover_user_id: Option<String>,
+    pub takeover_requested_at: Option<NaiveDateTime>,
+}
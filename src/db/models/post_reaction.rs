@@ -0,0 +1,17 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+pub const REACTION_LIKE: &str = "like";
+
+pub const REACTION_KINDS: [&str; 4] = [REACTION_LIKE, "love", "celebrate", "insightful"];
+
+/// A user's reaction to a post. Each user has at most one reaction per post.
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::post_reactions)]
+pub struct PostReactions {
+    pub post_id: String,
+    pub user_id: String,
+    pub kind: String,
+    pub created_at: NaiveDateTime,
+}
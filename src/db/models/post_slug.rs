@@ -0,0 +1,12 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// A slug one of an author's posts used to have, kept so links to it still lead to the post.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::post_slugs)]
+pub struct PostSlugs {
+    pub user_id: String,
+    pub slug: String,
+    pub post_id: String,
+    pub created_at: NaiveDateTime,
+}
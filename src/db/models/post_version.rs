@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = crate::db::schema::post_versions)]
+pub struct PostVersions {
+    pub id: String,
+    pub post_id: String,
+    pub user_id: String,
+    pub title: String,
+    pub content: String,
+    pub description: String,
+    pub commit_hash: String,
+    pub commit_message: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::db::schema::post_versions)]
+pub struct NewPostVersion {
+    pub id: String,
+    pub post_id: String,
+    pub user_id: String,
+    pub title: String,
+    pub content: String,
+    pub description: String,
+    pub commit_hash: String,
+    pub commit_message: String,
+    pub created_at: NaiveDateTime,
+}
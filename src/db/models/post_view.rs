@@ -0,0 +1,12 @@
+use chrono::NaiveDate;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// How many times a post was read on one day. Kept per day so popularity can be measured over
+/// a window.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::post_views)]
+pub struct PostViews {
+    pub post_id: String,
+    pub day: NaiveDate,
+    pub views: i32,
+}
@@ -0,0 +1,17 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// A browser's Web Push subscription. `p256dh` and `auth` are the browser's keys for
+/// encrypting payloads, base64url as the Push API hands them out.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::push_subscriptions)]
+pub struct PushSubscriptions {
+    pub id: String,
+    pub user_id: String,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
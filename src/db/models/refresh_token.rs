@@ -1,8 +1,8 @@
 use chrono::NaiveDateTime;
 use diesel::{Insertable, Queryable, Selectable};
-use serde::{Serialize};
+use serde::{Deserialize, Serialize};
 
-#[derive(Selectable, Queryable)]
+#[derive(Clone, Selectable, Queryable, Serialize, Deserialize)]
 #[diesel(table_name = crate::db::schema::refresh_tokens)]
 pub struct RefreshTokens {
     pub id: String,
@@ -10,6 +10,26 @@ pub struct RefreshTokens {
     pub user_id: String,
     pub expires_at: NaiveDateTime,
     pub created_at: NaiveDateTime,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// The session this token was issued in. Shared by every token rotated from the same
+    /// sign-in, so a reused token can revoke them all.
+    #[serde(default)]
+    pub family_id: String,
+    /// The token that replaced this one when it was refreshed. A replaced token is never valid.
+    #[serde(default)]
+    pub replaced_by: Option<String>,
+    /// A coarse label for the device the session was opened from, e.g. "Firefox on Linux".
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Whether the user asked to be remembered. Other sessions are short and their cookie
+    /// ends with the browser session.
+    #[serde(default = "remembered")]
+    pub persistent: bool,
+}
+
+fn remembered() -> bool {
+    true
 }
 
 #[derive(Insertable, Serialize)]
@@ -20,4 +40,9 @@ pub struct NewRefreshToken {
     pub user_id: String,
     pub expires_at: NaiveDateTime,
     pub created_at: NaiveDateTime,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub family_id: String,
+    pub device: Option<String>,
+    pub persistent: bool,
 }
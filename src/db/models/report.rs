@@ -0,0 +1,40 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+/// Shown wherever it would be anyway.
+pub const MODERATION_VISIBLE: &str = "visible";
+/// Hidden by a moderator: left out of public pages, feeds and APIs, though its author still
+/// sees it among their own.
+pub const MODERATION_HIDDEN: &str = "hidden";
+/// Comments only: held by the spam check until a moderator lets it through. Its author sees
+/// it, marked as waiting for review.
+pub const MODERATION_PENDING: &str = "pending";
+/// Comments only: hidden by the spam check without telling anyone. Its author sees it as if it
+/// were published.
+pub const MODERATION_SHADOWED: &str = "shadowed";
+
+pub const REPORT_STATUS_OPEN: &str = "open";
+/// A moderator hid the reported content.
+pub const REPORT_STATUS_ACTIONED: &str = "actioned";
+/// A moderator looked and let the content stand.
+pub const REPORT_STATUS_DISMISSED: &str = "dismissed";
+
+pub const REPORT_REASONS: &[&str] = &["spam", "harassment", "hate", "sexual", "violence", "misinformation", "other"];
+
+/// A user flagging a post, or a comment on it when `comment_id` is set, for moderators to review.
+/// Reports the spam check files have no `reporter_id`.
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::reports)]
+pub struct Reports {
+    pub id: String,
+    pub reporter_id: Option<String>,
+    pub post_id: String,
+    pub comment_id: Option<String>,
+    pub reason: String,
+    pub details: String,
+    pub status: String,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
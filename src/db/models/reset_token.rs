@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = crate::db::schema::reset_tokens)]
+pub struct ResetTokens {
+    pub expires_at: NaiveDateTime,
+    pub user_id: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::db::schema::reset_tokens)]
+pub struct NewResetToken {
+    pub id: String,
+    pub token: String,
+    pub expires_at: NaiveDateTime,
+    pub user_id: String,
+    pub created_at: NaiveDateTime,
+}
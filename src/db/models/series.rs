@@ -0,0 +1,34 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+/// An author's ordered collection of posts, such as the parts of a tutorial.
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::series)]
+pub struct Series {
+    pub id: String,
+    pub user_id: String,
+    pub title: String,
+    pub slug: String,
+    pub description: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(AsChangeset, Debug, Default)]
+#[diesel(table_name = crate::db::schema::series)]
+pub struct SeriesChanges {
+    pub title: Option<String>,
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// A post's place in a series. A post is in at most one series.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::series_posts)]
+pub struct SeriesPosts {
+    pub post_id: String,
+    pub series_id: String,
+    pub position: i32,
+}
@@ -0,0 +1,17 @@
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::tags)]
+pub struct Tags {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::db::schema::post_tags)]
+pub struct NewPostTag {
+    pub id: String,
+    pub post_id: String,
+    pub tag_id: String,
+}
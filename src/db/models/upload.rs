@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+pub const ALT_TEXT_PENDING: &str = "pending";
+pub const ALT_TEXT_SUGGESTED: &str = "suggested";
+pub const ALT_TEXT_FAILED: &str = "failed";
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::uploads)]
+pub struct Uploads {
+    pub id: String,
+    pub user_id: String,
+    /// Where the bytes live in the configured storage backend.
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub content_type: String,
+    pub byte_size: i32,
+    pub sha256: String,
+    pub original_filename: Option<String>,
+    pub created_at: NaiveDateTime,
+    /// Alt text the author approved, possibly edited from the suggestion.
+    pub alt_text: Option<String>,
+    /// Machine-generated alt text awaiting the author's review.
+    pub alt_text_suggestion: Option<String>,
+    /// `None` when no suggestion was requested, otherwise one of the `ALT_TEXT_*` states.
+    pub alt_text_status: Option<String>,
+    #[serde(skip_serializing)]
+    pub alt_text_attempts: i32,
+}
@@ -0,0 +1,13 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// A device the user has signed in from, keyed by its coarse label. Used to tell a sign-in
+/// from somewhere new apart from the usual browser.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::user_devices)]
+pub struct UserDevices {
+    pub user_id: String,
+    pub label: String,
+    pub first_seen_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+}
@@ -2,7 +2,12 @@ use chrono::NaiveDateTime;
 use diesel::{Insertable, Queryable, Selectable};
 use serde::{Deserialize, Serialize};
 
-#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+use crate::db::models::report::MODERATION_VISIBLE;
+
+pub const USER_STATUS_ACTIVE: &str = "active";
+pub const USER_STATUS_SUSPENDED: &str = "suspended";
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug, Clone)]
 #[diesel(table_name = crate::db::schema::users)]
 pub struct UserModel {
     pub id: String,
@@ -13,6 +18,67 @@ pub struct UserModel {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub deleted_at: Option<NaiveDateTime>,
+    pub is_admin: bool,
+    pub canonicalize_links: bool,
+    pub blog_styles_disabled: bool,
+    /// IANA time zone name, used to place quiet hours.
+    pub timezone: String,
+    /// Minutes past local midnight; see [`crate::services::notifications::quiet_until`].
+    pub quiet_hours_start: Option<i32>,
+    pub quiet_hours_end: Option<i32>,
+    /// Stamped into access tokens. Bumping it revokes every access token issued before.
+    #[serde(default)]
+    pub token_version: i32,
+    /// Language tag pages are shown in, ahead of the browser's `Accept-Language`.
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Where the avatar the user uploaded is served, e.g. `/avatars/3f2a…`. Without one, their
+    /// GitHub avatar or Gravatar is shown.
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub bio: String,
+    #[serde(default)]
+    pub website: Option<String>,
+    #[serde(default)]
+    pub location: Option<String>,
+    /// Hides the profile, email included, from signed-out viewers.
+    #[serde(default)]
+    pub profile_private: bool,
+    /// Shows the email on the profile to signed-in viewers.
+    #[serde(default)]
+    pub show_email: bool,
+    /// `active`, or `suspended` by an admin, which locks the user out until they're unsuspended.
+    #[serde(default = "active_status")]
+    pub status: String,
+    /// Where the user's new comments start out: `visible`, or `pending` or `shadowed` when the
+    /// spam check flagged their signup, until an admin trusts them.
+    #[serde(default = "visible_comments")]
+    pub comment_moderation: String,
+}
+
+fn active_status() -> String {
+    USER_STATUS_ACTIVE.to_string()
+}
+
+fn visible_comments() -> String {
+    MODERATION_VISIBLE.to_string()
+}
+
+impl UserModel {
+    /// Whether `viewer`, or a signed-out visitor for `None`, may see this user's profile.
+    pub fn profile_visible_to(&self, viewer: Option<&UserModel>) -> bool {
+        !self.profile_private || viewer.is_some()
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.status == USER_STATUS_SUSPENDED
+    }
+
+    /// Whether `viewer` may see this user's email on their profile.
+    pub fn email_visible_to(&self, viewer: Option<&UserModel>) -> bool {
+        viewer.is_some_and(|viewer| viewer.id == self.id || self.show_email)
+    }
 }
 
 #[derive(Insertable, Serialize, Deserialize, Debug)]
@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// Per-user settings kept out of `users`. Users without a row have the defaults.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::user_preferences)]
+pub struct UserPreferences {
+    pub user_id: String,
+    pub weekly_digest: bool,
+    pub digest_sent_at: Option<NaiveDateTime>,
+    pub updated_at: NaiveDateTime,
+    pub onboarding_dismissed_at: Option<NaiveDateTime>,
+}
+
+impl UserPreferences {
+    pub fn defaults(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            weekly_digest: true,
+            digest_sent_at: None,
+            updated_at: chrono::Utc::now().naive_utc(),
+            onboarding_dismissed_at: None,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+pub const WEBHOOK_EVENT_POST_PUBLISHED: &str = "post.published";
+pub const WEBHOOK_EVENT_POST_UNPUBLISHED: &str = "post.unpublished";
+pub const WEBHOOK_EVENT_POST_DELETED: &str = "post.deleted";
+pub const WEBHOOK_EVENT_COMMENT_CREATED: &str = "comment.created";
+pub const WEBHOOK_EVENT_REACTION_CREATED: &str = "reaction.created";
+
+pub const WEBHOOK_EVENTS: [&str; 5] = [
+    WEBHOOK_EVENT_POST_PUBLISHED,
+    WEBHOOK_EVENT_POST_UNPUBLISHED,
+    WEBHOOK_EVENT_POST_DELETED,
+    WEBHOOK_EVENT_COMMENT_CREATED,
+    WEBHOOK_EVENT_REACTION_CREATED,
+];
+
+/// An endpoint a user wants told about events on their content. `events` is a
+/// comma-separated list of the event names it subscribes to; `secret` signs each payload.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::webhooks)]
+pub struct Webhooks {
+    pub id: String,
+    pub user_id: String,
+    pub url: String,
+    pub secret: String,
+    pub events: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Webhooks {
+    pub fn event_list(&self) -> Vec<&str> {
+        self.events.split(',').filter(|event| !event.is_empty()).collect()
+    }
+
+    pub fn subscribes_to(&self, event: &str) -> bool {
+        self.event_list().contains(&event)
+    }
+}
@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+
+pub const WEBHOOK_DELIVERY_PENDING: &str = "pending";
+pub const WEBHOOK_DELIVERY_DELIVERED: &str = "delivered";
+pub const WEBHOOK_DELIVERY_FAILED: &str = "failed";
+
+/// One event sent, or still to be sent, to a webhook. `payload` is the exact JSON body, so
+/// retries are signed over the same bytes. `response_status` and `last_error` describe the
+/// latest attempt.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::db::schema::webhook_deliveries)]
+pub struct WebhookDeliveries {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+}
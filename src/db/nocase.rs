@@ -0,0 +1,15 @@
+use diesel::expression::AsExpression;
+use diesel::sql_types::Text;
+use diesel::sqlite::Sqlite;
+use diesel::Expression;
+
+diesel::infix_operator!(EqNoCase, " COLLATE NOCASE = ", backend: Sqlite);
+
+/// Case-insensitive comparisons that can use the `collate nocase` indexes on `users`.
+pub trait NoCaseExpressionMethods: Expression<SqlType = Text> + Sized {
+    fn eq_nocase<T: AsExpression<Text>>(self, other: T) -> EqNoCase<Self, T::Expression> {
+        EqNoCase::new(self, other.as_expression())
+    }
+}
+
+impl<T: Expression<SqlType = Text>> NoCaseExpressionMethods for T {}
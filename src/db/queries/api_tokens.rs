@@ -0,0 +1,52 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use crate::db::models::api_token::{ApiTokens, NewApiToken};
+use crate::db::schema::api_tokens;
+
+impl ApiTokens {
+    pub fn by_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<ApiTokens>> {
+        api_tokens::table
+            .filter(api_tokens::user_id.eq(user_id))
+            .order(api_tokens::created_at.desc())
+            .select(ApiTokens::as_select())
+            .load(conn)
+    }
+
+    pub fn by_hash(conn: &mut SqliteConnection, token_hash: &str) -> QueryResult<Option<ApiTokens>> {
+        api_tokens::table
+            .filter(api_tokens::token_hash.eq(token_hash))
+            .select(ApiTokens::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn create(conn: &mut SqliteConnection, new_token: &NewApiToken) -> QueryResult<ApiTokens> {
+        diesel::insert_into(api_tokens::table)
+            .values(new_token)
+            .returning(ApiTokens::as_select())
+            .get_result(conn)
+    }
+
+    pub fn touch(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        diesel::update(api_tokens::table.filter(api_tokens::id.eq(id)))
+            .set(api_tokens::last_used_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+
+    pub fn delete_for_user(conn: &mut SqliteConnection, id: &str, user_id: &str) -> QueryResult<usize> {
+        diesel::delete(
+            api_tokens::table
+                .filter(api_tokens::id.eq(id))
+                .filter(api_tokens::user_id.eq(user_id)),
+        )
+            .execute(conn)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at < Utc::now().naive_utc())
+    }
+
+    pub fn scope_list(&self) -> Vec<String> {
+        self.scopes.split_whitespace().map(String::from).collect()
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use crate::db::models::audit_log::AuditLogs;
+use crate::db::schema::audit_logs;
+use crate::http::pagination::SortDir;
+
+/// Optional narrowing for audit log listings. Every field left `None` matches everything.
+#[derive(Debug, Default)]
+pub struct AuditFilter<'a> {
+    pub user_id: Option<&'a str>,
+    pub actor_id: Option<&'a str>,
+    pub event: Option<&'a str>,
+    pub ip_address: Option<&'a str>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+impl AuditLogs {
+    pub fn create(conn: &mut SqliteConnection, entry: &AuditLogs) -> QueryResult<usize> {
+        diesel::insert_into(audit_logs::table)
+            .values(entry)
+            .execute(conn)
+    }
+
+    pub fn count_filtered(conn: &mut SqliteConnection, filter: &AuditFilter) -> QueryResult<i64> {
+        filtered(filter).count().get_result(conn)
+    }
+
+    /// A page of matching entries, newest first unless `dir` says otherwise.
+    pub fn page_filtered(
+        conn: &mut SqliteConnection,
+        filter: &AuditFilter,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<AuditLogs>> {
+        let query = filtered(filter);
+        let query = if dir.is_asc() {
+            query.order(audit_logs::created_at.asc())
+        } else {
+            query.order(audit_logs::created_at.desc())
+        };
+
+        query
+            .then_order_by(audit_logs::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select(AuditLogs::as_select())
+            .load(conn)
+    }
+
+    /// Deletes up to `limit` of the oldest entries created before `cutoff`.
+    pub fn delete_before(conn: &mut SqliteConnection, cutoff: NaiveDateTime, limit: i64) -> QueryResult<usize> {
+        let expired = audit_logs::table
+            .filter(audit_logs::created_at.lt(cutoff))
+            .order(audit_logs::created_at.asc())
+            .select(audit_logs::id)
+            .limit(limit)
+            .load::<String>(conn)?;
+        diesel::delete(audit_logs::table.filter(audit_logs::id.eq_any(&expired))).execute(conn)
+    }
+}
+
+fn filtered<'a>(filter: &AuditFilter) -> audit_logs::BoxedQuery<'a, Sqlite> {
+    let mut query = audit_logs::table.into_boxed();
+    if let Some(user_id) = filter.user_id {
+        query = query.filter(audit_logs::user_id.eq(user_id.to_owned()));
+    }
+    if let Some(actor_id) = filter.actor_id {
+        query = query.filter(audit_logs::actor_id.eq(actor_id.to_owned()));
+    }
+    if let Some(event) = filter.event {
+        query = query.filter(audit_logs::event.eq(event.to_owned()));
+    }
+    if let Some(ip_address) = filter.ip_address {
+        query = query.filter(audit_logs::ip_address.eq(ip_address.to_owned()));
+    }
+    if let Some(since) = filter.since {
+        query = query.filter(audit_logs::created_at.ge(since));
+    }
+    if let Some(until) = filter.until {
+        query = query.filter(audit_logs::created_at.lt(until));
+    }
+    query
+}
@@ -0,0 +1,43 @@
+use diesel::prelude::*;
+use crate::db::models::avatar_source::AvatarSources;
+use crate::db::schema::avatar_sources;
+
+impl AvatarSources {
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<AvatarSources>> {
+        avatar_sources::table
+            .find(id)
+            .select(AvatarSources::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// The source for `upstream_url`, registering it on first use.
+    pub fn find_or_create(conn: &mut SqliteConnection, user_id: &str, upstream_url: &str) -> QueryResult<AvatarSources> {
+        let existing = avatar_sources::table
+            .filter(avatar_sources::user_id.eq(user_id))
+            .filter(avatar_sources::upstream_url.eq(upstream_url))
+            .select(AvatarSources::as_select())
+            .first(conn)
+            .optional()?;
+        if let Some(source) = existing {
+            return Ok(source);
+        }
+
+        let id: [u8; 16] = rand::random();
+        let source = AvatarSources {
+            id: hex::encode(id),
+            user_id: user_id.to_string(),
+            upstream_url: upstream_url.to_string(),
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        // Two pages rendering at once may both get here; whichever loses reads the winner's row.
+        diesel::insert_or_ignore_into(avatar_sources::table)
+            .values(&source)
+            .execute(conn)?;
+        avatar_sources::table
+            .filter(avatar_sources::user_id.eq(user_id))
+            .filter(avatar_sources::upstream_url.eq(upstream_url))
+            .select(AvatarSources::as_select())
+            .first(conn)
+    }
+}
@@ -0,0 +1,131 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use crate::db::models::backfill_job::{
+    BackfillJobs, NewBackfillJob, BACKFILL_STATUS_COMPLETED, BACKFILL_STATUS_FAILED, BACKFILL_STATUS_PAUSED,
+    BACKFILL_STATUS_PENDING, BACKFILL_STATUS_RUNNING,
+};
+use crate::db::schema::backfill_jobs;
+
+impl BackfillJobs {
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<BackfillJobs>> {
+        backfill_jobs::table
+            .filter(backfill_jobs::id.eq(id))
+            .select(BackfillJobs::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn list(conn: &mut SqliteConnection) -> QueryResult<Vec<BackfillJobs>> {
+        backfill_jobs::table
+            .order(backfill_jobs::created_at.desc())
+            .select(BackfillJobs::as_select())
+            .load(conn)
+    }
+
+    pub fn create(conn: &mut SqliteConnection, new_job: &NewBackfillJob) -> QueryResult<BackfillJobs> {
+        diesel::insert_into(backfill_jobs::table)
+            .values(new_job)
+            .returning(BackfillJobs::as_select())
+            .get_result(conn)
+    }
+
+    /// An unfinished job of the given kind, if there is one.
+    pub fn active_for_kind(conn: &mut SqliteConnection, kind: &str) -> QueryResult<Option<BackfillJobs>> {
+        backfill_jobs::table
+            .filter(backfill_jobs::kind.eq(kind))
+            .filter(backfill_jobs::status.eq_any([
+                BACKFILL_STATUS_PENDING,
+                BACKFILL_STATUS_RUNNING,
+                BACKFILL_STATUS_PAUSED,
+            ]))
+            .select(BackfillJobs::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// The oldest job the worker should be making progress on. Jobs left running by a previous
+    /// process are picked up again here.
+    pub fn next_runnable(conn: &mut SqliteConnection) -> QueryResult<Option<BackfillJobs>> {
+        backfill_jobs::table
+            .filter(backfill_jobs::status.eq_any([BACKFILL_STATUS_PENDING, BACKFILL_STATUS_RUNNING]))
+            .order(backfill_jobs::created_at.asc())
+            .select(BackfillJobs::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Advances the job past `cursor`. Does nothing if the job was paused in the meantime.
+    pub fn record_progress(conn: &mut SqliteConnection, id: &str, cursor: &str, processed: i32) -> QueryResult<usize> {
+        diesel::update(
+            backfill_jobs::table
+                .filter(backfill_jobs::id.eq(id))
+                .filter(backfill_jobs::status.eq_any([BACKFILL_STATUS_PENDING, BACKFILL_STATUS_RUNNING])),
+        )
+        .set((
+            backfill_jobs::status.eq(BACKFILL_STATUS_RUNNING),
+            backfill_jobs::cursor.eq(cursor),
+            backfill_jobs::processed.eq(backfill_jobs::processed + processed),
+            backfill_jobs::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)
+    }
+
+    pub fn complete(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        let now = Utc::now().naive_utc();
+        diesel::update(backfill_jobs::table.filter(backfill_jobs::id.eq(id)))
+            .set((
+                backfill_jobs::status.eq(BACKFILL_STATUS_COMPLETED),
+                backfill_jobs::updated_at.eq(now),
+                backfill_jobs::finished_at.eq(now),
+            ))
+            .execute(conn)
+    }
+
+    pub fn fail(conn: &mut SqliteConnection, id: &str, error: &str) -> QueryResult<usize> {
+        let now = Utc::now().naive_utc();
+        diesel::update(backfill_jobs::table.filter(backfill_jobs::id.eq(id)))
+            .set((
+                backfill_jobs::status.eq(BACKFILL_STATUS_FAILED),
+                backfill_jobs::error.eq(error),
+                backfill_jobs::updated_at.eq(now),
+                backfill_jobs::finished_at.eq(now),
+            ))
+            .execute(conn)
+    }
+
+    /// Moves the job to `status` if it is currently in one of `from`, returning the updated job.
+    pub fn transition(
+        conn: &mut SqliteConnection,
+        id: &str,
+        from: &[&str],
+        status: &str,
+    ) -> QueryResult<Option<BackfillJobs>> {
+        diesel::update(
+            backfill_jobs::table
+                .filter(backfill_jobs::id.eq(id))
+                .filter(backfill_jobs::status.eq_any(from)),
+        )
+        .set((
+            backfill_jobs::status.eq(status),
+            backfill_jobs::error.eq(None::<String>),
+            backfill_jobs::finished_at.eq(None::<chrono::NaiveDateTime>),
+            backfill_jobs::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .returning(BackfillJobs::as_select())
+        .get_result(conn)
+        .optional()
+    }
+
+    /// Deletes up to `limit` of the oldest completed or failed jobs that finished before
+    /// `cutoff`. Jobs still pending, running or paused are never touched.
+    pub fn delete_finished_before(conn: &mut SqliteConnection, cutoff: NaiveDateTime, limit: i64) -> QueryResult<usize> {
+        let expired = backfill_jobs::table
+            .filter(backfill_jobs::status.eq_any([BACKFILL_STATUS_COMPLETED, BACKFILL_STATUS_FAILED]))
+            .filter(backfill_jobs::finished_at.lt(cutoff))
+            .order(backfill_jobs::finished_at.asc())
+            .select(backfill_jobs::id)
+            .limit(limit)
+            .load::<String>(conn)?;
+        diesel::delete(backfill_jobs::table.filter(backfill_jobs::id.eq_any(&expired))).execute(conn)
+    }
+}
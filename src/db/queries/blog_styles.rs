@@ -0,0 +1,57 @@
+use diesel::dsl::max;
+use diesel::prelude::*;
+use crate::db::models::blog_style::{BlogStyles, NewBlogStyle};
+use crate::db::schema::blog_styles;
+
+impl BlogStyles {
+    /// The live version of a blog's styles, if the owner ever saved any.
+    pub fn current(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Option<BlogStyles>> {
+        blog_styles::table
+            .filter(blog_styles::user_id.eq(user_id))
+            .order(blog_styles::version.desc())
+            .select(BlogStyles::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn by_version(conn: &mut SqliteConnection, user_id: &str, version: i32) -> QueryResult<Option<BlogStyles>> {
+        blog_styles::table
+            .filter(blog_styles::user_id.eq(user_id))
+            .filter(blog_styles::version.eq(version))
+            .select(BlogStyles::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Every saved version, newest first.
+    pub fn history(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<BlogStyles>> {
+        blog_styles::table
+            .filter(blog_styles::user_id.eq(user_id))
+            .order(blog_styles::version.desc())
+            .select(BlogStyles::as_select())
+            .load(conn)
+    }
+
+    /// Saves already-sanitized styles as the next version. Run inside a transaction so two
+    /// concurrent saves can't claim the same version number.
+    pub fn record(conn: &mut SqliteConnection, user_id: &str, css: &str, head_html: &str) -> QueryResult<BlogStyles> {
+        let latest: Option<i32> = blog_styles::table
+            .filter(blog_styles::user_id.eq(user_id))
+            .select(max(blog_styles::version))
+            .first(conn)?;
+
+        let style = NewBlogStyle {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_owned(),
+            version: latest.unwrap_or(0) + 1,
+            css: css.to_owned(),
+            head_html: head_html.to_owned(),
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+
+        diesel::insert_into(blog_styles::table)
+            .values(&style)
+            .returning(BlogStyles::as_select())
+            .get_result(conn)
+    }
+}
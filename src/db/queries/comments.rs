@@ -0,0 +1,160 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use crate::db::models::comment::{Comments, NewComment};
+use crate::db::models::report::{MODERATION_HIDDEN, MODERATION_PENDING, MODERATION_VISIBLE};
+use crate::db::schema::{comments, users};
+
+/// A comment with its author's name and the author's `deleted_at`.
+pub type CommentWithAuthor = (Comments, String, Option<NaiveDateTime>);
+
+impl Comments {
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<Comments>> {
+        comments::table
+            .filter(comments::id.eq(id))
+            .select(Comments::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Every comment the user has written, deleted ones included, oldest first.
+    pub fn by_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<Comments>> {
+        comments::table
+            .filter(comments::user_id.eq(user_id))
+            .order((comments::created_at.asc(), comments::id.asc()))
+            .select(Comments::as_select())
+            .load(conn)
+    }
+
+    pub fn create(conn: &mut SqliteConnection, new_comment: &NewComment) -> QueryResult<Comments> {
+        diesel::insert_into(comments::table)
+            .values(new_comment)
+            .returning(Comments::as_select())
+            .get_result(conn)
+    }
+
+    pub fn count_roots(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<i64> {
+        comments::table
+            .filter(comments::post_id.eq(post_id))
+            .filter(comments::root_id.is_null())
+            .count()
+            .get_result(conn)
+    }
+
+    /// A page of top-level comments on a post, oldest first.
+    pub fn roots_page(
+        conn: &mut SqliteConnection,
+        post_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<CommentWithAuthor>> {
+        comments::table
+            .inner_join(users::table)
+            .filter(comments::post_id.eq(post_id))
+            .filter(comments::root_id.is_null())
+            .order(comments::created_at.asc())
+            .offset(offset)
+            .limit(limit)
+            .select((Comments::as_select(), users::name, users::deleted_at))
+            .load(conn)
+    }
+
+    /// Every reply in the given threads, oldest first.
+    pub fn replies_for_roots(conn: &mut SqliteConnection, root_ids: &[String]) -> QueryResult<Vec<CommentWithAuthor>> {
+        comments::table
+            .inner_join(users::table)
+            .filter(comments::root_id.eq_any(root_ids))
+            .order(comments::created_at.asc())
+            .select((Comments::as_select(), users::name, users::deleted_at))
+            .load(conn)
+    }
+
+    pub fn count_recent_by_user(conn: &mut SqliteConnection, user_id: &str, since: NaiveDateTime) -> QueryResult<i64> {
+        comments::table
+            .filter(comments::user_id.eq(user_id))
+            .filter(comments::created_at.ge(since))
+            .count()
+            .get_result(conn)
+    }
+
+    pub fn update_body(conn: &mut SqliteConnection, id: &str, body: &str) -> QueryResult<Comments> {
+        diesel::update(comments::table.filter(comments::id.eq(id)))
+            .set((comments::body.eq(body), comments::updated_at.eq(Utc::now().naive_utc())))
+            .returning(Comments::as_select())
+            .get_result(conn)
+    }
+
+    /// Blanks the comment but keeps the row so replies stay attached to the thread.
+    pub fn soft_delete(conn: &mut SqliteConnection, id: &str, deleted_by: &str) -> QueryResult<usize> {
+        let now = Utc::now().naive_utc();
+        diesel::update(comments::table.filter(comments::id.eq(id)))
+            .set((
+                comments::body.eq(""),
+                comments::deleted_at.eq(now),
+                comments::deleted_by.eq(deleted_by),
+                comments::updated_at.eq(now),
+            ))
+            .execute(conn)
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Whether anything keeps the comment out of its thread, a moderator or the spam check.
+    pub fn is_hidden(&self) -> bool {
+        self.moderation_status != MODERATION_VISIBLE
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.moderation_status == MODERATION_PENDING
+    }
+
+    /// Whether `viewer_id` sees the comment despite it being hidden: the spam check keeps
+    /// pending and shadowed comments from everyone but their author.
+    pub fn visible_to(&self, viewer_id: Option<&str>) -> bool {
+        !self.is_hidden()
+            || (self.moderation_status != MODERATION_HIDDEN && viewer_id == Some(self.user_id.as_str()))
+    }
+
+    /// Hides the comment, which then shows as removed in its thread, or shows it again.
+    pub fn set_moderation_status(conn: &mut SqliteConnection, id: &str, status: &str) -> QueryResult<usize> {
+        diesel::update(comments::table.filter(comments::id.eq(id)))
+            .set(comments::moderation_status.eq(status))
+            .execute(conn)
+    }
+
+    /// Publishes the comment if the spam check is holding it for review.
+    pub fn release_pending(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        diesel::update(comments::table.filter(comments::id.eq(id)))
+            .filter(comments::moderation_status.eq(MODERATION_PENDING))
+            .set(comments::moderation_status.eq(MODERATION_VISIBLE))
+            .execute(conn)
+    }
+
+    /// How many comments the user left on each day since `since`, as `YYYY-MM-DD` days.
+    pub fn daily_counts_by_user(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        since: NaiveDateTime,
+    ) -> QueryResult<Vec<(String, i64)>> {
+        comments::table
+            .filter(comments::user_id.eq(user_id))
+            .filter(comments::deleted_at.is_null())
+            .filter(comments::created_at.ge(since))
+            .group_by(diesel::dsl::sql::<Text>("date(comments.created_at)"))
+            .select((diesel::dsl::sql::<Text>("date(comments.created_at)"), diesel::dsl::count_star()))
+            .load(conn)
+    }
+
+    /// Comments still standing, by authors who haven't been deleted.
+    pub fn count_visible(conn: &mut SqliteConnection) -> QueryResult<i64> {
+        comments::table
+            .inner_join(users::table)
+            .filter(comments::deleted_at.is_null())
+            .filter(comments::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .count()
+            .get_result(conn)
+    }
+}
@@ -0,0 +1,124 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::email_outbox::{EmailOutbox, EMAIL_OUTBOX_FAILED, EMAIL_OUTBOX_PENDING};
+use crate::db::schema::email_outbox;
+
+impl EmailOutbox {
+    pub fn create(conn: &mut SqliteConnection, message: &EmailOutbox) -> QueryResult<usize> {
+        diesel::insert_into(email_outbox::table)
+            .values(message)
+            .execute(conn)
+    }
+
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<EmailOutbox>> {
+        email_outbox::table
+            .find(id)
+            .select(EmailOutbox::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Pending messages in the `priority` lane whose next attempt is due, oldest first.
+    pub fn due(conn: &mut SqliteConnection, priority: &str, now: NaiveDateTime, limit: i64) -> QueryResult<Vec<EmailOutbox>> {
+        email_outbox::table
+            .filter(email_outbox::status.eq(EMAIL_OUTBOX_PENDING))
+            .filter(email_outbox::priority.eq(priority))
+            .filter(email_outbox::next_attempt_at.le(now))
+            .order(email_outbox::next_attempt_at.asc())
+            .limit(limit)
+            .select(EmailOutbox::as_select())
+            .load(conn)
+    }
+
+    /// Records that the mailer is done with the message, `status` saying how, and drops its
+    /// bodies.
+    pub fn mark_done(conn: &mut SqliteConnection, id: &str, status: &str, attempts: i32, now: NaiveDateTime) -> QueryResult<usize> {
+        diesel::update(email_outbox::table.filter(email_outbox::id.eq(id)))
+            .set((
+                email_outbox::status.eq(status),
+                email_outbox::attempts.eq(attempts),
+                email_outbox::text_body.eq(""),
+                email_outbox::html_body.eq(None::<String>),
+                email_outbox::last_error.eq(None::<String>),
+                email_outbox::sent_at.eq(Some(now)),
+            ))
+            .execute(conn)
+    }
+
+    /// Records a failed attempt. With `retry_at` the message stays pending until then;
+    /// without, it has run out of attempts and is marked failed.
+    pub fn mark_attempt_failed(
+        conn: &mut SqliteConnection,
+        id: &str,
+        attempts: i32,
+        error: &str,
+        retry_at: Option<NaiveDateTime>,
+    ) -> QueryResult<usize> {
+        let target = email_outbox::table.filter(email_outbox::id.eq(id));
+        let attempt = (email_outbox::attempts.eq(attempts), email_outbox::last_error.eq(Some(error)));
+        match retry_at {
+            Some(retry_at) => diesel::update(target)
+                .set((attempt, email_outbox::next_attempt_at.eq(retry_at)))
+                .execute(conn),
+            None => diesel::update(target)
+                .set((attempt, email_outbox::status.eq(EMAIL_OUTBOX_FAILED)))
+                .execute(conn),
+        }
+    }
+
+    /// Puts a failed message back in its lane with a fresh set of attempts, returning whether
+    /// there was one to retry.
+    pub fn retry_failed(conn: &mut SqliteConnection, id: &str, now: NaiveDateTime) -> QueryResult<bool> {
+        diesel::update(
+            email_outbox::table
+                .filter(email_outbox::id.eq(id))
+                .filter(email_outbox::status.eq(EMAIL_OUTBOX_FAILED)),
+        )
+        .set((
+            email_outbox::status.eq(EMAIL_OUTBOX_PENDING),
+            email_outbox::attempts.eq(0),
+            email_outbox::next_attempt_at.eq(now),
+        ))
+        .execute(conn)
+        .map(|updated| updated > 0)
+    }
+
+    /// How many messages there are of each priority and status.
+    pub fn counts(conn: &mut SqliteConnection) -> QueryResult<Vec<(String, String, i64)>> {
+        email_outbox::table
+            .group_by((email_outbox::priority, email_outbox::status))
+            .select((email_outbox::priority, email_outbox::status, diesel::dsl::count_star()))
+            .load(conn)
+    }
+
+    /// When the longest-waiting pending message was queued.
+    pub fn oldest_pending(conn: &mut SqliteConnection) -> QueryResult<Option<NaiveDateTime>> {
+        email_outbox::table
+            .filter(email_outbox::status.eq(EMAIL_OUTBOX_PENDING))
+            .select(diesel::dsl::min(email_outbox::created_at))
+            .first(conn)
+    }
+
+    /// The most recent failed messages, newest first.
+    pub fn recent_failed(conn: &mut SqliteConnection, limit: i64) -> QueryResult<Vec<EmailOutbox>> {
+        email_outbox::table
+            .filter(email_outbox::status.eq(EMAIL_OUTBOX_FAILED))
+            .order(email_outbox::created_at.desc())
+            .limit(limit)
+            .select(EmailOutbox::as_select())
+            .load(conn)
+    }
+
+    /// Deletes up to `limit` of the oldest sent, suppressed or failed messages created before
+    /// `cutoff`. Pending ones are kept however old they are.
+    pub fn delete_finished_before(conn: &mut SqliteConnection, cutoff: NaiveDateTime, limit: i64) -> QueryResult<usize> {
+        let expired = email_outbox::table
+            .filter(email_outbox::status.ne(EMAIL_OUTBOX_PENDING))
+            .filter(email_outbox::created_at.lt(cutoff))
+            .order(email_outbox::created_at.asc())
+            .select(email_outbox::id)
+            .limit(limit)
+            .load::<String>(conn)?;
+        diesel::delete(email_outbox::table.filter(email_outbox::id.eq_any(&expired))).execute(conn)
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use crate::db::models::email_suppression::{EmailSuppressions, NewEmailSuppression};
+use crate::db::schema::email_suppressions;
+
+impl EmailSuppressions {
+    pub fn list(conn: &mut SqliteConnection, include_reactivated: bool) -> QueryResult<Vec<EmailSuppressions>> {
+        let mut query = email_suppressions::table
+            .select(EmailSuppressions::as_select())
+            .order(email_suppressions::updated_at.desc())
+            .into_boxed();
+
+        if !include_reactivated {
+            query = query.filter(email_suppressions::reactivated_at.is_null());
+        }
+
+        query.load(conn)
+    }
+
+    pub fn is_suppressed(conn: &mut SqliteConnection, email: &str) -> QueryResult<bool> {
+        use diesel::dsl::{exists, select};
+        select(exists(
+            email_suppressions::table
+                .filter(email_suppressions::email.eq(email.to_lowercase()))
+                .filter(email_suppressions::reactivated_at.is_null())
+        )).get_result(conn)
+    }
+
+    /// Records a suppression, re-activating the suppression if the address had been cleared before.
+    pub fn suppress(
+        conn: &mut SqliteConnection,
+        email: &str,
+        reason: &str,
+        provider: &str,
+        details: Option<String>,
+    ) -> QueryResult<EmailSuppressions> {
+        let now = Utc::now().naive_utc();
+
+        let new_suppression = NewEmailSuppression {
+            id: uuid::Uuid::new_v4().to_string(),
+            email: email.to_lowercase(),
+            reason: reason.to_owned(),
+            provider: provider.to_owned(),
+            details: details.clone(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        diesel::insert_into(email_suppressions::table)
+            .values(&new_suppression)
+            .on_conflict(email_suppressions::email)
+            .do_update()
+            .set((
+                email_suppressions::reason.eq(reason),
+                email_suppressions::provider.eq(provider),
+                email_suppressions::details.eq(details),
+                email_suppressions::updated_at.eq(now),
+                email_suppressions::reactivated_at.eq(None::<chrono::NaiveDateTime>),
+            ))
+            .returning(EmailSuppressions::as_select())
+            .get_result(conn)
+    }
+
+    pub fn reactivate(conn: &mut SqliteConnection, email: &str) -> QueryResult<usize> {
+        let now = Utc::now().naive_utc();
+        diesel::update(
+            email_suppressions::table
+                .filter(email_suppressions::email.eq(email.to_lowercase()))
+                .filter(email_suppressions::reactivated_at.is_null()),
+        )
+            .set((
+                email_suppressions::reactivated_at.eq(now),
+                email_suppressions::updated_at.eq(now),
+            ))
+            .execute(conn)
+    }
+}
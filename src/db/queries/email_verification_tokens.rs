@@ -0,0 +1,44 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use crate::db::models::email_verification_token::{EmailVerificationTokens, NewEmailVerificationToken};
+use crate::db::schema::email_verification_tokens;
+
+impl EmailVerificationTokens {
+    pub fn by_token(conn: &mut SqliteConnection, token: &str) -> QueryResult<Option<EmailVerificationTokens>> {
+        email_verification_tokens::table
+            .filter(email_verification_tokens::token.eq(token))
+            .select(EmailVerificationTokens::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn create(conn: &mut SqliteConnection, token: &str, user_id: &str, hours: i64) -> QueryResult<usize> {
+        let now = Utc::now();
+
+        let new_token = NewEmailVerificationToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            token: token.to_owned(),
+            expires_at: (now + chrono::Duration::hours(hours)).naive_utc(),
+            user_id: user_id.to_owned(),
+            created_at: now.naive_utc(),
+        };
+
+        diesel::insert_into(email_verification_tokens::table)
+            .values(&new_token)
+            .execute(conn)
+    }
+
+    pub fn delete_by_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<usize> {
+        diesel::delete(email_verification_tokens::table.filter(email_verification_tokens::user_id.eq(user_id)))
+            .execute(conn)
+    }
+
+    pub fn delete_expired(conn: &mut SqliteConnection, now: NaiveDateTime) -> QueryResult<usize> {
+        diesel::delete(email_verification_tokens::table.filter(email_verification_tokens::expires_at.lt(now)))
+            .execute(conn)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now().naive_utc()
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use crate::db::models::export_job::{
+    ExportJobs, EXPORT_STATUS_EXPIRED, EXPORT_STATUS_FAILED, EXPORT_STATUS_PENDING, EXPORT_STATUS_READY,
+    EXPORT_STATUS_RUNNING,
+};
+use crate::db::schema::export_jobs;
+
+impl ExportJobs {
+    pub fn create(conn: &mut SqliteConnection, job: &ExportJobs) -> QueryResult<usize> {
+        diesel::insert_into(export_jobs::table)
+            .values(job)
+            .execute(conn)
+    }
+
+    pub fn by_id_for_user(conn: &mut SqliteConnection, id: &str, user_id: &str) -> QueryResult<Option<ExportJobs>> {
+        export_jobs::table
+            .filter(export_jobs::id.eq(id))
+            .filter(export_jobs::user_id.eq(user_id))
+            .select(ExportJobs::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// The user's export that is still being assembled, if there is one.
+    pub fn active_for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Option<ExportJobs>> {
+        export_jobs::table
+            .filter(export_jobs::user_id.eq(user_id))
+            .filter(export_jobs::status.eq_any([EXPORT_STATUS_PENDING, EXPORT_STATUS_RUNNING]))
+            .select(ExportJobs::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// The oldest export waiting to be assembled, marked running. Exports left running by a
+    /// previous process are picked up again here.
+    pub fn claim_next(conn: &mut SqliteConnection) -> QueryResult<Option<ExportJobs>> {
+        let job = export_jobs::table
+            .filter(export_jobs::status.eq_any([EXPORT_STATUS_PENDING, EXPORT_STATUS_RUNNING]))
+            .order(export_jobs::created_at.asc())
+            .select(ExportJobs::as_select())
+            .first(conn)
+            .optional()?;
+        let Some(job) = job else {
+            return Ok(None);
+        };
+
+        diesel::update(export_jobs::table.filter(export_jobs::id.eq(&job.id)))
+            .set((
+                export_jobs::status.eq(EXPORT_STATUS_RUNNING),
+                export_jobs::started_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(ExportJobs::as_select())
+            .get_result(conn)
+            .optional()
+    }
+
+    pub fn mark_ready(
+        conn: &mut SqliteConnection,
+        id: &str,
+        storage_key: &str,
+        size_bytes: i64,
+        expires_at: NaiveDateTime,
+    ) -> QueryResult<usize> {
+        diesel::update(export_jobs::table.filter(export_jobs::id.eq(id)))
+            .set((
+                export_jobs::status.eq(EXPORT_STATUS_READY),
+                export_jobs::storage_key.eq(storage_key),
+                export_jobs::size_bytes.eq(size_bytes),
+                export_jobs::finished_at.eq(Utc::now().naive_utc()),
+                export_jobs::expires_at.eq(expires_at),
+            ))
+            .execute(conn)
+    }
+
+    pub fn mark_failed(conn: &mut SqliteConnection, id: &str, error: &str) -> QueryResult<usize> {
+        diesel::update(export_jobs::table.filter(export_jobs::id.eq(id)))
+            .set((
+                export_jobs::status.eq(EXPORT_STATUS_FAILED),
+                export_jobs::error.eq(error),
+                export_jobs::finished_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+    }
+
+    /// Ready exports whose archive should be deleted by `now`.
+    pub fn expired(conn: &mut SqliteConnection, now: NaiveDateTime, limit: i64) -> QueryResult<Vec<ExportJobs>> {
+        export_jobs::table
+            .filter(export_jobs::status.eq(EXPORT_STATUS_READY))
+            .filter(export_jobs::expires_at.le(now))
+            .order(export_jobs::expires_at.asc())
+            .limit(limit)
+            .select(ExportJobs::as_select())
+            .load(conn)
+    }
+
+    pub fn mark_expired(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        diesel::update(export_jobs::table.filter(export_jobs::id.eq(id)))
+            .set((
+                export_jobs::status.eq(EXPORT_STATUS_EXPIRED),
+                export_jobs::storage_key.eq(None::<String>),
+            ))
+            .execute(conn)
+    }
+
+    /// Deletes up to `limit` failed or expired exports created before `cutoff`. Ready exports
+    /// are left for the worker, which deletes their archive first.
+    pub fn delete_finished_before(conn: &mut SqliteConnection, cutoff: NaiveDateTime, limit: i64) -> QueryResult<usize> {
+        let finished = export_jobs::table
+            .filter(export_jobs::status.eq_any([EXPORT_STATUS_FAILED, EXPORT_STATUS_EXPIRED]))
+            .filter(export_jobs::created_at.lt(cutoff))
+            .order(export_jobs::created_at.asc())
+            .select(export_jobs::id)
+            .limit(limit)
+            .load::<String>(conn)?;
+        diesel::delete(export_jobs::table.filter(export_jobs::id.eq_any(&finished))).execute(conn)
+    }
+}
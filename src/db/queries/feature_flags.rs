@@ -0,0 +1,53 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::feature_flag::FeatureFlags;
+use crate::db::schema::feature_flags;
+
+impl FeatureFlags {
+    pub fn all(conn: &mut SqliteConnection) -> QueryResult<Vec<FeatureFlags>> {
+        feature_flags::table
+            .order(feature_flags::name.asc())
+            .select(FeatureFlags::as_select())
+            .load(conn)
+    }
+
+    pub fn by_name(conn: &mut SqliteConnection, name: &str) -> QueryResult<Option<FeatureFlags>> {
+        feature_flags::table
+            .find(name)
+            .select(FeatureFlags::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn set(
+        conn: &mut SqliteConnection,
+        name: &str,
+        enabled: bool,
+        updated_by: &str,
+        now: NaiveDateTime,
+    ) -> QueryResult<FeatureFlags> {
+        diesel::insert_into(feature_flags::table)
+            .values(&FeatureFlags {
+                name: name.to_string(),
+                enabled,
+                updated_by: Some(updated_by.to_string()),
+                updated_at: now,
+            })
+            .on_conflict(feature_flags::name)
+            .do_update()
+            .set((
+                feature_flags::enabled.eq(enabled),
+                feature_flags::updated_by.eq(updated_by),
+                feature_flags::updated_at.eq(now),
+            ))
+            .returning(FeatureFlags::as_returning())
+            .get_result(conn)
+    }
+
+    /// Drops the override, returning whether there was one.
+    pub fn clear(conn: &mut SqliteConnection, name: &str) -> QueryResult<bool> {
+        diesel::delete(feature_flags::table.find(name))
+            .execute(conn)
+            .map(|deleted| deleted > 0)
+    }
+}
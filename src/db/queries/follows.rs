@@ -0,0 +1,109 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::follow::Follows;
+use crate::db::models::user_model::UserModel;
+use crate::db::schema::{follows, users};
+use crate::http::pagination::SortDir;
+
+impl Follows {
+    /// Records the follow, returning whether it's new.
+    pub fn follow(conn: &mut SqliteConnection, follow: &Follows) -> QueryResult<bool> {
+        diesel::insert_or_ignore_into(follows::table)
+            .values(follow)
+            .execute(conn)
+            .map(|inserted| inserted > 0)
+    }
+
+    /// Removes the follow, returning whether there was one.
+    pub fn unfollow(conn: &mut SqliteConnection, follower_id: &str, followee_id: &str) -> QueryResult<bool> {
+        diesel::delete(
+            follows::table
+                .filter(follows::follower_id.eq(follower_id))
+                .filter(follows::followee_id.eq(followee_id)),
+        )
+        .execute(conn)
+        .map(|deleted| deleted > 0)
+    }
+
+    pub fn count_followers(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<i64> {
+        follows::table
+            .inner_join(users::table.on(users::id.eq(follows::follower_id)))
+            .filter(follows::followee_id.eq(user_id))
+            .filter(users::deleted_at.is_null())
+            .count()
+            .get_result(conn)
+    }
+
+    /// Active users following `user_id`, ordered by when they followed.
+    pub fn followers_page(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<(UserModel, NaiveDateTime)>> {
+        let query = follows::table
+            .inner_join(users::table.on(users::id.eq(follows::follower_id)))
+            .filter(follows::followee_id.eq(user_id))
+            .filter(users::deleted_at.is_null())
+            .into_boxed();
+        let query = if dir.is_asc() {
+            query.order(follows::created_at.asc())
+        } else {
+            query.order(follows::created_at.desc())
+        };
+
+        query
+            .then_order_by(users::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select((UserModel::as_select(), follows::created_at))
+            .load(conn)
+    }
+
+    /// Ids of the active users following `user_id`.
+    pub fn follower_ids(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<String>> {
+        follows::table
+            .inner_join(users::table.on(users::id.eq(follows::follower_id)))
+            .filter(follows::followee_id.eq(user_id))
+            .filter(users::deleted_at.is_null())
+            .select(follows::follower_id)
+            .load(conn)
+    }
+
+    pub fn count_following(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<i64> {
+        follows::table
+            .inner_join(users::table.on(users::id.eq(follows::followee_id)))
+            .filter(follows::follower_id.eq(user_id))
+            .filter(users::deleted_at.is_null())
+            .count()
+            .get_result(conn)
+    }
+
+    /// Active users `user_id` follows, ordered by when they were followed.
+    pub fn following_page(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<(UserModel, NaiveDateTime)>> {
+        let query = follows::table
+            .inner_join(users::table.on(users::id.eq(follows::followee_id)))
+            .filter(follows::follower_id.eq(user_id))
+            .filter(users::deleted_at.is_null())
+            .into_boxed();
+        let query = if dir.is_asc() {
+            query.order(follows::created_at.asc())
+        } else {
+            query.order(follows::created_at.desc())
+        };
+
+        query
+            .then_order_by(users::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select((UserModel::as_select(), follows::created_at))
+            .load(conn)
+    }
+}
@@ -0,0 +1,59 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::idempotency_key::IdempotencyKeys;
+use crate::db::schema::idempotency_keys;
+
+impl IdempotencyKeys {
+    pub fn find(conn: &mut SqliteConnection, scope: &str, key: &str) -> QueryResult<Option<IdempotencyKeys>> {
+        idempotency_keys::table
+            .filter(idempotency_keys::scope.eq(scope))
+            .filter(idempotency_keys::idempotency_key.eq(key))
+            .select(IdempotencyKeys::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Takes the key for a request about to be handled. False when another request already
+    /// holds it.
+    pub fn claim(conn: &mut SqliteConnection, row: &IdempotencyKeys) -> QueryResult<bool> {
+        diesel::insert_into(idempotency_keys::table)
+            .values(row)
+            .on_conflict((idempotency_keys::scope, idempotency_keys::idempotency_key))
+            .do_nothing()
+            .execute(conn)
+            .map(|inserted| inserted > 0)
+    }
+
+    /// Stores the response to replay to retries.
+    pub fn complete(
+        conn: &mut SqliteConnection,
+        id: &str,
+        status_code: i32,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> QueryResult<usize> {
+        diesel::update(idempotency_keys::table.filter(idempotency_keys::id.eq(id)))
+            .set((
+                idempotency_keys::status_code.eq(status_code),
+                idempotency_keys::content_type.eq(content_type),
+                idempotency_keys::body.eq(body),
+            ))
+            .execute(conn)
+    }
+
+    /// Frees the key so the request can be tried again.
+    pub fn release(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        diesel::delete(idempotency_keys::table.filter(idempotency_keys::id.eq(id))).execute(conn)
+    }
+
+    /// Deletes up to `limit` keys that expired before `cutoff`.
+    pub fn delete_expired_before(conn: &mut SqliteConnection, cutoff: NaiveDateTime, limit: i64) -> QueryResult<usize> {
+        let expired = idempotency_keys::table
+            .filter(idempotency_keys::expires_at.lt(cutoff))
+            .order(idempotency_keys::expires_at.asc())
+            .select(idempotency_keys::id)
+            .limit(limit)
+            .load::<String>(conn)?;
+        diesel::delete(idempotency_keys::table.filter(idempotency_keys::id.eq_any(&expired))).execute(conn)
+    }
+}
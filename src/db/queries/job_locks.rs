@@ -0,0 +1,54 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::job_lock::JobLocks;
+use crate::db::schema::job_locks;
+
+impl JobLocks {
+    /// Locks that haven't expired.
+    pub fn held(conn: &mut SqliteConnection, now: NaiveDateTime) -> QueryResult<Vec<JobLocks>> {
+        job_locks::table
+            .filter(job_locks::expires_at.gt(now))
+            .order(job_locks::name.asc())
+            .select(JobLocks::as_select())
+            .load(conn)
+    }
+
+    /// Takes the lock for `holder` until `expires_at`, or extends it if `holder` has it
+    /// already. Returns false if another holder's lease hasn't run out. Run it in a
+    /// transaction, so two servers can't both see the lock free.
+    pub fn acquire(
+        conn: &mut SqliteConnection,
+        name: &str,
+        holder: &str,
+        now: NaiveDateTime,
+        expires_at: NaiveDateTime,
+    ) -> QueryResult<bool> {
+        let inserted = diesel::insert_into(job_locks::table)
+            .values(&JobLocks { name: name.to_string(), holder: holder.to_string(), acquired_at: now, expires_at })
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        if inserted == 1 {
+            return Ok(true);
+        }
+
+        let extended = diesel::update(job_locks::table.filter(job_locks::name.eq(name)).filter(job_locks::holder.eq(holder)))
+            .set(job_locks::expires_at.eq(expires_at))
+            .execute(conn)?;
+        if extended == 1 {
+            return Ok(true);
+        }
+
+        let taken = diesel::update(job_locks::table.filter(job_locks::name.eq(name)).filter(job_locks::expires_at.le(now)))
+            .set((job_locks::holder.eq(holder), job_locks::acquired_at.eq(now), job_locks::expires_at.eq(expires_at)))
+            .execute(conn)?;
+        Ok(taken == 1)
+    }
+
+    /// Gives up `holder`'s lock. Returns false if it didn't hold it, having lost it to
+    /// another server after its lease ran out.
+    pub fn release(conn: &mut SqliteConnection, name: &str, holder: &str) -> QueryResult<bool> {
+        let released = diesel::delete(job_locks::table.filter(job_locks::name.eq(name)).filter(job_locks::holder.eq(holder)))
+            .execute(conn)?;
+        Ok(released == 1)
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::job::Jobs;
+use crate::db::schema::jobs;
+
+impl Jobs {
+    pub fn all(conn: &mut SqliteConnection) -> QueryResult<Vec<Jobs>> {
+        jobs::table
+            .order(jobs::name.asc())
+            .select(Jobs::as_select())
+            .load(conn)
+    }
+
+    pub fn by_name(conn: &mut SqliteConnection, name: &str) -> QueryResult<Option<Jobs>> {
+        jobs::table
+            .find(name)
+            .select(Jobs::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Adds the job's row if it has none, first running at `first_run_at`. A job that has one
+    /// is brought forward to `latest` if it was due later, so shortening its schedule takes
+    /// effect without waiting out the old one; with no `latest`, runs still to come are dropped.
+    pub fn register(
+        conn: &mut SqliteConnection,
+        name: &str,
+        first_run_at: Option<NaiveDateTime>,
+        latest: Option<NaiveDateTime>,
+        now: NaiveDateTime,
+    ) -> QueryResult<()> {
+        diesel::insert_into(jobs::table)
+            .values(&Jobs {
+                name: name.to_string(),
+                next_run_at: first_run_at,
+                last_started_at: None,
+                last_finished_at: None,
+                last_error: None,
+            })
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        let target = jobs::table.filter(jobs::name.eq(name));
+        match latest {
+            Some(latest) => diesel::update(target.filter(jobs::next_run_at.is_null().or(jobs::next_run_at.gt(latest))))
+                .set(jobs::next_run_at.eq(latest))
+                .execute(conn)?,
+            None => diesel::update(target.filter(jobs::next_run_at.gt(now)))
+                .set(jobs::next_run_at.eq(None::<NaiveDateTime>))
+                .execute(conn)?,
+        };
+        Ok(())
+    }
+
+    /// Records the start of a run if the job is due. Returns whether it was. Run it holding the
+    /// job's lock.
+    pub fn start(conn: &mut SqliteConnection, name: &str, now: NaiveDateTime) -> QueryResult<bool> {
+        let started = diesel::update(jobs::table.filter(jobs::name.eq(name)).filter(jobs::next_run_at.le(now)))
+            .set(jobs::last_started_at.eq(now))
+            .execute(conn)?;
+        Ok(started == 1)
+    }
+
+    /// Records how a run went and when the job is next due.
+    pub fn finish(
+        conn: &mut SqliteConnection,
+        name: &str,
+        now: NaiveDateTime,
+        next_run_at: Option<NaiveDateTime>,
+        error: Option<&str>,
+    ) -> QueryResult<usize> {
+        diesel::update(jobs::table.filter(jobs::name.eq(name)))
+            .set((
+                jobs::last_finished_at.eq(now),
+                jobs::last_error.eq(error),
+                jobs::next_run_at.eq(next_run_at),
+            ))
+            .execute(conn)
+    }
+
+    /// Makes the job due now.
+    pub fn request_run(conn: &mut SqliteConnection, name: &str, now: NaiveDateTime) -> QueryResult<usize> {
+        diesel::insert_into(jobs::table)
+            .values(&Jobs {
+                name: name.to_string(),
+                next_run_at: Some(now),
+                last_started_at: None,
+                last_finished_at: None,
+                last_error: None,
+            })
+            .on_conflict(jobs::name)
+            .do_update()
+            .set(jobs::next_run_at.eq(now))
+            .execute(conn)
+    }
+}
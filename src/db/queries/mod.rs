@@ -1,2 +1,41 @@
 pub mod users;
-pub mod refresh_tokens;
\ No newline at end of file
+pub mod refresh_tokens;
+pub mod api_tokens;
+pub mod email_verification_tokens;
+pub mod email_suppressions;
+pub mod posts;
+pub mod post_versions;
+pub mod post_reactions;
+pub mod tags;
+pub mod comments;
+pub mod backfill_jobs;
+pub mod uploads;
+pub mod blog_styles;
+pub mod post_fingerprints;
+pub mod post_locks;
+pub mod post_docs;
+pub mod notification_deliveries;
+pub mod follows;
+pub mod push_subscriptions;
+pub mod notifications;
+pub mod user_preferences;
+pub mod audit_logs;
+pub mod webhooks;
+pub mod webhook_deliveries;
+pub mod avatar_sources;
+pub mod export_jobs;
+pub mod pages;
+pub mod page_versions;
+pub mod onboarding_steps;
+pub mod feature_flags;
+pub mod reset_tokens;
+pub mod user_devices;
+pub mod post_slugs;
+pub mod post_collaborators;
+pub mod series;
+pub mod post_views;
+pub mod idempotency_keys;
+pub mod reports;
+pub mod email_outbox;
+pub mod jobs;
+pub mod job_locks;
\ No newline at end of file
@@ -0,0 +1,59 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::notification_delivery::NotificationDeliveries;
+use crate::db::schema::notification_deliveries;
+
+impl NotificationDeliveries {
+    /// The delivery still waiting under `coalesce_key`, if any.
+    pub fn by_key(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        channel: &str,
+        coalesce_key: &str,
+    ) -> QueryResult<Option<NotificationDeliveries>> {
+        notification_deliveries::table
+            .filter(notification_deliveries::user_id.eq(user_id))
+            .filter(notification_deliveries::channel.eq(channel))
+            .filter(notification_deliveries::coalesce_key.eq(coalesce_key))
+            .select(NotificationDeliveries::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn create(conn: &mut SqliteConnection, delivery: &NotificationDeliveries) -> QueryResult<usize> {
+        diesel::insert_into(notification_deliveries::table)
+            .values(delivery)
+            .execute(conn)
+    }
+
+    /// Records another event folded into a pending delivery.
+    pub fn fold(conn: &mut SqliteConnection, id: &str, actors: &str, event_count: i32) -> QueryResult<usize> {
+        diesel::update(notification_deliveries::table.filter(notification_deliveries::id.eq(id)))
+            .set((
+                notification_deliveries::actors.eq(actors),
+                notification_deliveries::event_count.eq(event_count),
+            ))
+            .execute(conn)
+    }
+
+    /// Deliveries whose batching window has closed, oldest first.
+    pub fn due(conn: &mut SqliteConnection, now: NaiveDateTime, limit: i64) -> QueryResult<Vec<NotificationDeliveries>> {
+        notification_deliveries::table
+            .filter(notification_deliveries::deliver_after.le(now))
+            .order(notification_deliveries::deliver_after.asc())
+            .limit(limit)
+            .select(NotificationDeliveries::as_select())
+            .load(conn)
+    }
+
+    pub fn postpone(conn: &mut SqliteConnection, id: &str, until: NaiveDateTime) -> QueryResult<usize> {
+        diesel::update(notification_deliveries::table.filter(notification_deliveries::id.eq(id)))
+            .set(notification_deliveries::deliver_after.eq(until))
+            .execute(conn)
+    }
+
+    pub fn remove(conn: &mut SqliteConnection, ids: &[String]) -> QueryResult<usize> {
+        diesel::delete(notification_deliveries::table.filter(notification_deliveries::id.eq_any(ids)))
+            .execute(conn)
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use crate::db::models::notification::{NotificationEntry, Notifications};
+use crate::db::schema::{notifications, posts, users};
+use crate::http::pagination::SortDir;
+
+impl Notifications {
+    pub fn create_many(conn: &mut SqliteConnection, notifications: &[Notifications]) -> QueryResult<usize> {
+        diesel::insert_into(notifications::table)
+            .values(notifications)
+            .execute(conn)
+    }
+
+    /// Whether anyone has been told about `post_id` with a notification of `kind`.
+    pub fn exists_for_post(conn: &mut SqliteConnection, kind: &str, post_id: &str) -> QueryResult<bool> {
+        diesel::select(diesel::dsl::exists(
+            notifications::table
+                .filter(notifications::kind.eq(kind))
+                .filter(notifications::post_id.eq(post_id)),
+        ))
+        .get_result(conn)
+    }
+
+    pub fn count_for_user(conn: &mut SqliteConnection, user_id: &str, unread_only: bool) -> QueryResult<i64> {
+        for_user(user_id, unread_only).count().get_result(conn)
+    }
+
+    /// A page of the user's notifications by creation time, each with the name of whoever
+    /// caused it and the title of the post it's about.
+    pub fn page_for_user(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        unread_only: bool,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<NotificationEntry>> {
+        let query = for_user(user_id, unread_only);
+        let query = if dir.is_asc() {
+            query.order(notifications::created_at.asc())
+        } else {
+            query.order(notifications::created_at.desc())
+        };
+
+        let page: Vec<Notifications> = query
+            .then_order_by(notifications::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select(Notifications::as_select())
+            .load(conn)?;
+
+        let actor_ids: Vec<&str> = page.iter().filter_map(|n| n.actor_id.as_deref()).collect();
+        let actors: HashMap<String, String> = users::table
+            .filter(users::id.eq_any(&actor_ids))
+            .select((users::id, users::name))
+            .load(conn)?
+            .into_iter()
+            .collect();
+        let post_ids: Vec<&str> = page.iter().filter_map(|n| n.post_id.as_deref()).collect();
+        let titles: std::collections::HashMap<String, String> = posts::table
+            .filter(posts::id.eq_any(&post_ids))
+            .select((posts::id, posts::title))
+            .load(conn)?
+            .into_iter()
+            .collect();
+
+        Ok(page
+            .into_iter()
+            .map(|notification| NotificationEntry {
+                actor_name: notification.actor_id.as_ref().and_then(|id| actors.get(id).cloned()),
+                post_title: notification.post_id.as_ref().and_then(|id| titles.get(id).cloned()),
+                notification,
+            })
+            .collect())
+    }
+
+    pub fn by_id(conn: &mut SqliteConnection, user_id: &str, id: &str) -> QueryResult<Option<Notifications>> {
+        notifications::table
+            .filter(notifications::id.eq(id))
+            .filter(notifications::user_id.eq(user_id))
+            .select(Notifications::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Marks the notification read, returning whether it was unread until now, or `None`
+    /// when the user has no such notification.
+    pub fn mark_read(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        id: &str,
+        now: NaiveDateTime,
+    ) -> QueryResult<Option<bool>> {
+        let marked = diesel::update(
+            notifications::table
+                .filter(notifications::id.eq(id))
+                .filter(notifications::user_id.eq(user_id))
+                .filter(notifications::read_at.is_null()),
+        )
+        .set(notifications::read_at.eq(now))
+        .execute(conn)?;
+        if marked > 0 {
+            return Ok(Some(true));
+        }
+
+        Ok(Self::by_id(conn, user_id, id)?.map(|_| false))
+    }
+
+    /// Marks every unread notification of the user read, returning how many there were.
+    pub fn mark_all_read(conn: &mut SqliteConnection, user_id: &str, now: NaiveDateTime) -> QueryResult<usize> {
+        diesel::update(
+            notifications::table
+                .filter(notifications::user_id.eq(user_id))
+                .filter(notifications::read_at.is_null()),
+        )
+        .set(notifications::read_at.eq(now))
+        .execute(conn)
+    }
+
+    /// Deletes up to `limit` of the oldest notifications, read or not, created before `cutoff`.
+    pub fn delete_before(conn: &mut SqliteConnection, cutoff: NaiveDateTime, limit: i64) -> QueryResult<usize> {
+        let expired = notifications::table
+            .filter(notifications::created_at.lt(cutoff))
+            .order(notifications::created_at.asc())
+            .select(notifications::id)
+            .limit(limit)
+            .load::<String>(conn)?;
+        diesel::delete(notifications::table.filter(notifications::id.eq_any(&expired))).execute(conn)
+    }
+}
+
+fn for_user<'a>(user_id: &str, unread_only: bool) -> notifications::BoxedQuery<'a, Sqlite> {
+    let mut query = notifications::table
+        .filter(notifications::user_id.eq(user_id.to_owned()))
+        .into_boxed();
+    if unread_only {
+        query = query.filter(notifications::read_at.is_null());
+    }
+    query
+}
@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::onboarding_step::OnboardingSteps;
+use crate::db::schema::onboarding_steps;
+
+impl OnboardingSteps {
+    pub fn for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<OnboardingSteps>> {
+        onboarding_steps::table
+            .filter(onboarding_steps::user_id.eq(user_id))
+            .select(OnboardingSteps::as_select())
+            .load(conn)
+    }
+
+    /// Records the step as done, returning whether it wasn't already.
+    pub fn complete(conn: &mut SqliteConnection, user_id: &str, step: &str, now: NaiveDateTime) -> QueryResult<bool> {
+        diesel::insert_or_ignore_into(onboarding_steps::table)
+            .values(&OnboardingSteps {
+                user_id: user_id.to_string(),
+                step: step.to_string(),
+                completed_at: now,
+            })
+            .execute(conn)
+            .map(|inserted| inserted > 0)
+    }
+}
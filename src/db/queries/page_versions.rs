@@ -0,0 +1,45 @@
+use diesel::prelude::*;
+use crate::db::models::page::Pages;
+use crate::db::models::page_version::PageVersions;
+use crate::db::queries::post_versions::commit_hash;
+use crate::db::schema::page_versions;
+
+impl PageVersions {
+    pub fn by_page(conn: &mut SqliteConnection, page_id: &str) -> QueryResult<Vec<PageVersions>> {
+        page_versions::table
+            .filter(page_versions::page_id.eq(page_id))
+            .order(page_versions::created_at.desc())
+            .select(PageVersions::as_select())
+            .load(conn)
+    }
+
+    pub fn by_id_for_page(conn: &mut SqliteConnection, id: &str, page_id: &str) -> QueryResult<Option<PageVersions>> {
+        page_versions::table
+            .filter(page_versions::id.eq(id))
+            .filter(page_versions::page_id.eq(page_id))
+            .select(PageVersions::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Snapshots the page's current title, description and content as a new version.
+    pub fn record(conn: &mut SqliteConnection, page: &Pages, author_id: &str, message: &str) -> QueryResult<PageVersions> {
+        let now = chrono::Utc::now().naive_utc();
+        let version = PageVersions {
+            id: uuid::Uuid::new_v4().to_string(),
+            page_id: page.id.clone(),
+            user_id: Some(author_id.to_owned()),
+            title: page.title.clone(),
+            content: page.content.clone(),
+            description: page.description.clone(),
+            commit_hash: commit_hash(&page.id, &page.title, &page.description, &page.content, now),
+            commit_message: message.to_owned(),
+            created_at: now,
+        };
+
+        diesel::insert_into(page_versions::table)
+            .values(&version)
+            .returning(PageVersions::as_select())
+            .get_result(conn)
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::page::{PageChanges, Pages};
+use crate::db::schema::pages;
+
+impl Pages {
+    pub fn list(conn: &mut SqliteConnection) -> QueryResult<Vec<Pages>> {
+        pages::table
+            .order(pages::slug.asc())
+            .select(Pages::as_select())
+            .load(conn)
+    }
+
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<Pages>> {
+        pages::table
+            .find(id)
+            .select(Pages::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn published_by_slug(conn: &mut SqliteConnection, slug: &str) -> QueryResult<Option<Pages>> {
+        pages::table
+            .filter(pages::slug.eq(slug))
+            .filter(pages::published_at.is_not_null())
+            .select(Pages::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Slugs and last updates of published pages, for the sitemap.
+    pub fn sitemap_entries(conn: &mut SqliteConnection) -> QueryResult<Vec<(String, NaiveDateTime)>> {
+        pages::table
+            .filter(pages::published_at.is_not_null())
+            .order(pages::slug.asc())
+            .select((pages::slug, pages::updated_at))
+            .load(conn)
+    }
+
+    /// Like `Posts::sitemap_version`, for published pages.
+    pub fn sitemap_version(conn: &mut SqliteConnection) -> QueryResult<(i64, Option<NaiveDateTime>)> {
+        pages::table
+            .filter(pages::published_at.is_not_null())
+            .select((diesel::dsl::count_star(), diesel::dsl::max(pages::updated_at)))
+            .first(conn)
+    }
+
+    pub fn create(conn: &mut SqliteConnection, page: &Pages) -> QueryResult<Pages> {
+        diesel::insert_into(pages::table)
+            .values(page)
+            .returning(Pages::as_select())
+            .get_result(conn)
+    }
+
+    pub fn update(conn: &mut SqliteConnection, id: &str, changes: &PageChanges) -> QueryResult<Pages> {
+        diesel::update(pages::table.find(id))
+            .set(changes)
+            .returning(Pages::as_select())
+            .get_result(conn)
+    }
+
+    pub fn delete(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        diesel::delete(pages::table.find(id)).execute(conn)
+    }
+
+    pub fn is_published(&self) -> bool {
+        self.published_at.is_some()
+    }
+}
@@ -0,0 +1,46 @@
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use crate::db::models::post_collaborator::PostCollaborators;
+use crate::db::schema::{post_collaborators, users};
+
+impl PostCollaborators {
+    /// `user_id`'s role on the post, if they were invited to it.
+    pub fn role_of(conn: &mut SqliteConnection, post_id: &str, user_id: &str) -> QueryResult<Option<String>> {
+        post_collaborators::table
+            .filter(post_collaborators::post_id.eq(post_id))
+            .filter(post_collaborators::user_id.eq(user_id))
+            .select(post_collaborators::role)
+            .first(conn)
+            .optional()
+    }
+
+    /// The post's collaborators with their names, in the order they were invited.
+    pub fn by_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Vec<(PostCollaborators, String)>> {
+        post_collaborators::table
+            .inner_join(users::table.on(users::id.eq(post_collaborators::user_id)))
+            .filter(post_collaborators::post_id.eq(post_id))
+            .order(post_collaborators::created_at.asc())
+            .select((PostCollaborators::as_select(), users::name))
+            .load(conn)
+    }
+
+    /// Adds a collaborator, or changes the role of one already on the post.
+    pub fn upsert(conn: &mut SqliteConnection, collaborator: &PostCollaborators) -> QueryResult<PostCollaborators> {
+        diesel::insert_into(post_collaborators::table)
+            .values(collaborator)
+            .on_conflict((post_collaborators::post_id, post_collaborators::user_id))
+            .do_update()
+            .set(post_collaborators::role.eq(excluded(post_collaborators::role)))
+            .returning(PostCollaborators::as_select())
+            .get_result(conn)
+    }
+
+    pub fn remove(conn: &mut SqliteConnection, post_id: &str, user_id: &str) -> QueryResult<usize> {
+        diesel::delete(
+            post_collaborators::table
+                .filter(post_collaborators::post_id.eq(post_id))
+                .filter(post_collaborators::user_id.eq(user_id)),
+        )
+        .execute(conn)
+    }
+}
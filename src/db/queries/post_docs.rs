@@ -0,0 +1,63 @@
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use crate::db::models::post_doc::{PostDocUpdates, PostDocs};
+use crate::db::schema::{post_doc_updates, post_docs};
+
+impl PostDocs {
+    pub fn by_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Option<PostDocs>> {
+        post_docs::table
+            .filter(post_docs::post_id.eq(post_id))
+            .select(PostDocs::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn save(conn: &mut SqliteConnection, doc: &PostDocs) -> QueryResult<usize> {
+        diesel::insert_into(post_docs::table)
+            .values(doc)
+            .on_conflict(post_docs::post_id)
+            .do_update()
+            .set((
+                post_docs::state.eq(excluded(post_docs::state)),
+                post_docs::compacted_at.eq(excluded(post_docs::compacted_at)),
+            ))
+            .execute(conn)
+    }
+
+    /// Throws away a post's collaborative document and its pending updates, so the next
+    /// editor to connect starts again from the post's content.
+    pub fn reset(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<()> {
+        diesel::delete(post_doc_updates::table.filter(post_doc_updates::post_id.eq(post_id))).execute(conn)?;
+        diesel::delete(post_docs::table.filter(post_docs::post_id.eq(post_id))).execute(conn)?;
+        Ok(())
+    }
+}
+
+impl PostDocUpdates {
+    pub fn record(conn: &mut SqliteConnection, update: &PostDocUpdates) -> QueryResult<usize> {
+        diesel::insert_into(post_doc_updates::table)
+            .values(update)
+            .execute(conn)
+    }
+
+    pub fn by_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Vec<PostDocUpdates>> {
+        post_doc_updates::table
+            .filter(post_doc_updates::post_id.eq(post_id))
+            .order(post_doc_updates::created_at.asc())
+            .select(PostDocUpdates::as_select())
+            .load(conn)
+    }
+
+    /// Posts with updates waiting to be compacted.
+    pub fn pending_posts(conn: &mut SqliteConnection) -> QueryResult<Vec<String>> {
+        post_doc_updates::table
+            .select(post_doc_updates::post_id)
+            .distinct()
+            .load(conn)
+    }
+
+    pub fn remove(conn: &mut SqliteConnection, ids: &[String]) -> QueryResult<usize> {
+        diesel::delete(post_doc_updates::table.filter(post_doc_updates::id.eq_any(ids)))
+            .execute(conn)
+    }
+}
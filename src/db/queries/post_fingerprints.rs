@@ -0,0 +1,62 @@
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use crate::db::models::post_fingerprint::PostFingerprints;
+use crate::db::schema::post_fingerprints;
+
+impl PostFingerprints {
+    pub fn by_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Option<PostFingerprints>> {
+        post_fingerprints::table
+            .filter(post_fingerprints::post_id.eq(post_id))
+            .select(PostFingerprints::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn upsert(conn: &mut SqliteConnection, fingerprint: &PostFingerprints) -> QueryResult<usize> {
+        diesel::insert_into(post_fingerprints::table)
+            .values(fingerprint)
+            .on_conflict(post_fingerprints::post_id)
+            .do_update()
+            .set((
+                post_fingerprints::simhash.eq(excluded(post_fingerprints::simhash)),
+                post_fingerprints::band0.eq(excluded(post_fingerprints::band0)),
+                post_fingerprints::band1.eq(excluded(post_fingerprints::band1)),
+                post_fingerprints::band2.eq(excluded(post_fingerprints::band2)),
+                post_fingerprints::band3.eq(excluded(post_fingerprints::band3)),
+                post_fingerprints::duplicate_of.eq(excluded(post_fingerprints::duplicate_of)),
+                post_fingerprints::updated_at.eq(excluded(post_fingerprints::updated_at)),
+            ))
+            .execute(conn)
+    }
+
+    /// Other posts sharing at least one band with the fingerprint. Callers still have to check
+    /// the full distance; a shared band only makes a post a candidate.
+    pub fn candidates(conn: &mut SqliteConnection, post_id: &str, bands: [i32; 4]) -> QueryResult<Vec<PostFingerprints>> {
+        post_fingerprints::table
+            .filter(post_fingerprints::post_id.ne(post_id))
+            .filter(
+                post_fingerprints::band0.eq(bands[0])
+                    .or(post_fingerprints::band1.eq(bands[1]))
+                    .or(post_fingerprints::band2.eq(bands[2]))
+                    .or(post_fingerprints::band3.eq(bands[3])),
+            )
+            .select(PostFingerprints::as_select())
+            .load(conn)
+    }
+
+    /// Posts flagged as nearly duplicating another author's post, most recently published first.
+    pub fn flagged(conn: &mut SqliteConnection, limit: i64) -> QueryResult<Vec<PostFingerprints>> {
+        post_fingerprints::table
+            .filter(post_fingerprints::duplicate_of.is_not_null())
+            .order(post_fingerprints::updated_at.desc())
+            .limit(limit)
+            .select(PostFingerprints::as_select())
+            .load(conn)
+    }
+
+    /// Forgets a post's fingerprint once it's no longer public.
+    pub fn remove(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<usize> {
+        diesel::delete(post_fingerprints::table.filter(post_fingerprints::post_id.eq(post_id)))
+            .execute(conn)
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::post_lock::PostLocks;
+use crate::db::schema::post_locks;
+
+impl PostLocks {
+    pub fn by_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Option<PostLocks>> {
+        post_locks::table
+            .filter(post_locks::post_id.eq(post_id))
+            .select(PostLocks::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        self.expires_at <= now
+    }
+
+    /// Gives the lock to `lock.session_id` if nobody holds it, it has expired, the session
+    /// already holds it, or the session asked for a takeover at or before `takeover_due` and
+    /// the holder didn't let go. Each step is a single conditional statement, so two sessions
+    /// racing for a free lock can't both win. Returns whether the lock was granted.
+    pub fn claim(conn: &mut SqliteConnection, lock: &PostLocks, takeover_due: NaiveDateTime) -> QueryResult<bool> {
+        let now = lock.acquired_at;
+        let updated = diesel::update(post_locks::table)
+            .filter(post_locks::post_id.eq(&lock.post_id))
+            .filter(
+                post_locks::expires_at.le(now)
+                    .or(post_locks::session_id.eq(&lock.session_id))
+                    .or(post_locks::takeover_session_id.eq(&lock.session_id)
+                        .and(post_locks::takeover_requested_at.le(takeover_due))),
+            )
+            .set((
+                post_locks::user_id.eq(&lock.user_id),
+                post_locks::session_id.eq(&lock.session_id),
+                post_locks::acquired_at.eq(now),
+                post_locks::expires_at.eq(lock.expires_at),
+                post_locks::takeover_session_id.eq(None::<String>),
+                post_locks::takeover_user_id.eq(None::<String>),
+                post_locks::takeover_requested_at.eq(None::<NaiveDateTime>),
+            ))
+            .execute(conn)?;
+        if updated > 0 {
+            return Ok(true);
+        }
+
+        let inserted = diesel::insert_into(post_locks::table)
+            .values(lock)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        Ok(inserted > 0)
+    }
+
+    /// Extends the session's lease. Returns `None` when the session no longer holds the lock.
+    pub fn renew(conn: &mut SqliteConnection, post_id: &str, session_id: &str, expires_at: NaiveDateTime) -> QueryResult<Option<PostLocks>> {
+        diesel::update(post_locks::table)
+            .filter(post_locks::post_id.eq(post_id))
+            .filter(post_locks::session_id.eq(session_id))
+            .set(post_locks::expires_at.eq(expires_at))
+            .returning(PostLocks::as_select())
+            .get_result(conn)
+            .optional()
+    }
+
+    pub fn release(conn: &mut SqliteConnection, post_id: &str, session_id: &str) -> QueryResult<usize> {
+        diesel::delete(post_locks::table)
+            .filter(post_locks::post_id.eq(post_id))
+            .filter(post_locks::session_id.eq(session_id))
+            .execute(conn)
+    }
+
+    /// Records that another session wants the lock. An earlier pending request is replaced.
+    pub fn request_takeover(
+        conn: &mut SqliteConnection,
+        post_id: &str,
+        session_id: &str,
+        user_id: &str,
+        now: NaiveDateTime,
+    ) -> QueryResult<Option<PostLocks>> {
+        diesel::update(post_locks::table)
+            .filter(post_locks::post_id.eq(post_id))
+            .filter(post_locks::session_id.ne(session_id))
+            .set((
+                post_locks::takeover_session_id.eq(session_id),
+                post_locks::takeover_user_id.eq(user_id),
+                post_locks::takeover_requested_at.eq(now),
+            ))
+            .returning(PostLocks::as_select())
+            .get_result(conn)
+            .optional()
+    }
+}
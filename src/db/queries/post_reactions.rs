@@ -0,0 +1,78 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::post::{Posts, POST_STATUS_PUBLISHED};
+use crate::db::models::post_reaction::PostReactions;
+use crate::db::models::report::MODERATION_VISIBLE;
+use crate::db::schema::{post_reactions, posts, users};
+
+impl PostReactions {
+    /// Records the reaction, replacing any earlier reaction by the same user.
+    pub fn upsert(conn: &mut SqliteConnection, reaction: &PostReactions) -> QueryResult<PostReactions> {
+        diesel::insert_into(post_reactions::table)
+            .values(reaction)
+            .on_conflict((post_reactions::post_id, post_reactions::user_id))
+            .do_update()
+            .set((post_reactions::kind.eq(&reaction.kind), post_reactions::created_at.eq(reaction.created_at)))
+            .returning(PostReactions::as_select())
+            .get_result(conn)
+    }
+
+    pub fn remove(conn: &mut SqliteConnection, post_id: &str, user_id: &str) -> QueryResult<usize> {
+        diesel::delete(
+            post_reactions::table
+                .filter(post_reactions::post_id.eq(post_id))
+                .filter(post_reactions::user_id.eq(user_id)),
+        )
+        .execute(conn)
+    }
+
+    /// `(kind, count)` for each kind of reaction the post has received.
+    pub fn counts_for_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Vec<(String, i64)>> {
+        post_reactions::table
+            .filter(post_reactions::post_id.eq(post_id))
+            .group_by(post_reactions::kind)
+            .select((post_reactions::kind, diesel::dsl::count_star()))
+            .load(conn)
+    }
+
+    /// `(post_id, kind, count)` for a batch of posts, so lists don't need a query per post.
+    pub fn counts_for_posts(conn: &mut SqliteConnection, post_ids: &[String]) -> QueryResult<Vec<(String, String, i64)>> {
+        post_reactions::table
+            .filter(post_reactions::post_id.eq_any(post_ids))
+            .group_by((post_reactions::post_id, post_reactions::kind))
+            .select((post_reactions::post_id, post_reactions::kind, diesel::dsl::count_star()))
+            .load(conn)
+    }
+
+    /// Published posts by active authors that the user reacted to, most recent reaction first,
+    /// with the author's name, the reaction and when it was made.
+    pub fn reacted_posts(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<(Posts, String, String, NaiveDateTime)>> {
+        post_reactions::table
+            .inner_join(posts::table.inner_join(users::table))
+            .filter(post_reactions::user_id.eq(user_id))
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .order(post_reactions::created_at.desc())
+            .offset(offset)
+            .limit(limit)
+            .select((Posts::as_select(), users::name, post_reactions::kind, post_reactions::created_at))
+            .load(conn)
+    }
+
+    pub fn count_reacted_posts(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<i64> {
+        post_reactions::table
+            .inner_join(posts::table.inner_join(users::table))
+            .filter(post_reactions::user_id.eq(user_id))
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .count()
+            .get_result(conn)
+    }
+}
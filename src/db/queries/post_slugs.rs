@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use crate::db::models::post_slug::PostSlugs;
+use crate::db::schema::{post_slugs, posts};
+use crate::utils::escape_like;
+
+impl PostSlugs {
+    /// The post that used to be at `slug`.
+    pub fn post_id_for(conn: &mut SqliteConnection, user_id: &str, slug: &str) -> QueryResult<Option<String>> {
+        post_slugs::table
+            .filter(post_slugs::user_id.eq(user_id))
+            .filter(post_slugs::slug.eq(slug))
+            .select(post_slugs::post_id)
+            .first(conn)
+            .optional()
+    }
+
+    /// Points `slug` at `post_id`, taking it over from whichever post had it before.
+    pub fn record(conn: &mut SqliteConnection, user_id: &str, slug: &str, post_id: &str) -> QueryResult<usize> {
+        let old = PostSlugs {
+            user_id: user_id.to_string(),
+            slug: slug.to_string(),
+            post_id: post_id.to_string(),
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        diesel::insert_into(post_slugs::table)
+            .values(&old)
+            .on_conflict((post_slugs::user_id, post_slugs::slug))
+            .do_update()
+            .set((
+                post_slugs::post_id.eq(excluded(post_slugs::post_id)),
+                post_slugs::created_at.eq(excluded(post_slugs::created_at)),
+            ))
+            .execute(conn)
+    }
+
+    /// Forgets `slug` as an old one, for when a post takes it: a post's current slug always
+    /// wins over another's old one.
+    pub fn release(conn: &mut SqliteConnection, user_id: &str, slug: &str) -> QueryResult<usize> {
+        diesel::delete(
+            post_slugs::table
+                .filter(post_slugs::user_id.eq(user_id))
+                .filter(post_slugs::slug.eq(slug)),
+        )
+        .execute(conn)
+    }
+
+    /// The author's slugs, current or old, that are `base` or `base-` followed by anything.
+    /// Those belonging to `post_id` don't count, as a post can always go back to its own.
+    pub fn taken(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        base: &str,
+        post_id: Option<&str>,
+    ) -> QueryResult<HashSet<String>> {
+        let pattern = format!("{}-%", escape_like(base));
+        let post_id = post_id.unwrap_or_default();
+
+        let mut taken: HashSet<String> = posts::table
+            .filter(posts::user_id.eq(user_id))
+            .filter(posts::id.ne(post_id))
+            .filter(posts::slug.eq(base).or(posts::slug.like(&pattern).escape('\\')))
+            .select(posts::slug)
+            .load::<String>(conn)?
+            .into_iter()
+            .collect();
+        taken.extend(
+            post_slugs::table
+                .filter(post_slugs::user_id.eq(user_id))
+                .filter(post_slugs::post_id.ne(post_id))
+                .filter(post_slugs::slug.eq(base).or(post_slugs::slug.like(&pattern).escape('\\')))
+                .select(post_slugs::slug)
+                .load::<String>(conn)?,
+        );
+        Ok(taken)
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+use crate::db::models::post::Posts;
+use crate::db::models::post_version::{NewPostVersion, PostVersions};
+use crate::db::schema::post_versions;
+
+/// Identifies a saved revision by what was saved and when. Versions of other documents, such
+/// as site pages, are hashed the same way.
+pub fn commit_hash(id: &str, title: &str, description: &str, content: &str, at: NaiveDateTime) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(id.as_bytes());
+    hasher.update(title.as_bytes());
+    hasher.update(description.as_bytes());
+    hasher.update(content.as_bytes());
+    hasher.update(at.and_utc().timestamp_micros().to_be_bytes());
+    hex::encode(hasher.finalize())
+}
+
+impl PostVersions {
+    pub fn by_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Vec<PostVersions>> {
+        post_versions::table
+            .filter(post_versions::post_id.eq(post_id))
+            .order(post_versions::created_at.desc())
+            .select(PostVersions::as_select())
+            .load(conn)
+    }
+
+    /// Snapshots the post's current title, description and content as a new version.
+    pub fn record(conn: &mut SqliteConnection, post: &Posts, author_id: &str, message: &str) -> QueryResult<PostVersions> {
+        let now = chrono::Utc::now().naive_utc();
+
+        let version = NewPostVersion {
+            id: uuid::Uuid::new_v4().to_string(),
+            post_id: post.id.clone(),
+            user_id: author_id.to_owned(),
+            title: post.title.clone(),
+            content: post.content.clone(),
+            description: post.description.clone(),
+            commit_hash: commit_hash(&post.id, &post.title, &post.description, &post.content, now),
+            commit_message: message.to_owned(),
+            created_at: now,
+        };
+
+        diesel::insert_into(post_versions::table)
+            .values(&version)
+            .returning(PostVersions::as_select())
+            .get_result(conn)
+    }
+}
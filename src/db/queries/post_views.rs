@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use diesel::dsl::sum;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use crate::db::models::post::{Posts, POST_STATUS_PUBLISHED};
+use crate::db::models::post_view::PostViews;
+use crate::db::models::report::MODERATION_VISIBLE;
+use crate::db::schema::{post_views, posts, users};
+
+impl PostViews {
+    /// Adds `views` to the post's count for `day`.
+    pub fn add(conn: &mut SqliteConnection, post_id: &str, day: NaiveDate, views: i32) -> QueryResult<usize> {
+        let row = PostViews { post_id: post_id.to_string(), day, views };
+        diesel::insert_into(post_views::table)
+            .values(&row)
+            .on_conflict((post_views::post_id, post_views::day))
+            .do_update()
+            .set(post_views::views.eq(post_views::views + excluded(post_views::views)))
+            .execute(conn)
+    }
+
+    /// Every view the post has had.
+    pub fn total(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<i64> {
+        post_views::table
+            .filter(post_views::post_id.eq(post_id))
+            .select(sum(post_views::views))
+            .first::<Option<i64>>(conn)
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Views by post for a batch of posts, so lists don't need a query per post. Posts never
+    /// viewed are missing.
+    pub fn totals(conn: &mut SqliteConnection, post_ids: &[String]) -> QueryResult<HashMap<String, i64>> {
+        let totals: Vec<(String, Option<i64>)> = post_views::table
+            .filter(post_views::post_id.eq_any(post_ids))
+            .group_by(post_views::post_id)
+            .select((post_views::post_id, sum(post_views::views)))
+            .load(conn)?;
+        Ok(totals.into_iter().map(|(post_id, views)| (post_id, views.unwrap_or_default())).collect())
+    }
+
+    /// The most viewed published posts by active authors since `since`, with each author's
+    /// name and the views in that window.
+    pub fn popular(conn: &mut SqliteConnection, since: NaiveDate, limit: i64) -> QueryResult<Vec<(Posts, String, i64)>> {
+        let ranked: Vec<(String, Option<i64>)> = post_views::table
+            .inner_join(posts::table.inner_join(users::table))
+            .filter(post_views::day.ge(since))
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .group_by(post_views::post_id)
+            .select((post_views::post_id, sum(post_views::views)))
+            .order((sum(post_views::views).desc(), post_views::post_id.asc()))
+            .limit(limit)
+            .load(conn)?;
+
+        let post_ids: Vec<&String> = ranked.iter().map(|(post_id, _)| post_id).collect();
+        let mut posts: HashMap<String, (Posts, String)> = posts::table
+            .inner_join(users::table)
+            .filter(posts::id.eq_any(post_ids))
+            .select((Posts::as_select(), users::name))
+            .load::<(Posts, String)>(conn)?
+            .into_iter()
+            .map(|(post, author)| (post.id.clone(), (post, author)))
+            .collect();
+
+        Ok(ranked
+            .into_iter()
+            .filter_map(|(post_id, views)| {
+                posts.remove(&post_id).map(|(post, author)| (post, author, views.unwrap_or_default()))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,453 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel::sqlite::Sqlite;
+use crate::db::models::backfill_job::{
+    BACKFILL_KIND_DESCRIPTION, BACKFILL_KIND_OG_IMAGE, BACKFILL_KIND_SEARCH_INDEX, BACKFILL_KIND_WORD_COUNT,
+};
+use crate::db::models::post::{NewPost, PostChanges, Posts, POST_STATUS_PUBLISHED, POST_STATUS_SCHEDULED};
+use crate::db::models::report::{MODERATION_HIDDEN, MODERATION_VISIBLE};
+use crate::db::schema::{follows, post_search_index, post_tags, posts, tags, users};
+use crate::http::pagination::SortDir;
+use crate::utils::escape_like;
+
+/// Optional narrowing for an author's post list.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PostFilter<'a> {
+    pub status: Option<&'a str>,
+    pub tag: Option<&'a str>,
+}
+
+/// Optional narrowing for the public post list.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PublicPostFilter<'a> {
+    pub author_id: Option<&'a str>,
+    pub tag: Option<&'a str>,
+}
+
+impl Posts {
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<Posts>> {
+        posts::table
+            .filter(posts::id.eq(id))
+            .select(Posts::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn count_by_user(conn: &mut SqliteConnection, user_id: &str, filter: &PostFilter) -> QueryResult<i64> {
+        owned_by(user_id, filter).count().get_result(conn)
+    }
+
+    /// A page of the user's posts in any state, narrowed by `filter` and ordered by `sort`.
+    pub fn page_by_user(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        filter: &PostFilter,
+        sort: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<Posts>> {
+        let query = owned_by(user_id, filter);
+        let query = match (sort, dir.is_asc()) {
+            ("created_at", true) => query.order(posts::created_at.asc()),
+            ("created_at", false) => query.order(posts::created_at.desc()),
+            ("published_at", true) => query.order(posts::published_at.asc()),
+            ("published_at", false) => query.order(posts::published_at.desc()),
+            ("title", true) => query.order(posts::title.asc()),
+            ("title", false) => query.order(posts::title.desc()),
+            (_, true) => query.order(posts::updated_at.asc()),
+            (_, false) => query.order(posts::updated_at.desc()),
+        };
+
+        query
+            .then_order_by(posts::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select(Posts::as_select())
+            .load(conn)
+    }
+
+    /// Every post the user has written, drafts included, oldest first.
+    pub fn all_by_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<Posts>> {
+        posts::table
+            .filter(posts::user_id.eq(user_id))
+            .order((posts::created_at.asc(), posts::id.asc()))
+            .select(Posts::as_select())
+            .load(conn)
+    }
+
+    pub fn published_by_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<Posts>> {
+        posts::table
+            .filter(posts::user_id.eq(user_id))
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .order(posts::published_at.desc())
+            .select(Posts::as_select())
+            .load(conn)
+    }
+
+    pub fn latest_published_by_user(conn: &mut SqliteConnection, user_id: &str, limit: i64) -> QueryResult<Vec<Posts>> {
+        posts::table
+            .filter(posts::user_id.eq(user_id))
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .order(posts::published_at.desc())
+            .limit(limit)
+            .select(Posts::as_select())
+            .load(conn)
+    }
+
+    pub fn published_by_slug(conn: &mut SqliteConnection, user_id: &str, slug: &str) -> QueryResult<Option<Posts>> {
+        posts::table
+            .filter(posts::user_id.eq(user_id))
+            .filter(posts::slug.eq(slug))
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .select(Posts::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// A page of published posts from active authors, newest first, with the author's name.
+    pub fn published_page(conn: &mut SqliteConnection, offset: i64, limit: i64) -> QueryResult<Vec<(Posts, String)>> {
+        posts::table
+            .inner_join(users::table)
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .order(posts::published_at.desc())
+            .offset(offset)
+            .limit(limit)
+            .select((Posts::as_select(), users::name))
+            .load(conn)
+    }
+
+    pub fn count_public(conn: &mut SqliteConnection, filter: &PublicPostFilter) -> QueryResult<i64> {
+        public(filter).count().get_result(conn)
+    }
+
+    /// A page of published posts from active authors by publish time, with each author's name.
+    pub fn public_page(
+        conn: &mut SqliteConnection,
+        filter: &PublicPostFilter,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<(Posts, String)>> {
+        let query = public(filter);
+        let query = if dir.is_asc() {
+            query.order(posts::published_at.asc())
+        } else {
+            query.order(posts::published_at.desc())
+        };
+
+        query
+            .then_order_by(posts::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select((Posts::as_select(), users::name))
+            .load(conn)
+    }
+
+    pub fn count_published(conn: &mut SqliteConnection) -> QueryResult<i64> {
+        posts::table
+            .inner_join(users::table)
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .count()
+            .get_result(conn)
+    }
+
+    /// How many posts the user published on each day since `since`, as `YYYY-MM-DD` days.
+    pub fn daily_published_counts(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        since: NaiveDateTime,
+    ) -> QueryResult<Vec<(String, i64)>> {
+        posts::table
+            .filter(posts::user_id.eq(user_id))
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(posts::published_at.ge(since))
+            .group_by(diesel::dsl::sql::<Text>("date(posts.published_at)"))
+            .select((diesel::dsl::sql::<Text>("date(posts.published_at)"), diesel::dsl::count_star()))
+            .load(conn)
+    }
+
+    pub fn count_feed(conn: &mut SqliteConnection, follower_id: &str) -> QueryResult<i64> {
+        feed(follower_id).count().get_result(conn)
+    }
+
+    /// A page of published posts by the authors `follower_id` follows, by publish time.
+    pub fn feed_page(
+        conn: &mut SqliteConnection,
+        follower_id: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<Posts>> {
+        let query = feed(follower_id);
+        let query = if dir.is_asc() {
+            query.order(posts::published_at.asc())
+        } else {
+            query.order(posts::published_at.desc())
+        };
+
+        query
+            .then_order_by(posts::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select(Posts::as_select())
+            .load(conn)
+    }
+
+    /// Posts published after `since` by authors `follower_id` follows, newest first, each
+    /// with its author's name.
+    pub fn followed_since(
+        conn: &mut SqliteConnection,
+        follower_id: &str,
+        since: NaiveDateTime,
+        limit: i64,
+    ) -> QueryResult<Vec<(Posts, String)>> {
+        posts::table
+            .inner_join(users::table)
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(posts::published_at.gt(since))
+            .filter(users::deleted_at.is_null())
+            .filter(posts::user_id.eq_any(
+                follows::table.filter(follows::follower_id.eq(follower_id)).select(follows::followee_id),
+            ))
+            .order(posts::published_at.desc())
+            .limit(limit)
+            .select((Posts::as_select(), users::name))
+            .load(conn)
+    }
+
+    /// Posts in any state whose id matches `term` or whose slug starts with it.
+    pub fn search(conn: &mut SqliteConnection, term: &str, limit: i64) -> QueryResult<Vec<Posts>> {
+        let pattern = format!("{}%", escape_like(term));
+        posts::table
+            .filter(posts::id.eq(term).or(posts::slug.like(pattern).escape('\\')))
+            .order(posts::updated_at.desc())
+            .limit(limit)
+            .select(Posts::as_select())
+            .load(conn)
+    }
+
+    /// `(author name, slug, updated_at)` for every published post by an active author, grouped
+    /// by author so sitemap chunks are stable between requests.
+    pub fn sitemap_entries(conn: &mut SqliteConnection) -> QueryResult<Vec<(String, String, NaiveDateTime)>> {
+        posts::table
+            .inner_join(users::table)
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .order((users::name.asc(), posts::published_at.desc()))
+            .select((users::name, posts::slug, posts::updated_at))
+            .load(conn)
+    }
+
+    /// How many posts the sitemap lists and when the latest of them changed, to tell when it
+    /// needs rebuilding.
+    pub fn sitemap_version(conn: &mut SqliteConnection) -> QueryResult<(i64, Option<NaiveDateTime>)> {
+        posts::table
+            .inner_join(users::table)
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .select((diesel::dsl::count_star(), diesel::dsl::max(posts::updated_at)))
+            .first(conn)
+    }
+
+    pub fn create(conn: &mut SqliteConnection, new_post: &NewPost) -> QueryResult<Posts> {
+        diesel::insert_into(posts::table)
+            .values(new_post)
+            .returning(Posts::as_select())
+            .get_result(conn)
+    }
+
+    pub fn update(conn: &mut SqliteConnection, id: &str, changes: &PostChanges) -> QueryResult<Posts> {
+        diesel::update(posts::table.filter(posts::id.eq(id)))
+            .set(changes)
+            .returning(Posts::as_select())
+            .get_result(conn)
+    }
+
+    /// Moves a post between draft, scheduled and published. `published_at` is the go-live time
+    /// for scheduled posts and the time the post went live for published ones.
+    pub fn set_status(
+        conn: &mut SqliteConnection,
+        id: &str,
+        status: &str,
+        published_at: Option<NaiveDateTime>,
+    ) -> QueryResult<Posts> {
+        diesel::update(posts::table.filter(posts::id.eq(id)))
+            .set((
+                posts::status.eq(status),
+                posts::published_at.eq(published_at),
+                posts::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(Posts::as_select())
+            .get_result(conn)
+    }
+
+    /// Publishes every scheduled post whose publish time has passed, returning the number flipped.
+    /// Publishes every scheduled post whose time has come, returning them.
+    pub fn publish_due(conn: &mut SqliteConnection, now: NaiveDateTime) -> QueryResult<Vec<Posts>> {
+        diesel::update(
+            posts::table
+                .filter(posts::status.eq(POST_STATUS_SCHEDULED))
+                .filter(posts::published_at.le(now)),
+        )
+        .set((posts::status.eq(POST_STATUS_PUBLISHED), posts::updated_at.eq(now)))
+        .returning(Posts::as_returning())
+        .get_results(conn)
+    }
+
+    pub fn is_published(&self) -> bool {
+        self.status == POST_STATUS_PUBLISHED
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.moderation_status == MODERATION_HIDDEN
+    }
+
+    /// Published and not hidden by a moderator, so anyone may see it.
+    pub fn is_public(&self) -> bool {
+        self.is_published() && !self.is_hidden()
+    }
+
+    /// Hides the post from everyone but its author, or shows it again. `updated_at` is left
+    /// alone; the author didn't change anything.
+    pub fn set_moderation_status(conn: &mut SqliteConnection, id: &str, status: &str) -> QueryResult<usize> {
+        diesel::update(posts::table.filter(posts::id.eq(id)))
+            .set(posts::moderation_status.eq(status))
+            .execute(conn)
+    }
+
+    /// Number of posts still missing the metadata a backfill of `kind` fills in.
+    pub fn count_missing_metadata(conn: &mut SqliteConnection, kind: &str) -> QueryResult<i64> {
+        missing_metadata(kind).count().get_result(conn)
+    }
+
+    /// The next batch of posts missing `kind` metadata, in id order after `cursor`.
+    pub fn missing_metadata_after(
+        conn: &mut SqliteConnection,
+        kind: &str,
+        cursor: Option<&str>,
+        limit: i64,
+    ) -> QueryResult<Vec<Posts>> {
+        let mut query = missing_metadata(kind);
+        if let Some(cursor) = cursor {
+            query = query.filter(posts::id.gt(cursor.to_string()));
+        }
+
+        query
+            .order(posts::id.asc())
+            .limit(limit)
+            .select(Posts::as_select())
+            .load(conn)
+    }
+
+    /// Sets derived metadata without touching `updated_at`, so backfills don't reorder feeds.
+    pub fn set_metadata(conn: &mut SqliteConnection, id: &str, changes: &PostChanges) -> QueryResult<usize> {
+        diesel::update(posts::table.filter(posts::id.eq(id)))
+            .set(changes)
+            .execute(conn)
+    }
+
+    pub fn index_search_terms(conn: &mut SqliteConnection, id: &str, terms: &str) -> QueryResult<usize> {
+        let now = Utc::now().naive_utc();
+        diesel::insert_into(post_search_index::table)
+            .values((
+                post_search_index::post_id.eq(id),
+                post_search_index::terms.eq(terms),
+                post_search_index::indexed_at.eq(now),
+            ))
+            .on_conflict(post_search_index::post_id)
+            .do_update()
+            .set((post_search_index::terms.eq(terms), post_search_index::indexed_at.eq(now)))
+            .execute(conn)
+    }
+
+    pub fn delete(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        diesel::delete(posts::table.filter(posts::id.eq(id))).execute(conn)
+    }
+}
+
+fn owned_by<'a>(user_id: &str, filter: &PostFilter) -> posts::BoxedQuery<'a, Sqlite> {
+    let mut query = posts::table.filter(posts::user_id.eq(user_id.to_owned())).into_boxed();
+    if let Some(status) = filter.status {
+        query = query.filter(posts::status.eq(status.to_owned()));
+    }
+    if let Some(tag) = filter.tag {
+        query = query.filter(
+            posts::id.eq_any(
+                post_tags::table
+                    .inner_join(tags::table)
+                    .filter(tags::name.eq(tag.to_owned()))
+                    .select(post_tags::post_id),
+            ),
+        );
+    }
+    query
+}
+
+/// Published posts by active authors, narrowed by `filter`.
+fn public<'a>(filter: &PublicPostFilter) -> diesel::dsl::IntoBoxed<'a, diesel::dsl::InnerJoin<posts::table, users::table>, Sqlite> {
+    let mut query = posts::table
+        .inner_join(users::table)
+        .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+        .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+        .filter(users::deleted_at.is_null())
+        .into_boxed();
+    if let Some(author_id) = filter.author_id {
+        query = query.filter(posts::user_id.eq(author_id.to_owned()));
+    }
+    if let Some(tag) = filter.tag {
+        query = query.filter(
+            posts::id.eq_any(
+                post_tags::table
+                    .inner_join(tags::table)
+                    .filter(tags::name.eq(tag.to_owned()))
+                    .select(post_tags::post_id),
+            ),
+        );
+    }
+    query
+}
+
+/// Published posts by active authors `follower_id` follows.
+fn feed<'a>(follower_id: &str) -> posts::BoxedQuery<'a, Sqlite> {
+    posts::table
+        .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+        .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+        .filter(
+            posts::user_id.eq_any(
+                follows::table
+                    .inner_join(users::table.on(users::id.eq(follows::followee_id)))
+                    .filter(follows::follower_id.eq(follower_id.to_owned()))
+                    .filter(users::deleted_at.is_null())
+                    .select(follows::followee_id),
+            ),
+        )
+        .into_boxed()
+}
+
+fn missing_metadata(kind: &str) -> posts::BoxedQuery<'static, Sqlite> {
+    let query = posts::table.into_boxed();
+    match kind {
+        BACKFILL_KIND_WORD_COUNT => query.filter(posts::word_count.is_null().or(posts::reading_minutes.is_null())),
+        BACKFILL_KIND_DESCRIPTION => query.filter(posts::description.eq("")),
+        BACKFILL_KIND_SEARCH_INDEX => query.filter(diesel::dsl::not(diesel::dsl::exists(
+            post_search_index::table.filter(post_search_index::post_id.eq(posts::id)),
+        ))),
+        BACKFILL_KIND_OG_IMAGE => query
+            .filter(posts::og_image_url.is_null())
+            .filter(posts::content.like("%![%")),
+        _ => query.filter(posts::id.is_null()),
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use crate::db::models::push_subscription::PushSubscriptions;
+use crate::db::schema::push_subscriptions;
+
+impl PushSubscriptions {
+    /// Stores the subscription, taking over any existing one for the same endpoint: a browser
+    /// that subscribes again gets fresh keys, and may be signed in as someone else by then.
+    pub fn upsert(conn: &mut SqliteConnection, subscription: &PushSubscriptions) -> QueryResult<PushSubscriptions> {
+        diesel::insert_into(push_subscriptions::table)
+            .values(subscription)
+            .on_conflict(push_subscriptions::endpoint)
+            .do_update()
+            .set((
+                push_subscriptions::user_id.eq(excluded(push_subscriptions::user_id)),
+                push_subscriptions::p256dh.eq(excluded(push_subscriptions::p256dh)),
+                push_subscriptions::auth.eq(excluded(push_subscriptions::auth)),
+                push_subscriptions::expires_at.eq(excluded(push_subscriptions::expires_at)),
+                push_subscriptions::user_agent.eq(excluded(push_subscriptions::user_agent)),
+            ))
+            .returning(PushSubscriptions::as_returning())
+            .get_result(conn)
+    }
+
+    pub fn for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<PushSubscriptions>> {
+        push_subscriptions::table
+            .filter(push_subscriptions::user_id.eq(user_id))
+            .order(push_subscriptions::created_at.asc())
+            .select(PushSubscriptions::as_select())
+            .load(conn)
+    }
+
+    pub fn exists_for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<bool> {
+        diesel::select(diesel::dsl::exists(
+            push_subscriptions::table.filter(push_subscriptions::user_id.eq(user_id)),
+        ))
+        .get_result(conn)
+    }
+
+    /// Removes the user's subscription for `endpoint`, returning whether there was one.
+    pub fn delete_by_endpoint(conn: &mut SqliteConnection, user_id: &str, endpoint: &str) -> QueryResult<bool> {
+        diesel::delete(
+            push_subscriptions::table
+                .filter(push_subscriptions::user_id.eq(user_id))
+                .filter(push_subscriptions::endpoint.eq(endpoint)),
+        )
+        .execute(conn)
+        .map(|deleted| deleted > 0)
+    }
+
+    pub fn remove(conn: &mut SqliteConnection, ids: &[String]) -> QueryResult<usize> {
+        diesel::delete(push_subscriptions::table.filter(push_subscriptions::id.eq_any(ids))).execute(conn)
+    }
+
+    /// Drops subscriptions the browser said would lapse by `now`.
+    pub fn delete_expired(conn: &mut SqliteConnection, now: NaiveDateTime) -> QueryResult<usize> {
+        diesel::delete(push_subscriptions::table.filter(push_subscriptions::expires_at.le(now))).execute(conn)
+    }
+}
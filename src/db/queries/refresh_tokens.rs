@@ -1,9 +1,12 @@
 use diesel::dsl::{AsSelect, SqlTypeOf};
 use diesel::prelude::*;
 use diesel::sqlite::Sqlite;
-use chrono::{Utc};
+use chrono::{Duration, NaiveDateTime, Utc};
 use crate::db::models::refresh_token::{NewRefreshToken, RefreshTokens};
 use crate::db::schema::refresh_tokens;
+use crate::db::write::write;
+use crate::http::client::device_label;
+use crate::http::pagination::SortDir;
 use diesel::SelectableHelper;
 
 type SqlType = SqlTypeOf<AsSelect<RefreshTokens, Sqlite>>;
@@ -21,43 +24,164 @@ impl RefreshTokens {
             .get_result(conn)
     }
 
-    pub fn token_exists(conn: &mut SqliteConnection, token: &str) -> QueryResult<bool> {
-        use diesel::dsl::{exists, select};
-        select(exists(refresh_tokens::table.filter(refresh_tokens::token.eq(token))))
-            .get_result(conn)
-    }
-
     pub fn delete_by_token(conn: &mut SqliteConnection, token: &str) -> QueryResult<usize> {
         diesel::delete(refresh_tokens::table.filter(refresh_tokens::token.eq(token)))
             .execute(conn)
     }
 
-    pub fn is_expired(conn: &mut SqliteConnection, token: &str) -> QueryResult<bool> {
-        use diesel::dsl::{exists, select};
-        let now = Utc::now().naive_utc();
+    /// Revokes every token issued in a session, returning how many there were.
+    pub fn delete_family(conn: &mut SqliteConnection, family_id: &str) -> QueryResult<usize> {
+        diesel::delete(refresh_tokens::table.filter(refresh_tokens::family_id.eq(family_id)))
+            .execute(conn)
+    }
+
+    pub fn delete_by_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<usize> {
+        diesel::delete(refresh_tokens::table.filter(refresh_tokens::user_id.eq(user_id)))
+            .execute(conn)
+    }
 
-        select(exists(
-            refresh_tokens::table
-                .filter(refresh_tokens::token.eq(token))
-                .filter(refresh_tokens::expires_at.lt(now))
-        )).get_result(conn)
+    pub fn delete_expired(conn: &mut SqliteConnection, now: NaiveDateTime) -> QueryResult<usize> {
+        diesel::delete(refresh_tokens::table.filter(refresh_tokens::expires_at.lt(now)))
+            .execute(conn)
     }
 
-    pub fn create(conn: &mut SqliteConnection, token: &str, user_id: &str, days: i64) -> QueryResult<RefreshTokens> {
-        let now = Utc::now();
-        let expires_at = now + chrono::Duration::days(days);
+    pub fn count_for_user(conn: &mut SqliteConnection, user_id: &str, include_expired: bool) -> QueryResult<i64> {
+        let mut query = refresh_tokens::table
+            .filter(refresh_tokens::user_id.eq(user_id))
+            .filter(refresh_tokens::replaced_by.is_null())
+            .into_boxed();
+        if !include_expired {
+            query = query.filter(refresh_tokens::expires_at.gt(Utc::now().naive_utc()));
+        }
+        query.count().get_result(conn)
+    }
 
-        let new_token = NewRefreshToken {
-            id: uuid::Uuid::new_v4().to_string(),
-            token: token.to_owned(),
-            user_id: user_id.to_owned(),
-            expires_at: expires_at.naive_utc(),
-            created_at: now.naive_utc(),
+    /// A page of the user's sessions, ordered by `sort` (`created_at` or `expires_at`).
+    pub fn page_for_user(
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        include_expired: bool,
+        sort: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<RefreshTokens>> {
+        let mut query = Self::all()
+            .filter(refresh_tokens::user_id.eq(user_id.to_owned()))
+            .filter(refresh_tokens::replaced_by.is_null());
+        if !include_expired {
+            query = query.filter(refresh_tokens::expires_at.gt(Utc::now().naive_utc()));
+        }
+        let query = match (sort, dir.is_asc()) {
+            ("expires_at", true) => query.order(refresh_tokens::expires_at.asc()),
+            ("expires_at", false) => query.order(refresh_tokens::expires_at.desc()),
+            (_, true) => query.order(refresh_tokens::created_at.asc()),
+            (_, false) => query.order(refresh_tokens::created_at.desc()),
         };
 
+        query
+            .then_order_by(refresh_tokens::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .load(conn)
+    }
+
+    /// Sessions opened from the given IP address or belonging to the given user id.
+    pub fn search(conn: &mut SqliteConnection, term: &str, limit: i64) -> QueryResult<Vec<RefreshTokens>> {
+        refresh_tokens::table
+            .filter(refresh_tokens::ip_address.eq(term).or(refresh_tokens::user_id.eq(term)))
+            .filter(refresh_tokens::replaced_by.is_null())
+            .order(refresh_tokens::created_at.desc())
+            .limit(limit)
+            .select(RefreshTokens::as_select())
+            .load(conn)
+    }
+
+    pub fn is_replaced(&self) -> bool {
+        self.replaced_by.is_some()
+    }
+
+    /// Opens a session lasting `lifetime`, starting a new token family.
+    pub fn create(
+        conn: &mut SqliteConnection,
+        token: &str,
+        user_id: &str,
+        lifetime: Duration,
+        persistent: bool,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> QueryResult<RefreshTokens> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let new_token = new_token(id.clone(), id, token, user_id, lifetime, persistent, ip_address, user_agent);
+        Self::insert(conn, &new_token)
+    }
+
+    /// Issues the next token in `previous`'s family, lasting `lifetime`, and marks `previous`
+    /// as replaced by it.
+    /// The replaced token is kept until it expires so that presenting it again is noticed.
+    /// `None` when `previous` was already replaced, e.g. by a concurrent refresh.
+    pub fn rotate(
+        conn: &mut SqliteConnection,
+        previous: &RefreshTokens,
+        token: &str,
+        lifetime: Duration,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> QueryResult<Option<RefreshTokens>> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let next = new_token(
+            id,
+            previous.family_id.clone(),
+            token,
+            &previous.user_id,
+            lifetime,
+            previous.persistent,
+            ip_address,
+            user_agent,
+        );
+
+        write(conn, |conn| {
+            let replaced = diesel::update(refresh_tokens::table.find(&previous.id))
+                .filter(refresh_tokens::replaced_by.is_null())
+                .set(refresh_tokens::replaced_by.eq(&next.id))
+                .execute(conn)?;
+            if replaced == 0 {
+                return Ok(None);
+            }
+            Self::insert(conn, &next).map(Some)
+        })
+    }
+
+    fn insert(conn: &mut SqliteConnection, new_token: &NewRefreshToken) -> QueryResult<RefreshTokens> {
         diesel::insert_into(refresh_tokens::table)
-            .values(&new_token)
+            .values(new_token)
             .returning(RefreshTokens::as_select())
             .get_result(conn)
     }
-}
\ No newline at end of file
+}
+
+#[allow(clippy::too_many_arguments)]
+fn new_token(
+    id: String,
+    family_id: String,
+    token: &str,
+    user_id: &str,
+    lifetime: Duration,
+    persistent: bool,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+) -> NewRefreshToken {
+    let now = Utc::now();
+    NewRefreshToken {
+        id,
+        family_id,
+        token: token.to_owned(),
+        user_id: user_id.to_owned(),
+        expires_at: (now + lifetime).naive_utc(),
+        created_at: now.naive_utc(),
+        ip_address: ip_address.map(str::to_owned),
+        user_agent: user_agent.map(str::to_owned),
+        device: Some(device_label(user_agent)),
+        persistent,
+    }
+}
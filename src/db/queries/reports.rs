@@ -0,0 +1,106 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use crate::db::models::report::{Reports, REPORT_STATUS_OPEN};
+use crate::db::schema::{comments, posts, reports, users};
+use crate::http::pagination::SortDir;
+
+/// A report with its reporter's name, `None` when the spam check filed it, and a glimpse of
+/// what was reported: the post's title, and for comment reports the comment's body.
+pub type ReportEntry = (Reports, Option<String>, String, Option<String>);
+
+impl Reports {
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<Reports>> {
+        reports::table
+            .filter(reports::id.eq(id))
+            .select(Reports::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn create(conn: &mut SqliteConnection, report: &Reports) -> QueryResult<Reports> {
+        diesel::insert_into(reports::table)
+            .values(report)
+            .returning(Reports::as_select())
+            .get_result(conn)
+    }
+
+    /// Whether the user already has a report waiting on this post or comment.
+    pub fn open_exists(
+        conn: &mut SqliteConnection,
+        reporter_id: &str,
+        post_id: &str,
+        comment_id: Option<&str>,
+    ) -> QueryResult<bool> {
+        let query = same_target(post_id, comment_id)
+            .filter(reports::reporter_id.eq(reporter_id.to_owned()))
+            .filter(reports::status.eq(REPORT_STATUS_OPEN));
+        diesel::dsl::select(diesel::dsl::exists(query.select(reports::id))).get_result(conn)
+    }
+
+    pub fn count_by_status(conn: &mut SqliteConnection, status: &str) -> QueryResult<i64> {
+        reports::table
+            .filter(reports::status.eq(status))
+            .count()
+            .get_result(conn)
+    }
+
+    /// A page of reports in `status`, by when they were made.
+    pub fn page_by_status(
+        conn: &mut SqliteConnection,
+        status: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<ReportEntry>> {
+        let query = reports::table
+            .left_join(users::table.on(users::id.nullable().eq(reports::reporter_id)))
+            .inner_join(posts::table)
+            .left_join(comments::table)
+            .filter(reports::status.eq(status))
+            .into_boxed();
+        let query = if dir.is_asc() {
+            query.order(reports::created_at.asc())
+        } else {
+            query.order(reports::created_at.desc())
+        };
+
+        query
+            .then_order_by(reports::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select((Reports::as_select(), users::name.nullable(), posts::title, comments::body.nullable()))
+            .load(conn)
+    }
+
+    /// Closes every open report on the same post or comment as `report`, recording who
+    /// reviewed them, and returns how many were closed.
+    pub fn resolve_all_like(
+        conn: &mut SqliteConnection,
+        report: &Reports,
+        status: &str,
+        reviewer_id: &str,
+    ) -> QueryResult<usize> {
+        let ids: Vec<String> = same_target(&report.post_id, report.comment_id.as_deref())
+            .filter(reports::status.eq(REPORT_STATUS_OPEN))
+            .select(reports::id)
+            .load(conn)?;
+
+        diesel::update(reports::table.filter(reports::id.eq_any(&ids)))
+            .set((
+                reports::status.eq(status),
+                reports::reviewed_by.eq(reviewer_id),
+                reports::reviewed_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+    }
+}
+
+/// Reports on the post itself, or on the comment when there is one.
+fn same_target<'a>(post_id: &str, comment_id: Option<&str>) -> reports::BoxedQuery<'a, Sqlite> {
+    let query = reports::table.filter(reports::post_id.eq(post_id.to_owned())).into_boxed();
+    match comment_id {
+        Some(comment_id) => query.filter(reports::comment_id.eq(comment_id.to_owned())),
+        None => query.filter(reports::comment_id.is_null()),
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use crate::db::models::reset_token::{NewResetToken, ResetTokens};
+use crate::db::schema::reset_tokens;
+
+impl ResetTokens {
+    pub fn by_token(conn: &mut SqliteConnection, token: &str) -> QueryResult<Option<ResetTokens>> {
+        reset_tokens::table
+            .filter(reset_tokens::token.eq(token))
+            .select(ResetTokens::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn latest_for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Option<ResetTokens>> {
+        reset_tokens::table
+            .filter(reset_tokens::user_id.eq(user_id))
+            .order(reset_tokens::created_at.desc())
+            .select(ResetTokens::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn create(conn: &mut SqliteConnection, token: &str, user_id: &str, minutes: i64) -> QueryResult<usize> {
+        let now = Utc::now();
+
+        let new_token = NewResetToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            token: token.to_owned(),
+            expires_at: (now + chrono::Duration::minutes(minutes)).naive_utc(),
+            user_id: user_id.to_owned(),
+            created_at: now.naive_utc(),
+        };
+
+        diesel::insert_into(reset_tokens::table)
+            .values(&new_token)
+            .execute(conn)
+    }
+
+    pub fn delete_by_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<usize> {
+        diesel::delete(reset_tokens::table.filter(reset_tokens::user_id.eq(user_id)))
+            .execute(conn)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now().naive_utc()
+    }
+}
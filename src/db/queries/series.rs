@@ -0,0 +1,96 @@
+use diesel::dsl::max;
+use diesel::prelude::*;
+use crate::db::models::post::Posts;
+use crate::db::models::series::{Series, SeriesChanges, SeriesPosts};
+use crate::db::schema::{posts, series, series_posts};
+
+impl Series {
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<Series>> {
+        series::table
+            .filter(series::id.eq(id))
+            .select(Series::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// The author's series, newest first.
+    pub fn by_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<Series>> {
+        series::table
+            .filter(series::user_id.eq(user_id))
+            .order(series::created_at.desc())
+            .select(Series::as_select())
+            .load(conn)
+    }
+
+    /// The series `post_id` is part of.
+    pub fn of_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Option<Series>> {
+        series::table
+            .inner_join(series_posts::table)
+            .filter(series_posts::post_id.eq(post_id))
+            .select(Series::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn create(conn: &mut SqliteConnection, new_series: &Series) -> QueryResult<Series> {
+        diesel::insert_into(series::table)
+            .values(new_series)
+            .returning(Series::as_select())
+            .get_result(conn)
+    }
+
+    pub fn update(conn: &mut SqliteConnection, id: &str, changes: &SeriesChanges) -> QueryResult<Series> {
+        diesel::update(series::table.filter(series::id.eq(id)))
+            .set(changes)
+            .returning(Series::as_select())
+            .get_result(conn)
+    }
+
+    /// Deletes the series. Its posts stay, just no longer in a series.
+    pub fn delete(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        diesel::delete(series::table.filter(series::id.eq(id))).execute(conn)
+    }
+
+    /// The series' posts in any state, in order.
+    pub fn posts(conn: &mut SqliteConnection, id: &str) -> QueryResult<Vec<Posts>> {
+        posts::table
+            .inner_join(series_posts::table)
+            .filter(series_posts::series_id.eq(id))
+            .order(series_posts::position.asc())
+            .select(Posts::as_select())
+            .load(conn)
+    }
+
+    /// Puts `post_id` at the end of the series, taking it out of any other it was in.
+    pub fn add_post(conn: &mut SqliteConnection, id: &str, post_id: &str) -> QueryResult<usize> {
+        Self::remove_post(conn, post_id)?;
+        let last: Option<i32> = series_posts::table
+            .filter(series_posts::series_id.eq(id))
+            .select(max(series_posts::position))
+            .first(conn)?;
+        diesel::insert_into(series_posts::table)
+            .values(&SeriesPosts { post_id: post_id.to_string(), series_id: id.to_string(), position: last.unwrap_or(0) + 1 })
+            .execute(conn)
+    }
+
+    /// Takes `post_id` out of whichever series it's in.
+    pub fn remove_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<usize> {
+        diesel::delete(series_posts::table.filter(series_posts::post_id.eq(post_id))).execute(conn)
+    }
+
+    /// Makes `post_ids`, in that order, the series' posts. Posts that were in another series
+    /// move to this one.
+    pub fn set_posts(conn: &mut SqliteConnection, id: &str, post_ids: &[String]) -> QueryResult<()> {
+        diesel::delete(
+            series_posts::table.filter(series_posts::series_id.eq(id).or(series_posts::post_id.eq_any(post_ids))),
+        )
+        .execute(conn)?;
+        let rows: Vec<SeriesPosts> = post_ids
+            .iter()
+            .zip(1..)
+            .map(|(post_id, position)| SeriesPosts { post_id: post_id.clone(), series_id: id.to_string(), position })
+            .collect();
+        diesel::insert_into(series_posts::table).values(&rows).execute(conn)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use diesel::dsl::count;
+use diesel::prelude::*;
+use crate::db::models::post::POST_STATUS_PUBLISHED;
+use crate::db::models::report::MODERATION_VISIBLE;
+use crate::db::models::tag::{NewPostTag, Tags};
+use crate::db::schema::{post_tags, posts, tags, users};
+use crate::http::pagination::SortDir;
+use crate::utils::escape_like;
+
+impl Tags {
+    pub fn by_post(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Vec<Tags>> {
+        tags::table
+            .inner_join(post_tags::table)
+            .filter(post_tags::post_id.eq(post_id))
+            .order(tags::name.asc())
+            .select(Tags::as_select())
+            .load(conn)
+    }
+
+    pub fn find_or_create(conn: &mut SqliteConnection, name: &str) -> QueryResult<Tags> {
+        let existing = tags::table
+            .filter(tags::name.eq(name))
+            .select(Tags::as_select())
+            .first(conn)
+            .optional()?;
+
+        match existing {
+            Some(tag) => Ok(tag),
+            None => diesel::insert_into(tags::table)
+                .values(&Tags { id: uuid::Uuid::new_v4().to_string(), name: name.to_owned() })
+                .returning(Tags::as_select())
+                .get_result(conn),
+        }
+    }
+
+    /// Replaces the post's tags with the given names, creating tags that don't exist yet.
+    pub fn set_for_post(conn: &mut SqliteConnection, post_id: &str, names: &[String]) -> QueryResult<Vec<Tags>> {
+        diesel::delete(post_tags::table.filter(post_tags::post_id.eq(post_id))).execute(conn)?;
+
+        for name in names {
+            let tag = Tags::find_or_create(conn, name)?;
+            diesel::insert_into(post_tags::table)
+                .values(&NewPostTag {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    post_id: post_id.to_owned(),
+                    tag_id: tag.id,
+                })
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        Tags::by_post(conn, post_id)
+    }
+
+    /// Number of tags on at least one published post, optionally limited to names starting
+    /// with `prefix`.
+    pub fn count_in_use(conn: &mut SqliteConnection, prefix: Option<&str>) -> QueryResult<i64> {
+        tags::table
+            .inner_join(post_tags::table.inner_join(posts::table.inner_join(users::table)))
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .filter(tags::name.like(prefix_pattern(prefix)).escape('\\'))
+            .select(count(tags::id).aggregate_distinct())
+            .get_result(conn)
+    }
+
+    /// A page of tags on published posts by active authors, each with the number of those
+    /// posts carrying it.
+    pub fn page_in_use(
+        conn: &mut SqliteConnection,
+        prefix: Option<&str>,
+        sort: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<(Tags, i64)>> {
+        let query = tags::table
+            .inner_join(post_tags::table.inner_join(posts::table.inner_join(users::table)))
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::moderation_status.eq(MODERATION_VISIBLE))
+            .filter(users::deleted_at.is_null())
+            .filter(tags::name.like(prefix_pattern(prefix)).escape('\\'))
+            .group_by((tags::id, tags::name))
+            .select((Tags::as_select(), count(post_tags::id)))
+            .into_boxed();
+        let query = match (sort, dir.is_asc()) {
+            ("posts", true) => query.order(count(post_tags::id).asc()),
+            ("posts", false) => query.order(count(post_tags::id).desc()),
+            (_, true) => query.order(tags::name.asc()),
+            (_, false) => query.order(tags::name.desc()),
+        };
+
+        query
+            .then_order_by(tags::name.asc())
+            .offset(offset)
+            .limit(limit)
+            .load(conn)
+    }
+}
+
+fn prefix_pattern(prefix: Option<&str>) -> String {
+    format!("{}%", escape_like(prefix.unwrap_or_default()))
+}
@@ -0,0 +1,79 @@
+use diesel::prelude::*;
+use crate::db::models::upload::{Uploads, ALT_TEXT_FAILED, ALT_TEXT_PENDING, ALT_TEXT_SUGGESTED};
+use crate::db::schema::uploads;
+
+impl Uploads {
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<Uploads>> {
+        uploads::table
+            .filter(uploads::id.eq(id))
+            .select(Uploads::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn by_user(conn: &mut SqliteConnection, id: &str, user_id: &str) -> QueryResult<Option<Uploads>> {
+        uploads::table
+            .filter(uploads::id.eq(id))
+            .filter(uploads::user_id.eq(user_id))
+            .select(Uploads::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn create(conn: &mut SqliteConnection, upload: &Uploads) -> QueryResult<Uploads> {
+        diesel::insert_into(uploads::table)
+            .values(upload)
+            .returning(Uploads::as_select())
+            .get_result(conn)
+    }
+
+    /// Uploads waiting for an alt text suggestion, oldest first.
+    pub fn pending_alt_text(conn: &mut SqliteConnection, limit: i64) -> QueryResult<Vec<Uploads>> {
+        uploads::table
+            .filter(uploads::alt_text_status.eq(ALT_TEXT_PENDING))
+            .order(uploads::created_at.asc())
+            .limit(limit)
+            .select(Uploads::as_select())
+            .load(conn)
+    }
+
+    /// Queues the upload for a fresh alt text suggestion.
+    pub fn request_alt_text(conn: &mut SqliteConnection, id: &str) -> QueryResult<Uploads> {
+        diesel::update(uploads::table.filter(uploads::id.eq(id)))
+            .set((
+                uploads::alt_text_status.eq(ALT_TEXT_PENDING),
+                uploads::alt_text_attempts.eq(0),
+            ))
+            .returning(Uploads::as_select())
+            .get_result(conn)
+    }
+
+    pub fn record_alt_text_suggestion(conn: &mut SqliteConnection, id: &str, suggestion: &str) -> QueryResult<usize> {
+        diesel::update(uploads::table.filter(uploads::id.eq(id)))
+            .set((
+                uploads::alt_text_suggestion.eq(suggestion),
+                uploads::alt_text_status.eq(ALT_TEXT_SUGGESTED),
+                uploads::alt_text_attempts.eq(uploads::alt_text_attempts + 1),
+            ))
+            .execute(conn)
+    }
+
+    /// Counts a failed attempt. With `give_up` the upload stops being retried.
+    pub fn record_alt_text_failure(conn: &mut SqliteConnection, id: &str, give_up: bool) -> QueryResult<usize> {
+        let status = if give_up { ALT_TEXT_FAILED } else { ALT_TEXT_PENDING };
+        diesel::update(uploads::table.filter(uploads::id.eq(id)))
+            .set((
+                uploads::alt_text_status.eq(status),
+                uploads::alt_text_attempts.eq(uploads::alt_text_attempts + 1),
+            ))
+            .execute(conn)
+    }
+
+    /// Stores the author's reviewed alt text. `None` clears it.
+    pub fn set_alt_text(conn: &mut SqliteConnection, id: &str, alt_text: Option<&str>) -> QueryResult<Uploads> {
+        diesel::update(uploads::table.filter(uploads::id.eq(id)))
+            .set(uploads::alt_text.eq(alt_text))
+            .returning(Uploads::as_select())
+            .get_result(conn)
+    }
+}
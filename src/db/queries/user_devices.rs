@@ -0,0 +1,33 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::user_device::UserDevices;
+use crate::db::schema::user_devices;
+
+impl UserDevices {
+    pub fn count_for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<i64> {
+        user_devices::table
+            .filter(user_devices::user_id.eq(user_id))
+            .count()
+            .get_result(conn)
+    }
+
+    /// Notes a sign-in from the device, returning whether it hadn't been seen before.
+    pub fn record(conn: &mut SqliteConnection, user_id: &str, label: &str, now: NaiveDateTime) -> QueryResult<bool> {
+        let inserted = diesel::insert_or_ignore_into(user_devices::table)
+            .values(&UserDevices {
+                user_id: user_id.to_string(),
+                label: label.to_string(),
+                first_seen_at: now,
+                last_seen_at: now,
+            })
+            .execute(conn)?;
+        if inserted > 0 {
+            return Ok(true);
+        }
+
+        diesel::update(user_devices::table.find((user_id, label)))
+            .set(user_devices::last_seen_at.eq(now))
+            .execute(conn)?;
+        Ok(false)
+    }
+}
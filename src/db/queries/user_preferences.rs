@@ -0,0 +1,68 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::user_model::UserModel;
+use crate::db::models::user_preferences::UserPreferences;
+use crate::db::schema::{follows, user_preferences, users};
+
+impl UserPreferences {
+    /// The user's preferences, or the defaults if they've never changed any.
+    pub fn for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<UserPreferences> {
+        Ok(user_preferences::table
+            .find(user_id)
+            .select(UserPreferences::as_select())
+            .first(conn)
+            .optional()?
+            .unwrap_or_else(|| UserPreferences::defaults(user_id)))
+    }
+
+    pub fn set_weekly_digest(conn: &mut SqliteConnection, user_id: &str, enabled: bool) -> QueryResult<UserPreferences> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(user_preferences::table)
+            .values(&UserPreferences { weekly_digest: enabled, updated_at: now, ..UserPreferences::defaults(user_id) })
+            .on_conflict(user_preferences::user_id)
+            .do_update()
+            .set((user_preferences::weekly_digest.eq(enabled), user_preferences::updated_at.eq(now)))
+            .returning(UserPreferences::as_returning())
+            .get_result(conn)
+    }
+
+    pub fn mark_digest_sent(conn: &mut SqliteConnection, user_id: &str, now: NaiveDateTime) -> QueryResult<usize> {
+        diesel::insert_into(user_preferences::table)
+            .values(&UserPreferences { digest_sent_at: Some(now), updated_at: now, ..UserPreferences::defaults(user_id) })
+            .on_conflict(user_preferences::user_id)
+            .do_update()
+            .set(user_preferences::digest_sent_at.eq(now))
+            .execute(conn)
+    }
+
+    /// Hides the onboarding checklist.
+    pub fn dismiss_onboarding(conn: &mut SqliteConnection, user_id: &str, now: NaiveDateTime) -> QueryResult<UserPreferences> {
+        diesel::insert_into(user_preferences::table)
+            .values(&UserPreferences { onboarding_dismissed_at: Some(now), updated_at: now, ..UserPreferences::defaults(user_id) })
+            .on_conflict(user_preferences::user_id)
+            .do_update()
+            .set((user_preferences::onboarding_dismissed_at.eq(now), user_preferences::updated_at.eq(now)))
+            .returning(UserPreferences::as_returning())
+            .get_result(conn)
+    }
+
+    /// Active, verified users who follow someone, want the digest and haven't had one since
+    /// `cutoff`, with when they last had one.
+    pub fn due_for_digest(
+        conn: &mut SqliteConnection,
+        cutoff: NaiveDateTime,
+        limit: i64,
+    ) -> QueryResult<Vec<(UserModel, Option<NaiveDateTime>)>> {
+        users::table
+            .left_join(user_preferences::table)
+            .filter(users::deleted_at.is_null())
+            .filter(users::email_verified.eq(true))
+            .filter(users::id.eq_any(follows::table.select(follows::follower_id)))
+            .filter(user_preferences::weekly_digest.is_null().or(user_preferences::weekly_digest.eq(true)))
+            .filter(user_preferences::digest_sent_at.is_null().or(user_preferences::digest_sent_at.lt(cutoff)))
+            .order(users::id.asc())
+            .limit(limit)
+            .select((UserModel::as_select(), user_preferences::digest_sent_at.nullable()))
+            .load(conn)
+    }
+}
@@ -0,0 +1,567 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use crate::db::models::email_verification_token::EmailVerificationTokens;
+use crate::db::models::post::POST_STATUS_PUBLISHED;
+use crate::db::models::refresh_token::RefreshTokens;
+use crate::db::models::user_model::UserModel;
+use crate::db::nocase::NoCaseExpressionMethods;
+use crate::db::schema::{accounts, api_tokens, avatar_sources, comments, export_jobs, posts, refresh_tokens, reset_tokens, uploads, users};
+use crate::db::write::write;
+use crate::http::pagination::SortDir;
+use crate::utils::escape_like;
+
+#[derive(Debug, Clone, Copy)]
+pub enum DuplicateField {
+    Name,
+    Email,
+}
+
+/// Optional narrowing for the admin user list. `deleted` of `None` includes everyone.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UserFilter<'a> {
+    /// Name or email prefix.
+    pub query: Option<&'a str>,
+    pub is_admin: Option<bool>,
+    pub email_verified: Option<bool>,
+    pub deleted: Option<bool>,
+    /// `active` or `suspended`.
+    pub status: Option<&'a str>,
+}
+
+impl UserModel {
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<UserModel>> {
+        users::table
+            .filter(users::id.eq(id))
+            .filter(users::deleted_at.is_null())
+            .select(UserModel::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Like `by_id`, but finds deleted users too.
+    pub fn by_id_with_deleted(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<UserModel>> {
+        users::table
+            .filter(users::id.eq(id))
+            .select(UserModel::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn by_email(conn: &mut SqliteConnection, email: &str) -> QueryResult<Option<UserModel>> {
+        users::table
+            .filter(users::email.eq_nocase(email))
+            .select(UserModel::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn by_name(conn: &mut SqliteConnection, name: &str) -> QueryResult<Option<UserModel>> {
+        users::table
+            .filter(users::name.eq_nocase(name))
+            .filter(users::deleted_at.is_null())
+            .select(UserModel::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// The user's id at an OAuth `provider` they've linked, such as their numeric GitHub id.
+    pub fn linked_account_id(conn: &mut SqliteConnection, id: &str, provider: &str) -> QueryResult<Option<String>> {
+        accounts::table
+            .filter(accounts::user_id.eq(id))
+            .filter(accounts::provider.eq(provider))
+            .select(accounts::provider_account_id)
+            .first(conn)
+            .optional()
+    }
+
+    /// Users, including deleted ones, whose id matches `term` or whose name or email starts with it.
+    pub fn search(conn: &mut SqliteConnection, term: &str, limit: i64) -> QueryResult<Vec<UserModel>> {
+        let pattern = format!("{}%", escape_like(term));
+        users::table
+            .filter(
+                users::id.eq(term)
+                    .or(users::email.like(&pattern).escape('\\'))
+                    .or(users::name.like(&pattern).escape('\\')),
+            )
+            .order(users::created_at.desc())
+            .limit(limit)
+            .select(UserModel::as_select())
+            .load(conn)
+    }
+
+    pub fn count_filtered(conn: &mut SqliteConnection, filter: &UserFilter) -> QueryResult<i64> {
+        filtered(filter).count().get_result(conn)
+    }
+
+    /// A page of users, including deleted ones unless `filter` says otherwise, ordered by
+    /// `sort` (`created_at`, `name` or `email`).
+    pub fn page_filtered(
+        conn: &mut SqliteConnection,
+        filter: &UserFilter,
+        sort: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<UserModel>> {
+        let query = filtered(filter);
+        let query = match (sort, dir.is_asc()) {
+            ("name", true) => query.order(users::name.asc()),
+            ("name", false) => query.order(users::name.desc()),
+            ("email", true) => query.order(users::email.asc()),
+            ("email", false) => query.order(users::email.desc()),
+            (_, true) => query.order(users::created_at.asc()),
+            (_, false) => query.order(users::created_at.desc()),
+        };
+
+        query
+            .then_order_by(users::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select(UserModel::as_select())
+            .load(conn)
+    }
+
+    /// Groups of users whose `field` values collide once ASCII case is ignored (the folding
+    /// `COLLATE NOCASE` applies), keyed by the folded value.
+    pub fn case_duplicates(conn: &mut SqliteConnection, field: DuplicateField) -> QueryResult<Vec<(String, Vec<UserModel>)>> {
+        let all = users::table
+            .select(UserModel::as_select())
+            .order(users::created_at.asc())
+            .load(conn)?;
+
+        let mut groups: BTreeMap<String, Vec<UserModel>> = BTreeMap::new();
+        for user in all {
+            let key = match field {
+                DuplicateField::Name => user.name.to_ascii_lowercase(),
+                DuplicateField::Email => user.email.to_ascii_lowercase(),
+            };
+            groups.entry(key).or_default().push(user);
+        }
+
+        Ok(groups.into_iter().filter(|(_, users)| users.len() > 1).collect())
+    }
+
+    pub fn update_email(conn: &mut SqliteConnection, id: &str, email: &str) -> QueryResult<UserModel> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((
+                users::email.eq(email),
+                users::email_verified.eq(false),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(UserModel::as_returning())
+            .get_result(conn)
+    }
+
+    pub fn update_password(conn: &mut SqliteConnection, id: &str, password_hash: &str) -> QueryResult<usize> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((
+                users::password.eq(password_hash),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+    }
+
+    /// Invalidates every access token the user holds, returning the new version.
+    pub fn bump_token_version(conn: &mut SqliteConnection, id: &str) -> QueryResult<i32> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set(users::token_version.eq(users::token_version + 1))
+            .returning(users::token_version)
+            .get_result(conn)
+    }
+
+    pub fn update_preferences(
+        conn: &mut SqliteConnection,
+        id: &str,
+        canonicalize_links: bool,
+        timezone: &str,
+        quiet_hours_start: Option<i32>,
+        quiet_hours_end: Option<i32>,
+        locale: Option<&str>,
+    ) -> QueryResult<UserModel> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((
+                users::canonicalize_links.eq(canonicalize_links),
+                users::timezone.eq(timezone),
+                users::quiet_hours_start.eq(quiet_hours_start),
+                users::quiet_hours_end.eq(quiet_hours_end),
+                users::locale.eq(locale),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(UserModel::as_returning())
+            .get_result(conn)
+    }
+
+    pub fn update_profile(
+        conn: &mut SqliteConnection,
+        id: &str,
+        bio: &str,
+        website: Option<&str>,
+        location: Option<&str>,
+        profile_private: bool,
+        show_email: bool,
+    ) -> QueryResult<UserModel> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((
+                users::bio.eq(bio),
+                users::website.eq(website),
+                users::location.eq(location),
+                users::profile_private.eq(profile_private),
+                users::show_email.eq(show_email),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(UserModel::as_returning())
+            .get_result(conn)
+    }
+
+    pub fn set_avatar_url(conn: &mut SqliteConnection, id: &str, avatar_url: Option<&str>) -> QueryResult<UserModel> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((users::avatar_url.eq(avatar_url), users::updated_at.eq(Utc::now().naive_utc())))
+            .returning(UserModel::as_returning())
+            .get_result(conn)
+    }
+
+    /// Admin kill-switch for a blog's custom CSS and head snippets.
+    pub fn set_blog_styles_disabled(conn: &mut SqliteConnection, id: &str, disabled: bool) -> QueryResult<UserModel> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((
+                users::blog_styles_disabled.eq(disabled),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(UserModel::as_returning())
+            .get_result(conn)
+    }
+
+    pub fn mark_email_verified(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((
+                users::email_verified.eq(true),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+    }
+
+    /// Suspends the user, or with `USER_STATUS_ACTIVE` lifts a suspension.
+    pub fn set_status(conn: &mut SqliteConnection, id: &str, status: &str) -> QueryResult<UserModel> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((users::status.eq(status), users::updated_at.eq(Utc::now().naive_utc())))
+            .returning(UserModel::as_returning())
+            .get_result(conn)
+    }
+
+    /// Where the user's new comments start out; see [`UserModel::comment_moderation`].
+    pub fn set_comment_moderation(conn: &mut SqliteConnection, id: &str, status: &str) -> QueryResult<UserModel> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((users::comment_moderation.eq(status), users::updated_at.eq(Utc::now().naive_utc())))
+            .returning(UserModel::as_returning())
+            .get_result(conn)
+    }
+
+    /// Storage keys of everything the user has uploaded or exported, which purging leaves
+    /// behind in storage unless they're deleted too.
+    pub fn stored_files(conn: &mut SqliteConnection, id: &str) -> QueryResult<Vec<String>> {
+        let mut keys: Vec<String> = uploads::table
+            .filter(uploads::user_id.eq(id))
+            .select(uploads::storage_key)
+            .load(conn)?;
+        let exports: Vec<Option<String>> = export_jobs::table
+            .filter(export_jobs::user_id.eq(id))
+            .select(export_jobs::storage_key)
+            .load(conn)?;
+        keys.extend(exports.into_iter().flatten());
+        Ok(keys)
+    }
+
+    pub fn set_admin(conn: &mut SqliteConnection, id: &str, is_admin: bool) -> QueryResult<usize> {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((
+                users::is_admin.eq(is_admin),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+    }
+
+    /// Password reset links that can no longer be used.
+    pub fn delete_expired_reset_tokens(conn: &mut SqliteConnection, now: NaiveDateTime) -> QueryResult<usize> {
+        diesel::delete(reset_tokens::table.filter(reset_tokens::expires_at.lt(now))).execute(conn)
+    }
+
+    /// Removes every credential that can act as the user: sessions, API tokens, linked OAuth
+    /// accounts and outstanding verification/reset tokens. New tables holding per-user
+    /// credentials or personal data belong here as well as behind an `on delete cascade`.
+    pub fn delete_dependents(conn: &mut SqliteConnection, id: &str) -> QueryResult<()> {
+        RefreshTokens::delete_by_user(conn, id)?;
+        EmailVerificationTokens::delete_by_user(conn, id)?;
+        diesel::delete(reset_tokens::table.filter(reset_tokens::user_id.eq(id))).execute(conn)?;
+        diesel::delete(api_tokens::table.filter(api_tokens::user_id.eq(id))).execute(conn)?;
+        diesel::delete(accounts::table.filter(accounts::user_id.eq(id))).execute(conn)?;
+        diesel::delete(avatar_sources::table.filter(avatar_sources::user_id.eq(id))).execute(conn)?;
+        Ok(())
+    }
+
+    /// Marks the user deleted and revokes their credentials. Authored content is kept until the
+    /// account is purged.
+    pub fn soft_delete(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        write(conn, |conn| {
+            Self::delete_dependents(conn, id)?;
+
+            let now = Utc::now().naive_utc();
+            diesel::update(users::table.filter(users::id.eq(id)))
+                .set((users::deleted_at.eq(now), users::updated_at.eq(now)))
+                .execute(conn)
+        })
+    }
+
+    /// Permanently removes the user. Dependents are cleared explicitly before the row is deleted
+    /// so the result doesn't hinge on `PRAGMA foreign_keys` being enabled for this connection;
+    /// posts, versions and tags then go through the schema's cascades.
+    pub fn purge(conn: &mut SqliteConnection, id: &str) -> QueryResult<usize> {
+        write(conn, |conn| {
+            Self::delete_dependents(conn, id)?;
+            diesel::delete(users::table.filter(users::id.eq(id))).execute(conn)
+        })
+    }
+
+    pub fn count_active(conn: &mut SqliteConnection) -> QueryResult<i64> {
+        users::table
+            .filter(users::deleted_at.is_null())
+            .count()
+            .get_result(conn)
+    }
+
+    /// Users who signed in, published or commented since `since`. Sign-ins only count while
+    /// refresh tokens are kept in the database.
+    pub fn count_active_since(conn: &mut SqliteConnection, since: NaiveDateTime) -> QueryResult<i64> {
+        let signed_in = refresh_tokens::table
+            .filter(refresh_tokens::created_at.ge(since))
+            .select(refresh_tokens::user_id);
+        let published = posts::table
+            .filter(posts::status.eq(POST_STATUS_PUBLISHED))
+            .filter(posts::published_at.ge(since))
+            .select(posts::user_id);
+        let commented = comments::table
+            .filter(comments::created_at.ge(since))
+            .select(comments::user_id);
+
+        users::table
+            .filter(users::deleted_at.is_null())
+            .filter(
+                users::id.eq_any(signed_in)
+                    .or(users::id.eq_any(published))
+                    .or(users::id.eq_any(commented)),
+            )
+            .count()
+            .get_result(conn)
+    }
+}
+
+fn filtered<'a>(filter: &UserFilter) -> users::BoxedQuery<'a, Sqlite> {
+    let mut query = users::table.into_boxed();
+    if let Some(term) = filter.query {
+        let pattern = format!("{}%", escape_like(term));
+        query = query.filter(users::email.like(pattern.clone()).escape('\\').or(users::name.like(pattern).escape('\\')));
+    }
+    if let Some(is_admin) = filter.is_admin {
+        query = query.filter(users::is_admin.eq(is_admin));
+    }
+    if let Some(email_verified) = filter.email_verified {
+        query = query.filter(users::email_verified.eq(email_verified));
+    }
+    if let Some(status) = filter.status {
+        query = query.filter(users::status.eq(status.to_string()));
+    }
+    match filter.deleted {
+        Some(true) => query = query.filter(users::deleted_at.is_not_null()),
+        Some(false) => query = query.filter(users::deleted_at.is_null()),
+        None => {}
+    }
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use diesel::prelude::*;
+    use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+    use diesel_migrations::MigrationHarness;
+
+    use crate::db::connection::SqliteCustomizer;
+    use crate::db::models::api_token::{ApiTokens, NewApiToken};
+    use crate::db::models::email_verification_token::EmailVerificationTokens;
+    use crate::db::models::post::{NewPost, Posts};
+    use crate::db::models::post_version::PostVersions;
+    use crate::db::models::refresh_token::RefreshTokens;
+    use crate::db::models::tag::Tags;
+    use crate::db::models::user_model::{NewUser, UserModel};
+    use crate::db::schema::{
+        accounts, api_tokens, email_verification_tokens, post_tags, post_versions, posts, refresh_tokens,
+        reset_tokens, users,
+    };
+
+    type Conn = PooledConnection<ConnectionManager<SqliteConnection>>;
+
+    fn test_conn() -> Conn {
+        let pool = Pool::builder()
+            .max_size(1)
+            .connection_customizer(Box::new(SqliteCustomizer::default()))
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("failed to build test pool");
+
+        let mut conn = pool.get().expect("failed to get test connection");
+        conn.run_pending_migrations(crate::MIGRATIONS).expect("failed to run migrations");
+        conn
+    }
+
+    fn seed_user(conn: &mut SqliteConnection, name: &str) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Utc::now().naive_utc();
+
+        diesel::insert_into(users::table)
+            .values(&NewUser {
+                id: id.clone(),
+                name: name.to_string(),
+                email: format!("{}@example.com", name),
+                password: "hash".to_string(),
+                email_verified: true,
+                created_at: now,
+            })
+            .execute(conn)
+            .unwrap();
+
+        RefreshTokens::create(conn, &format!("refresh-{}", name), &id, chrono::Duration::days(7), true, Some("127.0.0.1"), None).unwrap();
+        EmailVerificationTokens::create(conn, &format!("verify-{}", name), &id, 24).unwrap();
+
+        diesel::insert_into(reset_tokens::table)
+            .values((
+                reset_tokens::id.eq(uuid::Uuid::new_v4().to_string()),
+                reset_tokens::token.eq(format!("reset-{}", name)),
+                reset_tokens::user_id.eq(&id),
+                reset_tokens::expires_at.eq(now + Duration::hours(1)),
+            ))
+            .execute(conn)
+            .unwrap();
+
+        diesel::insert_into(accounts::table)
+            .values((
+                accounts::id.eq(uuid::Uuid::new_v4().to_string()),
+                accounts::user_id.eq(&id),
+                accounts::type_.eq("oauth"),
+                accounts::provider.eq("github"),
+                accounts::provider_account_id.eq(format!("gh-{}", name)),
+                accounts::refresh_token.eq(""),
+                accounts::access_token.eq("gho_test"),
+                accounts::expires_at.eq(now + Duration::hours(8)),
+            ))
+            .execute(conn)
+            .unwrap();
+
+        ApiTokens::create(conn, &NewApiToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: id.clone(),
+            name: "ci".to_string(),
+            token_prefix: "tsumi_abc".to_string(),
+            token_hash: format!("hash-{}", name),
+            scopes: "posts:read".to_string(),
+            expires_at: None,
+            created_at: now,
+        })
+        .unwrap();
+
+        let post = Posts::create(conn, &NewPost {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: id.clone(),
+            title: "Hello".to_string(),
+            description: String::new(),
+            slug: "hello".to_string(),
+            content: "# Hello".to_string(),
+            created_at: now,
+            updated_at: now,
+            status: "published".to_string(),
+            published_at: Some(now),
+            word_count: None,
+            og_image_url: None,
+            cover_upload_id: None,
+            reading_minutes: None,
+        })
+        .unwrap();
+        PostVersions::record(conn, &post, &id, "Initial version").unwrap();
+        Tags::set_for_post(conn, &post.id, &["rust".to_string()]).unwrap();
+
+        id
+    }
+
+    fn credential_count(conn: &mut SqliteConnection, id: &str) -> i64 {
+        let refresh: i64 = refresh_tokens::table.filter(refresh_tokens::user_id.eq(id)).count().get_result(conn).unwrap();
+        let verify: i64 = email_verification_tokens::table
+            .filter(email_verification_tokens::user_id.eq(id))
+            .count()
+            .get_result(conn)
+            .unwrap();
+        let reset: i64 = reset_tokens::table.filter(reset_tokens::user_id.eq(id)).count().get_result(conn).unwrap();
+        let api: i64 = api_tokens::table.filter(api_tokens::user_id.eq(id)).count().get_result(conn).unwrap();
+        let linked: i64 = accounts::table.filter(accounts::user_id.eq(id)).count().get_result(conn).unwrap();
+        refresh + verify + reset + api + linked
+    }
+
+    fn content_count(conn: &mut SqliteConnection, id: &str) -> i64 {
+        let post_count: i64 = posts::table.filter(posts::user_id.eq(id)).count().get_result(conn).unwrap();
+        let version_count: i64 = post_versions::table.filter(post_versions::user_id.eq(id)).count().get_result(conn).unwrap();
+        let tag_count: i64 = post_tags::table
+            .inner_join(posts::table)
+            .filter(posts::user_id.eq(id))
+            .count()
+            .get_result(conn)
+            .unwrap();
+        post_count + version_count + tag_count
+    }
+
+    #[test]
+    fn soft_delete_revokes_credentials_and_keeps_content() {
+        let mut conn = test_conn();
+        let id = seed_user(&mut conn, "alice");
+
+        UserModel::soft_delete(&mut conn, &id).unwrap();
+
+        assert_eq!(credential_count(&mut conn, &id), 0);
+        assert_eq!(content_count(&mut conn, &id), 3);
+        assert!(UserModel::by_id(&mut conn, &id).unwrap().is_none());
+    }
+
+    #[test]
+    fn purge_removes_everything_owned_by_the_user() {
+        let mut conn = test_conn();
+        let id = seed_user(&mut conn, "alice");
+
+        assert_eq!(UserModel::purge(&mut conn, &id).unwrap(), 1);
+
+        assert_eq!(credential_count(&mut conn, &id), 0);
+        assert_eq!(content_count(&mut conn, &id), 0);
+        let remaining: i64 = users::table.filter(users::id.eq(&id)).count().get_result(&mut conn).unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn purge_leaves_other_users_alone() {
+        let mut conn = test_conn();
+        let alice = seed_user(&mut conn, "alice");
+        let bob = seed_user(&mut conn, "bob");
+
+        UserModel::purge(&mut conn, &alice).unwrap();
+
+        assert_eq!(credential_count(&mut conn, &bob), 5);
+        assert_eq!(content_count(&mut conn, &bob), 3);
+    }
+
+    #[test]
+    fn pool_connections_enforce_foreign_key_cascades() {
+        let mut conn = test_conn();
+        let id = seed_user(&mut conn, "alice");
+
+        diesel::delete(users::table.filter(users::id.eq(&id))).execute(&mut conn).unwrap();
+
+        assert_eq!(credential_count(&mut conn, &id), 0);
+        assert_eq!(content_count(&mut conn, &id), 0);
+    }
+}
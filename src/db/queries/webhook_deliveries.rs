@@ -0,0 +1,115 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use crate::db::models::webhook_delivery::{
+    WebhookDeliveries, WEBHOOK_DELIVERY_DELIVERED, WEBHOOK_DELIVERY_FAILED, WEBHOOK_DELIVERY_PENDING,
+};
+use crate::db::schema::webhook_deliveries;
+use crate::http::pagination::SortDir;
+
+impl WebhookDeliveries {
+    pub fn create_many(conn: &mut SqliteConnection, deliveries: &[WebhookDeliveries]) -> QueryResult<usize> {
+        diesel::insert_into(webhook_deliveries::table)
+            .values(deliveries)
+            .execute(conn)
+    }
+
+    /// Pending deliveries whose next attempt is due, oldest first.
+    pub fn due(conn: &mut SqliteConnection, now: NaiveDateTime, limit: i64) -> QueryResult<Vec<WebhookDeliveries>> {
+        webhook_deliveries::table
+            .filter(webhook_deliveries::status.eq(WEBHOOK_DELIVERY_PENDING))
+            .filter(webhook_deliveries::next_attempt_at.le(now))
+            .order(webhook_deliveries::next_attempt_at.asc())
+            .limit(limit)
+            .select(WebhookDeliveries::as_select())
+            .load(conn)
+    }
+
+    pub fn mark_delivered(
+        conn: &mut SqliteConnection,
+        id: &str,
+        attempts: i32,
+        response_status: i32,
+        now: NaiveDateTime,
+    ) -> QueryResult<usize> {
+        diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq(id)))
+            .set((
+                webhook_deliveries::status.eq(WEBHOOK_DELIVERY_DELIVERED),
+                webhook_deliveries::attempts.eq(attempts),
+                webhook_deliveries::response_status.eq(Some(response_status)),
+                webhook_deliveries::last_error.eq(None::<String>),
+                webhook_deliveries::delivered_at.eq(Some(now)),
+            ))
+            .execute(conn)
+    }
+
+    /// Records a failed attempt. With `retry_at` the delivery stays pending until then;
+    /// without, it has run out of attempts and is marked failed.
+    pub fn mark_attempt_failed(
+        conn: &mut SqliteConnection,
+        id: &str,
+        attempts: i32,
+        response_status: Option<i32>,
+        error: &str,
+        retry_at: Option<NaiveDateTime>,
+    ) -> QueryResult<usize> {
+        let target = webhook_deliveries::table.filter(webhook_deliveries::id.eq(id));
+        let attempt = (
+            webhook_deliveries::attempts.eq(attempts),
+            webhook_deliveries::response_status.eq(response_status),
+            webhook_deliveries::last_error.eq(Some(error)),
+        );
+        match retry_at {
+            Some(retry_at) => diesel::update(target)
+                .set((attempt, webhook_deliveries::next_attempt_at.eq(retry_at)))
+                .execute(conn),
+            None => diesel::update(target)
+                .set((attempt, webhook_deliveries::status.eq(WEBHOOK_DELIVERY_FAILED)))
+                .execute(conn),
+        }
+    }
+
+    pub fn count_for_webhook(conn: &mut SqliteConnection, webhook_id: &str) -> QueryResult<i64> {
+        webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+            .count()
+            .get_result(conn)
+    }
+
+    /// A page of the webhook's deliveries, newest first unless `dir` says otherwise.
+    pub fn page_for_webhook(
+        conn: &mut SqliteConnection,
+        webhook_id: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> QueryResult<Vec<WebhookDeliveries>> {
+        let query = webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+            .into_boxed();
+        let query = if dir.is_asc() {
+            query.order(webhook_deliveries::created_at.asc())
+        } else {
+            query.order(webhook_deliveries::created_at.desc())
+        };
+
+        query
+            .then_order_by(webhook_deliveries::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select(WebhookDeliveries::as_select())
+            .load(conn)
+    }
+
+    /// Deletes up to `limit` of the oldest delivered or failed deliveries created before
+    /// `cutoff`. Pending ones are kept however old they are.
+    pub fn delete_finished_before(conn: &mut SqliteConnection, cutoff: NaiveDateTime, limit: i64) -> QueryResult<usize> {
+        let expired = webhook_deliveries::table
+            .filter(webhook_deliveries::status.ne(WEBHOOK_DELIVERY_PENDING))
+            .filter(webhook_deliveries::created_at.lt(cutoff))
+            .order(webhook_deliveries::created_at.asc())
+            .select(webhook_deliveries::id)
+            .limit(limit)
+            .load::<String>(conn)?;
+        diesel::delete(webhook_deliveries::table.filter(webhook_deliveries::id.eq_any(&expired))).execute(conn)
+    }
+}
@@ -0,0 +1,55 @@
+use diesel::prelude::*;
+use crate::db::models::webhook::Webhooks;
+use crate::db::schema::webhooks;
+
+impl Webhooks {
+    pub fn create(conn: &mut SqliteConnection, webhook: &Webhooks) -> QueryResult<usize> {
+        diesel::insert_into(webhooks::table)
+            .values(webhook)
+            .execute(conn)
+    }
+
+    pub fn for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<Vec<Webhooks>> {
+        webhooks::table
+            .filter(webhooks::user_id.eq(user_id))
+            .order(webhooks::created_at.asc())
+            .select(Webhooks::as_select())
+            .load(conn)
+    }
+
+    pub fn count_for_user(conn: &mut SqliteConnection, user_id: &str) -> QueryResult<i64> {
+        webhooks::table
+            .filter(webhooks::user_id.eq(user_id))
+            .count()
+            .get_result(conn)
+    }
+
+    pub fn by_id_for_user(conn: &mut SqliteConnection, id: &str, user_id: &str) -> QueryResult<Option<Webhooks>> {
+        webhooks::table
+            .filter(webhooks::id.eq(id))
+            .filter(webhooks::user_id.eq(user_id))
+            .select(Webhooks::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn by_id(conn: &mut SqliteConnection, id: &str) -> QueryResult<Option<Webhooks>> {
+        webhooks::table
+            .find(id)
+            .select(Webhooks::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Removes one of the user's webhooks along with its delivery log, returning whether
+    /// there was one.
+    pub fn delete_for_user(conn: &mut SqliteConnection, id: &str, user_id: &str) -> QueryResult<bool> {
+        diesel::delete(
+            webhooks::table
+                .filter(webhooks::id.eq(id))
+                .filter(webhooks::user_id.eq(user_id)),
+        )
+        .execute(conn)
+        .map(|deleted| deleted > 0)
+    }
+}
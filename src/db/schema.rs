@@ -17,6 +17,115 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    api_tokens (id) {
+        id -> Text,
+        user_id -> Text,
+        name -> Text,
+        token_prefix -> Text,
+        token_hash -> Text,
+        scopes -> Text,
+        last_used_at -> Nullable<Timestamp>,
+        expires_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    audit_logs (id) {
+        id -> Text,
+        event -> Text,
+        user_id -> Nullable<Text>,
+        actor_id -> Nullable<Text>,
+        detail -> Nullable<Text>,
+        ip_address -> Nullable<Text>,
+        user_agent -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    avatar_sources (id) {
+        id -> Text,
+        user_id -> Text,
+        upstream_url -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    backfill_jobs (id) {
+        id -> Text,
+        kind -> Text,
+        status -> Text,
+        cursor -> Nullable<Text>,
+        total -> Integer,
+        processed -> Integer,
+        error -> Nullable<Text>,
+        created_by -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    blog_styles (id) {
+        id -> Text,
+        user_id -> Text,
+        version -> Integer,
+        css -> Text,
+        head_html -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    comments (id) {
+        id -> Text,
+        post_id -> Text,
+        user_id -> Text,
+        parent_id -> Nullable<Text>,
+        root_id -> Nullable<Text>,
+        body -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        deleted_by -> Nullable<Text>,
+        moderation_status -> Text,
+    }
+}
+
+diesel::table! {
+    email_outbox (id) {
+        id -> Text,
+        recipient -> Text,
+        subject -> Text,
+        text_body -> Text,
+        html_body -> Nullable<Text>,
+        priority -> Text,
+        status -> Text,
+        attempts -> Integer,
+        next_attempt_at -> Timestamp,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+        sent_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    email_suppressions (id) {
+        id -> Text,
+        email -> Text,
+        reason -> Text,
+        provider -> Text,
+        details -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        reactivated_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     email_verification_tokens (id) {
         id -> Text,
@@ -27,6 +136,216 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    export_jobs (id) {
+        id -> Text,
+        user_id -> Text,
+        status -> Text,
+        storage_key -> Nullable<Text>,
+        size_bytes -> Nullable<BigInt>,
+        error -> Nullable<Text>,
+        created_at -> Timestamp,
+        started_at -> Nullable<Timestamp>,
+        finished_at -> Nullable<Timestamp>,
+        expires_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    feature_flags (name) {
+        name -> Text,
+        enabled -> Bool,
+        updated_by -> Nullable<Text>,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    follows (follower_id, followee_id) {
+        follower_id -> Text,
+        followee_id -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    idempotency_keys (id) {
+        id -> Text,
+        scope -> Text,
+        idempotency_key -> Text,
+        request_hash -> Text,
+        status_code -> Nullable<Integer>,
+        content_type -> Nullable<Text>,
+        body -> Nullable<Binary>,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    job_locks (name) {
+        name -> Text,
+        holder -> Text,
+        acquired_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    jobs (name) {
+        name -> Text,
+        next_run_at -> Nullable<Timestamp>,
+        last_started_at -> Nullable<Timestamp>,
+        last_finished_at -> Nullable<Timestamp>,
+        last_error -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    notification_deliveries (id) {
+        id -> Text,
+        user_id -> Text,
+        channel -> Text,
+        coalesce_key -> Text,
+        kind -> Text,
+        post_id -> Text,
+        actors -> Text,
+        event_count -> Integer,
+        deliver_after -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    notifications (id) {
+        id -> Text,
+        user_id -> Text,
+        kind -> Text,
+        actor_id -> Nullable<Text>,
+        post_id -> Nullable<Text>,
+        comment_id -> Nullable<Text>,
+        read_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    onboarding_steps (user_id, step) {
+        user_id -> Text,
+        step -> Text,
+        completed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    page_versions (id) {
+        id -> Text,
+        page_id -> Text,
+        user_id -> Nullable<Text>,
+        title -> Text,
+        content -> Text,
+        description -> Text,
+        commit_hash -> Text,
+        commit_message -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    pages (id) {
+        id -> Text,
+        slug -> Text,
+        title -> Text,
+        description -> Text,
+        content -> Text,
+        published_at -> Nullable<Timestamp>,
+        created_by -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    post_collaborators (post_id, user_id) {
+        post_id -> Text,
+        user_id -> Text,
+        role -> Text,
+        invited_by -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    post_doc_updates (id) {
+        id -> Text,
+        post_id -> Text,
+        user_id -> Nullable<Text>,
+        data -> Binary,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    post_docs (post_id) {
+        post_id -> Text,
+        state -> Binary,
+        compacted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    post_fingerprints (post_id) {
+        post_id -> Text,
+        user_id -> Text,
+        simhash -> BigInt,
+        band0 -> Integer,
+        band1 -> Integer,
+        band2 -> Integer,
+        band3 -> Integer,
+        duplicate_of -> Nullable<Text>,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    post_locks (post_id) {
+        post_id -> Text,
+        user_id -> Text,
+        session_id -> Text,
+        acquired_at -> Timestamp,
+        expires_at -> Timestamp,
+        takeover_session_id -> Nullable<Text>,
+        takeover_user_id -> Nullable<Text>,
+        takeover_requested_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    post_reactions (post_id, user_id) {
+        post_id -> Text,
+        user_id -> Text,
+        kind -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    post_search_index (post_id) {
+        post_id -> Text,
+        terms -> Text,
+        indexed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    post_slugs (user_id, slug) {
+        user_id -> Text,
+        slug -> Text,
+        post_id -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     post_tags (id) {
         id -> Text,
@@ -49,6 +368,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    post_views (post_id, day) {
+        post_id -> Text,
+        day -> Date,
+        views -> Integer,
+    }
+}
+
 diesel::table! {
     posts (id) {
         id -> Text,
@@ -57,9 +384,28 @@ diesel::table! {
         description -> Text,
         slug -> Text,
         content -> Text,
-        is_published -> Bool,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        status -> Text,
+        published_at -> Nullable<Timestamp>,
+        word_count -> Nullable<Integer>,
+        og_image_url -> Nullable<Text>,
+        cover_upload_id -> Nullable<Text>,
+        reading_minutes -> Nullable<Integer>,
+        moderation_status -> Text,
+    }
+}
+
+diesel::table! {
+    push_subscriptions (id) {
+        id -> Text,
+        user_id -> Text,
+        endpoint -> Text,
+        p256dh -> Text,
+        auth -> Text,
+        expires_at -> Nullable<Timestamp>,
+        user_agent -> Nullable<Text>,
+        created_at -> Timestamp,
     }
 }
 
@@ -70,6 +416,27 @@ diesel::table! {
         expires_at -> Timestamp,
         user_id -> Text,
         created_at -> Timestamp,
+        ip_address -> Nullable<Text>,
+        user_agent -> Nullable<Text>,
+        family_id -> Text,
+        replaced_by -> Nullable<Text>,
+        device -> Nullable<Text>,
+        persistent -> Bool,
+    }
+}
+
+diesel::table! {
+    reports (id) {
+        id -> Text,
+        reporter_id -> Nullable<Text>,
+        post_id -> Text,
+        comment_id -> Nullable<Text>,
+        reason -> Text,
+        details -> Text,
+        status -> Text,
+        reviewed_by -> Nullable<Text>,
+        reviewed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
     }
 }
 
@@ -83,6 +450,26 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    series (id) {
+        id -> Text,
+        user_id -> Text,
+        title -> Text,
+        slug -> Text,
+        description -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    series_posts (post_id) {
+        post_id -> Text,
+        series_id -> Text,
+        position -> Integer,
+    }
+}
+
 diesel::table! {
     tags (id) {
         id -> Text,
@@ -90,6 +477,42 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    uploads (id) {
+        id -> Text,
+        user_id -> Text,
+        storage_key -> Text,
+        content_type -> Text,
+        byte_size -> Integer,
+        sha256 -> Text,
+        original_filename -> Nullable<Text>,
+        created_at -> Timestamp,
+        alt_text -> Nullable<Text>,
+        alt_text_suggestion -> Nullable<Text>,
+        alt_text_status -> Nullable<Text>,
+        alt_text_attempts -> Integer,
+    }
+}
+
+diesel::table! {
+    user_preferences (user_id) {
+        user_id -> Text,
+        weekly_digest -> Bool,
+        digest_sent_at -> Nullable<Timestamp>,
+        updated_at -> Timestamp,
+        onboarding_dismissed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    user_devices (user_id, label) {
+        user_id -> Text,
+        label -> Text,
+        first_seen_at -> Timestamp,
+        last_seen_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Text,
@@ -100,27 +523,146 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         deleted_at -> Nullable<Timestamp>,
+        is_admin -> Bool,
+        canonicalize_links -> Bool,
+        blog_styles_disabled -> Bool,
+        timezone -> Text,
+        quiet_hours_start -> Nullable<Integer>,
+        quiet_hours_end -> Nullable<Integer>,
+        token_version -> Integer,
+        locale -> Nullable<Text>,
+        avatar_url -> Nullable<Text>,
+        bio -> Text,
+        website -> Nullable<Text>,
+        location -> Nullable<Text>,
+        profile_private -> Bool,
+        show_email -> Bool,
+        status -> Text,
+        comment_moderation -> Text,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Text,
+        webhook_id -> Text,
+        event -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Integer,
+        next_attempt_at -> Timestamp,
+        response_status -> Nullable<Integer>,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    webhooks (id) {
+        id -> Text,
+        user_id -> Text,
+        url -> Text,
+        secret -> Text,
+        events -> Text,
+        created_at -> Timestamp,
     }
 }
 
 diesel::joinable!(accounts -> users (user_id));
+diesel::joinable!(api_tokens -> users (user_id));
+diesel::joinable!(avatar_sources -> users (user_id));
+diesel::joinable!(backfill_jobs -> users (created_by));
+diesel::joinable!(blog_styles -> users (user_id));
+diesel::joinable!(comments -> posts (post_id));
+diesel::joinable!(comments -> users (user_id));
 diesel::joinable!(email_verification_tokens -> users (user_id));
+diesel::joinable!(export_jobs -> users (user_id));
+diesel::joinable!(feature_flags -> users (updated_by));
+diesel::joinable!(notification_deliveries -> posts (post_id));
+diesel::joinable!(notification_deliveries -> users (user_id));
+diesel::joinable!(notifications -> comments (comment_id));
+diesel::joinable!(notifications -> posts (post_id));
+diesel::joinable!(onboarding_steps -> users (user_id));
+diesel::joinable!(page_versions -> pages (page_id));
+diesel::joinable!(page_versions -> users (user_id));
+diesel::joinable!(pages -> users (created_by));
+diesel::joinable!(post_doc_updates -> posts (post_id));
+diesel::joinable!(post_doc_updates -> users (user_id));
+diesel::joinable!(post_collaborators -> posts (post_id));
+diesel::joinable!(post_docs -> posts (post_id));
+diesel::joinable!(post_fingerprints -> users (user_id));
+diesel::joinable!(post_locks -> posts (post_id));
+diesel::joinable!(post_reactions -> posts (post_id));
+diesel::joinable!(post_reactions -> users (user_id));
+diesel::joinable!(post_search_index -> posts (post_id));
+diesel::joinable!(post_slugs -> posts (post_id));
+diesel::joinable!(post_slugs -> users (user_id));
 diesel::joinable!(post_tags -> posts (post_id));
 diesel::joinable!(post_tags -> tags (tag_id));
 diesel::joinable!(post_versions -> posts (post_id));
 diesel::joinable!(post_versions -> users (user_id));
+diesel::joinable!(post_views -> posts (post_id));
 diesel::joinable!(posts -> users (user_id));
+diesel::joinable!(push_subscriptions -> users (user_id));
 diesel::joinable!(refresh_tokens -> users (user_id));
+diesel::joinable!(reports -> comments (comment_id));
+diesel::joinable!(reports -> posts (post_id));
+diesel::joinable!(series -> users (user_id));
+diesel::joinable!(series_posts -> posts (post_id));
+diesel::joinable!(series_posts -> series (series_id));
 diesel::joinable!(reset_tokens -> users (user_id));
+diesel::joinable!(uploads -> users (user_id));
+diesel::joinable!(user_devices -> users (user_id));
+diesel::joinable!(user_preferences -> users (user_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhooks -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     accounts,
+    api_tokens,
+    audit_logs,
+    avatar_sources,
+    backfill_jobs,
+    blog_styles,
+    comments,
+    email_outbox,
+    email_suppressions,
     email_verification_tokens,
+    export_jobs,
+    feature_flags,
+    follows,
+    idempotency_keys,
+    job_locks,
+    jobs,
+    notification_deliveries,
+    notifications,
+    onboarding_steps,
+    page_versions,
+    pages,
+    post_collaborators,
+    post_doc_updates,
+    post_docs,
+    post_fingerprints,
+    post_locks,
+    post_reactions,
+    post_search_index,
+    post_slugs,
     post_tags,
     post_versions,
+    post_views,
     posts,
+    push_subscriptions,
     refresh_tokens,
+    reports,
     reset_tokens,
+    series,
+    series_posts,
     tags,
+    uploads,
+    user_devices,
+    user_preferences,
     users,
+    webhook_deliveries,
+    webhooks,
 );
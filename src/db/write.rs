@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use diesel::connection::{AnsiTransactionManager, TransactionManager};
+use diesel::result::Error;
+use diesel::{Connection, QueryResult, SqliteConnection};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// How many times a write that found the database locked is tried again.
+const BUSY_RETRIES: u32 = 5;
+/// The first wait before trying again, doubled each time.
+const BUSY_BACKOFF: Duration = Duration::from_millis(20);
+
+/// Lets one writer at a time into the database. SQLite only ever has one writer anyway; taking
+/// turns here means the others wait in line instead of spinning on `SQLITE_BUSY`. Request
+/// transactions, handlers and the background workers that write in batches hold it while they
+/// write. Reads don't need it.
+#[derive(Clone, Default)]
+pub struct WriteLock(Arc<Mutex<()>>);
+
+impl WriteLock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits for this process's turn to write. The turn lasts until the guard is dropped, so
+    /// don't wait on anything else that needs a turn while holding it.
+    pub async fn acquire(&self) -> OwnedMutexGuard<()> {
+        self.0.clone().lock_owned().await
+    }
+
+    /// [`write`] in this process's turn. Async code should use this rather than calling
+    /// `write` directly: neither waiting for the turn nor backing off while another process
+    /// holds SQLite's lock ties up a runtime thread. `write`s inside `f` run in savepoints.
+    pub async fn write<T, E>(&self, conn: &mut SqliteConnection, f: impl FnOnce(&mut SqliteConnection) -> Result<T, E>) -> Result<T, E>
+    where
+        E: From<Error>,
+    {
+        let _turn = self.acquire().await;
+        if in_transaction(conn) {
+            return conn.transaction(f);
+        }
+        begin_immediate(conn).await?;
+        finish(conn, f)
+    }
+}
+
+/// Whether a query failed only because another connection held the lock, so trying it again
+/// later can succeed. Shared-cache databases report `locked` where files report `busy`.
+pub fn is_busy(error: &Error) -> bool {
+    match error {
+        Error::DatabaseError(_, info) => {
+            let message = info.message();
+            message.contains("database is locked") || message.contains("database table is locked")
+        }
+        _ => false,
+    }
+}
+
+/// Runs `f`, trying again with a growing pause while the database is locked. The pause
+/// blocks the thread, so this is for code already on a blocking one; async code begins its
+/// writes with [`begin_immediate`].
+pub fn retry_busy<T>(mut f: impl FnMut() -> QueryResult<T>) -> QueryResult<T> {
+    let mut backoff = BUSY_BACKOFF;
+    for _ in 0..BUSY_RETRIES {
+        match f() {
+            Err(e) if is_busy(&e) => {
+                tracing::warn!("Database is locked, retrying in {:?}", backoff);
+                std::thread::sleep(backoff);
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+    f()
+}
+
+/// Begins a write transaction like [`write`] does, pausing on the runtime's timer rather than
+/// the thread while the database is locked.
+pub async fn begin_immediate(conn: &mut SqliteConnection) -> QueryResult<()> {
+    let mut backoff = BUSY_BACKOFF;
+    for _ in 0..BUSY_RETRIES {
+        match AnsiTransactionManager::begin_transaction_sql(&mut *conn, "BEGIN IMMEDIATE") {
+            Err(e) if is_busy(&e) => {
+                tracing::warn!("Database is locked, retrying in {:?}", backoff);
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+    AnsiTransactionManager::begin_transaction_sql(conn, "BEGIN IMMEDIATE")
+}
+
+/// Runs `f` in a write transaction, for anything that writes more than once. The transaction
+/// takes SQLite's write lock when it begins rather than at its first write, which SQLite can't
+/// wait for, and beginning is tried again while the database is locked; once begun, nothing
+/// else can write until it ends. Inside a transaction already, `f` runs in a savepoint of it.
+pub fn write<T, E>(conn: &mut SqliteConnection, f: impl FnOnce(&mut SqliteConnection) -> Result<T, E>) -> Result<T, E>
+where
+    E: From<Error>,
+{
+    if in_transaction(conn) {
+        return conn.transaction(f);
+    }
+
+    retry_busy(|| AnsiTransactionManager::begin_transaction_sql(&mut *conn, "BEGIN IMMEDIATE"))?;
+    finish(conn, f)
+}
+
+/// Runs `f` in the transaction just begun, then commits it, or rolls it back if either fails.
+fn finish<T, E>(conn: &mut SqliteConnection, f: impl FnOnce(&mut SqliteConnection) -> Result<T, E>) -> Result<T, E>
+where
+    E: From<Error>,
+{
+    match f(conn) {
+        Ok(value) => match AnsiTransactionManager::commit_transaction(conn) {
+            Ok(()) => Ok(value),
+            Err(e) => {
+                rollback(conn);
+                Err(e.into())
+            }
+        },
+        Err(e) => {
+            rollback(conn);
+            Err(e)
+        }
+    }
+}
+
+fn rollback(conn: &mut SqliteConnection) {
+    if let Err(e) = AnsiTransactionManager::rollback_transaction(conn) {
+        tracing::error!("Failed to roll back write transaction: {}", e);
+    }
+}
+
+fn in_transaction(conn: &mut SqliteConnection) -> bool {
+    matches!(AnsiTransactionManager::transaction_manager_status_mut(conn).transaction_depth(), Ok(Some(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::connection::SimpleConnection;
+    use diesel::RunQueryDsl;
+
+    use super::*;
+
+    #[test]
+    fn writes_nest_and_roll_back() {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute("create table items (name text not null)").unwrap();
+
+        let failed: QueryResult<()> = write(&mut conn, |conn| {
+            conn.batch_execute("insert into items values ('kept')")?;
+            let inner: QueryResult<()> = write(conn, |conn| {
+                conn.batch_execute("insert into items values ('dropped')")?;
+                Err(Error::RollbackTransaction)
+            });
+            assert!(inner.is_err());
+            Ok(())
+        });
+        assert!(failed.is_ok());
+
+        let count: i64 = diesel::dsl::select(diesel::dsl::sql::<diesel::sql_types::BigInt>("(select count(*) from items)"))
+            .get_result(&mut conn)
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+}
@@ -1,8 +1,24 @@
 use axum::Json;
 use axum::response::{IntoResponse, Response};
 use http::StatusCode;
-use serde::{Deserialize, Serialize};
 
+pub use tsumi_types::{ErrorDetails, ErrorResponse, FieldError};
+
+/// Any failure a handler can answer with. Each domain has its own error type; they all turn
+/// into the same `{"error": ...}` envelope through this one.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+
+    #[error(transparent)]
+    Post(#[from] PostError),
+
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
+
+/// Authentication and general request failures.
 #[derive(Debug, thiserror::Error)]
 pub enum AuthError {
     #[error("User with identifier '{id}' not found")]
@@ -12,7 +28,7 @@ pub enum AuthError {
     InternalServerError { message: String },
 
     #[error("Validation failed: {message}")]
-    ValidationError { message: String },
+    ValidationError { message: String, fields: Vec<FieldError> },
 
     #[error("Database operation failed: {message}")]
     DatabaseError { message: String },
@@ -22,22 +38,198 @@ pub enum AuthError {
 
     #[error("Unauthorized: {message}")]
     Unauthorized { message: String },
+
+    #[error("Forbidden: {message}")]
+    Forbidden { message: String },
+
+    #[error("Re-authentication required: {message}")]
+    ReauthRequired { message: String },
+
+    #[error("Payload too large: {message}")]
+    PayloadTooLarge { message: String },
+
+    #[error("Unsupported media type: {message}")]
+    UnsupportedMediaType { message: String },
+
+    #[error("Too many requests: {message}")]
+    RateLimited { message: String, retry_after: u64 },
+}
+
+/// Failures specific to posts.
+#[derive(Debug, thiserror::Error)]
+pub enum PostError {
+    #[error("Post '{id}' not found")]
+    NotFound { id: String },
+
+    #[error("You already have a post with this slug")]
+    SlugTaken,
+
+    #[error("Could not derive a slug from the title, please provide one")]
+    SlugRequired,
+
+    #[error("This slug is reserved, please choose another")]
+    SlugReserved,
+
+    #[error("Tags must be between 1 and 32 characters")]
+    InvalidTag,
+
+    #[error("Cover image must be one of your uploads")]
+    InvalidCoverImage,
+
+    #[error("Series '{id}' not found")]
+    SeriesNotFound { id: String },
+
+    #[error("You already have a series with this slug")]
+    SeriesSlugTaken,
+
+    #[error("Series must be one of the author's series")]
+    InvalidSeries,
+
+    /// Another editor session holds the post's edit lock, or it changed hands mid-request.
+    #[error("{message}")]
+    EditLocked { message: String },
+
+    /// The caller can see the post, but their role on it doesn't allow this.
+    #[error("{message}")]
+    NotPermitted { message: String },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ErrorResponse {
-    error: ErrorDetails,
-    timestamp: chrono::DateTime<chrono::Utc>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    request_id: Option<String>,
+/// A database failure. What went wrong is logged; clients only see what was being done.
+#[derive(Debug, thiserror::Error)]
+pub enum DbError {
+    #[error("Database connection failed")]
+    Connection { message: String },
+
+    #[error("Database operation failed: {context}")]
+    Query {
+        context: String,
+        #[source]
+        source: diesel::result::Error,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ErrorDetails {
-    code: String,
-    message: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    details: Option<serde_json::Value>,
+/// Every error code the API answers with, whether it comes from an `AuthError` or from a
+/// framework rejection rewritten by the API's error middleware. `GET /api/v1/errors` lists
+/// these, so clients can rely on the set being closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    NotFound,
+    Validation,
+    Unauthorized,
+    Forbidden,
+    ReauthRequired,
+    Conflict,
+    MethodNotAllowed,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    RateLimited,
+    RequestFailed,
+    Database,
+    Internal,
+}
+
+impl ErrorKind {
+    pub const ALL: &'static [ErrorKind] = &[
+        ErrorKind::NotFound,
+        ErrorKind::Validation,
+        ErrorKind::Unauthorized,
+        ErrorKind::Forbidden,
+        ErrorKind::ReauthRequired,
+        ErrorKind::Conflict,
+        ErrorKind::MethodNotAllowed,
+        ErrorKind::PayloadTooLarge,
+        ErrorKind::UnsupportedMediaType,
+        ErrorKind::RateLimited,
+        ErrorKind::RequestFailed,
+        ErrorKind::Database,
+        ErrorKind::Internal,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::NotFound => "NOT_FOUND",
+            Self::Validation => "VALIDATION_ERROR",
+            Self::Unauthorized => "UNAUTHORIZED",
+            Self::Forbidden => "FORBIDDEN",
+            Self::ReauthRequired => "REAUTH_REQUIRED",
+            Self::Conflict => "CONFLICT",
+            Self::MethodNotAllowed => "METHOD_NOT_ALLOWED",
+            Self::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            Self::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            Self::RateLimited => "RATE_LIMITED",
+            Self::RequestFailed => "REQUEST_FAILED",
+            Self::Database => "DATABASE_ERROR",
+            Self::Internal => "INTERNAL_SERVER_ERROR",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.code() == code)
+    }
+
+    /// The status this code is normally sent with. `REQUEST_FAILED` covers whatever client
+    /// error didn't map to anything more specific, so its actual status varies.
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Validation | Self::RequestFailed => StatusCode::BAD_REQUEST,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden | Self::ReauthRequired => StatusCode::FORBIDDEN,
+            Self::Conflict => StatusCode::CONFLICT,
+            Self::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::Database | Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// English description, also used as the fallback message when a response is localized.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::NotFound => "The requested resource does not exist or is not visible to you.",
+            Self::Validation => "The request was malformed or failed validation.",
+            Self::Unauthorized => "Authentication is missing, invalid or expired.",
+            Self::Forbidden => "You are signed in but not allowed to do this.",
+            Self::ReauthRequired => "This action requires re-entering your password first.",
+            Self::Conflict => "The request conflicts with existing data.",
+            Self::MethodNotAllowed => "The endpoint does not support this HTTP method.",
+            Self::PayloadTooLarge => "The request body is larger than this endpoint accepts.",
+            Self::UnsupportedMediaType => "The request body is not in a format this endpoint accepts.",
+            Self::RateLimited => "Too many requests; retry after the number of seconds in Retry-After.",
+            Self::RequestFailed => "The request could not be processed.",
+            Self::Database => "A database operation failed.",
+            Self::Internal => "An unexpected server error occurred.",
+        }
+    }
+}
+
+/// Flattens `validator`'s nested errors into one entry per failed rule, ordered by field.
+pub fn field_errors(errors: &validator::ValidationErrors) -> Vec<FieldError> {
+    fn collect(errors: &validator::ValidationErrors, prefix: &str, out: &mut Vec<FieldError>) {
+        for (field, kind) in errors.errors() {
+            let path = if prefix.is_empty() { field.to_string() } else { format!("{}.{}", prefix, field) };
+            match kind {
+                validator::ValidationErrorsKind::Field(failures) => {
+                    out.extend(failures.iter().map(|failure| {
+                        let message = failure.message.as_deref().map_or_else(|| format!("{} is invalid", path), str::to_string);
+                        FieldError::new(path.clone(), failure.code.to_string(), message)
+                    }));
+                }
+                validator::ValidationErrorsKind::Struct(nested) => collect(nested, &path, out),
+                validator::ValidationErrorsKind::List(items) => {
+                    for (index, nested) in items {
+                        collect(nested, &format!("{}[{}]", path, index), out);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    collect(errors, "", &mut out);
+    out.sort_by(|a, b| a.field.cmp(&b.field));
+    out
 }
 
 impl AuthError {
@@ -46,13 +238,45 @@ impl AuthError {
     }
 
     pub fn validation(message: impl Into<String>) -> Self {
-        Self::ValidationError { message: message.into() }
+        Self::ValidationError { message: message.into(), fields: Vec::new() }
+    }
+
+    /// A validation failure for one field.
+    pub fn invalid_field(field: &str, code: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self::ValidationError { fields: vec![FieldError::new(field, code, message.clone())], message }
+    }
+
+    /// A payload that failed `validate()`, keeping every failed field. `context` leads the
+    /// message, e.g. "Invalid signup data".
+    pub fn invalid(context: &str, errors: validator::ValidationErrors) -> Self {
+        Self::ValidationError { message: format!("{}: {}", context, errors), fields: field_errors(&errors) }
     }
 
     pub fn unauthorized(message: impl Into<String>) -> Self {
         Self::Unauthorized { message: message.into() }
     }
 
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::Forbidden { message: message.into() }
+    }
+
+    pub fn reauth_required(message: impl Into<String>) -> Self {
+        Self::ReauthRequired { message: message.into() }
+    }
+
+    pub fn rate_limited(message: impl Into<String>, retry_after: u64) -> Self {
+        Self::RateLimited { message: message.into(), retry_after }
+    }
+
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        Self::PayloadTooLarge { message: message.into() }
+    }
+
+    pub fn unsupported_media_type(message: impl Into<String>) -> Self {
+        Self::UnsupportedMediaType { message: message.into() }
+    }
+
     pub fn conflict(message: impl Into<String>) -> Self {
         Self::Conflict { message: message.into() }
     }
@@ -65,57 +289,251 @@ impl AuthError {
         Self::InternalServerError { message: message.into() }
     }
 
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::NotFound { .. } => ErrorKind::NotFound,
+            Self::ValidationError { .. } => ErrorKind::Validation,
+            Self::Unauthorized { .. } => ErrorKind::Unauthorized,
+            Self::Forbidden { .. } => ErrorKind::Forbidden,
+            Self::ReauthRequired { .. } => ErrorKind::ReauthRequired,
+            Self::Conflict { .. } => ErrorKind::Conflict,
+            Self::PayloadTooLarge { .. } => ErrorKind::PayloadTooLarge,
+            Self::UnsupportedMediaType { .. } => ErrorKind::UnsupportedMediaType,
+            Self::RateLimited { .. } => ErrorKind::RateLimited,
+            Self::DatabaseError { .. } => ErrorKind::Database,
+            Self::InternalServerError { .. } => ErrorKind::Internal,
+        }
+    }
+
     pub fn status_code(&self) -> StatusCode {
+        self.kind().status()
+    }
+
+    pub fn error_code(&self) -> &'static str {
+        self.kind().code()
+    }
+
+    pub fn should_log(&self) -> bool {
+        matches!(self, Self::DatabaseError { .. } | Self::InternalServerError { .. })
+    }
+}
+
+impl PostError {
+    pub fn not_found(id: impl Into<String>) -> Self {
+        Self::NotFound { id: id.into() }
+    }
+
+    pub fn edit_locked(message: impl Into<String>) -> Self {
+        Self::EditLocked { message: message.into() }
+    }
+
+    pub fn not_permitted(message: impl Into<String>) -> Self {
+        Self::NotPermitted { message: message.into() }
+    }
+
+    pub fn series_not_found(id: impl Into<String>) -> Self {
+        Self::SeriesNotFound { id: id.into() }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
         match self {
-            Self::NotFound { .. } => StatusCode::NOT_FOUND,
-            Self::ValidationError { .. } => StatusCode::BAD_REQUEST,
-            Self::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
-            Self::Conflict { .. } => StatusCode::CONFLICT,
-            Self::DatabaseError { .. } | Self::InternalServerError { .. } => {
-                StatusCode::INTERNAL_SERVER_ERROR
+            Self::NotFound { .. } | Self::SeriesNotFound { .. } => ErrorKind::NotFound,
+            Self::SlugTaken | Self::SeriesSlugTaken | Self::EditLocked { .. } => ErrorKind::Conflict,
+            Self::NotPermitted { .. } => ErrorKind::Forbidden,
+            Self::SlugRequired | Self::SlugReserved | Self::InvalidTag | Self::InvalidCoverImage | Self::InvalidSeries => {
+                ErrorKind::Validation
             }
         }
     }
 
-    pub fn error_code(&self) -> &'static str {
+    /// The request field a validation failure is about.
+    fn field(&self) -> Option<FieldError> {
+        let (field, code) = match self {
+            Self::SlugRequired => ("slug", "required"),
+            Self::SlugReserved => ("slug", "reserved"),
+            Self::InvalidTag => ("tags", "length"),
+            Self::InvalidCoverImage => ("cover_image_id", "unknown_upload"),
+            Self::InvalidSeries => ("series_id", "unknown_series"),
+            Self::NotFound { .. }
+            | Self::SeriesNotFound { .. }
+            | Self::SlugTaken
+            | Self::SeriesSlugTaken
+            | Self::EditLocked { .. }
+            | Self::NotPermitted { .. } => return None,
+        };
+        Some(FieldError::new(field, code, self.to_string()))
+    }
+}
+
+impl DbError {
+    pub fn connection(error: impl std::fmt::Display) -> Self {
+        Self::Connection { message: error.to_string() }
+    }
+
+    /// For `map_err`: a failed query, described by what it was for.
+    pub fn query(context: impl Into<String>) -> impl FnOnce(diesel::result::Error) -> Self {
+        let context = context.into();
+        move |source| Self::Query { context, source }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
         match self {
-            Self::NotFound { .. } => "NOT_FOUND",
-            Self::ValidationError { .. } => "VALIDATION_ERROR",
-            Self::Unauthorized { .. } => "UNAUTHORIZED",
-            Self::Conflict { .. } => "CONFLICT",
-            Self::DatabaseError { .. } => "DATABASE_ERROR",
-            Self::InternalServerError { .. } => "INTERNAL_SERVER_ERROR",
+            Self::Connection { .. } => ErrorKind::Internal,
+            Self::Query { .. } => ErrorKind::Database,
         }
     }
 
-    pub fn should_log(&self) -> bool {
-        matches!(self, Self::DatabaseError { .. } | Self::InternalServerError { .. })
+    /// The message for the log, with the underlying error clients don't get to see.
+    fn detail(&self) -> String {
+        match self {
+            Self::Connection { message } => format!("{}: {}", self, message),
+            Self::Query { source, .. } => format!("{}: {}", self, source),
+        }
     }
 }
 
-impl IntoResponse for AuthError {
+impl AppError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Auth(e) => e.kind(),
+            Self::Post(e) => e.kind(),
+            Self::Db(e) => e.kind(),
+        }
+    }
+
+    fn fields(&self) -> Vec<FieldError> {
+        match self {
+            Self::Auth(AuthError::ValidationError { fields, .. }) => fields.clone(),
+            Self::Post(e) => e.field().into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        if self.should_log() {
-            tracing::error!("Internal error occurred: {}", self);
+        match &self {
+            Self::Db(e) => tracing::error!("{}", e.detail()),
+            Self::Auth(e) if e.should_log() => tracing::error!("Internal error occurred: {}", e),
+            _ => {}
         }
 
-        let status = self.status_code();
-        let error_response = ErrorResponse {
-            error: ErrorDetails {
-                code: self.error_code().to_string(),  // Convert to String
-                message: self.to_string(),
-                details: None,
-            },
-            timestamp: chrono::Utc::now(),
-            request_id: None, // Could be populated from request extensions
-        };
+        let fields = self.fields();
+        let details = (!fields.is_empty()).then(|| serde_json::json!({ "fields": fields }));
+        let kind = self.kind();
+        let mut response = error_response_with_details(kind.status(), kind, self.to_string(), details);
+        if let Self::Auth(AuthError::RateLimited { retry_after, .. }) = self {
+            response.headers_mut().insert(http::header::RETRY_AFTER, retry_after.into());
+        }
+        response
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
 
-        (status, Json(error_response)).into_response()
+impl IntoResponse for PostError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
     }
 }
 
+impl IntoResponse for DbError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
+
+/// The JSON error envelope every API error is sent in.
+pub fn error_response(status: StatusCode, kind: ErrorKind, message: String) -> Response {
+    error_response_with_details(status, kind, message, None)
+}
+
+pub fn error_response_with_details(
+    status: StatusCode,
+    kind: ErrorKind,
+    message: String,
+    details: Option<serde_json::Value>,
+) -> Response {
+    let error_response = ErrorResponse {
+        error: ErrorDetails {
+            code: kind.code().to_string(),
+            message,
+            details,
+        },
+        timestamp: chrono::Utc::now(),
+        request_id: None, // Could be populated from request extensions
+    };
+
+    (status, Json(error_response)).into_response()
+}
+
 impl From<validator::ValidationErrors> for AuthError {
     fn from(err: validator::ValidationErrors) -> Self {
-        Self::validation(err.to_string())
+        Self::ValidationError { message: err.to_string(), fields: field_errors(&err) }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use validator::Validate;
+
+    use super::*;
+
+    #[derive(Validate)]
+    struct Author {
+        #[validate(length(min = 3, message = "Name is too short"))]
+        name: String,
+    }
+
+    #[derive(Validate)]
+    struct Submission {
+        #[validate(email)]
+        email: String,
+        #[validate(nested)]
+        author: Author,
+        #[validate(nested)]
+        coauthors: Vec<Author>,
+    }
+
+    #[test]
+    fn nested_failures_become_one_field_error_each() {
+        let submission = Submission {
+            email: "nope".to_string(),
+            author: Author { name: "ok!".to_string() },
+            coauthors: vec![Author { name: "fine".to_string() }, Author { name: "x".to_string() }],
+        };
+        let AuthError::ValidationError { fields, .. } = AuthError::invalid("Invalid submission", submission.validate().unwrap_err()) else {
+            panic!("expected a validation error");
+        };
+
+        assert_eq!(fields, [
+            FieldError::new("coauthors[1].name", "length", "Name is too short"),
+            FieldError::new("email", "email", "email is invalid"),
+        ]);
+    }
+
+    async fn body(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn domain_errors_share_the_envelope() {
+        let response = PostError::InvalidCoverImage.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let error = &body(response).await["error"];
+        assert_eq!(error["code"], "VALIDATION_ERROR");
+        assert_eq!(error["details"]["fields"][0]["field"], "cover_image_id");
+
+        let failure = DbError::query("Failed to load post")(diesel::result::Error::NotFound);
+        let response = AppError::from(failure).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let error = &body(response).await["error"];
+        assert_eq!(error["code"], "DATABASE_ERROR");
+        assert_eq!(error["message"], "Database operation failed: Failed to load post");
+    }
+}
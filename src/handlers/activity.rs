@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::comment::Comments;
+use crate::db::models::post::Posts;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::services::cache;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Days covered by the heatmap, today included.
+const ACTIVITY_DAYS: i64 = 365;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityDay {
+    pub date: NaiveDate,
+    pub posts: i64,
+    pub comments: i64,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityResponse {
+    pub username: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total: i64,
+    /// One entry per day from `from` to `to`, days without activity included.
+    pub days: Vec<ActivityDay>,
+}
+
+/// `GET /users/:username/activity`, posts published and comments written per UTC day over
+/// the past year, for a contribution heatmap. Computed once a day per user and cached
+/// until midnight, so today's activity shows up tomorrow. Private profiles' activity is only
+/// shown to signed-in users.
+pub async fn user_activity(
+    State(state): State<AppState>,
+    auth: Option<AuthUser>,
+    Path(username): Path<String>,
+) -> Result<ApiResponse<ActivityResponse>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading activity: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let user = UserModel::by_name(&mut conn, &username)
+        .map_err(|e| {
+            tracing::error!("Failed to load user {}: {}", username, e);
+            AuthError::database("Failed to load activity")
+        })?
+        .filter(|user| user.profile_visible_to(auth.as_ref().map(|auth| &auth.user)))
+        .ok_or_else(|| AuthError::not_found(&username))?;
+
+    let now = Utc::now();
+    let today = now.date_naive();
+    let key = cache::activity_key(&user.id, today);
+    if let Some(activity) = cache::get_json::<ActivityResponse>(state.cache.as_ref(), &key).await {
+        return Ok(ApiResponse::new(activity));
+    }
+
+    let from = today - chrono::Duration::days(ACTIVITY_DAYS - 1);
+    let since = from.and_hms_opt(0, 0, 0).unwrap_or_default();
+
+    let posts = Posts::daily_published_counts(&mut conn, &user.id, since)
+        .map_err(|e| {
+            tracing::error!("Failed to count posts per day for user {}: {}", user.id, e);
+            AuthError::database("Failed to load activity")
+        })?;
+    let comments = Comments::daily_counts_by_user(&mut conn, &user.id, since)
+        .map_err(|e| {
+            tracing::error!("Failed to count comments per day for user {}: {}", user.id, e);
+            AuthError::database("Failed to load activity")
+        })?;
+
+    let mut counts: BTreeMap<NaiveDate, (i64, i64)> = BTreeMap::new();
+    for (day, count) in posts {
+        if let Ok(day) = day.parse::<NaiveDate>() {
+            counts.entry(day).or_default().0 += count;
+        }
+    }
+    for (day, count) in comments {
+        if let Ok(day) = day.parse::<NaiveDate>() {
+            counts.entry(day).or_default().1 += count;
+        }
+    }
+
+    let days: Vec<ActivityDay> = from
+        .iter_days()
+        .take_while(|day| *day <= today)
+        .map(|date| {
+            let (posts, comments) = counts.get(&date).copied().unwrap_or_default();
+            ActivityDay { date, posts, comments, count: posts + comments }
+        })
+        .collect();
+
+    let activity = ActivityResponse {
+        username: user.name,
+        from,
+        to: today,
+        total: days.iter().map(|day| day.count).sum(),
+        days,
+    };
+
+    let midnight = (today + chrono::Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let ttl = Duration::from_secs((midnight - now).num_seconds().max(1) as u64);
+    cache::set_json(state.cache.as_ref(), &key, &activity, ttl).await;
+
+    Ok(ApiResponse::new(activity))
+}
@@ -0,0 +1,56 @@
+use axum::extract::{Query, State};
+
+use crate::db::models::audit_log::AuditLogs;
+use crate::db::queries::audit_logs::AuditFilter;
+use crate::errors::AuthError;
+use crate::handlers::admin::ListAuditLogsQuery;
+use crate::handlers::me::{AuditLogResponse, AuditSort};
+use crate::http::auth::AdminUser;
+use crate::http::dto::ApiResponse;
+use crate::http::pagination::{ListParams, Paginated};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|value| !value.is_empty())
+}
+
+/// The audit log across every account, newest first unless `dir=asc`. Filter with `user_id`,
+/// `actor_id`, `event`, `ip_address` and an RFC 3339 `since`/`until` range.
+pub async fn list_audit_logs(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    params: ListParams<AuditSort>,
+    Query(query): Query<ListAuditLogsQuery>,
+) -> Result<ApiResponse<Paginated<AuditLogResponse>>, AuthError> {
+    let filter = AuditFilter {
+        user_id: non_empty(&query.user_id),
+        actor_id: non_empty(&query.actor_id),
+        event: non_empty(&query.event),
+        ip_address: non_empty(&query.ip_address),
+        since: query.since.map(|since| since.naive_utc()),
+        until: query.until.map(|until| until.naive_utc()),
+    };
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing audit logs: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let total = AuditLogs::count_filtered(&mut conn, &filter)
+        .map_err(|e| {
+            tracing::error!("Failed to count audit logs: {}", e);
+            AuthError::database("Failed to list audit logs")
+        })?;
+
+    let entries = AuditLogs::page_filtered(&mut conn, &filter, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to list audit logs: {}", e);
+            AuthError::database("Failed to list audit logs")
+        })?;
+
+    let entries = entries.into_iter().map(AuditLogResponse::from).collect();
+
+    Ok(ApiResponse::new(params.paginate(entries, total)))
+}
@@ -0,0 +1,232 @@
+use axum::extract::{Path, State};
+use serde::Serialize;
+
+use crate::db::models::backfill_job::{
+    BackfillJobs, NewBackfillJob, BACKFILL_KINDS, BACKFILL_STATUS_FAILED, BACKFILL_STATUS_PAUSED,
+    BACKFILL_STATUS_PENDING, BACKFILL_STATUS_RUNNING,
+};
+use crate::db::models::post::Posts;
+use crate::errors::AuthError;
+use crate::handlers::admin::CreateBackfillRequest;
+use crate::http::auth::AdminUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::audit::{self, AUDIT_ADMIN_BACKFILL_CREATED, AUDIT_ADMIN_BACKFILL_PAUSED, AUDIT_ADMIN_BACKFILL_RESUMED};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct BackfillJobResponse {
+    #[serde(flatten)]
+    pub job: BackfillJobs,
+    pub percent_complete: f64,
+}
+
+impl From<BackfillJobs> for BackfillJobResponse {
+    fn from(job: BackfillJobs) -> Self {
+        let percent_complete = if job.total == 0 {
+            100.0
+        } else {
+            (f64::from(job.processed) / f64::from(job.total) * 100.0).min(100.0)
+        };
+        Self { job, percent_complete }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListBackfillsResponse {
+    pub jobs: Vec<BackfillJobResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MissingMetadata {
+    pub kind: &'static str,
+    pub posts: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MissingMetadataResponse {
+    pub missing: Vec<MissingMetadata>,
+}
+
+/// Reports how many posts are missing each kind of derived metadata.
+pub async fn missing_metadata(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<ApiResponse<MissingMetadataResponse>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while checking post metadata: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let missing = BACKFILL_KINDS
+        .into_iter()
+        .map(|kind| {
+            Posts::count_missing_metadata(&mut conn, kind)
+                .map(|posts| MissingMetadata { kind, posts })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            tracing::error!("Failed to count posts missing metadata: {}", e);
+            AuthError::database("Failed to check post metadata")
+        })?;
+
+    Ok(ApiResponse::new(MissingMetadataResponse { missing }))
+}
+
+pub async fn list_backfills(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<ApiResponse<ListBackfillsResponse>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing backfills: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let jobs = BackfillJobs::list(&mut conn)
+        .map_err(|e| {
+            tracing::error!("Failed to list backfill jobs: {}", e);
+            AuthError::database("Failed to list backfill jobs")
+        })?;
+
+    Ok(ApiResponse::new(ListBackfillsResponse { jobs: jobs.into_iter().map(BackfillJobResponse::from).collect() }))
+}
+
+pub async fn get_backfill(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<BackfillJobResponse>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading backfill {}: {}", id, e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let job = BackfillJobs::by_id(&mut conn, &id)
+        .map_err(|e| {
+            tracing::error!("Failed to load backfill job {}: {}", id, e);
+            AuthError::database("Failed to load backfill job")
+        })?
+        .ok_or_else(|| AuthError::not_found(id))?;
+
+    Ok(ApiResponse::new(job.into()))
+}
+
+/// Queues a backfill. The worker picks it up on its next poll.
+pub async fn create_backfill(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    AppJson(payload): AppJson<CreateBackfillRequest>,
+) -> Result<ApiResponse<BackfillJobResponse>, AuthError> {
+    if !BACKFILL_KINDS.contains(&payload.kind.as_str()) {
+        return Err(AuthError::validation(format!(
+            "Unknown backfill kind, expected one of: {}",
+            BACKFILL_KINDS.join(", ")
+        )));
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while creating backfill: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let active = BackfillJobs::active_for_kind(&mut conn, &payload.kind)
+        .map_err(|e| {
+            tracing::error!("Failed to check for active {} backfill: {}", payload.kind, e);
+            AuthError::database("Failed to create backfill job")
+        })?;
+    if let Some(active) = active {
+        return Err(AuthError::conflict(format!(
+            "A {} backfill is already {} ({})",
+            active.kind, active.status, active.id
+        )));
+    }
+
+    let total = Posts::count_missing_metadata(&mut conn, &payload.kind)
+        .map_err(|e| {
+            tracing::error!("Failed to count posts for {} backfill: {}", payload.kind, e);
+            AuthError::database("Failed to create backfill job")
+        })?;
+
+    let now = chrono::Utc::now().naive_utc();
+    let job = state.writes.write(&mut conn, |conn| BackfillJobs::create(conn, &NewBackfillJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind: payload.kind,
+        status: BACKFILL_STATUS_PENDING.to_string(),
+        total: total.try_into().unwrap_or(i32::MAX),
+        created_by: Some(admin.user.id.clone()),
+        created_at: now,
+        updated_at: now,
+    }))
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create backfill job: {}", e);
+        AuthError::database("Failed to create backfill job")
+    })?;
+
+    let detail = format!("{} {}", job.kind, job.id);
+    audit::record(&state, &client, AUDIT_ADMIN_BACKFILL_CREATED, None, Some(&admin.user.id), Some(&detail)).await;
+
+    tracing::info!("Admin {} queued {} backfill {} over {} post(s)", admin.user.id, job.kind, job.id, job.total);
+
+    Ok(ApiResponse::new(job.into()))
+}
+
+pub async fn pause_backfill(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<BackfillJobResponse>, AuthError> {
+    let job = transition(&state, &id, &[BACKFILL_STATUS_PENDING, BACKFILL_STATUS_RUNNING], BACKFILL_STATUS_PAUSED).await?;
+    audit::record(&state, &client, AUDIT_ADMIN_BACKFILL_PAUSED, None, Some(&admin.user.id), Some(&job.id)).await;
+    tracing::info!("Admin {} paused backfill {}", admin.user.id, job.id);
+    Ok(ApiResponse::new(job.into()))
+}
+
+/// Resumes a paused job, or retries a failed one from the last completed batch.
+pub async fn resume_backfill(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<BackfillJobResponse>, AuthError> {
+    let job = transition(&state, &id, &[BACKFILL_STATUS_PAUSED, BACKFILL_STATUS_FAILED], BACKFILL_STATUS_PENDING).await?;
+    audit::record(&state, &client, AUDIT_ADMIN_BACKFILL_RESUMED, None, Some(&admin.user.id), Some(&job.id)).await;
+    tracing::info!("Admin {} resumed backfill {}", admin.user.id, job.id);
+    Ok(ApiResponse::new(job.into()))
+}
+
+async fn transition(state: &AppState, id: &str, from: &[&str], to: &str) -> Result<BackfillJobs, AuthError> {
+    let mut conn = get_db_conn(state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while updating backfill {}: {}", id, e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let updated = state.writes.write(&mut conn, |conn| BackfillJobs::transition(conn, id, from, to))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update backfill job {}: {}", id, e);
+            AuthError::database("Failed to update backfill job")
+        })?;
+
+    if let Some(job) = updated {
+        return Ok(job);
+    }
+
+    match BackfillJobs::by_id(&mut conn, id) {
+        Ok(Some(job)) => Err(AuthError::conflict(format!("Backfill job is {}", job.status))),
+        Ok(None) => Err(AuthError::not_found(id)),
+        Err(e) => {
+            tracing::error!("Failed to load backfill job {}: {}", id, e);
+            Err(AuthError::database("Failed to update backfill job"))
+        }
+    }
+}
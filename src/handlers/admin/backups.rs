@@ -0,0 +1,51 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Serialize;
+
+use crate::errors::AuthError;
+use crate::handlers::admin::jobs::{job_status, request_run};
+use crate::http::auth::AdminUser;
+use crate::http::dto::ApiResponse;
+use crate::services::backups::{BackupInfo, BackupReport};
+use crate::services::scheduler::JobStatus;
+use crate::state::AppState;
+
+const BACKUP_JOB: &str = "backups";
+
+#[derive(Debug, Serialize)]
+pub struct BackupStatus {
+    #[serde(flatten)]
+    pub job: JobStatus,
+    #[serde(flatten)]
+    pub report: BackupReport,
+    /// Newest first.
+    pub backups: Vec<BackupInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunBackupResponse {
+    pub message: String,
+}
+
+/// The backups kept, and how the last backup run went.
+pub async fn backup_status(State(state): State<AppState>, _admin: AdminUser) -> Result<ApiResponse<BackupStatus>, AuthError> {
+    let backups = state.backups.list().await.map_err(|e| {
+        tracing::error!("Failed to list backups: {}", e);
+        AuthError::internal("Failed to list backups")
+    })?;
+
+    let job = job_status(&state, BACKUP_JOB)?;
+
+    Ok(ApiResponse::new(BackupStatus { job, report: state.backups.report(), backups }))
+}
+
+/// Starts a backup now rather than at the next `BACKUP_INTERVAL_HOURS`.
+pub async fn run_backup(
+    State(state): State<AppState>,
+    admin: AdminUser,
+) -> Result<(StatusCode, ApiResponse<RunBackupResponse>), AuthError> {
+    request_run(&state, BACKUP_JOB).await?;
+    tracing::info!("Admin {} started a backup", admin.user.id);
+
+    Ok((StatusCode::ACCEPTED, ApiResponse::new(RunBackupResponse { message: "Backup started".to_string() })))
+}
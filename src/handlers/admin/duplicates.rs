@@ -0,0 +1,87 @@
+use axum::extract::State;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::models::post::Posts;
+use crate::db::models::post_fingerprint::PostFingerprints;
+use crate::errors::AuthError;
+use crate::http::auth::AdminUser;
+use crate::http::dto::ApiResponse;
+use crate::services::simhash;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+const MAX_FLAGGED: i64 = 100;
+
+#[derive(Debug, Serialize)]
+pub struct DuplicatePost {
+    pub id: String,
+    pub author_id: String,
+    pub title: String,
+    pub slug: String,
+    pub status: String,
+}
+
+impl From<Posts> for DuplicatePost {
+    fn from(post: Posts) -> Self {
+        Self { id: post.id, author_id: post.user_id, title: post.title, slug: post.slug, status: post.status }
+    }
+}
+
+/// A post flagged on publish for nearly duplicating another author's post.
+#[derive(Debug, Serialize)]
+pub struct FlaggedDuplicate {
+    pub post: DuplicatePost,
+    pub duplicate_of: DuplicatePost,
+    pub distance: u32,
+    pub flagged_at: NaiveDateTime,
+}
+
+/// Posts that nearly duplicate another author's post, newest flags first. Flags are refreshed
+/// every time a post is published, and cleared when it's unpublished.
+pub async fn list_duplicates(
+    State(state): State<AppState>,
+    admin: AdminUser,
+) -> Result<ApiResponse<Vec<FlaggedDuplicate>>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing duplicates: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let flagged = PostFingerprints::flagged(&mut conn, MAX_FLAGGED)
+        .map_err(|e| {
+            tracing::error!("Failed to list flagged duplicates: {}", e);
+            AuthError::database("Failed to list duplicates")
+        })?;
+
+    let mut duplicates = Vec::with_capacity(flagged.len());
+    for fingerprint in flagged {
+        let Some(original_id) = fingerprint.duplicate_of.as_deref() else {
+            continue;
+        };
+        let post = Posts::by_id(&mut conn, &fingerprint.post_id);
+        let original = Posts::by_id(&mut conn, original_id);
+        let original_hash = PostFingerprints::by_post(&mut conn, original_id);
+        let (post, original, original_hash) = match (post, original, original_hash) {
+            (Ok(Some(post)), Ok(Some(original)), Ok(Some(original_hash))) => (post, original, original_hash),
+            // The original has been unpublished or deleted since the flag was raised.
+            (Ok(_), Ok(_), Ok(_)) => continue,
+            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                tracing::error!("Failed to load flagged duplicate {}: {}", fingerprint.post_id, e);
+                return Err(AuthError::database("Failed to list duplicates"));
+            }
+        };
+
+        duplicates.push(FlaggedDuplicate {
+            post: post.into(),
+            duplicate_of: original.into(),
+            distance: simhash::distance(fingerprint.simhash as u64, original_hash.simhash as u64),
+            flagged_at: fingerprint.updated_at,
+        });
+    }
+
+    tracing::info!("Admin {} listed {} flagged duplicate posts", admin.user.id, duplicates.len());
+
+    Ok(ApiResponse::new(duplicates))
+}
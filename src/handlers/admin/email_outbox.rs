@@ -0,0 +1,146 @@
+use axum::extract::{Path, State};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::models::email_outbox::{
+    EmailOutbox, EMAIL_OUTBOX_FAILED, EMAIL_OUTBOX_PENDING, EMAIL_OUTBOX_SENT, EMAIL_OUTBOX_SUPPRESSED,
+};
+use crate::errors::AuthError;
+use crate::http::auth::AdminUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::services::audit::{self, AUDIT_ADMIN_EMAIL_RETRIED};
+use crate::services::email_queue::EmailPriority;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Failed messages listed alongside the counts.
+const RECENT_FAILED: i64 = 50;
+
+#[derive(Debug, Default, Serialize)]
+pub struct LaneDepth {
+    pub pending: i64,
+    pub sent: i64,
+    pub suppressed: i64,
+    pub failed: i64,
+}
+
+/// A message that ran out of attempts. Bodies are left out: they can hold one-time links.
+#[derive(Debug, Serialize)]
+pub struct FailedEmail {
+    pub id: String,
+    pub recipient: String,
+    pub subject: String,
+    pub priority: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<EmailOutbox> for FailedEmail {
+    fn from(message: EmailOutbox) -> Self {
+        Self {
+            id: message.id,
+            recipient: message.recipient,
+            subject: message.subject,
+            priority: message.priority,
+            attempts: message.attempts,
+            last_error: message.last_error,
+            created_at: message.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmailOutboxStatus {
+    pub transactional: LaneDepth,
+    pub bulk: LaneDepth,
+    /// When the longest-waiting pending message was queued.
+    pub oldest_pending_at: Option<NaiveDateTime>,
+    /// Newest first.
+    pub failed: Vec<FailedEmail>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RetryEmailResponse {
+    pub message: String,
+}
+
+/// How much mail each lane has waiting, sent and given up on, with the latest failures.
+pub async fn email_outbox_status(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<ApiResponse<EmailOutboxStatus>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while reading the email outbox: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let load_failed = |e: diesel::result::Error| {
+        tracing::error!("Failed to read the email outbox: {}", e);
+        AuthError::database("Failed to read the email outbox")
+    };
+    let counts = EmailOutbox::counts(&mut conn).map_err(load_failed)?;
+    let oldest_pending_at = EmailOutbox::oldest_pending(&mut conn).map_err(load_failed)?;
+    let failed = EmailOutbox::recent_failed(&mut conn, RECENT_FAILED).map_err(load_failed)?;
+
+    let mut transactional = LaneDepth::default();
+    let mut bulk = LaneDepth::default();
+    for (priority, status, count) in counts {
+        let lane = if priority == EmailPriority::Bulk.as_str() { &mut bulk } else { &mut transactional };
+        match status.as_str() {
+            EMAIL_OUTBOX_PENDING => lane.pending = count,
+            EMAIL_OUTBOX_SENT => lane.sent = count,
+            EMAIL_OUTBOX_SUPPRESSED => lane.suppressed = count,
+            EMAIL_OUTBOX_FAILED => lane.failed = count,
+            _ => {}
+        }
+    }
+
+    Ok(ApiResponse::new(EmailOutboxStatus {
+        transactional,
+        bulk,
+        oldest_pending_at,
+        failed: failed.into_iter().map(FailedEmail::from).collect(),
+    }))
+}
+
+/// Puts a failed message back in its lane with a fresh set of attempts.
+pub async fn retry_email(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<RetryEmailResponse>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while retrying email {}: {}", id, e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let retried = state.writes.write(&mut conn, |conn| EmailOutbox::retry_failed(conn, &id, chrono::Utc::now().naive_utc()))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to retry email {}: {}", id, e);
+            AuthError::database("Failed to retry email")
+        })?;
+    if !retried {
+        return Err(AuthError::not_found(id));
+    }
+    let message = EmailOutbox::by_id(&mut conn, &id)
+        .map_err(|e| {
+            tracing::error!("Failed to load email {}: {}", id, e);
+            AuthError::database("Failed to retry email")
+        })?
+        .ok_or_else(|| AuthError::not_found(id.clone()))?;
+    drop(conn);
+
+    let priority = if message.priority == EmailPriority::Bulk.as_str() { EmailPriority::Bulk } else { EmailPriority::Transactional };
+    state.email_queue.wake(priority);
+
+    audit::record(&state, &client, AUDIT_ADMIN_EMAIL_RETRIED, None, Some(&admin.user.id), Some(&id)).await;
+    tracing::info!("Admin {} retried email {} to {}", admin.user.id, id, message.recipient);
+
+    Ok(ApiResponse::new(RetryEmailResponse { message: "Email queued again".to_string() }))
+}
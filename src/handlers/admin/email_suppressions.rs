@@ -0,0 +1,76 @@
+use axum::extract::{Path, Query, State};
+use serde::Serialize;
+
+use crate::db::models::email_suppression::EmailSuppressions;
+use crate::errors::AuthError;
+use crate::handlers::admin::ListSuppressionsQuery;
+use crate::http::auth::AdminUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::services::audit::{self, AUDIT_ADMIN_SUPPRESSION_REACTIVATED};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct ListSuppressionsResponse {
+    pub suppressions: Vec<EmailSuppressions>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReactivateSuppressionResponse {
+    pub message: String,
+    pub reactivated_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn list_suppressions(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(query): Query<ListSuppressionsQuery>,
+) -> Result<ApiResponse<ListSuppressionsResponse>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing suppressions: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let suppressions = EmailSuppressions::list(&mut conn, query.include_reactivated)
+        .map_err(|e| {
+            tracing::error!("Failed to list email suppressions: {}", e);
+            AuthError::database("Failed to list email suppressions")
+        })?;
+
+    Ok(ApiResponse::new(ListSuppressionsResponse { suppressions }))
+}
+
+pub async fn reactivate_suppression(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(email): Path<String>,
+) -> Result<ApiResponse<ReactivateSuppressionResponse>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while reactivating {}: {}", email, e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let updated = state.writes.write(&mut conn, |conn| EmailSuppressions::reactivate(conn, &email))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to reactivate {}: {}", email, e);
+            AuthError::database("Failed to reactivate address")
+        })?;
+
+    if updated == 0 {
+        return Err(AuthError::not_found(email));
+    }
+
+    audit::record(&state, &client, AUDIT_ADMIN_SUPPRESSION_REACTIVATED, None, Some(&admin.user.id), Some(&email)).await;
+
+    tracing::info!("Admin {} reactivated suppressed address {}", admin.user.id, email);
+
+    Ok(ApiResponse::new(ReactivateSuppressionResponse {
+        message: "Address reactivated".to_string(),
+        reactivated_at: chrono::Utc::now(),
+    }))
+}
@@ -0,0 +1,119 @@
+use axum::extract::{Path, State};
+use diesel::SqliteConnection;
+use serde::Serialize;
+
+use crate::db::models::feature_flag::FeatureFlags;
+use crate::errors::AuthError;
+use crate::handlers::admin::SetFeatureFlagRequest;
+use crate::http::auth::AdminUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::audit::{self, AUDIT_ADMIN_FEATURE_FLAG_CHANGED, AUDIT_ADMIN_FEATURE_FLAG_RESET};
+use crate::services::cache;
+use crate::services::feature_flags::{self, FlagState};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct ListFeatureFlagsResponse {
+    pub flags: Vec<FlagState>,
+}
+
+fn flag_state(state: &AppState, conn: &mut SqliteConnection, name: &str) -> Result<FlagState, AuthError> {
+    feature_flags::list(conn, &state.config.load())
+        .map_err(|e| {
+            tracing::error!("Failed to load feature flags: {}", e);
+            AuthError::database("Failed to load feature flags")
+        })?
+        .into_iter()
+        .find(|flag| flag.name == name)
+        .ok_or_else(|| AuthError::not_found(name))
+}
+
+pub async fn list_feature_flags(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<ApiResponse<ListFeatureFlagsResponse>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing feature flags: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let flags = feature_flags::list(&mut conn, &state.config.load())
+        .map_err(|e| {
+            tracing::error!("Failed to list feature flags: {}", e);
+            AuthError::database("Failed to list feature flags")
+        })?;
+
+    Ok(ApiResponse::new(ListFeatureFlagsResponse { flags }))
+}
+
+/// Overrides the flag's configured default until the override is removed.
+pub async fn set_feature_flag(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(name): Path<String>,
+    AppJson(payload): AppJson<SetFeatureFlagRequest>,
+) -> Result<ApiResponse<FlagState>, AuthError> {
+    if !feature_flags::is_known(&name) {
+        return Err(AuthError::not_found(name));
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while setting {}: {}", name, e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    state.writes.write(&mut conn, |conn| FeatureFlags::set(conn, &name, payload.enabled, &admin.user.id, chrono::Utc::now().naive_utc()))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set feature flag {}: {}", name, e);
+            AuthError::database("Failed to set feature flag")
+        })?;
+
+    let detail = format!("{}={}", name, payload.enabled);
+    audit::record(&state, &client, AUDIT_ADMIN_FEATURE_FLAG_CHANGED, None, Some(&admin.user.id), Some(&detail)).await;
+    // Cached pages were rendered with the old value.
+    cache::invalidate_all_pages(state.cache.as_ref()).await;
+
+    tracing::info!("Admin {} set feature flag {}", admin.user.id, detail);
+
+    Ok(ApiResponse::new(flag_state(&state, &mut conn, &name)?))
+}
+
+/// Removes the override, so the flag follows its configured default again.
+pub async fn reset_feature_flag(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(name): Path<String>,
+) -> Result<ApiResponse<FlagState>, AuthError> {
+    if !feature_flags::is_known(&name) {
+        return Err(AuthError::not_found(name));
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while resetting {}: {}", name, e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let cleared = state.writes.write(&mut conn, |conn| FeatureFlags::clear(conn, &name))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to reset feature flag {}: {}", name, e);
+            AuthError::database("Failed to reset feature flag")
+        })?;
+
+    if cleared {
+        audit::record(&state, &client, AUDIT_ADMIN_FEATURE_FLAG_RESET, None, Some(&admin.user.id), Some(&name)).await;
+        cache::invalidate_all_pages(state.cache.as_ref()).await;
+        tracing::info!("Admin {} reset feature flag {}", admin.user.id, name);
+    }
+
+    Ok(ApiResponse::new(flag_state(&state, &mut conn, &name)?))
+}
@@ -0,0 +1,109 @@
+use axum::extract::{Path, State};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tower_cookies::Cookies;
+
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::http::auth::{AdminUser, AuthUser};
+use crate::http::client::ClientInfo;
+use crate::http::dto::{ApiResponse, UserDto};
+use crate::services::audit::{self, AUDIT_ADMIN_IMPERSONATION_STARTED, AUDIT_ADMIN_IMPERSONATION_STOPPED};
+use crate::services::cookies::{self, AuthCookie};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationResponse {
+    /// Also set as the access token cookie, for browsers.
+    pub access_token: String,
+    pub user: UserDto,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StopImpersonationResponse {
+    pub access_token: String,
+    pub user: UserDto,
+    pub message: String,
+}
+
+fn load_user(state: &AppState, id: &str) -> Result<Option<UserModel>, AuthError> {
+    let mut conn = get_db_conn(state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection for impersonation: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+    UserModel::by_id(&mut conn, id)
+        .map_err(|e| {
+            tracing::error!("Failed to load user {} for impersonation: {}", id, e);
+            AuthError::database("Failed to load user")
+        })
+}
+
+/// Signs the admin in as another user, with an access token that names the admin and expires
+/// within the hour. Their own session is untouched: stopping, or letting the token expire and
+/// refreshing, puts them back. Other admins can't be impersonated.
+pub async fn start_impersonation(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    cookies: Cookies,
+    client: ClientInfo,
+    Path(user_id): Path<String>,
+) -> Result<ApiResponse<ImpersonationResponse>, AuthError> {
+    if admin.user.id == user_id {
+        return Err(AuthError::forbidden("Admins cannot impersonate themselves"));
+    }
+    let user = load_user(&state, &user_id)?.ok_or_else(|| AuthError::not_found(user_id.clone()))?;
+    if user.is_admin {
+        return Err(AuthError::forbidden("Admins cannot be impersonated"));
+    }
+
+    let (access_token, expires_at) = state.jwt.create_impersonation_token(&user.id, user.token_version, &admin.user.id, admin.user.token_version)
+        .map_err(|e| {
+            tracing::error!("Failed to create impersonation token for user {}: {}", user.id, e);
+            AuthError::internal("Failed to generate authentication tokens")
+        })?;
+    let max_age = time::Duration::seconds((expires_at - Utc::now()).num_seconds().max(0));
+    cookies::set(&cookies, &state.config.load(), AuthCookie::Access, &access_token, Some(max_age));
+
+    audit::record(&state, &client, AUDIT_ADMIN_IMPERSONATION_STARTED, Some(&user.id), Some(&admin.user.id), None).await;
+    tracing::info!("Admin {} started impersonating user {}", admin.user.id, user.id);
+
+    Ok(ApiResponse::new(ImpersonationResponse { access_token, user: UserDto::from(user), expires_at }))
+}
+
+/// Ends impersonation, handing the admin an access token of their own again. `AuthUser` has
+/// already checked the token against the admin's version; the admin is loaded fresh here so a
+/// stale cache entry can't mint a token for someone who lost admin or was suspended.
+pub async fn stop_impersonation(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    cookies: Cookies,
+    client: ClientInfo,
+) -> Result<ApiResponse<StopImpersonationResponse>, AuthError> {
+    let Some(admin_id) = auth.impersonator else {
+        return Err(AuthError::validation("Not impersonating anyone"));
+    };
+    let admin = load_user(&state, &admin_id)?
+        .filter(|admin| admin.is_admin && admin.deleted_at.is_none() && !admin.is_suspended())
+        .ok_or_else(|| AuthError::unauthorized("Impersonation has ended"))?;
+
+    let config = state.config.load();
+    let access_token = state.jwt.create_access_token(&admin.id, admin.token_version)
+        .map_err(|e| {
+            tracing::error!("Failed to create access token for user {}: {}", admin.id, e);
+            AuthError::internal("Failed to generate authentication tokens")
+        })?;
+    let max_age = time::Duration::minutes(config.access_token_expires_at());
+    cookies::set(&cookies, &config, AuthCookie::Access, &access_token, Some(max_age));
+
+    audit::record(&state, &client, AUDIT_ADMIN_IMPERSONATION_STOPPED, Some(&auth.user.id), Some(&admin.id), None).await;
+    tracing::info!("Admin {} stopped impersonating user {}", admin.id, auth.user.id);
+
+    Ok(ApiResponse::new(StopImpersonationResponse {
+        access_token,
+        user: UserDto::from(admin),
+        message: "Stopped impersonating".to_string(),
+    }))
+}
@@ -0,0 +1,71 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::Serialize;
+
+use crate::errors::AuthError;
+use crate::http::auth::AdminUser;
+use crate::http::dto::ApiResponse;
+use crate::services::scheduler::JobStatus;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct RunJobResponse {
+    pub message: String,
+}
+
+fn conn_error(e: impl std::fmt::Display) -> AuthError {
+    tracing::error!("Failed to get database connection while reading scheduled jobs: {}", e);
+    AuthError::internal("Database connection failed")
+}
+
+/// The status of the job called `name`, which the caller knows is registered.
+pub(super) fn job_status(state: &AppState, name: &str) -> Result<JobStatus, AuthError> {
+    let mut conn = get_db_conn(state).map_err(conn_error)?;
+    state
+        .scheduler
+        .status(&mut conn, name)
+        .map_err(|e| {
+            tracing::error!("Failed to load job {}: {}", name, e);
+            AuthError::database("Failed to load job")
+        })?
+        .ok_or_else(|| AuthError::not_found(name))
+}
+
+/// Makes the job called `name` due now, or a 404 if there's no such job.
+pub(super) async fn request_run(state: &AppState, name: &str) -> Result<(), AuthError> {
+    let mut conn = get_db_conn(state).map_err(conn_error)?;
+    let _turn = state.writes.acquire().await;
+    let found = state.scheduler.run_now(&mut conn, name).map_err(|e| {
+        tracing::error!("Failed to request a run of job {}: {}", name, e);
+        AuthError::database("Failed to start job")
+    })?;
+    if !found {
+        return Err(AuthError::not_found(name));
+    }
+    Ok(())
+}
+
+/// Every scheduled job, with when it next runs and how its last run went.
+pub async fn list_jobs(State(state): State<AppState>, _admin: AdminUser) -> Result<ApiResponse<Vec<JobStatus>>, AuthError> {
+    let mut conn = get_db_conn(&state).map_err(conn_error)?;
+    let jobs = state.scheduler.statuses(&mut conn).map_err(|e| {
+        tracing::error!("Failed to load scheduled jobs: {}", e);
+        AuthError::database("Failed to load jobs")
+    })?;
+
+    Ok(ApiResponse::new(jobs))
+}
+
+/// Runs a job now rather than at its next scheduled time. If it's running already it isn't
+/// started twice.
+pub async fn run_job(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Path(name): Path<String>,
+) -> Result<(StatusCode, ApiResponse<RunJobResponse>), AuthError> {
+    request_run(&state, &name).await?;
+    tracing::info!("Admin {} started job {}", admin.user.id, name);
+
+    Ok((StatusCode::ACCEPTED, ApiResponse::new(RunJobResponse { message: format!("Job {} started", name) })))
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::handlers::posts::SLUG_REGEX;
+use crate::http::pagination::{SortDir, Sortable};
+
+pub mod audit;
+pub mod backfills;
+pub mod backups;
+pub mod duplicates;
+pub mod email_outbox;
+pub mod email_suppressions;
+pub mod feature_flags;
+pub mod impersonation;
+pub mod jobs;
+pub mod pages;
+pub mod reports;
+pub mod retention;
+pub mod search;
+pub mod users;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ListSuppressionsQuery {
+    #[serde(default)]
+    pub include_reactivated: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SearchQuery {
+    pub q: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreateBackfillRequest {
+    pub kind: String,
+}
+
+/// Sort keys for the admin user list.
+pub struct UserSort;
+
+impl Sortable for UserSort {
+    const SORT_FIELDS: &'static [&'static str] = &["created_at", "name", "email"];
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ListUsersQuery {
+    /// Name or email prefix.
+    pub q: Option<String>,
+    pub admin: Option<bool>,
+    pub verified: Option<bool>,
+    /// `active` or `suspended`.
+    pub status: Option<String>,
+    pub deleted: Option<bool>,
+}
+
+/// Sort keys for the moderation queue, oldest first so reports are handled in turn.
+pub struct ReportSort;
+
+impl Sortable for ReportSort {
+    const SORT_FIELDS: &'static [&'static str] = &["created_at"];
+    const DEFAULT_DIR: SortDir = SortDir::Asc;
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ListReportsQuery {
+    /// `open` (the default), `actioned` or `dismissed`.
+    pub status: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ListAuditLogsQuery {
+    pub user_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub event: Option<String>,
+    pub ip_address: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SetBlogStylesRequest {
+    pub disabled: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SetFeatureFlagRequest {
+    pub enabled: bool,
+}
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct CreatePageRequest {
+    #[validate(regex(path = *SLUG_REGEX, message = "Slug may only contain lowercase letters, digits and hyphens"))]
+    pub slug: String,
+
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    pub title: String,
+
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    #[serde(default)]
+    pub description: String,
+
+    #[validate(length(max = 200000, message = "Content must be at most 200000 characters"))]
+    pub content: String,
+
+    #[serde(default)]
+    pub published: bool,
+
+    pub commit_message: Option<String>,
+}
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct UpdatePageRequest {
+    #[validate(regex(path = *SLUG_REGEX, message = "Slug may only contain lowercase letters, digits and hyphens"))]
+    pub slug: Option<String>,
+
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    pub title: Option<String>,
+
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    pub description: Option<String>,
+
+    #[validate(length(max = 200000, message = "Content must be at most 200000 characters"))]
+    pub content: Option<String>,
+
+    /// Publishes or unpublishes the page.
+    pub published: Option<bool>,
+
+    pub commit_message: Option<String>,
+}
@@ -0,0 +1,293 @@
+use axum::extract::{Path, State};
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::SqliteConnection;
+use serde::Serialize;
+use validator::Validate;
+
+use crate::db::models::page::{PageChanges, Pages};
+use crate::db::models::page_version::PageVersions;
+use crate::errors::AuthError;
+use crate::handlers::admin::{CreatePageRequest, UpdatePageRequest};
+use crate::http::auth::AdminUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::audit::{self, AUDIT_ADMIN_PAGE_CREATED, AUDIT_ADMIN_PAGE_DELETED, AUDIT_ADMIN_PAGE_UPDATED};
+use crate::services::cache;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct PageResponse {
+    #[serde(flatten)]
+    pub page: Pages,
+    pub url: String,
+}
+
+impl PageResponse {
+    pub fn new(page: Pages) -> Self {
+        Self { url: page_path(&page.slug), page }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListPagesResponse {
+    pub pages: Vec<PageResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListPageVersionsResponse {
+    pub versions: Vec<PageVersions>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeletePageResponse {
+    pub message: String,
+}
+
+pub fn page_path(slug: &str) -> String {
+    format!("/p/{}", slug)
+}
+
+fn map_page_write_error(e: diesel::result::Error) -> AuthError {
+    match e {
+        diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _) => {
+            AuthError::conflict("A page with this slug already exists")
+        }
+        e => {
+            tracing::error!("Failed to save page: {}", e);
+            AuthError::database("Failed to save page")
+        }
+    }
+}
+
+fn db_conn(state: &AppState) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, AuthError> {
+    get_db_conn(state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while managing pages: {}", e);
+            AuthError::internal("Database connection failed")
+        })
+}
+
+fn load_page(conn: &mut SqliteConnection, id: &str) -> Result<Pages, AuthError> {
+    Pages::by_id(conn, id)
+        .map_err(|e| {
+            tracing::error!("Failed to load page {}: {}", id, e);
+            AuthError::database("Failed to load page")
+        })?
+        .ok_or_else(|| AuthError::not_found(id))
+}
+
+/// Forgets the rendered page at its old and new slug.
+async fn invalidate(state: &AppState, slugs: &[&str]) {
+    for slug in slugs {
+        if let Err(e) = state.cache.invalidate(&cache::static_page_key(slug)).await {
+            tracing::warn!("Failed to invalidate cached page {}: {}", slug, e);
+        }
+    }
+}
+
+pub async fn list_pages(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<ApiResponse<ListPagesResponse>, AuthError> {
+    let mut conn = db_conn(&state)?;
+
+    let pages = Pages::list(&mut conn)
+        .map_err(|e| {
+            tracing::error!("Failed to list pages: {}", e);
+            AuthError::database("Failed to list pages")
+        })?;
+
+    Ok(ApiResponse::new(ListPagesResponse { pages: pages.into_iter().map(PageResponse::new).collect() }))
+}
+
+pub async fn get_page(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(page_id): Path<String>,
+) -> Result<ApiResponse<PageResponse>, AuthError> {
+    let mut conn = db_conn(&state)?;
+    let page = load_page(&mut conn, &page_id)?;
+    Ok(ApiResponse::new(PageResponse::new(page)))
+}
+
+pub async fn create_page(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    AppJson(payload): AppJson<CreatePageRequest>,
+) -> Result<ApiResponse<PageResponse>, AuthError> {
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid page data", err))?;
+
+    let now = chrono::Utc::now().naive_utc();
+    let page = Pages {
+        id: uuid::Uuid::new_v4().to_string(),
+        slug: payload.slug,
+        title: payload.title,
+        description: payload.description,
+        content: payload.content,
+        published_at: payload.published.then_some(now),
+        created_by: Some(admin.user.id.clone()),
+        created_at: now,
+        updated_at: now,
+    };
+    let commit_message = payload.commit_message.unwrap_or_else(|| "Create page".to_string());
+
+    let mut conn = db_conn(&state)?;
+    let page = state.writes.write(&mut conn, |conn| {
+        let page = Pages::create(conn, &page)?;
+        PageVersions::record(conn, &page, &admin.user.id, &commit_message)?;
+        Ok(page)
+    })
+    .await
+    .map_err(map_page_write_error)?;
+    drop(conn);
+
+    invalidate(&state, &[&page.slug]).await;
+    audit::record(&state, &client, AUDIT_ADMIN_PAGE_CREATED, None, Some(&admin.user.id), Some(&page.slug)).await;
+
+    tracing::info!("Admin {} created page {}", admin.user.id, page.slug);
+
+    Ok(ApiResponse::new(PageResponse::new(page)))
+}
+
+pub async fn update_page(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(page_id): Path<String>,
+    AppJson(payload): AppJson<UpdatePageRequest>,
+) -> Result<ApiResponse<PageResponse>, AuthError> {
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid page data", err))?;
+
+    let mut conn = db_conn(&state)?;
+    let existing = load_page(&mut conn, &page_id)?;
+
+    let content_changed = payload.title.as_ref().is_some_and(|title| *title != existing.title)
+        || payload.description.as_ref().is_some_and(|description| *description != existing.description)
+        || payload.content.as_ref().is_some_and(|content| *content != existing.content);
+
+    let now = chrono::Utc::now().naive_utc();
+    // Republishing keeps the original publication date.
+    let published_at = match payload.published {
+        Some(true) if !existing.is_published() => Some(Some(now)),
+        Some(false) if existing.is_published() => Some(None),
+        _ => None,
+    };
+    let changes = PageChanges {
+        slug: payload.slug,
+        title: payload.title,
+        description: payload.description,
+        content: payload.content,
+        published_at,
+        updated_at: Some(now),
+    };
+    let commit_message = payload.commit_message.unwrap_or_else(|| "Update page".to_string());
+
+    let page = state.writes.write(&mut conn, |conn| {
+        let page = Pages::update(conn, &existing.id, &changes)?;
+        if content_changed {
+            PageVersions::record(conn, &page, &admin.user.id, &commit_message)?;
+        }
+        Ok(page)
+    })
+    .await
+    .map_err(map_page_write_error)?;
+    drop(conn);
+
+    invalidate(&state, &[&existing.slug, &page.slug]).await;
+    audit::record(&state, &client, AUDIT_ADMIN_PAGE_UPDATED, None, Some(&admin.user.id), Some(&page.slug)).await;
+
+    tracing::info!("Admin {} updated page {}", admin.user.id, page.slug);
+
+    Ok(ApiResponse::new(PageResponse::new(page)))
+}
+
+pub async fn delete_page(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(page_id): Path<String>,
+) -> Result<ApiResponse<DeletePageResponse>, AuthError> {
+    let mut conn = db_conn(&state)?;
+    let page = load_page(&mut conn, &page_id)?;
+
+    state.writes.write(&mut conn, |conn| Pages::delete(conn, &page.id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete page {}: {}", page.id, e);
+            AuthError::database("Failed to delete page")
+        })?;
+    drop(conn);
+
+    invalidate(&state, &[&page.slug]).await;
+    audit::record(&state, &client, AUDIT_ADMIN_PAGE_DELETED, None, Some(&admin.user.id), Some(&page.slug)).await;
+
+    tracing::info!("Admin {} deleted page {}", admin.user.id, page.slug);
+
+    Ok(ApiResponse::new(DeletePageResponse { message: "Page deleted".to_string() }))
+}
+
+pub async fn list_page_versions(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(page_id): Path<String>,
+) -> Result<ApiResponse<ListPageVersionsResponse>, AuthError> {
+    let mut conn = db_conn(&state)?;
+    let page = load_page(&mut conn, &page_id)?;
+
+    let versions = PageVersions::by_page(&mut conn, &page.id)
+        .map_err(|e| {
+            tracing::error!("Failed to list versions for page {}: {}", page.id, e);
+            AuthError::database("Failed to list page versions")
+        })?;
+
+    Ok(ApiResponse::new(ListPageVersionsResponse { versions }))
+}
+
+/// Brings back an earlier version's title, description and content, saved as a new version
+/// so the history only ever grows.
+pub async fn restore_page_version(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path((page_id, version_id)): Path<(String, String)>,
+) -> Result<ApiResponse<PageResponse>, AuthError> {
+    let mut conn = db_conn(&state)?;
+    let existing = load_page(&mut conn, &page_id)?;
+
+    let version = PageVersions::by_id_for_page(&mut conn, &version_id, &existing.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load version {} of page {}: {}", version_id, existing.id, e);
+            AuthError::database("Failed to restore page version")
+        })?
+        .ok_or_else(|| AuthError::not_found(&version_id))?;
+
+    let changes = PageChanges {
+        title: Some(version.title),
+        description: Some(version.description),
+        content: Some(version.content),
+        updated_at: Some(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
+    let commit_message = format!("Restore version {}", version.commit_hash.get(..7).unwrap_or(&version.commit_hash));
+
+    let page = state.writes.write(&mut conn, |conn| {
+        let page = Pages::update(conn, &existing.id, &changes)?;
+        PageVersions::record(conn, &page, &admin.user.id, &commit_message)?;
+        Ok(page)
+    })
+    .await
+    .map_err(map_page_write_error)?;
+    drop(conn);
+
+    invalidate(&state, &[&page.slug]).await;
+    audit::record(&state, &client, AUDIT_ADMIN_PAGE_UPDATED, None, Some(&admin.user.id), Some(&page.slug)).await;
+
+    tracing::info!("Admin {} restored page {} to version {}", admin.user.id, page.slug, version.id);
+
+    Ok(ApiResponse::new(PageResponse::new(page)))
+}
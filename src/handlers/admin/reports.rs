@@ -0,0 +1,262 @@
+use axum::extract::{Path, Query, State};
+use chrono::NaiveDateTime;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::SqliteConnection;
+use serde::Serialize;
+
+use crate::db::models::comment::Comments;
+use crate::db::models::notification::NOTIFICATION_KIND_CONTENT_HIDDEN;
+use crate::db::models::post::Posts;
+use crate::db::models::report::{
+    Reports, MODERATION_HIDDEN, MODERATION_VISIBLE, REPORT_STATUS_ACTIONED, REPORT_STATUS_DISMISSED, REPORT_STATUS_OPEN,
+};
+use crate::db::models::user_model::UserModel;
+use crate::db::queries::reports::ReportEntry;
+use crate::errors::AuthError;
+use crate::handlers::admin::{ListReportsQuery, ReportSort};
+use crate::http::auth::AdminUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::http::pagination::{ListParams, Paginated};
+use crate::services::audit::{
+    self, AUDIT_ADMIN_CONTENT_HIDDEN, AUDIT_ADMIN_CONTENT_RESTORED, AUDIT_ADMIN_REPORTS_DISMISSED,
+};
+use crate::services::{cache, notifications};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+const REPORT_STATUSES: &[&str] = &[REPORT_STATUS_OPEN, REPORT_STATUS_ACTIONED, REPORT_STATUS_DISMISSED];
+
+#[derive(Debug, Serialize)]
+pub struct AdminReportResponse {
+    pub id: String,
+    /// `None` for reports the spam check filed.
+    pub reporter_id: Option<String>,
+    pub reporter_name: Option<String>,
+    pub post_id: String,
+    pub post_title: String,
+    pub comment_id: Option<String>,
+    pub comment_body: Option<String>,
+    pub reason: String,
+    pub details: String,
+    pub status: String,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<ReportEntry> for AdminReportResponse {
+    fn from((report, reporter_name, post_title, comment_body): ReportEntry) -> Self {
+        Self {
+            id: report.id,
+            reporter_id: report.reporter_id,
+            reporter_name,
+            post_id: report.post_id,
+            post_title,
+            comment_id: report.comment_id,
+            comment_body,
+            reason: report.reason,
+            details: report.details,
+            status: report.status,
+            reviewed_by: report.reviewed_by,
+            reviewed_at: report.reviewed_at,
+            created_at: report.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModerationResponse {
+    pub message: String,
+    /// Open reports on the same post or comment that this closed, the one acted on included.
+    pub resolved: usize,
+}
+
+fn db_conn(state: &AppState) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, AuthError> {
+    get_db_conn(state).map_err(|e| {
+        tracing::error!("Failed to get database connection for moderation: {}", e);
+        AuthError::internal("Database connection failed")
+    })
+}
+
+fn load_report(conn: &mut SqliteConnection, id: &str) -> Result<Reports, AuthError> {
+    Reports::by_id(conn, id)
+        .map_err(|e| {
+            tracing::error!("Failed to load report {}: {}", id, e);
+            AuthError::database("Failed to load report")
+        })?
+        .ok_or_else(|| AuthError::not_found(id.to_string()))
+}
+
+/// Who wrote what `report` is about, and their name.
+fn reported_author(conn: &mut SqliteConnection, report: &Reports) -> Result<(String, Option<String>), AuthError> {
+    let author_id = match &report.comment_id {
+        Some(comment_id) => Comments::by_id(conn, comment_id).map(|comment| comment.map(|comment| comment.user_id)),
+        None => Posts::by_id(conn, &report.post_id).map(|post| post.map(|post| post.user_id)),
+    }
+    .map_err(|e| {
+        tracing::error!("Failed to load the content of report {}: {}", report.id, e);
+        AuthError::database("Failed to load reported content")
+    })?
+    .ok_or_else(|| AuthError::not_found(report.comment_id.clone().unwrap_or_else(|| report.post_id.clone())))?;
+
+    let name = UserModel::by_id(conn, &author_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load author {} of reported content: {}", author_id, e);
+            AuthError::database("Failed to load reported content")
+        })?
+        .map(|author| author.name);
+    Ok((author_id, name))
+}
+
+/// Hides the post or comment a report is about, or shows it again.
+fn set_moderation(conn: &mut SqliteConnection, report: &Reports, status: &str) -> diesel::QueryResult<usize> {
+    match &report.comment_id {
+        Some(comment_id) => Comments::set_moderation_status(conn, comment_id, status),
+        None => Posts::set_moderation_status(conn, &report.post_id, status),
+    }
+}
+
+fn target(report: &Reports) -> String {
+    match &report.comment_id {
+        Some(comment_id) => format!("comment {}", comment_id),
+        None => format!("post {}", report.post_id),
+    }
+}
+
+/// The moderation queue. `status` picks `open` reports (the default), `actioned` or
+/// `dismissed` ones; oldest first unless `dir=desc`.
+pub async fn list_reports(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    params: ListParams<ReportSort>,
+    Query(query): Query<ListReportsQuery>,
+) -> Result<ApiResponse<Paginated<AdminReportResponse>>, AuthError> {
+    let status = query.status.as_deref().unwrap_or(REPORT_STATUS_OPEN);
+    if !REPORT_STATUSES.contains(&status) {
+        return Err(AuthError::invalid_field(
+            "status",
+            "invalid",
+            format!("Status must be one of {}", REPORT_STATUSES.join(", ")),
+        ));
+    }
+
+    let mut conn = db_conn(&state)?;
+    let total = Reports::count_by_status(&mut conn, status)
+        .map_err(|e| {
+            tracing::error!("Failed to count reports: {}", e);
+            AuthError::database("Failed to list reports")
+        })?;
+    let reports = Reports::page_by_status(&mut conn, status, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to list reports: {}", e);
+            AuthError::database("Failed to list reports")
+        })?;
+
+    let reports = reports.into_iter().map(AdminReportResponse::from).collect();
+    Ok(ApiResponse::new(params.paginate(reports, total)))
+}
+
+/// Hides the reported post or comment from everyone but its author, closes every open report
+/// on it and tells the author.
+pub async fn hide_reported(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<ModerationResponse>, AuthError> {
+    let mut conn = db_conn(&state)?;
+    let report = load_report(&mut conn, &id)?;
+    let (author_id, author_name) = reported_author(&mut conn, &report)?;
+
+    let resolved = state.writes.write(&mut conn, |conn| {
+        set_moderation(conn, &report, MODERATION_HIDDEN)?;
+        let resolved = Reports::resolve_all_like(conn, &report, REPORT_STATUS_ACTIONED, &admin.user.id)?;
+        notifications::record(
+            conn,
+            &author_id,
+            NOTIFICATION_KIND_CONTENT_HIDDEN,
+            &admin.user.id,
+            Some(&report.post_id),
+            report.comment_id.as_deref(),
+        )?;
+        Ok(resolved)
+    })
+    .await
+    .map_err(|e: diesel::result::Error| {
+        tracing::error!("Failed to hide the content of report {}: {}", id, e);
+        AuthError::database("Failed to hide content")
+    })?;
+    drop(conn);
+
+    if let Some(name) = author_name {
+        cache::invalidate_author_pages(state.cache.as_ref(), &name).await;
+    }
+
+    let target = target(&report);
+    audit::record(&state, &client, AUDIT_ADMIN_CONTENT_HIDDEN, Some(&author_id), Some(&admin.user.id), Some(&target)).await;
+    tracing::info!("Admin {} hid {} after report {}", admin.user.id, target, id);
+
+    Ok(ApiResponse::new(ModerationResponse { message: "Content hidden".to_string(), resolved }))
+}
+
+/// Lets the reported post or comment stand, closing every open report on it. A comment the spam
+/// check held for review is published.
+pub async fn dismiss_report(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<ModerationResponse>, AuthError> {
+    let mut conn = db_conn(&state)?;
+    let report = load_report(&mut conn, &id)?;
+
+    let resolved = state.writes.write(&mut conn, |conn| {
+        if let Some(comment_id) = &report.comment_id {
+            Comments::release_pending(conn, comment_id)?;
+        }
+        Reports::resolve_all_like(conn, &report, REPORT_STATUS_DISMISSED, &admin.user.id)
+    })
+    .await
+    .map_err(|e: diesel::result::Error| {
+        tracing::error!("Failed to dismiss report {}: {}", id, e);
+        AuthError::database("Failed to dismiss report")
+    })?;
+    drop(conn);
+
+    let target = target(&report);
+    audit::record(&state, &client, AUDIT_ADMIN_REPORTS_DISMISSED, None, Some(&admin.user.id), Some(&target)).await;
+    tracing::info!("Admin {} dismissed {} reports on {}", admin.user.id, resolved, target);
+
+    Ok(ApiResponse::new(ModerationResponse { message: "Reports dismissed".to_string(), resolved }))
+}
+
+/// Shows content hidden after a report again, e.g. when hiding it was a mistake. The reports
+/// stay closed.
+pub async fn restore_reported(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<ModerationResponse>, AuthError> {
+    let mut conn = db_conn(&state)?;
+    let report = load_report(&mut conn, &id)?;
+    let (author_id, author_name) = reported_author(&mut conn, &report)?;
+
+    set_moderation(&mut conn, &report, MODERATION_VISIBLE)
+        .map_err(|e| {
+            tracing::error!("Failed to restore the content of report {}: {}", id, e);
+            AuthError::database("Failed to restore content")
+        })?;
+    drop(conn);
+
+    if let Some(name) = author_name {
+        cache::invalidate_author_pages(state.cache.as_ref(), &name).await;
+    }
+
+    let target = target(&report);
+    audit::record(&state, &client, AUDIT_ADMIN_CONTENT_RESTORED, Some(&author_id), Some(&admin.user.id), Some(&target)).await;
+    tracing::info!("Admin {} restored {}", admin.user.id, target);
+
+    Ok(ApiResponse::new(ModerationResponse { message: "Content restored".to_string(), resolved: 0 }))
+}
@@ -0,0 +1,43 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Serialize;
+
+use crate::errors::AuthError;
+use crate::handlers::admin::jobs::{job_status, request_run};
+use crate::http::auth::AdminUser;
+use crate::http::dto::ApiResponse;
+use crate::services::retention::RetentionReport;
+use crate::services::scheduler::JobStatus;
+use crate::state::AppState;
+
+const RETENTION_JOB: &str = "retention";
+
+#[derive(Debug, Serialize)]
+pub struct RetentionStatus {
+    #[serde(flatten)]
+    pub job: JobStatus,
+    #[serde(flatten)]
+    pub report: RetentionReport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunRetentionResponse {
+    pub message: String,
+}
+
+/// Rows pruned per table by the retention job, on the last run and since startup.
+pub async fn retention_status(State(state): State<AppState>, _admin: AdminUser) -> Result<ApiResponse<RetentionStatus>, AuthError> {
+    let job = job_status(&state, RETENTION_JOB)?;
+    Ok(ApiResponse::new(RetentionStatus { job, report: state.retention.report() }))
+}
+
+/// Starts a pruning run now rather than at `RETENTION_RUN_HOUR`.
+pub async fn run_retention(
+    State(state): State<AppState>,
+    admin: AdminUser,
+) -> Result<(StatusCode, ApiResponse<RunRetentionResponse>), AuthError> {
+    request_run(&state, RETENTION_JOB).await?;
+    tracing::info!("Admin {} started a retention run", admin.user.id);
+
+    Ok((StatusCode::ACCEPTED, ApiResponse::new(RunRetentionResponse { message: "Retention run started".to_string() })))
+}
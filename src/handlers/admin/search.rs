@@ -0,0 +1,143 @@
+use axum::extract::{Query, State};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::models::post::Posts;
+use crate::db::models::refresh_token::RefreshTokens;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::admin::SearchQuery;
+use crate::http::auth::AdminUser;
+use crate::http::dto::ApiResponse;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+const RESULTS_PER_CATEGORY: i64 = 20;
+
+/// A single search hit, tagged with the kind of record it points at.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SearchResult {
+    User {
+        id: String,
+        name: String,
+        email: String,
+        email_verified: bool,
+        is_admin: bool,
+        created_at: NaiveDateTime,
+        deleted_at: Option<NaiveDateTime>,
+    },
+    Post {
+        id: String,
+        author_id: String,
+        title: String,
+        slug: String,
+        status: String,
+        updated_at: NaiveDateTime,
+    },
+    Session {
+        id: String,
+        user_id: String,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+        device: Option<String>,
+        created_at: NaiveDateTime,
+        expires_at: NaiveDateTime,
+    },
+}
+
+impl From<UserModel> for SearchResult {
+    fn from(user: UserModel) -> Self {
+        Self::User {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            email_verified: user.email_verified,
+            is_admin: user.is_admin,
+            created_at: user.created_at,
+            deleted_at: user.deleted_at,
+        }
+    }
+}
+
+impl From<Posts> for SearchResult {
+    fn from(post: Posts) -> Self {
+        Self::Post {
+            id: post.id,
+            author_id: post.user_id,
+            title: post.title,
+            slug: post.slug,
+            status: post.status,
+            updated_at: post.updated_at,
+        }
+    }
+}
+
+impl From<RefreshTokens> for SearchResult {
+    fn from(session: RefreshTokens) -> Self {
+        Self::Session {
+            id: session.id,
+            user_id: session.user_id,
+            ip_address: session.ip_address,
+            user_agent: session.user_agent,
+            device: session.device,
+            created_at: session.created_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub results: Vec<SearchResult>,
+}
+
+/// Looks `q` up as a user email, name or id, a post slug or id, and a session IP address.
+/// Each category is capped separately so a broad prefix can't crowd out the others.
+pub async fn search(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    Query(query): Query<SearchQuery>,
+) -> Result<ApiResponse<SearchResponse>, AuthError> {
+    let term = query.q.trim().to_string();
+    if term.is_empty() {
+        return Err(AuthError::validation("Search query must not be empty"));
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during admin search: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let users = UserModel::search(&mut conn, &term, RESULTS_PER_CATEGORY)
+        .map_err(|e| {
+            tracing::error!("Failed to search users for {:?}: {}", term, e);
+            AuthError::database("Failed to search users")
+        })?;
+
+    let posts = Posts::search(&mut conn, &term, RESULTS_PER_CATEGORY)
+        .map_err(|e| {
+            tracing::error!("Failed to search posts for {:?}: {}", term, e);
+            AuthError::database("Failed to search posts")
+        })?;
+
+    drop(conn);
+
+    let sessions = state.sessions.search(&term, RESULTS_PER_CATEGORY)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to search sessions for {:?}: {}", term, e);
+            AuthError::database("Failed to search sessions")
+        })?;
+
+    tracing::info!("Admin {} searched for {:?}", admin.user.id, term);
+
+    let results = users.into_iter().map(SearchResult::from)
+        .chain(posts.into_iter().map(SearchResult::from))
+        .chain(sessions.into_iter().map(SearchResult::from))
+        .collect();
+
+    Ok(ApiResponse::new(SearchResponse { query: term, results }))
+}
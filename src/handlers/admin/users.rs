@@ -0,0 +1,385 @@
+use axum::extract::{Path, Query, State};
+use diesel::result::Error as DieselError;
+use chrono::NaiveDateTime;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::SqliteConnection;
+use serde::Serialize;
+
+use crate::db::models::onboarding_step::ONBOARDING_STEP_VERIFY_EMAIL;
+use crate::db::models::report::MODERATION_VISIBLE;
+use crate::db::models::user_model::{UserModel, USER_STATUS_ACTIVE, USER_STATUS_SUSPENDED};
+use crate::db::queries::users::UserFilter;
+use crate::errors::AuthError;
+use crate::handlers::admin::{ListUsersQuery, SetBlogStylesRequest, UserSort};
+use crate::http::auth::AdminUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::http::locale::Locale;
+use crate::http::pagination::{ListParams, Paginated};
+use crate::services::audit::{
+    self, AUDIT_ADMIN_BLOG_STYLES_CHANGED, AUDIT_ADMIN_EMAIL_VERIFIED, AUDIT_ADMIN_PASSWORD_RESET_SENT,
+    AUDIT_ADMIN_USER_PURGED, AUDIT_ADMIN_USER_SUSPENDED, AUDIT_ADMIN_USER_TRUSTED, AUDIT_ADMIN_USER_UNSUSPENDED,
+};
+use crate::services::password_reset::send_reset_email;
+use crate::services::{avatars, cache, onboarding};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct AdminUserResponse {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub is_admin: bool,
+    pub status: String,
+    /// Where their new comments start out: `visible`, or held back after a flagged signup.
+    pub comment_moderation: String,
+    pub blog_styles_disabled: bool,
+    pub created_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl From<UserModel> for AdminUserResponse {
+    fn from(user: UserModel) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            email_verified: user.email_verified,
+            is_admin: user.is_admin,
+            status: user.status,
+            comment_moderation: user.comment_moderation,
+            blog_styles_disabled: user.blog_styles_disabled,
+            created_at: user.created_at,
+            deleted_at: user.deleted_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurgeUserResponse {
+    pub message: String,
+    pub purged_at: chrono::DateTime<chrono::Utc>,
+    /// Uploads and exports deleted from storage.
+    pub files_deleted: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasswordResetSentResponse {
+    pub message: String,
+    /// False when a reset link was sent moments ago and this one was skipped.
+    pub sent: bool,
+}
+
+fn db_conn(state: &AppState, action: &str) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, AuthError> {
+    get_db_conn(state).map_err(|e| {
+        tracing::error!("Failed to get database connection while {}: {}", action, e);
+        AuthError::internal("Database connection failed")
+    })
+}
+
+fn load_user(conn: &mut SqliteConnection, id: &str) -> Result<UserModel, AuthError> {
+    UserModel::by_id(conn, id)
+        .map_err(|e| {
+            tracing::error!("Failed to load user {}: {}", id, e);
+            AuthError::database("Failed to load user")
+        })?
+        .ok_or_else(|| AuthError::not_found(id.to_string()))
+}
+
+/// Every account, deleted ones included. Filter with `q` (name or email prefix), `admin`,
+/// `verified`, `status` and `deleted`; sort by `created_at` (the default), `name` or `email`.
+pub async fn list_users(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    params: ListParams<UserSort>,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<ApiResponse<Paginated<AdminUserResponse>>, AuthError> {
+    let term = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let filter = UserFilter {
+        query: term,
+        is_admin: query.admin,
+        email_verified: query.verified,
+        deleted: query.deleted,
+        status: query.status.as_deref(),
+    };
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing users: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let total = UserModel::count_filtered(&mut conn, &filter)
+        .map_err(|e| {
+            tracing::error!("Failed to count users: {}", e);
+            AuthError::database("Failed to list users")
+        })?;
+
+    let users = UserModel::page_filtered(&mut conn, &filter, params.sort, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to list users: {}", e);
+            AuthError::database("Failed to list users")
+        })?;
+
+    let users = users.into_iter().map(AdminUserResponse::from).collect();
+
+    Ok(ApiResponse::new(params.paginate(users, total)))
+}
+
+/// Deletes the user for good: their credentials, posts, comments and uploads, then the files
+/// they left in storage.
+pub async fn purge_user(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<PurgeUserResponse>, AuthError> {
+    if admin.user.id == id {
+        return Err(AuthError::forbidden("Admins cannot purge their own account"));
+    }
+
+    let mut conn = db_conn(&state, "purging a user")?;
+
+    let user = UserModel::by_id_with_deleted(&mut conn, &id)
+        .map_err(|e| {
+            tracing::error!("Failed to load user {} to purge: {}", id, e);
+            AuthError::database("Failed to purge user")
+        })?
+        .ok_or_else(|| AuthError::not_found(id.clone()))?;
+
+    // Collected first; the rows naming them go with the user.
+    let files = UserModel::stored_files(&mut conn, &id)
+        .map_err(|e| {
+            tracing::error!("Failed to list stored files of user {}: {}", id, e);
+            AuthError::database("Failed to purge user")
+        })?;
+
+    let deleted = state.writes.write(&mut conn, |conn| UserModel::purge(conn, &id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to purge user {}: {}", id, e);
+            AuthError::database("Failed to purge user")
+        })?;
+    drop(conn);
+
+    if deleted == 0 {
+        return Err(AuthError::not_found(id));
+    }
+
+    // Storage failures only leave unreferenced files behind, so the purge still stands.
+    let mut files_deleted = 0;
+    for key in &files {
+        match state.storage.delete(key).await {
+            Ok(()) => files_deleted += 1,
+            Err(e) => tracing::warn!("Failed to delete {} of purged user {}: {}", key, id, e),
+        }
+    }
+    if let Some(avatar) = user.avatar_url.as_deref().and_then(avatars::uploaded_id) {
+        avatars::delete_uploaded(state.storage.as_ref(), avatar).await;
+    }
+
+    // The database cascade only covers sessions kept in the database.
+    if let Err(e) = state.sessions.delete_by_user(&id).await {
+        tracing::error!("Failed to end sessions of deleted user {}: {}", id, e);
+    }
+
+    cache::invalidate_user(state.cache.as_ref(), &id).await;
+    cache::invalidate_all_pages(state.cache.as_ref()).await;
+
+    audit::record(&state, &client, AUDIT_ADMIN_USER_PURGED, Some(&id), Some(&admin.user.id), None).await;
+
+    tracing::info!("Admin {} purged user {}", admin.user.id, id);
+
+    Ok(ApiResponse::new(PurgeUserResponse {
+        message: "User purged".to_string(),
+        purged_at: chrono::Utc::now(),
+        files_deleted,
+    }))
+}
+
+/// Marks the user's email verified without a link, e.g. after confirming it some other way.
+pub async fn verify_user_email(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<AdminUserResponse>, AuthError> {
+    let mut conn = db_conn(&state, "verifying an email")?;
+    let user = load_user(&mut conn, &id)?;
+    if user.email_verified {
+        return Ok(ApiResponse::new(AdminUserResponse::from(user)));
+    }
+
+    state.writes.write(&mut conn, |conn| {
+        UserModel::mark_email_verified(conn, &id)?;
+        if let Err(e) = onboarding::complete(conn, &id, ONBOARDING_STEP_VERIFY_EMAIL) {
+            tracing::warn!("Failed to update onboarding for user {}: {}", id, e);
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e: DieselError| {
+        tracing::error!("Failed to mark user {} as verified: {}", id, e);
+        AuthError::database("Failed to verify email")
+    })?;
+    let user = load_user(&mut conn, &id)?;
+    drop(conn);
+
+    cache::invalidate_user(state.cache.as_ref(), &id).await;
+    audit::record(&state, &client, AUDIT_ADMIN_EMAIL_VERIFIED, Some(&id), Some(&admin.user.id), None).await;
+    tracing::info!("Admin {} verified the email of user {}", admin.user.id, id);
+
+    Ok(ApiResponse::new(AdminUserResponse::from(user)))
+}
+
+/// Locks the user out: signing in is refused, their sessions end and every token they hold,
+/// API tokens included, stops working until they're unsuspended. Admins can't be suspended.
+pub async fn suspend_user(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<AdminUserResponse>, AuthError> {
+    if admin.user.id == id {
+        return Err(AuthError::forbidden("Admins cannot suspend their own account"));
+    }
+    let mut conn = db_conn(&state, "suspending a user")?;
+    if load_user(&mut conn, &id)?.is_admin {
+        return Err(AuthError::forbidden("Admins cannot be suspended"));
+    }
+    let user = set_status(&state, &mut conn, &id, USER_STATUS_SUSPENDED).await?;
+    drop(conn);
+
+    if let Err(e) = state.sessions.delete_by_user(&id).await {
+        tracing::error!("Failed to end sessions of suspended user {}: {}", id, e);
+    }
+    cache::invalidate_user(state.cache.as_ref(), &id).await;
+
+    audit::record(&state, &client, AUDIT_ADMIN_USER_SUSPENDED, Some(&id), Some(&admin.user.id), None).await;
+    tracing::info!("Admin {} suspended user {}", admin.user.id, id);
+
+    Ok(ApiResponse::new(AdminUserResponse::from(user)))
+}
+
+/// Lifts a suspension. The user signs in again; sessions ended by the suspension stay ended.
+pub async fn unsuspend_user(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<AdminUserResponse>, AuthError> {
+    let mut conn = db_conn(&state, "unsuspending a user")?;
+    load_user(&mut conn, &id)?;
+    let user = set_status(&state, &mut conn, &id, USER_STATUS_ACTIVE).await?;
+    drop(conn);
+
+    cache::invalidate_user(state.cache.as_ref(), &id).await;
+
+    audit::record(&state, &client, AUDIT_ADMIN_USER_UNSUSPENDED, Some(&id), Some(&admin.user.id), None).await;
+    tracing::info!("Admin {} unsuspended user {}", admin.user.id, id);
+
+    Ok(ApiResponse::new(AdminUserResponse::from(user)))
+}
+
+/// Lifts the hold a flagged signup put on the user's comments, so new ones are published
+/// straight away. Comments already held stay where they are.
+pub async fn trust_user(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<AdminUserResponse>, AuthError> {
+    let mut conn = db_conn(&state, "trusting a user")?;
+    load_user(&mut conn, &id)?;
+    let user = state.writes.write(&mut conn, |conn| UserModel::set_comment_moderation(conn, &id, MODERATION_VISIBLE))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to trust user {}: {}", id, e);
+            AuthError::database("Failed to update user")
+        })?;
+    drop(conn);
+
+    cache::invalidate_user(state.cache.as_ref(), &id).await;
+
+    audit::record(&state, &client, AUDIT_ADMIN_USER_TRUSTED, Some(&id), Some(&admin.user.id), None).await;
+    tracing::info!("Admin {} trusted user {}", admin.user.id, id);
+
+    Ok(ApiResponse::new(AdminUserResponse::from(user)))
+}
+
+async fn set_status(state: &AppState, conn: &mut SqliteConnection, id: &str, status: &str) -> Result<UserModel, AuthError> {
+    state.writes.write(conn, |conn| UserModel::set_status(conn, id, status))
+        .await
+        .map_err(|e| match e {
+            DieselError::NotFound => AuthError::not_found(id.to_string()),
+            e => {
+                tracing::error!("Failed to set status of user {} to {}: {}", id, status, e);
+                AuthError::database("Failed to update user")
+            }
+        })
+}
+
+/// Emails the user a password reset link, the same one "forgot password" sends.
+pub async fn send_password_reset(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<PasswordResetSentResponse>, AuthError> {
+    let mut conn = db_conn(&state, "sending a password reset")?;
+    let user = load_user(&mut conn, &id)?;
+
+    let sent = send_reset_email(&state, &mut conn, &user, Locale::of_user(user.locale.as_deref()))
+        .await
+        .inspect_err(|e| tracing::error!("Failed to send password reset email to user {}: {}", id, e))?;
+    drop(conn);
+
+    let message = if sent {
+        audit::record(&state, &client, AUDIT_ADMIN_PASSWORD_RESET_SENT, Some(&id), Some(&admin.user.id), None).await;
+        tracing::info!("Admin {} sent a password reset link to user {}", admin.user.id, id);
+        "Password reset link sent"
+    } else {
+        "A password reset link was sent moments ago"
+    };
+
+    Ok(ApiResponse::new(PasswordResetSentResponse { message: message.to_string(), sent }))
+}
+
+/// Kill-switch for one blog's custom CSS and head snippets, e.g. after abuse. The owner's saved
+/// styles are kept and come back if the switch is turned off again.
+pub async fn set_blog_styles(
+    State(state): State<AppState>,
+    admin: AdminUser,
+    client: ClientInfo,
+    Path(id): Path<String>,
+    AppJson(payload): AppJson<SetBlogStylesRequest>,
+) -> Result<ApiResponse<AdminUserResponse>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while updating blog styles for {}: {}", id, e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let user = state.writes.write(&mut conn, |conn| UserModel::set_blog_styles_disabled(conn, &id, payload.disabled))
+        .await
+        .map_err(|e| match e {
+            DieselError::NotFound => AuthError::not_found(id.clone()),
+            e => {
+                tracing::error!("Failed to update blog styles for {}: {}", id, e);
+                AuthError::database("Failed to update blog styles")
+            }
+        })?;
+
+    cache::invalidate_user(state.cache.as_ref(), &user.id).await;
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    let change = if payload.disabled { "disabled" } else { "enabled" };
+    audit::record(&state, &client, AUDIT_ADMIN_BLOG_STYLES_CHANGED, Some(&user.id), Some(&admin.user.id), Some(change)).await;
+
+    tracing::info!("Admin {} {} blog styles for user {}", admin.user.id, change, user.id);
+
+    Ok(ApiResponse::new(AdminUserResponse::from(user)))
+}
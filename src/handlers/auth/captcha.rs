@@ -0,0 +1,25 @@
+use axum::extract::State;
+use serde::Serialize;
+
+use crate::http::dto::ApiResponse;
+use crate::services::captcha::{CaptchaEndpoint, CaptchaProvider};
+use crate::state::AppState;
+
+/// What a client needs to render the captcha widget.
+#[derive(Debug, Serialize)]
+pub struct CaptchaSettingsResponse {
+    pub provider: Option<CaptchaProvider>,
+    pub site_key: Option<String>,
+    pub signup: bool,
+    pub password_reset: bool,
+}
+
+pub async fn captcha_settings(State(state): State<AppState>) -> ApiResponse<CaptchaSettingsResponse> {
+    let config = state.config.load();
+    ApiResponse::new(CaptchaSettingsResponse {
+        provider: config.captcha_provider(),
+        site_key: config.captcha_site_key().map(str::to_string),
+        signup: config.captcha_required(CaptchaEndpoint::Signup),
+        password_reset: config.captcha_required(CaptchaEndpoint::PasswordReset),
+    })
+}
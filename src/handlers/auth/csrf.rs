@@ -0,0 +1,10 @@
+use tsumi_types::CsrfResponse;
+
+use crate::http::csrf::{CsrfToken, CSRF_HEADER};
+use crate::http::dto::ApiResponse;
+
+/// The caller's CSRF token, set as a cookie if they don't have one. The same token is returned
+/// until the cookie goes away, so open tabs don't invalidate each other.
+pub async fn csrf_token(CsrfToken(token): CsrfToken) -> ApiResponse<CsrfResponse> {
+    ApiResponse::new(CsrfResponse { csrf_token: token, header: CSRF_HEADER.to_string() })
+}
@@ -1,21 +1,32 @@
 use std::error::Error;
 use axum::extract::{Query, State};
 use axum::response::Redirect;
+use base64::Engine;
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
 use http::header;
-use reqwest::Client;
 use serde::Deserialize;
-use tower_cookies::{Cookie, Cookies};
-use tower_cookies::cookie::SameSite;
+use tower_cookies::Cookies;
+use crate::handlers::auth::NextQuery;
+use crate::http::features::Enabled;
+use crate::http::locale::Locale;
+use crate::http::redirect::next_or_home;
+use crate::services::cookies::{self, AuthCookie};
+use crate::services::feature_flags::GithubOAuthFeature;
+use crate::services::flash::{self, FlashLevel};
+use crate::services::http_client::HttpClient;
 use crate::state::AppState;
-use crate::utils::{create_jwt};
+use crate::utils::{constant_time_eq, create_jwt, generate_token};
 use std::fmt;
 use time::Duration;
 
-// todo: prevent csrf attacks
+/// How long the user has to get through GitHub's consent screen.
+const OAUTH_STATE_MINUTES: i64 = 10;
+
 // todo: add persistent logins
 #[derive(Deserialize)]
 pub struct GithubCallback {
     code: String,
+    state: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -57,54 +68,84 @@ impl fmt::Display for GithubOAuthError {
 
 impl Error for GithubOAuthError {}
 
-pub async fn github_oauth_start(State(state): State<AppState>) -> Redirect {
-    let client_id = state.config.github_auth_client_id();
-    Redirect::to(&format!("https://github\
-    .com/login/oauth/authorize?client_id={}&scope=read:user", client_id))
+/// Sends the user to GitHub with a random `state`, kept alongside the page to return to in a
+/// short-lived cookie that the callback checks.
+pub async fn github_oauth_start(
+    State(state): State<AppState>,
+    _enabled: Enabled<GithubOAuthFeature>,
+    Query(params): Query<NextQuery>,
+    cookies: Cookies,
+) -> Redirect {
+    let config = state.config.load();
+    let oauth_state = generate_token();
+    let next = next_or_home(config.public_url(), params.next.as_deref());
+    let value = format!("{}.{}", oauth_state, BASE64_URL_SAFE_NO_PAD.encode(&next));
+    cookies::set(&cookies, &config, AuthCookie::OauthState, value, Some(Duration::minutes(OAUTH_STATE_MINUTES)));
+
+    Redirect::to(&format!(
+        "https://github.com/login/oauth/authorize?client_id={}&scope=read:user&state={}",
+        config.github_auth_client_id(),
+        oauth_state,
+    ))
 }
 
-pub async fn github_oauth_callback(State(state):State<AppState>, params: Query<GithubCallback>,
+pub async fn github_oauth_callback(State(state):State<AppState>, _enabled: Enabled<GithubOAuthFeature>, params: Query<GithubCallback>,
                                    cookies:
-Cookies) ->
+Cookies, locale: Locale) ->
                                                                                         Redirect {
-    handle_github_oauth(params, cookies, &state).await.unwrap_or_else(|e| {
+    handle_github_oauth(params, cookies.clone(), &state).await.unwrap_or_else(|e| {
         tracing::error!("OAuth error: {}", e);
-        Redirect::to("/login?error=oauth_failed")
+        flash::push(&cookies, &state.config.load(), FlashLevel::Error, locale.text("flash.github_failed"));
+        Redirect::to("/login")
     })
 }
 async fn handle_github_oauth(params: Query<GithubCallback>, cookies: Cookies, state: &AppState
 ) ->
                                                                                Result<Redirect, GithubOAuthError> {
-    let client = Client::new();
+    let client = &state.http;
 
     tracing::info!("Processing github oauth callback, {}", params.code);
 
-    let token = exchange_code_for_token(&client, &params.code, &state).await?;
-    let user = get_github_user(&client, &token.access_token).await?;
-    let jwt = create_jwt(&user.login, &state).await.map_err(|e|
+    let next = take_oauth_state(&cookies, state, params.state.as_deref())?;
+    let token = exchange_code_for_token(client, &params.code, state).await?;
+    let user = get_github_user(client, &token.access_token).await?;
+    let jwt = create_jwt(&user.login, state).await.map_err(|e|
         GithubOAuthError::JwtCreationError(e.to_string()))?;
 
-    let cookie = Cookie::build(("auth_token", jwt))
-        .http_only(true)
-        .path("/")
-        .secure(true)
-        .same_site(SameSite::Strict)
-        .max_age(Duration::hours(8))
-        .build();
-
-    cookies.add(cookie);
+    cookies::set(&cookies, &state.config.load(), AuthCookie::Access, jwt, Some(Duration::hours(8)));
 
     tracing::info!("Successfully processed github oauth callback");
-    Ok(Redirect::to("/"))
+    Ok(Redirect::to(&next))
 }
 
-async fn get_github_user(client: &Client, access_token: &str) -> Result<GithubUser, GithubOAuthError> {
-    let response = client
+/// Checks the callback's `state` against the cookie set by [`github_oauth_start`] and returns
+/// the page to send the user back to. The cookie only works once.
+fn take_oauth_state(cookies: &Cookies, state: &AppState, returned: Option<&str>) -> Result<String, GithubOAuthError> {
+    let config = state.config.load();
+    let stored = cookies::get(cookies, &config, AuthCookie::OauthState);
+    cookies::clear(cookies, &config, AuthCookie::OauthState);
+
+    let (expected, next) = stored
+        .as_deref()
+        .and_then(|value| value.split_once('.'))
+        .ok_or(GithubOAuthError::CsrfError)?;
+    if !returned.is_some_and(|returned| constant_time_eq(returned.as_bytes(), expected.as_bytes())) {
+        return Err(GithubOAuthError::CsrfError);
+    }
+
+    // Checked again on the way out, as cookies can be edited.
+    let next = BASE64_URL_SAFE_NO_PAD.decode(next).ok().and_then(|next| String::from_utf8(next).ok());
+    Ok(next_or_home(config.public_url(), next.as_deref()))
+}
+
+async fn get_github_user(client: &HttpClient, access_token: &str) -> Result<GithubUser, GithubOAuthError> {
+    let request = client
         .get("https://api.github.com/user")
         .header(header::ACCEPT, "application/json")
         .header(header::USER_AGENT, "tsumi/1.0")
-        .header("Authorization", format!("Bearer {}", access_token))
-        .send()
+        .header("Authorization", format!("Bearer {}", access_token));
+    let response = client
+        .send_idempotent(request)
         .await
         .map_err(GithubOAuthError::NetworkError)?;
 
@@ -131,7 +172,8 @@ async fn get_github_user(client: &Client, access_token: &str) -> Result<GithubUs
     Ok(user)
 }
 
-async fn exchange_code_for_token(client: &Client, code: &str, state: &AppState) ->
+/// Not retried: the code only works once.
+async fn exchange_code_for_token(client: &HttpClient, code: &str, state: &AppState) ->
                                                                                  Result<GithubToken, GithubOAuthError> {
 
     let response = client
@@ -140,8 +182,8 @@ async fn exchange_code_for_token(client: &Client, code: &str, state: &AppState)
         .header(header::USER_AGENT, "tsumi/1.0")
         .json(&serde_json::json!({
             "code": code,
-            "client_id": state.config.github_auth_client_id(),
-            "client_secret": state.config.github_auth_client_secret(),
+            "client_id": state.config.load().github_auth_client_id(),
+            "client_secret": state.config.load().github_auth_client_secret(),
         })).send().await.map_err(GithubOAuthError::NetworkError)?;
 
     if !response.status().is_success() {
@@ -1,63 +1,80 @@
-use chrono::NaiveDateTime;
-use diesel::Insertable;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use validator::Validate;
-use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::services::normalize::{self, Normalize};
 
 pub mod signup;
 pub mod signin;
 pub mod signout;
 pub mod refresh;
 pub mod github;
+pub mod reauth;
+pub mod verify;
+pub mod password_reset;
+pub mod captcha;
+pub mod csrf;
 
-#[derive(Validate, Deserialize,Insertable,  Debug)]
-#[diesel(table_name = crate::db::schema::users)]
-pub struct SignUpRequest {
-    #[validate(length(min = 3, max = 50, message = "Username must be between 3 and 50 characters.\
-    ."))]
-    pub name: String,
+pub use tsumi_types::{SignInRequest, SignUpRequest};
 
-    #[validate(email(message = "Email must be a valid email."))]
-    pub email: String,
+impl Normalize for SignUpRequest {
+    fn normalize(mut self) -> Result<Self, AuthError> {
+        self.name = normalize::username(&self.name)?;
+        self.email = normalize::email(&self.email);
+        Ok(self)
+    }
+}
 
-    #[validate(length(min = 8, max = 128, message = "Password must be between 8 and 128 characters"))]
-    pub password: String,
+/// Where to go once signed in; checked with `http::redirect` before it's followed.
+#[derive(Deserialize, Debug)]
+pub struct NextQuery {
+    pub next: Option<String>,
 }
 
-#[derive(Insertable, Debug)]
-#[diesel(table_name = crate::db::schema::email_verification_tokens)]
-pub struct NewEmailVerificationTable {
+#[derive(Deserialize, Debug)]
+pub struct VerifyEmailQuery {
     pub token: String,
-    pub expires_at: String,
 }
 
-#[derive(Validate, Deserialize,Insertable,  Debug)]
-#[diesel(table_name = crate::db::schema::users)]
-pub struct SignInRequest {
+impl Normalize for SignInRequest {
+    fn normalize(mut self) -> Result<Self, AuthError> {
+        self.email = normalize::email(&self.email);
+        Ok(self)
+    }
+}
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct ForgotPasswordRequest {
     #[validate(email(message = "Email must be a valid email."))]
     pub email: String,
 
+    /// The solved captcha, when password resets need one.
+    #[serde(default)]
+    pub captcha_token: Option<String>,
+}
+
+impl Normalize for ForgotPasswordRequest {
+    fn normalize(mut self) -> Result<Self, AuthError> {
+        self.email = normalize::email(&self.email);
+        Ok(self)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ResetPasswordQuery {
+    #[serde(default)]
+    pub token: String,
+}
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+
     #[validate(length(min = 8, max = 128, message = "Password must be between 8 and 128 characters"))]
     pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SignUpResponse {
-    pub id: String,
-    pub username: String,
-    pub email: String,
-    pub email_verified: bool,
-    pub created_at: NaiveDateTime,
-}
-
-impl From<UserModel> for SignUpResponse {
-    fn from(user: UserModel) -> Self {
-        Self {
-            id: user.id,
-            username: user.name,
-            email: user.email,
-            email_verified: user.email_verified,
-            created_at: user.created_at,
-        }
-    }
+#[derive(Validate, Deserialize, Debug)]
+pub struct ReauthRequest {
+    #[validate(length(min = 1, max = 128, message = "Password is required"))]
+    pub password: String,
 }
\ No newline at end of file
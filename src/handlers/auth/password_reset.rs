@@ -0,0 +1,131 @@
+use axum::extract::State;
+use serde::Serialize;
+use validator::Validate;
+
+use crate::db::models::reset_token::ResetTokens;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::auth::{ForgotPasswordRequest, ResetPasswordRequest};
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::audit::{self, AUDIT_PASSWORD_RESET, AUDIT_PASSWORD_RESET_REQUESTED};
+use crate::services::cache;
+use crate::services::captcha::{self, CaptchaEndpoint};
+use crate::services::normalize::Normalize;
+use crate::services::passwords::hash_password;
+use crate::services::password_reset::send_reset_email;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct ForgotPasswordResponse {
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResetPasswordResponse {
+    pub message: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Emails a reset link when the address belongs to an account. The answer is the same either
+/// way, so the endpoint can't be used to find out which addresses are registered.
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    AppJson(payload): AppJson<ForgotPasswordRequest>,
+) -> Result<ApiResponse<ForgotPasswordResponse>, AuthError> {
+    let payload = payload.normalize()?;
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid password reset data", err))?;
+
+    captcha::check(&state, CaptchaEndpoint::PasswordReset, payload.captcha_token.as_deref(), client.ip_address.as_deref()).await?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during password reset request: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let user = UserModel::by_email(&mut conn, &payload.email)
+        .map_err(|e| {
+            tracing::error!("Failed to look up user for password reset: {}", e);
+            AuthError::database("Failed to request password reset")
+        })?
+        .filter(|user| user.deleted_at.is_none());
+
+    if let Some(user) = user {
+        match send_reset_email(&state, &mut conn, &user, client.locale_for(user.locale.as_deref())).await {
+            Ok(true) => {
+                audit::record(&state, &client, AUDIT_PASSWORD_RESET_REQUESTED, Some(&user.id), None, None).await;
+                tracing::info!("Sent a password reset link to user {}", user.id);
+            }
+            Ok(false) => tracing::info!("Skipped password reset email for user {}; one was just sent", user.id),
+            Err(e) => tracing::error!("Failed to send password reset email to user {}: {}", user.id, e),
+        }
+    }
+
+    Ok(ApiResponse::new(ForgotPasswordResponse {
+        message: "If an account uses that address, a reset link is on its way".to_string(),
+    }))
+}
+
+/// Sets a new password from a reset link and signs the user out everywhere.
+pub async fn reset_password(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    AppJson(payload): AppJson<ResetPasswordRequest>,
+) -> Result<ApiResponse<ResetPasswordResponse>, AuthError> {
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid password reset data", err))?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during password reset: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let token = ResetTokens::by_token(&mut conn, &payload.token)
+        .map_err(|e| {
+            tracing::error!("Failed to look up reset token: {}", e);
+            AuthError::database("Failed to reset password")
+        })?
+        .ok_or_else(|| AuthError::unauthorized("Invalid reset link"))?;
+
+    if token.is_expired() {
+        tracing::info!("Expired reset token used for user: {}", token.user_id);
+        return Err(AuthError::unauthorized("Reset link has expired"));
+    }
+
+    let hashed_password = hash_password(&state.config.load(), &token.user_id, &payload.password)
+        .map_err(|e| {
+            tracing::error!("Password hashing failed: {}", e);
+            AuthError::internal("Failed to process password")
+        })?;
+
+    state.writes.write(&mut conn, |conn| {
+        UserModel::update_password(conn, &token.user_id, &hashed_password)?;
+        ResetTokens::delete_by_user(conn, &token.user_id)
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to reset password for user {}: {}", token.user_id, e);
+        AuthError::database("Failed to reset password")
+    })?;
+
+    // Whoever knew the old password shouldn't stay signed in.
+    if let Err(e) = state.sessions.delete_by_user(&token.user_id).await {
+        tracing::error!("Failed to end sessions after password reset for user {}: {}", token.user_id, e);
+    }
+
+    cache::invalidate_user(state.cache.as_ref(), &token.user_id).await;
+    audit::record(&state, &client, AUDIT_PASSWORD_RESET, Some(&token.user_id), None, None).await;
+
+    tracing::info!("User {} reset their password", token.user_id);
+
+    Ok(ApiResponse::new(ResetPasswordResponse {
+        message: "Password reset; sign in with the new password".to_string(),
+        updated_at: chrono::Utc::now(),
+    }))
+}
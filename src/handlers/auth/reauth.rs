@@ -0,0 +1,83 @@
+use axum::extract::State;
+use serde::Serialize;
+use time::Duration;
+use tower_cookies::Cookies;
+use validator::Validate;
+
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::auth::ReauthRequest;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::cookies::{self, AuthCookie};
+use crate::services::passwords::verify_password;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct ReauthResponse {
+    pub sudo_token: String,
+    pub message: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn reauth(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    cookies: Cookies,
+    AppJson(payload): AppJson<ReauthRequest>,
+) -> Result<ApiResponse<ReauthResponse>, AuthError> {
+    if auth.is_api_token() {
+        return Err(AuthError::forbidden("API tokens cannot re-authenticate"));
+    }
+
+    let user = auth.user;
+    tracing::info!("Processing re-authentication request for user: {}", user.id);
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid re-authentication data", err))?;
+
+    // The extractor leaves the hash out of `auth.user`, so read it fresh.
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during re-authentication: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+    let password_hash = UserModel::by_id(&mut conn, &user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load user {} for re-authentication: {}", user.id, e);
+            AuthError::database("Failed to load user")
+        })?
+        .ok_or_else(|| AuthError::unauthorized("User no longer exists"))?
+        .password;
+
+    let password_valid = verify_password(&payload.password, &password_hash)
+        .map_err(|e| {
+            tracing::error!("Password verification failed: {}", e);
+            AuthError::internal("Authentication processing failed")
+        })?;
+
+    if !password_valid {
+        tracing::info!("Invalid re-authentication attempt for user: {}", user.id);
+        return Err(AuthError::unauthorized("Invalid password"));
+    }
+
+    let (sudo_token, expires_at) = state.jwt.create_sudo_token(&user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to create sudo token for user {}: {}", user.id, e);
+            AuthError::internal("Failed to complete re-authentication")
+        })?;
+
+    let config = state.config.load();
+    let sudo_expires = Duration::minutes(config.reauth_window_minutes());
+    cookies::set(&cookies, &config, AuthCookie::Sudo, sudo_token.clone(), Some(sudo_expires));
+
+    tracing::info!("User {} re-authenticated", user.id);
+
+    Ok(ApiResponse::new(ReauthResponse {
+        sudo_token,
+        message: "Re-authenticated successfully".to_string(),
+        expires_at,
+    }))
+}
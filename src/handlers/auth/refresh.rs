@@ -1,141 +1,160 @@
 use axum::extract::State;
-use axum::Json;
-use serde::Serialize;
 use time::Duration;
-use tower_cookies::{Cookie, Cookies};
+use tower_cookies::Cookies;
+use tsumi_types::RefreshResponse;
 
-use crate::state::AppState;
 use crate::db::models::refresh_token::RefreshTokens;
+use crate::db::models::user_model::UserModel;
+use crate::http::dto::ApiResponse;
+use crate::state::AppState;
 use crate::errors::AuthError;
-use crate::services::jwt::{create_access_token, create_refresh_token, decode_refresh_token};
+use crate::http::client::ClientInfo;
+use crate::services::audit::{self, AUDIT_REFRESH_TOKEN_REUSED};
+use crate::services::cookies::{self, AuthCookie};
+use crate::services::jwt::is_opaque_refresh_token;
 use crate::utils::get_db_conn;
 
-#[derive(Debug, Serialize)]
-pub struct RefreshResponse {
-    pub access_token: String,
-    pub message: String,
-    pub refreshed_at: chrono::DateTime<chrono::Utc>,
-}
-
 pub async fn refresh(
     State(state): State<AppState>,
     cookies: Cookies,
-) -> Result<Json<RefreshResponse>, AuthError> {
+    client: ClientInfo,
+) -> Result<ApiResponse<RefreshResponse>, AuthError> {
     tracing::info!("Processing token refresh request");
 
-    let refresh_token_cookie = cookies
-        .get("refresh_token")
+    let refresh_token_cookie = cookies::get(&cookies, &state.config.load(), AuthCookie::Refresh)
         .ok_or_else(|| {
             tracing::debug!("No refresh token found in cookies");
             AuthError::unauthorized("No refresh token provided")
         })?;
 
-    let refresh_token_value = refresh_token_cookie.value();
-
-    let decoded_token = decode_refresh_token(refresh_token_value)
+    let refresh_token_value = refresh_token_cookie.as_str();
+
+    // Opaque tokens carry no claims; the stored session is the only source of truth for them.
+    let claimed_user_id = if is_opaque_refresh_token(refresh_token_value) {
+        None
+    } else {
+        let decoded_token = state.jwt.decode_refresh_token(refresh_token_value)
+            .map_err(|e| {
+                tracing::warn!("Failed to decode refresh token: {}", e);
+                AuthError::unauthorized("Invalid or malformed refresh token")
+            })?;
+        Some(decoded_token.claims.user_id)
+    };
+
+    let token_record = state.sessions.by_token(refresh_token_value)
         .await
         .map_err(|e| {
-            tracing::warn!("Failed to decode refresh token: {}", e);
-            AuthError::unauthorized("Invalid or malformed refresh token")
-        })?;
-
-    let user_id = &decoded_token.claims.user_id;
-    tracing::debug!("Processing token refresh for user: {}", user_id);
-
-    let mut conn = get_db_conn(&state)
-        .map_err(|e| {
-            tracing::error!("Failed to get database connection during token refresh: {}", e);
-            AuthError::internal("Database connection failed")
-        })?;
-
-    let token_record = RefreshTokens::by_token(&mut conn, refresh_token_value)
-        .map_err(|e| {
-            tracing::warn!("Refresh token not found in database: {}", e);
+            tracing::error!("Failed to look up refresh token: {}", e);
+            AuthError::database("Failed to validate refresh token")
+        })?
+        .ok_or_else(|| {
+            tracing::warn!("Refresh token not found in the session store");
             AuthError::unauthorized("Invalid refresh token")
         })?;
 
-    if token_record.user_id != *user_id {
+    if let Some(claimed_user_id) = &claimed_user_id
+        && token_record.user_id != *claimed_user_id
+    {
         tracing::error!("Token user ID mismatch. Token user: {}, Decoded user: {}",
-                       token_record.user_id, user_id);
+                       token_record.user_id, claimed_user_id);
         // Clean up the invalid token
-        let _ = RefreshTokens::delete_by_token(&mut conn, refresh_token_value);
+        let _ = state.sessions.delete_by_token(refresh_token_value).await;
         return Err(AuthError::unauthorized("Token validation failed"));
     }
 
-    let is_expired = RefreshTokens::is_expired(&mut conn, &token_record.token)
-        .map_err(|e| {
-            tracing::error!("Failed to check token expiration: {}", e);
-            AuthError::database("Failed to validate token expiration")
-        })?;
+    let user_id = &token_record.user_id;
+    tracing::debug!("Processing token refresh for user: {}", user_id);
+
+    if token_record.is_replaced() {
+        return Err(revoke_reused_family(&state, &cookies, &client, &token_record).await);
+    }
 
-    if is_expired {
+    if token_record.expires_at < chrono::Utc::now().naive_utc() {
         tracing::info!("Expired refresh token used for user: {}", user_id);
-        let _ = RefreshTokens::delete_by_token(&mut conn, refresh_token_value);
+        let _ = state.sessions.delete_by_token(refresh_token_value).await;
         return Err(AuthError::unauthorized("Refresh token has expired"));
     }
 
-    RefreshTokens::delete_by_token(&mut conn, refresh_token_value)
+    let user = get_db_conn(&state)
+        .map_err(|e| e.to_string())
+        .and_then(|mut conn| UserModel::by_id(&mut conn, user_id).map_err(|e| e.to_string()))
         .map_err(|e| {
-            tracing::error!("Failed to delete old refresh token: {}", e);
-            AuthError::database("Failed to invalidate old token")
+            tracing::error!("Failed to load user {} during token refresh: {}", user_id, e);
+            AuthError::database("Failed to validate refresh token")
+        })?
+        .ok_or_else(|| {
+            tracing::info!("Refresh token presented for missing user: {}", user_id);
+            AuthError::unauthorized("User no longer exists")
         })?;
 
-    let new_access_token = create_access_token(user_id)
-        .await
+    if user.is_suspended() {
+        tracing::info!("Refresh token presented for suspended user: {}", user_id);
+        let _ = state.sessions.delete_by_token(refresh_token_value).await;
+        return Err(AuthError::forbidden("Account suspended"));
+    }
+
+    let new_access_token = state.jwt.create_access_token(user_id, user.token_version)
         .map_err(|e| {
             tracing::error!("Failed to create new access token for user {}: {}", user_id, e);
             AuthError::internal("Failed to generate new access token")
         })?;
 
-    let new_refresh_token = create_refresh_token(user_id)
-        .await
+    let new_refresh_token = state.jwt.issue_refresh_token(&state.config.load(), user_id)
         .map_err(|e| {
             tracing::error!("Failed to create new refresh token for user {}: {}", user_id, e);
             AuthError::internal("Failed to generate new refresh token")
         })?;
 
-    RefreshTokens::create(
-        &mut conn,
+    let rotated = state.sessions.rotate(
+        &token_record,
         &new_refresh_token,
-        user_id,
-        state.config.refresh_token_expires_at(),
+        state.config.load().session_lifetime(token_record.persistent),
+        client.ip_address.as_deref(),
+        client.user_agent.as_deref(),
     )
+        .await
         .map_err(|e| {
             tracing::error!("Failed to store new refresh token for user {}: {}", user_id, e);
             AuthError::database("Failed to store new refresh token")
         })?;
 
-    set_refresh_token_cookie(&cookies, &new_refresh_token, &state);
+    // Another request rotated the same token first.
+    if rotated.is_none() {
+        return Err(revoke_reused_family(&state, &cookies, &client, &token_record).await);
+    }
+
+    set_refresh_token_cookie(&cookies, &new_refresh_token, token_record.persistent, &state);
 
     tracing::info!("Successfully refreshed tokens for user: {}", user_id);
 
-    Ok(Json(RefreshResponse {
+    Ok(ApiResponse::new(RefreshResponse {
         access_token: new_access_token,
         message: "Tokens refreshed successfully".to_string(),
         refreshed_at: chrono::Utc::now(),
     }))
 }
 
-fn set_refresh_token_cookie(cookies: &Cookies, refresh_token: &str, state: &AppState) {
-    let remove_cookie = Cookie::build(("refresh_token", ""))
-        .http_only(true)
-        .path("/")
-        .secure(true)
-        .same_site(tower_cookies::cookie::SameSite::Strict)
-        .max_age(Duration::seconds(0)) // Expire immediately
-        .build()
-        .into_owned();
-
-    cookies.add(remove_cookie);
-
-    let refresh_cookie = Cookie::build(("refresh_token", refresh_token))
-        .http_only(true)
-        .path("/")
-        .secure(true) // Only secure in production
-        .same_site(tower_cookies::cookie::SameSite::Strict)
-        .max_age(Duration::days(state.config.refresh_token_expires_at()))
-        .build()
-        .into_owned();
-
-    cookies.add(refresh_cookie);
-}
\ No newline at end of file
+/// A token that was already rotated is being presented again, so either it or its successor
+/// is in the wrong hands. Signs out every token issued since the same sign-in.
+async fn revoke_reused_family(state: &AppState, cookies: &Cookies, client: &ClientInfo, token: &RefreshTokens) -> AuthError {
+    tracing::warn!("Refresh token reuse detected for user {}, revoking session family {}", token.user_id, token.family_id);
+
+    match state.sessions.delete_family(&token.family_id).await {
+        Ok(revoked) => {
+            let detail = format!("family {}, {} tokens revoked", token.family_id, revoked);
+            audit::record(state, client, AUDIT_REFRESH_TOKEN_REUSED, Some(&token.user_id), None, Some(&detail)).await;
+        }
+        Err(e) => tracing::error!("Failed to revoke session family {}: {}", token.family_id, e),
+    }
+    cookies::clear(cookies, &state.config.load(), AuthCookie::Refresh);
+
+    AuthError::unauthorized("Refresh token has already been used")
+}
+
+/// Sets the rotated token's cookie, which outlives the browser session only when the session
+/// was opened with remember me.
+fn set_refresh_token_cookie(cookies: &Cookies, refresh_token: &str, persistent: bool, state: &AppState) {
+    let config = state.config.load();
+    let max_age = persistent.then(|| Duration::days(config.refresh_token_expires_at()));
+    cookies::set(cookies, &config, AuthCookie::Refresh, refresh_token, max_age);
+}
@@ -1,60 +1,63 @@
 use axum::extract::State;
-use axum::Json;
-use bcrypt::verify;
 use diesel::prelude::*;
-use serde::{Deserialize, Serialize};
 use time::Duration;
-use tower_cookies::{Cookie, Cookies};
+use tower_cookies::Cookies;
+use tsumi_types::SignInResponse;
 use validator::Validate;
-use crate::config::config;
-use crate::db::models::refresh_token::{NewRefreshToken, RefreshTokens};
 use crate::db::models::user_model::UserModel;
-use crate::db::schema::{refresh_tokens, users};
+use crate::db::nocase::NoCaseExpressionMethods;
+use crate::db::schema::users;
 use crate::errors::AuthError;
 use crate::handlers::auth::SignInRequest;
-use crate::services::jwt::{create_access_token, create_refresh_token};
+use crate::http::client::ClientInfo;
+use crate::http::dto::{ApiResponse, UserDto};
+use crate::http::json::AppJson;
+use crate::http::redirect::next_or_home;
+use crate::services::audit::{self, AUDIT_SIGN_IN, AUDIT_SIGN_IN_FAILED};
+use crate::services::cookies::{self, AuthCookie};
+use crate::services::devices;
+use crate::services::normalize::Normalize;
+use crate::services::passwords::{hash_password, needs_rehash, verify_password};
 use crate::state::AppState;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SignInResponse {
-    pub user: UserModel,
-    pub message: String,
-    pub signed_in_at: chrono::DateTime<chrono::Utc>,
-}
+use crate::utils::get_db_conn;
 
 pub async fn sign_in(
     State(state): State<AppState>,
     cookies: Cookies,
-    Json(payload): Json<SignInRequest>,
-) -> Result<Json<SignInResponse>, AuthError> {
+    client: ClientInfo,
+    AppJson(payload): AppJson<SignInRequest>,
+) -> Result<ApiResponse<SignInResponse>, AuthError> {
+    let payload = payload.normalize()?;
     tracing::info!("Processing sign in request for email: {}", payload.email);
 
-    let config = config().await;
+    let config = state.config.load();
 
     payload.validate()
-        .map_err(|err| AuthError::validation(format!("Invalid sign in data: {}", err)))?;
+        .map_err(|err| AuthError::invalid("Invalid sign in data", err))?;
 
-    let mut conn = state.db_pool.get()
+    let mut conn = get_db_conn(&state)
         .map_err(|e| {
-            tracing::error!("Failed to get database connection: {}", e);
+            tracing::error!("Failed to get database connection during sign in: {}", e);
             AuthError::internal("Database connection failed")
         })?;
 
     let user = users::table
-        .filter(users::email.eq(&payload.email))
+        .filter(users::email.eq_nocase(&payload.email))
         .select(UserModel::as_select())
         .first(&mut conn)
         .optional()
         .map_err(|e| {
             tracing::error!("Database query failed while finding user: {}", e);
             AuthError::database("Failed to verify user credentials")
-        })?
-        .ok_or_else(|| {
-            tracing::info!("Sign in attempt with non-existent email: {}", payload.email);
-            AuthError::unauthorized("Invalid email or password")
         })?;
+    let Some(user) = user else {
+        tracing::info!("Sign in attempt with non-existent email: {}", payload.email);
+        let detail = format!("unknown email {}", payload.email);
+        audit::record(&state, &client, AUDIT_SIGN_IN_FAILED, None, None, Some(&detail)).await;
+        return Err(AuthError::unauthorized("Invalid email or password"));
+    };
 
-    let password_valid = verify(&payload.password, &user.password)
+    let password_valid = verify_password(&payload.password, &user.password)
         .map_err(|e| {
             tracing::error!("Password verification failed: {}", e);
             AuthError::internal("Authentication processing failed")
@@ -62,71 +65,101 @@ pub async fn sign_in(
 
     if !password_valid {
         tracing::info!("Invalid password attempt for user: {}", user.id);
+        audit::record(&state, &client, AUDIT_SIGN_IN_FAILED, Some(&user.id), None, Some("wrong password")).await;
         return Err(AuthError::unauthorized("Invalid email or password"));
     }
 
+    // Move the stored hash to the format the user's rollout bucket now calls for. A failure
+    // here only delays the migration, so it doesn't fail the sign in.
+    if needs_rehash(&config, &user.id, &user.password) {
+        let rehashed = match hash_password(&config, &user.id, &payload.password) {
+            Ok(hash) => state.writes.write(&mut conn, |conn| UserModel::update_password(conn, &user.id, &hash))
+                .await
+                .map_err(|e| AuthError::database(e.to_string())),
+            Err(e) => Err(e),
+        };
+        match rehashed {
+            Ok(_) => tracing::info!("Rehashed password for user {}", user.id),
+            Err(e) => tracing::warn!("Failed to rehash password for user {}: {}", user.id, e),
+        }
+    }
+
+    // Checked after the password so a wrong guess can't tell whether an account is suspended.
+    if user.is_suspended() {
+        tracing::info!("Sign in attempt by suspended user: {}", user.id);
+        audit::record(&state, &client, AUDIT_SIGN_IN_FAILED, Some(&user.id), None, Some("account suspended")).await;
+        return Err(AuthError::forbidden("Account suspended"));
+    }
+
     if !user.email_verified {
         tracing::info!("Sign in attempt with unverified email: {}", user.email);
+        audit::record(&state, &client, AUDIT_SIGN_IN_FAILED, Some(&user.id), None, Some("email not verified")).await;
         return Err(AuthError::unauthorized("Please verify your email address before signing in"));
     }
 
-    cleanup_existing_tokens(&mut conn, &cookies, &user.id).await?;
+    drop(conn);
 
-    let new_access_token = create_access_token(&user.id)
-        .await
+    cleanup_existing_tokens(&state, &cookies, &user.id).await?;
+
+    let new_access_token = state.jwt.create_access_token(&user.id, user.token_version)
         .map_err(|e| {
             tracing::error!("Failed to create access token for user {}: {}", user.id, e);
             AuthError::internal("Failed to generate authentication tokens")
         })?;
 
-    let new_refresh_token = create_refresh_token(&user.id)
-        .await
+    let new_refresh_token = state.jwt.issue_refresh_token(&config, &user.id)
         .map_err(|e| {
             tracing::error!("Failed to create refresh token for user {}: {}", user.id, e);
             AuthError::internal("Failed to generate authentication tokens")
         })?;
 
-    let new_refresh_token_record = NewRefreshToken {
-        id: uuid::Uuid::new_v4().to_string(),
-        token: new_refresh_token.clone(),
-        user_id: user.id.clone(),
-        expires_at: chrono::Utc::now().naive_utc() + chrono::Duration::days(config.refresh_token_expires_at
-        ()),
-        created_at: chrono::Utc::now().naive_utc(),
-    };
-
-    diesel::insert_into(refresh_tokens::table)
-        .values(&new_refresh_token_record)
-        .execute(&mut conn)
+    state.sessions.create(
+        &new_refresh_token,
+        &user.id,
+        config.session_lifetime(payload.remember_me),
+        payload.remember_me,
+        client.ip_address.as_deref(),
+        client.user_agent.as_deref(),
+    )
+        .await
         .map_err(|e| {
             tracing::error!("Failed to store refresh token for user {}: {}", user.id, e);
             AuthError::database("Failed to create user session")
         })?;
 
-    set_auth_cookies(&cookies, &new_access_token, &new_refresh_token, &config);
+    set_auth_cookies(&cookies, &new_access_token, &new_refresh_token, payload.remember_me, &config);
+
+    // The sign in has already succeeded; a missed notice shouldn't undo it.
+    let conn = get_db_conn(&state).map_err(|e| AuthError::internal(e.to_string()));
+    let noticed = match conn {
+        Ok(mut conn) => devices::note_sign_in(&state, &mut conn, &user, &client).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = noticed {
+        tracing::warn!("Failed to check the sign in device for user {}: {}", user.id, e);
+    }
+
+    audit::record(&state, &client, AUDIT_SIGN_IN, Some(&user.id), None, None).await;
 
     tracing::info!("User {} successfully signed in", user.id);
 
-    Ok(Json(SignInResponse {
-        user: UserModel::from(user),
+    Ok(ApiResponse::new(SignInResponse {
+        user: UserDto::from(user),
         message: "Successfully signed in".to_string(),
         signed_in_at: chrono::Utc::now(),
+        redirect_to: next_or_home(config.public_url(), payload.next.as_deref()),
     }))
 }
 
 async fn cleanup_existing_tokens(
-    conn: &mut SqliteConnection,
+    state: &AppState,
     cookies: &Cookies,
     user_id: &str,
 ) -> Result<(), AuthError> {
-    if let Some(cookie_refresh_token) = cookies.get("refresh_token") {
-        let token_value = cookie_refresh_token.value();
-
-        let existing_token = refresh_tokens::table
-            .filter(refresh_tokens::token.eq(token_value))
-            .select(RefreshTokens::as_select())
-            .first(conn)
-            .optional()
+    let existing_cookie = cookies::get(cookies, &state.config.load(), AuthCookie::Refresh);
+    if let Some(token_value) = existing_cookie {
+        let existing_token = state.sessions.by_token(&token_value)
+            .await
             .map_err(|e| {
                 tracing::error!("Failed to query existing refresh token: {}", e);
                 AuthError::database("Failed to verify existing session")
@@ -135,15 +168,15 @@ async fn cleanup_existing_tokens(
         if let Some(token) = existing_token {
             if token.user_id != user_id {
                 tracing::warn!("Token mismatch detected, cleaning up tokens for user: {}", user_id);
-                diesel::delete(refresh_tokens::table.filter(refresh_tokens::user_id.eq(user_id)))
-                    .execute(conn)
+                state.sessions.delete_by_user(user_id)
+                    .await
                     .map_err(|e| {
                         tracing::error!("Failed to delete user tokens: {}", e);
                         AuthError::database("Failed to clean up user sessions")
                     })?;
             } else {
-                diesel::delete(refresh_tokens::table.filter(refresh_tokens::token.eq(token_value)))
-                    .execute(conn)
+                state.sessions.delete_by_token(&token_value)
+                    .await
                     .map_err(|e| {
                         tracing::error!("Failed to delete existing refresh token: {}", e);
                         AuthError::database("Failed to update user session")
@@ -159,31 +192,13 @@ fn set_auth_cookies(
     cookies: &Cookies,
     access_token: &str,
     refresh_token: &str,
+    remember_me: bool,
     config: &crate::config::Config,
 ) {
-    // Access token cookie
-    let access_cookie = Cookie::build(("access_token", access_token))
-        .path("/")
-        .secure(true) // Only secure in production
-        .http_only(true)
-        .same_site(tower_cookies::cookie::SameSite::Strict)
-        .max_age(Duration::minutes(config.access_token_expires_at()))
-        .build()
-        .into_owned();
-
-    // Refresh token cookie
-    let refresh_cookie = Cookie::build(("refresh_token", refresh_token))
-        .path("/")
-        .secure(true)
-        .http_only(true)
-        .same_site(tower_cookies::cookie::SameSite::Strict)
-        .max_age(Duration::days(config.refresh_token_expires_at()))
-        .build()
-        .into_owned();
-
-    cookies.remove(Cookie::from("access_token"));
-    cookies.remove(Cookie::from("refresh_token"));
-
-    cookies.add(access_cookie);
-    cookies.add(refresh_cookie);
-}
\ No newline at end of file
+    let access_expires = Duration::minutes(config.access_token_expires_at());
+    cookies::set(cookies, config, AuthCookie::Access, access_token, Some(access_expires));
+
+    // Kept past the browser session only when asked to
+    let refresh_expires = remember_me.then(|| Duration::days(config.refresh_token_expires_at()));
+    cookies::set(cookies, config, AuthCookie::Refresh, refresh_token, refresh_expires);
+}
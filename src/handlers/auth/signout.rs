@@ -1,73 +1,109 @@
 use axum::extract::State;
-use axum::Json;
-use serde::Serialize;
-use tower_cookies::{Cookie, Cookies};
+use tower_cookies::Cookies;
+use tsumi_types::{SignOutAllResponse, SignOutResponse};
 
+use crate::db::models::user_model::UserModel;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
 use crate::state::AppState;
-use crate::db::models::refresh_token::RefreshTokens;
 use crate::errors::AuthError;
+use crate::http::client::ClientInfo;
+use crate::services::audit::{self, AUDIT_SIGN_OUT, AUDIT_SIGN_OUT_ALL};
+use crate::services::cache;
+use crate::services::cookies::{self, AuthCookie};
 use crate::utils::get_db_conn;
 
-#[derive(Debug, Serialize)]
-pub struct SignOutResponse {
-    pub message: String,
-    pub signed_out_at: chrono::DateTime<chrono::Utc>,
-}
-
 pub async fn sign_out(
     State(state): State<AppState>,
     cookies: Cookies,
-) -> Result<Json<SignOutResponse>, AuthError> {
+    client: ClientInfo,
+) -> Result<ApiResponse<SignOutResponse>, AuthError> {
     tracing::info!("Processing sign out request");
 
-    let refresh_token = cookies
-        .get("refresh_token")
+    let refresh_token = cookies::get(&cookies, &state.config.load(), AuthCookie::Refresh)
         .ok_or_else(|| {
             tracing::debug!("No refresh token found in cookies");
             AuthError::unauthorized("No active session found")
         })?;
 
-    let mut conn = get_db_conn(&state)
+    let session = state.sessions.by_token(&refresh_token)
+        .await
         .map_err(|e| {
-            tracing::error!("Failed to get database connection during sign out: {}", e);
-            AuthError::internal("Database connection failed")
+            tracing::error!("Failed to look up refresh token during sign out: {}", e);
+            AuthError::database("Failed to invalidate session")
         })?;
 
-    let token_exists = RefreshTokens::token_exists(&mut conn, refresh_token.value())
+    let signed_out = state.sessions.delete_by_token(&refresh_token)
+        .await
         .map_err(|e| {
-            tracing::error!("Failed to verify refresh token existence: {}", e);
-            AuthError::database("Failed to verify session")
+            tracing::error!("Failed to delete refresh token during sign out: {}", e);
+            AuthError::database("Failed to invalidate session")
         })?;
 
-    if !token_exists {
+    if !signed_out {
         tracing::warn!("Attempt to sign out with invalid refresh token");
-        remove_refresh_token_cookie(&cookies);
+        cookies::clear(&cookies, &state.config.load(), AuthCookie::Refresh);
         return Err(AuthError::unauthorized("Invalid or expired session"));
     }
 
-    RefreshTokens::delete_by_token(&mut conn, refresh_token.value())
-        .map_err(|e| {
-            tracing::error!("Failed to delete refresh token during sign out: {}", e);
-            AuthError::database("Failed to invalidate session")
-        })?;
+    cookies::clear(&cookies, &state.config.load(), AuthCookie::Refresh);
 
-    remove_refresh_token_cookie(&cookies);
+    if let Some(session) = session {
+        audit::record(&state, &client, AUDIT_SIGN_OUT, Some(&session.user_id), None, None).await;
+    }
 
     tracing::info!("User successfully signed out");
 
-    Ok(Json(SignOutResponse {
+    Ok(ApiResponse::new(SignOutResponse {
         message: "Successfully signed out".to_string(),
         signed_out_at: chrono::Utc::now(),
     }))
 }
 
-fn remove_refresh_token_cookie(cookies: &Cookies) {
-    let mut cookie = Cookie::new("refresh_token", "");
-    cookie.set_path("/");
-    cookie.set_http_only(true);
-    cookie.set_secure(true);
-    cookie.set_same_site(tower_cookies::cookie::SameSite::Strict);
-    cookie.set_max_age(time::Duration::seconds(0));
+/// Signs the user out everywhere: every session is deleted and access tokens issued so far
+/// stop working, this request's included. API tokens are left alone; they're revoked one by
+/// one.
+pub async fn sign_out_all(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    cookies: Cookies,
+    client: ClientInfo,
+) -> Result<ApiResponse<SignOutAllResponse>, AuthError> {
+    auth.forbid_impersonation()?;
+    let user_id = &auth.user.id;
+    tracing::info!("Signing user {} out of every session", user_id);
 
-    cookies.add(cookie);
-}
\ No newline at end of file
+    let revoked = state.sessions.delete_by_user(user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete sessions for user {}: {}", user_id, e);
+            AuthError::database("Failed to sign out of every session")
+        })?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during sign out: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+    state.writes.write(&mut conn, |conn| UserModel::bump_token_version(conn, user_id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to revoke access tokens for user {}: {}", user_id, e);
+            AuthError::database("Failed to sign out of every session")
+        })?;
+    drop(conn);
+    cache::invalidate_user(state.cache.as_ref(), user_id).await;
+
+    let config = state.config.load();
+    cookies::clear(&cookies, &config, AuthCookie::Refresh);
+    cookies::clear(&cookies, &config, AuthCookie::Access);
+
+    let detail = format!("{} sessions revoked", revoked);
+    audit::record(&state, &client, AUDIT_SIGN_OUT_ALL, Some(user_id), None, Some(&detail)).await;
+
+    Ok(ApiResponse::new(SignOutAllResponse {
+        message: "Signed out of every session".to_string(),
+        sessions_revoked: revoked,
+        signed_out_at: chrono::Utc::now(),
+    }))
+}
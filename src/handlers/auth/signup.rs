@@ -1,24 +1,53 @@
 use axum::extract::State;
-use axum::Json;
 use axum::response::Result;
-use bcrypt::{hash, DEFAULT_COST};
 use diesel::prelude::*;
 use uuid::Uuid;
 use validator::Validate;
+use crate::http::client::ClientInfo;
+use crate::http::dto::{ApiResponse, UserDto};
+use crate::http::features::Enabled;
+use crate::http::json::AppJson;
+use crate::services::captcha::{self, CaptchaEndpoint};
+use crate::services::feature_flags::SignupFeature;
+use crate::services::normalize::Normalize;
+use crate::services::spam::{self, SpamCheck, Submission, Verdict};
 use crate::state::AppState;
 use crate::db::models::user_model::{UserModel, NewUser};
+use crate::db::nocase::NoCaseExpressionMethods;
 use crate::db::schema::users;
 use crate::errors::AuthError;
-use crate::handlers::auth::{SignUpRequest, SignUpResponse};
+use crate::handlers::auth::SignUpRequest;
+use crate::services::email_queue::EmailPriority;
+use crate::services::email_verification::VerificationEmail;
+use crate::services::passwords::hash_password;
 
 pub async fn sign_up(
     State(state): State<AppState>,
-    Json(payload): Json<SignUpRequest>,
-) -> Result<Json<SignUpResponse>, AuthError> {
+    _enabled: Enabled<SignupFeature>,
+    client: ClientInfo,
+    AppJson(payload): AppJson<SignUpRequest>,
+) -> Result<ApiResponse<UserDto>, AuthError> {
+    let payload = payload.normalize()?;
     tracing::info!("Processing signup request for email: {}", payload.email);
 
     payload.validate()
-        .map_err(|err| AuthError::validation(format!("Invalid signup data: {}", err)))?;
+        .map_err(|err| AuthError::invalid("Invalid signup data", err))?;
+
+    captcha::check(&state, CaptchaEndpoint::Signup, payload.captcha_token.as_deref(), client.ip_address.as_deref()).await?;
+
+    // A flagged signup still gets its account, but its comments are held back until an admin
+    // trusts it.
+    let verdict = spam::check(&state, &Submission {
+        check: SpamCheck::Signup,
+        author: &payload.name,
+        email: &payload.email,
+        content: "",
+        ip_address: client.ip_address.as_deref(),
+        user_agent: client.user_agent.as_deref(),
+    }).await;
+    if verdict == Verdict::Reject {
+        return Err(AuthError::validation("This signup looks like spam"));
+    }
 
     let mut conn = state.db_pool.get()
         .map_err(|e| {
@@ -27,7 +56,7 @@ pub async fn sign_up(
         })?;
 
     let email_exists = users::table
-        .filter(users::email.eq(&payload.email))
+        .filter(users::email.eq_nocase(&payload.email))
         .select(UserModel::as_select())
         .first(&mut conn)
         .optional()
@@ -42,7 +71,7 @@ pub async fn sign_up(
     }
 
     let username_exists = users::table
-        .filter(users::name.eq(&payload.name))
+        .filter(users::name.eq_nocase(&payload.name))
         .select(UserModel::as_select())
         .first(&mut conn)
         .optional()
@@ -56,14 +85,14 @@ pub async fn sign_up(
         return Err(AuthError::conflict("Username is already taken"));
     }
 
-    let hashed_password = hash(&payload.password, DEFAULT_COST)
+    let user_id = Uuid::new_v4().to_string();
+
+    let hashed_password = hash_password(&state.config.load(), &user_id, &payload.password)
         .map_err(|e| {
             tracing::error!("Password hashing failed: {}", e);
             AuthError::internal("Failed to process password")
         })?;
 
-    let user_id = Uuid::new_v4().to_string();
-
     let new_user = NewUser {
         id: user_id,
         name: payload.name,
@@ -73,24 +102,29 @@ pub async fn sign_up(
         created_at: chrono::Utc::now().naive_utc(),
     };
 
-    let user = diesel::insert_into(users::table)
-        .values(&new_user)
-        .returning(UserModel::as_returning())
-        .get_result(&mut conn)
-        .map_err(|e| {
-            tracing::error!("Failed to create user in database: {}", e);
-            match e {
-                diesel::result::Error::DatabaseError(
-                    diesel::result::DatabaseErrorKind::UniqueViolation, _
-                ) => AuthError::conflict("Email or username already exists"),
-                _ => AuthError::database("Failed to create user account"),
-            }
-        })?;
+    // The account and its verification email are kept or dropped together.
+    let verification = VerificationEmail::render(&state, client.locale, &new_user.name, &new_user.email)?;
+    let user = state.writes.write(&mut conn, |conn| {
+        let user = diesel::insert_into(users::table)
+            .values((&new_user, users::comment_moderation.eq(verdict.moderation_status())))
+            .returning(UserModel::as_returning())
+            .get_result(conn)?;
+        verification.queue(conn, &user.id)?;
+        Ok(user)
+    })
+    .await
+    .map_err(|e: diesel::result::Error| {
+        tracing::error!("Failed to create user in database: {}", e);
+        match e {
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation, _
+            ) => AuthError::conflict("Email or username already exists"),
+            _ => AuthError::database("Failed to create user account"),
+        }
+    })?;
+    state.email_queue.wake(EmailPriority::Transactional);
 
     tracing::info!("Successfully created user account: {}", user.id);
 
-    // TODO: Send email verification
-    // email_service::send_verification_email(&user.email, &user.id).await?;
-
-    Ok(Json(SignUpResponse::from(user)))
+    Ok(ApiResponse::new(UserDto::from(user)))
 }
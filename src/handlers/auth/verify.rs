@@ -0,0 +1,67 @@
+use axum::extract::{Query, State};
+use serde::Serialize;
+
+use crate::db::models::email_verification_token::EmailVerificationTokens;
+use crate::db::models::onboarding_step::ONBOARDING_STEP_VERIFY_EMAIL;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::auth::VerifyEmailQuery;
+use crate::http::dto::ApiResponse;
+use crate::services::cache;
+use crate::services::onboarding;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct VerifyEmailResponse {
+    pub message: String,
+    pub verified_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Query(query): Query<VerifyEmailQuery>,
+) -> Result<ApiResponse<VerifyEmailResponse>, AuthError> {
+    tracing::info!("Processing email verification request");
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during email verification: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let token = EmailVerificationTokens::by_token(&mut conn, &query.token)
+        .map_err(|e| {
+            tracing::error!("Failed to look up verification token: {}", e);
+            AuthError::database("Failed to verify email")
+        })?
+        .ok_or_else(|| AuthError::unauthorized("Invalid verification link"))?;
+
+    if token.is_expired() {
+        tracing::info!("Expired verification token used for user: {}", token.user_id);
+        return Err(AuthError::unauthorized("Verification link has expired"));
+    }
+
+    state.writes.write(&mut conn, |conn| {
+        UserModel::mark_email_verified(conn, &token.user_id)?;
+        EmailVerificationTokens::delete_by_user(conn, &token.user_id)?;
+        if let Err(e) = onboarding::complete(conn, &token.user_id, ONBOARDING_STEP_VERIFY_EMAIL) {
+            tracing::warn!("Failed to update onboarding for user {}: {}", token.user_id, e);
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e: diesel::result::Error| {
+        tracing::error!("Failed to mark user {} as verified: {}", token.user_id, e);
+        AuthError::database("Failed to verify email")
+    })?;
+
+    cache::invalidate_user(state.cache.as_ref(), &token.user_id).await;
+
+    tracing::info!("User {} verified their email address", token.user_id);
+
+    Ok(ApiResponse::new(VerifyEmailResponse {
+        message: "Email address verified".to_string(),
+        verified_at: chrono::Utc::now(),
+    }))
+}
@@ -0,0 +1,92 @@
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use http::header::{CACHE_CONTROL, CONTENT_SECURITY_POLICY, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS};
+use http::HeaderValue;
+use serde::Deserialize;
+
+use crate::db::models::avatar_source::AvatarSources;
+use crate::errors::AuthError;
+use crate::services::avatars;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Shown when an avatar can't be fetched, so pages never render a broken image.
+const FALLBACK_AVATAR: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 64 64"><rect width="64" height="64" fill="#d4d4d8"/><circle cx="32" cy="25" r="12" fill="#a1a1aa"/><path d="M10 60c2-13 11-19 22-19s20 6 22 19z" fill="#a1a1aa"/></svg>"##;
+
+/// The fallback is cached briefly, so the real avatar shows up soon after its host recovers.
+const FALLBACK_CACHE_CONTROL: &str = "public, max-age=300";
+
+/// Uploaded avatars get a new id whenever they're replaced, so they may be cached forever.
+const UPLOADED_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+#[derive(Deserialize, Debug, Default)]
+pub struct AvatarQuery {
+    /// Requested size in pixels, snapped to the nearest served variant.
+    pub s: Option<u32>,
+}
+
+/// `GET /avatars/{id}?s=64`, the avatar a user uploaded, or their Gravatar or GitHub avatar
+/// fetched and cached by the server, so readers' browsers never contact those hosts.
+pub async fn serve_avatar(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<AvatarQuery>,
+) -> Result<Response, AuthError> {
+    if id.len() != 32 || !id.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(AuthError::not_found(id));
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while serving avatar: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let source = AvatarSources::by_id(&mut conn, &id)
+        .map_err(|e| {
+            tracing::error!("Failed to load avatar source {}: {}", id, e);
+            AuthError::database("Failed to load avatar")
+        })?;
+    drop(conn);
+
+    let size = avatars::snap_size(query.s);
+    let Some(source) = source else {
+        let bytes = state.storage.get(&avatars::variant_key(&id, size)).await?
+            .ok_or_else(|| AuthError::not_found(&id))?;
+        let mut response = bytes.into_response();
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("image/png"));
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static(UPLOADED_CACHE_CONTROL));
+        return Ok(with_image_headers(response));
+    };
+
+    let response = match state.avatars.get(&source, size).await {
+        Ok(avatar) => {
+            let mut response = avatar.bytes.into_response();
+            let headers = response.headers_mut();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(avatar.content_type));
+            if let Ok(value) = HeaderValue::from_str(&format!("public, max-age={}", state.avatars.ttl().as_secs())) {
+                headers.insert(CACHE_CONTROL, value);
+            }
+            response
+        }
+        Err(e) => {
+            tracing::warn!("Failed to fetch avatar {}: {}", source.id, e);
+            let mut response = FALLBACK_AVATAR.into_response();
+            let headers = response.headers_mut();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("image/svg+xml"));
+            headers.insert(CACHE_CONTROL, HeaderValue::from_static(FALLBACK_CACHE_CONTROL));
+            response
+        }
+    };
+
+    Ok(with_image_headers(response))
+}
+
+/// Keeps browsers from treating an avatar as anything but an image.
+fn with_image_headers(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(CONTENT_SECURITY_POLICY, HeaderValue::from_static("default-src 'none'; style-src 'unsafe-inline'"));
+    response
+}
@@ -0,0 +1,175 @@
+use axum::extract::{Path, State};
+use validator::Validate;
+
+use crate::db::models::comment::{Comments, NewComment};
+use crate::db::models::notification::NOTIFICATION_KIND_COMMENT;
+use crate::db::models::report::{Reports, REPORT_STATUS_OPEN};
+use crate::db::models::webhook::WEBHOOK_EVENT_COMMENT_CREATED;
+use crate::errors::{AppError, AuthError};
+use crate::handlers::comments::{comment_response, load_comment, CommentResponse, CreateCommentRequest};
+use crate::handlers::posts::load_published_post;
+use crate::http::auth::AuthUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::services::live::LiveEvent;
+use crate::services::notifications::{self, Event, KIND_COMMENT};
+use crate::services::spam::{self, SpamCheck, Submission, Verdict};
+use crate::services::webhooks;
+use crate::http::features::Enabled;
+use crate::http::json::AppJson;
+use crate::services::feature_flags::CommentsFeature;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+pub async fn create_comment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    client: ClientInfo,
+    _enabled: Enabled<CommentsFeature>,
+    Path(post_id): Path<String>,
+    AppJson(payload): AppJson<CreateCommentRequest>,
+) -> Result<ApiResponse<CommentResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid comment", err))?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during comment creation: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let window = state.config.load().comment_rate_window_seconds();
+    let since = chrono::Utc::now().naive_utc() - chrono::Duration::seconds(window);
+    let recent = Comments::count_recent_by_user(&mut conn, &user.id, since)
+        .map_err(|e| {
+            tracing::error!("Failed to count recent comments for user {}: {}", user.id, e);
+            AuthError::database("Failed to create comment")
+        })?;
+
+    if recent >= state.config.load().comment_rate_limit() {
+        tracing::info!("User {} hit the comment rate limit", user.id);
+        return Err(AuthError::rate_limited(
+            "You're commenting too quickly, please wait a moment",
+            window.max(0) as u64,
+        ).into());
+    }
+
+    let post = load_published_post(&mut conn, &post_id)?;
+    let author_id = post.user_id.clone();
+
+    let root_id = match &payload.parent_id {
+        Some(parent_id) => {
+            let parent = load_comment(&mut conn, parent_id)?;
+            if parent.post_id != post.id {
+                return Err(AuthError::validation("Parent comment belongs to a different post").into());
+            }
+            if parent.is_deleted() {
+                return Err(AuthError::validation("Cannot reply to a deleted comment").into());
+            }
+            Some(parent.root_id.unwrap_or(parent.id))
+        }
+        None => None,
+    };
+
+    let verdict = spam::check(&state, &Submission {
+        check: SpamCheck::Comment,
+        author: &user.name,
+        email: &user.email,
+        content: &payload.body,
+        ip_address: client.ip_address.as_deref(),
+        user_agent: client.user_agent.as_deref(),
+    })
+    .await
+    .max(Verdict::from_moderation(&user.comment_moderation));
+    if verdict == Verdict::Reject {
+        return Err(AuthError::invalid_field("body", "spam", "Your comment looks like spam").into());
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    let new_comment = NewComment {
+        id: uuid::Uuid::new_v4().to_string(),
+        post_id: post.id,
+        user_id: user.id.clone(),
+        parent_id: payload.parent_id,
+        root_id,
+        body: payload.body,
+        created_at: now,
+        updated_at: now,
+        moderation_status: verdict.moderation_status().to_string(),
+    };
+    let comment = state.writes.write(&mut conn, |conn| {
+        let comment = Comments::create(conn, &new_comment)?;
+        if verdict == Verdict::Queue {
+            Reports::create(conn, &Reports {
+                id: uuid::Uuid::new_v4().to_string(),
+                reporter_id: None,
+                post_id: comment.post_id.clone(),
+                comment_id: Some(comment.id.clone()),
+                reason: "spam".to_string(),
+                details: "Held for review by the spam check".to_string(),
+                status: REPORT_STATUS_OPEN.to_string(),
+                reviewed_by: None,
+                reviewed_at: None,
+                created_at: now,
+            })?;
+        }
+        Ok(comment)
+    })
+    .await
+    .map_err(|e: diesel::result::Error| {
+        tracing::error!("Failed to create comment for user {}: {}", user.id, e);
+        AuthError::database("Failed to create comment")
+    })?;
+
+    // Nobody else sees it yet, so nobody hears about it.
+    if comment.is_hidden() {
+        tracing::info!("Spam check held back comment {} by user {} on post {}", comment.id, user.id, comment.post_id);
+        return Ok(ApiResponse::new(comment_response(comment, Some(user.name), Some(&user.id))));
+    }
+
+    let event = Event {
+        kind: KIND_COMMENT,
+        recipient_id: &author_id,
+        actor_id: &user.id,
+        actor_name: &user.name,
+        post_id: &comment.post_id,
+    };
+    let announced = state.writes.write(&mut conn, |conn| {
+        if let Err(e) = notifications::notify(conn, &state.config.load(), &event) {
+            tracing::warn!("Failed to queue comment notification for post {}: {}", comment.post_id, e);
+        }
+        if let Err(e) = notifications::record(
+            conn,
+            &author_id,
+            NOTIFICATION_KIND_COMMENT,
+            &user.id,
+            Some(&comment.post_id),
+            Some(&comment.id),
+        ) {
+            tracing::warn!("Failed to record comment notification for post {}: {}", comment.post_id, e);
+        }
+        let data = webhooks::comment_data(&comment, &user.name);
+        if let Err(e) = webhooks::emit(conn, &author_id, WEBHOOK_EVENT_COMMENT_CREATED, data) {
+            tracing::warn!("Failed to queue webhooks for comment {}: {}", comment.id, e);
+        }
+        Ok::<_, diesel::result::Error>(())
+    });
+    if let Err(e) = announced.await {
+        tracing::warn!("Failed to announce comment {}: {}", comment.id, e);
+    }
+    if author_id != user.id {
+        state.live.publish(&author_id, LiveEvent::NewComment {
+            post_id: comment.post_id.clone(),
+            comment_id: comment.id.clone(),
+            author: user.name.clone(),
+        });
+    }
+
+    tracing::info!("User {} commented on post {}", user.id, comment.post_id);
+
+    Ok(ApiResponse::new(comment_response(comment, Some(user.name), Some(&user.id))))
+}
@@ -0,0 +1,54 @@
+use axum::extract::{Path, State};
+use tsumi_types::DeleteCommentResponse;
+
+use crate::db::models::comment::Comments;
+use crate::errors::AuthError;
+use crate::handlers::comments::load_comment;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Comments can be deleted by their author, or by an admin for moderation.
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(comment_id): Path<String>,
+) -> Result<ApiResponse<DeleteCommentResponse>, AuthError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let is_moderator = auth.user.is_admin && !auth.is_api_token();
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during comment deletion: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let comment = load_comment(&mut conn, &comment_id)?;
+    if comment.is_deleted() {
+        return Err(AuthError::not_found(comment_id));
+    }
+    if comment.user_id != user.id && !is_moderator {
+        return Err(AuthError::forbidden("You can only delete your own comments"));
+    }
+
+    state.writes.write(&mut conn, |conn| Comments::soft_delete(conn, &comment.id, &user.id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete comment {}: {}", comment.id, e);
+            AuthError::database("Failed to delete comment")
+        })?;
+
+    if comment.user_id == user.id {
+        tracing::info!("User {} deleted their comment {}", user.id, comment.id);
+    } else {
+        tracing::info!("Admin {} removed comment {} by {}", user.id, comment.id, comment.user_id);
+    }
+
+    Ok(ApiResponse::new(DeleteCommentResponse {
+        message: "Comment deleted".to_string(),
+        deleted_at: chrono::Utc::now(),
+    }))
+}
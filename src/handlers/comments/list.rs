@@ -0,0 +1,58 @@
+use axum::extract::{Path, Query, State};
+use tsumi_types::ListCommentsResponse;
+
+use crate::db::models::comment::Comments;
+use crate::errors::{AppError, AuthError};
+use crate::handlers::comments::{build_threads, ListCommentsQuery, COMMENTS_PER_PAGE};
+use crate::handlers::posts::load_published_post;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Pages through top-level comments; each carries its whole reply tree.
+pub async fn list_comments(
+    State(state): State<AppState>,
+    auth: Option<AuthUser>,
+    Path(post_id): Path<String>,
+    Query(query): Query<ListCommentsQuery>,
+) -> Result<ApiResponse<ListCommentsResponse>, AppError> {
+    let page = query.page.unwrap_or(1);
+    if page < 1 {
+        return Err(AuthError::validation("Page must be at least 1").into());
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing comments: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let post = load_published_post(&mut conn, &post_id)?;
+
+    let total_threads = Comments::count_roots(&mut conn, &post.id)
+        .map_err(|e| {
+            tracing::error!("Failed to count comments for post {}: {}", post.id, e);
+            AuthError::database("Failed to list comments")
+        })?;
+
+    let roots = Comments::roots_page(&mut conn, &post.id, (page - 1) * COMMENTS_PER_PAGE, COMMENTS_PER_PAGE)
+        .map_err(|e| {
+            tracing::error!("Failed to list comments for post {}: {}", post.id, e);
+            AuthError::database("Failed to list comments")
+        })?;
+
+    let root_ids: Vec<String> = roots.iter().map(|(comment, _, _)| comment.id.clone()).collect();
+    let replies = Comments::replies_for_roots(&mut conn, &root_ids)
+        .map_err(|e| {
+            tracing::error!("Failed to load replies for post {}: {}", post.id, e);
+            AuthError::database("Failed to list comments")
+        })?;
+
+    Ok(ApiResponse::new(ListCommentsResponse {
+        comments: build_threads(roots, replies, auth.as_ref().map(|auth| auth.user.id.as_str())),
+        page,
+        total_pages: (total_threads + COMMENTS_PER_PAGE - 1) / COMMENTS_PER_PAGE,
+        total_threads,
+    }))
+}
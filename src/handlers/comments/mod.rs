@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use diesel::SqliteConnection;
+use serde::Deserialize;
+
+use crate::db::models::comment::Comments;
+use crate::db::queries::comments::CommentWithAuthor;
+use crate::errors::AuthError;
+use crate::services::markdown;
+
+pub mod create;
+pub mod delete;
+pub mod list;
+pub mod update;
+
+pub use tsumi_types::{CommentResponse, CreateCommentRequest, UpdateCommentRequest};
+
+pub const COMMENTS_PER_PAGE: i64 = 20;
+
+#[derive(Deserialize, Debug)]
+pub struct ListCommentsQuery {
+    pub page: Option<i64>,
+}
+
+/// The comment as `viewer_id` sees it: comments the spam check holds back look normal to
+/// their own author.
+pub fn comment_response(comment: Comments, author: Option<String>, viewer_id: Option<&str>) -> CommentResponse {
+    let deleted = comment.is_deleted();
+    let hidden = !comment.visible_to(viewer_id);
+    let pending_review = comment.is_pending() && !hidden;
+    let shown = !deleted && !hidden;
+    CommentResponse {
+        body_html: shown.then(|| markdown::render(&comment.body)),
+        body: shown.then_some(comment.body),
+        author: if shown { author } else { None },
+        id: comment.id,
+        post_id: comment.post_id,
+        parent_id: comment.parent_id,
+        deleted,
+        hidden,
+        pending_review,
+        created_at: comment.created_at,
+        updated_at: comment.updated_at,
+        replies: Vec::new(),
+    }
+}
+
+fn comment_from_row((comment, author, author_deleted_at): CommentWithAuthor, viewer_id: Option<&str>) -> CommentResponse {
+    comment_response(comment, author_deleted_at.is_none().then_some(author), viewer_id)
+}
+
+/// Nests replies under their parents. Replies whose parent isn't in `replies` are dropped.
+pub fn build_threads(
+    roots: Vec<CommentWithAuthor>,
+    replies: Vec<CommentWithAuthor>,
+    viewer_id: Option<&str>,
+) -> Vec<CommentResponse> {
+    let mut children: HashMap<String, Vec<CommentResponse>> = HashMap::new();
+    for row in replies {
+        let reply = comment_from_row(row, viewer_id);
+        if let Some(parent_id) = reply.parent_id.clone() {
+            children.entry(parent_id).or_default().push(reply);
+        }
+    }
+
+    fn attach(mut comment: CommentResponse, children: &mut HashMap<String, Vec<CommentResponse>>) -> CommentResponse {
+        if let Some(replies) = children.remove(&comment.id) {
+            comment.replies = replies.into_iter().map(|reply| attach(reply, children)).collect();
+        }
+        comment
+    }
+
+    roots
+        .into_iter()
+        .map(|row| attach(comment_from_row(row, viewer_id), &mut children))
+        .collect()
+}
+
+pub fn load_comment(conn: &mut SqliteConnection, comment_id: &str) -> Result<Comments, AuthError> {
+    Comments::by_id(conn, comment_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load comment {}: {}", comment_id, e);
+            AuthError::database("Failed to load comment")
+        })?
+        .ok_or_else(|| AuthError::not_found(comment_id))
+}
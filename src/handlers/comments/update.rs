@@ -0,0 +1,50 @@
+use axum::extract::{Path, State};
+use validator::Validate;
+
+use crate::db::models::comment::Comments;
+use crate::errors::{AppError, AuthError};
+use crate::handlers::comments::{comment_response, load_comment, CommentResponse, UpdateCommentRequest};
+use crate::handlers::posts::load_published_post;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Only the comment's author can edit it.
+pub async fn update_comment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(comment_id): Path<String>,
+    AppJson(payload): AppJson<UpdateCommentRequest>,
+) -> Result<ApiResponse<CommentResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid comment", err))?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during comment update: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let comment = load_comment(&mut conn, &comment_id)?;
+    if comment.user_id != user.id || comment.is_deleted() {
+        return Err(AuthError::not_found(comment_id).into());
+    }
+    load_published_post(&mut conn, &comment.post_id)?;
+
+    let comment = state.writes.write(&mut conn, |conn| Comments::update_body(conn, &comment.id, &payload.body))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update comment {}: {}", comment.id, e);
+            AuthError::database("Failed to update comment")
+        })?;
+
+    tracing::info!("User {} edited comment {}", user.id, comment.id);
+
+    Ok(ApiResponse::new(comment_response(comment, Some(user.name), Some(&user.id))))
+}
@@ -0,0 +1,347 @@
+use axum::extract::{FromRequestParts, OptionalFromRequestParts, Path, Query, State};
+use axum::response::{Html, IntoResponse, Response};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::request::Parts;
+use serde::{Deserialize, Serialize};
+use tower_cookies::Cookies;
+
+use crate::db::models::api_token::ApiTokens;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::http::auth::{AdminUser, AuthUser, SudoUser, SUDO_TOKEN_HEADER};
+use crate::http::dto::ApiResponse;
+use crate::http::locale::Locale;
+use crate::services::api_tokens::{hash_api_token, is_api_token};
+use crate::services::cookies::{self, AuthCookie};
+use crate::services::email_templates::EmailTemplate;
+use crate::services::jwt::{inspect_token, is_opaque_refresh_token};
+#[cfg(debug_assertions)]
+use crate::services::seed::{seed, SeedReport};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct WhoAmIResponse {
+    pub environment: String,
+    pub user: Option<UserSummary>,
+    pub access_token: Option<TokenReport>,
+    pub api_token: Option<ApiTokenReport>,
+    pub refresh_token: Option<TokenReport>,
+    pub session: Option<SessionReport>,
+    pub sudo_token: Option<TokenReport>,
+    /// What each authentication extractor would decide for this request.
+    pub decisions: Vec<Decision>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserSummary {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub is_admin: bool,
+    pub email_verified: bool,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenReport {
+    /// Where the token was found, e.g. `cookie:access_token` or `header:authorization`.
+    pub source: String,
+    pub claims: Option<serde_json::Value>,
+    pub signature_valid: bool,
+    pub issued_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Negative once the token has expired.
+    pub expires_in_seconds: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiTokenReport {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expired: bool,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionReport {
+    /// Whether the refresh token still has a row in `refresh_tokens`. Rotated and signed-out
+    /// tokens don't.
+    pub found: bool,
+    pub user_id: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub expired: Option<bool>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Decision {
+    pub extractor: &'static str,
+    pub allowed: bool,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmailPreviewQuery {
+    /// A locale tag; English when left out.
+    pub lang: Option<String>,
+    /// `text` for the plain text part instead of the HTML one.
+    pub format: Option<String>,
+}
+
+/// `GET /api/v1/dev/whoami`, a report on whatever credentials the request carries. Only mounted when
+/// `APP_ENV=development`; it echoes token claims back, which production should never do.
+pub async fn whoami(
+    State(state): State<AppState>,
+    cookies: Cookies,
+    mut parts: Parts,
+) -> Result<ApiResponse<WhoAmIResponse>, AuthError> {
+    let config = state.config.load();
+    let now = Utc::now();
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during whoami: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let bearer = parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_owned());
+
+    let (access, api_token) = match bearer {
+        Some(token) if is_api_token(&token) => {
+            let api_token = ApiTokens::by_hash(&mut conn, &hash_api_token(&token))
+                .map_err(|e| {
+                    tracing::error!("Failed to look up API token: {}", e);
+                    AuthError::database("Failed to look up API token")
+                })?
+                .map(|api_token| ApiTokenReport {
+                    expired: api_token.is_expired(),
+                    scopes: api_token.scope_list(),
+                    id: api_token.id,
+                    name: api_token.name,
+                    expires_at: api_token.expires_at,
+                    last_used_at: api_token.last_used_at,
+                });
+            (None, api_token)
+        }
+        Some(token) => (Some(report("header:authorization", &token, config.access_token_secret(), now)), None),
+        None => {
+            let name = AuthCookie::Access.name(&config);
+            let access = cookies::get(&cookies, &config, AuthCookie::Access)
+                .map(|token| cookie_report(name, &token, config.access_token_secret(), now));
+            (access, None)
+        }
+    };
+
+    let refresh_name = AuthCookie::Refresh.name(&config);
+    let refresh_value = cookies::get(&cookies, &config, AuthCookie::Refresh);
+    let refresh = refresh_value.as_deref().map(|token| {
+        if is_opaque_refresh_token(token) {
+            opaque_report(refresh_name)
+        } else {
+            cookie_report(refresh_name, token, config.refresh_token_secret(), now)
+        }
+    });
+
+    drop(conn);
+
+    let session = match refresh_value.as_deref() {
+        Some(token) => Some(session_report(&state, token).await?),
+        None => None,
+    };
+
+    let sudo_token = match parts.headers.get(SUDO_TOKEN_HEADER).and_then(|value| value.to_str().ok()) {
+        Some(token) => Some(report(&format!("header:{}", SUDO_TOKEN_HEADER), token, config.access_token_secret(), now)),
+        None => cookies::get(&cookies, &config, AuthCookie::Sudo)
+            .map(|token| cookie_report(AuthCookie::Sudo.name(&config), &token, config.access_token_secret(), now)),
+    };
+
+    let mut decisions = Vec::new();
+    let auth = <AuthUser as FromRequestParts<AppState>>::from_request_parts(&mut parts, &state).await;
+    decisions.push(decision("AuthUser", &auth, |auth| match &auth.scopes {
+        Some(scopes) => format!("API token for {} with scopes [{}]", auth.user.id, scopes.join(", ")),
+        None => format!("session for {}", auth.user.id),
+    }));
+
+    let optional = <AuthUser as OptionalFromRequestParts<AppState>>::from_request_parts(&mut parts, &state).await;
+    decisions.push(decision("Option<AuthUser>", &optional, |auth| match auth {
+        Some(auth) => format!("signed in as {}", auth.user.id),
+        None => "anonymous".to_string(),
+    }));
+
+    let sudo = <SudoUser as FromRequestParts<AppState>>::from_request_parts(&mut parts, &state).await;
+    decisions.push(decision("SudoUser", &sudo, |sudo| format!("re-authenticated as {}", sudo.user.id)));
+
+    let admin = <AdminUser as FromRequestParts<AppState>>::from_request_parts(&mut parts, &state).await;
+    decisions.push(decision("AdminUser", &admin, |admin| format!("admin {}", admin.user.id)));
+
+    let user = auth.ok().map(|auth| summary(auth.user));
+
+    Ok(ApiResponse::new(WhoAmIResponse {
+        environment: config.environment().to_string(),
+        user,
+        access_token: access,
+        api_token,
+        refresh_token: refresh,
+        session,
+        sudo_token,
+        decisions,
+        checked_at: now,
+    }))
+}
+
+fn cookie_report(name: &str, token: &str, secret: &str, now: DateTime<Utc>) -> TokenReport {
+    report(&format!("cookie:{}", name), token, secret, now)
+}
+
+fn report(source: &str, token: &str, secret: &str, now: DateTime<Utc>) -> TokenReport {
+    match inspect_token(token, secret) {
+        Ok((claims, signature_valid)) => {
+            let timestamp = |field: &str| {
+                claims.get(field).and_then(|value| value.as_i64()).and_then(|ts| DateTime::from_timestamp(ts, 0))
+            };
+            let issued_at = timestamp("iat");
+            let expires_at = timestamp("exp");
+            TokenReport {
+                source: source.to_string(),
+                signature_valid,
+                issued_at,
+                expires_at,
+                expires_in_seconds: expires_at.map(|exp| (exp - now).num_seconds()),
+                error: (!signature_valid).then(|| "Signature does not match the configured secret".to_string()),
+                claims: Some(claims),
+            }
+        }
+        Err(e) => TokenReport {
+            source: source.to_string(),
+            claims: None,
+            signature_valid: false,
+            issued_at: None,
+            expires_at: None,
+            expires_in_seconds: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// Opaque refresh tokens carry no claims; the session report says everything there is to know.
+fn opaque_report(name: &str) -> TokenReport {
+    TokenReport {
+        source: format!("cookie:{}", name),
+        claims: None,
+        signature_valid: false,
+        issued_at: None,
+        expires_at: None,
+        expires_in_seconds: None,
+        error: Some("Opaque token; see the session for its expiry".to_string()),
+    }
+}
+
+async fn session_report(state: &AppState, token: &str) -> Result<SessionReport, AuthError> {
+    let record = state.sessions.by_token(token)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up session: {}", e);
+            AuthError::database("Failed to look up session")
+        })?;
+
+    Ok(match record {
+        Some(record) => SessionReport {
+            found: true,
+            expired: Some(record.expires_at < Utc::now().naive_utc()),
+            user_id: Some(record.user_id),
+            created_at: Some(record.created_at),
+            expires_at: Some(record.expires_at),
+            ip_address: record.ip_address,
+            user_agent: record.user_agent,
+        },
+        None => SessionReport {
+            found: false,
+            user_id: None,
+            created_at: None,
+            expires_at: None,
+            expired: None,
+            ip_address: None,
+            user_agent: None,
+        },
+    })
+}
+
+/// `GET /api/v1/dev/emails/{template}`, an email rendered with made-up details, so template
+/// edits can be checked in a browser. The HTML part carries the subject as its title; the
+/// text one is headed by it.
+pub async fn preview_email(
+    State(state): State<AppState>,
+    Path(template): Path<String>,
+    Query(query): Query<EmailPreviewQuery>,
+) -> Result<Response, AuthError> {
+    let template = template.parse::<EmailTemplate>().map_err(|_| AuthError::not_found(template))?;
+    let locale = match query.lang.as_deref() {
+        Some(tag) => Locale::from_tag(tag).ok_or_else(|| {
+            let tags: Vec<&str> = Locale::ALL.iter().map(|locale| locale.tag()).collect();
+            AuthError::invalid_field("lang", "invalid", format!("Language must be one of {}", tags.join(", ")))
+        })?,
+        None => Locale::DEFAULT,
+    };
+
+    let message = template.sample(&state.templates, locale).map_err(|e| {
+        tracing::error!("Failed to render the {} email: {:?}", template.name(), e);
+        AuthError::internal(format!("Failed to render the {} email: {}", template.name(), e))
+    })?;
+
+    let response = match query.format.as_deref() {
+        Some("text") => {
+            let body = format!("Subject: {}\n\n{}", message.subject, message.text_body);
+            ([(CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response()
+        }
+        _ => Html(message.html_body.unwrap_or_default()).into_response(),
+    };
+    Ok(response)
+}
+
+/// `POST /api/v1/dev/seed`, the `tsumi seed` command for frontends that can only make requests.
+/// Compiled into debug builds only, and mounted with the other development routes.
+#[cfg(debug_assertions)]
+pub async fn seed_database(State(state): State<AppState>) -> Result<ApiResponse<SeedReport>, AuthError> {
+    let config = state.config.load();
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection to seed: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let _turn = state.writes.acquire().await;
+    let report = seed(&mut conn, &config)?.ok_or_else(|| AuthError::conflict("The database is already seeded"))?;
+    tracing::info!("Seeded the database with {} users and {} posts", report.users, report.posts);
+    Ok(ApiResponse::new(report))
+}
+
+fn decision<T>(extractor: &'static str, result: &Result<T, AuthError>, describe: impl Fn(&T) -> String) -> Decision {
+    match result {
+        Ok(value) => Decision { extractor, allowed: true, reason: describe(value) },
+        Err(e) => Decision { extractor, allowed: false, reason: format!("{} ({})", e, e.error_code()) },
+    }
+}
+
+fn summary(user: UserModel) -> UserSummary {
+    UserSummary {
+        id: user.id,
+        name: user.name,
+        email: user.email,
+        is_admin: user.is_admin,
+        email_verified: user.email_verified,
+        deleted_at: user.deleted_at,
+    }
+}
@@ -0,0 +1,48 @@
+use axum::extract::{Query, State};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::user_preferences::UserPreferences;
+use crate::errors::AuthError;
+use crate::http::dto::ApiResponse;
+use crate::services::digest::verify_unsubscribe_token;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Deserialize)]
+pub struct UnsubscribeQuery {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnsubscribeResponse {
+    pub message: String,
+}
+
+/// `GET /digest/unsubscribe?token=`, the link at the bottom of every weekly digest. The
+/// token is signed for one user, so no login is needed.
+pub async fn unsubscribe_digest(
+    State(state): State<AppState>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<ApiResponse<UnsubscribeResponse>, AuthError> {
+    let user_id = verify_unsubscribe_token(state.config.load().access_token_secret(), &query.token)
+        .ok_or_else(|| AuthError::validation("Invalid unsubscribe link"))?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while unsubscribing from the digest: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    state.writes.write(&mut conn, |conn| UserPreferences::set_weekly_digest(conn, &user_id, false))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to unsubscribe user {} from the digest: {}", user_id, e);
+            AuthError::database("Failed to unsubscribe")
+        })?;
+
+    tracing::info!("User {} unsubscribed from the weekly digest", user_id);
+
+    Ok(ApiResponse::new(UnsubscribeResponse {
+        message: "You won't get the weekly digest any more".to_string(),
+    }))
+}
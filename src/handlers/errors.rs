@@ -0,0 +1,35 @@
+use axum::response::IntoResponse;
+use http::header::CONTENT_LANGUAGE;
+use serde::Serialize;
+
+use crate::errors::ErrorKind;
+use crate::http::dto::ApiResponse;
+use crate::http::locale::Locale;
+
+#[derive(Debug, Serialize)]
+pub struct ErrorCodeResponse {
+    pub code: &'static str,
+    pub status: u16,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorCatalogResponse {
+    pub locale: &'static str,
+    pub errors: Vec<ErrorCodeResponse>,
+}
+
+/// Every error code the API can return, with the status it's sent with and a description in
+/// the caller's `Accept-Language`.
+pub async fn list_error_codes(locale: Locale) -> impl IntoResponse {
+    let errors = ErrorKind::ALL
+        .iter()
+        .map(|kind| ErrorCodeResponse {
+            code: kind.code(),
+            status: kind.status().as_u16(),
+            description: locale.describe(*kind),
+        })
+        .collect();
+
+    ([(CONTENT_LANGUAGE, locale.tag())], ApiResponse::new(ErrorCatalogResponse { locale: locale.tag(), errors }))
+}
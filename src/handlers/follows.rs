@@ -0,0 +1,234 @@
+use axum::extract::{Path, State};
+use chrono::NaiveDateTime;
+use diesel::SqliteConnection;
+use serde::Serialize;
+
+use crate::db::models::follow::Follows;
+use crate::db::models::notification::NOTIFICATION_KIND_FOLLOW;
+use crate::db::models::post::Posts;
+use crate::db::models::user_model::UserModel;
+use crate::errors::{AppError, AuthError};
+use crate::handlers::posts::list_responses;
+use crate::http::auth::AuthUser;
+use crate::http::dto::{ApiResponse, PostDto};
+use crate::http::pagination::{ListParams, Paginated, Sortable};
+use crate::services::api_tokens::{SCOPE_POSTS_READ, SCOPE_PROFILE_WRITE};
+use crate::services::notifications;
+use crate::services::onboarding;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Sort keys for follower and following lists.
+pub struct FollowSort;
+
+impl Sortable for FollowSort {
+    const SORT_FIELDS: &'static [&'static str] = &["followed_at"];
+}
+
+/// Sort keys for the feed.
+pub struct FeedSort;
+
+impl Sortable for FeedSort {
+    const SORT_FIELDS: &'static [&'static str] = &["published_at"];
+}
+
+#[derive(Debug, Serialize)]
+pub struct FollowUserResponse {
+    pub id: String,
+    pub name: String,
+    pub followed_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FollowResponse {
+    pub following: bool,
+    pub followers: i64,
+}
+
+fn load_active_user(conn: &mut SqliteConnection, id: &str) -> Result<UserModel, AuthError> {
+    UserModel::by_id(conn, id)
+        .map_err(|e| {
+            tracing::error!("Failed to load user {}: {}", id, e);
+            AuthError::database("Failed to load user")
+        })?
+        .ok_or_else(|| AuthError::not_found(id))
+}
+
+fn count_followers(conn: &mut SqliteConnection, id: &str) -> Result<i64, AuthError> {
+    Follows::count_followers(conn, id).map_err(|e| {
+        tracing::error!("Failed to count followers of user {}: {}", id, e);
+        AuthError::database("Failed to count followers")
+    })
+}
+
+pub async fn follow_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<FollowResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+    if auth.user.id == id {
+        return Err(AuthError::validation("You can't follow yourself"));
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while following: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let followee = load_active_user(&mut conn, &id)?;
+
+    let follow = Follows {
+        follower_id: auth.user.id.clone(),
+        followee_id: followee.id.clone(),
+        created_at: chrono::Utc::now().naive_utc(),
+    };
+    let created = state.writes.write(&mut conn, |conn| {
+        let created = Follows::follow(conn, &follow)?;
+        if created {
+            if let Err(e) = notifications::record(conn, &followee.id, NOTIFICATION_KIND_FOLLOW, &auth.user.id, None, None) {
+                tracing::warn!("Failed to record follow notification for user {}: {}", followee.id, e);
+            }
+            if let Err(e) = onboarding::followed(conn, &state.config.load(), &auth.user.id) {
+                tracing::warn!("Failed to update onboarding for user {}: {}", auth.user.id, e);
+            }
+        }
+        Ok(created)
+    })
+    .await
+    .map_err(|e: diesel::result::Error| {
+        tracing::error!("Failed to follow user {} as {}: {}", followee.id, auth.user.id, e);
+        AuthError::database("Failed to follow user")
+    })?;
+    if created {
+        tracing::info!("User {} followed {}", auth.user.id, followee.id);
+    }
+
+    let followers = count_followers(&mut conn, &followee.id)?;
+
+    Ok(ApiResponse::new(FollowResponse { following: true, followers }))
+}
+
+pub async fn unfollow_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<FollowResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while unfollowing: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let followee = load_active_user(&mut conn, &id)?;
+
+    let removed = state.writes.write(&mut conn, |conn| Follows::unfollow(conn, &auth.user.id, &followee.id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to unfollow user {} as {}: {}", followee.id, auth.user.id, e);
+            AuthError::database("Failed to unfollow user")
+        })?;
+    if removed {
+        tracing::info!("User {} unfollowed {}", auth.user.id, followee.id);
+    }
+
+    let followers = count_followers(&mut conn, &followee.id)?;
+
+    Ok(ApiResponse::new(FollowResponse { following: false, followers }))
+}
+
+/// Active users following `id`, most recent first unless `dir=asc`.
+pub async fn list_followers(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    params: ListParams<FollowSort>,
+) -> Result<ApiResponse<Paginated<FollowUserResponse>>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing followers: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let user = load_active_user(&mut conn, &id)?;
+
+    let total = count_followers(&mut conn, &user.id)?;
+    let followers = Follows::followers_page(&mut conn, &user.id, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to list followers of user {}: {}", user.id, e);
+            AuthError::database("Failed to list followers")
+        })?;
+
+    let followers = followers
+        .into_iter()
+        .map(|(user, followed_at)| FollowUserResponse { id: user.id, name: user.name, followed_at })
+        .collect();
+
+    Ok(ApiResponse::new(params.paginate(followers, total)))
+}
+
+/// Active users `id` follows, most recently followed first unless `dir=asc`.
+pub async fn list_following(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    params: ListParams<FollowSort>,
+) -> Result<ApiResponse<Paginated<FollowUserResponse>>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing followed users: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let user = load_active_user(&mut conn, &id)?;
+
+    let total = Follows::count_following(&mut conn, &user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to count users followed by {}: {}", user.id, e);
+            AuthError::database("Failed to list followed users")
+        })?;
+    let following = Follows::following_page(&mut conn, &user.id, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to list users followed by {}: {}", user.id, e);
+            AuthError::database("Failed to list followed users")
+        })?;
+
+    let following = following
+        .into_iter()
+        .map(|(user, followed_at)| FollowUserResponse { id: user.id, name: user.name, followed_at })
+        .collect();
+
+    Ok(ApiResponse::new(params.paginate(following, total)))
+}
+
+/// Published posts from the authors the caller follows, newest first unless `dir=asc`.
+pub async fn feed(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    params: ListParams<FeedSort>,
+) -> Result<ApiResponse<Paginated<PostDto>>, AppError> {
+    auth.require_scope(SCOPE_POSTS_READ)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading the feed: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let total = Posts::count_feed(&mut conn, &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to count feed posts for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to load feed")
+        })?;
+
+    let posts = Posts::feed_page(&mut conn, &auth.user.id, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to load feed for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to load feed")
+        })?;
+
+    let responses = list_responses(&mut conn, posts)?;
+
+    Ok(ApiResponse::new(params.paginate(responses, total)))
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::errors::AuthError;
+use crate::http::auth::AuthUser;
+use crate::services::api_tokens::SCOPE_PROFILE_READ;
+use crate::state::AppState;
+
+/// `GET /ws`, a stream of live events for the signed-in user as JSON text frames, e.g.
+/// `{"type":"new_comment",...}`. Messages from the client are ignored. The server pings
+/// periodically and closes connections that stay silent past the idle timeout.
+pub async fn live_events(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+
+    Ok(ws.on_upgrade(move |socket| run_live(state, auth.user.id, socket)))
+}
+
+async fn run_live(state: AppState, user_id: String, mut socket: WebSocket) {
+    let mut events = state.live.subscribe(&user_id);
+    tracing::debug!("User {} connected to live events ({} users connected)", user_id, state.live.connected_users());
+
+    let idle_timeout = Duration::from_secs(state.config.load().live_idle_timeout_seconds().max(1));
+    let mut ping = tokio::time::interval(Duration::from_secs(state.config.load().live_ping_interval_seconds().max(1)));
+    ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_heard = Instant::now();
+
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pongs and anything else the client sends just show it's still there.
+                Some(Ok(_)) => last_heard = Instant::now(),
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let Ok(text) = serde_json::to_string(&event) else { continue };
+                    if socket.send(Message::Text(text.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Live connection of user {} missed {} events", user_id, skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = ping.tick() => {
+                if last_heard.elapsed() > idle_timeout {
+                    let _ = socket.send(Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "Idle timeout".into(),
+                    }))).await;
+                    break;
+                }
+                if socket.send(Message::Ping(Vec::new().into())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    drop(events);
+    state.live.unsubscribe(&user_id);
+    tracing::debug!("User {} disconnected from live events", user_id);
+}
@@ -0,0 +1,60 @@
+use axum::extract::State;
+use serde::Serialize;
+use tower_cookies::Cookies;
+
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::http::auth::SudoUser;
+use crate::http::dto::ApiResponse;
+use crate::services::cache;
+use crate::services::cookies::{self, AuthCookie};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct DeleteAccountResponse {
+    pub message: String,
+    pub deleted_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn delete_account(
+    State(state): State<AppState>,
+    sudo: SudoUser,
+    cookies: Cookies,
+) -> Result<ApiResponse<DeleteAccountResponse>, AuthError> {
+    let user = sudo.user;
+    tracing::info!("Processing account deletion for user: {}", user.id);
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during account deletion: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    state.writes.write(&mut conn, |conn| UserModel::soft_delete(conn, &user.id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete user {}: {}", user.id, e);
+            AuthError::database("Failed to delete account")
+        })?;
+
+    // The database cascade only covers sessions kept in the database.
+    if let Err(e) = state.sessions.delete_by_user(&user.id).await {
+        tracing::error!("Failed to end sessions of deleted user {}: {}", user.id, e);
+    }
+
+    cache::invalidate_user(state.cache.as_ref(), &user.id).await;
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    let config = state.config.load();
+    for kind in [AuthCookie::Access, AuthCookie::Refresh, AuthCookie::Sudo] {
+        cookies::clear(&cookies, &config, kind);
+    }
+
+    tracing::info!("User {} deleted their account", user.id);
+
+    Ok(ApiResponse::new(DeleteAccountResponse {
+        message: "Account deleted".to_string(),
+        deleted_at: chrono::Utc::now(),
+    }))
+}
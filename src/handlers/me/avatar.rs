@@ -0,0 +1,149 @@
+use axum::extract::{Multipart, State};
+use http::StatusCode;
+
+use crate::db::models::onboarding_step::ONBOARDING_STEP_SET_AVATAR;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::me::AvatarResponse;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::services::api_tokens::SCOPE_PROFILE_WRITE;
+use crate::services::avatars::{self, UPLOAD_TYPES};
+use crate::services::cache;
+use crate::services::images::sniff;
+use crate::services::onboarding;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// `PUT /api/v1/me/avatar`, a multipart form with a PNG, JPEG or WebP image in a `file` field.
+/// It's stored at every served size, replacing the previous upload.
+pub async fn update_avatar(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut multipart: Multipart,
+) -> Result<ApiResponse<AvatarResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+    let user = auth.user;
+    let max_bytes = state.config.load().upload_max_bytes();
+
+    let field = loop {
+        let field = multipart.next_field().await
+            .map_err(|e| match e.status() {
+                StatusCode::PAYLOAD_TOO_LARGE => {
+                    AuthError::payload_too_large(format!("Avatars are limited to {} bytes", max_bytes))
+                }
+                _ => AuthError::validation(format!("Invalid multipart body: {}", e.body_text())),
+            })?
+            .ok_or_else(|| AuthError::validation("Missing `file` field"))?;
+
+        if field.name() == Some("file") {
+            break field;
+        }
+    };
+
+    let declared = field.content_type().unwrap_or_default().to_string();
+    let bytes = field.bytes().await
+        .map_err(|e| match e.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => {
+                AuthError::payload_too_large(format!("Avatars are limited to {} bytes", max_bytes))
+            }
+            _ => AuthError::validation(format!("Failed to read avatar: {}", e.body_text())),
+        })?;
+
+    if bytes.is_empty() {
+        return Err(AuthError::validation("Uploaded file is empty"));
+    }
+    if bytes.len() > max_bytes {
+        return Err(AuthError::payload_too_large(format!("Avatars are limited to {} bytes", max_bytes)));
+    }
+    sniff(&declared, &bytes)
+        .filter(|(content_type, _)| UPLOAD_TYPES.contains(content_type))
+        .ok_or_else(|| {
+            AuthError::unsupported_media_type(format!("Accepted avatar types are {}", UPLOAD_TYPES.join(", ")))
+        })?;
+
+    // Decoding and resampling are CPU-bound, so they stay off the async workers.
+    let variants = tokio::task::spawn_blocking(move || avatars::resize(&bytes)).await
+        .map_err(|e| AuthError::internal(format!("Avatar resizing failed: {}", e)))?
+        .map_err(|e| {
+            tracing::info!("Rejected an unreadable avatar from user {}: {}", user.id, e);
+            AuthError::validation("The image couldn't be read")
+        })?;
+
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    for (size, png) in variants {
+        if let Err(e) = state.storage.put(&avatars::variant_key(&id, size), "image/png", png).await {
+            tracing::error!("Failed to store avatar for user {}: {}", user.id, e);
+            avatars::delete_uploaded(state.storage.as_ref(), &id).await;
+            return Err(e);
+        }
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while saving avatar: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let avatar_url = avatars::uploaded_path(&id);
+    let saved = state.writes.write(&mut conn, |conn| {
+        UserModel::set_avatar_url(conn, &user.id, Some(&avatar_url))?;
+        if let Err(e) = onboarding::complete(conn, &user.id, ONBOARDING_STEP_SET_AVATAR) {
+            tracing::warn!("Failed to update onboarding for user {}: {}", user.id, e);
+        }
+        Ok::<_, diesel::result::Error>(())
+    });
+    if let Err(e) = saved.await {
+        tracing::error!("Failed to record avatar {} for user {}: {}", id, user.id, e);
+        drop(conn);
+        avatars::delete_uploaded(state.storage.as_ref(), &id).await;
+        return Err(AuthError::database("Failed to save avatar"));
+    }
+    drop(conn);
+
+    if let Some(previous) = user.avatar_url.as_deref().and_then(avatars::uploaded_id) {
+        avatars::delete_uploaded(state.storage.as_ref(), previous).await;
+    }
+    cache::invalidate_user(state.cache.as_ref(), &user.id).await;
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    tracing::info!("User {} uploaded avatar {}", user.id, id);
+
+    Ok(ApiResponse::new(AvatarResponse { avatar_url, uploaded: true }))
+}
+
+/// `DELETE /api/v1/me/avatar`, going back to the GitHub avatar or Gravatar.
+pub async fn delete_avatar(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<AvatarResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while removing avatar: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let updated = state.writes.write(&mut conn, |conn| UserModel::set_avatar_url(conn, &user.id, None))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to remove avatar for user {}: {}", user.id, e);
+            AuthError::database("Failed to remove avatar")
+        })?;
+    let avatar_url = avatars::avatar_path(&mut conn, &updated)
+        .map_err(|e| {
+            tracing::error!("Failed to look up avatar for {}: {}", user.id, e);
+            AuthError::database("Failed to load avatar")
+        })?;
+    drop(conn);
+
+    if let Some(previous) = user.avatar_url.as_deref().and_then(avatars::uploaded_id) {
+        avatars::delete_uploaded(state.storage.as_ref(), previous).await;
+    }
+    cache::invalidate_user(state.cache.as_ref(), &user.id).await;
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    Ok(ApiResponse::new(AvatarResponse { avatar_url, uploaded: false }))
+}
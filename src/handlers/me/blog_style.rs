@@ -0,0 +1,161 @@
+use axum::extract::{Path, State};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::db::models::blog_style::BlogStyles;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::me::UpdateBlogStyleRequest;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::http::tx::Tx;
+use crate::services::api_tokens::{SCOPE_PROFILE_READ, SCOPE_PROFILE_WRITE};
+use crate::services::blog_styles::{sanitize_css, sanitize_head};
+use crate::services::cache;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct BlogStyleResponse {
+    /// `None` until styles are saved for the first time.
+    pub version: Option<i32>,
+    pub css: String,
+    pub head_html: String,
+    pub updated_at: Option<NaiveDateTime>,
+    /// Whether the styles are currently rendered on the blog. They aren't when an admin has
+    /// switched them off for this blog or for the whole site.
+    pub active: bool,
+}
+
+impl BlogStyleResponse {
+    fn new(style: Option<BlogStyles>, user: &UserModel, state: &AppState) -> Self {
+        let active = state.config.load().blog_styles_enabled() && !user.blog_styles_disabled;
+        match style {
+            Some(style) => Self {
+                version: Some(style.version),
+                css: style.css,
+                head_html: style.head_html,
+                updated_at: Some(style.created_at),
+                active,
+            },
+            None => Self { version: None, css: String::new(), head_html: String::new(), updated_at: None, active },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlogStyleVersionResponse {
+    pub version: i32,
+    pub css: String,
+    pub head_html: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<BlogStyles> for BlogStyleVersionResponse {
+    fn from(style: BlogStyles) -> Self {
+        Self { version: style.version, css: style.css, head_html: style.head_html, created_at: style.created_at }
+    }
+}
+
+pub async fn get_blog_style(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<BlogStyleResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading blog styles: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let style = BlogStyles::current(&mut conn, &user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load blog styles for user {}: {}", user.id, e);
+            AuthError::database("Failed to load blog styles")
+        })?;
+
+    Ok(ApiResponse::new(BlogStyleResponse::new(style, &user, &state)))
+}
+
+/// Saves new custom CSS and head snippets as the next version. CSS that could inject script
+/// is rejected; head snippets are cleaned down to `<meta>` and https `<link>` tags.
+pub async fn update_blog_style(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut tx: Tx,
+    AppJson(payload): AppJson<UpdateBlogStyleRequest>,
+) -> Result<ApiResponse<BlogStyleResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+    let user = auth.user;
+
+    let css = sanitize_css(&payload.css)?;
+    let head_html = sanitize_head(&payload.head_html)?;
+
+    let style = BlogStyles::record(&mut tx, &user.id, &css, &head_html)
+        .map_err(|e| {
+            tracing::error!("Failed to save blog styles for user {}: {}", user.id, e);
+            AuthError::database("Failed to save blog styles")
+        })?;
+
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    tracing::info!("User {} saved blog styles version {}", user.id, style.version);
+
+    Ok(ApiResponse::new(BlogStyleResponse::new(Some(style), &user, &state)))
+}
+
+/// Every saved version of the caller's blog styles, newest first.
+pub async fn list_blog_style_versions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<Vec<BlogStyleVersionResponse>>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing blog style versions: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let versions = BlogStyles::history(&mut conn, &user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to list blog style versions for user {}: {}", user.id, e);
+            AuthError::database("Failed to list blog style versions")
+        })?;
+
+    Ok(ApiResponse::new(versions.into_iter().map(BlogStyleVersionResponse::from).collect()))
+}
+
+/// Makes an earlier version live again by saving a copy of it as the newest version.
+pub async fn restore_blog_style_version(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut tx: Tx,
+    Path(version): Path<i32>,
+) -> Result<ApiResponse<BlogStyleResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+    let user = auth.user;
+
+    let previous = BlogStyles::by_version(&mut tx, &user.id, version)
+        .map_err(|e| {
+            tracing::error!("Failed to load blog style version {} for user {}: {}", version, user.id, e);
+            AuthError::database("Failed to load blog style version")
+        })?
+        .ok_or_else(|| AuthError::not_found(format!("blog style version {}", version)))?;
+
+    let style = BlogStyles::record(&mut tx, &user.id, &previous.css, &previous.head_html)
+        .map_err(|e| {
+            tracing::error!("Failed to restore blog styles for user {}: {}", user.id, e);
+            AuthError::database("Failed to restore blog styles")
+        })?;
+
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    tracing::info!("User {} restored blog styles version {} as {}", user.id, version, style.version);
+
+    Ok(ApiResponse::new(BlogStyleResponse::new(Some(style), &user, &state)))
+}
@@ -0,0 +1,83 @@
+use axum::extract::State;
+use serde::Serialize;
+use validator::Validate;
+
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::me::UpdateEmailRequest;
+use crate::http::auth::SudoUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::{ApiResponse, UserDto};
+use crate::http::json::AppJson;
+use crate::services::audit::{self, AUDIT_EMAIL_CHANGED};
+use crate::services::cache;
+use crate::services::email_queue::EmailPriority;
+use crate::services::email_verification::VerificationEmail;
+use crate::services::normalize::Normalize;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct UpdateEmailResponse {
+    pub user: UserDto,
+    pub message: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn update_email(
+    State(state): State<AppState>,
+    sudo: SudoUser,
+    client: ClientInfo,
+    AppJson(payload): AppJson<UpdateEmailRequest>,
+) -> Result<ApiResponse<UpdateEmailResponse>, AuthError> {
+    let user = sudo.user;
+    tracing::info!("Processing email change request for user: {}", user.id);
+    let payload = payload.normalize()?;
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid email change data", err))?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during email change: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let existing = UserModel::by_email(&mut conn, &payload.email)
+        .map_err(|e| {
+            tracing::error!("Database query failed while checking email existence: {}", e);
+            AuthError::database("Failed to verify email availability")
+        })?;
+
+    if existing.is_some_and(|existing| existing.id != user.id) {
+        tracing::info!("Email change attempt to existing email: {}", payload.email);
+        return Err(AuthError::conflict("Email address is already registered"));
+    }
+
+    let locale = client.locale_for(user.locale.as_deref());
+    let verification = VerificationEmail::render(&state, locale, &user.name, &payload.email)?;
+    let updated = state.writes.write(&mut conn, |conn| {
+        let updated = UserModel::update_email(conn, &user.id, &payload.email)?;
+        verification.queue(conn, &user.id)?;
+        Ok(updated)
+    })
+    .await
+    .map_err(|e: diesel::result::Error| {
+        tracing::error!("Failed to update email for user {}: {}", user.id, e);
+        AuthError::database("Failed to update email address")
+    })?;
+    state.email_queue.wake(EmailPriority::Transactional);
+
+    cache::invalidate_user(state.cache.as_ref(), &user.id).await;
+
+    let detail = format!("{} -> {}", user.email, updated.email);
+    audit::record(&state, &client, AUDIT_EMAIL_CHANGED, Some(&user.id), None, Some(&detail)).await;
+
+    tracing::info!("User {} changed their email address", user.id);
+
+    Ok(ApiResponse::new(UpdateEmailResponse {
+        user: UserDto::from(updated),
+        message: "Email address updated, please verify the new address".to_string(),
+        updated_at: chrono::Utc::now(),
+    }))
+}
@@ -0,0 +1,119 @@
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE};
+use http::{HeaderValue, StatusCode};
+
+use crate::db::models::export_job::{ExportJobs, EXPORT_STATUS_PENDING, EXPORT_STATUS_READY};
+use crate::errors::AuthError;
+use crate::handlers::me::{ExportJobResponse, ExportQuery};
+use crate::http::auth::{AuthUser, SudoUser};
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::services::audit::{self, AUDIT_EXPORT_REQUESTED};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// `POST /api/v1/me/export`. The archive is assembled in the background; poll
+/// `GET /api/v1/me/export/{id}` until it's ready. While one export is in progress, asking
+/// again returns that one.
+pub async fn request_export(
+    State(state): State<AppState>,
+    sudo: SudoUser,
+    client: ClientInfo,
+) -> Result<(StatusCode, ApiResponse<ExportJobResponse>), AuthError> {
+    let user = sudo.user;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while requesting export: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let active = ExportJobs::active_for_user(&mut conn, &user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to look up exports for user {}: {}", user.id, e);
+            AuthError::database("Failed to request export")
+        })?;
+    if let Some(job) = active {
+        return Ok((StatusCode::ACCEPTED, ApiResponse::new(ExportJobResponse::from(job))));
+    }
+
+    let job = ExportJobs {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user.id.clone(),
+        status: EXPORT_STATUS_PENDING.to_string(),
+        storage_key: None,
+        size_bytes: None,
+        error: None,
+        created_at: chrono::Utc::now().naive_utc(),
+        started_at: None,
+        finished_at: None,
+        expires_at: None,
+    };
+    state.writes.write(&mut conn, |conn| ExportJobs::create(conn, &job))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create export for user {}: {}", user.id, e);
+            AuthError::database("Failed to request export")
+        })?;
+    drop(conn);
+
+    audit::record(&state, &client, AUDIT_EXPORT_REQUESTED, Some(&user.id), None, Some(&job.id)).await;
+
+    tracing::info!("User {} requested export {}", user.id, job.id);
+
+    Ok((StatusCode::ACCEPTED, ApiResponse::new(ExportJobResponse::from(job))))
+}
+
+/// `GET /api/v1/me/export/{id}`, the export's status, or with `?download=true` the archive
+/// itself once it's ready. Only browser sessions may fetch exports, never API tokens.
+pub async fn get_export(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(export_id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, AuthError> {
+    if auth.is_api_token() {
+        return Err(AuthError::forbidden("API tokens cannot access account exports"));
+    }
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading export: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let job = ExportJobs::by_id_for_user(&mut conn, &export_id, &user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load export {}: {}", export_id, e);
+            AuthError::database("Failed to load export")
+        })?
+        .ok_or_else(|| AuthError::not_found(&export_id))?;
+    drop(conn);
+
+    if !query.download {
+        return Ok(ApiResponse::new(ExportJobResponse::from(job)).into_response());
+    }
+
+    let key = match (&job.status[..], &job.storage_key) {
+        (EXPORT_STATUS_READY, Some(key)) => key,
+        _ => return Err(AuthError::conflict(format!("Export is {}, not ready to download", job.status))),
+    };
+    let bytes = state.storage.get(key).await?
+        .ok_or_else(|| {
+            tracing::error!("Export {} is missing from storage at {}", job.id, key);
+            AuthError::not_found(&export_id)
+        })?;
+
+    let filename = format!("attachment; filename=\"tsumi-export-{}.zip\"", job.created_at.format("%Y-%m-%d"));
+    let mut response = bytes.into_response();
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/zip"));
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("private, no-store"));
+    if let Ok(value) = HeaderValue::from_str(&filename) {
+        headers.insert(CONTENT_DISPOSITION, value);
+    }
+
+    Ok(response)
+}
@@ -0,0 +1,393 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::db::models::api_token::ApiTokens;
+use crate::db::models::audit_log::AuditLogs;
+use crate::db::models::export_job::{ExportJobs, EXPORT_STATUS_READY};
+use crate::db::models::push_subscription::PushSubscriptions;
+use crate::db::models::user_model::UserModel;
+use crate::db::models::user_preferences::UserPreferences;
+use crate::db::models::webhook::Webhooks;
+use crate::db::models::webhook_delivery::{WebhookDeliveries, WEBHOOK_DELIVERY_PENDING};
+use crate::errors::AuthError;
+use crate::http::negotiation::API_PREFIX;
+use crate::http::pagination::Sortable;
+use crate::services::normalize::{self, Normalize};
+use crate::services::notifications::format_time_of_day;
+use crate::services::onboarding::ChecklistStep;
+
+pub mod account;
+pub mod avatar;
+pub mod blog_style;
+pub mod email;
+pub mod export;
+pub mod onboarding;
+pub mod password;
+pub mod preferences;
+pub mod profile;
+pub mod push_subscriptions;
+pub mod security;
+pub mod sessions;
+pub mod tokens;
+pub mod webhooks;
+
+pub use tsumi_types::{AvatarResponse, ProfileResponse, UpdateProfileRequest};
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct UpdateEmailRequest {
+    #[validate(email(message = "Email must be a valid email."))]
+    pub email: String,
+}
+
+impl Normalize for UpdateEmailRequest {
+    fn normalize(mut self) -> Result<Self, AuthError> {
+        self.email = normalize::email(&self.email);
+        Ok(self)
+    }
+}
+
+impl Normalize for UpdateProfileRequest {
+    fn normalize(mut self) -> Result<Self, AuthError> {
+        for field in [&mut self.bio, &mut self.website, &mut self.location].into_iter().flatten() {
+            *field = field.trim().to_string();
+        }
+        Ok(self)
+    }
+}
+
+/// The signed-in user's own profile, with its privacy settings.
+pub fn profile_response(user: UserModel, avatar_url: Option<String>) -> ProfileResponse {
+    ProfileResponse {
+        username: user.name,
+        email: user.email,
+        bio: user.bio,
+        website: user.website,
+        location: user.location,
+        avatar_url,
+        profile_private: user.profile_private,
+        show_email: user.show_email,
+        joined_at: user.created_at,
+    }
+}
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct UpdatePasswordRequest {
+    #[validate(length(min = 8, max = 128, message = "Password must be between 8 and 128 characters"))]
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdatePreferencesRequest {
+    pub canonicalize_links: Option<bool>,
+
+    /// IANA time zone name, e.g. `Europe/Berlin`.
+    pub timezone: Option<String>,
+
+    /// Local `HH:MM-HH:MM` window during which notification emails are held back. An empty
+    /// string turns quiet hours off.
+    pub quiet_hours: Option<String>,
+
+    /// Whether to get the weekly email of new posts from followed authors.
+    pub weekly_digest: Option<bool>,
+
+    /// Language for pages, e.g. `fr`. An empty string goes back to the browser's choice.
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreferencesResponse {
+    pub canonicalize_links: bool,
+    pub timezone: String,
+    pub quiet_hours: Option<String>,
+    pub weekly_digest: bool,
+    pub locale: Option<String>,
+}
+
+impl PreferencesResponse {
+    pub fn new(user: &UserModel, preferences: &UserPreferences) -> Self {
+        Self {
+            canonicalize_links: user.canonicalize_links,
+            timezone: user.timezone.clone(),
+            quiet_hours: user.quiet_hours_start.zip(user.quiet_hours_end).map(|(start, end)| {
+                format!("{}-{}", format_time_of_day(start), format_time_of_day(end))
+            }),
+            weekly_digest: preferences.weekly_digest,
+            locale: user.locale.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdateBlogStyleRequest {
+    #[serde(default)]
+    pub css: String,
+    #[serde(default)]
+    pub head_html: String,
+}
+
+/// Sort keys for the session list.
+pub struct SessionSort;
+
+impl Sortable for SessionSort {
+    const SORT_FIELDS: &'static [&'static str] = &["created_at", "expires_at"];
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ListSessionsQuery {
+    #[serde(default)]
+    pub include_expired: bool,
+}
+
+/// Sort keys for audit log listings.
+pub struct AuditSort;
+
+impl Sortable for AuditSort {
+    const SORT_FIELDS: &'static [&'static str] = &["created_at"];
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ListAuditQuery {
+    pub event: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    pub id: String,
+    pub event: String,
+    pub user_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub detail: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<AuditLogs> for AuditLogResponse {
+    fn from(entry: AuditLogs) -> Self {
+        Self {
+            id: entry.id,
+            event: entry.event,
+            user_id: entry.user_id,
+            actor_id: entry.actor_id,
+            detail: entry.detail,
+            ip_address: entry.ip_address,
+            user_agent: entry.user_agent,
+            created_at: entry.created_at,
+        }
+    }
+}
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct CreateApiTokenRequest {
+    #[validate(length(min = 1, max = 64, message = "Token name must be between 1 and 64 characters"))]
+    pub name: String,
+
+    pub scopes: Vec<String>,
+
+    #[validate(range(min = 1, max = 365, message = "Token expiry must be between 1 and 365 days"))]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiTokenResponse {
+    pub id: String,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<ApiTokens> for ApiTokenResponse {
+    fn from(token: ApiTokens) -> Self {
+        Self {
+            scopes: token.scope_list(),
+            id: token.id,
+            name: token.name,
+            token_prefix: token.token_prefix,
+            last_used_at: token.last_used_at,
+            expires_at: token.expires_at,
+            created_at: token.created_at,
+        }
+    }
+}
+
+/// A browser's `PushSubscription`, as serialized by its `toJSON()`.
+#[derive(Validate, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePushSubscriptionRequest {
+    #[validate(url(message = "Endpoint must be a valid URL"))]
+    pub endpoint: String,
+
+    /// Milliseconds since the epoch, when the browser knows the subscription will lapse.
+    pub expiration_time: Option<i64>,
+
+    pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeletePushSubscriptionRequest {
+    pub endpoint: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PushSubscriptionResponse {
+    pub id: String,
+    pub endpoint: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<PushSubscriptions> for PushSubscriptionResponse {
+    fn from(subscription: PushSubscriptions) -> Self {
+        Self {
+            id: subscription.id,
+            endpoint: subscription.endpoint,
+            expires_at: subscription.expires_at,
+            user_agent: subscription.user_agent,
+            created_at: subscription.created_at,
+        }
+    }
+}
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct CreateWebhookRequest {
+    #[validate(url(message = "URL must be a valid URL"))]
+    pub url: String,
+
+    /// Event names to subscribe to, e.g. `post.published`.
+    pub events: Vec<String>,
+
+    /// Signing secret. One is generated when left out.
+    #[validate(length(min = 16, max = 256, message = "Secret must be between 16 and 256 characters"))]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    pub created_at: NaiveDateTime,
+    /// Only returned when the webhook is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl From<Webhooks> for WebhookResponse {
+    fn from(webhook: Webhooks) -> Self {
+        let events = webhook.event_list().into_iter().map(str::to_string).collect();
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            events,
+            created_at: webhook.created_at,
+            secret: None,
+        }
+    }
+}
+
+/// Sort keys for webhook delivery listings.
+pub struct WebhookDeliverySort;
+
+impl Sortable for WebhookDeliverySort {
+    const SORT_FIELDS: &'static [&'static str] = &["created_at"];
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliveryResponse {
+    pub id: String,
+    pub event: String,
+    pub status: String,
+    pub attempts: i32,
+    /// When the next attempt is due, while the delivery is still pending.
+    pub next_attempt_at: Option<NaiveDateTime>,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub payload: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+impl From<WebhookDeliveries> for WebhookDeliveryResponse {
+    fn from(delivery: WebhookDeliveries) -> Self {
+        Self {
+            next_attempt_at: (delivery.status == WEBHOOK_DELIVERY_PENDING).then_some(delivery.next_attempt_at),
+            payload: serde_json::from_str(&delivery.payload).unwrap_or(serde_json::Value::Null),
+            id: delivery.id,
+            event: delivery.event,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ExportQuery {
+    /// Download the archive instead of the export's status.
+    #[serde(default)]
+    pub download: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportJobResponse {
+    pub id: String,
+    pub status: String,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    /// Where to fetch the archive, once it's ready.
+    pub download_url: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl From<ExportJobs> for ExportJobResponse {
+    fn from(job: ExportJobs) -> Self {
+        Self {
+            download_url: (job.status == EXPORT_STATUS_READY).then(|| format!("{}/me/export/{}?download=true", API_PREFIX, job.id)),
+            id: job.id,
+            status: job.status,
+            size_bytes: job.size_bytes,
+            error: job.error,
+            created_at: job.created_at,
+            finished_at: job.finished_at,
+            expires_at: job.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OnboardingResponse {
+    pub steps: Vec<ChecklistStep>,
+    pub completed: usize,
+    pub total: usize,
+    /// Every step is done.
+    pub finished: bool,
+    pub dismissed_at: Option<NaiveDateTime>,
+}
+
+impl OnboardingResponse {
+    pub fn new(steps: Vec<ChecklistStep>, preferences: &UserPreferences) -> Self {
+        let completed = steps.iter().filter(|step| step.completed).count();
+        Self {
+            completed,
+            total: steps.len(),
+            finished: completed == steps.len(),
+            steps,
+            dismissed_at: preferences.onboarding_dismissed_at,
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,75 @@
+use axum::extract::State;
+
+use crate::db::models::user_preferences::UserPreferences;
+use crate::errors::AuthError;
+use crate::handlers::me::OnboardingResponse;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::services::api_tokens::{SCOPE_PROFILE_READ, SCOPE_PROFILE_WRITE};
+use crate::services::onboarding;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// `GET /api/v1/me/onboarding`, the new-user checklist and how far along it the user is.
+pub async fn get_onboarding(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<OnboardingResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading onboarding: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let steps = onboarding::checklist(&mut conn, &state.config.load(), &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load onboarding for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to load onboarding")
+        })?;
+    let preferences = UserPreferences::for_user(&mut conn, &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load preferences for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to load onboarding")
+        })?;
+
+    Ok(ApiResponse::new(OnboardingResponse::new(steps, &preferences)))
+}
+
+/// `POST /api/v1/me/onboarding/dismiss` hides the checklist. Steps are still recorded, so the
+/// progress is intact if the dashboard shows it again.
+pub async fn dismiss_onboarding(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<OnboardingResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while dismissing onboarding: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let mut preferences = UserPreferences::for_user(&mut conn, &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load preferences for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to dismiss onboarding")
+        })?;
+    if preferences.onboarding_dismissed_at.is_none() {
+        preferences = state.writes.write(&mut conn, |conn| UserPreferences::dismiss_onboarding(conn, &auth.user.id, chrono::Utc::now().naive_utc()))
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to dismiss onboarding for user {}: {}", auth.user.id, e);
+                AuthError::database("Failed to dismiss onboarding")
+            })?;
+    }
+
+    let steps = onboarding::checklist(&mut conn, &state.config.load(), &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load onboarding for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to load onboarding")
+        })?;
+
+    Ok(ApiResponse::new(OnboardingResponse::new(steps, &preferences)))
+}
@@ -0,0 +1,62 @@
+use axum::extract::State;
+use serde::Serialize;
+use validator::Validate;
+
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::me::UpdatePasswordRequest;
+use crate::http::auth::SudoUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::audit::{self, AUDIT_PASSWORD_CHANGED};
+use crate::services::passwords::hash_password;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct UpdatePasswordResponse {
+    pub message: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn update_password(
+    State(state): State<AppState>,
+    sudo: SudoUser,
+    client: ClientInfo,
+    AppJson(payload): AppJson<UpdatePasswordRequest>,
+) -> Result<ApiResponse<UpdatePasswordResponse>, AuthError> {
+    let user = sudo.user;
+    tracing::info!("Processing password change request for user: {}", user.id);
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid password change data", err))?;
+
+    let hashed_password = hash_password(&state.config.load(), &user.id, &payload.new_password)
+        .map_err(|e| {
+            tracing::error!("Password hashing failed: {}", e);
+            AuthError::internal("Failed to process password")
+        })?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during password change: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    state.writes.write(&mut conn, |conn| UserModel::update_password(conn, &user.id, &hashed_password))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update password for user {}: {}", user.id, e);
+            AuthError::database("Failed to update password")
+        })?;
+
+    audit::record(&state, &client, AUDIT_PASSWORD_CHANGED, Some(&user.id), None, None).await;
+
+    tracing::info!("User {} changed their password", user.id);
+
+    Ok(ApiResponse::new(UpdatePasswordResponse {
+        message: "Password updated successfully".to_string(),
+        updated_at: chrono::Utc::now(),
+    }))
+}
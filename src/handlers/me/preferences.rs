@@ -0,0 +1,112 @@
+use axum::extract::State;
+use chrono_tz::Tz;
+
+use crate::db::models::user_model::UserModel;
+use crate::db::models::user_preferences::UserPreferences;
+use crate::errors::AuthError;
+use crate::handlers::me::{PreferencesResponse, UpdatePreferencesRequest};
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::http::locale::Locale;
+use crate::services::api_tokens::{SCOPE_PROFILE_READ, SCOPE_PROFILE_WRITE};
+use crate::services::cache;
+use crate::services::notifications::parse_time_of_day;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<PreferencesResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading preferences: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let preferences = UserPreferences::for_user(&mut conn, &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load preferences for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to load preferences")
+        })?;
+
+    Ok(ApiResponse::new(PreferencesResponse::new(&auth.user, &preferences)))
+}
+
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    AppJson(payload): AppJson<UpdatePreferencesRequest>,
+) -> Result<ApiResponse<PreferencesResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+    let user = auth.user;
+
+    let timezone = match payload.timezone {
+        Some(timezone) => {
+            timezone.parse::<Tz>()
+                .map_err(|_| AuthError::validation(format!("Unknown time zone: {}", timezone)))?;
+            timezone
+        }
+        None => user.timezone.clone(),
+    };
+
+    let (quiet_hours_start, quiet_hours_end) = match payload.quiet_hours.as_deref().map(str::trim) {
+        None => (user.quiet_hours_start, user.quiet_hours_end),
+        Some("") => (None, None),
+        Some(window) => {
+            let parsed = window.split_once('-')
+                .and_then(|(start, end)| Some((parse_time_of_day(start.trim())?, parse_time_of_day(end.trim())?)))
+                .ok_or_else(|| AuthError::validation("Quiet hours must look like 22:00-07:00"))?;
+            (Some(parsed.0), Some(parsed.1))
+        }
+    };
+
+    let locale = match payload.locale.as_deref().map(str::trim) {
+        None => user.locale.clone(),
+        Some("") => None,
+        Some(tag) => {
+            let locale = Locale::from_tag(tag)
+                .ok_or_else(|| AuthError::validation(format!("Unsupported language: {}", tag)))?;
+            Some(locale.tag().to_string())
+        }
+    };
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while updating preferences: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let canonicalize_links = payload.canonicalize_links.unwrap_or(user.canonicalize_links);
+
+    let (user, preferences) = state.writes.write(&mut conn, |conn| {
+        let user = UserModel::update_preferences(
+            conn,
+            &user.id,
+            canonicalize_links,
+            &timezone,
+            quiet_hours_start,
+            quiet_hours_end,
+            locale.as_deref(),
+        )?;
+        let preferences = match payload.weekly_digest {
+            Some(enabled) => UserPreferences::set_weekly_digest(conn, &user.id, enabled)?,
+            None => UserPreferences::for_user(conn, &user.id)?,
+        };
+        Ok((user, preferences))
+    })
+    .await
+    .map_err(|e: diesel::result::Error| {
+        tracing::error!("Failed to update preferences for user {}: {}", user.id, e);
+        AuthError::database("Failed to update preferences")
+    })?;
+
+    cache::invalidate_user(state.cache.as_ref(), &user.id).await;
+
+    tracing::info!("User {} updated their preferences", user.id);
+
+    Ok(ApiResponse::new(PreferencesResponse::new(&user, &preferences)))
+}
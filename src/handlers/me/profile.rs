@@ -0,0 +1,103 @@
+use axum::extract::State;
+use diesel::SqliteConnection;
+use validator::Validate;
+
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::me::{profile_response, ProfileResponse, UpdateProfileRequest};
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::api_tokens::{SCOPE_PROFILE_READ, SCOPE_PROFILE_WRITE};
+use crate::services::avatars;
+use crate::services::cache;
+use crate::services::normalize::Normalize;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// `GET /api/v1/me`, the signed-in user's profile as they edit it.
+pub async fn get_profile(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<ProfileResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading profile: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let avatar_url = avatar_url(&mut conn, &user);
+    Ok(ApiResponse::new(profile_response(user, avatar_url)))
+}
+
+/// `PATCH /api/v1/me`, the bio, website, location and privacy settings.
+pub async fn update_profile(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    AppJson(payload): AppJson<UpdateProfileRequest>,
+) -> Result<ApiResponse<ProfileResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+    let user = auth.user;
+    let payload = payload.normalize()?;
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid profile data", err))?;
+
+    let website = match payload.website.as_deref() {
+        None => user.website.clone(),
+        Some("") => None,
+        Some(website) => {
+            let is_web = url::Url::parse(website).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+            if !is_web {
+                return Err(AuthError::invalid_field("website", "url", "Website must be an http or https URL"));
+            }
+            Some(website.to_string())
+        }
+    };
+    let location = match payload.location {
+        None => user.location.clone(),
+        Some(location) => Some(location).filter(|location| !location.is_empty()),
+    };
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while updating profile: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let updated = state.writes.write(&mut conn, |conn| {
+        UserModel::update_profile(
+            conn,
+            &user.id,
+            payload.bio.as_deref().unwrap_or(&user.bio),
+            website.as_deref(),
+            location.as_deref(),
+            payload.profile_private.unwrap_or(user.profile_private),
+            payload.show_email.unwrap_or(user.show_email),
+        )
+    })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update profile for user {}: {}", user.id, e);
+            AuthError::database("Failed to update profile")
+        })?;
+
+    let avatar_url = avatar_url(&mut conn, &updated);
+    drop(conn);
+
+    cache::invalidate_user(state.cache.as_ref(), &user.id).await;
+
+    tracing::info!("User {} updated their profile", user.id);
+
+    Ok(ApiResponse::new(profile_response(updated, avatar_url)))
+}
+
+/// The profile is still useful without an avatar.
+fn avatar_url(conn: &mut SqliteConnection, user: &UserModel) -> Option<String> {
+    avatars::avatar_path(conn, user)
+        .inspect_err(|e| tracing::error!("Failed to look up avatar for {}: {}", user.id, e))
+        .ok()
+}
@@ -0,0 +1,143 @@
+use axum::extract::State;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::DateTime;
+use serde::Serialize;
+use validator::Validate;
+
+use crate::db::models::push_subscription::PushSubscriptions;
+use crate::errors::AuthError;
+use crate::handlers::me::{CreatePushSubscriptionRequest, DeletePushSubscriptionRequest, PushSubscriptionResponse};
+use crate::http::auth::AuthUser;
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::api_tokens::{SCOPE_PROFILE_READ, SCOPE_PROFILE_WRITE};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct ListPushSubscriptionsResponse {
+    /// The `applicationServerKey` to subscribe with, or `None` when push is off.
+    pub public_key: Option<String>,
+    pub subscriptions: Vec<PushSubscriptionResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeletePushSubscriptionResponse {
+    pub message: String,
+}
+
+/// Decodes a base64url key from the Push API, which some browsers pad.
+fn decode_key(value: &str, len: usize, name: &str) -> Result<(), AuthError> {
+    match URL_SAFE_NO_PAD.decode(value.trim_end_matches('=')) {
+        Ok(bytes) if bytes.len() == len => Ok(()),
+        _ => Err(AuthError::validation(format!("keys.{} must be a {}-byte base64url value", name, len))),
+    }
+}
+
+pub async fn list_push_subscriptions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<ListPushSubscriptionsResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing push subscriptions: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let subscriptions = PushSubscriptions::for_user(&mut conn, &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to list push subscriptions for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to list push subscriptions")
+        })?;
+
+    Ok(ApiResponse::new(ListPushSubscriptionsResponse {
+        public_key: state.push.public_key().map(str::to_string),
+        subscriptions: subscriptions.into_iter().map(PushSubscriptionResponse::from).collect(),
+    }))
+}
+
+pub async fn create_push_subscription(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    client: ClientInfo,
+    AppJson(payload): AppJson<CreatePushSubscriptionRequest>,
+) -> Result<ApiResponse<PushSubscriptionResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+    if !state.push.is_enabled() {
+        return Err(AuthError::validation("Push notifications are not enabled on this server"));
+    }
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid push subscription", err))?;
+    if !payload.endpoint.starts_with("https://") {
+        return Err(AuthError::validation("Endpoint must be an https URL"));
+    }
+    decode_key(&payload.keys.p256dh, 65, "p256dh")?;
+    decode_key(&payload.keys.auth, 16, "auth")?;
+    let expires_at = match payload.expiration_time {
+        Some(millis) => Some(
+            DateTime::from_timestamp_millis(millis)
+                .ok_or_else(|| AuthError::validation("expirationTime is out of range"))?
+                .naive_utc(),
+        ),
+        None => None,
+    };
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while subscribing to push: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let subscription = state.writes.write(&mut conn, |conn| PushSubscriptions::upsert(conn, &PushSubscriptions {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: auth.user.id.clone(),
+        endpoint: payload.endpoint,
+        p256dh: payload.keys.p256dh,
+        auth: payload.keys.auth,
+        expires_at,
+        user_agent: client.user_agent,
+        created_at: chrono::Utc::now().naive_utc(),
+    }))
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to store push subscription for user {}: {}", auth.user.id, e);
+        AuthError::database("Failed to store push subscription")
+    })?;
+
+    tracing::info!("User {} subscribed to push notifications ({})", auth.user.id, subscription.id);
+
+    Ok(ApiResponse::new(PushSubscriptionResponse::from(subscription)))
+}
+
+pub async fn delete_push_subscription(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    AppJson(payload): AppJson<DeletePushSubscriptionRequest>,
+) -> Result<ApiResponse<DeletePushSubscriptionResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while unsubscribing from push: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let deleted = state.writes.write(&mut conn, |conn| PushSubscriptions::delete_by_endpoint(conn, &auth.user.id, &payload.endpoint))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete push subscription for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to delete push subscription")
+        })?;
+    if !deleted {
+        return Err(AuthError::not_found(payload.endpoint));
+    }
+
+    Ok(ApiResponse::new(DeletePushSubscriptionResponse {
+        message: "Push subscription deleted".to_string(),
+    }))
+}
@@ -0,0 +1,53 @@
+use axum::extract::{Query, State};
+
+use crate::db::models::audit_log::AuditLogs;
+use crate::db::queries::audit_logs::AuditFilter;
+use crate::errors::AuthError;
+use crate::handlers::me::{AuditLogResponse, AuditSort, ListAuditQuery};
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::pagination::{ListParams, Paginated};
+use crate::services::api_tokens::SCOPE_PROFILE_READ;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Security events on the caller's account, newest first unless `dir=asc`: sign-ins (failed
+/// ones included), password and email changes, revoked tokens and admin actions. Narrow to
+/// one kind with `event`.
+pub async fn list_audit_log(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    params: ListParams<AuditSort>,
+    Query(query): Query<ListAuditQuery>,
+) -> Result<ApiResponse<Paginated<AuditLogResponse>>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+    let user = auth.user;
+
+    let filter = AuditFilter {
+        user_id: Some(&user.id),
+        event: query.event.as_deref().filter(|event| !event.is_empty()),
+        ..AuditFilter::default()
+    };
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing audit log: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let total = AuditLogs::count_filtered(&mut conn, &filter)
+        .map_err(|e| {
+            tracing::error!("Failed to count audit log for user {}: {}", user.id, e);
+            AuthError::database("Failed to list audit log")
+        })?;
+
+    let entries = AuditLogs::page_filtered(&mut conn, &filter, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to list audit log for user {}: {}", user.id, e);
+            AuthError::database("Failed to list audit log")
+        })?;
+
+    let entries = entries.into_iter().map(AuditLogResponse::from).collect();
+
+    Ok(ApiResponse::new(params.paginate(entries, total)))
+}
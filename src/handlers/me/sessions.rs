@@ -0,0 +1,56 @@
+use axum::extract::{Query, State};
+use tower_cookies::Cookies;
+
+use crate::errors::AuthError;
+use crate::handlers::me::{ListSessionsQuery, SessionSort};
+use crate::http::auth::AuthUser;
+use crate::http::dto::{session_dto, ApiResponse, SessionDto};
+use crate::http::pagination::{ListParams, Paginated};
+use crate::services::api_tokens::SCOPE_PROFILE_READ;
+use crate::services::cookies::{self, AuthCookie};
+use crate::state::AppState;
+
+/// The caller's signed-in sessions. Expired sessions are left out unless `include_expired`
+/// is set; sort by `created_at` (the default) or `expires_at`.
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    cookies: Cookies,
+    auth: AuthUser,
+    params: ListParams<SessionSort>,
+    Query(query): Query<ListSessionsQuery>,
+) -> Result<ApiResponse<Paginated<SessionDto>>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+    let user = auth.user;
+
+    let total = state.sessions.count_for_user(&user.id, query.include_expired)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to count sessions for user {}: {}", user.id, e);
+            AuthError::database("Failed to list sessions")
+        })?;
+
+    let sessions = state.sessions.page_for_user(
+        &user.id,
+        query.include_expired,
+        params.sort,
+        params.dir,
+        params.offset(),
+        params.limit(),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list sessions for user {}: {}", user.id, e);
+        AuthError::database("Failed to list sessions")
+    })?;
+
+    let current_token = cookies::get(&cookies, &state.config.load(), AuthCookie::Refresh);
+    let sessions = sessions
+        .into_iter()
+        .map(|session| {
+            let current = current_token.as_deref() == Some(session.token.as_str());
+            session_dto(session, current)
+        })
+        .collect();
+
+    Ok(ApiResponse::new(params.paginate(sessions, total)))
+}
@@ -0,0 +1,138 @@
+use axum::extract::{Path, State};
+use serde::Serialize;
+use validator::Validate;
+
+use crate::db::models::api_token::{ApiTokens, NewApiToken};
+use crate::errors::AuthError;
+use crate::handlers::me::{ApiTokenResponse, CreateApiTokenRequest};
+use crate::http::auth::{AuthUser, SudoUser};
+use crate::http::client::ClientInfo;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::audit::{self, AUDIT_API_TOKEN_REVOKED};
+use crate::services::api_tokens::{display_prefix, generate_api_token, hash_api_token, validate_scopes, SCOPE_PROFILE_READ};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct CreateApiTokenResponse {
+    /// The plaintext token. It is only ever returned once.
+    pub token: String,
+    pub api_token: ApiTokenResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListApiTokensResponse {
+    pub tokens: Vec<ApiTokenResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteApiTokenResponse {
+    pub message: String,
+    pub deleted_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn create_token(
+    State(state): State<AppState>,
+    sudo: SudoUser,
+    AppJson(payload): AppJson<CreateApiTokenRequest>,
+) -> Result<ApiResponse<CreateApiTokenResponse>, AuthError> {
+    let user = sudo.user;
+    tracing::info!("Processing API token creation for user: {}", user.id);
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid API token data", err))?;
+    validate_scopes(&payload.scopes)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during API token creation: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let token = generate_api_token();
+    let now = chrono::Utc::now().naive_utc();
+
+    let new_token = NewApiToken {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user.id.clone(),
+        name: payload.name,
+        token_prefix: display_prefix(&token),
+        token_hash: hash_api_token(&token),
+        scopes: payload.scopes.join(" "),
+        expires_at: payload.expires_in_days.map(|days| now + chrono::Duration::days(days)),
+        created_at: now,
+    };
+
+    let api_token = state.writes.write(&mut conn, |conn| ApiTokens::create(conn, &new_token))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to store API token for user {}: {}", user.id, e);
+            AuthError::database("Failed to create API token")
+        })?;
+
+    tracing::info!("User {} created API token {}", user.id, api_token.id);
+
+    Ok(ApiResponse::new(CreateApiTokenResponse {
+        token,
+        api_token: ApiTokenResponse::from(api_token),
+    }))
+}
+
+pub async fn list_tokens(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<ListApiTokensResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing API tokens: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let tokens = ApiTokens::by_user(&mut conn, &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to list API tokens for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to list API tokens")
+        })?;
+
+    Ok(ApiResponse::new(ListApiTokensResponse {
+        tokens: tokens.into_iter().map(ApiTokenResponse::from).collect(),
+    }))
+}
+
+pub async fn delete_token(
+    State(state): State<AppState>,
+    sudo: SudoUser,
+    client: ClientInfo,
+    Path(token_id): Path<String>,
+) -> Result<ApiResponse<DeleteApiTokenResponse>, AuthError> {
+    let user = sudo.user;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection during API token deletion: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let deleted = state.writes.write(&mut conn, |conn| ApiTokens::delete_for_user(conn, &token_id, &user.id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete API token {}: {}", token_id, e);
+            AuthError::database("Failed to delete API token")
+        })?;
+
+    if deleted == 0 {
+        return Err(AuthError::not_found(token_id));
+    }
+
+    audit::record(&state, &client, AUDIT_API_TOKEN_REVOKED, Some(&user.id), None, Some(&token_id)).await;
+
+    tracing::info!("User {} revoked API token {}", user.id, token_id);
+
+    Ok(ApiResponse::new(DeleteApiTokenResponse {
+        message: "API token revoked".to_string(),
+        deleted_at: chrono::Utc::now(),
+    }))
+}
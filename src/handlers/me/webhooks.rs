@@ -0,0 +1,199 @@
+use axum::extract::{Path, State};
+use serde::Serialize;
+use validator::Validate;
+
+use crate::db::models::webhook::{Webhooks, WEBHOOK_EVENTS};
+use crate::db::models::webhook_delivery::WebhookDeliveries;
+use crate::errors::AuthError;
+use crate::handlers::me::{CreateWebhookRequest, WebhookDeliveryResponse, WebhookDeliverySort, WebhookResponse};
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::http::pagination::{ListParams, Paginated};
+use crate::services::api_tokens::{SCOPE_PROFILE_READ, SCOPE_PROFILE_WRITE};
+use crate::services::webhooks;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct ListWebhooksResponse {
+    pub webhooks: Vec<WebhookResponse>,
+    /// Every event a webhook can subscribe to.
+    pub available_events: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteWebhookResponse {
+    pub message: String,
+}
+
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<ListWebhooksResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing webhooks: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let webhooks = Webhooks::for_user(&mut conn, &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to list webhooks for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to list webhooks")
+        })?;
+
+    Ok(ApiResponse::new(ListWebhooksResponse {
+        webhooks: webhooks.into_iter().map(WebhookResponse::from).collect(),
+        available_events: WEBHOOK_EVENTS.to_vec(),
+    }))
+}
+
+/// Registers an endpoint for the given events. The signing secret is only ever returned
+/// here, so callers that let the server generate it need to keep it.
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    AppJson(payload): AppJson<CreateWebhookRequest>,
+) -> Result<ApiResponse<WebhookResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid webhook", err))?;
+    // Plain http, and receivers on the server's own network, are only allowed in development.
+    let development = state.config.load().is_development();
+    let allowed = payload.url.starts_with("https://") || (development && payload.url.starts_with("http://"));
+    if !allowed {
+        return Err(AuthError::validation("URL must be an https URL"));
+    }
+    webhooks::check_destination(&payload.url, development)
+        .await
+        .map_err(AuthError::validation)?;
+
+    let mut events: Vec<&str> = Vec::new();
+    for event in &payload.events {
+        let Some(known) = WEBHOOK_EVENTS.iter().find(|known| **known == event.as_str()) else {
+            return Err(AuthError::validation(format!(
+                "Unknown event {}, expected any of: {}",
+                event,
+                WEBHOOK_EVENTS.join(", ")
+            )));
+        };
+        if !events.contains(known) {
+            events.push(known);
+        }
+    }
+    if events.is_empty() {
+        return Err(AuthError::validation("Subscribe to at least one event"));
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while creating webhook: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let existing = Webhooks::count_for_user(&mut conn, &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to count webhooks for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to create webhook")
+        })?;
+    if existing >= state.config.load().webhook_max_per_user() {
+        return Err(AuthError::validation(format!(
+            "You can have at most {} webhooks",
+            state.config.load().webhook_max_per_user()
+        )));
+    }
+
+    let webhook = Webhooks {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: auth.user.id.clone(),
+        url: payload.url,
+        secret: payload.secret.unwrap_or_else(webhooks::generate_secret),
+        events: events.join(","),
+        created_at: chrono::Utc::now().naive_utc(),
+    };
+    state.writes.write(&mut conn, |conn| Webhooks::create(conn, &webhook))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create webhook for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to create webhook")
+        })?;
+
+    tracing::info!("User {} registered webhook {}", auth.user.id, webhook.id);
+
+    let secret = webhook.secret.clone();
+    Ok(ApiResponse::new(WebhookResponse { secret: Some(secret), ..WebhookResponse::from(webhook) }))
+}
+
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(webhook_id): Path<String>,
+) -> Result<ApiResponse<DeleteWebhookResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while deleting webhook: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let deleted = state.writes.write(&mut conn, |conn| Webhooks::delete_for_user(conn, &webhook_id, &auth.user.id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete webhook {}: {}", webhook_id, e);
+            AuthError::database("Failed to delete webhook")
+        })?;
+    if !deleted {
+        return Err(AuthError::not_found(webhook_id));
+    }
+
+    tracing::info!("User {} deleted webhook {}", auth.user.id, webhook_id);
+
+    Ok(ApiResponse::new(DeleteWebhookResponse {
+        message: "Webhook deleted".to_string(),
+    }))
+}
+
+/// The webhook's delivery log, newest first unless `dir=asc`, with each delivery's payload
+/// and how its latest attempt went.
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(webhook_id): Path<String>,
+    params: ListParams<WebhookDeliverySort>,
+) -> Result<ApiResponse<Paginated<WebhookDeliveryResponse>>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing webhook deliveries: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let webhook = Webhooks::by_id_for_user(&mut conn, &webhook_id, &auth.user.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load webhook {}: {}", webhook_id, e);
+            AuthError::database("Failed to list webhook deliveries")
+        })?
+        .ok_or_else(|| AuthError::not_found(webhook_id))?;
+
+    let total = WebhookDeliveries::count_for_webhook(&mut conn, &webhook.id)
+        .map_err(|e| {
+            tracing::error!("Failed to count deliveries for webhook {}: {}", webhook.id, e);
+            AuthError::database("Failed to list webhook deliveries")
+        })?;
+
+    let deliveries = WebhookDeliveries::page_for_webhook(&mut conn, &webhook.id, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to list deliveries for webhook {}: {}", webhook.id, e);
+            AuthError::database("Failed to list webhook deliveries")
+        })?;
+
+    let deliveries = deliveries.into_iter().map(WebhookDeliveryResponse::from).collect();
+
+    Ok(ApiResponse::new(params.paginate(deliveries, total)))
+}
@@ -1 +1,23 @@
-pub mod auth;
\ No newline at end of file
+pub mod activity;
+pub mod admin;
+pub mod auth;
+pub mod avatars;
+pub mod comments;
+pub mod dev;
+pub mod digest;
+pub mod errors;
+pub mod follows;
+pub mod live;
+pub mod me;
+pub mod nodeinfo;
+pub mod notifications;
+pub mod pages;
+pub mod posts;
+pub mod public;
+pub mod reports;
+pub mod series;
+pub mod sitemap;
+pub mod tags;
+pub mod uploads;
+pub mod webhooks;
+pub mod widgets;
\ No newline at end of file
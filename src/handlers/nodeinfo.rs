@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use http::header::CONTENT_TYPE;
+use serde::Serialize;
+
+use crate::errors::AuthError;
+use crate::services::cache;
+use crate::services::nodeinfo::{self, NodeInfo, NODEINFO_SCHEMA};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Directories poll NodeInfo rarely, so counts may lag by this much.
+const NODEINFO_TTL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Serialize)]
+pub struct NodeInfoLink {
+    pub rel: String,
+    pub href: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodeInfoLinks {
+    pub links: Vec<NodeInfoLink>,
+}
+
+/// `GET /.well-known/nodeinfo`, pointing crawlers at the versioned document.
+pub async fn well_known_nodeinfo(State(state): State<AppState>) -> Json<NodeInfoLinks> {
+    Json(NodeInfoLinks {
+        links: vec![NodeInfoLink {
+            rel: NODEINFO_SCHEMA.to_string(),
+            href: format!("{}/nodeinfo/2.1", state.config.load().canonical_url()),
+        }],
+    })
+}
+
+/// `GET /nodeinfo/2.1`, software and usage figures as `NODEINFO_STATS` allows.
+pub async fn nodeinfo_document(State(state): State<AppState>) -> Result<Response, AuthError> {
+    let key = cache::nodeinfo_key();
+    let document = match cache::get_json::<NodeInfo>(state.cache.as_ref(), &key).await {
+        Some(document) => document,
+        None => {
+            let mut conn = get_db_conn(&state)
+                .map_err(|e| {
+                    tracing::error!("Failed to get database connection for nodeinfo: {}", e);
+                    AuthError::internal("Database connection failed")
+                })?;
+
+            let usage = nodeinfo::usage(&mut conn, state.config.load().nodeinfo_stats(), chrono::Utc::now().naive_utc())
+                .map_err(|e| {
+                    tracing::error!("Failed to count nodeinfo usage: {}", e);
+                    AuthError::database("Failed to build nodeinfo")
+                })?;
+
+            let document = nodeinfo::document(state.config.load().canonical_url(), state.config.load().nodeinfo_node_name(), usage);
+            cache::set_json(state.cache.as_ref(), &key, &document, NODEINFO_TTL).await;
+            document
+        }
+    };
+
+    let content_type = format!("application/json; profile=\"{}#\"", NODEINFO_SCHEMA);
+    Ok(([(CONTENT_TYPE, content_type)], Json(document)).into_response())
+}
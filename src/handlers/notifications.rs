@@ -0,0 +1,180 @@
+use axum::extract::{Path, Query, State};
+use chrono::NaiveDateTime;
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::notification::{NotificationEntry, Notifications};
+use crate::errors::AuthError;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::pagination::{ListParams, Paginated, Sortable};
+use crate::services::api_tokens::{SCOPE_PROFILE_READ, SCOPE_PROFILE_WRITE};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Sort keys for the notification list.
+pub struct NotificationSort;
+
+impl Sortable for NotificationSort {
+    const SORT_FIELDS: &'static [&'static str] = &["created_at"];
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ListNotificationsQuery {
+    /// Only notifications not yet marked read.
+    pub unread: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationActor {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationResponse {
+    pub id: String,
+    pub kind: String,
+    pub actor: Option<NotificationActor>,
+    pub post_id: Option<String>,
+    pub post_title: Option<String>,
+    pub comment_id: Option<String>,
+    pub read_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnreadCountResponse {
+    pub unread: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkReadResponse {
+    /// Notifications that were unread until now.
+    pub marked: usize,
+    pub unread: i64,
+}
+
+fn count_unread(conn: &mut SqliteConnection, user_id: &str) -> Result<i64, AuthError> {
+    Notifications::count_for_user(conn, user_id, true).map_err(|e| {
+        tracing::error!("Failed to count unread notifications for user {}: {}", user_id, e);
+        AuthError::database("Failed to count notifications")
+    })
+}
+
+/// The caller's notifications, newest first unless `dir=asc`. Pass `unread=true` for only
+/// the unread ones.
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    params: ListParams<NotificationSort>,
+    Query(query): Query<ListNotificationsQuery>,
+) -> Result<ApiResponse<Paginated<NotificationResponse>>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+    let unread_only = query.unread.unwrap_or(false);
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing notifications: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let total = Notifications::count_for_user(&mut conn, &auth.user.id, unread_only)
+        .map_err(|e| {
+            tracing::error!("Failed to count notifications for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to list notifications")
+        })?;
+
+    let notifications = Notifications::page_for_user(
+        &mut conn,
+        &auth.user.id,
+        unread_only,
+        params.dir,
+        params.offset(),
+        params.limit(),
+    )
+    .map_err(|e| {
+        tracing::error!("Failed to list notifications for user {}: {}", auth.user.id, e);
+        AuthError::database("Failed to list notifications")
+    })?;
+
+    let notifications = notifications
+        .into_iter()
+        .map(|NotificationEntry { notification, actor_name, post_title }| NotificationResponse {
+            actor: notification.actor_id.zip(actor_name).map(|(id, name)| NotificationActor { id, name }),
+            id: notification.id,
+            kind: notification.kind,
+            post_id: notification.post_id,
+            post_title,
+            comment_id: notification.comment_id,
+            read_at: notification.read_at,
+            created_at: notification.created_at,
+        })
+        .collect();
+
+    Ok(ApiResponse::new(params.paginate(notifications, total)))
+}
+
+pub async fn unread_count(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<UnreadCountResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_READ)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while counting notifications: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let unread = count_unread(&mut conn, &auth.user.id)?;
+
+    Ok(ApiResponse::new(UnreadCountResponse { unread }))
+}
+
+pub async fn mark_notification_read(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<MarkReadResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while marking a notification read: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let marked = state.writes.write(&mut conn, |conn| Notifications::mark_read(conn, &auth.user.id, &id, chrono::Utc::now().naive_utc()))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark notification {} read: {}", id, e);
+            AuthError::database("Failed to mark notification read")
+        })?
+        .ok_or_else(|| AuthError::not_found(&id))?;
+    let unread = count_unread(&mut conn, &auth.user.id)?;
+
+    Ok(ApiResponse::new(MarkReadResponse { marked: usize::from(marked), unread }))
+}
+
+pub async fn mark_all_notifications_read(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<MarkReadResponse>, AuthError> {
+    auth.require_scope(SCOPE_PROFILE_WRITE)?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while marking notifications read: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let marked = state.writes.write(&mut conn, |conn| Notifications::mark_all_read(conn, &auth.user.id, chrono::Utc::now().naive_utc()))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark notifications read for user {}: {}", auth.user.id, e);
+            AuthError::database("Failed to mark notifications read")
+        })?;
+
+    Ok(ApiResponse::new(MarkReadResponse { marked, unread: 0 }))
+}
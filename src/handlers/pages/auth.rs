@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::Form;
+use serde::{Deserialize, Serialize};
+use tera::Context;
+use tower_cookies::Cookies;
+
+use crate::errors::AuthError;
+use crate::handlers::auth::signin::sign_in;
+use crate::handlers::auth::signup::sign_up;
+use crate::handlers::auth::{NextQuery, SignInRequest, SignUpRequest};
+use crate::handlers::pages::{render, render_with_status};
+use crate::http::client::ClientInfo;
+use crate::http::features::Enabled;
+use crate::http::json::AppJson;
+use crate::http::page_context::{page_locale, PageContext};
+use crate::http::redirect::safe_next;
+use crate::services::captcha::{CaptchaEndpoint, CaptchaProvider};
+use crate::services::feature_flags::SignupFeature;
+use crate::services::flash::{self, FlashLevel};
+use crate::state::AppState;
+
+/// Where the register form sends new users, who can't sign in until they verify their email.
+const REGISTERED_REDIRECT: &str = "/login";
+
+/// The login form's fields. Missing ones are left empty for validation to report, and
+/// `remember_me` is a checkbox, sent as `on` when ticked and left out otherwise.
+#[derive(Deserialize, Debug)]
+pub struct SignInForm {
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub remember_me: Option<String>,
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+/// The register form's fields. The captcha widgets post their answer under their own names.
+#[derive(Deserialize, Debug)]
+pub struct SignUpForm {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default, alias = "h-captcha-response", alias = "g-recaptcha-response")]
+    pub captcha_token: Option<String>,
+}
+
+/// What the register page needs to show the captcha widget.
+#[derive(Serialize, Debug)]
+struct CaptchaView<'a> {
+    provider: CaptchaProvider,
+    site_key: &'a str,
+}
+
+pub async fn login_page(
+    State(state): State<AppState>,
+    PageContext(mut ctx): PageContext,
+    Query(query): Query<NextQuery>,
+) -> Response {
+    insert_next(&state, &mut ctx, query.next.as_deref());
+    render(&state, "login.html", &ctx)
+}
+
+/// The login form's target. Signs in like `POST /api/v1/auth/signin` and redirects to `next`,
+/// or shows the form again with what went wrong.
+pub async fn login_form(
+    State(state): State<AppState>,
+    cookies: Cookies,
+    client: ClientInfo,
+    PageContext(mut ctx): PageContext,
+    Form(form): Form<SignInForm>,
+) -> Response {
+    let request = SignInRequest {
+        email: form.email.clone(),
+        password: form.password,
+        remember_me: form.remember_me.is_some(),
+        next: form.next.clone(),
+    };
+
+    match sign_in(State(state.clone()), cookies, client, AppJson(request)).await {
+        Ok(response) => Redirect::to(&response.data.redirect_to).into_response(),
+        Err(error) => {
+            insert_next(&state, &mut ctx, form.next.as_deref());
+            ctx.insert("email", &form.email);
+            insert_error(&mut ctx, &error);
+            render_with_status(&state, "login.html", &ctx, error.status_code())
+        }
+    }
+}
+
+pub async fn register_page(
+    State(state): State<AppState>,
+    _enabled: Enabled<SignupFeature>,
+    PageContext(mut ctx): PageContext,
+) -> Response {
+    insert_captcha(&state, &mut ctx);
+    render(&state, "register.html", &ctx)
+}
+
+/// The register form's target. Signs up like `POST /api/v1/auth/signup` and sends the user
+/// to log in once they've verified their email, or shows the form again with what went wrong.
+pub async fn register_form(
+    State(state): State<AppState>,
+    enabled: Enabled<SignupFeature>,
+    cookies: Cookies,
+    client: ClientInfo,
+    PageContext(mut ctx): PageContext,
+    Form(form): Form<SignUpForm>,
+) -> Response {
+    let request = SignUpRequest {
+        name: form.name.clone(),
+        email: form.email.clone(),
+        password: form.password,
+        captcha_token: form.captcha_token,
+    };
+
+    match sign_up(State(state.clone()), enabled, client, AppJson(request)).await {
+        Ok(_) => {
+            let message = page_locale(&ctx).text("flash.verify_email");
+            flash::push(&cookies, &state.config.load(), FlashLevel::Notice, message);
+            Redirect::to(REGISTERED_REDIRECT).into_response()
+        }
+        Err(error) => {
+            insert_captcha(&state, &mut ctx);
+            ctx.insert("name", &form.name);
+            ctx.insert("email", &form.email);
+            insert_error(&mut ctx, &error);
+            render_with_status(&state, "register.html", &ctx, error.status_code())
+        }
+    }
+}
+
+fn insert_next(state: &AppState, ctx: &mut Context, next: Option<&str>) {
+    let next = next.and_then(|next| safe_next(state.config.load().public_url(), next));
+    ctx.insert("next", &next);
+}
+
+fn insert_captcha(state: &AppState, ctx: &mut Context) {
+    let config = state.config.load();
+    if config.captcha_required(CaptchaEndpoint::Signup)
+        && let (Some(provider), Some(site_key)) = (config.captcha_provider(), config.captcha_site_key())
+    {
+        ctx.insert("captcha", &CaptchaView { provider, site_key });
+    }
+}
+
+/// Puts a failed submission on the page in the page's language: a summary as `error`, and each
+/// invalid field's message under `field_errors`, keyed by field. Server-side failures only get
+/// their generic description.
+fn insert_error(ctx: &mut Context, error: &AuthError) {
+    if error.should_log() {
+        tracing::error!("Form submission failed: {}", error);
+    }
+
+    let locale = page_locale(ctx);
+    let message = match error {
+        AuthError::ValidationError { fields, .. } if !fields.is_empty() => {
+            let by_field: HashMap<&str, &str> = fields
+                .iter()
+                .rev()
+                .map(|field| (field.field.as_str(), locale.field_message(&field.message)))
+                .collect();
+            ctx.insert("field_errors", &by_field);
+            locale.text("form.fix_fields").to_string()
+        }
+        AuthError::ValidationError { message, .. }
+        | AuthError::Unauthorized { message }
+        | AuthError::Forbidden { message }
+        | AuthError::Conflict { message }
+        | AuthError::RateLimited { message, .. } => {
+            locale.message(&error.to_string()).map_or_else(|| message.clone(), str::to_string)
+        }
+        other => locale.describe(other.kind()).to_string(),
+    };
+    ctx.insert("error", &message);
+}
@@ -0,0 +1,56 @@
+use axum::extract::{Path, State};
+use axum::response::Response;
+use tera::Context;
+
+use crate::db::models::post::Posts;
+use crate::db::models::user_model::UserModel;
+use crate::handlers::pages::{cached_page, error_page, insert_avatar, insert_blog_style, not_found_page, render, PostView};
+use crate::services::cache::author_page_key;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+pub async fn author_page(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+) -> Response {
+    cached_page(&state, &author_page_key(&username), || build_author_page(&state, &username)).await
+}
+
+fn build_author_page(state: &AppState, username: &str) -> Response {
+    let Ok(mut conn) = get_db_conn(state) else {
+        tracing::error!("Failed to get database connection for author page");
+        return error_page(state);
+    };
+
+    let author = match UserModel::by_name(&mut conn, username) {
+        Ok(Some(author)) => author,
+        Ok(None) => return not_found_page(state),
+        Err(e) => {
+            tracing::error!("Failed to load author {}: {}", username, e);
+            return error_page(state);
+        }
+    };
+
+    let posts = match Posts::published_by_user(&mut conn, &author.id) {
+        Ok(posts) => posts,
+        Err(e) => {
+            tracing::error!("Failed to load posts for author {}: {}", author.id, e);
+            return error_page(state);
+        }
+    };
+
+    let posts: Vec<PostView> = posts
+        .into_iter()
+        .map(|post| PostView::new(post, author.name.clone()))
+        .collect();
+
+    let mut ctx = Context::new();
+    ctx.insert("author", &author.name);
+    ctx.insert("joined_at", &author.created_at);
+    ctx.insert("posts", &posts);
+
+    insert_blog_style(state, &mut conn, &author, &mut ctx);
+    insert_avatar(&mut conn, &author, &mut ctx);
+
+    render(state, "author.html", &ctx)
+}
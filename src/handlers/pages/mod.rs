@@ -0,0 +1,179 @@
+use axum::body::to_bytes;
+use axum::response::{Html, IntoResponse, Response};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tera::Context;
+
+use diesel::SqliteConnection;
+
+use crate::db::models::blog_style::BlogStyles;
+use crate::db::models::post::Posts;
+use crate::db::models::user_model::UserModel;
+use crate::handlers::uploads::media_path;
+use crate::http::csrf::{CsrfToken, CSRF_FORM_FIELD};
+use crate::services::avatars;
+use crate::services::cache;
+use crate::services::feature_flags;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+pub mod auth;
+pub mod author;
+pub mod page;
+pub mod post;
+pub mod posts;
+
+pub const POSTS_PER_PAGE: i64 = 20;
+
+#[derive(Deserialize, Debug)]
+pub struct PageQuery {
+    pub page: Option<i64>,
+}
+
+/// The subset of a post exposed to public templates.
+#[derive(Serialize, Debug)]
+pub struct PostView {
+    pub title: String,
+    pub description: String,
+    pub slug: String,
+    pub content: String,
+    pub author: String,
+    pub cover_image_url: Option<String>,
+    pub reading_minutes: Option<i32>,
+    pub published_at: Option<chrono::NaiveDateTime>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl PostView {
+    pub fn new(post: Posts, author: String) -> Self {
+        Self {
+            title: post.title,
+            description: post.description,
+            slug: post.slug,
+            content: post.content,
+            author,
+            cover_image_url: post.cover_upload_id.as_deref().map(media_path),
+            reading_minutes: post.reading_minutes,
+            published_at: post.published_at,
+            updated_at: post.updated_at,
+        }
+    }
+}
+
+/// A blog's custom CSS and head snippets as handed to templates. Both were sanitized when saved.
+#[derive(Serialize, Debug)]
+pub struct BlogStyleView {
+    pub css: String,
+    pub head_html: String,
+}
+
+/// Adds the author's custom styles to the page context as `blog_style`, unless they're
+/// switched off site-wide or for this blog. A failure to load them only costs the styling.
+pub fn insert_blog_style(state: &AppState, conn: &mut SqliteConnection, author: &UserModel, ctx: &mut Context) {
+    if !state.config.load().blog_styles_enabled() || author.blog_styles_disabled {
+        return;
+    }
+
+    match BlogStyles::current(conn, &author.id) {
+        Ok(Some(style)) => ctx.insert("blog_style", &BlogStyleView { css: style.css, head_html: style.head_html }),
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to load blog styles for {}: {}", author.id, e),
+    }
+}
+
+/// Adds the author's proxied avatar to the page context as `avatar_url`. Pages render without
+/// one if it can't be looked up.
+pub fn insert_avatar(conn: &mut SqliteConnection, author: &UserModel, ctx: &mut Context) {
+    match avatars::avatar_path(conn, author) {
+        Ok(path) => ctx.insert("avatar_url", &path),
+        Err(e) => tracing::error!("Failed to look up avatar for {}: {}", author.id, e),
+    }
+}
+
+/// Adds the CSRF token to the page context as `csrf_token`, and as `csrf_field`, a ready-made
+/// hidden input for forms: `{{ csrf_field | safe }}`.
+pub fn insert_csrf(ctx: &mut Context, csrf: &CsrfToken) {
+    ctx.insert("csrf_token", &csrf.0);
+    ctx.insert("csrf_field", &format!(r#"<input type="hidden" name="{}" value="{}">"#, CSRF_FORM_FIELD, csrf.0));
+}
+
+pub fn render(state: &AppState, template: &str, ctx: &Context) -> Response {
+    render_with_status(state, template, ctx, StatusCode::OK)
+}
+
+/// Adds the feature flags to the page context as `features`, so templates can hide what's
+/// switched off. Falls back to the configured defaults if the overrides can't be read.
+pub fn insert_features(state: &AppState, ctx: &mut Context) {
+    let config = state.config.load();
+    let flags = get_db_conn(state)
+        .map_err(|e| e.to_string())
+        .and_then(|mut conn| feature_flags::list(&mut conn, &config).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to load feature flags for a page: {}", e);
+            feature_flags::defaults(&config)
+        });
+    ctx.insert("features", &feature_flags::template_values(&flags));
+}
+
+pub fn render_with_status(state: &AppState, template: &str, ctx: &Context, status: StatusCode) -> Response {
+    let mut ctx = ctx.clone();
+    // Pages built on a `PageContext` have them already.
+    if !ctx.contains_key("features") {
+        insert_features(state, &mut ctx);
+    }
+    match state.templates.render(template, &ctx) {
+        Ok(rendered) => (status, Html(rendered)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to render template {}: {}", template, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Html("Something went wrong".to_string())).into_response()
+        }
+    }
+}
+
+/// Serves a public page from the cache, building and caching it on a miss. Only successful
+/// renders are cached, so a 404 for a post that's about to be published never sticks around.
+/// Writers invalidate through `cache::invalidate_author_pages`.
+pub async fn cached_page(state: &AppState, key: &str, build: impl FnOnce() -> Response) -> Response {
+    if let Some(page) = cache::get_bytes(state.cache.as_ref(), key).await {
+        return Html(String::from_utf8_lossy(&page).into_owned()).into_response();
+    }
+
+    let response = build();
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let page = match to_bytes(body, usize::MAX).await {
+        Ok(page) => page,
+        Err(e) => {
+            tracing::error!("Failed to buffer page {}: {}", key, e);
+            return error_page(state);
+        }
+    };
+    cache::set_bytes(state.cache.as_ref(), key, page.to_vec(), cache::page_ttl(&state.config.load())).await;
+    Response::from_parts(parts, page.into())
+}
+
+pub fn not_found_page(state: &AppState) -> Response {
+    render_with_status(state, "404.html", &Context::new(), StatusCode::NOT_FOUND)
+}
+
+pub fn error_page(state: &AppState) -> Response {
+    render_with_status(state, "500.html", &Context::new(), StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// The error page for `status`: the not-found page, the generic failure page for server errors,
+/// or a page naming the status for anything else.
+pub fn status_page(state: &AppState, status: StatusCode) -> Response {
+    match status {
+        StatusCode::NOT_FOUND => not_found_page(state),
+        status if status.is_server_error() => error_page(state),
+        status => {
+            let mut ctx = Context::new();
+            ctx.insert("status", &status.as_u16());
+            ctx.insert("reason", status.canonical_reason().unwrap_or("Request failed"));
+            render_with_status(state, "error.html", &ctx, status)
+        }
+    }
+}
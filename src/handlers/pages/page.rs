@@ -0,0 +1,38 @@
+use axum::extract::{Path, State};
+use axum::response::Response;
+use tera::Context;
+
+use crate::db::models::page::Pages;
+use crate::handlers::pages::{cached_page, error_page, not_found_page, render};
+use crate::services::cache::static_page_key;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// `GET /p/{slug}`, a published site page such as the terms of service.
+pub async fn static_page(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Response {
+    cached_page(&state, &static_page_key(&slug), || build_static_page(&state, &slug)).await
+}
+
+fn build_static_page(state: &AppState, slug: &str) -> Response {
+    let Ok(mut conn) = get_db_conn(state) else {
+        tracing::error!("Failed to get database connection for page");
+        return error_page(state);
+    };
+
+    let page = match Pages::published_by_slug(&mut conn, slug) {
+        Ok(Some(page)) => page,
+        Ok(None) => return not_found_page(state),
+        Err(e) => {
+            tracing::error!("Failed to load page {}: {}", slug, e);
+            return error_page(state);
+        }
+    };
+
+    let mut ctx = Context::new();
+    ctx.insert("page", &page);
+
+    render(state, "page.html", &ctx)
+}
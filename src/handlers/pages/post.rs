@@ -0,0 +1,109 @@
+use axum::extract::{Path, State};
+use axum::response::{IntoResponse, Response};
+use diesel::SqliteConnection;
+use http::{header, StatusCode};
+use tera::Context;
+
+use crate::db::models::post::Posts;
+use crate::db::models::post_slug::PostSlugs;
+use crate::db::models::tag::Tags;
+use crate::db::models::upload::Uploads;
+use crate::db::models::user_model::UserModel;
+use crate::handlers::pages::{cached_page, error_page, insert_avatar, insert_blog_style, not_found_page, render, PostView};
+use crate::handlers::series::load_series_nav;
+use crate::http::client::ClientInfo;
+use crate::services::cache::post_page_key;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Views are counted here rather than when the page is built, so cached pages count too.
+pub async fn post_page(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Path((username, slug)): Path<(String, String)>,
+) -> Response {
+    let response = cached_page(&state, &post_page_key(&username, &slug), || build_post_page(&state, &username, &slug)).await;
+    if response.status() == StatusCode::OK && !client.is_bot() {
+        state.views.record(&username, &slug).await;
+    }
+    response
+}
+
+fn build_post_page(state: &AppState, username: &str, slug: &str) -> Response {
+    let Ok(mut conn) = get_db_conn(state) else {
+        tracing::error!("Failed to get database connection for post page");
+        return error_page(state);
+    };
+
+    let author = match UserModel::by_name(&mut conn, username) {
+        Ok(Some(author)) => author,
+        Ok(None) => return not_found_page(state),
+        Err(e) => {
+            tracing::error!("Failed to load author {}: {}", username, e);
+            return error_page(state);
+        }
+    };
+
+    let post = match Posts::published_by_slug(&mut conn, &author.id, slug) {
+        Ok(Some(post)) => post,
+        Ok(None) => return old_slug_redirect(state, &mut conn, &author, slug),
+        Err(e) => {
+            tracing::error!("Failed to load post {}/{}: {}", username, slug, e);
+            return error_page(state);
+        }
+    };
+
+    let tags: Vec<String> = match Tags::by_post(&mut conn, &post.id) {
+        Ok(tags) => tags.into_iter().map(|tag| tag.name).collect(),
+        Err(e) => {
+            tracing::error!("Failed to load tags for post {}: {}", post.id, e);
+            return error_page(state);
+        }
+    };
+
+    let series = match load_series_nav(&mut conn, &post) {
+        Ok(series) => series,
+        Err(e) => {
+            tracing::warn!("Failed to load series for post {}: {}", post.id, e);
+            None
+        }
+    };
+
+    // Only alt text the author reviewed is published; pending suggestions stay in the editor.
+    let cover_alt = match post.cover_upload_id.as_deref().map(|id| Uploads::by_id(&mut conn, id)) {
+        Some(Ok(upload)) => upload.and_then(|upload| upload.alt_text).unwrap_or_default(),
+        Some(Err(e)) => {
+            tracing::warn!("Failed to load cover image for post {}: {}", post.id, e);
+            String::new()
+        }
+        None => String::new(),
+    };
+
+    let mut ctx = Context::new();
+    insert_blog_style(state, &mut conn, &author, &mut ctx);
+    insert_avatar(&mut conn, &author, &mut ctx);
+    ctx.insert("post", &PostView::new(post, author.name));
+    ctx.insert("tags", &tags);
+    ctx.insert("cover_alt", &cover_alt);
+    ctx.insert("series", &series);
+
+    render(state, "post.html", &ctx)
+}
+
+/// A permanent redirect to where a post that used to be at `slug` is now, or the not-found page.
+fn old_slug_redirect(state: &AppState, conn: &mut SqliteConnection, author: &UserModel, slug: &str) -> Response {
+    let post = PostSlugs::post_id_for(conn, &author.id, slug)
+        .and_then(|post_id| post_id.map(|post_id| Posts::by_id(conn, &post_id)).transpose())
+        .map(Option::flatten);
+    match post {
+        Ok(Some(post)) if post.is_public() => {
+            let location = format!("/{}/{}", author.name, post.slug);
+            (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, location)]).into_response()
+        }
+        Ok(_) => not_found_page(state),
+        Err(e) => {
+            tracing::error!("Failed to look up old slug {}/{}: {}", author.name, slug, e);
+            error_page(state)
+        }
+    }
+}
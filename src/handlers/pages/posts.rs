@@ -0,0 +1,61 @@
+use axum::extract::{Query, State};
+use axum::response::Response;
+use tera::Context;
+
+use crate::db::models::post::Posts;
+use crate::handlers::pages::{cached_page, error_page, not_found_page, render, PageQuery, PostView, POSTS_PER_PAGE};
+use crate::services::cache::posts_page_key;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+pub async fn posts_page(
+    State(state): State<AppState>,
+    Query(query): Query<PageQuery>,
+) -> Response {
+    let page = query.page.unwrap_or(1);
+    if page < 1 {
+        return not_found_page(&state);
+    }
+
+    cached_page(&state, &posts_page_key(page), || build_posts_page(&state, page)).await
+}
+
+fn build_posts_page(state: &AppState, page: i64) -> Response {
+    let Ok(mut conn) = get_db_conn(state) else {
+        tracing::error!("Failed to get database connection for posts page");
+        return error_page(state);
+    };
+
+    let total = match Posts::count_published(&mut conn) {
+        Ok(total) => total,
+        Err(e) => {
+            tracing::error!("Failed to count published posts: {}", e);
+            return error_page(state);
+        }
+    };
+
+    let total_pages = ((total + POSTS_PER_PAGE - 1) / POSTS_PER_PAGE).max(1);
+    if page > total_pages {
+        return not_found_page(state);
+    }
+
+    let posts = match Posts::published_page(&mut conn, (page - 1) * POSTS_PER_PAGE, POSTS_PER_PAGE) {
+        Ok(posts) => posts,
+        Err(e) => {
+            tracing::error!("Failed to load published posts: {}", e);
+            return error_page(state);
+        }
+    };
+
+    let posts: Vec<PostView> = posts
+        .into_iter()
+        .map(|(post, author)| PostView::new(post, author))
+        .collect();
+
+    let mut ctx = Context::new();
+    ctx.insert("posts", &posts);
+    ctx.insert("page", &page);
+    ctx.insert("total_pages", &total_pages);
+
+    render(state, "posts.html", &ctx)
+}
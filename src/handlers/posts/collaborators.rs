@@ -0,0 +1,120 @@
+use axum::extract::{Path, State};
+use diesel::SqliteConnection;
+use tsumi_types::{CollaboratorResponse, CollaboratorsResponse};
+
+use crate::db::models::post_collaborator::{PostCollaborators, COLLABORATOR_ROLES, COLLABORATOR_ROLE_EDITOR};
+use crate::db::models::user_model::UserModel;
+use crate::errors::{AppError, AuthError, DbError};
+use crate::handlers::posts::{load_post_as, InviteCollaboratorRequest, PostRole};
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::api_tokens::{SCOPE_POSTS_READ, SCOPE_POSTS_WRITE};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+fn collaborators(conn: &mut SqliteConnection, post_id: &str) -> Result<CollaboratorsResponse, AppError> {
+    let collaborators = PostCollaborators::by_post(conn, post_id)
+        .map_err(DbError::query("Failed to list collaborators"))?
+        .into_iter()
+        .map(|(collaborator, name)| CollaboratorResponse {
+            user_id: collaborator.user_id,
+            name,
+            role: collaborator.role,
+            added_at: collaborator.created_at,
+        })
+        .collect();
+    Ok(CollaboratorsResponse { collaborators })
+}
+
+/// `GET /posts/{id}/collaborators`, for the author and everyone they invited.
+pub async fn list_collaborators(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+) -> Result<ApiResponse<CollaboratorsResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_READ)?;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = load_post_as(&mut conn, &post_id, &auth.user.id, PostRole::Viewer)?;
+
+    Ok(ApiResponse::new(collaborators(&mut conn, &post.id)?))
+}
+
+/// `POST /posts/{id}/collaborators`, the author inviting someone by name. Inviting someone
+/// who's already on the post changes their role.
+pub async fn invite_collaborator(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+    AppJson(payload): AppJson<InviteCollaboratorRequest>,
+) -> Result<ApiResponse<CollaboratorsResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    let role = payload.role.unwrap_or_else(|| COLLABORATOR_ROLE_EDITOR.to_string());
+    if !COLLABORATOR_ROLES.contains(&role.as_str()) {
+        return Err(AuthError::invalid_field(
+            "role",
+            "unknown_role",
+            format!("Role must be one of: {}", COLLABORATOR_ROLES.join(", ")),
+        ).into());
+    }
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = load_post_as(&mut conn, &post_id, &user.id, PostRole::Owner)?;
+
+    let invitee = UserModel::by_name(&mut conn, payload.username.trim())
+        .map_err(DbError::query("Failed to load user"))?
+        .ok_or_else(|| AuthError::invalid_field("username", "unknown_user", "No user has that name"))?;
+    if invitee.id == post.user_id {
+        return Err(AuthError::invalid_field("username", "author", "The author can't be a collaborator on their own post").into());
+    }
+
+    state.writes.write(&mut conn, |conn| PostCollaborators::upsert(conn, &PostCollaborators {
+        post_id: post.id.clone(),
+        user_id: invitee.id.clone(),
+        role,
+        invited_by: Some(user.id.clone()),
+        created_at: chrono::Utc::now().naive_utc(),
+    }))
+    .await
+    .map_err(DbError::query("Failed to add collaborator"))?;
+
+    tracing::info!("User {} added {} as a collaborator on post {}", user.id, invitee.id, post.id);
+
+    Ok(ApiResponse::new(collaborators(&mut conn, &post.id)?))
+}
+
+/// `DELETE /posts/{id}/collaborators/{user_id}`. The author can remove anyone, and
+/// collaborators can remove themselves.
+pub async fn remove_collaborator(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((post_id, user_id)): Path<(String, String)>,
+) -> Result<ApiResponse<CollaboratorsResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let needed = if user_id == user.id { PostRole::Viewer } else { PostRole::Owner };
+    let post = load_post_as(&mut conn, &post_id, &user.id, needed)?;
+
+    let removed = state.writes.write(&mut conn, |conn| PostCollaborators::remove(conn, &post.id, &user_id))
+        .await
+        .map_err(DbError::query("Failed to remove collaborator"))?;
+    if removed == 0 {
+        return Err(AuthError::not_found(user_id).into());
+    }
+
+    tracing::info!("User {} removed {} from post {}", user.id, user_id, post.id);
+
+    // Someone who just left the post can no longer see who's on it.
+    if post.user_id != user.id {
+        return Ok(ApiResponse::new(CollaboratorsResponse { collaborators: Vec::new() }));
+    }
+    Ok(ApiResponse::new(collaborators(&mut conn, &post.id)?))
+}
@@ -0,0 +1,153 @@
+use axum::extract::State;
+use validator::Validate;
+
+use crate::db::models::onboarding_step::ONBOARDING_STEP_FIRST_POST;
+use crate::db::models::post::{NewPost, Posts, POST_STATUS_DRAFT};
+use crate::db::models::post_slug::PostSlugs;
+use crate::db::models::post_version::PostVersions;
+use crate::db::models::series::Series;
+use crate::db::models::tag::Tags;
+use crate::db::models::webhook::WEBHOOK_EVENT_POST_PUBLISHED;
+use crate::errors::{AppError, AuthError, DbError, PostError};
+use crate::handlers::posts::{
+    map_post_write_error, normalize_tags, publication, resolve_cover_image, resolve_series, CreatePostRequest,
+    SLUG_REGEX,
+};
+use crate::http::auth::AuthUser;
+use crate::http::dto::{post_dto, ApiResponse, PostDto};
+use crate::http::json::AppJson;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::services::cache;
+use crate::services::markdown::split_front_matter;
+use crate::services::notifications;
+use crate::services::onboarding;
+use crate::services::post_metadata;
+use crate::services::slugs;
+use crate::services::webhooks;
+use crate::state::AppState;
+use crate::utils::{get_db_conn, slugify};
+
+pub async fn create_post(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    AppJson(payload): AppJson<CreatePostRequest>,
+) -> Result<ApiResponse<PostDto>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    tracing::info!("Processing post creation for user: {}", user.id);
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid post data", err))?;
+
+    // Front matter fills in anything the request leaves blank.
+    let front_matter = split_front_matter(&payload.content).0.unwrap_or_default();
+
+    let description = if payload.description.is_empty() {
+        front_matter.description.unwrap_or_default()
+    } else {
+        payload.description
+    };
+    let tags = normalize_tags(if payload.tags.is_empty() { &front_matter.tags } else { &payload.tags })?;
+    // A slug the author chose is theirs to keep or to clash with; one made from the title
+    // gets a number added until it's free.
+    let chosen_slug = payload.slug.or(front_matter.slug);
+    if chosen_slug.as_deref().is_some_and(|slug| !SLUG_REGEX.is_match(slug)) {
+        return Err(AuthError::invalid_field("slug", "regex", "Slug may only contain lowercase letters, digits and hyphens").into());
+    }
+    if chosen_slug.as_deref().is_some_and(slugs::is_reserved) {
+        return Err(PostError::SlugReserved.into());
+    }
+    let title_slug = slugify(&payload.title);
+    if chosen_slug.as_deref().unwrap_or(&title_slug).is_empty() {
+        return Err(PostError::SlugRequired.into());
+    }
+
+    let content = if user.canonicalize_links {
+        state.link_rules.canonicalize_markdown(&payload.content)
+    } else {
+        payload.content
+    };
+
+    let description = if description.is_empty() {
+        post_metadata::description(&content)
+    } else {
+        description
+    };
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let slug = match chosen_slug {
+        Some(slug) => slug,
+        None => slugs::available(&mut conn, &user.id, &title_slug, None).map_err(map_post_write_error)?,
+    };
+
+    let cover_upload_id = payload.cover_image_id
+        .as_deref()
+        .map(|upload_id| resolve_cover_image(&mut conn, upload_id, &user.id))
+        .transpose()?;
+    let series_id = payload.series_id
+        .as_deref()
+        .map(|series_id| resolve_series(&mut conn, series_id, &user.id))
+        .transpose()?;
+
+    let (status, published_at) = if payload.is_published || payload.publish_at.is_some() {
+        let (status, published_at) = publication(payload.publish_at);
+        (status, Some(published_at))
+    } else {
+        (POST_STATUS_DRAFT, None)
+    };
+
+    let word_count = post_metadata::word_count(&content);
+    let now = chrono::Utc::now().naive_utc();
+    let new_post = NewPost {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user.id.clone(),
+        title: payload.title,
+        description,
+        slug,
+        created_at: now,
+        updated_at: now,
+        status: status.to_string(),
+        published_at,
+        word_count: Some(word_count),
+        og_image_url: post_metadata::og_image(&content),
+        cover_upload_id,
+        reading_minutes: Some(post_metadata::reading_minutes(word_count)),
+        content,
+    };
+    let search_terms = post_metadata::search_terms(&new_post.title, &new_post.description, &new_post.content);
+    let commit_message = payload.commit_message.unwrap_or_else(|| "Initial version".to_string());
+
+    let (post, tags) = state.writes.write(&mut conn, |conn| {
+        let post = Posts::create(conn, &new_post)?;
+        PostSlugs::release(conn, &user.id, &post.slug)?;
+        PostVersions::record(conn, &post, &user.id, &commit_message)?;
+        Posts::index_search_terms(conn, &post.id, &search_terms)?;
+        let tags = Tags::set_for_post(conn, &post.id, &tags)?;
+        if let Some(series_id) = &series_id {
+            Series::add_post(conn, series_id, &post.id)?;
+        }
+        if post.is_published() {
+            if let Err(e) = notifications::record_published(conn, &post) {
+                tracing::warn!("Failed to notify followers about post {}: {}", post.id, e);
+            }
+            if let Err(e) = webhooks::emit(conn, &user.id, WEBHOOK_EVENT_POST_PUBLISHED, webhooks::post_data(&post)) {
+                tracing::warn!("Failed to queue webhooks for post {}: {}", post.id, e);
+            }
+            if let Err(e) = onboarding::complete(conn, &user.id, ONBOARDING_STEP_FIRST_POST) {
+                tracing::warn!("Failed to update onboarding for user {}: {}", user.id, e);
+            }
+        }
+        Ok((post, tags))
+    })
+    .await
+    .map_err(map_post_write_error)?;
+
+    if post.is_published() {
+        cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+    }
+
+    tracing::info!("User {} created post {}", user.id, post.id);
+
+    Ok(ApiResponse::new(post_dto(post, tags)))
+}
@@ -0,0 +1,46 @@
+use axum::extract::{Path, State};
+use tsumi_types::DeletePostResponse;
+
+use crate::db::models::post::Posts;
+use crate::db::models::webhook::WEBHOOK_EVENT_POST_DELETED;
+use crate::errors::{AppError, DbError};
+use crate::handlers::posts::load_owned_post;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::services::cache;
+use crate::services::webhooks;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+pub async fn delete_post(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+) -> Result<ApiResponse<DeletePostResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = load_owned_post(&mut conn, &post_id, &user.id)?;
+
+    state.writes.write(&mut conn, |conn| {
+        Posts::delete(conn, &post.id)?;
+        if let Err(e) = webhooks::emit(conn, &user.id, WEBHOOK_EVENT_POST_DELETED, webhooks::post_data(&post)) {
+            tracing::warn!("Failed to queue webhooks for post {}: {}", post.id, e);
+        }
+        Ok(())
+    })
+    .await
+    .map_err(DbError::query("Failed to delete post"))?;
+
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    tracing::info!("User {} deleted post {}", user.id, post.id);
+
+    Ok(ApiResponse::new(DeletePostResponse {
+        message: "Post deleted".to_string(),
+        deleted_at: chrono::Utc::now(),
+    }))
+}
@@ -0,0 +1,125 @@
+use axum::extract::{Path, Query, State};
+use tsumi_types::{ListPostVersionsResponse, PostVersionDto};
+
+use crate::db::models::post::{Posts, POST_STATUS_DRAFT, POST_STATUS_PUBLISHED, POST_STATUS_SCHEDULED};
+use crate::db::models::post_version::PostVersions;
+use crate::db::models::tag::Tags;
+use crate::db::queries::posts::PostFilter;
+use crate::errors::{AppError, AuthError, DbError, PostError};
+use crate::handlers::posts::{
+    list_responses, load_post_as, load_reaction_counts, load_view_count, ListMyPostsQuery, PostRole, PostSort,
+};
+use crate::handlers::series::load_series_nav;
+use crate::http::auth::AuthUser;
+use crate::http::dto::{post_dto, ApiResponse, PostDto};
+use crate::http::pagination::{ListParams, Paginated};
+use crate::services::api_tokens::SCOPE_POSTS_READ;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+impl From<PostVersions> for PostVersionDto {
+    fn from(version: PostVersions) -> Self {
+        Self {
+            id: version.id,
+            post_id: version.post_id,
+            user_id: version.user_id,
+            title: version.title,
+            content: version.content,
+            description: version.description,
+            commit_hash: version.commit_hash,
+            commit_message: version.commit_message,
+            created_at: version.created_at,
+        }
+    }
+}
+
+/// Published posts are visible to everyone; drafts only to their author and collaborators.
+pub async fn get_post(
+    State(state): State<AppState>,
+    auth: Option<AuthUser>,
+    Path(post_id): Path<String>,
+) -> Result<ApiResponse<PostDto>, AppError> {
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = Posts::by_id(&mut conn, &post_id)
+        .map_err(DbError::query("Failed to load post"))?
+        .ok_or_else(|| PostError::not_found(&post_id))?;
+
+    if !post.is_public() {
+        let role = match auth.as_ref().filter(|auth| auth.require_scope(SCOPE_POSTS_READ).is_ok()) {
+            Some(auth) => PostRole::of(&mut conn, &post, &auth.user.id)?,
+            None => None,
+        };
+        if role.is_none() {
+            return Err(PostError::not_found(post_id).into());
+        }
+    }
+
+    let tags = Tags::by_post(&mut conn, &post.id)
+        .map_err(DbError::query("Failed to load post"))?;
+
+    let reactions = load_reaction_counts(&mut conn, &post.id)?;
+
+    let views = load_view_count(&mut conn, &post.id)?;
+    let series = load_series_nav(&mut conn, &post)?;
+
+    Ok(ApiResponse::new(post_dto(post, tags).with_reactions(reactions).with_views(views).with_series(series)))
+}
+
+/// The caller's posts in any state. Filter with `status` and `tag`; sort by `updated_at`
+/// (the default), `created_at`, `published_at` or `title`.
+pub async fn list_my_posts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    params: ListParams<PostSort>,
+    Query(query): Query<ListMyPostsQuery>,
+) -> Result<ApiResponse<Paginated<PostDto>>, AppError> {
+    auth.require_scope(SCOPE_POSTS_READ)?;
+
+    let status = query.status.as_deref().filter(|status| !status.is_empty());
+    if status.is_some_and(|status| ![POST_STATUS_DRAFT, POST_STATUS_SCHEDULED, POST_STATUS_PUBLISHED].contains(&status)) {
+        return Err(AuthError::validation("Status must be one of: draft, scheduled, published").into());
+    }
+    let tag = query.tag.as_deref().map(|tag| tag.trim().to_lowercase()).filter(|tag| !tag.is_empty());
+    let filter = PostFilter { status, tag: tag.as_deref() };
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let total = Posts::count_by_user(&mut conn, &auth.user.id, &filter)
+        .map_err(DbError::query("Failed to list posts"))?;
+
+    let posts = Posts::page_by_user(
+        &mut conn,
+        &auth.user.id,
+        &filter,
+        params.sort,
+        params.dir,
+        params.offset(),
+        params.limit(),
+    )
+    .map_err(DbError::query("Failed to list posts"))?;
+
+    let responses = list_responses(&mut conn, posts)?;
+
+    Ok(ApiResponse::new(params.paginate(responses, total)))
+}
+
+pub async fn list_post_versions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+) -> Result<ApiResponse<ListPostVersionsResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_READ)?;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = load_post_as(&mut conn, &post_id, &auth.user.id, PostRole::Viewer)?;
+
+    let versions = PostVersions::by_post(&mut conn, &post.id)
+        .map_err(DbError::query("Failed to list post versions"))?
+        .into_iter()
+        .map(PostVersionDto::from)
+        .collect();
+
+    Ok(ApiResponse::new(ListPostVersionsResponse { versions }))
+}
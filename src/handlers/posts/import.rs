@@ -0,0 +1,227 @@
+use axum::extract::{Multipart, State};
+use diesel::SqliteConnection;
+use http::StatusCode;
+use serde::Serialize;
+use validator::Validate;
+
+use crate::db::models::onboarding_step::ONBOARDING_STEP_FIRST_POST;
+use crate::db::models::post::{NewPost, Posts, POST_STATUS_DRAFT, POST_STATUS_PUBLISHED, POST_STATUS_SCHEDULED};
+use crate::db::models::post_slug::PostSlugs;
+use crate::db::models::post_version::PostVersions;
+use crate::db::models::tag::Tags;
+use crate::db::models::user_model::UserModel;
+use crate::db::write::write;
+use crate::errors::{AppError, AuthError, DbError, PostError};
+use crate::handlers::posts::{map_post_write_error, normalize_tags, CreatePostRequest};
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::services::cache;
+use crate::services::links::LinkRules;
+use crate::services::onboarding;
+use crate::services::post_import::{self, ImportFile};
+use crate::services::post_metadata;
+use crate::services::slugs;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Debug, Serialize)]
+pub struct ImportFileResult {
+    pub file: String,
+    pub imported: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ImportFileResult {
+    fn imported(file: String, post: &Posts) -> Self {
+        Self {
+            file,
+            imported: true,
+            post_id: Some(post.id.clone()),
+            slug: Some(post.slug.clone()),
+            status: Some(post.status.clone()),
+            error: None,
+        }
+    }
+
+    fn failed(file: String, error: impl Into<String>) -> Self {
+        Self { file, imported: false, post_id: None, slug: None, status: None, error: Some(error.into()) }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub failed: usize,
+    pub files: Vec<ImportFileResult>,
+}
+
+fn multipart_error(e: axum::extract::multipart::MultipartError, max_bytes: usize) -> AuthError {
+    match e.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => AuthError::payload_too_large(format!("Imports are limited to {} bytes", max_bytes)),
+        _ => AuthError::validation(format!("Invalid multipart body: {}", e.body_text())),
+    }
+}
+
+/// Checks an imported file the way `create_post` checks a request, and builds the post.
+fn prepare(user: &UserModel, link_rules: &LinkRules, file: ImportFile) -> Result<(NewPost, Vec<String>), String> {
+    let parsed = post_import::parse(&file.name, file.contents?)?;
+
+    let request = CreatePostRequest {
+        title: parsed.title,
+        description: parsed.description,
+        slug: parsed.slug,
+        content: parsed.content,
+        tags: parsed.tags,
+        is_published: false,
+        publish_at: None,
+        cover_image_id: None,
+        series_id: None,
+        commit_message: None,
+    };
+    request.validate().map_err(|err| format!("Invalid post data: {}", err))?;
+    let tags = normalize_tags(&request.tags).map_err(|e| e.to_string())?;
+    let slug = request.slug.ok_or("Could not derive a slug from the title, please provide one")?;
+    if slugs::is_reserved(&slug) {
+        return Err(PostError::SlugReserved.to_string());
+    }
+
+    let content = if user.canonicalize_links {
+        link_rules.canonicalize_markdown(&request.content)
+    } else {
+        request.content
+    };
+    let description = if request.description.is_empty() {
+        post_metadata::description(&content)
+    } else {
+        request.description
+    };
+
+    let word_count = post_metadata::word_count(&content);
+    let now = chrono::Utc::now().naive_utc();
+    let status = match parsed.published_at {
+        Some(at) if at > now => POST_STATUS_SCHEDULED,
+        Some(_) => POST_STATUS_PUBLISHED,
+        None => POST_STATUS_DRAFT,
+    };
+    let post = NewPost {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user.id.clone(),
+        title: request.title,
+        description,
+        slug,
+        created_at: now,
+        updated_at: now,
+        status: status.to_string(),
+        published_at: parsed.published_at,
+        word_count: Some(word_count),
+        og_image_url: post_metadata::og_image(&content),
+        cover_upload_id: None,
+        reading_minutes: Some(post_metadata::reading_minutes(word_count)),
+        content,
+    };
+    Ok((post, tags))
+}
+
+fn create(conn: &mut SqliteConnection, post: &NewPost, tags: &[String], file: &str) -> diesel::QueryResult<Posts> {
+    let search_terms = post_metadata::search_terms(&post.title, &post.description, &post.content);
+    let post = Posts::create(conn, post)?;
+    PostSlugs::release(conn, &post.user_id, &post.slug)?;
+    PostVersions::record(conn, &post, &post.user_id, &format!("Imported from {}", file))?;
+    Posts::index_search_terms(conn, &post.id, &search_terms)?;
+    Tags::set_for_post(conn, &post.id, tags)?;
+    if post.is_published() {
+        onboarding::complete(conn, &post.user_id, ONBOARDING_STEP_FIRST_POST)?;
+    }
+    Ok(post)
+}
+
+/// `POST /api/v1/posts/import`, a multipart form of markdown files and zip archives of them.
+///
+/// Every file becomes a post, or an error in the report: one bad file doesn't stop the rest.
+/// Imported posts keep their front matter `date` as their publication time, so followers
+/// aren't notified and webhooks don't fire for them.
+pub async fn import_posts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut multipart: Multipart,
+) -> Result<ApiResponse<ImportReport>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    let max_bytes = state.config.load().import_max_bytes();
+    let max_files = state.config.load().import_max_files();
+
+    let mut files: Vec<ImportFile> = Vec::new();
+    // Uploaded bytes and whatever archives expand to both count against the limit.
+    let mut remaining = max_bytes;
+    while let Some(field) = multipart.next_field().await.map_err(|e| multipart_error(e, max_bytes))? {
+        let Some(name) = field.file_name().map(str::to_string) else {
+            continue;
+        };
+        let bytes = field.bytes().await.map_err(|e| multipart_error(e, max_bytes))?;
+        remaining = remaining.checked_sub(bytes.len())
+            .ok_or_else(|| AuthError::payload_too_large(format!("Imports are limited to {} bytes", max_bytes)))?;
+
+        if post_import::is_zip(&name) {
+            let room = max_files.saturating_sub(files.len());
+            let expanded = post_import::expand_zip(&name, &bytes, room, &mut remaining)
+                .map_err(AuthError::validation)?;
+            files.extend(expanded);
+        } else if post_import::is_markdown(&name) {
+            files.push(post_import::decode(&name, bytes.to_vec()));
+        } else {
+            files.push(ImportFile { name, contents: Err("Not a markdown file or zip archive".to_string()) });
+        }
+        if files.len() > max_files {
+            return Err(AuthError::validation(format!("Imports are limited to {} files", max_files)).into());
+        }
+    }
+    if files.is_empty() {
+        return Err(AuthError::validation("Upload at least one .md file or .zip archive").into());
+    }
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    // Each file gets its own savepoint, so a failing one is rolled back alone and the rest
+    // commit together.
+    let results = state.writes.write::<_, diesel::result::Error>(&mut conn, |conn| {
+        let mut imported = Vec::new();
+        for file in files {
+            let name = file.name.clone();
+            let (post, tags) = match prepare(&user, &state.link_rules, file) {
+                Ok(prepared) => prepared,
+                Err(e) => {
+                    imported.push(ImportFileResult::failed(name, e));
+                    continue;
+                }
+            };
+            match write(conn, |conn| create(conn, &post, &tags, &name)) {
+                Ok(post) => imported.push(ImportFileResult::imported(name, &post)),
+                Err(e) => imported.push(ImportFileResult::failed(name, map_post_write_error(e).to_string())),
+            }
+        }
+        Ok(imported)
+    })
+    .await
+    .map_err(DbError::query("Failed to import posts"))?;
+
+    let succeeded = results.iter().filter(|result| result.imported).count();
+    if succeeded > 0 {
+        cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+    }
+
+    tracing::info!("User {} imported {} of {} file(s)", user.id, succeeded, results.len());
+
+    Ok(ApiResponse::new(ImportReport {
+        imported: succeeded,
+        failed: results.len() - succeeded,
+        files: results,
+    }))
+}
@@ -0,0 +1,275 @@
+use axum::extract::{Path, State};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::SqliteConnection;
+use http::{HeaderMap, StatusCode};
+use serde::Serialize;
+
+use crate::db::models::post_lock::PostLocks;
+use crate::db::models::user_model::UserModel;
+use crate::errors::{AppError, AuthError, DbError, PostError};
+use crate::handlers::posts::{load_post_as, PostRole};
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::tx::Tx;
+use crate::services::api_tokens::{SCOPE_POSTS_READ, SCOPE_POSTS_WRITE};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Identifies the editor tab or device making the request. Clients pick a random id once
+/// per editing session and send it with every lock call and post update.
+pub const EDITOR_SESSION_HEADER: &str = "x-editor-session";
+
+#[derive(Debug, Serialize)]
+pub struct LockHolder {
+    pub user_id: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TakeoverRequest {
+    pub requested_by: LockHolder,
+    pub requested_at: NaiveDateTime,
+    /// When the requester may claim the lock if the holder hasn't let go.
+    pub claimable_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LockResponse {
+    pub post_id: String,
+    pub holder: LockHolder,
+    /// Whether the lock belongs to the session that made this request.
+    pub held_by_you: bool,
+    pub acquired_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    /// A pending request from another session. The holder should save and release the lock.
+    pub takeover: Option<TakeoverRequest>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReleaseLockResponse {
+    pub message: String,
+}
+
+fn editor_session(headers: &HeaderMap) -> Result<Option<&str>, AuthError> {
+    let Some(value) = headers.get(EDITOR_SESSION_HEADER) else {
+        return Ok(None);
+    };
+    let session = value
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|session| (8..=64).contains(&session.len()))
+        .ok_or_else(|| AuthError::validation("X-Editor-Session must be between 8 and 64 characters"))?;
+    Ok(Some(session))
+}
+
+fn require_editor_session(headers: &HeaderMap) -> Result<&str, AuthError> {
+    editor_session(headers)?.ok_or_else(|| AuthError::validation("Missing X-Editor-Session header"))
+}
+
+fn lock_holder(conn: &mut SqliteConnection, user_id: &str) -> LockHolder {
+    let name = UserModel::by_id(conn, user_id)
+        .inspect_err(|e| tracing::warn!("Failed to load lock holder {}: {}", user_id, e))
+        .ok()
+        .flatten()
+        .map(|user| user.name);
+    LockHolder { user_id: user_id.to_string(), name }
+}
+
+fn lock_response(conn: &mut SqliteConnection, lock: PostLocks, session_id: Option<&str>, grace: i64) -> LockResponse {
+    let takeover = match (lock.takeover_user_id.as_deref(), lock.takeover_requested_at) {
+        (Some(user_id), Some(requested_at)) => Some(TakeoverRequest {
+            requested_by: lock_holder(conn, user_id),
+            requested_at,
+            claimable_at: requested_at + Duration::seconds(grace),
+        }),
+        _ => None,
+    };
+
+    LockResponse {
+        holder: lock_holder(conn, &lock.user_id),
+        held_by_you: session_id == Some(lock.session_id.as_str()),
+        post_id: lock.post_id,
+        acquired_at: lock.acquired_at,
+        expires_at: lock.expires_at,
+        takeover,
+    }
+}
+
+fn lease(post_id: &str, user_id: &str, session_id: &str, now: NaiveDateTime, ttl: i64) -> PostLocks {
+    PostLocks {
+        post_id: post_id.to_string(),
+        user_id: user_id.to_string(),
+        session_id: session_id.to_string(),
+        acquired_at: now,
+        expires_at: now + Duration::seconds(ttl),
+        takeover_session_id: None,
+        takeover_user_id: None,
+        takeover_requested_at: None,
+    }
+}
+
+fn held_elsewhere(lock: &PostLocks) -> PostError {
+    PostError::edit_locked(format!(
+        "Post is being edited in another session until {} UTC; request a takeover to edit it",
+        lock.expires_at.format("%H:%M:%S"),
+    ))
+}
+
+fn active_lock(conn: &mut SqliteConnection, post_id: &str, now: NaiveDateTime) -> Result<Option<PostLocks>, DbError> {
+    let lock = PostLocks::by_post(conn, post_id)
+        .map_err(DbError::query("Failed to load edit lock"))?;
+    Ok(lock.filter(|lock| !lock.is_expired(now)))
+}
+
+/// Rejects a post update from an editor session while another session holds the lock.
+/// Updates that don't name a session skip the check, so scripts and older clients keep
+/// working; the lock is advisory for them.
+pub fn check_edit_lock(conn: &mut SqliteConnection, post_id: &str, headers: &HeaderMap) -> Result<(), AppError> {
+    let Some(session_id) = editor_session(headers)? else {
+        return Ok(());
+    };
+    match active_lock(conn, post_id, Utc::now().naive_utc())? {
+        Some(lock) if lock.session_id != session_id => Err(held_elsewhere(&lock).into()),
+        _ => Ok(()),
+    }
+}
+
+/// `GET /posts/{id}/lock`, the current lock if any session holds one.
+pub async fn get_lock(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<ApiResponse<Option<LockResponse>>, AppError> {
+    auth.require_scope(SCOPE_POSTS_READ)?;
+    let session_id = editor_session(&headers)?;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = load_post_as(&mut conn, &post_id, &auth.user.id, PostRole::Viewer)?;
+    let grace = state.config.load().edit_lock_takeover_grace_seconds();
+    let lock = active_lock(&mut conn, &post.id, Utc::now().naive_utc())?
+        .map(|lock| lock_response(&mut conn, lock, session_id, grace));
+
+    Ok(ApiResponse::new(lock))
+}
+
+/// `POST /posts/{id}/lock`, acquiring the lock for the requesting session. Succeeds when the
+/// lock is free or expired, already held by this session, or this session's takeover request
+/// has waited out the grace period.
+pub async fn acquire_lock(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut tx: Tx,
+    Path(post_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<ApiResponse<LockResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    let session_id = require_editor_session(&headers)?;
+
+    let post = load_post_as(&mut tx, &post_id, &user.id, PostRole::Editor)?;
+
+    let now = Utc::now().naive_utc();
+    let grace = state.config.load().edit_lock_takeover_grace_seconds();
+    let lock = lease(&post.id, &user.id, session_id, now, state.config.load().edit_lock_ttl_seconds());
+
+    let granted = PostLocks::claim(&mut tx, &lock, now - Duration::seconds(grace))
+        .map_err(DbError::query("Failed to acquire edit lock"))?;
+
+    if !granted {
+        return match active_lock(&mut tx, &post.id, now)? {
+            Some(current) => Err(held_elsewhere(&current).into()),
+            None => Err(PostError::edit_locked("Edit lock changed hands, try again").into()),
+        };
+    }
+
+    tracing::info!("User {} locked post {} for editing", user.id, post.id);
+
+    Ok(ApiResponse::new(lock_response(&mut tx, lock, Some(session_id), grace)))
+}
+
+/// `POST /posts/{id}/lock/heartbeat`, extending the session's lease. The response carries any
+/// pending takeover request so the editor can tell its user.
+pub async fn heartbeat_lock(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<ApiResponse<LockResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    let session_id = require_editor_session(&headers)?;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = load_post_as(&mut conn, &post_id, &user.id, PostRole::Editor)?;
+    let expires_at = Utc::now().naive_utc() + Duration::seconds(state.config.load().edit_lock_ttl_seconds());
+    let lock = state.writes.write(&mut conn, |conn| PostLocks::renew(conn, &post.id, session_id, expires_at))
+        .await
+        .map_err(DbError::query("Failed to renew edit lock"))?
+        .ok_or_else(|| PostError::edit_locked("This session no longer holds the edit lock"))?;
+
+    let grace = state.config.load().edit_lock_takeover_grace_seconds();
+    Ok(ApiResponse::new(lock_response(&mut conn, lock, Some(session_id), grace)))
+}
+
+/// `POST /posts/{id}/lock/takeover`, asking the session holding the lock to hand it over.
+/// A free lock is simply acquired. Otherwise the holder sees the request on its next
+/// heartbeat, and the requester can acquire the lock once the grace period has passed.
+pub async fn request_lock_takeover(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut tx: Tx,
+    Path(post_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, ApiResponse<LockResponse>), AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    let session_id = require_editor_session(&headers)?;
+
+    let post = load_post_as(&mut tx, &post_id, &user.id, PostRole::Editor)?;
+    let now = Utc::now().naive_utc();
+    let grace = state.config.load().edit_lock_takeover_grace_seconds();
+
+    let current = active_lock(&mut tx, &post.id, now)?;
+    if current.as_ref().is_none_or(|lock| lock.session_id == session_id) {
+        let lock = lease(&post.id, &user.id, session_id, now, state.config.load().edit_lock_ttl_seconds());
+        if PostLocks::claim(&mut tx, &lock, now - Duration::seconds(grace)).map_err(DbError::query("Failed to acquire edit lock"))? {
+            return Ok((StatusCode::OK, ApiResponse::new(lock_response(&mut tx, lock, Some(session_id), grace))));
+        }
+    }
+
+    let lock = PostLocks::request_takeover(&mut tx, &post.id, session_id, &user.id, now)
+        .map_err(DbError::query("Failed to request edit lock takeover"))?
+        .ok_or_else(|| PostError::edit_locked("Edit lock changed hands, try again"))?;
+
+    tracing::info!("User {} requested a takeover of the edit lock on post {}", user.id, post.id);
+
+    Ok((StatusCode::ACCEPTED, ApiResponse::new(lock_response(&mut tx, lock, Some(session_id), grace))))
+}
+
+/// `DELETE /posts/{id}/lock`, releasing the session's lock. Releasing a lock the session
+/// doesn't hold is a no-op. Any collaborator can let go, even once the post is out of their
+/// hands.
+pub async fn release_lock(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<ApiResponse<ReleaseLockResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    let session_id = require_editor_session(&headers)?;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = load_post_as(&mut conn, &post_id, &user.id, PostRole::Viewer)?;
+    let released = state.writes.write(&mut conn, |conn| PostLocks::release(conn, &post.id, session_id))
+        .await
+        .map_err(DbError::query("Failed to release edit lock"))?;
+
+    let message = if released > 0 { "Edit lock released" } else { "Edit lock was not held by this session" };
+    Ok(ApiResponse::new(ReleaseLockResponse { message: message.to_string() }))
+}
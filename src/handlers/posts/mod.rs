@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::SqliteConnection;
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::db::models::post_collaborator::{PostCollaborators, COLLABORATOR_ROLE_EDITOR};
+use crate::db::models::post_fingerprint::PostFingerprints;
+use crate::db::models::post_reaction::PostReactions;
+use crate::db::models::post_view::PostViews;
+use crate::db::models::post::{Posts, POST_STATUS_DRAFT, POST_STATUS_PUBLISHED, POST_STATUS_SCHEDULED};
+use crate::db::models::series::Series;
+use crate::db::models::tag::Tags;
+use crate::db::models::upload::Uploads;
+use crate::errors::{AppError, DbError, PostError};
+use crate::http::dto::{post_dto, NearDuplicate, PostDto};
+use crate::http::pagination::Sortable;
+use crate::services::{post_metadata, simhash};
+
+pub mod collaborators;
+pub mod create;
+pub mod delete;
+pub mod get;
+pub mod import;
+pub mod lock;
+pub mod publish;
+pub mod react;
+pub mod sync;
+pub mod update;
+
+pub use tsumi_types::{
+    CreatePostRequest, InviteCollaboratorRequest, ListMyPostsQuery, PublishPostRequest, ReactPostRequest,
+    UpdatePostRequest, SLUG_REGEX,
+};
+
+/// Sort keys for an author's post list.
+pub struct PostSort;
+
+impl Sortable for PostSort {
+    const SORT_FIELDS: &'static [&'static str] = &["updated_at", "created_at", "published_at", "title"];
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReactedPostsQuery {
+    pub page: Option<i64>,
+}
+
+#[derive(Validate, Deserialize, Debug, Default)]
+pub struct CommitDocRequest {
+    #[validate(length(max = 500, message = "Commit message must be at most 500 characters"))]
+    pub message: Option<String>,
+}
+
+/// Resolves a publish request into a status and `published_at`. A publish time in the future
+/// schedules the post; anything else publishes it now.
+pub fn publication(publish_at: Option<DateTime<Utc>>) -> (&'static str, NaiveDateTime) {
+    let now = Utc::now();
+    match publish_at {
+        Some(at) if at > now => (POST_STATUS_SCHEDULED, at.naive_utc()),
+        _ => (POST_STATUS_PUBLISHED, now.naive_utc()),
+    }
+}
+
+/// Trims, lowercases and de-duplicates tag names, rejecting empty or overlong ones.
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, PostError> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() || tag.chars().count() > 32 {
+            return Err(PostError::InvalidTag);
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+/// Stores the post's content fingerprint and returns the public posts it nearly duplicates,
+/// closest first. A near duplicate of another author's post also flags this one for admins;
+/// nothing is blocked, since quoting or syndicating your own work is legitimate.
+pub fn record_fingerprint(conn: &mut SqliteConnection, post: &Posts) -> Result<Vec<NearDuplicate>, DbError> {
+    let hash = simhash::fingerprint(&post.title, &post_metadata::plain_text(&post.content));
+    let bands = simhash::bands(hash);
+
+    let candidates = PostFingerprints::candidates(conn, &post.id, bands)
+        .map_err(DbError::query("Failed to check for duplicate posts"))?;
+
+    let mut near_duplicates = Vec::new();
+    for candidate in candidates {
+        let distance = simhash::distance(hash, candidate.simhash as u64);
+        if distance > simhash::MAX_DISTANCE {
+            continue;
+        }
+        let existing = Posts::by_id(conn, &candidate.post_id)
+            .map_err(DbError::query("Failed to check for duplicate posts"))?;
+        if let Some(existing) = existing {
+            near_duplicates.push(NearDuplicate {
+                post_id: existing.id,
+                author_id: existing.user_id,
+                title: existing.title,
+                status: existing.status,
+                distance,
+            });
+        }
+    }
+    near_duplicates.sort_by_key(|duplicate| duplicate.distance);
+
+    let duplicate_of = near_duplicates
+        .iter()
+        .find(|duplicate| duplicate.author_id != post.user_id)
+        .map(|duplicate| duplicate.post_id.clone());
+    if let Some(original) = &duplicate_of {
+        tracing::warn!("Post {} by user {} nearly duplicates post {}", post.id, post.user_id, original);
+    }
+
+    let fingerprint = PostFingerprints {
+        post_id: post.id.clone(),
+        user_id: post.user_id.clone(),
+        simhash: hash as i64,
+        band0: bands[0],
+        band1: bands[1],
+        band2: bands[2],
+        band3: bands[3],
+        duplicate_of,
+        updated_at: Utc::now().naive_utc(),
+    };
+    PostFingerprints::upsert(conn, &fingerprint)
+        .map_err(DbError::query("Failed to publish post"))?;
+
+    Ok(near_duplicates)
+}
+
+/// What a user may do with a post: read it before it's published as an invited viewer,
+/// change its draft as an invited editor, or anything at all as its author.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PostRole {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+impl PostRole {
+    /// `user_id`'s role on `post`, if they have one.
+    pub fn of(conn: &mut SqliteConnection, post: &Posts, user_id: &str) -> Result<Option<Self>, AppError> {
+        if post.user_id == user_id {
+            return Ok(Some(Self::Owner));
+        }
+        let role = PostCollaborators::role_of(conn, &post.id, user_id)
+            .map_err(DbError::query("Failed to load post"))?;
+        Ok(role.map(|role| if role == COLLABORATOR_ROLE_EDITOR { Self::Editor } else { Self::Viewer }))
+    }
+}
+
+/// Loads a post the given user has at least `needed` on. Posts they have no part in are
+/// reported as missing, and those their role doesn't cover as not permitted. Editors only get
+/// to change drafts: once a post is scheduled or published, it's its author's alone.
+pub fn load_post_as(conn: &mut SqliteConnection, post_id: &str, user_id: &str, needed: PostRole) -> Result<Posts, AppError> {
+    let post = Posts::by_id(conn, post_id)
+        .map_err(DbError::query("Failed to load post"))?
+        .ok_or_else(|| PostError::not_found(post_id))?;
+
+    let Some(role) = PostRole::of(conn, &post, user_id)? else {
+        tracing::info!("User {} attempted to access post {} they have no part in", user_id, post_id);
+        return Err(PostError::not_found(post_id).into());
+    };
+
+    if role < needed {
+        let message = match needed {
+            PostRole::Owner => "Only the post's author can do this",
+            _ => "Viewers can't change this post",
+        };
+        return Err(PostError::not_permitted(message).into());
+    }
+    if needed == PostRole::Editor && role == PostRole::Editor && post.status != POST_STATUS_DRAFT {
+        return Err(PostError::not_permitted("Only the author can change a post once it's scheduled or published").into());
+    }
+
+    Ok(post)
+}
+
+/// Loads a post that the given user owns, for what only its author may do.
+pub fn load_owned_post(conn: &mut SqliteConnection, post_id: &str, user_id: &str) -> Result<Posts, AppError> {
+    load_post_as(conn, post_id, user_id, PostRole::Owner)
+}
+
+/// Loads a post that readers can interact with. Drafts and scheduled posts are reported as missing.
+pub fn load_published_post(conn: &mut SqliteConnection, post_id: &str) -> Result<Posts, AppError> {
+    let post = Posts::by_id(conn, post_id)
+        .map_err(DbError::query("Failed to load post"))?
+        .filter(Posts::is_public)
+        .ok_or_else(|| PostError::not_found(post_id))?;
+
+    Ok(post)
+}
+
+/// Checks that a cover image is one of the author's own uploads.
+pub fn resolve_cover_image(conn: &mut SqliteConnection, upload_id: &str, user_id: &str) -> Result<String, AppError> {
+    let upload = Uploads::by_user(conn, upload_id, user_id)
+        .map_err(DbError::query("Failed to load cover image"))?
+        .ok_or(PostError::InvalidCoverImage)?;
+
+    Ok(upload.id)
+}
+
+/// Checks `series_id` is one of the post author's series.
+pub fn resolve_series(conn: &mut SqliteConnection, series_id: &str, author_id: &str) -> Result<String, AppError> {
+    let series = Series::by_id(conn, series_id)
+        .map_err(DbError::query("Failed to load series"))?
+        .filter(|series| series.user_id == author_id)
+        .ok_or(PostError::InvalidSeries)?;
+
+    Ok(series.id)
+}
+
+pub fn load_reaction_counts(conn: &mut SqliteConnection, post_id: &str) -> Result<Vec<(String, i64)>, DbError> {
+    PostReactions::counts_for_post(conn, post_id).map_err(DbError::query("Failed to load post"))
+}
+
+pub fn load_view_count(conn: &mut SqliteConnection, post_id: &str) -> Result<i64, DbError> {
+    PostViews::total(conn, post_id).map_err(DbError::query("Failed to load post"))
+}
+
+/// Builds the responses for a page of posts, loading their tags, reactions and views.
+pub fn list_responses(conn: &mut SqliteConnection, posts: Vec<Posts>) -> Result<Vec<PostDto>, DbError> {
+    let post_ids: Vec<String> = posts.iter().map(|post| post.id.clone()).collect();
+    let views = PostViews::totals(conn, &post_ids).map_err(DbError::query("Failed to list posts"))?;
+    let mut reactions: HashMap<String, Vec<(String, i64)>> = HashMap::new();
+    for (post_id, kind, count) in PostReactions::counts_for_posts(conn, &post_ids).map_err(DbError::query("Failed to list posts"))? {
+        reactions.entry(post_id).or_default().push((kind, count));
+    }
+
+    let mut responses = Vec::with_capacity(posts.len());
+    for post in posts {
+        let tags = Tags::by_post(conn, &post.id).map_err(DbError::query("Failed to list posts"))?;
+        let counts = reactions.remove(&post.id).unwrap_or_default();
+        let post_views = views.get(&post.id).copied().unwrap_or_default();
+        responses.push(post_dto(post, tags).with_reactions(counts).with_views(post_views));
+    }
+    Ok(responses)
+}
+
+/// Slug collisions are the one write failure the author can fix.
+pub fn map_post_write_error(e: diesel::result::Error) -> AppError {
+    match e {
+        diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _) => PostError::SlugTaken.into(),
+        e => DbError::query("Failed to save post")(e).into(),
+    }
+}
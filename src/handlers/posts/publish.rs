@@ -0,0 +1,116 @@
+use axum::extract::{Path, State};
+
+use crate::db::models::onboarding_step::ONBOARDING_STEP_FIRST_POST;
+use crate::db::models::post::{Posts, POST_STATUS_DRAFT};
+use crate::db::models::post_fingerprint::PostFingerprints;
+use crate::db::models::tag::Tags;
+use crate::db::models::webhook::{WEBHOOK_EVENT_POST_PUBLISHED, WEBHOOK_EVENT_POST_UNPUBLISHED};
+use crate::errors::{AppError, DbError};
+use crate::handlers::posts::{
+    load_owned_post, load_reaction_counts, load_view_count, publication, record_fingerprint, PublishPostRequest,
+};
+use crate::http::auth::AuthUser;
+use crate::http::dto::{post_dto, ApiResponse, PostDto};
+use crate::http::json::AppJson;
+use crate::http::tx::Tx;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::services::cache;
+use crate::services::notifications;
+use crate::services::onboarding;
+use crate::services::webhooks;
+use crate::state::AppState;
+
+/// Publishes a post now, or schedules it when `publish_at` is in the future. Republishing an
+/// already published post without a new time leaves its original `published_at` alone.
+///
+/// The response lists existing posts this one nearly duplicates, as a warning for the author.
+pub async fn publish_post(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut conn: Tx,
+    Path(post_id): Path<String>,
+    payload: Option<AppJson<PublishPostRequest>>,
+) -> Result<ApiResponse<PostDto>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    let AppJson(payload) = payload.unwrap_or_default();
+
+    let post = load_owned_post(&mut conn, &post_id, &user.id)?;
+    let was_published = post.is_published();
+
+    let post = if was_published && payload.publish_at.is_none() {
+        post
+    } else {
+        let (status, published_at) = publication(payload.publish_at);
+        Posts::set_status(&mut conn, &post.id, status, Some(published_at))
+            .map_err(DbError::query("Failed to publish post"))?
+    };
+
+    let near_duplicates = record_fingerprint(&mut conn, &post)?;
+
+    if post.is_published()
+        && let Err(e) = notifications::record_published(&mut conn, &post)
+    {
+        tracing::warn!("Failed to notify followers about post {}: {}", post.id, e);
+    }
+    if !was_published && post.is_published() {
+        if let Err(e) = webhooks::emit(&mut conn, &user.id, WEBHOOK_EVENT_POST_PUBLISHED, webhooks::post_data(&post)) {
+            tracing::warn!("Failed to queue webhooks for post {}: {}", post.id, e);
+        }
+        if let Err(e) = onboarding::complete(&mut conn, &user.id, ONBOARDING_STEP_FIRST_POST) {
+            tracing::warn!("Failed to update onboarding for user {}: {}", user.id, e);
+        }
+    }
+
+    let tags = Tags::by_post(&mut conn, &post.id)
+        .map_err(DbError::query("Failed to load post"))?;
+
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    tracing::info!("User {} set post {} to {}", user.id, post.id, post.status);
+
+    let reactions = load_reaction_counts(&mut conn, &post.id)?;
+
+    let views = load_view_count(&mut conn, &post.id)?;
+
+    Ok(ApiResponse::new(post_dto(post, tags).with_reactions(reactions).with_views(views).with_near_duplicates(near_duplicates)))
+}
+
+/// Returns a published or scheduled post to draft.
+pub async fn unpublish_post(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut conn: Tx,
+    Path(post_id): Path<String>,
+) -> Result<ApiResponse<PostDto>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    let post = load_owned_post(&mut conn, &post_id, &user.id)?;
+    let was_published = post.is_published();
+
+    let post = Posts::set_status(&mut conn, &post.id, POST_STATUS_DRAFT, None)
+        .map_err(DbError::query("Failed to unpublish post"))?;
+
+    PostFingerprints::remove(&mut conn, &post.id)
+        .map_err(DbError::query("Failed to unpublish post"))?;
+
+    let tags = Tags::by_post(&mut conn, &post.id)
+        .map_err(DbError::query("Failed to load post"))?;
+
+    if was_published
+        && let Err(e) = webhooks::emit(&mut conn, &user.id, WEBHOOK_EVENT_POST_UNPUBLISHED, webhooks::post_data(&post))
+    {
+        tracing::warn!("Failed to queue webhooks for post {}: {}", post.id, e);
+    }
+
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    tracing::info!("User {} unpublished post {}", user.id, post.id);
+
+    let reactions = load_reaction_counts(&mut conn, &post.id)?;
+
+    let views = load_view_count(&mut conn, &post.id)?;
+
+    Ok(ApiResponse::new(post_dto(post, tags).with_reactions(reactions).with_views(views)))
+}
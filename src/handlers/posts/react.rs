@@ -0,0 +1,142 @@
+use axum::extract::{Path, Query, State};
+use tsumi_types::{ListReactedPostsResponse, ReactedPost, ReactionResponse};
+
+use crate::db::models::post_reaction::{PostReactions, REACTION_KINDS, REACTION_LIKE};
+use crate::db::models::webhook::WEBHOOK_EVENT_REACTION_CREATED;
+use crate::errors::{AppError, AuthError, DbError};
+use crate::handlers::pages::POSTS_PER_PAGE;
+use crate::handlers::posts::{load_published_post, load_reaction_counts, ReactPostRequest, ReactedPostsQuery};
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::api_tokens::{SCOPE_POSTS_READ, SCOPE_POSTS_WRITE};
+use crate::services::live::LiveEvent;
+use crate::services::notifications::{self, Event, KIND_REACTION};
+use crate::services::webhooks;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Reacts to a published post. Reacting again replaces the earlier reaction.
+pub async fn react_post(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+    payload: Option<AppJson<ReactPostRequest>>,
+) -> Result<ApiResponse<ReactionResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    let AppJson(payload) = payload.unwrap_or_default();
+
+    let kind = payload.reaction.unwrap_or_else(|| REACTION_LIKE.to_string());
+    if !REACTION_KINDS.contains(&kind.as_str()) {
+        return Err(AuthError::validation(format!(
+            "Unknown reaction, expected one of: {}",
+            REACTION_KINDS.join(", ")
+        )).into());
+    }
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = load_published_post(&mut conn, &post_id)?;
+
+    let event = Event {
+        kind: KIND_REACTION,
+        recipient_id: &post.user_id,
+        actor_id: &user.id,
+        actor_name: &user.name,
+        post_id: &post.id,
+    };
+    let reaction = state.writes.write(&mut conn, |conn| {
+        let reaction = PostReactions::upsert(conn, &PostReactions {
+            post_id: post.id.clone(),
+            user_id: user.id.clone(),
+            kind,
+            created_at: chrono::Utc::now().naive_utc(),
+        })?;
+        if let Err(e) = notifications::notify(conn, &state.config.load(), &event) {
+            tracing::warn!("Failed to queue reaction notification for post {}: {}", post.id, e);
+        }
+        let data = webhooks::reaction_data(&reaction, &user.name);
+        if let Err(e) = webhooks::emit(conn, &post.user_id, WEBHOOK_EVENT_REACTION_CREATED, data) {
+            tracing::warn!("Failed to queue webhooks for reaction to post {}: {}", post.id, e);
+        }
+        Ok(reaction)
+    })
+    .await
+    .map_err(DbError::query("Failed to save reaction"))?;
+    if post.user_id != user.id {
+        state.live.publish(&post.user_id, LiveEvent::NewReaction {
+            post_id: post.id.clone(),
+            reaction: reaction.kind.clone(),
+            user: user.name.clone(),
+        });
+    }
+
+    let counts = load_reaction_counts(&mut conn, &post.id)?;
+
+    Ok(ApiResponse::new(ReactionResponse::new(post.id, Some(reaction.kind), counts)))
+}
+
+pub async fn unreact_post(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+) -> Result<ApiResponse<ReactionResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let post = load_published_post(&mut conn, &post_id)?;
+
+    state.writes.write(&mut conn, |conn| PostReactions::remove(conn, &post.id, &user.id))
+        .await
+        .map_err(DbError::query("Failed to remove reaction"))?;
+
+    let counts = load_reaction_counts(&mut conn, &post.id)?;
+
+    Ok(ApiResponse::new(ReactionResponse::new(post.id, None, counts)))
+}
+
+/// Published posts the caller has reacted to, most recent first.
+pub async fn list_reacted_posts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<ReactedPostsQuery>,
+) -> Result<ApiResponse<ListReactedPostsResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_READ)?;
+    let user = auth.user;
+
+    let page = query.page.unwrap_or(1);
+    if page < 1 {
+        return Err(AuthError::validation("Page must be at least 1").into());
+    }
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let total = PostReactions::count_reacted_posts(&mut conn, &user.id)
+        .map_err(DbError::query("Failed to list reacted posts"))?;
+
+    let rows = PostReactions::reacted_posts(&mut conn, &user.id, (page - 1) * POSTS_PER_PAGE, POSTS_PER_PAGE)
+        .map_err(DbError::query("Failed to list reacted posts"))?;
+
+    let posts = rows
+        .into_iter()
+        .map(|(post, author, reaction, reacted_at)| ReactedPost {
+            id: post.id,
+            title: post.title,
+            description: post.description,
+            slug: post.slug,
+            author,
+            published_at: post.published_at,
+            reaction,
+            reacted_at,
+        })
+        .collect();
+
+    Ok(ApiResponse::new(ListReactedPostsResponse {
+        posts,
+        page,
+        total_pages: (total + POSTS_PER_PAGE - 1) / POSTS_PER_PAGE,
+    }))
+}
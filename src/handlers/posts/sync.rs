@@ -0,0 +1,190 @@
+use axum::extract::ws::{close_code, CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::response::Response;
+use tokio::sync::broadcast::error::RecvError;
+use validator::Validate;
+use yrs::sync::{Message, SyncMessage};
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::Update;
+
+use crate::db::models::post::Posts;
+use crate::db::models::post_doc::PostDocUpdates;
+use crate::db::models::post_version::PostVersions;
+use crate::db::models::tag::Tags;
+use crate::errors::{AppError, AuthError, DbError};
+use crate::handlers::posts::{
+    load_post_as, load_reaction_counts, load_view_count, map_post_write_error, CommitDocRequest, PostRole,
+};
+use crate::http::auth::AuthUser;
+use crate::http::dto::{post_dto, ApiResponse, PostDto};
+use crate::http::json::AppJson;
+use crate::http::tx::Tx;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::services::cache;
+use crate::services::collab::{self, Frame, Peer};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// `GET /ws/posts/{id}/sync`, a y-websocket compatible sync channel for the post's content.
+///
+/// Binds to the shared text named `content`. Every update an editor sends is stored before it
+/// is relayed to the others; awareness (cursors, presence) is relayed but never stored.
+pub async fn sync_post(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+    let post = load_post_as(&mut conn, &post_id, &user.id, PostRole::Editor)?;
+    drop(conn);
+
+    Ok(ws.on_upgrade(move |socket| run_sync(state, post, user.id, socket)))
+}
+
+async fn run_sync(state: AppState, post: Posts, user_id: String, mut socket: WebSocket) {
+    let mut peer = match state.collab.join(&state.db_pool, &post).await {
+        Ok(peer) => peer,
+        Err(e) => {
+            let _ = socket.send(close(close_code::ERROR, &e.to_string())).await;
+            return;
+        }
+    };
+    tracing::info!("User {} joined collaborative editing of post {}", user_id, post.id);
+
+    if socket.send(WsMessage::Binary(peer.room.sync_step1().into())).await.is_ok() {
+        loop {
+            tokio::select! {
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(WsMessage::Binary(data))) => match handle_message(&state, &peer, &user_id, &data).await {
+                        Ok(Some(reply)) => {
+                            if socket.send(WsMessage::Binary(reply.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            tracing::warn!("Dropping editor of post {}: {}", post.id, e);
+                            let _ = socket.send(close(close_code::ERROR, "Failed to apply update")).await;
+                            break;
+                        }
+                    },
+                    Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+                frame = peer.frames.recv() => match frame {
+                    Ok(Frame::Message { from, data }) => {
+                        if from != peer.id && socket.send(WsMessage::Binary(data.to_vec().into())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(Frame::Closed) => {
+                        let _ = socket.send(close(close_code::RESTART, "Document was replaced, reconnect")).await;
+                        break;
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Editor of post {} fell {} messages behind, disconnecting", post.id, skipped);
+                        let _ = socket.send(close(close_code::AGAIN, "Fell behind, reconnect")).await;
+                        break;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    }
+
+    state.collab.leave(&peer).await;
+    tracing::info!("User {} left collaborative editing of post {}", user_id, post.id);
+}
+
+fn close(code: u16, reason: &str) -> WsMessage {
+    WsMessage::Close(Some(CloseFrame { code, reason: reason.into() }))
+}
+
+/// Handles one message from an editor, returning the reply to send back, if any.
+async fn handle_message(state: &AppState, peer: &Peer, user_id: &str, data: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let message = Message::decode_v1(data).map_err(|e| e.to_string())?;
+    match message {
+        Message::Sync(SyncMessage::SyncStep1(state_vector)) => Ok(Some(peer.room.sync_step2(&state_vector))),
+        Message::Sync(SyncMessage::SyncStep2(data)) | Message::Sync(SyncMessage::Update(data)) => {
+            if collab::is_empty_update(&data) {
+                return Ok(None);
+            }
+            // Decoded up front so malformed updates are rejected before they're stored.
+            Update::decode_v1(&data).map_err(|e| e.to_string())?;
+
+            // Stored first: an update the room has seen but the database hasn't would never
+            // be resent, since the editor's next sync only asks for what the room lacks.
+            let record = PostDocUpdates {
+                id: uuid::Uuid::new_v4().to_string(),
+                post_id: peer.post_id.clone(),
+                user_id: Some(user_id.to_string()),
+                data: data.clone(),
+                created_at: chrono::Utc::now().naive_utc(),
+            };
+            let pool = state.db_pool.clone();
+            let _turn = state.writes.acquire().await;
+            tokio::task::spawn_blocking(move || {
+                let mut conn = pool.get().map_err(|e| e.to_string())?;
+                PostDocUpdates::record(&mut conn, &record).map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+
+            peer.room.apply(&data)?;
+            peer.room.relay(peer.id, Message::Sync(SyncMessage::Update(data)).encode_v1());
+            Ok(None)
+        }
+        Message::Awareness(_) => {
+            peer.room.relay(peer.id, data.to_vec());
+            Ok(None)
+        }
+        Message::Auth(_) | Message::AwarenessQuery | Message::Custom(..) => Ok(None),
+    }
+}
+
+/// `POST /posts/{id}/sync/commit`, folding the collaborative document into the post right away
+/// and recording a version, the collaborative counterpart of saving with a commit message.
+pub async fn commit_doc(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut tx: Tx,
+    Path(post_id): Path<String>,
+    payload: Option<AppJson<CommitDocRequest>>,
+) -> Result<ApiResponse<PostDto>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    let AppJson(payload) = payload.unwrap_or_default();
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid commit", err))?;
+
+    let post = load_post_as(&mut tx, &post_id, &user.id, PostRole::Editor)?;
+    let post = match collab::compact(&mut tx, &post.id).map_err(map_post_write_error)? {
+        Some(post) => {
+            if post.is_published() {
+                cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+            }
+            post
+        }
+        None => post,
+    };
+
+    let message = payload.message.unwrap_or_else(|| "Collaborative edit".to_string());
+    PostVersions::record(&mut tx, &post, &user.id, &message).map_err(map_post_write_error)?;
+
+    let tags = Tags::by_post(&mut tx, &post.id)
+        .map_err(DbError::query("Failed to load post"))?;
+
+    tracing::info!("User {} committed the collaborative document of post {}", user.id, post.id);
+
+    let reactions = load_reaction_counts(&mut tx, &post.id)?;
+
+    let views = load_view_count(&mut tx, &post.id)?;
+
+    Ok(ApiResponse::new(post_dto(post, tags).with_reactions(reactions).with_views(views)))
+}
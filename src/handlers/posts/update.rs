@@ -0,0 +1,142 @@
+use axum::extract::{Path, State};
+use http::HeaderMap;
+use validator::Validate;
+
+use crate::db::models::post::{PostChanges, Posts};
+use crate::db::models::post_doc::PostDocs;
+use crate::db::models::post_slug::PostSlugs;
+use crate::db::models::post_version::PostVersions;
+use crate::db::models::series::Series;
+use crate::db::models::tag::Tags;
+use crate::errors::{AppError, AuthError, PostError};
+use crate::handlers::posts::lock::check_edit_lock;
+use crate::handlers::posts::{
+    load_post_as, load_reaction_counts, load_view_count, map_post_write_error, normalize_tags, resolve_cover_image,
+    resolve_series, PostRole, UpdatePostRequest,
+};
+use crate::http::auth::AuthUser;
+use crate::http::dto::{post_dto, ApiResponse, PostDto};
+use crate::http::json::AppJson;
+use crate::http::tx::Tx;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::services::cache;
+use crate::services::post_metadata;
+use crate::services::slugs;
+use crate::state::AppState;
+use crate::utils::slugify;
+
+pub async fn update_post(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut tx: Tx,
+    Path(post_id): Path<String>,
+    headers: HeaderMap,
+    AppJson(payload): AppJson<UpdatePostRequest>,
+) -> Result<ApiResponse<PostDto>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    tracing::info!("Processing update of post {} for user: {}", post_id, user.id);
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid post data", err))?;
+
+    let tags = payload.tags.as_deref().map(normalize_tags).transpose()?;
+
+    let existing = load_post_as(&mut tx, &post_id, &user.id, PostRole::Editor)?;
+    check_edit_lock(&mut tx, &existing.id, &headers)?;
+
+    // A slug made from the title moves with it. The old slug keeps leading to the post.
+    let slug = match payload.slug {
+        Some(slug) if slugs::is_reserved(&slug) => return Err(PostError::SlugReserved.into()),
+        Some(slug) => Some(slug),
+        None => match payload.title.as_deref() {
+            Some(title) if slugs::follows_title(&existing.slug, &existing.title)
+                && !slugs::follows_title(&existing.slug, title)
+                && !slugify(title).is_empty() =>
+            {
+                Some(slugs::available(&mut tx, &existing.user_id, &slugify(title), Some(&existing.id)).map_err(map_post_write_error)?)
+            }
+            _ => None,
+        },
+    };
+
+    let cover_upload_id = match payload.cover_image_id.as_deref() {
+        None => None,
+        Some("") => Some(None),
+        Some(upload_id) => Some(Some(resolve_cover_image(&mut tx, upload_id, &user.id)?)),
+    };
+
+    let series_id = match payload.series_id.as_deref() {
+        None => None,
+        Some("") => Some(None),
+        Some(series_id) => Some(Some(resolve_series(&mut tx, series_id, &existing.user_id)?)),
+    };
+
+    let content = match payload.content {
+        Some(content) if user.canonicalize_links => Some(state.link_rules.canonicalize_markdown(&content)),
+        content => content,
+    };
+
+    let body_changed = content.as_ref().is_some_and(|content| *content != existing.content);
+    let content_changed = payload.title.as_ref().is_some_and(|title| *title != existing.title)
+        || payload.description.as_ref().is_some_and(|description| *description != existing.description)
+        || body_changed;
+
+    let word_count = content.as_deref().map(post_metadata::word_count);
+    let changes = PostChanges {
+        title: payload.title,
+        description: payload.description,
+        slug,
+        word_count,
+        reading_minutes: word_count.map(post_metadata::reading_minutes),
+        og_image_url: content.as_deref().map(post_metadata::og_image),
+        cover_upload_id,
+        content,
+        updated_at: Some(chrono::Utc::now().naive_utc()),
+    };
+    let commit_message = payload.commit_message.unwrap_or_else(|| "Update post".to_string());
+
+    let post = Posts::update(&mut tx, &existing.id, &changes).map_err(map_post_write_error)?;
+    if post.slug != existing.slug {
+        PostSlugs::release(&mut tx, &post.user_id, &post.slug).map_err(map_post_write_error)?;
+        PostSlugs::record(&mut tx, &post.user_id, &existing.slug, &post.id).map_err(map_post_write_error)?;
+    }
+    if content_changed {
+        PostVersions::record(&mut tx, &post, &user.id, &commit_message).map_err(map_post_write_error)?;
+        let terms = post_metadata::search_terms(&post.title, &post.description, &post.content);
+        Posts::index_search_terms(&mut tx, &post.id, &terms).map_err(map_post_write_error)?;
+    }
+    // Replacing the body outside the collaborative editor throws its document away; the next
+    // editor to connect starts over from the new content.
+    if body_changed {
+        PostDocs::reset(&mut tx, &post.id).map_err(map_post_write_error)?;
+        state.collab.close(&post.id).await;
+    }
+    match &series_id {
+        Some(Some(series_id)) => {
+            let current = Series::of_post(&mut tx, &post.id).map_err(map_post_write_error)?;
+            if current.is_none_or(|current| current.id != *series_id) {
+                Series::add_post(&mut tx, series_id, &post.id).map_err(map_post_write_error)?;
+            }
+        }
+        Some(None) => {
+            Series::remove_post(&mut tx, &post.id).map_err(map_post_write_error)?;
+        }
+        None => {}
+    }
+    let tags = match &tags {
+        Some(tags) => Tags::set_for_post(&mut tx, &post.id, tags),
+        None => Tags::by_post(&mut tx, &post.id),
+    }
+    .map_err(map_post_write_error)?;
+
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    tracing::info!("User {} updated post {}", user.id, post.id);
+
+    let reactions = load_reaction_counts(&mut tx, &post.id)?;
+
+    let views = load_view_count(&mut tx, &post.id)?;
+
+    Ok(ApiResponse::new(post_dto(post, tags).with_reactions(reactions).with_views(views)))
+}
@@ -0,0 +1,104 @@
+use axum::response::Response;
+use chrono::NaiveDateTime;
+use http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL};
+use http::{HeaderValue, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::post::Posts;
+use crate::db::models::tag::Tags;
+use crate::handlers::uploads::media_path;
+use crate::http::pagination::Sortable;
+use crate::services::markdown;
+
+pub mod posts;
+pub mod users;
+
+pub use tsumi_types::PublicProfileResponse;
+
+/// Public API responses are readable from any origin and may be cached briefly, since
+/// nothing in them depends on who's asking.
+pub async fn public_headers(mut response: Response) -> Response {
+    let cacheable = response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED;
+    let headers = response.headers_mut();
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    if cacheable {
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("public, max-age=60"));
+    }
+    response
+}
+
+/// Sort keys for the public post list.
+pub struct PublicPostSort;
+
+impl Sortable for PublicPostSort {
+    const SORT_FIELDS: &'static [&'static str] = &["published_at"];
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ListPublicPostsQuery {
+    /// Only posts by this author, by name.
+    pub author: Option<String>,
+    pub tag: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct PopularPostsQuery {
+    /// How far back to count views, in days, like `7d`. A week when left out.
+    pub window: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// A published post as third parties see it. URLs are absolute so the post can be rendered
+/// off-site.
+#[derive(Debug, Serialize)]
+pub struct PublicPostResponse {
+    pub id: String,
+    pub author: String,
+    pub title: String,
+    pub description: String,
+    pub slug: String,
+    pub url: String,
+    pub content: String,
+    pub content_html: String,
+    pub tags: Vec<String>,
+    pub cover_image_url: Option<String>,
+    /// Reads of the post's page by people, not bots.
+    pub views: i64,
+    pub published_at: Option<NaiveDateTime>,
+    pub updated_at: NaiveDateTime,
+}
+
+impl PublicPostResponse {
+    pub fn new(post: Posts, author: String, tags: Vec<Tags>, base_url: &str) -> Self {
+        Self {
+            url: format!("{}/{}/{}", base_url, author, post.slug),
+            content_html: markdown::render(&post.content),
+            cover_image_url: post.cover_upload_id.as_deref().map(|id| format!("{}{}", base_url, media_path(id))),
+            id: post.id,
+            author,
+            title: post.title,
+            description: post.description,
+            slug: post.slug,
+            content: post.content,
+            tags: tags.into_iter().map(|tag| tag.name).collect(),
+            views: 0,
+            published_at: post.published_at,
+            updated_at: post.updated_at,
+        }
+    }
+
+    pub fn with_views(mut self, views: i64) -> Self {
+        self.views = views;
+        self
+    }
+}
+
+/// A post on the popular list, with the views that put it there.
+#[derive(Debug, Serialize)]
+pub struct PopularPostResponse {
+    #[serde(flatten)]
+    pub post: PublicPostResponse,
+    /// Views within the requested window; `views` is still every view the post has had.
+    pub window_views: i64,
+}
+
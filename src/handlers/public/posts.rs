@@ -0,0 +1,186 @@
+use axum::extract::{Path, Query, State};
+use diesel::SqliteConnection;
+
+use crate::db::models::post::Posts;
+use crate::db::models::post_view::PostViews;
+use crate::db::models::tag::Tags;
+use crate::db::models::user_model::UserModel;
+use crate::db::queries::posts::PublicPostFilter;
+use crate::errors::{AppError, AuthError};
+use crate::handlers::posts::load_published_post;
+use crate::handlers::public::{
+    ListPublicPostsQuery, PopularPostResponse, PopularPostsQuery, PublicPostResponse, PublicPostSort,
+};
+use crate::http::dto::ApiResponse;
+use crate::http::pagination::{ListParams, Paginated, MAX_PER_PAGE};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// The window `popular` counts views over when none is given.
+const DEFAULT_POPULAR_WINDOW_DAYS: i64 = 7;
+
+/// Views are kept per day, so windows are whole days, up to a quarter.
+const MAX_POPULAR_WINDOW_DAYS: i64 = 90;
+
+const DEFAULT_POPULAR_LIMIT: i64 = 10;
+
+fn load_tags(conn: &mut SqliteConnection, post_id: &str) -> Result<Vec<Tags>, AuthError> {
+    Tags::by_post(conn, post_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load tags for post {}: {}", post_id, e);
+            AuthError::database("Failed to load post")
+        })
+}
+
+/// Published posts from every active author, newest first unless `dir=asc`. Narrow with
+/// `author` (a name) and `tag`.
+pub async fn list_public_posts(
+    State(state): State<AppState>,
+    params: ListParams<PublicPostSort>,
+    Query(query): Query<ListPublicPostsQuery>,
+) -> Result<ApiResponse<Paginated<PublicPostResponse>>, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing public posts: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let author = match query.author.as_deref().map(str::trim).filter(|author| !author.is_empty()) {
+        Some(name) => Some(
+            UserModel::by_name(&mut conn, name)
+                .map_err(|e| {
+                    tracing::error!("Failed to load user {}: {}", name, e);
+                    AuthError::database("Failed to list posts")
+                })?
+                .ok_or_else(|| AuthError::not_found(name))?,
+        ),
+        None => None,
+    };
+    let tag = query.tag.as_deref().map(|tag| tag.trim().to_lowercase()).filter(|tag| !tag.is_empty());
+    let filter = PublicPostFilter {
+        author_id: author.as_ref().map(|author| author.id.as_str()),
+        tag: tag.as_deref(),
+    };
+
+    let total = Posts::count_public(&mut conn, &filter)
+        .map_err(|e| {
+            tracing::error!("Failed to count public posts: {}", e);
+            AuthError::database("Failed to list posts")
+        })?;
+
+    let posts = Posts::public_page(&mut conn, &filter, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to list public posts: {}", e);
+            AuthError::database("Failed to list posts")
+        })?;
+
+    let post_ids: Vec<String> = posts.iter().map(|(post, _)| post.id.clone()).collect();
+    let views = PostViews::totals(&mut conn, &post_ids)
+        .map_err(|e| {
+            tracing::error!("Failed to load views for public posts: {}", e);
+            AuthError::database("Failed to list posts")
+        })?;
+
+    let config = state.config.load();
+    let base_url = config.canonical_url();
+    let mut responses = Vec::with_capacity(posts.len());
+    for (post, author) in posts {
+        let tags = load_tags(&mut conn, &post.id)?;
+        let post_views = views.get(&post.id).copied().unwrap_or_default();
+        responses.push(PublicPostResponse::new(post, author, tags, base_url).with_views(post_views));
+    }
+
+    Ok(ApiResponse::new(params.paginate(responses, total)))
+}
+
+/// The most viewed published posts over the last `window` days (a week by default), most
+/// viewed first.
+pub async fn list_popular_posts(
+    State(state): State<AppState>,
+    Query(query): Query<PopularPostsQuery>,
+) -> Result<ApiResponse<Vec<PopularPostResponse>>, AuthError> {
+    let days = match query.window.as_deref() {
+        None | Some("") => DEFAULT_POPULAR_WINDOW_DAYS,
+        Some(window) => parse_window(window).ok_or_else(|| {
+            AuthError::invalid_field(
+                "window",
+                "invalid_window",
+                format!("Window must be a number of days from 1d to {}d", MAX_POPULAR_WINDOW_DAYS),
+            )
+        })?,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_POPULAR_LIMIT);
+    if !(1..=MAX_PER_PAGE).contains(&limit) {
+        return Err(AuthError::validation(format!("limit must be between 1 and {}", MAX_PER_PAGE)));
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing popular posts: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    // Today counts as the window's first day.
+    let since = chrono::Utc::now().date_naive() - chrono::Days::new(days as u64 - 1);
+    let posts = PostViews::popular(&mut conn, since, limit)
+        .map_err(|e| {
+            tracing::error!("Failed to list popular posts: {}", e);
+            AuthError::database("Failed to list posts")
+        })?;
+
+    let post_ids: Vec<String> = posts.iter().map(|(post, _, _)| post.id.clone()).collect();
+    let views = PostViews::totals(&mut conn, &post_ids)
+        .map_err(|e| {
+            tracing::error!("Failed to load views for popular posts: {}", e);
+            AuthError::database("Failed to list posts")
+        })?;
+
+    let config = state.config.load();
+    let base_url = config.canonical_url();
+    let mut responses = Vec::with_capacity(posts.len());
+    for (post, author, window_views) in posts {
+        let tags = load_tags(&mut conn, &post.id)?;
+        let post_views = views.get(&post.id).copied().unwrap_or_default();
+        let post = PublicPostResponse::new(post, author, tags, base_url).with_views(post_views);
+        responses.push(PopularPostResponse { post, window_views });
+    }
+
+    Ok(ApiResponse::new(responses))
+}
+
+/// Days in a window like `7d`.
+fn parse_window(window: &str) -> Option<i64> {
+    let days = window.strip_suffix('d')?.parse::<i64>().ok()?;
+    (1..=MAX_POPULAR_WINDOW_DAYS).contains(&days).then_some(days)
+}
+
+/// One published post. Drafts, scheduled posts and posts by deleted authors are missing.
+pub async fn get_public_post(
+    State(state): State<AppState>,
+    Path(post_id): Path<String>,
+) -> Result<ApiResponse<PublicPostResponse>, AppError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading public post: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let post = load_published_post(&mut conn, &post_id)?;
+    let author = UserModel::by_id(&mut conn, &post.user_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load author of post {}: {}", post.id, e);
+            AuthError::database("Failed to load post")
+        })?
+        .ok_or_else(|| AuthError::not_found(&post_id))?;
+
+    let tags = load_tags(&mut conn, &post.id)?;
+    let views = PostViews::total(&mut conn, &post.id)
+        .map_err(|e| {
+            tracing::error!("Failed to load views for post {}: {}", post.id, e);
+            AuthError::database("Failed to load post")
+        })?;
+
+    Ok(ApiResponse::new(
+        PublicPostResponse::new(post, author.name, tags, state.config.load().canonical_url()).with_views(views),
+    ))
+}
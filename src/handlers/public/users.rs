@@ -0,0 +1,83 @@
+use axum::extract::{Path, State};
+
+use crate::db::models::follow::Follows;
+use crate::db::models::post::Posts;
+use crate::db::models::user_model::UserModel;
+use crate::db::queries::posts::PublicPostFilter;
+use crate::errors::AuthError;
+use crate::handlers::public::PublicProfileResponse;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::services::avatars;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// An active author's public profile, as signed-out readers see it.
+pub async fn get_public_profile(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+) -> Result<ApiResponse<PublicProfileResponse>, AuthError> {
+    load_profile(&state, &username, None).map(ApiResponse::new)
+}
+
+/// `GET /api/v1/users/{username}`, a user's profile as the caller may see it: private
+/// profiles only to signed-in users, and the email only where its owner allows.
+pub async fn get_user_profile(
+    State(state): State<AppState>,
+    auth: Option<AuthUser>,
+    Path(username): Path<String>,
+) -> Result<ApiResponse<PublicProfileResponse>, AuthError> {
+    let viewer = auth.map(|auth| auth.user);
+    load_profile(&state, &username, viewer.as_ref()).map(ApiResponse::new)
+}
+
+fn load_profile(state: &AppState, username: &str, viewer: Option<&UserModel>) -> Result<PublicProfileResponse, AuthError> {
+    let mut conn = get_db_conn(state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading public profile: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    // Private profiles look like missing ones, so they don't give away that the name is taken.
+    let user = UserModel::by_name(&mut conn, username)
+        .map_err(|e| {
+            tracing::error!("Failed to load user {}: {}", username, e);
+            AuthError::database("Failed to load profile")
+        })?
+        .filter(|user| user.profile_visible_to(viewer))
+        .ok_or_else(|| AuthError::not_found(username))?;
+
+    let filter = PublicPostFilter { author_id: Some(&user.id), ..PublicPostFilter::default() };
+    let counts = Posts::count_public(&mut conn, &filter).and_then(|post_count| {
+        Ok((
+            post_count,
+            Follows::count_followers(&mut conn, &user.id)?,
+            Follows::count_following(&mut conn, &user.id)?,
+        ))
+    });
+    let (post_count, followers, following) = counts
+        .map_err(|e| {
+            tracing::error!("Failed to count profile stats for user {}: {}", user.id, e);
+            AuthError::database("Failed to load profile")
+        })?;
+
+    // The profile is still useful without an avatar.
+    let avatar_url = avatars::avatar_path(&mut conn, &user)
+        .inspect_err(|e| tracing::error!("Failed to look up avatar for {}: {}", user.id, e))
+        .ok()
+        .map(|path| format!("{}{}", state.config.load().canonical_url(), path));
+
+    Ok(PublicProfileResponse {
+        url: format!("{}/{}", state.config.load().canonical_url(), user.name),
+        avatar_url,
+        email: user.email_visible_to(viewer).then(|| user.email.clone()),
+        name: user.name,
+        bio: user.bio,
+        website: user.website,
+        location: user.location,
+        joined_at: user.created_at,
+        post_count,
+        followers,
+        following,
+    })
+}
@@ -0,0 +1,125 @@
+use axum::extract::{Path, State};
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::SqliteConnection;
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::db::models::report::{Reports, REPORT_REASONS, REPORT_STATUS_OPEN};
+use crate::errors::{AppError, AuthError};
+use crate::handlers::comments::load_comment;
+use crate::handlers::posts::load_published_post;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::api_tokens::SCOPE_POSTS_WRITE;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct CreateReportRequest {
+    /// One of [`REPORT_REASONS`].
+    pub reason: String,
+
+    #[validate(length(max = 2000, message = "Details must be at most 2000 characters"))]
+    #[serde(default)]
+    pub details: String,
+}
+
+/// Flags a published post for moderators to review.
+pub async fn report_post(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(post_id): Path<String>,
+    AppJson(payload): AppJson<CreateReportRequest>,
+) -> Result<ApiResponse<Reports>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let mut conn = db_conn(&state)?;
+
+    let post = load_published_post(&mut conn, &post_id)?;
+    if post.user_id == auth.user.id {
+        return Err(AuthError::validation("You can't report your own post").into());
+    }
+
+    file_report(&state, &mut conn, &auth, post.id, None, payload).await
+}
+
+/// Flags a comment on a published post for moderators to review.
+pub async fn report_comment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(comment_id): Path<String>,
+    AppJson(payload): AppJson<CreateReportRequest>,
+) -> Result<ApiResponse<Reports>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let mut conn = db_conn(&state)?;
+
+    let comment = load_comment(&mut conn, &comment_id)?;
+    if comment.is_deleted() || comment.is_hidden() {
+        return Err(AuthError::not_found(comment_id).into());
+    }
+    load_published_post(&mut conn, &comment.post_id)?;
+    if comment.user_id == auth.user.id {
+        return Err(AuthError::validation("You can't report your own comment").into());
+    }
+
+    file_report(&state, &mut conn, &auth, comment.post_id, Some(comment.id), payload).await
+}
+
+fn db_conn(state: &AppState) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, AuthError> {
+    get_db_conn(state).map_err(|e| {
+        tracing::error!("Failed to get database connection while reporting: {}", e);
+        AuthError::internal("Database connection failed")
+    })
+}
+
+/// Records the report unless the user already has one waiting on the same post or comment.
+async fn file_report(
+    state: &AppState,
+    conn: &mut SqliteConnection,
+    auth: &AuthUser,
+    post_id: String,
+    comment_id: Option<String>,
+    payload: CreateReportRequest,
+) -> Result<ApiResponse<Reports>, AppError> {
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid report", err))?;
+    if !REPORT_REASONS.contains(&payload.reason.as_str()) {
+        return Err(AuthError::invalid_field(
+            "reason",
+            "invalid",
+            format!("Reason must be one of {}", REPORT_REASONS.join(", ")),
+        ).into());
+    }
+
+    let reporter_id = &auth.user.id;
+    let already = Reports::open_exists(conn, reporter_id, &post_id, comment_id.as_deref())
+        .map_err(|e| {
+            tracing::error!("Failed to look up reports by user {}: {}", reporter_id, e);
+            AuthError::database("Failed to file report")
+        })?;
+    if already {
+        return Err(AuthError::conflict("You've already reported this").into());
+    }
+
+    let report = state.writes.write(conn, |conn| Reports::create(conn, &Reports {
+        id: uuid::Uuid::new_v4().to_string(),
+        reporter_id: Some(reporter_id.clone()),
+        post_id,
+        comment_id,
+        reason: payload.reason,
+        details: payload.details.trim().to_string(),
+        status: REPORT_STATUS_OPEN.to_string(),
+        reviewed_by: None,
+        reviewed_at: None,
+        created_at: chrono::Utc::now().naive_utc(),
+    }))
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to file report by user {}: {}", reporter_id, e);
+        AuthError::database("Failed to file report")
+    })?;
+
+    tracing::info!("User {} filed report {}", reporter_id, report.id);
+
+    Ok(ApiResponse::new(report))
+}
@@ -0,0 +1,224 @@
+use axum::extract::{Path, State};
+use diesel::SqliteConnection;
+use tsumi_types::{
+    CreateSeriesRequest, DeleteSeriesResponse, ListSeriesResponse, SeriesPostResponse, SeriesResponse,
+    SetSeriesPostsRequest, UpdateSeriesRequest,
+};
+use validator::Validate;
+
+use crate::db::models::post::Posts;
+use crate::db::models::series::{Series, SeriesChanges};
+use crate::errors::{AppError, AuthError, DbError, PostError};
+use crate::http::auth::AuthUser;
+use crate::http::dto::{series_nav, ApiResponse, SeriesNav};
+use crate::http::json::AppJson;
+use crate::http::tx::Tx;
+use crate::services::api_tokens::{SCOPE_POSTS_READ, SCOPE_POSTS_WRITE};
+use crate::services::cache;
+use crate::state::AppState;
+use crate::utils::{get_db_conn, slugify};
+
+fn load_series_response(conn: &mut SqliteConnection, series: Series, drafts: bool) -> Result<SeriesResponse, DbError> {
+    let posts = Series::posts(conn, &series.id)
+        .map_err(DbError::query("Failed to load series"))?
+        .into_iter()
+        .filter(|post| drafts || post.is_public())
+        .map(|post| SeriesPostResponse { id: post.id, title: post.title, slug: post.slug, status: post.status })
+        .collect();
+    Ok(SeriesResponse {
+        id: series.id,
+        author_id: series.user_id,
+        title: series.title,
+        slug: series.slug,
+        description: series.description,
+        posts,
+        created_at: series.created_at,
+        updated_at: series.updated_at,
+    })
+}
+
+/// Where `post` sits in its series, for the post's response and page.
+pub fn load_series_nav(conn: &mut SqliteConnection, post: &Posts) -> Result<Option<SeriesNav>, DbError> {
+    let Some(series) = Series::of_post(conn, &post.id).map_err(DbError::query("Failed to load series"))? else {
+        return Ok(None);
+    };
+    let posts = Series::posts(conn, &series.id).map_err(DbError::query("Failed to load series"))?;
+    Ok(series_nav(series, &posts, &post.id))
+}
+
+/// Loads a series the given user owns. Series owned by other users are reported as missing.
+fn load_owned_series(conn: &mut SqliteConnection, series_id: &str, user_id: &str) -> Result<Series, AppError> {
+    let series = Series::by_id(conn, series_id)
+        .map_err(DbError::query("Failed to load series"))?
+        .filter(|series| series.user_id == user_id)
+        .ok_or_else(|| PostError::series_not_found(series_id))?;
+
+    Ok(series)
+}
+
+fn map_series_write_error(e: diesel::result::Error) -> AppError {
+    match e {
+        diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _) => PostError::SeriesSlugTaken.into(),
+        e => DbError::query("Failed to save series")(e).into(),
+    }
+}
+
+/// `GET /series`, the caller's series, newest first.
+pub async fn list_series(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<ApiResponse<ListSeriesResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_READ)?;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let series = Series::by_user(&mut conn, &auth.user.id)
+        .map_err(DbError::query("Failed to list series"))?
+        .into_iter()
+        .map(|series| load_series_response(&mut conn, series, true))
+        .collect::<Result<_, _>>()?;
+
+    Ok(ApiResponse::new(ListSeriesResponse { series }))
+}
+
+/// `GET /series/{id}`. Anyone can read a series; only its author sees its unpublished parts.
+pub async fn get_series(
+    State(state): State<AppState>,
+    auth: Option<AuthUser>,
+    Path(series_id): Path<String>,
+) -> Result<ApiResponse<SeriesResponse>, AppError> {
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let series = Series::by_id(&mut conn, &series_id)
+        .map_err(DbError::query("Failed to load series"))?
+        .ok_or_else(|| PostError::series_not_found(&series_id))?;
+
+    let is_author = auth.as_ref().is_some_and(|auth| {
+        auth.user.id == series.user_id && auth.require_scope(SCOPE_POSTS_READ).is_ok()
+    });
+
+    Ok(ApiResponse::new(load_series_response(&mut conn, series, is_author)?))
+}
+
+pub async fn create_series(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    AppJson(payload): AppJson<CreateSeriesRequest>,
+) -> Result<ApiResponse<SeriesResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid series", err))?;
+
+    let slug = payload.slug.unwrap_or_else(|| slugify(&payload.title));
+    if slug.is_empty() {
+        return Err(PostError::SlugRequired.into());
+    }
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let now = chrono::Utc::now().naive_utc();
+    let series = state.writes.write(&mut conn, |conn| Series::create(conn, &Series {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user.id.clone(),
+        title: payload.title,
+        slug,
+        description: payload.description,
+        created_at: now,
+        updated_at: now,
+    }))
+    .await
+    .map_err(map_series_write_error)?;
+
+    tracing::info!("User {} created series {}", user.id, series.id);
+
+    Ok(ApiResponse::new(load_series_response(&mut conn, series, true)?))
+}
+
+pub async fn update_series(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(series_id): Path<String>,
+    AppJson(payload): AppJson<UpdateSeriesRequest>,
+) -> Result<ApiResponse<SeriesResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid series", err))?;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let series = load_owned_series(&mut conn, &series_id, &user.id)?;
+    let series = state.writes.write(&mut conn, |conn| Series::update(conn, &series.id, &SeriesChanges {
+        title: payload.title,
+        slug: payload.slug,
+        description: payload.description,
+        updated_at: Some(chrono::Utc::now().naive_utc()),
+    }))
+    .await
+    .map_err(map_series_write_error)?;
+
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    Ok(ApiResponse::new(load_series_response(&mut conn, series, true)?))
+}
+
+/// `PUT /series/{id}/posts`, setting the series' posts and their order. Posts left out leave
+/// the series; posts from another of the author's series move to this one.
+pub async fn set_series_posts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut tx: Tx,
+    Path(series_id): Path<String>,
+    AppJson(payload): AppJson<SetSeriesPostsRequest>,
+) -> Result<ApiResponse<SeriesResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid series", err))?;
+
+    let series = load_owned_series(&mut tx, &series_id, &user.id)?;
+
+    for (index, post_id) in payload.post_ids.iter().enumerate() {
+        if payload.post_ids[..index].contains(post_id) {
+            return Err(AuthError::invalid_field("post_ids", "duplicate", "A post can only appear once in a series").into());
+        }
+        Posts::by_id(&mut tx, post_id)
+            .map_err(DbError::query("Failed to load post"))?
+            .filter(|post| post.user_id == user.id)
+            .ok_or_else(|| PostError::not_found(post_id))?;
+    }
+
+    Series::set_posts(&mut tx, &series.id, &payload.post_ids)
+        .map_err(DbError::query("Failed to save series"))?;
+
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    Ok(ApiResponse::new(load_series_response(&mut tx, series, true)?))
+}
+
+/// Deletes the series. Its posts are kept.
+pub async fn delete_series(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(series_id): Path<String>,
+) -> Result<ApiResponse<DeleteSeriesResponse>, AppError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state).map_err(DbError::connection)?;
+
+    let series = load_owned_series(&mut conn, &series_id, &user.id)?;
+    state.writes.write(&mut conn, |conn| Series::delete(conn, &series.id))
+        .await
+        .map_err(DbError::query("Failed to delete series"))?;
+
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    tracing::info!("User {} deleted series {}", user.id, series.id);
+
+    Ok(ApiResponse::new(DeleteSeriesResponse { message: "Series deleted".to_string() }))
+}
@@ -0,0 +1,62 @@
+use axum::extract::{Path, State};
+use axum::response::{IntoResponse, Response};
+use http::header::{CONTENT_TYPE, ETAG};
+use http::{HeaderMap, StatusCode};
+
+use crate::errors::AuthError;
+use crate::http::conditional::etag_matches;
+use crate::services::sitemap::{SitemapFile, SitemapFiles};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// `GET /sitemap.xml`. Small sites get the URL set directly; once there are more URLs than fit
+/// in one file this becomes a sitemap index over `/sitemaps/{n}.xml`.
+pub async fn sitemap_xml(State(state): State<AppState>, headers: HeaderMap) -> Result<Response, AuthError> {
+    let files = load_files(&state)?;
+    Ok(xml_response(&headers, &files.root))
+}
+
+/// `GET /sitemaps/{n}.xml`, one chunk of a sitemap index, numbered from 1.
+pub async fn sitemap_chunk(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(file): Path<String>,
+) -> Result<Response, AuthError> {
+    let index = file
+        .strip_suffix(".xml")
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|n| *n >= 1)
+        .ok_or_else(|| AuthError::not_found(&file))?;
+
+    let files = load_files(&state)?;
+    // Small sites have no chunks, but `/sitemaps/1.xml` still answers with the whole set.
+    let chunk = match files.chunks.get(index - 1) {
+        Some(chunk) => chunk,
+        None if index == 1 && files.chunks.is_empty() => &files.root,
+        None => return Err(AuthError::not_found(file)),
+    };
+
+    Ok(xml_response(&headers, chunk))
+}
+
+fn load_files(state: &AppState) -> Result<std::sync::Arc<SitemapFiles>, AuthError> {
+    let mut conn = get_db_conn(state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection for sitemap: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    state.sitemaps.current(&mut conn)
+        .map_err(|e| {
+            tracing::error!("Failed to build sitemap: {}", e);
+            AuthError::database("Failed to build sitemap")
+        })
+}
+
+/// The file, or a bare 304 when the client already has this version of it.
+fn xml_response(headers: &HeaderMap, file: &SitemapFile) -> Response {
+    if etag_matches(headers, &file.etag) {
+        return (StatusCode::NOT_MODIFIED, [(ETAG, file.etag.clone())]).into_response();
+    }
+    ([(CONTENT_TYPE, "application/xml; charset=utf-8".to_string()), (ETAG, file.etag.clone())], file.xml.clone()).into_response()
+}
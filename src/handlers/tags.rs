@@ -0,0 +1,58 @@
+use axum::extract::{Query, State};
+use serde::Deserialize;
+use tsumi_types::TagResponse;
+
+use crate::db::models::tag::Tags;
+use crate::errors::AuthError;
+use crate::http::dto::ApiResponse;
+use crate::http::pagination::{ListParams, Paginated, SortDir, Sortable};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Sort keys for the tag list.
+pub struct TagSort;
+
+impl Sortable for TagSort {
+    const SORT_FIELDS: &'static [&'static str] = &["name", "posts"];
+    const DEFAULT_DIR: SortDir = SortDir::Asc;
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ListTagsQuery {
+    /// Only tags whose name starts with this.
+    pub q: Option<String>,
+}
+
+/// Tags in use on published posts. Sort by `name` (the default) or `posts`.
+pub async fn list_tags(
+    State(state): State<AppState>,
+    params: ListParams<TagSort>,
+    Query(query): Query<ListTagsQuery>,
+) -> Result<ApiResponse<Paginated<TagResponse>>, AuthError> {
+    let prefix = query.q.as_deref().map(|q| q.trim().to_lowercase()).filter(|q| !q.is_empty());
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while listing tags: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let total = Tags::count_in_use(&mut conn, prefix.as_deref())
+        .map_err(|e| {
+            tracing::error!("Failed to count tags: {}", e);
+            AuthError::database("Failed to list tags")
+        })?;
+
+    let tags = Tags::page_in_use(&mut conn, prefix.as_deref(), params.sort, params.dir, params.offset(), params.limit())
+        .map_err(|e| {
+            tracing::error!("Failed to list tags: {}", e);
+            AuthError::database("Failed to list tags")
+        })?;
+
+    let tags = tags
+        .into_iter()
+        .map(|(tag, post_count)| TagResponse { name: tag.name, post_count })
+        .collect();
+
+    Ok(ApiResponse::new(params.paginate(tags, total)))
+}
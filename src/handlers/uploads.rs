@@ -0,0 +1,287 @@
+use axum::extract::{Multipart, Path, State};
+use axum::response::{IntoResponse, Response};
+use http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_NONE_MATCH, X_CONTENT_TYPE_OPTIONS};
+use http::{HeaderMap, HeaderValue, StatusCode};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tsumi_types::UploadResponse;
+use validator::Validate;
+
+use crate::db::models::upload::{Uploads, ALT_TEXT_PENDING};
+use crate::errors::AuthError;
+use crate::http::auth::AuthUser;
+use crate::http::dto::ApiResponse;
+use crate::http::json::AppJson;
+use crate::services::api_tokens::{SCOPE_POSTS_READ, SCOPE_POSTS_WRITE};
+use crate::services::cache;
+use crate::services::images::{sniff, IMAGE_TYPES};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Media ids never change what they point at, so clients may cache them forever.
+const MEDIA_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+#[derive(Validate, Deserialize, Debug)]
+pub struct UpdateAltTextRequest {
+    /// An empty string or `null` clears the alt text, marking the image as decorative.
+    #[validate(length(max = 1000, message = "Alt text must be at most 1000 characters"))]
+    pub alt_text: Option<String>,
+}
+
+impl From<Uploads> for UploadResponse {
+    fn from(upload: Uploads) -> Self {
+        Self {
+            url: media_path(&upload.id),
+            id: upload.id,
+            user_id: upload.user_id,
+            content_type: upload.content_type,
+            byte_size: upload.byte_size.into(),
+            sha256: upload.sha256,
+            original_filename: upload.original_filename,
+            created_at: upload.created_at,
+            alt_text: upload.alt_text,
+            alt_text_suggestion: upload.alt_text_suggestion,
+            alt_text_status: upload.alt_text_status,
+        }
+    }
+}
+
+pub fn media_path(id: &str) -> String {
+    format!("/media/{}", id)
+}
+
+/// `POST /api/v1/uploads`, a multipart form with the image in a `file` field.
+pub async fn create_upload(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    mut multipart: Multipart,
+) -> Result<ApiResponse<UploadResponse>, AuthError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+    let max_bytes = state.config.load().upload_max_bytes();
+
+    let field = loop {
+        let field = multipart.next_field().await
+            .map_err(|e| match e.status() {
+                StatusCode::PAYLOAD_TOO_LARGE => {
+                    AuthError::payload_too_large(format!("Uploads are limited to {} bytes", max_bytes))
+                }
+                _ => AuthError::validation(format!("Invalid multipart body: {}", e.body_text())),
+            })?
+            .ok_or_else(|| AuthError::validation("Missing `file` field"))?;
+
+        if field.name() == Some("file") {
+            break field;
+        }
+    };
+
+    let declared = field.content_type().unwrap_or_default().to_string();
+    let original_filename = field.file_name().map(str::to_string);
+    let bytes = field.bytes().await
+        .map_err(|e| match e.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => {
+                AuthError::payload_too_large(format!("Uploads are limited to {} bytes", max_bytes))
+            }
+            _ => AuthError::validation(format!("Failed to read upload: {}", e.body_text())),
+        })?;
+
+    if bytes.is_empty() {
+        return Err(AuthError::validation("Uploaded file is empty"));
+    }
+    if bytes.len() > max_bytes {
+        return Err(AuthError::payload_too_large(format!("Uploads are limited to {} bytes", max_bytes)));
+    }
+
+    let (content_type, extension) = sniff(&declared, &bytes).ok_or_else(|| {
+        let accepted: Vec<&str> = IMAGE_TYPES.iter().map(|(content_type, _, _)| *content_type).collect();
+        AuthError::unsupported_media_type(format!("Accepted image types are {}", accepted.join(", ")))
+    })?;
+
+    let now = chrono::Utc::now().naive_utc();
+    let id = uuid::Uuid::new_v4().to_string();
+    let upload = Uploads {
+        storage_key: format!("{}/{}.{}", now.format("%Y/%m"), id, extension),
+        id,
+        user_id: user.id.clone(),
+        content_type: content_type.to_string(),
+        byte_size: bytes.len() as i32,
+        sha256: hex::encode(Sha256::digest(&bytes)),
+        original_filename,
+        created_at: now,
+        alt_text: None,
+        alt_text_suggestion: None,
+        alt_text_status: state.config.load().alt_text_captioner_url().map(|_| ALT_TEXT_PENDING.to_string()),
+        alt_text_attempts: 0,
+    };
+
+    state.storage.put(&upload.storage_key, content_type, bytes.to_vec()).await
+        .inspect_err(|e| tracing::error!("Failed to store upload for user {}: {}", user.id, e))?;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while saving upload: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let upload = match state.writes.write(&mut conn, |conn| Uploads::create(conn, &upload)).await {
+        Ok(upload) => upload,
+        Err(e) => {
+            tracing::error!("Failed to record upload {}: {}", upload.id, e);
+            if let Err(e) = state.storage.delete(&upload.storage_key).await {
+                tracing::warn!("Failed to clean up orphaned upload {}: {}", upload.storage_key, e);
+            }
+            return Err(AuthError::database("Failed to save upload"));
+        }
+    };
+
+    tracing::info!("User {} uploaded {} ({} bytes)", user.id, upload.id, upload.byte_size);
+
+    Ok(ApiResponse::new(UploadResponse::from(upload)))
+}
+
+/// `GET /api/v1/uploads/{id}`, including any alt text suggestion waiting for review.
+pub async fn get_upload(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<UploadResponse>, AuthError> {
+    auth.require_scope(SCOPE_POSTS_READ)?;
+    let user = auth.user;
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while loading upload: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let upload = own_upload(&mut conn, &id, &user.id)?;
+
+    Ok(ApiResponse::new(UploadResponse::from(upload)))
+}
+
+/// `PUT /api/v1/uploads/{id}/alt-text`, storing the alt text the author settled on, typically
+/// an accepted or edited suggestion.
+pub async fn update_alt_text(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+    AppJson(payload): AppJson<UpdateAltTextRequest>,
+) -> Result<ApiResponse<UploadResponse>, AuthError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    payload.validate()
+        .map_err(|err| AuthError::invalid("Invalid alt text", err))?;
+    let alt_text = payload.alt_text.as_deref().map(str::trim).filter(|text| !text.is_empty());
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while updating alt text: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    own_upload(&mut conn, &id, &user.id)?;
+    let upload = state.writes.write(&mut conn, |conn| Uploads::set_alt_text(conn, &id, alt_text))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update alt text for upload {}: {}", id, e);
+            AuthError::database("Failed to update alt text")
+        })?;
+
+    // Published cover images show their alt text.
+    cache::invalidate_author_pages(state.cache.as_ref(), &user.name).await;
+
+    Ok(ApiResponse::new(UploadResponse::from(upload)))
+}
+
+/// `POST /api/v1/uploads/{id}/alt-text/suggest`, queueing the image for a fresh suggestion.
+/// The suggestion arrives asynchronously; poll the upload to pick it up.
+pub async fn suggest_alt_text(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Result<(StatusCode, ApiResponse<UploadResponse>), AuthError> {
+    auth.require_scope(SCOPE_POSTS_WRITE)?;
+    let user = auth.user;
+
+    if state.config.load().alt_text_captioner_url().is_none() {
+        return Err(AuthError::validation("Alt text suggestions are not enabled on this server"));
+    }
+
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while requesting alt text: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    own_upload(&mut conn, &id, &user.id)?;
+    let upload = state.writes.write(&mut conn, |conn| Uploads::request_alt_text(conn, &id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to queue alt text suggestion for upload {}: {}", id, e);
+            AuthError::database("Failed to request alt text suggestion")
+        })?;
+
+    Ok((StatusCode::ACCEPTED, ApiResponse::new(UploadResponse::from(upload))))
+}
+
+fn own_upload(conn: &mut diesel::SqliteConnection, id: &str, user_id: &str) -> Result<Uploads, AuthError> {
+    Uploads::by_user(conn, id, user_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load upload {}: {}", id, e);
+            AuthError::database("Failed to load upload")
+        })?
+        .ok_or_else(|| AuthError::not_found(id))
+}
+
+/// `GET /media/{id}`.
+pub async fn serve_media(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AuthError> {
+    let mut conn = get_db_conn(&state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection while serving media: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let upload = Uploads::by_id(&mut conn, &id)
+        .map_err(|e| {
+            tracing::error!("Failed to load upload {}: {}", id, e);
+            AuthError::database("Failed to load media")
+        })?
+        .ok_or_else(|| AuthError::not_found(&id))?;
+    drop(conn);
+
+    let etag = format!("\"{}\"", upload.sha256);
+    let etag_matches = headers
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|candidate| candidate.trim() == etag || candidate.trim() == "*"));
+
+    let mut response = if etag_matches {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        let bytes = state.storage.get(&upload.storage_key).await?
+            .ok_or_else(|| {
+                tracing::error!("Upload {} is missing from storage at {}", upload.id, upload.storage_key);
+                AuthError::not_found(&id)
+            })?;
+
+        let mut response = bytes.into_response();
+        response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_str(&upload.content_type)
+            .map_err(|_| AuthError::internal("Invalid stored content type"))?);
+        response.headers_mut().insert(CONTENT_DISPOSITION, HeaderValue::from_static("inline"));
+        response
+    };
+
+    let response_headers = response.headers_mut();
+    response_headers.insert(CACHE_CONTROL, HeaderValue::from_static(MEDIA_CACHE_CONTROL));
+    response_headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(ETAG, value);
+    }
+
+    Ok(response)
+}
@@ -0,0 +1,85 @@
+use axum::extract::{Query, State};
+use serde::Serialize;
+
+use crate::db::models::email_suppression::EmailSuppressions;
+use crate::errors::AuthError;
+use crate::handlers::webhooks::WebhookAuth;
+use crate::http::dto::ApiResponse;
+use crate::services::email_suppression::{parse_mailgun, parse_postmark, parse_ses, SuppressionEvent};
+use crate::state::AppState;
+use crate::utils::{constant_time_eq, get_db_conn};
+
+#[derive(Debug, Serialize)]
+pub struct EmailWebhookResponse {
+    pub suppressed: usize,
+}
+
+pub async fn ses_webhook(
+    State(state): State<AppState>,
+    Query(auth): Query<WebhookAuth>,
+    body: String,
+) -> Result<ApiResponse<EmailWebhookResponse>, AuthError> {
+    verify_webhook_secret(&state, &auth)?;
+    record_suppressions(&state, "ses", parse_ses(&body)?).await
+}
+
+pub async fn mailgun_webhook(
+    State(state): State<AppState>,
+    Query(auth): Query<WebhookAuth>,
+    body: String,
+) -> Result<ApiResponse<EmailWebhookResponse>, AuthError> {
+    verify_webhook_secret(&state, &auth)?;
+    record_suppressions(&state, "mailgun", parse_mailgun(&body)?).await
+}
+
+pub async fn postmark_webhook(
+    State(state): State<AppState>,
+    Query(auth): Query<WebhookAuth>,
+    body: String,
+) -> Result<ApiResponse<EmailWebhookResponse>, AuthError> {
+    verify_webhook_secret(&state, &auth)?;
+    record_suppressions(&state, "postmark", parse_postmark(&body)?).await
+}
+
+fn verify_webhook_secret(state: &AppState, auth: &WebhookAuth) -> Result<(), AuthError> {
+    let config = state.config.load();
+    let Some(expected) = config.email_webhook_secret() else {
+        tracing::warn!("Email webhook called but EMAIL_WEBHOOK_SECRET is not configured");
+        return Err(AuthError::unauthorized("Email webhooks are not enabled"));
+    };
+
+    match &auth.secret {
+        Some(secret) if constant_time_eq(secret.as_bytes(), expected.as_bytes()) => Ok(()),
+        _ => {
+            tracing::warn!("Email webhook called with an invalid secret");
+            Err(AuthError::unauthorized("Invalid webhook secret"))
+        }
+    }
+}
+
+async fn record_suppressions(
+    state: &AppState,
+    provider: &str,
+    events: Vec<SuppressionEvent>,
+) -> Result<ApiResponse<EmailWebhookResponse>, AuthError> {
+    let mut conn = get_db_conn(state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection for email webhook: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    for event in &events {
+        state.writes.write(&mut conn, |conn| {
+            EmailSuppressions::suppress(conn, &event.email, event.reason, provider, event.details.clone())
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record suppression for {}: {}", event.email, e);
+            AuthError::database("Failed to record suppression")
+        })?;
+
+        tracing::info!("Suppressed {} after {} reported by {}", event.email, event.reason, provider);
+    }
+
+    Ok(ApiResponse::new(EmailWebhookResponse { suppressed: events.len() }))
+}
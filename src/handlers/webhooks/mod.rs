@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+pub mod email;
+
+#[derive(Deserialize, Debug)]
+pub struct WebhookAuth {
+    pub secret: Option<String>,
+}
@@ -0,0 +1,120 @@
+use axum::extract::{Query, State};
+use axum::response::{Html, IntoResponse, Response};
+use chrono::NaiveDateTime;
+use http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_SECURITY_POLICY, X_CONTENT_TYPE_OPTIONS};
+use http::HeaderValue;
+use serde::Serialize;
+use tera::Context;
+
+use crate::db::models::post::Posts;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::handlers::widgets::LatestPostsQuery;
+use crate::http::dto::ApiResponse;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+const DEFAULT_LIMIT: i64 = 5;
+const MAX_LIMIT: i64 = 20;
+
+/// Widgets are fetched and framed by arbitrary third-party sites, so the iframe version may be
+/// embedded anywhere but can't load or submit anything itself.
+const WIDGET_CSP: &str = "default-src 'none'; style-src 'unsafe-inline'; base-uri 'none'; \
+    form-action 'none'; frame-ancestors *";
+
+#[derive(Debug, Serialize)]
+pub struct WidgetPost {
+    pub title: String,
+    pub description: String,
+    pub url: String,
+    pub published_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LatestPostsWidget {
+    pub author: String,
+    pub profile_url: String,
+    pub posts: Vec<WidgetPost>,
+}
+
+/// `GET /api/v1/widgets/latest-posts?user=` as JSON, readable cross-origin.
+pub async fn latest_posts_json(
+    State(state): State<AppState>,
+    Query(query): Query<LatestPostsQuery>,
+) -> Response {
+    let mut response = match load_widget(&state, &query) {
+        Ok(widget) => ApiResponse::new(widget).into_response(),
+        Err(e) => e.into_response(),
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("public, max-age=300"));
+    response
+}
+
+/// `GET /widgets/latest-posts/embed?user=`, a self-contained page meant for an `<iframe>`.
+pub async fn latest_posts_embed(
+    State(state): State<AppState>,
+    Query(query): Query<LatestPostsQuery>,
+) -> Response {
+    let mut response = match load_widget(&state, &query) {
+        Ok(widget) => {
+            let mut ctx = Context::new();
+            ctx.insert("widget", &widget);
+            match state.templates.render("widgets/latest_posts.html", &ctx) {
+                Ok(rendered) => Html(rendered).into_response(),
+                Err(e) => {
+                    tracing::error!("Failed to render latest posts widget: {}", e);
+                    AuthError::internal("Failed to render widget").into_response()
+                }
+            }
+        }
+        Err(e) => e.into_response(),
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_SECURITY_POLICY, HeaderValue::from_static(WIDGET_CSP));
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("public, max-age=300"));
+    response
+}
+
+fn load_widget(state: &AppState, query: &LatestPostsQuery) -> Result<LatestPostsWidget, AuthError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let mut conn = get_db_conn(state)
+        .map_err(|e| {
+            tracing::error!("Failed to get database connection for widget: {}", e);
+            AuthError::internal("Database connection failed")
+        })?;
+
+    let author = UserModel::by_name(&mut conn, &query.user)
+        .map_err(|e| {
+            tracing::error!("Failed to load widget author {}: {}", query.user, e);
+            AuthError::database("Failed to load author")
+        })?
+        .ok_or_else(|| AuthError::not_found(&query.user))?;
+
+    let posts = Posts::latest_published_by_user(&mut conn, &author.id, limit)
+        .map_err(|e| {
+            tracing::error!("Failed to load widget posts for {}: {}", author.id, e);
+            AuthError::database("Failed to load posts")
+        })?;
+
+    let config = state.config.load();
+    let base = config.public_url();
+    Ok(LatestPostsWidget {
+        profile_url: format!("{}/{}", base, author.name),
+        posts: posts
+            .into_iter()
+            .map(|post| WidgetPost {
+                url: format!("{}/{}/{}", base, author.name, post.slug),
+                title: post.title,
+                description: post.description,
+                published_at: post.published_at,
+            })
+            .collect(),
+        author: author.name,
+    })
+}
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+pub mod latest_posts;
+
+#[derive(Deserialize, Debug)]
+pub struct LatestPostsQuery {
+    pub user: String,
+    pub limit: Option<i64>,
+}
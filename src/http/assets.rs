@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use http::{HeaderValue, StatusCode, Uri};
+use sha2::{Digest, Sha256};
+
+pub const STATIC_PREFIX: &str = "/static";
+
+/// Hashed names change whenever the content does, so clients may keep them forever.
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+/// Plain names can change under the same URL; clients revalidate with the ETag every time.
+const REVALIDATE_CACHE_CONTROL: &str = "no-cache";
+
+/// Hex digits of the content hash put into fingerprinted filenames.
+const HASH_LENGTH: usize = 12;
+
+/// Every file under the static directory, scanned once at startup, with a content-hashed
+/// alias such as `index.3f2a9c81b0de.js` for each.
+///
+/// Templates link assets through the `asset` function so they get the hashed name when
+/// `STATIC_ASSET_HASHING` is on, letting browsers cache them for a year and still pick up a
+/// new deploy immediately. With hashing off (the default in development) templates get plain
+/// names and edits show up on the next reload.
+#[derive(Debug, Default)]
+pub struct AssetManifest {
+    dir: PathBuf,
+    hashing: bool,
+    /// Plain relative path to its hashed alias.
+    hashed: HashMap<String, String>,
+    /// Hashed alias back to the plain relative path.
+    originals: HashMap<String, String>,
+}
+
+impl AssetManifest {
+    pub fn build(dir: impl Into<PathBuf>, hashing: bool) -> Self {
+        let dir = dir.into();
+        let mut manifest = Self { dir, hashing, ..Self::default() };
+
+        let mut files = Vec::new();
+        collect_files(&manifest.dir, &mut files);
+        for path in files {
+            let Ok(relative) = path.strip_prefix(&manifest.dir) else {
+                continue;
+            };
+            let Some(relative) = relative.to_str().map(|relative| relative.replace('\\', "/")) else {
+                continue;
+            };
+            let bytes = match fs::read(&path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::warn!("Failed to read static asset {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            let hash = hex::encode(Sha256::digest(&bytes));
+            let alias = hashed_name(&relative, &hash[..HASH_LENGTH]);
+            manifest.originals.insert(alias.clone(), relative.clone());
+            manifest.hashed.insert(relative, alias);
+        }
+
+        tracing::info!(
+            "Found {} static assets in {}{}",
+            manifest.hashed.len(),
+            manifest.dir.display(),
+            if hashing { ", serving hashed filenames" } else { "" },
+        );
+        manifest
+    }
+
+    /// The URL templates should use for a static file, given its path under the static directory.
+    pub fn url(&self, path: &str) -> String {
+        let path = path.trim_start_matches('/');
+        match self.hashed.get(path).filter(|_| self.hashing) {
+            Some(alias) => format!("{}/{}", STATIC_PREFIX, alias),
+            None => format!("{}/{}", STATIC_PREFIX, path),
+        }
+    }
+
+    /// A weak validator for a known file, from its size and modification time. Read per
+    /// request rather than cached so edits during development are never masked by a 304.
+    fn etag(&self, path: &str) -> Option<String> {
+        if !self.hashed.contains_key(path) {
+            return None;
+        }
+        let metadata = fs::metadata(self.dir.join(path)).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(format!("W/\"{:x}-{:x}\"", metadata.len(), modified.as_nanos()))
+    }
+}
+
+/// `index.js` with hash `abc` becomes `index.abc.js`; files without an extension get the hash
+/// appended.
+fn hashed_name(path: &str, hash: &str) -> String {
+    let (dir, file) = path.rsplit_once('/').map_or(("", path), |(dir, file)| (dir, file));
+    let file = match file.split_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{}.{}.{}", stem, hash, extension),
+        _ => format!("{}.{}", file, hash),
+    };
+    if dir.is_empty() { file } else { format!("{}/{}", dir, file) }
+}
+
+pub(crate) fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+}
+
+/// Lets `asset(path="index.js")` in templates resolve to the right URL.
+pub struct AssetFunction(pub Arc<AssetManifest>);
+
+impl tera::Function for AssetFunction {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let path = args
+            .get("path")
+            .and_then(tera::Value::as_str)
+            .ok_or_else(|| tera::Error::msg("asset() needs a `path` string argument"))?;
+        Ok(tera::Value::String(self.0.url(path)))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// Sits in front of the static file service: maps hashed names back to the files they alias,
+/// answers conditional requests from the ETag, and sets cache headers. Requests arrive with
+/// the `/static` prefix already stripped.
+pub async fn static_assets(State(manifest): State<Arc<AssetManifest>>, mut request: Request, next: Next) -> Response {
+    let requested = request.uri().path().trim_start_matches('/').to_string();
+    let (path, immutable) = match manifest.originals.get(&requested) {
+        Some(original) => (original.clone(), true),
+        None => (requested, false),
+    };
+
+    if immutable {
+        let mut uri = format!("/{}", path);
+        if let Some(query) = request.uri().query() {
+            uri.push('?');
+            uri.push_str(query);
+        }
+        if let Ok(uri) = uri.parse::<Uri>() {
+            *request.uri_mut() = uri;
+        }
+    }
+
+    let etag = manifest.etag(&path);
+    let cache_control = HeaderValue::from_static(if immutable { IMMUTABLE_CACHE_CONTROL } else { REVALIDATE_CACHE_CONTROL });
+
+    let not_modified = etag.as_deref().is_some_and(|etag| {
+        request
+            .headers()
+            .get(IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.split(',').any(|candidate| candidate.trim() == etag || candidate.trim() == "*"))
+    });
+
+    let mut response = if not_modified { StatusCode::NOT_MODIFIED.into_response() } else { next.run(request).await };
+
+    if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+        let headers = response.headers_mut();
+        headers.insert(CACHE_CONTROL, cache_control);
+        if let Some(value) = etag.and_then(|etag| HeaderValue::from_str(&etag).ok()) {
+            headers.insert(ETAG, value);
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_go_before_the_extension() {
+        assert_eq!(hashed_name("index.js", "abc"), "index.abc.js");
+        assert_eq!(hashed_name("css/site.min.css", "abc"), "css/site.abc.min.css");
+        assert_eq!(hashed_name("fonts/LICENSE", "abc"), "fonts/LICENSE.abc");
+        assert_eq!(hashed_name(".well-known", "abc"), ".well-known.abc");
+    }
+}
@@ -0,0 +1,266 @@
+use axum::extract::{FromRequestParts, OptionalFromRequestParts};
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::SqliteConnection;
+use http::header::AUTHORIZATION;
+use http::Method;
+use http::request::Parts;
+use tower_cookies::Cookies;
+
+use crate::db::models::api_token::ApiTokens;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::services::api_tokens::{hash_api_token, is_api_token};
+use crate::services::cache;
+use crate::services::cookies::{self, AuthCookie};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+pub const SUDO_TOKEN_HEADER: &str = "x-sudo-token";
+
+/// The signed-in user, resolved from a bearer token or the access token cookie.
+///
+/// Requests authenticated with a personal access token carry the token's scopes;
+/// browser sessions and access tokens are unrestricted.
+///
+/// The user may come from the cache, so `user.password` is always blank; handlers that
+/// check a password load the hash from the database.
+///
+/// An admin impersonating the user is named in `impersonator`. Their requests can't delete
+/// anything, and the extractors guarding sensitive actions turn them away.
+///
+/// Once resolved, the user is kept in the request's extensions, so middleware that needs it
+/// and the handler after it share one lookup.
+#[derive(Clone)]
+pub struct AuthUser {
+    pub user: UserModel,
+    pub scopes: Option<Vec<String>>,
+    pub impersonator: Option<String>,
+}
+
+impl AuthUser {
+    pub fn require_scope(&self, scope: &str) -> Result<(), AuthError> {
+        match &self.scopes {
+            Some(scopes) if !scopes.iter().any(|granted| granted == scope) => {
+                Err(AuthError::forbidden(format!("API token is missing the '{}' scope", scope)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_api_token(&self) -> bool {
+        self.scopes.is_some()
+    }
+
+    /// For actions an admin mustn't take on a user's behalf.
+    pub fn forbid_impersonation(&self) -> Result<(), AuthError> {
+        match self.impersonator {
+            Some(_) => Err(AuthError::forbidden("Not allowed while impersonating a user")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A signed-in user who has re-entered their credentials within the reauth window.
+///
+/// Required by handlers that change credentials, manage API keys or delete the account.
+pub struct SudoUser {
+    pub user: UserModel,
+}
+
+/// A signed-in administrator. API tokens never grant admin access.
+pub struct AdminUser {
+    pub user: UserModel,
+}
+
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(auth) = parts.extensions.get::<AuthUser>() {
+            return Ok(auth.clone());
+        }
+
+        let token = match bearer_token(parts) {
+            Some(token) => token,
+            None => request_cookie(parts, state, AuthCookie::Access)
+                .await?
+                .ok_or_else(|| AuthError::unauthorized("No access token provided"))?,
+        };
+
+        let (user_id, scopes, version, impersonator) = if is_api_token(&token) {
+            let mut conn = auth_db_conn(state)?;
+            let api_token = ApiTokens::by_hash(&mut conn, &hash_api_token(&token))
+                .map_err(|e| {
+                    tracing::error!("Failed to look up API token: {}", e);
+                    AuthError::database("Failed to verify API token")
+                })?
+                .ok_or_else(|| AuthError::unauthorized("Invalid API token"))?;
+
+            if api_token.is_expired() {
+                tracing::info!("Expired API token {} used", api_token.id);
+                return Err(AuthError::unauthorized("API token has expired"));
+            }
+
+            if let Err(e) = state.writes.write(&mut conn, |conn| ApiTokens::touch(conn, &api_token.id)).await {
+                tracing::warn!("Failed to record API token usage: {}", e);
+            }
+
+            (api_token.user_id.clone(), Some(api_token.scope_list()), None, None)
+        } else {
+            let claims = state.jwt.decode_access_token(&token)?.claims;
+            let impersonator = claims.imp.map(|admin_id| (admin_id, claims.imp_ver));
+            (claims.user_id, None, Some(claims.ver), impersonator)
+        };
+
+        let user = authenticated_user(state, &user_id).await?;
+
+        // Signing out everywhere bumps the version, so access tokens issued before it stop
+        // working here even though they haven't expired.
+        if version.is_some_and(|version| version != user.token_version) {
+            tracing::info!("Revoked access token presented for user {}", user.id);
+            return Err(AuthError::unauthorized("Access token has been revoked"));
+        }
+
+        if user.is_suspended() {
+            tracing::info!("Credentials presented for suspended user {}", user.id);
+            return Err(AuthError::forbidden("Account suspended"));
+        }
+
+        if let Some((admin_id, admin_version)) = &impersonator {
+            // Impersonation ends as soon as the admin stops being one, is suspended or signs
+            // out everywhere.
+            let admin = authenticated_user(state, admin_id).await?;
+            if !admin.is_admin
+                || admin.deleted_at.is_some()
+                || admin.is_suspended()
+                || *admin_version != Some(admin.token_version)
+            {
+                tracing::warn!("Impersonation token for user {} outlived admin {}", user.id, admin_id);
+                return Err(AuthError::unauthorized("Impersonation has ended"));
+            }
+            if parts.method == Method::DELETE {
+                return Err(AuthError::forbidden("Deleting is not allowed while impersonating a user"));
+            }
+        }
+
+        let impersonator = impersonator.map(|(admin_id, _)| admin_id);
+        let auth = AuthUser { user, scopes, impersonator };
+        parts.extensions.insert(auth.clone());
+        Ok(auth)
+    }
+}
+
+fn auth_db_conn(state: &AppState) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, AuthError> {
+    get_db_conn(state).map_err(|e| {
+        tracing::error!("Failed to get database connection during authentication: {}", e);
+        AuthError::internal("Database connection failed")
+    })
+}
+
+/// Every authenticated request needs its user, so lookups go through the cache. Account
+/// changes drop the entry with `cache::invalidate_user`. The password hash is never cached.
+async fn authenticated_user(state: &AppState, user_id: &str) -> Result<UserModel, AuthError> {
+    let key = cache::user_key(user_id);
+    if let Some(user) = cache::get_json::<UserModel>(state.cache.as_ref(), &key).await {
+        return Ok(user);
+    }
+
+    let mut conn = auth_db_conn(state)?;
+    let mut user = UserModel::by_id(&mut conn, user_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load authenticated user: {}", e);
+            AuthError::database("Failed to load user")
+        })?
+        .ok_or_else(|| {
+            tracing::info!("Credentials presented for missing user: {}", user_id);
+            AuthError::unauthorized("User no longer exists")
+        })?;
+    user.password.clear();
+
+    cache::set_json(state.cache.as_ref(), &key, &user, cache::user_ttl(&state.config.load())).await;
+    Ok(user)
+}
+
+/// Lets public handlers personalise responses for signed-in users. Missing or invalid
+/// credentials are treated as an anonymous request rather than rejected.
+impl OptionalFromRequestParts<AppState> for AuthUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Option<Self>, Self::Rejection> {
+        match <AuthUser as FromRequestParts<AppState>>::from_request_parts(parts, state).await {
+            Ok(auth) => Ok(Some(auth)),
+            Err(AuthError::Unauthorized { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for SudoUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let auth = <AuthUser as FromRequestParts<AppState>>::from_request_parts(parts, state).await?;
+
+        if auth.is_api_token() {
+            return Err(AuthError::forbidden("API tokens cannot perform sensitive account operations"));
+        }
+        auth.forbid_impersonation()?;
+
+        let user = auth.user;
+
+        let header_token = parts
+            .headers
+            .get(SUDO_TOKEN_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+
+        let token = match header_token {
+            Some(token) => token,
+            None => request_cookie(parts, state, AuthCookie::Sudo)
+                .await?
+                .ok_or_else(|| AuthError::reauth_required("This action requires recent re-authentication"))?,
+        };
+
+        let decoded = state.jwt.decode_sudo_token(&token)?;
+
+        if decoded.claims.user_id != user.id {
+            tracing::warn!("Sudo token user mismatch for user: {}", user.id);
+            return Err(AuthError::reauth_required("Invalid re-authentication token"));
+        }
+
+        Ok(SudoUser { user })
+    }
+}
+
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let auth = <AuthUser as FromRequestParts<AppState>>::from_request_parts(parts, state).await?;
+
+        auth.forbid_impersonation()?;
+        if auth.is_api_token() || !auth.user.is_admin {
+            tracing::warn!("Non-admin user {} attempted to access an admin route", auth.user.id);
+            return Err(AuthError::forbidden("Administrator access required"));
+        }
+
+        Ok(AdminUser { user: auth.user })
+    }
+}
+
+fn bearer_token(parts: &Parts) -> Option<String> {
+    parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_owned())
+}
+
+async fn request_cookie(parts: &mut Parts, state: &AppState, kind: AuthCookie) -> Result<Option<String>, AuthError> {
+    let cookies = Cookies::from_request_parts(parts, state)
+        .await
+        .map_err(|(_, message)| AuthError::internal(message))?;
+
+    Ok(cookies::get(&cookies, &state.config.load(), kind))
+}
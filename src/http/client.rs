@@ -0,0 +1,156 @@
+use std::convert::Infallible;
+
+use axum::extract::FromRequestParts;
+use http::header::USER_AGENT;
+use http::request::Parts;
+
+use crate::http::forwarded::ClientAddr;
+use crate::http::locale::Locale;
+
+/// Where a request came from, recorded against sessions so they can be traced later.
+///
+/// The address and user agent are best effort: the address is the one resolved through
+/// trusted proxies and is missing when the server isn't run with connect info, and the user
+/// agent is whatever the client chose to send.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// The best match for the browser's `Accept-Language`.
+    pub locale: Locale,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Ok(client) = ClientAddr::from_request_parts(parts, state).await;
+        let ip_address = client.ip.map(|ip| ip.to_string());
+
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(512).collect());
+
+        Ok(Self { ip_address, user_agent, locale: Locale::from_headers(&parts.headers) })
+    }
+}
+
+impl ClientInfo {
+    /// The coarse device label for this client's user agent.
+    pub fn device(&self) -> String {
+        device_label(self.user_agent.as_deref())
+    }
+
+    pub fn is_bot(&self) -> bool {
+        is_bot(self.user_agent.as_deref())
+    }
+
+    /// The language to write to a user in: the one they chose, otherwise the browser's.
+    pub fn locale_for(&self, user_locale: Option<&str>) -> Locale {
+        user_locale.and_then(Locale::from_tag).unwrap_or(self.locale)
+    }
+}
+
+/// Whether a user agent is a crawler, link previewer or script rather than someone reading.
+/// Clients that don't send one are assumed to be scripts.
+pub fn is_bot(user_agent: Option<&str>) -> bool {
+    let Some(user_agent) = user_agent.map(str::trim).filter(|ua| !ua.is_empty()) else {
+        return true;
+    };
+    let user_agent = user_agent.to_lowercase();
+    [
+        "bot", "crawl", "spider", "slurp", "preview", "facebookexternalhit", "headless", "lighthouse",
+        "curl/", "wget/", "python-", "go-http-client", "java/", "okhttp", "node-fetch", "axios/",
+    ]
+    .iter()
+    .any(|token| user_agent.contains(token))
+}
+
+/// A short, human readable name for the device behind a user agent, such as "Firefox on
+/// Linux". Deliberately coarse: versions are dropped so a browser update doesn't look like a
+/// new device. Clients that aren't browsers are named after their product token, e.g. "curl".
+pub fn device_label(user_agent: Option<&str>) -> String {
+    let Some(user_agent) = user_agent.map(str::trim).filter(|ua| !ua.is_empty()) else {
+        return "Unknown device".to_string();
+    };
+
+    let browser = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("FxiOS/", "Firefox"),
+        ("CriOS/", "Chrome"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+    ]
+    .into_iter()
+    .find(|(token, _)| user_agent.contains(token))
+    .map(|(_, name)| name);
+
+    let os = [
+        ("iPhone", "iOS"),
+        ("iPad", "iOS"),
+        ("Android", "Android"),
+        ("Windows", "Windows"),
+        ("CrOS", "ChromeOS"),
+        ("Macintosh", "macOS"),
+        ("Linux", "Linux"),
+    ]
+    .into_iter()
+    .find(|(token, _)| user_agent.contains(token))
+    .map(|(_, name)| name);
+
+    match (browser, os) {
+        (Some(browser), Some(os)) => format!("{} on {}", browser, os),
+        (Some(browser), None) => browser.to_string(),
+        (None, Some(os)) => format!("Unknown browser on {}", os),
+        (None, None) => user_agent
+            .split(['/', ' '])
+            .next()
+            .filter(|product| !product.is_empty())
+            .map_or_else(|| "Unknown device".to_string(), |product| product.chars().take(64).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_agents_are_reduced_to_browser_and_os() {
+        let cases = [
+            ("Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0", "Firefox on Linux"),
+            ("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Safari/605.1.15", "Safari on macOS"),
+            ("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0", "Edge on Windows"),
+            ("Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36", "Chrome on Android"),
+            ("Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) CriOS/126.0 Mobile/15E148 Safari/604.1", "Chrome on iOS"),
+            ("curl/8.8.0", "curl"),
+        ];
+        for (user_agent, label) in cases {
+            assert_eq!(device_label(Some(user_agent)), label, "{}", user_agent);
+        }
+
+        assert_eq!(device_label(None), "Unknown device");
+        assert_eq!(device_label(Some("  ")), "Unknown device");
+    }
+
+    #[test]
+    fn crawlers_and_scripts_are_bots() {
+        let bots = [
+            "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+            "Mozilla/5.0 AppleWebKit/537.36 (KHTML, like Gecko; compatible; bingbot/2.0) Chrome/116.0 Safari/537.36",
+            "facebookexternalhit/1.1 (+http://www.facebook.com/externalhit_uatext.php)",
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/126.0.0.0 Safari/537.36",
+            "curl/8.8.0",
+            "python-requests/2.32.3",
+        ];
+        for user_agent in bots {
+            assert!(is_bot(Some(user_agent)), "{}", user_agent);
+        }
+        assert!(is_bot(None));
+        assert!(is_bot(Some(" ")));
+        assert!(!is_bot(Some("Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0")));
+    }
+}
@@ -0,0 +1,85 @@
+use axum::body::{to_bytes, Body};
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http::header::{AUTHORIZATION, CACHE_CONTROL, CONTENT_LENGTH, COOKIE, ETAG, IF_NONE_MATCH, VARY};
+use http::{HeaderMap, HeaderValue, Method, StatusCode};
+use sha2::{Digest, Sha256};
+
+/// Whether `If-None-Match` names `etag`. Weak comparison, as RFC 9110 asks for here: `W/`
+/// prefixes on either side are ignored.
+pub fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    headers
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value.split(',').map(str::trim).any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+        })
+}
+
+/// Conditional GETs for JSON reads. Successful responses get a weak ETag from a hash of the
+/// body, and a request whose `If-None-Match` already names it gets a bodyless 304 instead.
+///
+/// Responses to signed-in requests are `private`, so shared caches never hand one reader's
+/// view to another, and anonymous ones are `public`. Either way they're revalidated before
+/// reuse, and `Cache-Control` set by the route is left alone.
+pub async fn conditional_get(request: Request, next: Next) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return next.run(request).await;
+    }
+    let signed_in = request.headers().contains_key(AUTHORIZATION) || request.headers().contains_key(COOKIE);
+    let request_headers = request.headers().clone();
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    // JSON responses are serialized up front, so this doesn't hold anything new in memory.
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to read a response to tag it: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let etag = format!("W/\"{}\"", &hex::encode(Sha256::digest(&bytes))[..32]);
+    let headers = &mut parts.headers;
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        headers.insert(ETAG, value);
+    }
+    headers.append(VARY, HeaderValue::from_static("Authorization, Cookie"));
+    if !headers.contains_key(CACHE_CONTROL) {
+        let cache_control = if signed_in { "private, no-cache" } else { "public, no-cache" };
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    }
+
+    if etag_matches(&request_headers, &etag) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_none_match_compares_weakly() {
+        let mut headers = HeaderMap::new();
+        assert!(!etag_matches(&headers, "W/\"abc\""));
+
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("\"xyz\", W/\"abc\""));
+        assert!(etag_matches(&headers, "W/\"abc\""));
+        assert!(etag_matches(&headers, "\"abc\""));
+        assert!(!etag_matches(&headers, "W/\"ab\""));
+
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(etag_matches(&headers, "\"anything\""));
+    }
+}
@@ -0,0 +1,20 @@
+use http::HeaderValue;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+use crate::config::ConfigHandle;
+
+/// CORS for the JSON API. Only origins listed in `CORS_ORIGIN` get access, with credentials
+/// so browser clients on those origins can use their session cookie. The list is read per
+/// request, so a config reload applies without a restart.
+pub fn layer(config: ConfigHandle) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+            let config = config.load();
+            origin
+                .to_str()
+                .is_ok_and(|origin| config.cors_origin().iter().any(|allowed| allowed.trim() == origin))
+        }))
+        .allow_credentials(true)
+        .allow_methods(AllowMethods::mirror_request())
+        .allow_headers(AllowHeaders::mirror_request())
+}
@@ -0,0 +1,136 @@
+use axum::body::{to_bytes, Body};
+use axum::extract::{FromRequestParts, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::request::Parts;
+use http::Method;
+use tower_cookies::Cookies;
+
+use crate::errors::AuthError;
+use crate::services::cookies::{self, AuthCookie};
+use crate::state::AppState;
+use crate::utils::{constant_time_eq, generate_csrf_token};
+
+pub const CSRF_HEADER: &str = "x-csrf-token";
+/// The hidden field HTML forms carry the token in, since they can't set headers.
+pub const CSRF_FORM_FIELD: &str = "csrf_token";
+
+/// Larger form bodies aren't searched for the token. Uploads are multipart and send the header.
+const MAX_FORM_BYTES: usize = 64 * 1024;
+
+/// The request's CSRF token, issued in a new cookie when it doesn't have one yet. Pages with
+/// forms put it in a hidden `csrf_token` field; scripts send it back as `X-CSRF-Token`.
+pub struct CsrfToken(pub String);
+
+impl FromRequestParts<AppState> for CsrfToken {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let cookies = Cookies::from_request_parts(parts, state)
+            .await
+            .map_err(|(_, message)| AuthError::internal(message))?;
+
+        let config = state.config.load();
+        let token = cookies::get(&cookies, &config, AuthCookie::Csrf).unwrap_or_else(|| {
+            let token = generate_csrf_token();
+            cookies::set(&cookies, &config, AuthCookie::Csrf, token.clone(), None);
+            token
+        });
+        Ok(CsrfToken(token))
+    }
+}
+
+/// Double-submit CSRF protection, with nothing stored server-side. A state-changing request
+/// that carries our auth cookies must echo the CSRF cookie's value in `X-CSRF-Token` or a
+/// `csrf_token` form field; another site can make the browser send the cookie but can't read
+/// it. Requests with an `Authorization` header authenticate without cookies and aren't checked.
+pub async fn verify_csrf(State(state): State<AppState>, cookies: Cookies, request: Request, next: Next) -> Response {
+    if is_safe(request.method()) || request.headers().contains_key(AUTHORIZATION) {
+        return next.run(request).await;
+    }
+
+    let config = state.config.load();
+    let uses_cookies = [AuthCookie::Access, AuthCookie::Refresh, AuthCookie::Sudo]
+        .into_iter()
+        .any(|kind| cookies::get(&cookies, &config, kind).is_some());
+    if !uses_cookies {
+        return next.run(request).await;
+    }
+    let expected = cookies::get(&cookies, &config, AuthCookie::Csrf);
+    drop(config);
+
+    let (submitted, request) = submitted_token(request).await;
+    match (expected, submitted) {
+        (Some(expected), Some(submitted)) if constant_time_eq(expected.as_bytes(), submitted.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => {
+            tracing::info!("Rejected {} {} without a valid CSRF token", request.method(), request.uri().path());
+            AuthError::forbidden("Missing or invalid CSRF token").into_response()
+        }
+    }
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE)
+}
+
+/// The token from the header, or else from the body of a urlencoded form. The body is read
+/// to find it, so the request is handed back rebuilt.
+async fn submitted_token(request: Request) -> (Option<String>, Request) {
+    let header = request
+        .headers()
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    if header.is_some() {
+        return (header, request);
+    }
+
+    let is_form = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+    if !is_form {
+        return (None, request);
+    }
+
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_FORM_BYTES).await else {
+        return (None, Request::from_parts(parts, Body::empty()));
+    };
+    let token = url::form_urlencoded::parse(&bytes)
+        .find(|(name, _)| name == CSRF_FORM_FIELD)
+        .map(|(_, value)| value.into_owned());
+    (token, Request::from_parts(parts, Body::from(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn forms_submit_the_token_in_a_field_and_keep_their_body() {
+        let request = Request::builder()
+            .method(Method::POST)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from("email=a%40b.test&csrf_token=abc"))
+            .unwrap();
+        let (token, request) = submitted_token(request).await;
+        assert_eq!(token.as_deref(), Some("abc"));
+        let body = to_bytes(request.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"email=a%40b.test&csrf_token=abc");
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .header(CSRF_HEADER, "xyz")
+            .body(Body::from("csrf_token=abc"))
+            .unwrap();
+        assert_eq!(submitted_token(request).await.0.as_deref(), Some("xyz"));
+
+        let json = Request::builder().method(Method::POST).body(Body::from(r#"{"csrf_token":"abc"}"#)).unwrap();
+        assert_eq!(submitted_token(json).await.0, None);
+    }
+}
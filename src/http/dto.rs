@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use crate::db::models::post::Posts;
+use crate::db::models::refresh_token::RefreshTokens;
+use crate::db::models::series::Series;
+use crate::db::models::tag::Tags;
+use crate::db::models::user_model::UserModel;
+use crate::handlers::uploads::media_path;
+use crate::services::markdown;
+
+// The wire types live in `tsumi-types`, shared with `tsumi-client`; this module builds them
+// from database rows.
+pub use tsumi_types::{ApiResponse, NearDuplicate, PostDto, SeriesNav, SeriesNavPost, SessionDto, UserDto};
+
+impl From<UserModel> for UserDto {
+    fn from(user: UserModel) -> Self {
+        Self {
+            id: user.id,
+            username: user.name,
+            email: user.email,
+            email_verified: user.email_verified,
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// `post` as its author sees it, with no reactions, views or series filled in yet.
+pub fn post_dto(post: Posts, tags: Vec<Tags>) -> PostDto {
+    PostDto {
+        content_html: markdown::render(&post.content),
+        id: post.id,
+        author_id: post.user_id,
+        title: post.title,
+        description: post.description,
+        slug: post.slug,
+        content: post.content,
+        status: post.status,
+        published_at: post.published_at,
+        tags: tags.into_iter().map(|tag| tag.name).collect(),
+        cover_image_url: post.cover_upload_id.as_deref().map(media_path),
+        word_count: post.word_count,
+        reading_minutes: post.reading_minutes,
+        reactions: BTreeMap::new(),
+        reaction_count: 0,
+        views: 0,
+        near_duplicates: Vec::new(),
+        series: None,
+        created_at: post.created_at,
+        updated_at: post.updated_at,
+    }
+}
+
+/// `post_id`'s place among `posts`, the series' posts in order.
+pub fn series_nav(series: Series, posts: &[Posts], post_id: &str) -> Option<SeriesNav> {
+    let visible: Vec<&Posts> = posts.iter().filter(|post| post.is_public() || post.id == post_id).collect();
+    let index = visible.iter().position(|post| post.id == post_id)?;
+    let link = |post: &&Posts| SeriesNavPost { id: post.id.clone(), title: post.title.clone(), slug: post.slug.clone() };
+    Some(SeriesNav {
+        id: series.id,
+        title: series.title,
+        slug: series.slug,
+        part: index + 1,
+        parts: visible.len(),
+        prev: index.checked_sub(1).and_then(|prev| visible.get(prev)).map(link),
+        next: visible.get(index + 1).map(link),
+    })
+}
+
+pub fn session_dto(session: RefreshTokens, current: bool) -> SessionDto {
+    SessionDto {
+        id: session.id,
+        ip_address: session.ip_address,
+        user_agent: session.user_agent,
+        device: session.device,
+        created_at: session.created_at,
+        expires_at: session.expires_at,
+        current,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+    use serde_json::json;
+
+    use super::*;
+
+    fn user() -> UserModel {
+        UserModel {
+            id: "u1".to_string(),
+            name: "ann".to_string(),
+            email: "ann@example.com".to_string(),
+            password: "$argon2id$secret-hash".to_string(),
+            email_verified: true,
+            created_at: NaiveDateTime::default(),
+            updated_at: NaiveDateTime::default(),
+            deleted_at: None,
+            is_admin: false,
+            canonicalize_links: false,
+            blog_styles_disabled: false,
+            timezone: "UTC".to_string(),
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            token_version: 0,
+            locale: None,
+            avatar_url: None,
+            bio: String::new(),
+            website: None,
+            location: None,
+            profile_private: false,
+            show_email: false,
+            status: "active".to_string(),
+            comment_moderation: "visible".to_string(),
+        }
+    }
+
+    #[test]
+    fn responses_are_wrapped_in_data_and_users_never_show_their_password() {
+        let body = serde_json::to_value(ApiResponse::new(UserDto::from(user()))).unwrap();
+
+        assert_eq!(body, json!({
+            "data": {
+                "id": "u1",
+                "username": "ann",
+                "email": "ann@example.com",
+                "email_verified": true,
+                "created_at": "1970-01-01T00:00:00",
+            }
+        }));
+        assert!(!body.to_string().contains("secret-hash"));
+    }
+
+    #[test]
+    fn posts_only_list_near_duplicates_when_there_are_some() {
+        let post = Posts {
+            id: "p1".to_string(),
+            user_id: "u1".to_string(),
+            title: "Hello".to_string(),
+            description: String::new(),
+            slug: "hello".to_string(),
+            content: "*hi*".to_string(),
+            created_at: NaiveDateTime::default(),
+            updated_at: NaiveDateTime::default(),
+            status: "draft".to_string(),
+            published_at: None,
+            word_count: Some(1),
+            og_image_url: None,
+            cover_upload_id: Some("c1".to_string()),
+            reading_minutes: Some(1),
+            moderation_status: "visible".to_string(),
+        };
+        let body = serde_json::to_value(post_dto(post, Vec::new()).with_reactions([("like".to_string(), 2), ("fire".to_string(), 1)])).unwrap();
+
+        assert!(body.get("near_duplicates").is_none());
+        assert_eq!(body["reaction_count"], json!(3));
+        assert_eq!(body["content_html"], json!("<p><em>hi</em></p>\n"));
+        assert_eq!(body["cover_image_url"], json!(media_path("c1")));
+    }
+
+    #[test]
+    fn series_navigation_skips_unpublished_parts() {
+        let part = |id: &str, status: &str| Posts {
+            id: id.to_string(),
+            user_id: "u1".to_string(),
+            title: id.to_uppercase(),
+            description: String::new(),
+            slug: id.to_string(),
+            content: String::new(),
+            created_at: NaiveDateTime::default(),
+            updated_at: NaiveDateTime::default(),
+            status: status.to_string(),
+            published_at: None,
+            word_count: None,
+            og_image_url: None,
+            cover_upload_id: None,
+            reading_minutes: None,
+            moderation_status: "visible".to_string(),
+        };
+        let series = Series {
+            id: "s1".to_string(),
+            user_id: "u1".to_string(),
+            title: "Rust".to_string(),
+            slug: "rust".to_string(),
+            description: String::new(),
+            created_at: NaiveDateTime::default(),
+            updated_at: NaiveDateTime::default(),
+        };
+        let posts = [part("one", "published"), part("two", "draft"), part("three", "published")];
+
+        let nav = series_nav(series.clone(), &posts, "three").unwrap();
+        assert_eq!((nav.part, nav.parts), (2, 2));
+        assert_eq!(nav.prev.unwrap().id, "one");
+        assert!(nav.next.is_none());
+
+        let nav = series_nav(series.clone(), &posts, "two").unwrap();
+        assert_eq!((nav.part, nav.parts), (2, 3));
+        assert_eq!(nav.next.unwrap().id, "three");
+        assert!(series_nav(series, &posts, "four").is_none());
+    }
+
+    #[test]
+    fn sessions_leave_out_the_refresh_token() {
+        let session = RefreshTokens {
+            id: "s1".to_string(),
+            token: "refresh-secret".to_string(),
+            user_id: "u1".to_string(),
+            expires_at: NaiveDateTime::default(),
+            created_at: NaiveDateTime::default(),
+            ip_address: Some("127.0.0.1".to_string()),
+            user_agent: None,
+            family_id: "s1".to_string(),
+            replaced_by: None,
+            device: Some("Firefox on Linux".to_string()),
+            persistent: true,
+        };
+        let body = serde_json::to_value(session_dto(session, true)).unwrap();
+
+        assert_eq!(body["current"], json!(true));
+        assert_eq!(body["device"], json!("Firefox on Linux"));
+        assert!(body.get("user_id").is_none());
+        assert!(!body.to_string().contains("refresh-secret"));
+    }
+}
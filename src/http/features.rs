@@ -0,0 +1,36 @@
+use std::marker::PhantomData;
+
+use axum::extract::FromRequestParts;
+use http::request::Parts;
+
+use crate::errors::AuthError;
+use crate::services::feature_flags::{self, Flag};
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+/// Rejects the request with a 403 unless the flag `F` is on. Handlers take it as
+/// `_: Enabled<SignupFeature>` ahead of any body extractor.
+pub struct Enabled<F: Flag>(PhantomData<F>);
+
+impl<F: Flag> FromRequestParts<AppState> for Enabled<F> {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let mut conn = get_db_conn(state)
+            .map_err(|e| {
+                tracing::error!("Failed to get database connection while checking {}: {}", F::NAME, e);
+                AuthError::internal("Database connection failed")
+            })?;
+
+        let enabled = feature_flags::is_enabled(&mut conn, &state.config.load(), F::NAME)
+            .map_err(|e| {
+                tracing::error!("Failed to check feature flag {}: {}", F::NAME, e);
+                AuthError::database("Failed to check feature flag")
+            })?;
+
+        if !enabled {
+            return Err(AuthError::forbidden(F::DISABLED_MESSAGE));
+        }
+        Ok(Self(PhantomData))
+    }
+}
@@ -0,0 +1,194 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use axum::extract::{ConnectInfo, FromRequestParts, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::request::Parts;
+use http::HeaderMap;
+use tracing::Instrument;
+
+use crate::state::AppState;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+
+/// An address or CIDR block, as listed in `TRUSTED_PROXIES`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, canonical(ip)) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix)).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix)).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (address, prefix) = match value.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (value, None),
+        };
+        let network = canonical(address.trim().parse().map_err(|_| format!("'{}' is not an IP address", address))?);
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.trim().parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .ok_or_else(|| format!("'{}' is not a valid prefix length for {}", prefix, address))?,
+            None => max_prefix,
+        };
+        Ok(Self { network, prefix })
+    }
+}
+
+/// IPv4 clients of a dual-stack listener show up as `::ffff:a.b.c.d`; compare them as IPv4.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        v4 => v4,
+    }
+}
+
+/// The client a request really came from, after looking through any trusted reverse proxies.
+///
+/// Inserted into the request extensions by [`resolve_client`] for everything downstream:
+/// session metadata, rate limiting and the request's log span. Forwarded headers are only
+/// believed when the peer is listed in `TRUSTED_PROXIES`; anyone else could send them.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientAddr {
+    /// Missing only when the server runs without connect info.
+    pub ip: Option<IpAddr>,
+    /// Whether the client reached us, or the proxy in front of us, over HTTPS.
+    pub https: bool,
+}
+
+impl ClientAddr {
+    fn resolve(peer: Option<IpAddr>, headers: &HeaderMap, trusted: &[IpRange], served_over_tls: bool) -> Self {
+        let is_trusted = |ip: IpAddr| trusted.iter().any(|range| range.contains(ip));
+        let Some(peer) = peer.map(canonical).filter(|peer| is_trusted(*peer)) else {
+            return Self { ip: peer.map(canonical), https: served_over_tls };
+        };
+
+        // Each proxy appends the address it received the request from, so walk the chain
+        // from the right and stop at the first hop we don't operate ourselves.
+        // A hop that isn't an address ends the walk at the last one that was.
+        let hops: Vec<&str> = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+        let mut ip = peer;
+        for hop in hops.iter().rev() {
+            let Ok(hop) = hop.trim().parse::<IpAddr>() else {
+                break;
+            };
+            ip = canonical(hop);
+            if !is_trusted(ip) {
+                break;
+            }
+        }
+
+        // The leftmost protocol is the one the client used with the outermost proxy.
+        let https = headers
+            .get(X_FORWARDED_PROTO)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map_or(served_over_tls, |proto| proto.trim().eq_ignore_ascii_case("https"));
+
+        Self { ip: Some(ip), https }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientAddr {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<ClientAddr>().copied().unwrap_or_else(|| ClientAddr {
+            ip: parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| canonical(addr.ip())),
+            https: false,
+        }))
+    }
+}
+
+/// Works out the real client address and scheme for every request and runs the rest of the
+/// request inside a span carrying the client IP and scheme.
+pub async fn resolve_client(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+    let client = ClientAddr::resolve(
+        peer,
+        request.headers(),
+        state.config.load().trusted_proxies(),
+        state.config.load().tls_cert_and_key().is_some(),
+    );
+    request.extensions_mut().insert(client);
+
+    let span = tracing::info_span!(
+        "request",
+        client_ip = %client.ip.map_or_else(|| "-".to_string(), |ip| ip.to_string()),
+        scheme = if client.https { "https" } else { "http" },
+    );
+    next.run(request).instrument(span).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn parses_addresses_and_cidr_blocks() {
+        let range: IpRange = "10.0.0.0/8".parse().unwrap();
+        assert!(range.contains("10.1.2.3".parse().unwrap()));
+        assert!(range.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!range.contains("11.0.0.1".parse().unwrap()));
+
+        let single: IpRange = "::1".parse().unwrap();
+        assert!(single.contains("::1".parse().unwrap()));
+        assert!("0.0.0.0/0".parse::<IpRange>().unwrap().contains("8.8.8.8".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("nginx".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn only_trusted_peers_may_forward() {
+        let trusted = vec!["127.0.0.1".parse().unwrap(), "10.0.0.0/8".parse().unwrap()];
+        let forwarded = headers(&[("x-forwarded-for", "6.6.6.6, 203.0.113.9, 10.0.0.2"), ("x-forwarded-proto", "https")]);
+
+        let client = ClientAddr::resolve(Some("127.0.0.1".parse().unwrap()), &forwarded, &trusted, false);
+        assert_eq!(client.ip, Some("203.0.113.9".parse().unwrap()));
+        assert!(client.https);
+
+        let client = ClientAddr::resolve(Some("198.51.100.7".parse().unwrap()), &forwarded, &trusted, false);
+        assert_eq!(client.ip, Some("198.51.100.7".parse().unwrap()));
+        assert!(!client.https);
+
+        let client = ClientAddr::resolve(Some("127.0.0.1".parse().unwrap()), &HeaderMap::new(), &trusted, true);
+        assert_eq!(client.ip, Some("127.0.0.1".parse().unwrap()));
+        assert!(client.https);
+    }
+}
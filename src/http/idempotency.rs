@@ -0,0 +1,236 @@
+use axum::body::{to_bytes, Body};
+use axum::extract::{FromRequestParts, OptionalFromRequestParts, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{Duration, Utc};
+use diesel::SqliteConnection;
+use http::header::{CONTENT_TYPE, SET_COOKIE};
+use http::{HeaderName, HeaderValue, Method, StatusCode};
+use sha2::{Digest, Sha256};
+
+use crate::db::models::idempotency_key::{IdempotencyKeys, ANONYMOUS_SCOPE_PREFIX};
+use crate::errors::AuthError;
+use crate::http::auth::AuthUser;
+use crate::http::client::ClientInfo;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+/// Set on a response that was replayed from an earlier request with the same key.
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+const MAX_KEY_LEN: usize = 255;
+
+/// A request still unanswered after this long is taken to have died with its process, and
+/// its key can be used again.
+const ABANDONED_AFTER_MINUTES: i64 = 5;
+
+/// What to do with a request carrying a key.
+enum Claim {
+    /// Handle it, and store the response under the claimed row.
+    Handle(String),
+    Replay(IdempotencyKeys),
+}
+
+/// `Idempotency-Key` support for POSTs, so a client can retry one it never got an answer to
+/// without doing the work twice. The first request with a key is handled as usual and its
+/// response stored; retries with the same key and body get that response back, marked
+/// `Idempotent-Replayed`. Reusing a key for a different request is rejected, as is a retry
+/// that arrives while the first is still being handled.
+///
+/// Keys belong to the signed-in user, or when signed out to the client, told apart by its
+/// address and user agent, and are kept for `IDEMPOTENCY_KEY_TTL_HOURS`. Server errors, rate limiting, and responses that set cookies
+/// aren't stored, since a replay couldn't stand in for them; the key is freed instead.
+pub async fn idempotency(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get(&IDEMPOTENCY_KEY) else {
+        return next.run(request).await;
+    };
+    let Some(key) = key
+        .to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+        .map(str::to_string)
+    else {
+        return AuthError::invalid_field(
+            "Idempotency-Key",
+            "invalid",
+            format!("Idempotency-Key must be 1 to {MAX_KEY_LEN} visible characters"),
+        )
+        .into_response();
+    };
+
+    let (mut parts, body) = request.into_parts();
+    // The resolved user stays in the extensions for the handler's own `AuthUser`.
+    let scope = match <AuthUser as OptionalFromRequestParts<AppState>>::from_request_parts(&mut parts, &state).await {
+        Ok(Some(auth)) => auth.user.id,
+        // The route decides what to do about a bad token.
+        Ok(None) | Err(_) => {
+            let Ok(client) = ClientInfo::from_request_parts(&mut parts, &state).await;
+            anonymous_scope(&client)
+        }
+    };
+
+    let config = state.config.load();
+    let body_limit = config.upload_max_bytes().max(config.import_max_bytes()) + 64 * 1024;
+    let ttl = Duration::hours(config.idempotency_key_ttl_hours().max(1));
+    drop(config);
+
+    let bytes = match to_bytes(body, body_limit).await {
+        Ok(bytes) => bytes,
+        Err(_) => return AuthError::payload_too_large("Request body is too large").into_response(),
+    };
+    let hash = request_hash(&parts.method, parts.uri.path_and_query().map_or("", |path| path.as_str()), &bytes);
+
+    let claimed = match claim(&state, &scope, &key, &hash, ttl).await {
+        Ok(Claim::Handle(id)) => id,
+        Ok(Claim::Replay(row)) => return replay(row),
+        Err(e) => return e.into_response(),
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    store(&state, &claimed, response).await
+}
+
+/// Signed-out clients can't see or replay each other's responses, short of sending from the
+/// same address with the same user agent.
+fn anonymous_scope(client: &ClientInfo) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(client.ip_address.as_deref().unwrap_or_default());
+    hasher.update(b"\n");
+    hasher.update(client.user_agent.as_deref().unwrap_or_default());
+    format!("{}{}", ANONYMOUS_SCOPE_PREFIX, hex::encode(hasher.finalize()))
+}
+
+fn request_hash(method: &Method, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str());
+    hasher.update(b"\n");
+    hasher.update(path);
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+async fn claim(state: &AppState, scope: &str, key: &str, hash: &str, ttl: Duration) -> Result<Claim, AuthError> {
+    let mut conn = get_db_conn(state).map_err(|e| {
+        tracing::error!("Failed to get db connection: {}", e);
+        AuthError::database("Failed to connect to database")
+    })?;
+    let now = Utc::now().naive_utc();
+
+    let existing = IdempotencyKeys::find(&mut conn, scope, key).map_err(|e| {
+        tracing::error!("Failed to look up idempotency key: {}", e);
+        AuthError::database("Failed to look up idempotency key")
+    })?;
+    let mut stale = None;
+    if let Some(row) = existing {
+        let abandoned = row.status_code.is_none() && row.created_at < now - Duration::minutes(ABANDONED_AFTER_MINUTES);
+        if row.expires_at > now && !abandoned {
+            if row.request_hash != hash {
+                return Err(AuthError::invalid_field(
+                    "Idempotency-Key",
+                    "reused",
+                    "This Idempotency-Key was already used for a different request",
+                ));
+            }
+            if row.status_code.is_none() {
+                return Err(AuthError::conflict("A request with this Idempotency-Key is still being processed"));
+            }
+            return Ok(Claim::Replay(row));
+        }
+        stale = Some(row.id);
+    }
+
+    let row = IdempotencyKeys {
+        id: uuid::Uuid::new_v4().to_string(),
+        scope: scope.to_string(),
+        idempotency_key: key.to_string(),
+        request_hash: hash.to_string(),
+        status_code: None,
+        content_type: None,
+        body: None,
+        created_at: now,
+        expires_at: now + ttl,
+    };
+    let claimed = state.writes.write(&mut conn, |conn| {
+        if let Some(stale) = &stale {
+            IdempotencyKeys::release(conn, stale)?;
+        }
+        IdempotencyKeys::claim(conn, &row)
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to claim idempotency key: {}", e);
+        AuthError::database("Failed to claim idempotency key")
+    })?;
+    if !claimed {
+        // Another request with the key got in between the lookup and the insert.
+        return Err(AuthError::conflict("A request with this Idempotency-Key is still being processed"));
+    }
+    Ok(Claim::Handle(row.id))
+}
+
+fn replay(row: IdempotencyKeys) -> Response {
+    let status = row
+        .status_code
+        .and_then(|status| u16::try_from(status).ok())
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut response = (status, row.body.unwrap_or_default()).into_response();
+    let headers = response.headers_mut();
+    match row.content_type.as_deref().and_then(|content_type| HeaderValue::from_str(content_type).ok()) {
+        Some(content_type) => headers.insert(CONTENT_TYPE, content_type),
+        None => headers.remove(CONTENT_TYPE),
+    };
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    response
+}
+
+/// Keeps the response for retries, or frees the key when a retry should be handled afresh.
+async fn store(state: &AppState, id: &str, response: Response) -> Response {
+    let status = response.status();
+    let replayable = !status.is_server_error()
+        && status != StatusCode::TOO_MANY_REQUESTS
+        && !response.headers().contains_key(SET_COOKIE);
+
+    let mut conn = match get_db_conn(state) {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::error!("Failed to get db connection to store an idempotent response: {}", e);
+            return response;
+        }
+    };
+    if !replayable {
+        release(state, &mut conn, id).await;
+        return response;
+    }
+
+    // Responses to POSTs are small JSON documents, already serialized.
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to read a response to store it: {}", e);
+            release(state, &mut conn, id).await;
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let content_type = parts.headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok());
+    let stored = state.writes.write(&mut conn, |conn| {
+        IdempotencyKeys::complete(conn, id, i32::from(status.as_u16()), content_type, &bytes)
+    });
+    if let Err(e) = stored.await {
+        tracing::error!("Failed to store idempotent response: {}", e);
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Frees the key so a retry is handled afresh.
+async fn release(state: &AppState, conn: &mut SqliteConnection, id: &str) {
+    if let Err(e) = state.writes.write(conn, |conn| IdempotencyKeys::release(conn, id)).await {
+        tracing::error!("Failed to release idempotency key: {}", e);
+    }
+}
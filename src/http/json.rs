@@ -0,0 +1,146 @@
+use axum::body::Bytes;
+use axum::extract::{FromRequest, OptionalFromRequest, Request};
+use http::header::CONTENT_TYPE;
+use http::{HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::error::Category;
+
+use crate::errors::AuthError;
+
+/// A JSON request body, like axum's `Json`, but whose rejections are ours: a malformed body or
+/// one of the wrong shape is a validation error naming the field at fault, an oversized body
+/// is a 413, and a body that isn't JSON at all is a 415, all in the usual error envelope.
+/// Every API handler takes its body through this rather than `Json`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AppJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for AppJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_json(request.headers()) {
+            return Err(AuthError::unsupported_media_type("Expected a request with Content-Type: application/json"));
+        }
+        let bytes = Bytes::from_request(request, state).await.map_err(|rejection| match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => AuthError::payload_too_large("Request body is too large"),
+            _ => AuthError::validation(rejection.body_text()),
+        })?;
+        parse(&bytes).map(AppJson)
+    }
+}
+
+/// For bodies a route can do without: no `Content-Type` means no body.
+impl<T, S> OptionalFromRequest<S> for AppJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if !request.headers().contains_key(CONTENT_TYPE) {
+            return Ok(None);
+        }
+        <Self as FromRequest<S>>::from_request(request, state).await.map(Some)
+    }
+}
+
+/// `application/json`, or any `+json` type, with or without parameters.
+fn is_json(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    essence == "application/json" || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, AuthError> {
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(rejection)?;
+    deserializer.end().map_err(malformed)?;
+    Ok(value)
+}
+
+fn rejection(error: serde_path_to_error::Error<serde_json::Error>) -> AuthError {
+    let path = error.path().to_string();
+    let inner = error.into_inner();
+    if inner.classify() != Category::Data {
+        return malformed(inner);
+    }
+
+    // serde reports a missing field against the object it's missing from.
+    let message = without_position(&inner.to_string());
+    if let Some(missing) = message.strip_prefix("missing field `").and_then(|rest| rest.strip_suffix('`')) {
+        let field = if path == "." { missing.to_string() } else { format!("{path}.{missing}") };
+        return AuthError::invalid_field(&field, "required", format!("{field} is required"));
+    }
+    let field = if path == "." { "body" } else { path.as_str() };
+    AuthError::invalid_field(field, "invalid", message)
+}
+
+fn malformed(error: serde_json::Error) -> AuthError {
+    AuthError::validation(format!("Request body isn't valid JSON: {error}"))
+}
+
+/// serde_json ends its messages with where in the input it was, which means nothing to the
+/// client once the field is named.
+fn without_position(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Body {
+        title: String,
+        tags: Vec<String>,
+    }
+
+    fn field(error: AuthError) -> (String, String) {
+        match error {
+            AuthError::ValidationError { fields, .. } => (fields[0].field.clone(), fields[0].code.clone()),
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejections_name_the_field() {
+        assert!(parse::<Body>(br#"{"title": "a", "tags": []}"#).is_ok());
+
+        let missing = parse::<Body>(br#"{"tags": []}"#).unwrap_err();
+        assert_eq!(field(missing), ("title".to_string(), "required".to_string()));
+
+        let wrong_type = parse::<Body>(br#"{"title": "a", "tags": [1]}"#).unwrap_err();
+        assert_eq!(field(wrong_type), ("tags[0]".to_string(), "invalid".to_string()));
+
+        let malformed = parse::<Body>(br#"{"title": "#).unwrap_err();
+        assert!(matches!(malformed, AuthError::ValidationError { ref fields, .. } if fields.is_empty()));
+
+        let trailing = parse::<Body>(br#"{"title": "a", "tags": []} x"#).unwrap_err();
+        assert!(matches!(trailing, AuthError::ValidationError { ref fields, .. } if fields.is_empty()));
+    }
+
+    #[test]
+    fn json_content_types() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_json(&headers));
+        headers.insert(CONTENT_TYPE, "application/json; charset=utf-8".parse().unwrap());
+        assert!(is_json(&headers));
+        headers.insert(CONTENT_TYPE, "application/merge-patch+json".parse().unwrap());
+        assert!(is_json(&headers));
+        headers.insert(CONTENT_TYPE, "text/plain".parse().unwrap());
+        assert!(!is_json(&headers));
+    }
+}
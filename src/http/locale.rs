@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{FromRequestParts, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use http::header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_LENGTH};
+use http::request::Parts;
+use http::{HeaderMap, HeaderValue};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::errors::{ErrorDetails, ErrorKind, ErrorResponse};
+use crate::http::negotiation::{has_content_type, is_error, MAX_ERROR_BODY_BYTES};
+
+/// Languages API responses and pages can be served in. English is the source language; the
+/// others are translated from `locales/<tag>.json`. Page text has English in `locales/en.json`
+/// too, as templates refer to it by key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    Fr,
+    De,
+}
+
+#[derive(Deserialize, Default)]
+struct Translations {
+    #[serde(default)]
+    errors: ErrorTranslations,
+    /// Page and email text for the `t` template function, keyed by a dotted name like
+    /// `login.title`.
+    #[serde(default)]
+    pages: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Default)]
+struct ErrorTranslations {
+    /// Error code descriptions, keyed by code.
+    #[serde(default)]
+    codes: HashMap<String, String>,
+    /// Exact translations of specific error messages, keyed by the English message as sent.
+    #[serde(default)]
+    messages: HashMap<String, String>,
+    /// Validation messages for single fields, sent under `details.fields`, keyed by the English.
+    #[serde(default)]
+    fields: HashMap<String, String>,
+}
+
+static TRANSLATIONS: Lazy<HashMap<Locale, Translations>> = Lazy::new(|| {
+    Locale::ALL
+        .iter()
+        .map(|locale| {
+            let translations = serde_json::from_str(locale.source())
+                .unwrap_or_else(|e| panic!("locales/{}.json is invalid: {}", locale.tag(), e));
+            (*locale, translations)
+        })
+        .collect()
+});
+
+impl Locale {
+    pub const ALL: &'static [Locale] = &[Locale::En, Locale::Es, Locale::Fr, Locale::De];
+    pub const DEFAULT: Locale = Locale::En;
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Es => "es",
+            Self::Fr => "fr",
+            Self::De => "de",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Self::En => include_str!("../../locales/en.json"),
+            Self::Es => include_str!("../../locales/es.json"),
+            Self::Fr => include_str!("../../locales/fr.json"),
+            Self::De => include_str!("../../locales/de.json"),
+        }
+    }
+
+    /// Matches a language tag on its primary subtag, so `fr-CA` is served French.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next()?;
+        Self::ALL.iter().copied().find(|locale| locale.tag().eq_ignore_ascii_case(primary))
+    }
+
+    /// Picks the best supported locale from an `Accept-Language` header, honouring quality
+    /// values, and falls back to English.
+    pub fn negotiate(accept_language: &str) -> Self {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut params = range.split(';');
+                let tag = params.next()?.trim();
+                let quality = params
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(1.0, |q| q.trim().parse().unwrap_or(0.0));
+                (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges.into_iter().find_map(|(tag, _)| Self::from_tag(tag)).unwrap_or(Self::DEFAULT)
+    }
+
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map_or(Self::DEFAULT, Self::negotiate)
+    }
+
+    /// The user's chosen language, or English when they haven't picked one. For writing to
+    /// them outside a request.
+    pub fn of_user(user_locale: Option<&str>) -> Self {
+        user_locale.and_then(Self::from_tag).unwrap_or(Self::DEFAULT)
+    }
+
+    /// The user's chosen language when they have one, otherwise the browser's.
+    pub fn preferred(user_locale: Option<&str>, headers: &HeaderMap) -> Self {
+        user_locale.and_then(Self::from_tag).unwrap_or_else(|| Self::from_headers(headers))
+    }
+
+    fn translations(self) -> Option<&'static ErrorTranslations> {
+        TRANSLATIONS.get(&self).map(|translations| &translations.errors)
+    }
+
+    /// Page text for `key`, falling back to English and then to the key itself, so a missing
+    /// translation shows up on the page instead of breaking it.
+    pub fn text(self, key: &str) -> &str {
+        [self, Self::DEFAULT]
+            .into_iter()
+            .find_map(|locale| TRANSLATIONS.get(&locale)?.pages.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    /// [`Locale::text`] with each `{name}` in it replaced by the matching value.
+    pub fn format(self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter().fold(self.text(key).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+
+    /// An error message as sent, e.g. `Unauthorized: Invalid password`, if it has a translation.
+    pub fn message(self, message: &str) -> Option<&'static str> {
+        self.translations()?.messages.get(message).map(String::as_str)
+    }
+
+    /// A field's validation message in this locale, or as it was when there's no translation.
+    pub fn field_message(self, message: &str) -> &str {
+        self.translations()
+            .and_then(|translations| translations.fields.get(message))
+            .map_or(message, String::as_str)
+    }
+
+    /// The description of an error code in this locale, falling back to English.
+    pub fn describe(self, kind: ErrorKind) -> &'static str {
+        self.translations()
+            .and_then(|translations| translations.codes.get(kind.code()))
+            .map_or(kind.description(), String::as_str)
+    }
+
+    /// Translates an error's message. Messages without an exact translation are replaced
+    /// by the localized description of their code, and the original English message is kept
+    /// under `details.reason` so nothing is lost.
+    fn localize(self, error: &mut ErrorDetails) {
+        let Some(translations) = self.translations() else {
+            return;
+        };
+
+        let fields = error.details.as_mut().and_then(|details| details.get_mut("fields"));
+        if let Some(fields) = fields.and_then(serde_json::Value::as_array_mut) {
+            for field in fields {
+                if let Some(message) = field.get("message").and_then(|message| message.as_str()) {
+                    let translated = self.field_message(message).to_string();
+                    field["message"] = translated.into();
+                }
+            }
+        }
+
+        if let Some(message) = translations.messages.get(&error.message) {
+            error.message = message.clone();
+            return;
+        }
+
+        let Some(kind) = ErrorKind::from_code(&error.code) else {
+            return;
+        };
+        let original = std::mem::replace(&mut error.message, self.describe(kind).to_string());
+        match &mut error.details {
+            None => error.details = Some(json!({ "reason": original })),
+            Some(serde_json::Value::Object(details)) => {
+                details.insert("reason".to_string(), original.into());
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Locale {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
+
+/// `t(key="login.title", lang=locale)` in templates: page text in the page's language. Pages
+/// built on a `PageContext` have `locale` set; without `lang` the text is English. Any other
+/// argument fills in the `{placeholder}` of the same name, as `t(key=..., name=user)` does.
+pub struct Translate;
+
+impl tera::Function for Translate {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let key = args
+            .get("key")
+            .and_then(tera::Value::as_str)
+            .ok_or_else(|| tera::Error::msg("t() needs a `key` string argument"))?;
+        let locale = args
+            .get("lang")
+            .and_then(tera::Value::as_str)
+            .and_then(Locale::from_tag)
+            .unwrap_or(Locale::DEFAULT);
+        let values: Vec<(&str, String)> = args
+            .iter()
+            .filter(|(name, _)| !matches!(name.as_str(), "key" | "lang"))
+            .map(|(name, value)| {
+                let value = value.as_str().map_or_else(|| value.to_string(), str::to_string);
+                (name.as_str(), value)
+            })
+            .collect();
+        let values: Vec<(&str, &str)> = values.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        Ok(tera::Value::String(locale.format(key, &values)))
+    }
+}
+
+/// Translates JSON error envelopes into the language the client asked for with
+/// `Accept-Language`. Runs outside `json_errors` so rewritten framework errors are covered too.
+pub async fn localize_errors(request: Request, next: Next) -> Response {
+    let locale = Locale::from_headers(request.headers());
+    let response = next.run(request).await;
+
+    if locale == Locale::DEFAULT
+        || !is_error(response.status())
+        || !has_content_type(response.headers(), "application/json")
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("Failed to read error response for localization: {}", e);
+            parts.headers.remove(CONTENT_LENGTH);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+
+    let Ok(mut envelope) = serde_json::from_slice::<ErrorResponse>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    locale.localize(&mut envelope.error);
+
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(CONTENT_LANGUAGE, HeaderValue::from_static(locale.tag()));
+    Response::from_parts(parts, Json(envelope).into_response().into_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_locale_describes_every_error_code() {
+        for locale in Locale::ALL.iter().filter(|locale| **locale != Locale::DEFAULT) {
+            let translations = locale.translations().expect("translations load");
+            for kind in ErrorKind::ALL {
+                assert!(
+                    translations.codes.contains_key(kind.code()),
+                    "locales/{}.json has no description for {}",
+                    locale.tag(),
+                    kind.code()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn negotiates_by_quality_and_primary_subtag() {
+        assert_eq!(Locale::negotiate("fr-CA,fr;q=0.9,en;q=0.8"), Locale::Fr);
+        assert_eq!(Locale::negotiate("ja, de;q=0.5, es;q=0.7"), Locale::Es);
+        assert_eq!(Locale::negotiate("de;q=0, *"), Locale::En);
+        assert_eq!(Locale::negotiate(""), Locale::En);
+    }
+
+    #[test]
+    fn every_locale_has_every_page_text() {
+        let english = &TRANSLATIONS[&Locale::DEFAULT].pages;
+        for locale in Locale::ALL.iter().filter(|locale| **locale != Locale::DEFAULT) {
+            for key in english.keys() {
+                assert!(
+                    TRANSLATIONS[locale].pages.contains_key(key),
+                    "locales/{}.json has no page text for {}",
+                    locale.tag(),
+                    key
+                );
+            }
+        }
+        assert_eq!(Locale::Fr.text("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn a_users_choice_wins_over_the_browsers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("fr"));
+        assert_eq!(Locale::preferred(Some("de"), &headers), Locale::De);
+        assert_eq!(Locale::preferred(None, &headers), Locale::Fr);
+        assert_eq!(Locale::preferred(Some("ja"), &headers), Locale::Fr);
+    }
+
+    #[test]
+    fn field_messages_are_translated_in_place() {
+        let mut error = ErrorDetails {
+            code: ErrorKind::Validation.code().to_string(),
+            message: "Validation error: Invalid input".to_string(),
+            details: Some(json!({ "fields": [{ "field": "email", "message": "Email must be a valid email." }] })),
+        };
+        Locale::Es.localize(&mut error);
+        let field = &error.details.as_ref().unwrap()["fields"][0];
+        assert_eq!(field["message"], "El correo electrónico debe ser válido.");
+    }
+}
@@ -0,0 +1,19 @@
+pub mod assets;
+pub mod auth;
+pub mod client;
+pub mod conditional;
+pub mod forwarded;
+pub mod locale;
+pub mod negotiation;
+pub mod pagination;
+pub mod tx;
+pub mod rate_limit;
+pub mod openapi;
+pub mod cors;
+pub mod csrf;
+pub mod page_context;
+pub mod redirect;
+pub mod features;
+pub mod dto;
+pub mod idempotency;
+pub mod json;
\ No newline at end of file
@@ -0,0 +1,103 @@
+use axum::body::to_bytes;
+use axum::extract::{OriginalUri, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
+use http::{HeaderMap, StatusCode};
+
+use crate::errors::{error_response, ErrorKind};
+use crate::handlers::pages::status_page;
+use crate::state::AppState;
+
+pub const API_PREFIX: &str = "/api/v1";
+
+/// The read-only API for anonymous readers, throttled separately from the main one.
+pub const PUBLIC_API_PREFIX: &str = "/api/public/v1";
+
+/// Largest plain-text error body worth carrying over into a JSON error message.
+pub(crate) const MAX_ERROR_BODY_BYTES: usize = 16 * 1024;
+
+/// Whether the client asked for JSON rather than a page. Browsers always list `text/html`, so
+/// only an explicit `Accept: application/json` without it counts.
+pub fn prefers_json(headers: &HeaderMap) -> bool {
+    let accept = headers.get(ACCEPT).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    accept.contains("application/json") && !accept.contains("text/html")
+}
+
+/// Fallback for paths outside the API: the not-found page, or the JSON envelope when asked for.
+pub async fn not_found(State(state): State<AppState>, OriginalUri(uri): OriginalUri, headers: HeaderMap) -> Response {
+    if prefers_json(&headers) {
+        return api_not_found(OriginalUri(uri)).await;
+    }
+    status_page(&state, StatusCode::NOT_FOUND)
+}
+
+/// Fallback for unknown API paths, which always answer in JSON.
+pub async fn api_not_found(OriginalUri(uri): OriginalUri) -> Response {
+    error_response(StatusCode::NOT_FOUND, ErrorKind::NotFound, format!("No route for {}", uri.path()))
+}
+
+/// Rewrites API error responses that didn't come from `AuthError` — extractor rejections,
+/// 405s, body limit errors — into the JSON error envelope, so API clients never see plain text.
+pub async fn json_errors(response: Response) -> Response {
+    let status = response.status();
+    if !is_error(status) || has_content_type(response.headers(), "application/json") {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let message = match to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+        Ok(bytes) if !bytes.is_empty() => String::from_utf8_lossy(&bytes).trim().to_string(),
+        _ => status.canonical_reason().unwrap_or("Request failed").to_string(),
+    };
+
+    let rewritten = error_response(status, error_kind(status), message);
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(CONTENT_TYPE, rewritten.headers()[CONTENT_TYPE].clone());
+    Response::from_parts(parts, rewritten.into_body())
+}
+
+/// Replaces error responses on browser routes with a rendered error page, unless the client
+/// explicitly asked for JSON.
+pub async fn html_errors(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let wants_json = prefers_json(request.headers());
+    let response = next.run(request).await;
+
+    let status = response.status();
+    if wants_json || !is_error(status) || has_content_type(response.headers(), "text/html") {
+        return response;
+    }
+
+    let (mut parts, _) = response.into_parts();
+    let page = status_page(&state, status);
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(CONTENT_TYPE, page.headers()[CONTENT_TYPE].clone());
+    parts.status = page.status();
+    Response::from_parts(parts, page.into_body())
+}
+
+pub(crate) fn is_error(status: StatusCode) -> bool {
+    status.is_client_error() || status.is_server_error()
+}
+
+pub(crate) fn has_content_type(headers: &HeaderMap, expected: &str) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with(expected))
+}
+
+fn error_kind(status: StatusCode) -> ErrorKind {
+    match status {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => ErrorKind::Validation,
+        StatusCode::UNAUTHORIZED => ErrorKind::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorKind::Forbidden,
+        StatusCode::NOT_FOUND => ErrorKind::NotFound,
+        StatusCode::METHOD_NOT_ALLOWED => ErrorKind::MethodNotAllowed,
+        StatusCode::PAYLOAD_TOO_LARGE => ErrorKind::PayloadTooLarge,
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorKind::UnsupportedMediaType,
+        StatusCode::TOO_MANY_REQUESTS => ErrorKind::RateLimited,
+        status if status.is_server_error() => ErrorKind::Internal,
+        _ => ErrorKind::RequestFailed,
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+use crate::http::negotiation::{API_PREFIX, PUBLIC_API_PREFIX};
+
+/// Who may call an operation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    Public,
+    User,
+    Admin,
+}
+
+struct Operation {
+    method: &'static str,
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    access: Access,
+}
+
+const fn op(method: &'static str, path: &'static str, tag: &'static str, summary: &'static str, access: Access) -> Operation {
+    Operation { method, path, tag, summary, access }
+}
+
+use Access::{Admin, Public, User};
+
+/// Every JSON endpoint, relative to its API's prefix. Keep in step with `routes.rs`; the test
+/// below fails when a route is missing here or documented but gone.
+const API_OPERATIONS: &[Operation] = &[
+    op("post", "/auth/signup", "auth", "Create an account", Public),
+    op("post", "/auth/signin", "auth", "Sign in with email and password", Public),
+    op("post", "/auth/signout", "auth", "Sign out of this session", User),
+    op("post", "/auth/signout-all", "auth", "Sign out of every session and revoke access tokens", User),
+    op("post", "/auth/refresh", "auth", "Exchange the refresh cookie for a new access token", Public),
+    op("post", "/auth/reauth", "auth", "Confirm the password before a sensitive change", User),
+    op("get", "/auth/csrf", "auth", "Get the CSRF token cookie-authenticated requests send back", Public),
+    op("post", "/auth/impersonation/stop", "auth", "Stop impersonating a user and return to the admin's account", User),
+    op("get", "/auth/verify-email", "auth", "Verify an email address from its link", Public),
+    op("post", "/auth/forgot-password", "auth", "Email a password reset link", Public),
+    op("post", "/auth/reset-password", "auth", "Set a new password from a reset link", Public),
+    op("get", "/auth/captcha", "auth", "Captcha provider and the endpoints that need one", Public),
+    op("get", "/me", "me", "Get the user's own profile", User),
+    op("patch", "/me", "me", "Update the bio, website, location and privacy settings", User),
+    op("delete", "/me", "me", "Delete the account", User),
+    op("put", "/me/avatar", "me", "Upload an avatar", User),
+    op("delete", "/me/avatar", "me", "Remove the uploaded avatar", User),
+    op("get", "/me/blog-style", "me", "Get the blog's custom CSS and head HTML", User),
+    op("put", "/me/blog-style", "me", "Replace the blog's custom CSS and head HTML", User),
+    op("get", "/me/blog-style/versions", "me", "List earlier blog styles", User),
+    op("post", "/me/blog-style/versions/{version}/restore", "me", "Restore an earlier blog style", User),
+    op("put", "/me/email", "me", "Change the email address", User),
+    op("post", "/me/export", "me", "Request an archive of the account's data", User),
+    op("get", "/me/export/{id}", "me", "Get an export's status or download it", User),
+    op("get", "/me/onboarding", "me", "Get the onboarding checklist", User),
+    op("post", "/me/onboarding/dismiss", "me", "Hide the onboarding checklist", User),
+    op("put", "/me/password", "me", "Change the password", User),
+    op("get", "/me/preferences", "me", "Get preferences", User),
+    op("patch", "/me/preferences", "me", "Update preferences", User),
+    op("get", "/me/push-subscriptions", "me", "List Web Push subscriptions", User),
+    op("post", "/me/push-subscriptions", "me", "Subscribe a browser to Web Push", User),
+    op("delete", "/me/push-subscriptions", "me", "Unsubscribe a browser from Web Push", User),
+    op("get", "/me/posts", "me", "List the user's own posts", User),
+    op("get", "/me/reactions", "me", "List posts the user reacted to", User),
+    op("get", "/me/security/audit", "me", "List security events on the account", User),
+    op("get", "/me/sessions", "me", "List signed-in sessions", User),
+    op("get", "/me/tokens", "me", "List API tokens", User),
+    op("post", "/me/tokens", "me", "Create an API token", User),
+    op("delete", "/me/tokens/{id}", "me", "Revoke an API token", User),
+    op("get", "/me/webhooks", "me", "List webhooks", User),
+    op("post", "/me/webhooks", "me", "Create a webhook", User),
+    op("delete", "/me/webhooks/{id}", "me", "Delete a webhook", User),
+    op("get", "/me/webhooks/{id}/deliveries", "me", "List a webhook's recent deliveries", User),
+    op("post", "/posts/import", "posts", "Import posts from markdown files and zip archives", User),
+    op("post", "/posts", "posts", "Create a post", User),
+    op("get", "/posts/{id}", "posts", "Get a post", Public),
+    op("patch", "/posts/{id}", "posts", "Update a post", User),
+    op("delete", "/posts/{id}", "posts", "Delete a post", User),
+    op("get", "/posts/{id}/versions", "posts", "List a post's versions", User),
+    op("get", "/posts/{id}/collaborators", "posts", "List a post's collaborators", User),
+    op("post", "/posts/{id}/collaborators", "posts", "Invite a collaborator to a post", User),
+    op("delete", "/posts/{id}/collaborators/{user_id}", "posts", "Remove a collaborator from a post", User),
+    op("get", "/series", "series", "List your series", User),
+    op("post", "/series", "series", "Create a series", User),
+    op("get", "/series/{id}", "series", "Get a series and its posts", Public),
+    op("patch", "/series/{id}", "series", "Update a series", User),
+    op("delete", "/series/{id}", "series", "Delete a series", User),
+    op("put", "/series/{id}/posts", "series", "Set a series' posts and their order", User),
+    op("get", "/posts/{id}/lock", "posts", "Get who is editing a post", User),
+    op("post", "/posts/{id}/lock", "posts", "Take the edit lock on a post", User),
+    op("delete", "/posts/{id}/lock", "posts", "Release the edit lock", User),
+    op("post", "/posts/{id}/lock/heartbeat", "posts", "Keep the edit lock alive", User),
+    op("post", "/posts/{id}/lock/takeover", "posts", "Ask the lock holder to hand over", User),
+    op("post", "/posts/{id}/sync/commit", "posts", "Save the collaborative draft as a version", User),
+    op("post", "/posts/{id}/publish", "posts", "Publish or schedule a post", User),
+    op("post", "/posts/{id}/unpublish", "posts", "Return a post to draft", User),
+    op("get", "/posts/{id}/comments", "comments", "List a post's comments", Public),
+    op("post", "/posts/{id}/comments", "comments", "Comment on a post", User),
+    op("post", "/posts/{id}/react", "posts", "React to a post", User),
+    op("delete", "/posts/{id}/react", "posts", "Remove a reaction", User),
+    op("post", "/posts/{id}/report", "posts", "Report a post to moderators", User),
+    op("patch", "/comments/{id}", "comments", "Edit a comment", User),
+    op("delete", "/comments/{id}", "comments", "Delete a comment", User),
+    op("post", "/comments/{id}/report", "comments", "Report a comment to moderators", User),
+    op("post", "/uploads", "uploads", "Upload an image", User),
+    op("get", "/uploads/{id}", "uploads", "Get an upload", User),
+    op("put", "/uploads/{id}/alt-text", "uploads", "Set an upload's alt text", User),
+    op("post", "/uploads/{id}/alt-text/suggest", "uploads", "Suggest alt text for an upload", User),
+    op("get", "/admin/audit", "admin", "List audit log entries", Admin),
+    op("get", "/admin/backfills", "admin", "List backfill jobs", Admin),
+    op("post", "/admin/backfills", "admin", "Start a backfill job", Admin),
+    op("get", "/admin/backfills/missing", "admin", "Count posts missing derived metadata", Admin),
+    op("get", "/admin/backfills/{id}", "admin", "Get a backfill job", Admin),
+    op("post", "/admin/backfills/{id}/pause", "admin", "Pause a backfill job", Admin),
+    op("post", "/admin/backfills/{id}/resume", "admin", "Resume a backfill job", Admin),
+    op("get", "/admin/backups", "admin", "List database backups and the last backup run", Admin),
+    op("post", "/admin/backups/run", "admin", "Back up the database now", Admin),
+    op("get", "/admin/duplicates", "admin", "List near-duplicate posts", Admin),
+    op("get", "/admin/email-outbox", "admin", "Show the outgoing email queue and failed messages", Admin),
+    op("post", "/admin/email-outbox/{id}/retry", "admin", "Send a failed email again", Admin),
+    op("get", "/admin/email-suppressions", "admin", "List suppressed email addresses", Admin),
+    op("post", "/admin/email-suppressions/{email}/reactivate", "admin", "Lift an email suppression", Admin),
+    op("get", "/admin/feature-flags", "admin", "List feature flags and their overrides", Admin),
+    op("put", "/admin/feature-flags/{name}", "admin", "Turn a feature flag on or off", Admin),
+    op("delete", "/admin/feature-flags/{name}", "admin", "Return a feature flag to its configured default", Admin),
+    op("post", "/admin/impersonate/{user_id}", "admin", "Act as a user with a short-lived access token", Admin),
+    op("get", "/admin/jobs", "admin", "List scheduled jobs and their last runs", Admin),
+    op("post", "/admin/jobs/{name}/run", "admin", "Run a scheduled job now", Admin),
+    op("get", "/admin/pages", "admin", "List site pages", Admin),
+    op("post", "/admin/pages", "admin", "Create a site page", Admin),
+    op("get", "/admin/pages/{id}", "admin", "Get a site page", Admin),
+    op("patch", "/admin/pages/{id}", "admin", "Update a site page", Admin),
+    op("delete", "/admin/pages/{id}", "admin", "Delete a site page", Admin),
+    op("get", "/admin/pages/{id}/versions", "admin", "List a site page's versions", Admin),
+    op("post", "/admin/pages/{id}/versions/{version_id}/restore", "admin", "Restore a site page version", Admin),
+    op("get", "/admin/reports", "admin", "List reported posts and comments", Admin),
+    op("post", "/admin/reports/{id}/hide", "admin", "Hide reported content and tell its author", Admin),
+    op("post", "/admin/reports/{id}/dismiss", "admin", "Dismiss the reports on a post or comment", Admin),
+    op("post", "/admin/reports/{id}/restore", "admin", "Show hidden reported content again", Admin),
+    op("get", "/admin/retention", "admin", "Get retention policies and their last runs", Admin),
+    op("post", "/admin/retention/run", "admin", "Run retention policies now", Admin),
+    op("get", "/admin/search", "admin", "Search users, posts and sessions", Admin),
+    op("get", "/admin/users", "admin", "List users", Admin),
+    op("delete", "/admin/users/{id}", "admin", "Purge a user", Admin),
+    op("put", "/admin/users/{id}/blog-styles", "admin", "Allow or block a user's blog style", Admin),
+    op("post", "/admin/users/{id}/verify-email", "admin", "Mark a user's email verified", Admin),
+    op("post", "/admin/users/{id}/suspend", "admin", "Suspend a user", Admin),
+    op("post", "/admin/users/{id}/unsuspend", "admin", "Lift a user's suspension", Admin),
+    op("post", "/admin/users/{id}/trust", "admin", "Stop holding back a flagged user's comments", Admin),
+    op("post", "/admin/users/{id}/password-reset", "admin", "Email a user a password reset link", Admin),
+    op("post", "/webhooks/email/ses", "webhooks", "Receive Amazon SES bounce and complaint events", Public),
+    op("post", "/webhooks/email/mailgun", "webhooks", "Receive Mailgun bounce and complaint events", Public),
+    op("post", "/webhooks/email/postmark", "webhooks", "Receive Postmark bounce and complaint events", Public),
+    op("get", "/widgets/latest-posts", "widgets", "Latest posts for an embeddable widget", Public),
+    op("get", "/users/{username}", "users", "Get a user's profile", Public),
+    op("get", "/users/{username}/activity", "users", "Get a user's recent activity", Public),
+    op("post", "/users/{id}/follow", "users", "Follow a user", User),
+    op("delete", "/users/{id}/follow", "users", "Unfollow a user", User),
+    op("get", "/users/{id}/followers", "users", "List a user's followers", Public),
+    op("get", "/users/{id}/following", "users", "List who a user follows", Public),
+    op("get", "/notifications", "notifications", "List notifications", User),
+    op("get", "/notifications/unread-count", "notifications", "Count unread notifications", User),
+    op("post", "/notifications/read-all", "notifications", "Mark every notification read", User),
+    op("post", "/notifications/{id}/read", "notifications", "Mark a notification read", User),
+    op("get", "/feed", "posts", "Posts from followed authors", User),
+    op("get", "/digest/unsubscribe", "me", "Unsubscribe from the weekly digest by link", Public),
+    op("get", "/tags", "tags", "List tags", Public),
+    op("get", "/errors", "errors", "List the API's error codes", Public),
+];
+
+/// The read-only public API, relative to its prefix.
+const PUBLIC_API_OPERATIONS: &[Operation] = &[
+    op("get", "/posts", "public", "List published posts", Public),
+    op("get", "/posts/popular", "public", "List the most viewed posts", Public),
+    op("get", "/posts/{id}", "public", "Get a published post", Public),
+    op("get", "/users/{username}", "public", "Get an author's public profile", Public),
+    op("get", "/tags", "public", "List tags", Public),
+];
+
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+        .collect()
+}
+
+fn operation(op: &Operation) -> Value {
+    let mut responses = json!({
+        "200": {
+            "description": "Success",
+            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Envelope" } } },
+        },
+        "default": {
+            "description": "Error",
+            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } },
+        },
+    });
+    let mut operation = json!({
+        "tags": [op.tag],
+        "summary": op.summary,
+        "operationId": format!("{}{}", op.method, op.path.replace(['/', '{', '}', '-'], "_")),
+        "parameters": path_parameters(op.path),
+    });
+    if op.access != Access::Public {
+        operation["security"] = json!([{ "bearer": [] }, { "cookie": [] }]);
+        responses["401"] = json!({ "description": "Not signed in" });
+    }
+    if op.access == Access::Admin {
+        responses["403"] = json!({ "description": "Not an administrator" });
+    }
+    operation["responses"] = responses;
+    operation
+}
+
+/// An OpenAPI 3.1 description of the JSON APIs, for client generators and API explorers.
+/// `access_cookie` is the configured name of the cookie browser sessions authenticate with.
+pub fn document(public_url: &str, access_cookie: &str) -> Value {
+    let mut paths: BTreeMap<String, serde_json::Map<String, Value>> = BTreeMap::new();
+    for (prefix, operations) in [(API_PREFIX, API_OPERATIONS), (PUBLIC_API_PREFIX, PUBLIC_API_OPERATIONS)] {
+        for op in operations {
+            paths
+                .entry(format!("{}{}", prefix, op.path))
+                .or_default()
+                .insert(op.method.to_string(), operation(op));
+        }
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": { "title": "tsumi", "version": env!("CARGO_PKG_VERSION") },
+        "servers": [{ "url": public_url.trim_end_matches('/') }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer", "description": "An access token or API token" },
+                "cookie": { "type": "apiKey", "in": "cookie", "name": access_cookie },
+            },
+            "schemas": {
+                "Envelope": {
+                    "type": "object",
+                    "required": ["data"],
+                    "properties": { "data": {} },
+                },
+                "Error": {
+                    "type": "object",
+                    "required": ["error", "timestamp"],
+                    "properties": {
+                        "error": {
+                            "type": "object",
+                            "required": ["code", "message"],
+                            "properties": {
+                                "code": { "type": "string" },
+                                "message": { "type": "string" },
+                            },
+                        },
+                        "timestamp": { "type": "string", "format": "date-time" },
+                    },
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::*;
+
+    /// `(method, path)` for every route `routes.rs` registers under `root`, following `.nest`
+    /// into the `*_routes` functions. Development-only routes aren't documented.
+    fn registered(source: &str, root: &str) -> BTreeSet<(String, String)> {
+        let mut bodies: HashMap<&str, &str> = HashMap::new();
+        for chunk in source.split("\nfn ").skip(1) {
+            if let Some((name, body)) = chunk.split_once('(') {
+                bodies.insert(name, body);
+            }
+        }
+
+        let mut found = BTreeSet::new();
+        let mut pending = vec![(root.to_string(), String::new())];
+        while let Some((function, prefix)) = pending.pop() {
+            for line in bodies[function.as_str()].lines().map(str::trim) {
+                if let Some(rest) = line.strip_prefix(".nest(\"") {
+                    let (path, call) = rest.split_once("\", ").unwrap();
+                    let nested = call.split('(').next().unwrap();
+                    if nested != "dev_routes" {
+                        pending.push((nested.to_string(), format!("{}{}", prefix, path)));
+                    }
+                } else if let Some(rest) = line.strip_prefix(".route(\"") {
+                    let (path, handlers) = rest.split_once("\", ").unwrap();
+                    let path = format!("{}{}", prefix, path.trim_end_matches('/'));
+                    let path = if path.is_empty() { "/".to_string() } else { path };
+                    for method in ["get", "post", "put", "patch", "delete"] {
+                        if handlers.starts_with(&format!("{}(", method)) || handlers.contains(&format!(".{}(", method)) {
+                            found.insert((method.to_string(), path.clone()));
+                        }
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    fn documented(operations: &[Operation]) -> BTreeSet<(String, String)> {
+        operations.iter().map(|op| (op.method.to_string(), op.path.to_string())).collect()
+    }
+
+    #[test]
+    fn every_api_route_is_documented() {
+        let source = include_str!("../routes.rs");
+        assert_eq!(registered(source, "api_routes"), documented(API_OPERATIONS));
+        assert_eq!(registered(source, "public_api_routes"), documented(PUBLIC_API_OPERATIONS));
+    }
+
+    #[test]
+    fn path_parameters_are_declared() {
+        let document = document("https://example.com/", "access_token");
+        let restore = &document["paths"]["/api/v1/admin/pages/{id}/versions/{version_id}/restore"]["post"];
+        let names: Vec<&str> = restore["parameters"].as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["id", "version_id"]);
+        assert_eq!(document["servers"][0]["url"], "https://example.com");
+    }
+}
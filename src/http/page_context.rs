@@ -0,0 +1,91 @@
+use axum::extract::{FromRequestParts, OptionalFromRequestParts, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::request::Parts;
+use tera::Context;
+use tower_cookies::Cookies;
+
+use crate::errors::AuthError;
+use crate::handlers::pages::{insert_csrf, insert_features};
+use crate::http::auth::AuthUser;
+use crate::http::csrf::CsrfToken;
+use crate::http::dto::UserDto;
+use crate::http::locale::Locale;
+use crate::services::flash;
+use crate::state::AppState;
+
+/// What [`page_context`] leaves in the request for [`PageContext`] to pick up.
+#[derive(Clone)]
+struct BaseContext(Context);
+
+/// A page's template context with what every page shows already in it: `current_user` when
+/// someone is signed in, `impersonating` when that's an admin acting as them, the page's
+/// language tag as `locale`, the CSRF token as `csrf_token` and `csrf_field`, the feature
+/// flags as `features`, and the pending one-shot messages as `flash`. Handlers take it as
+/// `PageContext(mut ctx)` and add their own values.
+///
+/// The messages are used up when it's extracted, so only pages that show them clear them.
+/// Pages served from the shared cache mustn't take it, as it's specific to the visitor.
+pub struct PageContext(pub Context);
+
+/// Builds the base context for every request to a page route.
+pub async fn page_context(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let ctx = base_context(&state, &mut parts).await;
+    parts.extensions.insert(BaseContext(ctx));
+    next.run(Request::from_parts(parts, body)).await
+}
+
+/// A failure here only costs the page one of its shared values, never the page itself.
+async fn base_context(state: &AppState, parts: &mut Parts) -> Context {
+    let mut ctx = Context::new();
+
+    let auth = match <AuthUser as OptionalFromRequestParts<AppState>>::from_request_parts(parts, state).await {
+        Ok(auth) => auth,
+        Err(e) => {
+            tracing::warn!("Failed to resolve the signed-in user for a page: {}", e);
+            None
+        }
+    };
+    ctx.insert("impersonating", &auth.as_ref().is_some_and(|auth| auth.impersonator.is_some()));
+    let user = auth.map(|auth| auth.user);
+    let locale = Locale::preferred(user.as_ref().and_then(|user| user.locale.as_deref()), &parts.headers);
+    ctx.insert("locale", locale.tag());
+    if let Some(user) = user {
+        ctx.insert("current_user", &UserDto::from(user));
+    }
+
+    match CsrfToken::from_request_parts(parts, state).await {
+        Ok(csrf) => insert_csrf(&mut ctx, &csrf),
+        Err(e) => tracing::error!("Failed to issue a CSRF token for a page: {}", e),
+    }
+
+    insert_features(state, &mut ctx);
+    ctx
+}
+
+/// The language a page built on a [`PageContext`] is in.
+pub fn page_locale(ctx: &Context) -> Locale {
+    ctx.get("locale")
+        .and_then(|locale| locale.as_str())
+        .and_then(Locale::from_tag)
+        .unwrap_or(Locale::DEFAULT)
+}
+
+impl FromRequestParts<AppState> for PageContext {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let BaseContext(mut ctx) = parts
+            .extensions
+            .get::<BaseContext>()
+            .cloned()
+            .ok_or_else(|| AuthError::internal("Page context used on a route without the page_context layer"))?;
+
+        let cookies = Cookies::from_request_parts(parts, state)
+            .await
+            .map_err(|(_, message)| AuthError::internal(message))?;
+        ctx.insert("flash", &flash::take(&cookies, &state.config.load()));
+        Ok(PageContext(ctx))
+    }
+}
@@ -0,0 +1,96 @@
+use std::marker::PhantomData;
+
+use axum::extract::{FromRequestParts, Query};
+use http::request::Parts;
+use tsumi_types::ListQuery;
+
+use crate::errors::AuthError;
+use crate::state::AppState;
+
+pub use tsumi_types::{Paginated, SortDir};
+
+pub const DEFAULT_PER_PAGE: i64 = 20;
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// The sort keys a list endpoint accepts. Implemented by a marker type per endpoint and
+/// used as `ListParams<PostSort>`.
+pub trait Sortable {
+    /// Accepted `sort` values; the first is used when the caller doesn't pick one.
+    const SORT_FIELDS: &'static [&'static str];
+    const DEFAULT_DIR: SortDir = SortDir::Desc;
+}
+
+/// `page`, `per_page`, `sort` and `dir` query parameters, validated against the endpoint's
+/// sort keys. Endpoint-specific filters are read with a separate `Query` from the same
+/// query string.
+#[derive(Debug)]
+pub struct ListParams<S> {
+    pub page: i64,
+    pub per_page: i64,
+    pub sort: &'static str,
+    pub dir: SortDir,
+    sortable: PhantomData<fn() -> S>,
+}
+
+impl<S: Sortable> ListParams<S> {
+    fn from_raw(raw: ListQuery) -> Result<Self, AuthError> {
+        let page = raw.page.unwrap_or(1);
+        if page < 1 {
+            return Err(AuthError::validation("Page must be at least 1"));
+        }
+
+        let per_page = raw.per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(AuthError::validation(format!("per_page must be between 1 and {}", MAX_PER_PAGE)));
+        }
+
+        let sort = match raw.sort.as_deref() {
+            None | Some("") => S::SORT_FIELDS[0],
+            Some(sort) => S::SORT_FIELDS.iter().copied().find(|field| *field == sort).ok_or_else(|| {
+                AuthError::validation(format!("Unknown sort, expected one of: {}", S::SORT_FIELDS.join(", ")))
+            })?,
+        };
+
+        let dir = match raw.dir.as_deref() {
+            None | Some("") => S::DEFAULT_DIR,
+            Some("asc") => SortDir::Asc,
+            Some("desc") => SortDir::Desc,
+            Some(_) => return Err(AuthError::validation("dir must be 'asc' or 'desc'")),
+        };
+
+        Ok(Self { page, per_page, sort, dir, sortable: PhantomData })
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.per_page
+    }
+
+    /// `items`, the requested page of `total` results, with the metadata to ask for the next.
+    pub fn paginate<T>(&self, items: Vec<T>, total: i64) -> Paginated<T> {
+        Paginated {
+            items,
+            page: self.page,
+            per_page: self.per_page,
+            total,
+            total_pages: (total + self.per_page - 1) / self.per_page,
+            sort: self.sort.to_string(),
+            dir: self.dir,
+        }
+    }
+}
+
+impl<S: Sortable> FromRequestParts<AppState> for ListParams<S> {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Query(raw) = Query::<ListQuery>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AuthError::validation(e.body_text()))?;
+
+        Self::from_raw(raw)
+    }
+}
@@ -0,0 +1,108 @@
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http::HeaderValue;
+use lru::LruCache;
+
+use crate::errors::AuthError;
+use crate::http::forwarded::ClientAddr;
+
+/// Addresses tracked at once. The least recently seen are forgotten first, which at worst
+/// hands a long-idle client a fresh window.
+const TRACKED_CLIENTS: usize = 10_000;
+
+const X_RATELIMIT_LIMIT: &str = "x-ratelimit-limit";
+const X_RATELIMIT_REMAINING: &str = "x-ratelimit-remaining";
+
+/// Fixed-window request counting per client address, kept in memory. Requests without a
+/// known address share one window. The limit and window are read on every request, so they
+/// can follow a reloaded config.
+pub struct RateLimiter {
+    limits: Box<dyn Fn() -> (u32, Duration) + Send + Sync>,
+    windows: Mutex<LruCache<Option<IpAddr>, (u32, Instant)>>,
+}
+
+/// What the limiter decided for one request.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Decision {
+    Allowed { remaining: u32 },
+    Limited { retry_after: u64 },
+}
+
+impl RateLimiter {
+    pub fn new(limits: impl Fn() -> (u32, Duration) + Send + Sync + 'static) -> Arc<Self> {
+        let capacity = NonZeroUsize::new(TRACKED_CLIENTS).unwrap_or(NonZeroUsize::MIN);
+        Arc::new(Self { limits: Box::new(limits), windows: Mutex::new(LruCache::new(capacity)) })
+    }
+
+    pub fn limit(&self) -> u32 {
+        (self.limits)().0
+    }
+
+    pub fn check(&self, client: Option<IpAddr>, now: Instant) -> Decision {
+        let (limit, window) = (self.limits)();
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let (count, started) = windows.get_or_insert_mut(client, || (0, now));
+        if now.duration_since(*started) >= window {
+            *count = 0;
+            *started = now;
+        }
+
+        if *count >= limit {
+            let reset = window.saturating_sub(now.duration_since(*started));
+            return Decision::Limited { retry_after: reset.as_secs().max(1) };
+        }
+
+        *count += 1;
+        Decision::Allowed { remaining: limit - *count }
+    }
+}
+
+/// Rejects clients over the limit with a 429 and `Retry-After`, and tells everyone else how
+/// much of the window they have left.
+pub async fn throttle(
+    State(limiter): State<Arc<RateLimiter>>,
+    client: ClientAddr,
+    request: Request,
+    next: Next,
+) -> Response {
+    let remaining = match limiter.check(client.ip, Instant::now()) {
+        Decision::Allowed { remaining } => remaining,
+        Decision::Limited { retry_after } => {
+            tracing::info!("Rate limited {:?} for {}s", client.ip, retry_after);
+            let mut response = AuthError::rate_limited("Too many requests", retry_after).into_response();
+            response.headers_mut().insert(X_RATELIMIT_LIMIT, HeaderValue::from(limiter.limit()));
+            response.headers_mut().insert(X_RATELIMIT_REMAINING, HeaderValue::from(0));
+            return response;
+        }
+    };
+
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(X_RATELIMIT_LIMIT, HeaderValue::from(limiter.limit()));
+    response.headers_mut().insert(X_RATELIMIT_REMAINING, HeaderValue::from(remaining));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_each_client_per_window() {
+        let limiter = RateLimiter::new(|| (2, Duration::from_secs(60)));
+        let start = Instant::now();
+        let a = Some("192.0.2.1".parse().unwrap());
+        let b = Some("192.0.2.2".parse().unwrap());
+
+        assert_eq!(limiter.check(a, start), Decision::Allowed { remaining: 1 });
+        assert_eq!(limiter.check(a, start), Decision::Allowed { remaining: 0 });
+        assert_eq!(limiter.check(a, start + Duration::from_secs(15)), Decision::Limited { retry_after: 45 });
+        assert_eq!(limiter.check(b, start), Decision::Allowed { remaining: 1 });
+        assert_eq!(limiter.check(a, start + Duration::from_secs(60)), Decision::Allowed { remaining: 1 });
+    }
+}
@@ -0,0 +1,72 @@
+use url::Url;
+
+/// Where users land after signing in when they didn't come from anywhere in particular.
+pub const HOME: &str = "/";
+
+/// `next` as a path on this site, or `None` when following it could leave the site. Absolute
+/// URLs are accepted only on `public_url`'s origin; anything else, including scheme-relative
+/// `//host` paths and backslashes browsers read as slashes, is refused.
+pub fn safe_next(public_url: &str, next: &str) -> Option<String> {
+    let next = next.trim();
+    if next.is_empty() || next.chars().any(|c| c.is_control() || c == '\\') {
+        return None;
+    }
+
+    let base = Url::parse(public_url).ok()?;
+    let target = base.join(next).ok()?;
+    if target.origin() != base.origin() {
+        return None;
+    }
+
+    let mut path = target.path().to_string();
+    // Dot segments can collapse into a leading `//`, which browsers follow off-site.
+    if path.starts_with("//") {
+        return None;
+    }
+    if let Some(query) = target.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    if let Some(fragment) = target.fragment() {
+        path.push('#');
+        path.push_str(fragment);
+    }
+    Some(path)
+}
+
+/// `next` if it's safe to follow, the home page otherwise.
+pub fn next_or_home(public_url: &str, next: Option<&str>) -> String {
+    next.and_then(|next| safe_next(public_url, next)).unwrap_or_else(|| HOME.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITE: &str = "https://tsumi.test";
+
+    #[test]
+    fn paths_on_this_site_are_kept() {
+        assert_eq!(safe_next(SITE, "/posts?page=2#top").as_deref(), Some("/posts?page=2#top"));
+        assert_eq!(safe_next(SITE, "https://tsumi.test/ann/hello").as_deref(), Some("/ann/hello"));
+        assert_eq!(safe_next(SITE, "/a/../b").as_deref(), Some("/b"));
+    }
+
+    #[test]
+    fn anything_that_could_leave_the_site_is_refused() {
+        for next in [
+            "https://evil.test/",
+            "http://tsumi.test/",
+            "//evil.test",
+            "/\\evil.test",
+            "/.//evil.test",
+            "javascript:alert(1)",
+            "/posts\r\nSet-Cookie: a=b",
+            "",
+        ] {
+            assert_eq!(safe_next(SITE, next), None, "{:?}", next);
+        }
+        assert_eq!(next_or_home(SITE, Some("//evil.test")), HOME);
+        assert_eq!(next_or_home(SITE, None), HOME);
+    }
+}
@@ -0,0 +1,138 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{FromRequestParts, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use diesel::connection::{AnsiTransactionManager, TransactionManager};
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::SqliteConnection;
+use http::request::Parts;
+use tokio::sync::OwnedMutexGuard;
+
+use crate::db::write::begin_immediate;
+use crate::errors::AuthError;
+use crate::state::AppState;
+use crate::utils::get_db_conn;
+
+type PooledConn = PooledConnection<ConnectionManager<SqliteConnection>>;
+
+/// The transaction's connection, and its turn at the write lock.
+type Held = (PooledConn, OwnedMutexGuard<()>);
+
+/// Where a [`Tx`] hands its connection back once the handler is done with it, so
+/// [`transactions`] can finish the transaction after seeing the response.
+#[derive(Clone, Default)]
+struct TxSlot(Arc<Mutex<Option<Held>>>);
+
+impl TxSlot {
+    fn put(&self, held: Held) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(held);
+    }
+
+    fn take(&self) -> Option<Held> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+/// A database connection with a transaction open for the rest of the request.
+///
+/// The transaction is committed if the handler answers with a 2xx or 3xx, and rolled back
+/// for any error response, so a handler that returns early with `?` never leaves half of its
+/// writes behind. Use it in place of `get_db_conn` in handlers that write more than once;
+/// it derefs to the connection, so pass `&mut tx` to queries. Only one `Tx` can be extracted
+/// per request, and only on routes behind the [`transactions`] layer.
+///
+/// The request holds the process's [`WriteLock`](crate::db::write::WriteLock) from extraction
+/// until the transaction ends, and the transaction takes SQLite's write lock when it begins.
+pub struct Tx {
+    conn: Option<Held>,
+    slot: TxSlot,
+}
+
+impl Deref for Tx {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &SqliteConnection {
+        self.conn.as_ref().map(|(conn, _)| &**conn).expect("transaction connection is present until drop")
+    }
+}
+
+impl DerefMut for Tx {
+    fn deref_mut(&mut self) -> &mut SqliteConnection {
+        self.conn.as_mut().map(|(conn, _)| &mut **conn).expect("transaction connection is present until drop")
+    }
+}
+
+impl Drop for Tx {
+    fn drop(&mut self) {
+        if let Some(held) = self.conn.take() {
+            self.slot.put(held);
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for Tx {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        // Taking the slot out of the extensions makes a second `Tx` in the same request fail
+        // loudly instead of silently replacing the first.
+        let slot = parts.extensions.remove::<TxSlot>().ok_or_else(|| {
+            tracing::error!("Tx extracted on {} without a transactions layer, or twice", parts.uri.path());
+            AuthError::internal("Database transaction unavailable")
+        })?;
+
+        let turn = state.writes.acquire().await;
+        let mut conn = get_db_conn(state)
+            .map_err(|e| {
+                tracing::error!("Failed to get database connection for request transaction: {}", e);
+                AuthError::internal("Database connection failed")
+            })?;
+
+        begin_immediate(&mut conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to begin request transaction: {}", e);
+                AuthError::database("Failed to begin transaction")
+            })?;
+
+        Ok(Tx { conn: Some((conn, turn)), slot })
+    }
+}
+
+/// Commits or rolls back the transaction of any [`Tx`] the handler extracted, depending on
+/// the response status. A failed commit turns the response into a database error.
+///
+/// A connection that somehow comes back to the pool with its transaction still open is
+/// discarded by the pool rather than reused.
+pub async fn transactions(mut request: Request, next: Next) -> Response {
+    let slot = TxSlot::default();
+    request.extensions_mut().insert(slot.clone());
+
+    let response = next.run(request).await;
+
+    // The turn at the write lock is given up once this returns.
+    let Some((mut conn, _turn)) = slot.take() else {
+        return response;
+    };
+
+    let status = response.status();
+    if status.is_success() || status.is_redirection() {
+        if let Err(e) = AnsiTransactionManager::commit_transaction(&mut *conn) {
+            tracing::error!("Failed to commit request transaction: {}", e);
+            rollback(&mut conn);
+            return AuthError::database("Failed to save changes").into_response();
+        }
+    } else {
+        rollback(&mut conn);
+    }
+
+    response
+}
+
+fn rollback(conn: &mut SqliteConnection) {
+    if let Err(e) = AnsiTransactionManager::rollback_transaction(conn) {
+        tracing::error!("Failed to roll back request transaction: {}", e);
+    }
+}
@@ -0,0 +1,18 @@
+//! The server as a library, shared by the `tsumi` binary and the integration tests.
+
+use diesel_migrations::{embed_migrations, EmbeddedMigrations};
+
+pub mod app;
+pub mod commands;
+pub mod config;
+pub mod db;
+pub mod errors;
+pub mod handlers;
+pub mod http;
+pub mod routes;
+pub mod server;
+pub mod services;
+pub mod state;
+pub mod utils;
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/");
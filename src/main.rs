@@ -1,60 +1,124 @@
 extern crate core;
 
-use axum::serve;
 use std::net::{IpAddr, SocketAddr};
-use diesel::r2d2::{ConnectionManager, Pool};
-use diesel_migrations::{embed_migrations, EmbeddedMigrations};
+use clap::Parser;
 use tracing_subscriber::prelude::*;
-use tera::Tera;
-use tokio::net::TcpListener;
-use diesel::sqlite::SqliteConnection;
+use tracing_subscriber::{reload, EnvFilter};
+use tokio::sync::watch;
+use tracing_appender::non_blocking::WorkerGuard;
 
-mod config;
-mod handlers;
-mod db;
-mod services;
-mod state;
-mod routes;
-mod utils;
-mod errors;
-
-use crate::config::config;
-use crate::routes::app_router;
-use crate::state::AppState;
-
-pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/");
+use tsumi::{app, commands, config, server};
+use tsumi::commands::{Cli, Command, CommandResult};
+use tsumi::config::Config;
+use tsumi::routes::app_router;
+use tsumi::db::connection::build_pool;
+use tsumi::services::config_watcher::{apply_log_level, LogFilter};
+use tsumi::state::DbPool;
 
 #[tokio::main]
 async fn main() {
-    init_tracing();
-    let config = config().await;
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Serve);
+    let (log_guard, log_filter) = init_tracing(matches!(command, Command::Serve));
+    let config = config::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    apply_log_level(&log_filter, &config);
 
-    let manager = ConnectionManager::<SqliteConnection>::new(config.db_url().to_string());
-    let pool = Pool::builder().build(manager).expect("Failed to create pool.");
+    let code = match command {
+        Command::Serve => {
+            serve(&config, build_pool(&config), log_filter).await;
+            0
+        }
+        // Restoring replaces the database file, so no connection to it may be open.
+        Command::Restore { name, .. } => match commands::restore::run(&config, name.as_deref()).await {
+            Ok(_) => 0,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                1
+            }
+        },
+        command => run_command(command, &config, &build_pool(&config)).await.unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            1
+        }),
+    };
 
-    let tera = Tera::new("templates/**/*").unwrap_or_else(|_| panic!("Couldn't find templates"));
+    // Flushes buffered log lines before the process exits.
+    drop(log_guard);
+    std::process::exit(code);
+}
 
-    let app_state = AppState {
-        tera,
-        db_pool: pool,
-        config,
-    };
+/// Runs a one-off command, returning the process's exit code.
+async fn run_command(command: Command, config: &Config, pool: &DbPool) -> CommandResult<i32> {
+    let mut conn = pool.get()?;
+    match command {
+        Command::Serve | Command::Restore { .. } => unreachable!("handled before the pool is built"),
+        Command::Migrate => {
+            commands::migrate::run(&mut conn)?;
+        }
+        Command::CreateAdmin { email, name, password } => {
+            commands::create_admin::run(&mut conn, config, &email, name.as_deref(), password.as_deref())?;
+        }
+        Command::PurgeTokens => {
+            commands::purge_tokens::run(&mut conn)?;
+        }
+        Command::ExportOpenapi { output } => {
+            commands::export_openapi::run(config, output.as_deref())?;
+        }
+        Command::DedupeReport => {
+            let groups = commands::dedupe_report::run(&mut conn)?;
+            return Ok(if groups == 0 { 0 } else { 1 });
+        }
+        Command::Seed => {
+            commands::seed::run(&mut conn, config)?;
+        }
+        Command::Backup => {
+            commands::backup::run(config, pool).await?;
+        }
+    }
+    Ok(0)
+}
+
+async fn serve(config: &Config, pool: DbPool, log_filter: LogFilter) {
+    let app_state = app::build_state(config, pool, Some(log_filter));
+    let registry = app_state.services.clone();
+    registry.start_all().await.expect("Failed to start background services");
 
-    let app = app_router(app_state.clone());
+    let app = app_router(app_state);
 
     let addr = SocketAddr::from((
         config.server_host().parse::<IpAddr>().expect("Invalid IP Address"),
         config.server_port()
     ));
 
-    tracing::info!("Server listening at http://{}", addr);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        server::shutdown_signal().await;
+        shutdown_tx.send_replace(true);
+    });
+
+    let result = server::run(app, config, addr, shutdown_rx).await;
 
-    let listener = TcpListener::bind(addr).await.expect("Failed to bind");
-    serve(listener, app).await.expect("Failed to run server");
+    registry.stop_all().await;
+    result.expect("Failed to run server");
+    tracing::info!("Shutdown complete");
 }
 
-fn init_tracing() {
+/// Logs are written from a background thread; the returned guard flushes them when dropped.
+/// Commands other than the server log to stderr, keeping their stdout clean for output.
+/// Logging starts at `info` and switches to `LOG_LEVEL` once the config is loaded.
+fn init_tracing(serving: bool) -> (WorkerGuard, LogFilter) {
+    let (writer, guard) = if serving {
+        tracing_appender::non_blocking(std::io::stdout())
+    } else {
+        tracing_appender::non_blocking(std::io::stderr())
+    };
+    let (filter, log_filter) = reload::Layer::new(EnvFilter::new("info"));
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .init()
-}
\ No newline at end of file
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(writer))
+        .init();
+    (guard, log_filter)
+}
@@ -1,26 +1,226 @@
+use std::time::Duration;
+
 use axum::http::StatusCode;
-use axum::response::{Html, IntoResponse};
-use axum::{Router};
-use axum::extract::State;
-use axum::routing::{get, post};
-use tera::Context;
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use axum::extract::{DefaultBodyLimit, Query, State};
+use axum::middleware;
+use axum::routing::{delete, get, patch, post, put};
+use serde_json::json;
 use tower_cookies::CookieManagerLayer;
+use crate::handlers::activity::user_activity;
+use crate::handlers::digest::unsubscribe_digest;
+use crate::handlers::auth::captcha::captcha_settings;
+use crate::handlers::auth::csrf::csrf_token;
 use crate::handlers::auth::github::{github_oauth_callback, github_oauth_start};
+use crate::handlers::auth::ResetPasswordQuery;
+use crate::handlers::auth::password_reset::{forgot_password, reset_password};
+use crate::handlers::auth::reauth::reauth;
 use crate::handlers::auth::refresh::refresh;
 use crate::handlers::auth::signin::sign_in;
-use crate::handlers::auth::signout::sign_out;
+use crate::handlers::auth::signout::{sign_out, sign_out_all};
 use crate::handlers::auth::signup::sign_up;
+use crate::handlers::auth::verify::verify_email;
+use crate::handlers::comments::create::create_comment;
+use crate::handlers::comments::delete::delete_comment;
+use crate::handlers::comments::list::list_comments;
+use crate::handlers::comments::update::update_comment;
+use crate::handlers::dev::{preview_email, whoami};
+#[cfg(debug_assertions)]
+use crate::handlers::dev::seed_database;
+use crate::handlers::errors::list_error_codes;
+use crate::handlers::follows::{feed, follow_user, list_followers, list_following, unfollow_user};
+use crate::handlers::live::live_events;
+use crate::handlers::notifications::{
+    list_notifications, mark_all_notifications_read, mark_notification_read, unread_count,
+};
+use crate::handlers::admin::audit::list_audit_logs;
+use crate::handlers::admin::backfills::{
+    create_backfill, get_backfill, list_backfills, missing_metadata, pause_backfill, resume_backfill,
+};
+use crate::handlers::admin::backups::{backup_status, run_backup};
+use crate::handlers::admin::duplicates::list_duplicates;
+use crate::handlers::admin::email_outbox::{email_outbox_status, retry_email};
+use crate::handlers::admin::email_suppressions::{list_suppressions, reactivate_suppression};
+use crate::handlers::admin::feature_flags::{list_feature_flags, reset_feature_flag, set_feature_flag};
+use crate::handlers::admin::impersonation::{start_impersonation, stop_impersonation};
+use crate::handlers::admin::jobs::{list_jobs, run_job};
+use crate::handlers::admin::pages::{
+    create_page, delete_page, get_page, list_page_versions, list_pages, restore_page_version, update_page,
+};
+use crate::handlers::admin::reports::{dismiss_report, hide_reported, list_reports, restore_reported};
+use crate::handlers::admin::retention::{retention_status, run_retention};
+use crate::handlers::admin::search::search;
+use crate::handlers::admin::users::{
+    list_users, purge_user, send_password_reset, set_blog_styles, suspend_user, trust_user, unsuspend_user,
+    verify_user_email,
+};
+use crate::handlers::pages::auth::{login_form, login_page, register_form, register_page};
+use crate::handlers::pages::author::author_page;
+use crate::handlers::pages::page::static_page;
+use crate::handlers::pages::post::post_page;
+use crate::handlers::pages::posts::posts_page;
+use crate::handlers::pages::render;
+use crate::handlers::reports::{report_comment, report_post};
+use crate::handlers::series::{
+    create_series, delete_series, get_series, list_series, set_series_posts, update_series,
+};
+use crate::handlers::posts::collaborators::{invite_collaborator, list_collaborators, remove_collaborator};
+use crate::handlers::posts::create::create_post;
+use crate::handlers::posts::publish::{publish_post, unpublish_post};
+use crate::handlers::posts::react::{list_reacted_posts, react_post, unreact_post};
+use crate::handlers::posts::delete::delete_post;
+use crate::handlers::posts::import::import_posts;
+use crate::handlers::posts::get::{get_post, list_my_posts, list_post_versions};
+use crate::handlers::posts::lock::{acquire_lock, get_lock, heartbeat_lock, release_lock, request_lock_takeover};
+use crate::handlers::posts::sync::{commit_doc, sync_post};
+use crate::handlers::posts::update::update_post;
+use crate::handlers::public::posts::{get_public_post, list_popular_posts, list_public_posts};
+use crate::handlers::public::public_headers;
+use crate::handlers::public::users::{get_public_profile, get_user_profile};
+use crate::handlers::sitemap::{sitemap_chunk, sitemap_xml};
+use crate::handlers::tags::list_tags;
+use crate::handlers::avatars::serve_avatar;
+use crate::handlers::uploads::{create_upload, get_upload, serve_media, suggest_alt_text, update_alt_text};
+use crate::handlers::webhooks::email::{mailgun_webhook, postmark_webhook, ses_webhook};
+use crate::handlers::widgets::latest_posts::{latest_posts_embed, latest_posts_json};
+use crate::handlers::me::account::delete_account;
+use crate::handlers::me::avatar::{delete_avatar, update_avatar};
+use crate::handlers::me::blog_style::{get_blog_style, list_blog_style_versions, restore_blog_style_version, update_blog_style};
+use crate::handlers::me::email::update_email;
+use crate::handlers::me::export::{get_export, request_export};
+use crate::handlers::me::onboarding::{dismiss_onboarding, get_onboarding};
+use crate::handlers::me::password::update_password;
+use crate::handlers::me::preferences::{get_preferences, update_preferences};
+use crate::handlers::me::profile::{get_profile, update_profile};
+use crate::handlers::me::push_subscriptions::{
+    create_push_subscription, delete_push_subscription, list_push_subscriptions,
+};
+use crate::handlers::me::security::list_audit_log;
+use crate::handlers::me::sessions::list_sessions;
+use crate::handlers::nodeinfo::{nodeinfo_document, well_known_nodeinfo};
+use crate::handlers::me::tokens::{create_token, delete_token, list_tokens};
+use crate::handlers::me::webhooks::{create_webhook, delete_webhook, list_webhook_deliveries, list_webhooks};
+use crate::db::connection::pool_stats;
+use crate::http::assets::{static_assets, STATIC_PREFIX};
+use crate::http::conditional::conditional_get;
+use crate::http::idempotency::idempotency;
+use crate::http::forwarded::resolve_client;
+use crate::http::locale::localize_errors;
+use crate::http::page_context::{page_context, PageContext};
+use crate::http::negotiation::{api_not_found, html_errors, json_errors, not_found, API_PREFIX, PUBLIC_API_PREFIX};
+use crate::http::cors;
+use crate::http::csrf::verify_csrf;
+use crate::http::rate_limit::{throttle, RateLimiter};
+use crate::http::tx::transactions;
 use crate::state::AppState;
+use tower_http::compression::CompressionLayer;
 use tower_http::services::ServeDir;
 
+/// Browser pages live at the root and every JSON endpoint under `/api/v1`. Errors are
+/// negotiated per side: rendered pages for browsers, the JSON envelope for the API.
 pub fn app_router(state: AppState) -> Router {
+    let pages = Router::new()
+        .route("/", get(index))
+        .route("/login", get(login_page).post(login_form))
+        .route("/register", get(register_page).post(register_form))
+        .route("/reset-password", get(reset_password_page))
+        .route("/posts", get(posts_page))
+        .route("/auth/github", get(github_oauth_start))
+        .route("/auth/github/callback", get(github_oauth_callback))
+        .route("/widgets/latest-posts/embed", get(latest_posts_embed))
+        .route("/p/{slug}", get(static_page))
+        .route("/{username}", get(author_page))
+        .route("/{username}/{slug}", get(post_page))
+        .layer(middleware::from_fn_with_state(state.clone(), page_context))
+        .layer(middleware::from_fn_with_state(state.clone(), verify_csrf))
+        .layer(middleware::from_fn_with_state(state.clone(), html_errors));
+
     Router::new()
+        .merge(pages)
         .route("/healthz", get(health))
-        .route("/", get(index))
+        .route("/readyz", get(ready))
+        .route("/sitemap.xml", get(sitemap_xml))
+        .route("/sitemaps/{file}", get(sitemap_chunk))
+        .route("/.well-known/nodeinfo", get(well_known_nodeinfo))
+        .route("/nodeinfo/2.1", get(nodeinfo_document))
+        .route("/media/{id}", get(serve_media))
+        .route("/avatars/{id}", get(serve_avatar))
+        .route("/ws", get(live_events))
+        .route("/ws/posts/{id}/sync", get(sync_post))
+        .nest_service(STATIC_PREFIX, static_routes(&state))
+        .nest(API_PREFIX, api_routes(state.clone()))
+        .nest(PUBLIC_API_PREFIX, public_api_routes(state.clone()))
+        .fallback(not_found)
+        .layer(middleware::from_fn_with_state(state.clone(), resolve_client))
+        .with_state(state)
+        .layer(CookieManagerLayer::new())
+        .layer(CompressionLayer::new())
+}
+
+fn static_routes(state: &AppState) -> Router {
+    Router::new()
+        .fallback_service(ServeDir::new("static"))
+        .layer(middleware::from_fn_with_state(state.assets.clone(), static_assets))
+}
+
+fn api_routes(state: AppState) -> Router<AppState> {
+    // Route groups that take bigger bodies set their own limit, which wins over this one.
+    let body_limit = state.config.load().api_body_max_bytes();
+
+    let mut router = Router::new()
         .nest("/auth", auth_routes(state.clone()))
-        .route("/login", get(login_page))
-        .nest_service("/static", ServeDir::new("static"))
-        .fallback(handler_404)
+        .nest("/me", me_routes(state.clone()))
+        .nest("/posts", post_routes(state.clone()))
+        .nest("/comments", comment_routes(state.clone()))
+        .nest("/uploads", upload_routes(state.clone()))
+        .nest("/admin", admin_routes(state.clone()))
+        .nest("/webhooks", webhook_routes(state.clone()))
+        .nest("/widgets", widget_routes(state.clone()))
+        .nest("/users", user_routes(state.clone()))
+        .nest("/series", series_routes(state.clone()))
+        .nest("/notifications", notification_routes(state.clone()))
+        .route("/feed", get(feed))
+        .route("/digest/unsubscribe", get(unsubscribe_digest))
+        .route("/tags", get(list_tags))
+        .route("/errors", get(list_error_codes));
+
+    if state.config.load().is_development() {
+        router = router.nest("/dev", dev_routes(state.clone()));
+    }
+
+    router
+        .fallback(api_not_found)
+        .layer(DefaultBodyLimit::max(body_limit))
+        .layer(middleware::from_fn(transactions))
+        .layer(middleware::from_fn_with_state(state.clone(), idempotency))
+        .layer(middleware::from_fn_with_state(state.clone(), verify_csrf))
+        .layer(middleware::map_response(json_errors))
+        .layer(middleware::from_fn(localize_errors))
+        .layer(cors::layer(state.config.clone()))
+        .with_state(state)
+}
+
+/// Read-only endpoints for third-party readers and static-site builders. Nothing here reads
+/// cookies or tokens, and every client is throttled by address.
+fn public_api_routes(state: AppState) -> Router<AppState> {
+    let config = state.config.clone();
+    let limiter = RateLimiter::new(move || {
+        let config = config.load();
+        (config.public_api_rate_limit(), Duration::from_secs(config.public_api_rate_window_seconds()))
+    });
+
+    Router::new()
+        .route("/posts", get(list_public_posts).layer(middleware::from_fn(conditional_get)))
+        .route("/posts/popular", get(list_popular_posts))
+        .route("/posts/{id}", get(get_public_post).layer(middleware::from_fn(conditional_get)))
+        .route("/users/{username}", get(get_public_profile).layer(middleware::from_fn(conditional_get)))
+        .route("/tags", get(list_tags))
+        .fallback(api_not_found)
+        .layer(middleware::from_fn_with_state(limiter, throttle))
+        .layer(middleware::map_response(public_headers))
+        .layer(middleware::map_response(json_errors))
+        .layer(middleware::from_fn(localize_errors))
         .with_state(state)
 }
 
@@ -28,26 +228,31 @@ async fn health() -> impl IntoResponse {
     (StatusCode::OK, "Server is healthy")
 }
 
-
-async fn login_page(State(state): State<AppState>) -> Html<String> {
-    let ctx = Context::new();
-    match state.tera.render("login.html", &ctx) {
-        Ok(rendered) => Html(rendered),
-        Err(e) => {Html(format!("Error rendering template: {}", e))},
-    }
+/// Ready once every background service is running; load balancers should hold traffic until then.
+/// Also reports how busy the database pool is.
+async fn ready(State(state): State<AppState>) -> impl IntoResponse {
+    let status = if state.services.is_ready() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = json!({
+        "ready": status == StatusCode::OK,
+        "services": state.services.statuses(),
+        "database": pool_stats(&state.db_pool),
+    });
+    (status, Json(body))
 }
-async fn handler_404() -> impl IntoResponse {
-    (StatusCode::NOT_FOUND, "The requested resource was not found")
+
+
+async fn reset_password_page(
+    State(state): State<AppState>,
+    PageContext(mut ctx): PageContext,
+    Query(query): Query<ResetPasswordQuery>,
+) -> Response {
+    ctx.insert("token", &query.token);
+    render(&state, "reset-password.html", &ctx)
 }
 
-async fn index(State(state): State<AppState>) -> Html<String> {
-    let mut ctx = Context::new();
+async fn index(State(state): State<AppState>, PageContext(mut ctx): PageContext) -> Response {
     ctx.insert("name", "quantinium");
-
-    match state.tera.render("index.html", &ctx) {
-        Ok(rendered) => Html(rendered),
-        Err(e) => Html(format!("Error rendering template: {}", e)),
-    }
+    render(&state, "index.html", &ctx)
 }
 
 fn auth_routes(state: AppState) -> Router<AppState> {
@@ -55,9 +260,186 @@ fn auth_routes(state: AppState) -> Router<AppState> {
         .route("/signup", post(sign_up))
         .route("/signin", post(sign_in))
         .route("/signout", post(sign_out))
+        .route("/signout-all", post(sign_out_all))
         .route("/refresh", post(refresh))
-        .route("/github", get(github_oauth_start))
-        .route("/github/callback", get(github_oauth_callback))
+        .route("/reauth", post(reauth))
+        .route("/verify-email", get(verify_email))
+        .route("/forgot-password", post(forgot_password))
+        .route("/reset-password", post(reset_password))
+        .route("/captcha", get(captcha_settings))
+        .route("/csrf", get(csrf_token))
+        .route("/impersonation/stop", post(stop_impersonation))
+        .with_state(state)
+}
+
+fn me_routes(state: AppState) -> Router<AppState> {
+    // Leave room for the multipart framing around the image itself.
+    let avatar_body_limit = state.config.load().upload_max_bytes() + 64 * 1024;
+
+    Router::new()
+        .route("/avatar", put(update_avatar).delete(delete_avatar))
+        .layer(DefaultBodyLimit::max(avatar_body_limit))
+        .route("/", get(get_profile).layer(middleware::from_fn(conditional_get)).patch(update_profile).delete(delete_account))
+        .route("/blog-style", get(get_blog_style).put(update_blog_style))
+        .route("/blog-style/versions", get(list_blog_style_versions))
+        .route("/blog-style/versions/{version}/restore", post(restore_blog_style_version))
+        .route("/email", put(update_email))
+        .route("/export", post(request_export))
+        .route("/export/{id}", get(get_export))
+        .route("/onboarding", get(get_onboarding))
+        .route("/onboarding/dismiss", post(dismiss_onboarding))
+        .route("/password", put(update_password))
+        .route("/preferences", get(get_preferences).patch(update_preferences))
+        .route("/push-subscriptions", get(list_push_subscriptions).post(create_push_subscription).delete(delete_push_subscription))
+        .route("/posts", get(list_my_posts).layer(middleware::from_fn(conditional_get)))
+        .route("/reactions", get(list_reacted_posts))
+        .route("/security/audit", get(list_audit_log))
+        .route("/sessions", get(list_sessions))
+        .route("/tokens", get(list_tokens).post(create_token))
+        .route("/tokens/{id}", delete(delete_token))
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route("/webhooks/{id}", delete(delete_webhook))
+        .route("/webhooks/{id}/deliveries", get(list_webhook_deliveries))
+        .with_state(state)
+}
+
+fn post_routes(state: AppState) -> Router<AppState> {
+    // Leave room for the multipart framing around the files.
+    let import_body_limit = state.config.load().import_max_bytes() + 64 * 1024;
+    let post_body_limit = state.config.load().post_body_max_bytes();
+
+    // The innermost limit wins, so each group keeps its own.
+    Router::new()
+        .route("/", post(create_post))
+        .route("/{id}", get(get_post).layer(middleware::from_fn(conditional_get)).patch(update_post).delete(delete_post))
+        .route("/{id}/sync/commit", post(commit_doc))
+        .layer(DefaultBodyLimit::max(post_body_limit))
+        .route("/import", post(import_posts))
+        .layer(DefaultBodyLimit::max(import_body_limit))
+        .route("/{id}/versions", get(list_post_versions))
+        .route("/{id}/collaborators", get(list_collaborators).post(invite_collaborator))
+        .route("/{id}/collaborators/{user_id}", delete(remove_collaborator))
+        .route("/{id}/lock", get(get_lock).post(acquire_lock).delete(release_lock))
+        .route("/{id}/lock/heartbeat", post(heartbeat_lock))
+        .route("/{id}/lock/takeover", post(request_lock_takeover))
+        .route("/{id}/publish", post(publish_post))
+        .route("/{id}/unpublish", post(unpublish_post))
+        .route("/{id}/comments", get(list_comments).post(create_comment))
+        .route("/{id}/react", post(react_post).delete(unreact_post))
+        .route("/{id}/report", post(report_post))
+        .with_state(state)
+}
+
+fn user_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/{username}", get(get_user_profile).layer(middleware::from_fn(conditional_get)))
+        .route("/{username}/activity", get(user_activity))
+        .route("/{id}/follow", post(follow_user).delete(unfollow_user))
+        .route("/{id}/followers", get(list_followers))
+        .route("/{id}/following", get(list_following))
+        .with_state(state)
+}
+
+fn series_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_series).post(create_series))
+        .route("/{id}", get(get_series).patch(update_series).delete(delete_series))
+        .route("/{id}/posts", put(set_series_posts))
+        .with_state(state)
+}
+
+fn notification_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_notifications))
+        .route("/unread-count", get(unread_count))
+        .route("/read-all", post(mark_all_notifications_read))
+        .route("/{id}/read", post(mark_notification_read))
+        .with_state(state)
+}
+
+fn comment_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/{id}", patch(update_comment).delete(delete_comment))
+        .route("/{id}/report", post(report_comment))
+        .with_state(state)
+}
+
+fn upload_routes(state: AppState) -> Router<AppState> {
+    // Leave room for the multipart framing around the file itself.
+    let body_limit = state.config.load().upload_max_bytes() + 64 * 1024;
+
+    Router::new()
+        .route("/", post(create_upload))
+        .layer(DefaultBodyLimit::max(body_limit))
+        .route("/{id}", get(get_upload))
+        .route("/{id}/alt-text", put(update_alt_text))
+        .route("/{id}/alt-text/suggest", post(suggest_alt_text))
+        .with_state(state)
+}
+
+fn admin_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/audit", get(list_audit_logs))
+        .route("/backfills", get(list_backfills).post(create_backfill))
+        .route("/backfills/missing", get(missing_metadata))
+        .route("/backfills/{id}", get(get_backfill))
+        .route("/backfills/{id}/pause", post(pause_backfill))
+        .route("/backfills/{id}/resume", post(resume_backfill))
+        .route("/backups", get(backup_status))
+        .route("/backups/run", post(run_backup))
+        .route("/duplicates", get(list_duplicates))
+        .route("/email-outbox", get(email_outbox_status))
+        .route("/email-outbox/{id}/retry", post(retry_email))
+        .route("/email-suppressions", get(list_suppressions))
+        .route("/email-suppressions/{email}/reactivate", post(reactivate_suppression))
+        .route("/feature-flags", get(list_feature_flags))
+        .route("/feature-flags/{name}", put(set_feature_flag).delete(reset_feature_flag))
+        .route("/impersonate/{user_id}", post(start_impersonation))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/{name}/run", post(run_job))
+        .route("/pages", get(list_pages).post(create_page))
+        .route("/pages/{id}", get(get_page).patch(update_page).delete(delete_page))
+        .route("/pages/{id}/versions", get(list_page_versions))
+        .route("/pages/{id}/versions/{version_id}/restore", post(restore_page_version))
+        .route("/reports", get(list_reports))
+        .route("/reports/{id}/hide", post(hide_reported))
+        .route("/reports/{id}/dismiss", post(dismiss_report))
+        .route("/reports/{id}/restore", post(restore_reported))
+        .route("/retention", get(retention_status))
+        .route("/retention/run", post(run_retention))
+        .route("/search", get(search))
+        .route("/users", get(list_users))
+        .route("/users/{id}", delete(purge_user))
+        .route("/users/{id}/blog-styles", put(set_blog_styles))
+        .route("/users/{id}/verify-email", post(verify_user_email))
+        .route("/users/{id}/suspend", post(suspend_user))
+        .route("/users/{id}/unsuspend", post(unsuspend_user))
+        .route("/users/{id}/trust", post(trust_user))
+        .route("/users/{id}/password-reset", post(send_password_reset))
+        .with_state(state)
+}
+
+fn dev_routes(state: AppState) -> Router<AppState> {
+    let router = Router::new()
+        .route("/whoami", get(whoami))
+        .route("/emails/{template}", get(preview_email));
+    // Seeding writes made-up accounts with a published password, so release builds leave it
+    // out even when `APP_ENV=development`.
+    #[cfg(debug_assertions)]
+    let router = router.route("/seed", post(seed_database));
+    router.with_state(state)
+}
+
+fn webhook_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/email/ses", post(ses_webhook))
+        .route("/email/mailgun", post(mailgun_webhook))
+        .route("/email/postmark", post(postmark_webhook))
+        .with_state(state)
+}
+
+fn widget_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/latest-posts", get(latest_posts_json))
         .with_state(state)
-        .layer(CookieManagerLayer::new())
 }
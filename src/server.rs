@@ -0,0 +1,127 @@
+use std::future::IntoFuture;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use axum::extract::Request;
+use axum::response::Redirect;
+use axum::{serve, Router};
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+use http::header::HOST;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+use crate::config::Config;
+
+/// Serves the app until `shutdown` flips, over TLS when a certificate is configured and plain
+/// HTTP otherwise. In-flight requests get the configured drain timeout to finish.
+pub async fn run(app: Router, config: &Config, addr: SocketAddr, shutdown: watch::Receiver<bool>) -> io::Result<()> {
+    let drain_timeout = Duration::from_secs(config.shutdown_timeout_seconds());
+
+    let Some((cert_path, key_path)) = config.tls_cert_and_key() else {
+        tracing::info!("Server listening at http://{}", addr);
+        return serve_plain(app, addr, shutdown, drain_timeout).await;
+    };
+
+    // Several dependencies pull in rustls with different crypto backends, so pick one
+    // explicitly. An error only means a provider was already installed.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let tls = RustlsConfig::from_pem_file(cert_path, key_path).await?;
+
+    if let Some(port) = config.http_redirect_port() {
+        let redirect_addr = SocketAddr::new(addr.ip(), port);
+        let https_port = addr.port();
+        let redirects = Router::new().fallback(move |request: Request| async move { https_redirect(&request, https_port) });
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            tracing::info!("Redirecting http://{} to HTTPS", redirect_addr);
+            if let Err(e) = serve_plain(redirects, redirect_addr, shutdown, drain_timeout).await {
+                tracing::error!("HTTP redirect listener failed: {}", e);
+            }
+        });
+    }
+
+    let handle = Handle::new();
+    let stopper = handle.clone();
+    tokio::spawn(async move {
+        shutdown_requested(shutdown).await;
+        stopper.graceful_shutdown(Some(drain_timeout));
+    });
+
+    tracing::info!("Server listening at https://{}", addr);
+    axum_server::bind_rustls(addr, tls)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+}
+
+async fn serve_plain(app: Router, addr: SocketAddr, shutdown: watch::Receiver<bool>, drain_timeout: Duration) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+
+    // Once shutdown is requested the listener stops accepting and in-flight requests get the
+    // drain timeout to finish; whatever is still running after that is dropped.
+    let server = serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_requested(shutdown.clone()))
+        .into_future();
+    let drain_deadline = async {
+        shutdown_requested(shutdown).await;
+        tokio::time::sleep(drain_timeout).await;
+    };
+
+    tokio::select! {
+        result = server => result,
+        _ = drain_deadline => {
+            tracing::warn!("In-flight requests on {} did not finish within {:?}, shutting down anyway", addr, drain_timeout);
+            Ok(())
+        }
+    }
+}
+
+/// Resolves on SIGINT (Ctrl+C) or SIGTERM.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c().await.expect("Failed to listen for SIGINT");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => tracing::info!("Received SIGINT, shutting down"),
+        _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+    }
+}
+
+/// Permanent redirect to the same host and path on the HTTPS port.
+fn https_redirect(request: &Request, https_port: u16) -> Redirect {
+    let host = request
+        .headers()
+        .get(HOST)
+        .and_then(|value| value.to_str().ok())
+        .map(strip_port)
+        .unwrap_or("localhost");
+    let authority = if https_port == 443 { host.to_string() } else { format!("{}:{}", host, https_port) };
+    let path = request.uri().path_and_query().map_or("/", |path| path.as_str());
+
+    Redirect::permanent(&format!("https://{}{}", authority, path))
+}
+
+fn strip_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        Some((name, port)) if !port.ends_with(']') => name,
+        _ => host,
+    }
+}
+
+async fn shutdown_requested(mut shutdown: watch::Receiver<bool>) {
+    // An error means the sender is gone, which only happens after it has sent.
+    let _ = shutdown.wait_for(|requested| *requested).await;
+}
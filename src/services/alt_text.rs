@@ -0,0 +1,209 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::Config;
+use crate::db::models::upload::Uploads;
+use crate::errors::AuthError;
+use crate::services::job_locks::Leases;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+use crate::services::storage::Storage;
+use crate::state::DbPool;
+
+/// Attempts per upload before the worker gives up on suggesting alt text for it.
+const MAX_ATTEMPTS: i32 = 3;
+const BATCH_SIZE: i64 = 10;
+const CAPTION_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longer suggestions are cut; alt text should be a sentence, not a description.
+const MAX_SUGGESTION_CHARS: usize = 500;
+
+/// Describes an image in a sentence suitable for its `alt` attribute. Implemented per backend
+/// so no particular captioning vendor is baked in.
+#[async_trait]
+pub trait Captioner: Send + Sync {
+    async fn suggest_alt_text(&self, content_type: &str, image: Vec<u8>) -> Result<String, AuthError>;
+}
+
+/// The configured captioning backend, if any.
+pub fn from_config(config: &Config) -> Option<Arc<dyn Captioner>> {
+    let url = config.alt_text_captioner_url()?;
+    tracing::info!("Suggesting alt text with the captioning backend at {}", url);
+    Some(Arc::new(HttpCaptioner::new(url, config.alt_text_captioner_api_key())))
+}
+
+/// A captioning service behind a small HTTP contract: the image is POSTed as the raw request
+/// body with its `Content-Type`, and the service answers `{"alt_text": "..."}`. An API key, if
+/// configured, is sent as a bearer token.
+pub struct HttpCaptioner {
+    http: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CaptionResponse {
+    alt_text: String,
+}
+
+impl HttpCaptioner {
+    pub fn new(url: &str, api_key: Option<&str>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.to_string(),
+            api_key: api_key.map(str::to_string),
+        }
+    }
+}
+
+#[async_trait]
+impl Captioner for HttpCaptioner {
+    async fn suggest_alt_text(&self, content_type: &str, image: Vec<u8>) -> Result<String, AuthError> {
+        let mut request = self.http
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .timeout(CAPTION_TIMEOUT)
+            .body(image);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await
+            .map_err(|e| AuthError::internal(format!("Captioning request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AuthError::internal(format!("Captioning backend answered {}", response.status())));
+        }
+
+        let caption: CaptionResponse = response.json().await
+            .map_err(|e| AuthError::internal(format!("Invalid captioning response: {}", e)))?;
+        Ok(caption.alt_text)
+    }
+}
+
+/// Works through uploads waiting for an alt text suggestion and stores what the captioning
+/// backend proposes for the author to review. Only registered when a backend is configured.
+pub struct AltTextWorker {
+    period: Duration,
+    pool: DbPool,
+    storage: Arc<dyn Storage>,
+    captioner: Arc<dyn Captioner>,
+    leases: Leases,
+    tasks: Tasks,
+}
+
+impl AltTextWorker {
+    pub fn new(config: &Config, pool: DbPool, storage: Arc<dyn Storage>, captioner: Arc<dyn Captioner>, leases: Leases) -> Self {
+        Self {
+            period: Duration::from_secs(config.alt_text_poll_interval_seconds().max(1)),
+            pool,
+            storage,
+            captioner,
+            leases,
+            tasks: Tasks::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Service for AltTextWorker {
+    fn name(&self) -> &'static str {
+        "alt-text-worker"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let period = self.period;
+        let pool = self.pool.clone();
+        let storage = self.storage.clone();
+        let captioner = self.captioner.clone();
+        let mut lease = self.leases.lease(self.name());
+
+        self.tasks.spawn(|mut shutdown| async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.wait() => break,
+                }
+                if !lease.hold().await {
+                    continue;
+                }
+
+                let pending = {
+                    let pool = pool.clone();
+                    tokio::task::spawn_blocking(move || {
+                        let mut conn = pool.get().map_err(|e| e.to_string())?;
+                        Uploads::pending_alt_text(&mut conn, BATCH_SIZE).map_err(|e| e.to_string())
+                    })
+                    .await
+                };
+
+                let pending = match pending {
+                    Ok(Ok(pending)) => pending,
+                    Ok(Err(e)) => {
+                        tracing::error!("Failed to load uploads awaiting alt text: {}", e);
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::error!("Alt text lookup task panicked: {}", e);
+                        continue;
+                    }
+                };
+
+                for upload in pending {
+                    suggest(&pool, storage.as_ref(), captioner.as_ref(), upload).await;
+                }
+            }
+
+            lease.release().await;
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
+
+async fn suggest(pool: &DbPool, storage: &dyn Storage, captioner: &dyn Captioner, upload: Uploads) {
+    let suggestion = match storage.get(&upload.storage_key).await {
+        Ok(Some(bytes)) => captioner.suggest_alt_text(&upload.content_type, bytes).await,
+        Ok(None) => Err(AuthError::not_found(&upload.storage_key)),
+        Err(e) => Err(e),
+    };
+
+    let suggestion = suggestion.map(|text| text.trim().chars().take(MAX_SUGGESTION_CHARS).collect::<String>());
+    let give_up = upload.alt_text_attempts + 1 >= MAX_ATTEMPTS;
+
+    let pool = pool.clone();
+    let id = upload.id.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| e.to_string())?;
+        match suggestion {
+            Ok(text) if !text.is_empty() => Uploads::record_alt_text_suggestion(&mut conn, &id, &text).map(|_| None),
+            Ok(_) => Uploads::record_alt_text_failure(&mut conn, &id, give_up)
+                .map(|_| Some("the backend returned an empty suggestion".to_string())),
+            Err(e) => Uploads::record_alt_text_failure(&mut conn, &id, give_up).map(|_| Some(e.to_string())),
+        }
+        .map_err(|e| e.to_string())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(None)) => tracing::info!("Suggested alt text for upload {}", upload.id),
+        Ok(Ok(Some(reason))) if give_up => {
+            tracing::warn!("Giving up on alt text for upload {}: {}", upload.id, reason)
+        }
+        Ok(Ok(Some(reason))) => tracing::warn!("Failed to suggest alt text for upload {}: {}", upload.id, reason),
+        Ok(Err(e)) => tracing::error!("Failed to record alt text for upload {}: {}", upload.id, e),
+        Err(e) => tracing::error!("Alt text task panicked: {}", e),
+    }
+}
@@ -0,0 +1,51 @@
+use base64::Engine;
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::errors::AuthError;
+
+pub const API_TOKEN_PREFIX: &str = "tsumi_";
+
+pub const SCOPE_PROFILE_READ: &str = "profile:read";
+pub const SCOPE_PROFILE_WRITE: &str = "profile:write";
+pub const SCOPE_POSTS_READ: &str = "posts:read";
+pub const SCOPE_POSTS_WRITE: &str = "posts:write";
+
+pub const SCOPES: &[&str] = &[
+    SCOPE_PROFILE_READ,
+    SCOPE_PROFILE_WRITE,
+    SCOPE_POSTS_READ,
+    SCOPE_POSTS_WRITE,
+];
+
+/// Number of characters of the plaintext token kept for display, including the prefix.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+pub fn generate_api_token() -> String {
+    let bytes: [u8; 32] = rand::rng().random();
+    format!("{}{}", API_TOKEN_PREFIX, BASE64_URL_SAFE_NO_PAD.encode(bytes))
+}
+
+pub fn is_api_token(token: &str) -> bool {
+    token.starts_with(API_TOKEN_PREFIX)
+}
+
+pub fn hash_api_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn display_prefix(token: &str) -> String {
+    token.chars().take(DISPLAY_PREFIX_LEN).collect()
+}
+
+pub fn validate_scopes(scopes: &[String]) -> Result<(), AuthError> {
+    if scopes.is_empty() {
+        return Err(AuthError::validation("At least one scope is required"));
+    }
+
+    match scopes.iter().find(|scope| !SCOPES.contains(&scope.as_str())) {
+        Some(unknown) => Err(AuthError::validation(format!("Unknown scope '{}'", unknown))),
+        None => Ok(()),
+    }
+}
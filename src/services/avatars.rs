@@ -0,0 +1,311 @@
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageReader, Limits};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::db::models::avatar_source::AvatarSources;
+use crate::db::models::user_model::UserModel;
+use crate::services::cache::{self, Cache};
+use crate::services::images::sniff;
+use crate::services::storage::Storage;
+
+/// Sizes, in pixels, avatars are served at. Other requested sizes snap to the next one up.
+pub const AVATAR_SIZES: [u32; 4] = [32, 64, 128, 256];
+pub const DEFAULT_AVATAR_SIZE: u32 = 64;
+
+/// Image types avatars can be uploaded as. GIFs aren't, as resizing would keep only their
+/// first frame.
+pub const UPLOAD_TYPES: &[&str] = &["image/png", "image/jpeg", "image/webp"];
+
+/// Uploaded images wider or taller than this are refused before they're decoded.
+const MAX_UPLOAD_DIMENSION: u32 = 4096;
+
+/// Upstream images larger than this are refused rather than proxied.
+const MAX_AVATAR_BYTES: usize = 512 * 1024;
+
+/// Hosts avatars are fetched from, redirects included. Anything else is refused, so a source
+/// row can't turn the proxy into a way to fetch arbitrary URLs.
+const UPSTREAM_HOSTS: &[&str] = &[
+    "gravatar.com",
+    "www.gravatar.com",
+    "secure.gravatar.com",
+    "avatars.githubusercontent.com",
+];
+
+const MAX_REDIRECTS: usize = 3;
+
+/// Cached avatars are kept this many TTLs past their fetch, so one can still be served while
+/// its upstream is unreachable.
+const STALE_TTL_FACTOR: u32 = 7;
+
+/// The variant of `requested` to serve: the smallest configured size at least as big.
+pub fn snap_size(requested: Option<u32>) -> u32 {
+    let Some(requested) = requested else {
+        return DEFAULT_AVATAR_SIZE;
+    };
+    AVATAR_SIZES
+        .into_iter()
+        .find(|size| *size >= requested)
+        .unwrap_or(AVATAR_SIZES[AVATAR_SIZES.len() - 1])
+}
+
+/// Where the user's avatar lives upstream: their GitHub avatar when they've linked GitHub,
+/// and their Gravatar otherwise, with an identicon for emails Gravatar doesn't know.
+fn upstream_url(conn: &mut SqliteConnection, user: &UserModel) -> QueryResult<String> {
+    if let Some(github_id) = UserModel::linked_account_id(conn, &user.id, "github")? {
+        return Ok(format!("https://avatars.githubusercontent.com/u/{}", github_id));
+    }
+    let email_hash = hex::encode(Sha256::digest(user.email.trim().to_lowercase().as_bytes()));
+    Ok(format!("https://gravatar.com/avatar/{}?d=identicon", email_hash))
+}
+
+/// The user's avatar as served by this site, e.g. `/avatars/3f2a…`: the one they uploaded, or
+/// their upstream one through the proxy. Readers' browsers only ever see this path, never the
+/// upstream URL.
+pub fn avatar_path(conn: &mut SqliteConnection, user: &UserModel) -> QueryResult<String> {
+    if let Some(avatar_url) = &user.avatar_url {
+        return Ok(avatar_url.clone());
+    }
+    let upstream = upstream_url(conn, user)?;
+    let source = AvatarSources::find_or_create(conn, &user.id, &upstream)?;
+    Ok(format!("/avatars/{}", source.id))
+}
+
+/// Where an uploaded avatar is served. Its id is a fresh random one for every upload, so the
+/// path never points at a different image.
+pub fn uploaded_path(id: &str) -> String {
+    format!("/avatars/{}", id)
+}
+
+/// The id of an uploaded avatar, from the path stored on its user.
+pub fn uploaded_id(avatar_url: &str) -> Option<&str> {
+    avatar_url.strip_prefix("/avatars/")
+}
+
+/// Storage key of an uploaded avatar at one of [`AVATAR_SIZES`].
+pub fn variant_key(id: &str, size: u32) -> String {
+    format!("avatars/{}/{}.png", id, size)
+}
+
+/// Decodes an uploaded image and renders it as a PNG at every size in [`AVATAR_SIZES`],
+/// cropped to a centred square.
+pub fn resize(bytes: &[u8]) -> Result<Vec<(u32, Vec<u8>)>, String> {
+    let mut reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format().map_err(|e| e.to_string())?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_UPLOAD_DIMENSION);
+    limits.max_image_height = Some(MAX_UPLOAD_DIMENSION);
+    reader.limits(limits);
+    let image = reader.decode().map_err(|e| e.to_string())?;
+
+    AVATAR_SIZES
+        .into_iter()
+        .map(|size| {
+            let mut png = Vec::new();
+            image
+                .resize_to_fill(size, size, FilterType::Lanczos3)
+                .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                .map_err(|e| e.to_string())?;
+            Ok((size, png))
+        })
+        .collect()
+}
+
+/// Removes every size of an uploaded avatar. Failures are only logged, leaving an unreferenced
+/// file behind at worst.
+pub async fn delete_uploaded(storage: &dyn Storage, id: &str) {
+    for size in AVATAR_SIZES {
+        if let Err(e) = storage.delete(&variant_key(id, size)).await {
+            tracing::warn!("Failed to delete avatar {} at {}px: {}", id, size, e);
+        }
+    }
+}
+
+fn is_upstream(url: &Url) -> bool {
+    url.scheme() == "https" && url.host_str().is_some_and(|host| UPSTREAM_HOSTS.contains(&host))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Avatar {
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+/// A cached avatar: when it was fetched, then its content type and bytes.
+fn encode_entry(fetched_at: i64, avatar: &Avatar) -> Vec<u8> {
+    let mut entry = Vec::with_capacity(avatar.bytes.len() + 32);
+    entry.extend_from_slice(&fetched_at.to_be_bytes());
+    entry.extend_from_slice(avatar.content_type.as_bytes());
+    entry.push(b'\n');
+    entry.extend_from_slice(&avatar.bytes);
+    entry
+}
+
+fn decode_entry(entry: &[u8]) -> Option<(i64, Avatar)> {
+    let fetched_at = i64::from_be_bytes(entry.get(..8)?.try_into().ok()?);
+    let rest = &entry[8..];
+    let newline = rest.iter().position(|byte| *byte == b'\n')?;
+    let declared = std::str::from_utf8(&rest[..newline]).ok()?;
+    let bytes = rest[newline + 1..].to_vec();
+    let (content_type, _) = sniff(declared, &bytes)?;
+    Some((fetched_at, Avatar { content_type, bytes }))
+}
+
+/// Fetches avatars from their upstream and caches each size variant, refetching once the
+/// cached copy is older than `AVATAR_CACHE_TTL_SECONDS`. Only PNG, JPEG, GIF and WebP images
+/// whose bytes match their declared type are passed on.
+pub struct AvatarProxy {
+    client: reqwest::Client,
+    cache: Arc<dyn Cache>,
+    ttl: Duration,
+}
+
+impl AvatarProxy {
+    pub fn new(config: &Config, cache: Arc<dyn Cache>) -> Self {
+        let redirects = reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() < MAX_REDIRECTS && is_upstream(attempt.url()) {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        });
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.avatar_fetch_timeout_seconds()))
+            .redirect(redirects)
+            .user_agent(concat!("tsumi-avatars/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("Failed to build avatar HTTP client");
+
+        Self {
+            client,
+            cache,
+            ttl: Duration::from_secs(config.avatar_cache_ttl_seconds()),
+        }
+    }
+
+    /// How long browsers may keep a served avatar.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// The avatar at `size`, from cache while it's fresh. A stale copy is served when the
+    /// upstream can't be reached.
+    pub async fn get(&self, source: &AvatarSources, size: u32) -> Result<Avatar, String> {
+        let key = cache::avatar_key(&source.id, size);
+        let cached = cache::get_bytes(self.cache.as_ref(), &key).await.and_then(|entry| decode_entry(&entry));
+        let now = Utc::now().timestamp();
+        if let Some((fetched_at, avatar)) = &cached
+            && now.saturating_sub(*fetched_at) < self.ttl.as_secs() as i64
+        {
+            return Ok(avatar.clone());
+        }
+
+        match self.fetch(&source.upstream_url, size).await {
+            Ok(avatar) => {
+                let stale_ttl = self.ttl * STALE_TTL_FACTOR;
+                cache::set_bytes(self.cache.as_ref(), &key, encode_entry(now, &avatar), stale_ttl).await;
+                Ok(avatar)
+            }
+            Err(e) => match cached {
+                Some((_, avatar)) => {
+                    tracing::warn!("Serving stale avatar {}: {}", source.id, e);
+                    Ok(avatar)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn fetch(&self, upstream: &str, size: u32) -> Result<Avatar, String> {
+        let mut url = Url::parse(upstream).map_err(|e| format!("Invalid avatar URL: {}", e))?;
+        if !is_upstream(&url) {
+            return Err(format!("{} is not an avatar host", url.host_str().unwrap_or_default()));
+        }
+        url.query_pairs_mut().append_pair("s", &size.to_string());
+
+        let mut response = self.client
+            .get(url)
+            .header(http::header::ACCEPT, "image/png, image/jpeg, image/gif, image/webp")
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Upstream answered {}", response.status()));
+        }
+        if response.content_length().is_some_and(|length| length as usize > MAX_AVATAR_BYTES) {
+            return Err("Upstream avatar is too large".to_string());
+        }
+        let declared = response.headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+            if bytes.len() + chunk.len() > MAX_AVATAR_BYTES {
+                return Err("Upstream avatar is too large".to_string());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        let (content_type, _) = sniff(&declared, &bytes)
+            .ok_or_else(|| format!("Upstream sent {:?}, which isn't an accepted image", declared))?;
+        Ok(Avatar { content_type, bytes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_snap_up_to_a_variant() {
+        assert_eq!(snap_size(None), DEFAULT_AVATAR_SIZE);
+        assert_eq!(snap_size(Some(1)), 32);
+        assert_eq!(snap_size(Some(64)), 64);
+        assert_eq!(snap_size(Some(65)), 128);
+        assert_eq!(snap_size(Some(4096)), 256);
+    }
+
+    #[test]
+    fn only_known_https_hosts_are_fetched() {
+        assert!(is_upstream(&Url::parse("https://gravatar.com/avatar/abc").unwrap()));
+        assert!(is_upstream(&Url::parse("https://avatars.githubusercontent.com/u/1").unwrap()));
+        assert!(!is_upstream(&Url::parse("http://gravatar.com/avatar/abc").unwrap()));
+        assert!(!is_upstream(&Url::parse("https://127.0.0.1/avatar").unwrap()));
+    }
+
+    #[test]
+    fn cache_entries_round_trip_and_are_rechecked() {
+        let avatar = Avatar { content_type: "image/png", bytes: b"\x89PNG\r\n\x1a\nrest".to_vec() };
+        assert_eq!(decode_entry(&encode_entry(42, &avatar)), Some((42, avatar)));
+
+        let mislabeled = Avatar { content_type: "image/png", bytes: b"<svg/>".to_vec() };
+        assert_eq!(decode_entry(&encode_entry(42, &mislabeled)), None);
+    }
+
+    #[test]
+    fn uploads_are_cropped_square_at_every_size() {
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(300, 200)
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+
+        let variants = resize(&jpeg).unwrap();
+        assert_eq!(variants.iter().map(|(size, _)| *size).collect::<Vec<_>>(), AVATAR_SIZES);
+        for (size, png) in variants {
+            assert_eq!(sniff("image/png", &png), Some(("image/png", "png")));
+            let decoded = image::load_from_memory(&png).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (size, size));
+        }
+        assert!(resize(b"\x89PNG\r\n\x1a\nnot really").is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use diesel::{QueryResult, SqliteConnection};
+
+use crate::config::Config;
+use crate::db::models::backfill_job::{
+    BackfillJobs, BACKFILL_KIND_DESCRIPTION, BACKFILL_KIND_OG_IMAGE, BACKFILL_KIND_SEARCH_INDEX,
+    BACKFILL_KIND_WORD_COUNT,
+};
+use crate::db::models::post::{PostChanges, Posts};
+use crate::db::write::{write, WriteLock};
+use crate::errors::AuthError;
+use crate::services::job_locks::Leases;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+use crate::services::post_metadata;
+use crate::state::DbPool;
+
+/// Works through queued backfill jobs one batch at a time.
+///
+/// All progress lives in `backfill_jobs`, so a restart picks up where the last batch left off.
+/// The pause between batches keeps a large backfill from starving request traffic of the
+/// database.
+pub struct BackfillWorker {
+    batch_size: i64,
+    batch_delay: Duration,
+    idle_delay: Duration,
+    pool: DbPool,
+    writes: WriteLock,
+    leases: Leases,
+    tasks: Tasks,
+}
+
+impl BackfillWorker {
+    pub fn new(config: &Config, pool: DbPool, writes: WriteLock, leases: Leases) -> Self {
+        Self {
+            batch_size: config.backfill_batch_size().max(1),
+            batch_delay: Duration::from_millis(config.backfill_batch_delay_ms()),
+            idle_delay: Duration::from_secs(config.backfill_poll_interval_seconds().max(1)),
+            pool,
+            writes,
+            leases,
+            tasks: Tasks::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Service for BackfillWorker {
+    fn name(&self) -> &'static str {
+        "backfill-worker"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let (batch_size, batch_delay, idle_delay) = (self.batch_size, self.batch_delay, self.idle_delay);
+        let pool = self.pool.clone();
+        let writes = self.writes.clone();
+        let mut lease = self.leases.lease(self.name());
+
+        self.tasks.spawn(|mut shutdown| async move {
+            loop {
+                if !lease.hold().await {
+                    tokio::select! {
+                        _ = tokio::time::sleep(idle_delay) => continue,
+                        _ = shutdown.wait() => break,
+                    }
+                }
+
+                let pool = pool.clone();
+                let turn = writes.acquire().await;
+                // Each batch is its own transaction, so stopping between batches loses nothing.
+                let result = tokio::task::spawn_blocking(move || {
+                    let mut conn = pool.get().map_err(|e| e.to_string())?;
+                    run_batch(&mut conn, batch_size).map_err(|e| e.to_string())
+                })
+                .await;
+                drop(turn);
+
+                let delay = match result {
+                    Ok(Ok(true)) => batch_delay,
+                    Ok(Ok(false)) => idle_delay,
+                    Ok(Err(e)) => {
+                        tracing::error!("Backfill worker failed: {}", e);
+                        idle_delay
+                    }
+                    Err(e) => {
+                        tracing::error!("Backfill task panicked: {}", e);
+                        idle_delay
+                    }
+                };
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = shutdown.wait() => break,
+                }
+            }
+
+            lease.release().await;
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
+
+/// Processes the next batch of the oldest runnable job, returning whether there was one.
+pub fn run_batch(conn: &mut SqliteConnection, batch_size: i64) -> QueryResult<bool> {
+    let Some(job) = BackfillJobs::next_runnable(conn)? else {
+        return Ok(false);
+    };
+
+    let result: QueryResult<usize> = write(conn, |conn| {
+        let posts = Posts::missing_metadata_after(conn, &job.kind, job.cursor.as_deref(), batch_size)?;
+        let Some(last) = posts.last() else {
+            BackfillJobs::complete(conn, &job.id)?;
+            return Ok(0);
+        };
+
+        for post in &posts {
+            apply(conn, &job.kind, post)?;
+        }
+
+        BackfillJobs::record_progress(conn, &job.id, &last.id, posts.len() as i32)?;
+        Ok(posts.len())
+    });
+
+    match result {
+        Ok(0) => tracing::info!("Backfill {} ({}) completed", job.id, job.kind),
+        Ok(processed) => tracing::debug!("Backfill {} ({}) processed {} post(s)", job.id, job.kind, processed),
+        Err(e) => {
+            tracing::error!("Backfill {} ({}) failed: {}", job.id, job.kind, e);
+            BackfillJobs::fail(conn, &job.id, &e.to_string())?;
+        }
+    }
+
+    Ok(true)
+}
+
+fn apply(conn: &mut SqliteConnection, kind: &str, post: &Posts) -> QueryResult<()> {
+    let changes = match kind {
+        BACKFILL_KIND_WORD_COUNT => {
+            let word_count = post_metadata::word_count(&post.content);
+            PostChanges {
+                word_count: Some(word_count),
+                reading_minutes: Some(post_metadata::reading_minutes(word_count)),
+                ..Default::default()
+            }
+        }
+        BACKFILL_KIND_DESCRIPTION => {
+            let description = post_metadata::description(&post.content);
+            if description.is_empty() {
+                return Ok(());
+            }
+            PostChanges { description: Some(description), ..Default::default() }
+        }
+        BACKFILL_KIND_OG_IMAGE => match post_metadata::og_image(&post.content) {
+            Some(url) => PostChanges { og_image_url: Some(Some(url)), ..Default::default() },
+            None => return Ok(()),
+        },
+        BACKFILL_KIND_SEARCH_INDEX => {
+            let terms = post_metadata::search_terms(&post.title, &post.description, &post.content);
+            Posts::index_search_terms(conn, &post.id, &terms)?;
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+
+    Posts::set_metadata(conn, &post.id, &changes)?;
+    Ok(())
+}
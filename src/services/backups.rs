@@ -0,0 +1,203 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use diesel::connection::SimpleConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::errors::AuthError;
+use crate::services::lifecycle::Shutdown;
+use crate::services::s3::S3Storage;
+use crate::services::scheduler::{Job, Schedule};
+use crate::services::storage::{DiskStorage, Storage};
+use crate::state::DbPool;
+
+/// Lists every backup kept, newest first. Storage can't list what it holds, so this is how
+/// backups are found again.
+const MANIFEST: &str = "manifest.json";
+const CONTENT_TYPE: &str = "application/vnd.sqlite3";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub size_bytes: u64,
+    pub created_at: NaiveDateTime,
+}
+
+/// Snapshots of the database, kept in a storage backend of their own: `BACKUP_DIR` on disk,
+/// or `backups/` in the S3 bucket. Only the newest `BACKUP_KEEP` are kept.
+pub struct Backups {
+    storage: Arc<dyn Storage>,
+    prefix: &'static str,
+    keep: usize,
+}
+
+impl Backups {
+    pub fn new(storage: Arc<dyn Storage>, keep: usize) -> Self {
+        Self { storage, prefix: "", keep: keep.max(1) }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        if config.backups_to_s3()
+            && let Some(s3) = S3Storage::from_config(config)
+        {
+            return Self { storage: Arc::new(s3), prefix: "backups/", keep: config.backup_keep().max(1) };
+        }
+        Self::new(Arc::new(DiskStorage::new(config.backup_dir())), config.backup_keep())
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// The backups kept, newest first.
+    pub async fn list(&self) -> Result<Vec<BackupInfo>, AuthError> {
+        match self.storage.get(&self.key(MANIFEST)).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| AuthError::internal(format!("Backup manifest is unreadable: {}", e))),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Snapshots the database into a new backup, then deletes the oldest past `BACKUP_KEEP`.
+    pub async fn create(&self, pool: &DbPool) -> Result<BackupInfo, AuthError> {
+        let pool = pool.clone();
+        let bytes = tokio::task::spawn_blocking(move || snapshot(&pool))
+            .await
+            .map_err(|e| AuthError::internal(format!("Backup task panicked: {}", e)))??;
+
+        let created_at = Utc::now().naive_utc();
+        let info = BackupInfo {
+            name: format!("tsumi-{}.sqlite3", created_at.format("%Y%m%dT%H%M%S%.3fZ")),
+            size_bytes: bytes.len() as u64,
+            created_at,
+        };
+        self.storage.put(&self.key(&info.name), CONTENT_TYPE, bytes).await?;
+
+        let mut backups = self.list().await?;
+        backups.retain(|backup| backup.name != info.name);
+        backups.push(info.clone());
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+        let expired = if backups.len() > self.keep { backups.split_off(self.keep) } else { Vec::new() };
+        for backup in expired {
+            if let Err(e) = self.storage.delete(&self.key(&backup.name)).await {
+                tracing::error!("Failed to delete old backup {}: {}", backup.name, e);
+                // Still listed, so the next backup tries again.
+                backups.push(backup);
+            }
+        }
+
+        let manifest = serde_json::to_vec_pretty(&backups)
+            .map_err(|e| AuthError::internal(format!("Failed to write backup manifest: {}", e)))?;
+        self.storage.put(&self.key(MANIFEST), "application/json", manifest).await?;
+        Ok(info)
+    }
+
+    /// The snapshot saved as `name`, or `None` if there's no such backup.
+    pub async fn fetch(&self, name: &str) -> Result<Option<Vec<u8>>, AuthError> {
+        if name.contains('/') || !name.ends_with(".sqlite3") {
+            return Err(AuthError::validation(format!("{} isn't the name of a backup", name)));
+        }
+        self.storage.get(&self.key(name)).await
+    }
+}
+
+/// A consistent copy of the whole database, taken with `VACUUM INTO` so writers carry on
+/// while it's made. Runs on a blocking thread.
+fn snapshot(pool: &DbPool) -> Result<Vec<u8>, AuthError> {
+    let path = std::env::temp_dir().join(format!("tsumi-backup-{}.sqlite3", uuid::Uuid::new_v4()));
+    let mut conn = pool.get().map_err(|e| {
+        tracing::error!("Failed to get db connection: {}", e);
+        AuthError::database("Failed to connect to database")
+    })?;
+
+    let result = conn
+        .batch_execute(&format!("VACUUM INTO {}", quote(&path)))
+        .map_err(|e| AuthError::database(format!("Failed to snapshot the database: {}", e)))
+        .and_then(|()| {
+            std::fs::read(&path).map_err(|e| AuthError::internal(format!("Failed to read {}: {}", path.display(), e)))
+        });
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+fn quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "''"))
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackupReport {
+    /// 0 when backups are only taken on demand.
+    pub interval_hours: u64,
+    pub keep: usize,
+    pub last_backup: Option<BackupInfo>,
+}
+
+/// Shared between the backup job and the admin API: the backups kept and the newest one
+/// taken since startup.
+pub struct BackupHandle {
+    backups: Backups,
+    report: Mutex<BackupReport>,
+}
+
+impl BackupHandle {
+    pub fn report(&self) -> BackupReport {
+        self.lock().clone()
+    }
+
+    pub async fn list(&self) -> Result<Vec<BackupInfo>, AuthError> {
+        self.backups.list().await
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BackupReport> {
+        self.report.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Backs the database up every `BACKUP_INTERVAL_HOURS`, and whenever an admin asks for one.
+pub struct BackupWorker {
+    pool: DbPool,
+    handle: Arc<BackupHandle>,
+}
+
+impl BackupWorker {
+    pub fn new(config: &Config, pool: DbPool) -> Self {
+        let report = BackupReport {
+            interval_hours: config.backup_interval_hours(),
+            keep: config.backup_keep(),
+            ..BackupReport::default()
+        };
+        let handle = Arc::new(BackupHandle { backups: Backups::from_config(config), report: Mutex::new(report) });
+        Self { pool, handle }
+    }
+
+    pub fn handle(&self) -> Arc<BackupHandle> {
+        self.handle.clone()
+    }
+}
+
+#[async_trait]
+impl Job for BackupWorker {
+    fn name(&self) -> &'static str {
+        "backups"
+    }
+
+    fn schedule(&self) -> Schedule {
+        match self.handle.report().interval_hours {
+            0 => Schedule::OnDemand,
+            hours => Schedule::Every(Duration::from_secs(hours.saturating_mul(3600))),
+        }
+    }
+
+    /// A backup is let finish even when shutdown comes in the middle of it, rather than
+    /// leaving a snapshot the manifest doesn't know about.
+    async fn run(&self, _shutdown: Shutdown) -> Result<(), String> {
+        let backup = self.handle.backups.create(&self.pool).await.map_err(|e| e.to_string())?;
+        tracing::info!(name = %backup.name, size_bytes = backup.size_bytes, "Backed up the database");
+        self.handle.lock().last_backup = Some(backup);
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use ammonia::UrlRelative;
+use once_cell::sync::Lazy;
+
+use crate::errors::AuthError;
+
+pub const MAX_CSS_BYTES: usize = 64 * 1024;
+pub const MAX_HEAD_BYTES: usize = 8 * 1024;
+
+/// Constructs that can run script, pull in unreviewed stylesheets or load remote behaviour.
+const FORBIDDEN_CSS: &[&str] = &["expression(", "javascript:", "vbscript:", "behavior:", "-moz-binding", "@import"];
+
+/// `rel` values a head `<link>` may use: enough for web fonts and external stylesheets.
+const ALLOWED_LINK_RELS: &[&str] = &["stylesheet", "preconnect", "dns-prefetch", "preload"];
+
+/// Head snippets may only be `<meta>` tags (analytics verification, social cards) and `<link>`
+/// tags pointing at https URLs (fonts, stylesheets). Everything else, scripts included, is
+/// dropped.
+static HEAD_SANITIZER: Lazy<ammonia::Builder<'static>> = Lazy::new(|| {
+    let mut builder = ammonia::Builder::empty();
+    builder
+        .add_tags(&["meta", "link"])
+        .add_tag_attributes("meta", &["name", "property", "content"])
+        .add_tag_attributes("link", &["rel", "href", "crossorigin", "type", "as", "media"])
+        .url_schemes(HashSet::from(["https"]))
+        .url_relative(UrlRelative::Deny)
+        .link_rel(None)
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            ("link", "rel") => {
+                let allowed = value
+                    .split_whitespace()
+                    .all(|rel| ALLOWED_LINK_RELS.iter().any(|allowed| rel.eq_ignore_ascii_case(allowed)));
+                allowed.then_some(Cow::Borrowed(value))
+            }
+            _ => Some(Cow::Borrowed(value)),
+        });
+    builder
+});
+
+/// Checks a blog's custom CSS and returns it with comments stripped.
+///
+/// The CSS is embedded in a `<style>` element, so anything that could close the element,
+/// hide a keyword behind an escape, or reference a non-https URL is rejected rather than
+/// rewritten; the author gets told what to fix instead of finding their styles altered.
+pub fn sanitize_css(css: &str) -> Result<String, AuthError> {
+    if css.len() > MAX_CSS_BYTES {
+        return Err(AuthError::validation(format!("Custom CSS must be at most {} bytes", MAX_CSS_BYTES)));
+    }
+
+    let css = strip_comments(css)?;
+    if css.contains('<') {
+        return Err(AuthError::validation("Custom CSS may not contain '<'"));
+    }
+    if css.contains('\\') {
+        return Err(AuthError::validation("Custom CSS may not contain backslash escapes"));
+    }
+
+    let lowered = css.to_ascii_lowercase();
+    if let Some(forbidden) = FORBIDDEN_CSS.iter().find(|forbidden| lowered.contains(*forbidden)) {
+        return Err(AuthError::validation(format!("Custom CSS may not use '{}'", forbidden)));
+    }
+
+    for target in url_arguments(&lowered) {
+        if !is_allowed_css_url(target) {
+            return Err(AuthError::validation(format!("Custom CSS may only load https URLs, found '{}'", target)));
+        }
+    }
+
+    Ok(css.trim().to_string())
+}
+
+/// Cleans head snippets down to `<meta>` and https `<link>` tags.
+pub fn sanitize_head(head_html: &str) -> Result<String, AuthError> {
+    if head_html.len() > MAX_HEAD_BYTES {
+        return Err(AuthError::validation(format!("Head snippets must be at most {} bytes", MAX_HEAD_BYTES)));
+    }
+
+    Ok(HEAD_SANITIZER.clean(head_html).to_string().trim().to_string())
+}
+
+fn strip_comments(css: &str) -> Result<String, AuthError> {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        let end = rest[start + 2..]
+            .find("*/")
+            .ok_or_else(|| AuthError::validation("Custom CSS has an unterminated comment"))?;
+        rest = &rest[start + 2 + end + 2..];
+    }
+    stripped.push_str(rest);
+    Ok(stripped)
+}
+
+/// The argument of every `url(...)` in the stylesheet, with quotes and whitespace removed.
+fn url_arguments(css: &str) -> Vec<&str> {
+    css.match_indices("url(")
+        .map(|(index, _)| {
+            let argument = &css[index + 4..];
+            let end = argument.find(')').unwrap_or(argument.len());
+            argument[..end].trim().trim_matches(['"', '\'']).trim()
+        })
+        .collect()
+}
+
+fn is_allowed_css_url(target: &str) -> bool {
+    if target.starts_with("https://") || target.starts_with("data:image/") {
+        return true;
+    }
+    // Relative URLs stay on this site; anything else with a scheme (or protocol-relative) doesn't.
+    let scheme_end = target.find(':');
+    let path_start = target.find('/');
+    !target.starts_with("//") && scheme_end.is_none_or(|colon| path_start.is_some_and(|slash| slash < colon))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn css_keeps_ordinary_rules_and_strips_comments() {
+        let css = sanitize_css("/* theme */ body { color: #333; background: url('https://cdn.example.com/bg.png'); }").unwrap();
+        assert_eq!(css, "body { color: #333; background: url('https://cdn.example.com/bg.png'); }");
+        assert!(sanitize_css("h1 { background: url(/media/abc) }").is_ok());
+    }
+
+    #[test]
+    fn css_rejects_injection_attempts() {
+        for css in [
+            "</style><script>alert(1)</script>",
+            "body { background: url(javascript:alert(1)) }",
+            "body { width: expre/**/ssion(alert(1)) }",
+            "@import url(https://evil.example/x.css);",
+            "body { background: url(http://tracker.example/p.gif) }",
+            "body { background: url(//tracker.example/p.gif) }",
+            "body { background: u\\72l(x) }",
+            "body { color: red /* never closed",
+        ] {
+            assert!(sanitize_css(css).is_err(), "accepted {:?}", css);
+        }
+    }
+
+    #[test]
+    fn head_keeps_meta_and_https_links_only() {
+        let head = sanitize_head(concat!(
+            r#"<meta name="google-site-verification" content="abc">"#,
+            r#"<link rel="stylesheet" href="https://fonts.googleapis.com/css2?family=Inter">"#,
+            r#"<script>alert(1)</script>"#,
+            r#"<meta http-equiv="refresh" content="0;url=https://evil.example">"#,
+            r#"<link rel="import" href="https://evil.example/x.html">"#,
+            r#"<link rel="stylesheet" href="http://insecure.example/x.css">"#,
+        ))
+        .unwrap();
+
+        assert!(head.contains(r#"<meta name="google-site-verification" content="abc">"#));
+        assert!(head.contains(r#"href="https://fonts.googleapis.com/css2?family=Inter""#));
+        assert!(!head.contains("script"));
+        assert!(!head.contains("http-equiv"));
+        assert!(!head.contains("import"));
+        assert!(!head.contains("http://"));
+    }
+}
@@ -0,0 +1,232 @@
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use lru::LruCache;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::errors::AuthError;
+use crate::services::redis_cache::RedisCache;
+
+/// Cached users are re-read at least this often, whatever the configured TTL, so changes
+/// made outside the API (an admin editing the database by hand) still show up quickly.
+const MAX_USER_TTL: Duration = Duration::from_secs(60);
+
+/// A key-value cache for hot reads. Every entry expires after its TTL; writers invalidate
+/// the keys they affect so readers don't wait out the TTL for their own changes.
+///
+/// The cache is an optimisation only. Use the helpers below, which log backend failures and
+/// carry on as if the entry was missing, rather than failing a request over it.
+#[async_trait]
+pub trait Cache: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, AuthError>;
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<(), AuthError>;
+
+    async fn invalidate(&self, key: &str) -> Result<(), AuthError>;
+
+    /// Drops every entry whose key starts with `prefix`.
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<(), AuthError>;
+}
+
+/// Redis when `REDIS_URL` is set, so several app instances share one cache, and an
+/// in-process LRU otherwise.
+pub fn from_config(config: &Config) -> Arc<dyn Cache> {
+    match RedisCache::from_config(config) {
+        Some(redis) => {
+            tracing::info!("Caching hot reads in Redis");
+            Arc::new(redis)
+        }
+        None => {
+            tracing::info!("Caching up to {} hot reads in memory", config.cache_capacity());
+            Arc::new(MemoryCache::new(config.cache_capacity()))
+        }
+    }
+}
+
+pub struct MemoryCache {
+    entries: Mutex<LruCache<String, (Vec<u8>, Instant)>>,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self { entries: Mutex::new(LruCache::new(capacity)) }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, LruCache<String, (Vec<u8>, Instant)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl Cache for MemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, AuthError> {
+        let mut entries = self.entries();
+        match entries.get(key) {
+            Some((value, expires_at)) if *expires_at > Instant::now() => Ok(Some(value.clone())),
+            Some(_) => {
+                entries.pop(key);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<(), AuthError> {
+        self.entries().put(key.to_string(), (value, Instant::now() + ttl));
+        Ok(())
+    }
+
+    async fn invalidate(&self, key: &str) -> Result<(), AuthError> {
+        self.entries().pop(key);
+        Ok(())
+    }
+
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<(), AuthError> {
+        let mut entries = self.entries();
+        let keys: Vec<String> = entries.iter().map(|(key, _)| key).filter(|key| key.starts_with(prefix)).cloned().collect();
+        for key in keys {
+            entries.pop(&key);
+        }
+        Ok(())
+    }
+}
+
+pub async fn get_json<T: DeserializeOwned>(cache: &dyn Cache, key: &str) -> Option<T> {
+    let bytes = cache.get(key).await
+        .inspect_err(|e| tracing::warn!("Cache read of {} failed: {}", key, e))
+        .ok()??;
+    serde_json::from_slice(&bytes)
+        .inspect_err(|e| tracing::warn!("Discarding unreadable cache entry {}: {}", key, e))
+        .ok()
+}
+
+/// Caches `value` for `ttl`. A zero TTL turns caching off.
+pub async fn set_json<T: Serialize>(cache: &dyn Cache, key: &str, value: &T, ttl: Duration) {
+    if ttl.is_zero() {
+        return;
+    }
+    match serde_json::to_vec(value) {
+        Ok(bytes) => {
+            if let Err(e) = cache.set(key, bytes, ttl).await {
+                tracing::warn!("Cache write of {} failed: {}", key, e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to serialize cache entry {}: {}", key, e),
+    }
+}
+
+pub async fn get_bytes(cache: &dyn Cache, key: &str) -> Option<Vec<u8>> {
+    cache.get(key).await
+        .inspect_err(|e| tracing::warn!("Cache read of {} failed: {}", key, e))
+        .ok()?
+}
+
+/// Caches raw bytes for `ttl`. A zero TTL turns caching off.
+pub async fn set_bytes(cache: &dyn Cache, key: &str, value: Vec<u8>, ttl: Duration) {
+    if ttl.is_zero() {
+        return;
+    }
+    if let Err(e) = cache.set(key, value, ttl).await {
+        tracing::warn!("Cache write of {} failed: {}", key, e);
+    }
+}
+
+pub fn user_key(user_id: &str) -> String {
+    format!("user:{}", user_id)
+}
+
+/// Names are matched case-insensitively in URLs, so page keys use the lowercased name.
+pub fn post_page_key(username: &str, slug: &str) -> String {
+    format!("page:post:{}/{}", username.to_lowercase(), slug)
+}
+
+pub fn author_page_key(username: &str) -> String {
+    format!("page:author:{}", username.to_lowercase())
+}
+
+pub fn static_page_key(slug: &str) -> String {
+    format!("page:static:{}", slug)
+}
+
+pub fn posts_page_key(page: i64) -> String {
+    format!("page:posts:{}", page)
+}
+
+/// A user's activity heatmap as computed on `day`.
+pub fn activity_key(user_id: &str, day: chrono::NaiveDate) -> String {
+    format!("activity:{}:{}", user_id, day)
+}
+
+/// A proxied avatar at one of its size variants.
+pub fn avatar_key(source_id: &str, size: u32) -> String {
+    format!("avatar:{}:{}", source_id, size)
+}
+
+pub fn nodeinfo_key() -> String {
+    "nodeinfo".to_string()
+}
+
+pub fn user_ttl(config: &Config) -> Duration {
+    Duration::from_secs(config.cache_ttl_seconds()).min(MAX_USER_TTL)
+}
+
+pub fn page_ttl(config: &Config) -> Duration {
+    Duration::from_secs(config.cache_ttl_seconds())
+}
+
+/// Forgets a cached user after their account changes.
+pub async fn invalidate_user(cache: &dyn Cache, user_id: &str) {
+    if let Err(e) = cache.invalidate(&user_key(user_id)).await {
+        tracing::warn!("Failed to invalidate cached user {}: {}", user_id, e);
+    }
+}
+
+/// Forgets every cached page showing an author's posts: their post pages, their author page
+/// and the site-wide post list.
+pub async fn invalidate_author_pages(cache: &dyn Cache, username: &str) {
+    let username = username.to_lowercase();
+    let results = [
+        cache.invalidate_prefix(&format!("page:post:{}/", username)).await,
+        cache.invalidate(&author_page_key(&username)).await,
+        cache.invalidate_prefix("page:posts:").await,
+    ];
+    for result in results {
+        if let Err(e) = result {
+            tracing::warn!("Failed to invalidate cached pages for {}: {}", username, e);
+        }
+    }
+}
+
+/// Forgets every cached page, for changes that touch posts without saying whose.
+pub async fn invalidate_all_pages(cache: &dyn Cache) {
+    if let Err(e) = cache.invalidate_prefix("page:").await {
+        tracing::warn!("Failed to invalidate cached pages: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn memory_cache_expires_evicts_and_invalidates() {
+        let cache = MemoryCache::new(2);
+        cache.set("page:post:ann/a", b"a".to_vec(), Duration::from_secs(60)).await.unwrap();
+        cache.set("page:post:ann/b", b"b".to_vec(), Duration::ZERO).await.unwrap();
+        assert_eq!(cache.get("page:post:ann/a").await.unwrap(), Some(b"a".to_vec()));
+        assert_eq!(cache.get("page:post:ann/b").await.unwrap(), None);
+
+        cache.set("page:post:bob/a", b"c".to_vec(), Duration::from_secs(60)).await.unwrap();
+        cache.set("user:1", b"d".to_vec(), Duration::from_secs(60)).await.unwrap();
+        assert_eq!(cache.get("page:post:ann/a").await.unwrap(), None, "least recently used entry is evicted");
+
+        cache.invalidate_prefix("page:post:bob/").await.unwrap();
+        assert_eq!(cache.get("page:post:bob/a").await.unwrap(), None);
+        assert_eq!(cache.get("user:1").await.unwrap(), Some(b"d".to_vec()));
+    }
+}
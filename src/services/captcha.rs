@@ -0,0 +1,123 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::errors::AuthError;
+use crate::services::http_client::HttpClient;
+use crate::state::AppState;
+
+/// Captcha services with the shared `siteverify` contract: a form POST of `secret`, `response`
+/// and `remoteip`, answered with `{"success": bool, "error-codes": [...]}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptchaProvider {
+    HCaptcha,
+    ReCaptcha,
+}
+
+impl CaptchaProvider {
+    fn verify_url(self) -> &'static str {
+        match self {
+            Self::HCaptcha => "https://api.hcaptcha.com/siteverify",
+            Self::ReCaptcha => "https://www.google.com/recaptcha/api/siteverify",
+        }
+    }
+}
+
+impl FromStr for CaptchaProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hcaptcha" => Ok(Self::HCaptcha),
+            "recaptcha" => Ok(Self::ReCaptcha),
+            other => Err(format!("expected hcaptcha or recaptcha, got {}", other)),
+        }
+    }
+}
+
+/// Endpoints that can be put behind a captcha, each switched on with its own setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptchaEndpoint {
+    Signup,
+    PasswordReset,
+}
+
+#[derive(Deserialize)]
+struct VerifyResponse {
+    success: bool,
+    #[serde(default, rename = "error-codes")]
+    error_codes: Vec<String>,
+}
+
+pub struct CaptchaVerifier {
+    http: HttpClient,
+    verify_url: String,
+    secret: String,
+}
+
+/// The verifier for the configured provider, if any.
+pub fn from_config(config: &Config, http: HttpClient) -> Option<Arc<CaptchaVerifier>> {
+    let provider = config.captcha_provider()?;
+    let verify_url = config.captcha_verify_url().unwrap_or(provider.verify_url());
+    tracing::info!("Verifying captchas with {:?} at {}", provider, verify_url);
+    Some(Arc::new(CaptchaVerifier {
+        http,
+        verify_url: verify_url.to_string(),
+        secret: config.captcha_secret().unwrap_or_default().to_string(),
+    }))
+}
+
+impl CaptchaVerifier {
+    /// Whether the provider accepts `token`. Errors mean the provider couldn't be asked.
+    pub async fn verify(&self, token: &str, remote_ip: Option<&str>) -> Result<bool, AuthError> {
+        let mut form = vec![("secret", self.secret.as_str()), ("response", token)];
+        if let Some(remote_ip) = remote_ip {
+            form.push(("remoteip", remote_ip));
+        }
+
+        let response = self.http
+            .post(&self.verify_url)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| AuthError::internal(format!("Captcha verification request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AuthError::internal(format!("Captcha provider answered {}", response.status())));
+        }
+
+        let verdict: VerifyResponse = response.json().await
+            .map_err(|e| AuthError::internal(format!("Invalid captcha verification response: {}", e)))?;
+        if !verdict.success {
+            tracing::info!("Captcha rejected: {}", verdict.error_codes.join(", "));
+        }
+        Ok(verdict.success)
+    }
+}
+
+/// Rejects the request unless `endpoint` needs no captcha or `token` is a solved one. When the
+/// provider can't be reached the request is refused rather than let through.
+pub async fn check(
+    state: &AppState,
+    endpoint: CaptchaEndpoint,
+    token: Option<&str>,
+    remote_ip: Option<&str>,
+) -> Result<(), AuthError> {
+    let Some(verifier) = state.captcha.as_ref().filter(|_| state.config.load().captcha_required(endpoint)) else {
+        return Ok(());
+    };
+    let Some(token) = token.filter(|token| !token.is_empty()) else {
+        return Err(AuthError::invalid_field("captcha_token", "required", "A captcha is required"));
+    };
+
+    match verifier.verify(token, remote_ip).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(AuthError::invalid_field("captcha_token", "rejected", "Captcha verification failed")),
+        Err(e) => {
+            tracing::error!("Failed to verify captcha for {:?}: {}", endpoint, e);
+            Err(AuthError::internal("Captcha verification is unavailable"))
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use diesel::{QueryResult, SqliteConnection};
+use tokio::sync::broadcast;
+use tokio::time::MissedTickBehavior;
+use yrs::sync::{Message, SyncMessage};
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, GetString, ReadTxn, StateVector, Text, Transact, TransactionMut, Update};
+
+use crate::config::Config;
+use crate::db::models::post::{PostChanges, Posts};
+use crate::db::models::post_doc::{PostDocUpdates, PostDocs};
+use crate::db::models::user_model::UserModel;
+use crate::db::write::write;
+use crate::errors::AuthError;
+use crate::services::cache::{self, Cache};
+use crate::services::job_locks::Leases;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+use crate::services::post_metadata;
+use crate::state::DbPool;
+
+/// The shared text holding a post's Markdown. Clients bind their editor to this name.
+pub const CONTENT_TEXT: &str = "content";
+
+/// Frames a room buffers for its slowest peer. A peer that falls further behind is
+/// disconnected and resyncs when it reconnects.
+const ROOM_BUFFER: usize = 256;
+
+/// A v1 update with no changes: zero structs and an empty delete set. Clients send one as
+/// their sync reply when they have nothing the server lacks.
+const EMPTY_UPDATE: &[u8] = &[0, 0];
+
+/// Whether an update carries any changes worth storing.
+pub fn is_empty_update(data: &[u8]) -> bool {
+    data == EMPTY_UPDATE
+}
+
+/// Rebuilds a post's document from its last compacted state plus the updates since. Returns
+/// the document, the ids of the updates folded in, and whether anything was stored at all.
+fn load_doc(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<(Doc, Vec<String>, bool)> {
+    let snapshot = PostDocs::by_post(conn, post_id)?;
+    let updates = PostDocUpdates::by_post(conn, post_id)?;
+    let stored = snapshot.is_some() || !updates.is_empty();
+
+    let doc = Doc::new();
+    doc.get_or_insert_text(CONTENT_TEXT);
+    {
+        let mut txn = doc.transact_mut();
+        if let Some(snapshot) = snapshot {
+            apply_stored(&mut txn, post_id, &snapshot.state);
+        }
+        for update in &updates {
+            apply_stored(&mut txn, post_id, &update.data);
+        }
+    }
+
+    Ok((doc, updates.into_iter().map(|update| update.id).collect(), stored))
+}
+
+/// Stored updates were checked before they were saved, so a failure here means corruption.
+/// Skip the update rather than lose the whole document.
+fn apply_stored(txn: &mut TransactionMut, post_id: &str, data: &[u8]) {
+    let result = Update::decode_v1(data)
+        .map_err(|e| e.to_string())
+        .and_then(|update| txn.apply_update(update).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        tracing::warn!("Skipping unreadable document update for post {}: {}", post_id, e);
+    }
+}
+
+fn text_of(doc: &Doc) -> String {
+    let text = doc.get_or_insert_text(CONTENT_TEXT);
+    text.get_string(&doc.transact())
+}
+
+/// Loads a post's document, seeding it from the post's content the first time anyone opens it.
+/// The seed is stored like any other update so every server builds the same document.
+pub fn open_doc(conn: &mut SqliteConnection, post: &Posts) -> QueryResult<Doc> {
+    write(conn, |conn| {
+        let (doc, _, stored) = load_doc(conn, &post.id)?;
+        if stored {
+            return Ok(doc);
+        }
+
+        let text = doc.get_or_insert_text(CONTENT_TEXT);
+        let seed = {
+            let mut txn = doc.transact_mut();
+            text.insert(&mut txn, 0, &post.content);
+            txn.encode_update_v1()
+        };
+        PostDocUpdates::record(conn, &PostDocUpdates {
+            id: uuid::Uuid::new_v4().to_string(),
+            post_id: post.id.clone(),
+            user_id: Some(post.user_id.clone()),
+            data: seed,
+            created_at: chrono::Utc::now().naive_utc(),
+        })?;
+        Ok(doc)
+    })
+}
+
+/// Folds a post's pending updates into its compacted state and copies the document's text
+/// into the post. Returns the post when its content changed. Versions are only recorded on
+/// an explicit commit, not here.
+pub fn compact(conn: &mut SqliteConnection, post_id: &str) -> QueryResult<Option<Posts>> {
+    write(conn, |conn| {
+        let Some(post) = Posts::by_id(conn, post_id)? else {
+            return Ok(None);
+        };
+        let (doc, update_ids, _) = load_doc(conn, post_id)?;
+        if update_ids.is_empty() {
+            return Ok(None);
+        }
+
+        let state = doc.transact().encode_state_as_update_v1(&StateVector::default());
+        PostDocs::save(conn, &PostDocs {
+            post_id: post_id.to_string(),
+            state,
+            compacted_at: chrono::Utc::now().naive_utc(),
+        })?;
+        PostDocUpdates::remove(conn, &update_ids)?;
+
+        let content = text_of(&doc);
+        if content == post.content {
+            return Ok(None);
+        }
+
+        let word_count = post_metadata::word_count(&content);
+        let changes = PostChanges {
+            word_count: Some(word_count),
+            reading_minutes: Some(post_metadata::reading_minutes(word_count)),
+            og_image_url: Some(post_metadata::og_image(&content)),
+            updated_at: Some(chrono::Utc::now().naive_utc()),
+            content: Some(content),
+            ..PostChanges::default()
+        };
+        let post = Posts::update(conn, post_id, &changes)?;
+        let terms = post_metadata::search_terms(&post.title, &post.description, &post.content);
+        Posts::index_search_terms(conn, &post.id, &terms)?;
+        Ok(Some(post))
+    })
+}
+
+/// What a room fans out to its peers.
+#[derive(Clone, Debug)]
+pub enum Frame {
+    /// An encoded sync or awareness message, relayed to every peer but its sender.
+    Message { from: u64, data: Arc<Vec<u8>> },
+    /// The document was reset underneath the room; peers must reconnect.
+    Closed,
+}
+
+/// The live document for one post and the editors connected to it.
+pub struct Room {
+    doc: Mutex<Doc>,
+    frames: broadcast::Sender<Frame>,
+    peers: AtomicUsize,
+}
+
+impl Room {
+    fn doc(&self) -> std::sync::MutexGuard<'_, Doc> {
+        self.doc.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sync step 1: the server's state vector, asking the client for whatever it has that
+    /// the server doesn't.
+    pub fn sync_step1(&self) -> Vec<u8> {
+        let state_vector = self.doc().transact().state_vector();
+        Message::Sync(SyncMessage::SyncStep1(state_vector)).encode_v1()
+    }
+
+    /// Sync step 2: everything the server has that a client with `state_vector` is missing.
+    pub fn sync_step2(&self, state_vector: &StateVector) -> Vec<u8> {
+        let update = self.doc().transact().encode_diff_v1(state_vector);
+        Message::Sync(SyncMessage::SyncStep2(update)).encode_v1()
+    }
+
+    pub fn apply(&self, data: &[u8]) -> Result<(), String> {
+        let update = Update::decode_v1(data).map_err(|e| e.to_string())?;
+        let doc = self.doc();
+        let mut txn = doc.transact_mut();
+        txn.apply_update(update).map_err(|e| e.to_string())
+    }
+
+    pub fn relay(&self, from: u64, data: Vec<u8>) {
+        // Sending only fails when nobody else is listening.
+        let _ = self.frames.send(Frame::Message { from, data: Arc::new(data) });
+    }
+}
+
+/// One editor's connection to a room.
+pub struct Peer {
+    pub id: u64,
+    pub post_id: String,
+    pub room: Arc<Room>,
+    pub frames: broadcast::Receiver<Frame>,
+}
+
+/// Every post currently open for collaborative editing on this server. A room lives while at
+/// least one editor is connected; the document itself is always rebuilt from the database.
+#[derive(Default)]
+pub struct CollabHub {
+    rooms: tokio::sync::Mutex<HashMap<String, Arc<Room>>>,
+    next_peer: AtomicU64,
+}
+
+impl CollabHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn join(&self, pool: &DbPool, post: &Posts) -> Result<Peer, AuthError> {
+        let mut rooms = self.rooms.lock().await;
+
+        let room = match rooms.get(&post.id) {
+            Some(room) => room.clone(),
+            None => {
+                let pool = pool.clone();
+                let opened = post.clone();
+                let doc = tokio::task::spawn_blocking(move || {
+                    let mut conn = pool.get().map_err(|e| e.to_string())?;
+                    open_doc(&mut conn, &opened).map_err(|e| e.to_string())
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| result)
+                .map_err(|e| {
+                    tracing::error!("Failed to open collaborative document for post {}: {}", post.id, e);
+                    AuthError::database("Failed to open document")
+                })?;
+
+                let (frames, _) = broadcast::channel(ROOM_BUFFER);
+                let room = Arc::new(Room { doc: Mutex::new(doc), frames, peers: AtomicUsize::new(0) });
+                rooms.insert(post.id.clone(), room.clone());
+                room
+            }
+        };
+
+        room.peers.fetch_add(1, Ordering::SeqCst);
+        Ok(Peer {
+            id: self.next_peer.fetch_add(1, Ordering::Relaxed),
+            post_id: post.id.clone(),
+            frames: room.frames.subscribe(),
+            room,
+        })
+    }
+
+    pub async fn leave(&self, peer: &Peer) {
+        let mut rooms = self.rooms.lock().await;
+        if peer.room.peers.fetch_sub(1, Ordering::SeqCst) == 1
+            && rooms.get(&peer.post_id).is_some_and(|room| Arc::ptr_eq(room, &peer.room))
+        {
+            rooms.remove(&peer.post_id);
+        }
+    }
+
+    /// Drops a post's room after its document was reset, disconnecting its editors.
+    pub async fn close(&self, post_id: &str) {
+        if let Some(room) = self.rooms.lock().await.remove(post_id) {
+            let _ = room.frames.send(Frame::Closed);
+        }
+    }
+}
+
+/// Periodically folds collaborative edits into post content, so published pages and the
+/// API see them without waiting for a commit.
+pub struct CollabCompactor {
+    period: Duration,
+    pool: DbPool,
+    cache: Arc<dyn Cache>,
+    leases: Leases,
+    tasks: Tasks,
+}
+
+impl CollabCompactor {
+    pub fn new(config: &Config, pool: DbPool, cache: Arc<dyn Cache>, leases: Leases) -> Self {
+        Self {
+            period: Duration::from_secs(config.collab_compact_interval_seconds().max(1)),
+            pool,
+            cache,
+            leases,
+            tasks: Tasks::new(),
+        }
+    }
+}
+
+/// Compacts every post with pending updates. Returns the authors whose published posts
+/// changed, for cache invalidation.
+fn compact_pending(conn: &mut SqliteConnection) -> QueryResult<Vec<String>> {
+    let mut authors = Vec::new();
+    for post_id in PostDocUpdates::pending_posts(conn)? {
+        match compact(conn, &post_id) {
+            Ok(Some(post)) if post.is_published() => {
+                if let Some(author) = UserModel::by_id(conn, &post.user_id)? {
+                    authors.push(author.name);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to compact collaborative document for post {}: {}", post_id, e),
+        }
+    }
+    Ok(authors)
+}
+
+#[async_trait]
+impl Service for CollabCompactor {
+    fn name(&self) -> &'static str {
+        "collab-compactor"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let period = self.period;
+        let pool = self.pool.clone();
+        let cache = self.cache.clone();
+        let mut lease = self.leases.lease(self.name());
+
+        self.tasks.spawn(|mut shutdown| async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.wait() => break,
+                }
+                if !lease.hold().await {
+                    continue;
+                }
+
+                let pool = pool.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let mut conn = pool.get().map_err(|e| e.to_string())?;
+                    compact_pending(&mut conn).map_err(|e| e.to_string())
+                })
+                .await;
+
+                match result {
+                    Ok(Ok(authors)) => {
+                        for author in authors {
+                            cache::invalidate_author_pages(cache.as_ref(), &author).await;
+                        }
+                    }
+                    Ok(Err(e)) => tracing::error!("Failed to compact collaborative documents: {}", e),
+                    Err(e) => tracing::error!("Collaborative compaction task panicked: {}", e),
+                }
+            }
+
+            lease.release().await;
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::config::{config_file, Config, ConfigHandle};
+use crate::errors::AuthError;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+
+/// The running subscriber's filter, swapped when `LOG_LEVEL` changes.
+pub type LogFilter = reload::Handle<EnvFilter, Registry>;
+
+/// Sets the subscriber's filter to the configured `LOG_LEVEL`.
+pub fn apply_log_level(log_filter: &LogFilter, config: &Config) {
+    // The level was validated when the config was loaded.
+    let Ok(filter) = EnvFilter::try_new(config.log_level()) else {
+        return;
+    };
+    if let Err(e) = log_filter.reload(filter) {
+        tracing::warn!("Failed to change the log level: {}", e);
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn reload(config: &ConfigHandle, log_filter: &LogFilter, trigger: &str) {
+    match config.reload() {
+        Ok(changed) if changed.is_empty() => {
+            tracing::info!("Reloaded configuration after {}; no reloadable setting changed", trigger);
+        }
+        Ok(changed) => {
+            // Logged first, since the new level may hide it.
+            tracing::info!("Reloaded configuration after {}: {} changed", trigger, changed.join(", "));
+            if changed.contains(&"log level") {
+                apply_log_level(log_filter, &config.load());
+            }
+        }
+        Err(e) => tracing::error!("Kept the running configuration after {}: {}", trigger, e),
+    }
+}
+
+/// Reloads the configuration on `SIGHUP`, and when the config file's modification time
+/// changes. Only some settings change on a running server; see [`ConfigHandle::reload`].
+pub struct ConfigWatcher {
+    config: ConfigHandle,
+    log_filter: LogFilter,
+    /// How often the file is checked; `None` leaves reloads to `SIGHUP`.
+    period: Option<Duration>,
+    tasks: Tasks,
+}
+
+impl ConfigWatcher {
+    pub fn new(config: ConfigHandle, log_filter: LogFilter) -> Self {
+        let interval = config.load().config_watch_interval_seconds();
+        Self {
+            period: (interval > 0).then(|| Duration::from_secs(interval)),
+            config,
+            log_filter,
+            tasks: Tasks::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Service for ConfigWatcher {
+    fn name(&self) -> &'static str {
+        "config-watcher"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        #[cfg(unix)]
+        let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+            .map_err(|e| AuthError::internal(format!("Failed to listen for SIGHUP: {}", e)))?;
+
+        let config = self.config.clone();
+        let log_filter = self.log_filter.clone();
+        let period = self.period;
+        let path = config_file();
+        let mut last_modified = path.as_deref().and_then(modified);
+
+        self.tasks.spawn(|mut shutdown| async move {
+            loop {
+                #[cfg(unix)]
+                let hangup = hangup.recv();
+                #[cfg(not(unix))]
+                let hangup = std::future::pending::<Option<()>>();
+
+                let poll = async {
+                    match period {
+                        Some(period) => tokio::time::sleep(period).await,
+                        None => std::future::pending().await,
+                    }
+                };
+
+                tokio::select! {
+                    _ = hangup => reload(&config, &log_filter, "SIGHUP"),
+                    _ = poll => {
+                        let current = path.as_deref().and_then(modified);
+                        if current != last_modified {
+                            last_modified = current;
+                            reload(&config, &log_filter, "a config file change");
+                        }
+                    }
+                    _ = shutdown.wait() => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
@@ -0,0 +1,111 @@
+use time::Duration;
+use tower_cookies::cookie::SameSite;
+use tower_cookies::{Cookie, Cookies};
+
+use crate::config::Config;
+
+/// The cookies the server sets. Names and attributes all come from `Config`, so they're only
+/// ever built here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthCookie {
+    Access,
+    Refresh,
+    Sudo,
+    /// Echoed back in a header or form field by state-changing requests; see `http::csrf`.
+    Csrf,
+    /// Ties a GitHub sign-in's callback to the browser that started it.
+    OauthState,
+    /// Messages for the next page rendered; see `services::flash`.
+    Flash,
+}
+
+impl AuthCookie {
+    pub fn name(self, config: &Config) -> &str {
+        match self {
+            AuthCookie::Access => config.access_cookie_name(),
+            AuthCookie::Refresh => config.refresh_cookie_name(),
+            AuthCookie::Sudo => config.sudo_cookie_name(),
+            AuthCookie::Csrf => config.csrf_cookie_name(),
+            AuthCookie::OauthState => config.oauth_state_cookie_name(),
+            AuthCookie::Flash => config.flash_cookie_name(),
+        }
+    }
+}
+
+/// `kind` holding `value`: HTTP-only, with the configured domain, path, SameSite and secure
+/// flag. Without a `max_age` it's a session cookie, dropped when the browser closes.
+pub fn build(config: &Config, kind: AuthCookie, value: impl Into<String>, max_age: Option<Duration>) -> Cookie<'static> {
+    let mut cookie = Cookie::build((kind.name(config).to_owned(), value.into()))
+        .path(config.cookie_path().to_owned())
+        .secure(config.secure_cookies())
+        .http_only(true)
+        .same_site(same_site(config, kind))
+        .build();
+    if let Some(domain) = config.cookie_domain() {
+        cookie.set_domain(domain.to_owned());
+    }
+    if let Some(max_age) = max_age {
+        cookie.set_max_age(max_age);
+    }
+    cookie
+}
+
+/// The configured SameSite, except that the OAuth state and flash cookies are at most Lax: they
+/// have to come back on the redirect from GitHub, which Strict cookies don't.
+fn same_site(config: &Config, kind: AuthCookie) -> SameSite {
+    match (kind, config.cookie_same_site()) {
+        (AuthCookie::OauthState | AuthCookie::Flash, SameSite::Strict) => SameSite::Lax,
+        (_, same_site) => same_site,
+    }
+}
+
+pub fn set(cookies: &Cookies, config: &Config, kind: AuthCookie, value: impl Into<String>, max_age: Option<Duration>) {
+    cookies.add(build(config, kind, value, max_age));
+}
+
+pub fn get(cookies: &Cookies, config: &Config, kind: AuthCookie) -> Option<String> {
+    cookies.get(kind.name(config)).map(|cookie| cookie.value().to_owned())
+}
+
+/// Has the browser drop `kind`. Sent with the same domain and path it was set with, or the
+/// browser would keep the original.
+pub fn clear(cookies: &Cookies, config: &Config, kind: AuthCookie) {
+    cookies.add(build(config, kind, "", Some(Duration::ZERO)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cookies_carry_the_configured_name_and_attributes() {
+        let config = Config::from_values([
+            ("DATABASE_URL", "test.db"),
+            ("CORS_ORIGIN", "http://localhost"),
+            ("ACCESS_SECRET", "access"),
+            ("ACCESS_EXPIRES", "1"),
+            ("REFRESH_TOKEN", "refresh"),
+            ("REFRESH_EXPIRES", "24"),
+            ("GITHUB_OAUTH_CLIENT_ID", "id"),
+            ("GITHUB_OAUTH_CLIENT_SECRET", "secret"),
+            ("COOKIE_DOMAIN", "tsumi.test"),
+            ("COOKIE_PATH", "/app"),
+            ("COOKIE_SAME_SITE", "lax"),
+            ("COOKIE_SECURE", "true"),
+            ("COOKIE_REFRESH_NAME", "__Secure-rt"),
+        ])
+        .unwrap();
+
+        let cookie = build(&config, AuthCookie::Refresh, "token", Some(Duration::days(1)));
+        assert_eq!(cookie.name(), "__Secure-rt");
+        assert_eq!(cookie.value(), "token");
+        assert_eq!(cookie.domain(), Some("tsumi.test"));
+        assert_eq!(cookie.path(), Some("/app"));
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.max_age(), Some(Duration::days(1)));
+
+        assert_eq!(build(&config, AuthCookie::Sudo, "", None).max_age(), None);
+    }
+}
@@ -0,0 +1,53 @@
+use diesel::SqliteConnection;
+
+use crate::db::models::user_device::UserDevices;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::http::client::ClientInfo;
+use crate::services::audit::{self, AUDIT_NEW_DEVICE_SIGN_IN};
+use crate::services::email_queue::{self, EmailPriority};
+use crate::services::email_templates::{self, NewDevice};
+use crate::state::AppState;
+
+/// Remembers the device the user just signed in from and, when it's one they haven't used
+/// before, lets them know by email. The very first device an account signs in from is only
+/// remembered: there's nothing to compare it with yet.
+pub async fn note_sign_in(
+    state: &AppState,
+    conn: &mut SqliteConnection,
+    user: &UserModel,
+    client: &ClientInfo,
+) -> Result<(), AuthError> {
+    let label = client.device();
+    let now = chrono::Utc::now();
+
+    let login = format!("{}/login", state.config.load().public_url());
+    let time = now.format("%Y-%m-%d %H:%M UTC").to_string();
+    let locale = client.locale_for(user.locale.as_deref());
+    let message = email_templates::new_device(&state.templates, locale, &user.name, &user.email, &NewDevice {
+        device: &label,
+        ip_address: client.ip_address.as_deref(),
+        time: &time,
+        login_url: &login,
+    })
+    .map_err(|e| AuthError::internal(format!("Failed to render new device email: {}", e)))?;
+
+    let noticed = state.writes.write(conn, |conn| {
+        let known = UserDevices::count_for_user(conn, &user.id)?;
+        let is_new = UserDevices::record(conn, &user.id, &label, now.naive_utc())?;
+        let notice = is_new && known > 0;
+        if notice {
+            email_queue::enqueue(conn, &message, EmailPriority::Transactional)?;
+        }
+        Ok(notice)
+    })
+    .await
+    .map_err(|e: diesel::result::Error| AuthError::database(format!("Failed to record device: {}", e)))?;
+    if !noticed {
+        return Ok(());
+    }
+
+    state.email_queue.wake(EmailPriority::Transactional);
+    audit::record(state, client, AUDIT_NEW_DEVICE_SIGN_IN, Some(&user.id), None, Some(&label)).await;
+    Ok(())
+}
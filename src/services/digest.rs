@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::config::Config;
+use crate::db::models::post::Posts;
+use crate::db::models::user_model::UserModel;
+use crate::db::models::user_preferences::UserPreferences;
+use crate::db::write::write;
+use crate::http::locale::Locale;
+use crate::services::email::EmailMessage;
+use crate::services::email_queue::{self, EmailPriority, EmailQueue};
+use crate::services::email_templates::{self, DigestPost};
+use crate::services::lifecycle::Shutdown;
+use crate::services::scheduler::{Job, Schedule};
+use crate::services::templates::Templates;
+use crate::state::DbPool;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const DIGEST_PERIOD_DAYS: i64 = 7;
+
+/// Users handled per run.
+const DIGEST_BATCH: i64 = 50;
+
+/// Posts listed in one digest.
+const DIGEST_MAX_POSTS: i64 = 20;
+
+const UNSUBSCRIBE_PURPOSE: &str = "digest-unsubscribe";
+
+fn unsubscribe_mac(secret: &str, user_id: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(UNSUBSCRIBE_PURPOSE.as_bytes());
+    mac.update(b":");
+    mac.update(user_id.as_bytes());
+    mac
+}
+
+/// A token for the digest's unsubscribe link: the user id and an HMAC of it. It never
+/// expires, so links in old digests keep working.
+pub fn unsubscribe_token(secret: &str, user_id: &str) -> String {
+    let signature = unsubscribe_mac(secret, user_id).finalize().into_bytes();
+    format!("{}.{}", URL_SAFE_NO_PAD.encode(user_id), URL_SAFE_NO_PAD.encode(signature))
+}
+
+/// The user id an unsubscribe token was signed for, if the signature holds.
+pub fn verify_unsubscribe_token(secret: &str, token: &str) -> Option<String> {
+    let (user_id, signature) = token.split_once('.')?;
+    let user_id = String::from_utf8(URL_SAFE_NO_PAD.decode(user_id).ok()?).ok()?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    unsubscribe_mac(secret, &user_id).verify_slice(&signature).ok()?;
+    Some(user_id)
+}
+
+fn render(templates: &Templates, user: &UserModel, posts: &[(Posts, String)], public_url: &str, unsubscribe_url: &str) -> tera::Result<EmailMessage> {
+    let posts: Vec<DigestPost> = posts
+        .iter()
+        .map(|(post, author)| DigestPost {
+            title: post.title.clone(),
+            author: author.clone(),
+            url: format!("{}/{}/{}", public_url, author, post.slug),
+        })
+        .collect();
+    email_templates::digest(templates, Locale::of_user(user.locale.as_deref()), &user.name, &user.email, &posts, unsubscribe_url)
+}
+
+/// Sends each user who wants it a weekly email of new posts from the authors they follow.
+/// Users with nothing new that week are skipped until the next one.
+pub struct DigestWorker {
+    period: Duration,
+    public_url: String,
+    secret: String,
+    pool: DbPool,
+    email_queue: EmailQueue,
+    templates: Templates,
+}
+
+impl DigestWorker {
+    pub fn new(config: &Config, pool: DbPool, email_queue: EmailQueue, templates: Templates) -> Self {
+        Self {
+            period: Duration::from_secs(config.digest_check_interval_seconds().max(1)),
+            public_url: config.public_url().to_string(),
+            secret: config.access_token_secret().to_string(),
+            pool,
+            email_queue,
+            templates,
+        }
+    }
+}
+
+/// Queues the digests of users who are due one, recording them as sent. Returns how many
+/// were queued.
+fn queue_due(conn: &mut diesel::SqliteConnection, templates: &Templates, public_url: &str, secret: &str) -> Result<usize, String> {
+    let now = Utc::now().naive_utc();
+    let period = chrono::Duration::days(DIGEST_PERIOD_DAYS);
+    let mut queued = 0;
+
+    for (user, last_sent) in UserPreferences::due_for_digest(conn, now - period, DIGEST_BATCH).map_err(|e| e.to_string())? {
+        let since: NaiveDateTime = last_sent.unwrap_or(now - period);
+        let posts = Posts::followed_since(conn, &user.id, since, DIGEST_MAX_POSTS).map_err(|e| e.to_string())?;
+        let message = if posts.is_empty() {
+            None
+        } else {
+            let unsubscribe_url = format!(
+                "{}/api/v1/digest/unsubscribe?token={}",
+                public_url,
+                unsubscribe_token(secret, &user.id)
+            );
+            Some(render(templates, &user, &posts, public_url, &unsubscribe_url).map_err(|e| e.to_string())?)
+        };
+        // Queued and marked sent together, so a digest goes out once.
+        write(conn, |conn| {
+            if let Some(message) = &message {
+                email_queue::enqueue(conn, message, EmailPriority::Bulk)?;
+            }
+            UserPreferences::mark_digest_sent(conn, &user.id, now)
+        })
+        .map_err(|e: diesel::result::Error| e.to_string())?;
+        queued += usize::from(message.is_some());
+    }
+
+    Ok(queued)
+}
+
+#[async_trait]
+impl Job for DigestWorker {
+    fn name(&self) -> &'static str {
+        "digests"
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(self.period)
+    }
+
+    async fn run(&self, _shutdown: Shutdown) -> Result<(), String> {
+        let pool = self.pool.clone();
+        let public_url = self.public_url.clone();
+        let secret = self.secret.clone();
+        let templates = self.templates.clone();
+        let queued = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| e.to_string())?;
+            queue_due(&mut conn, &templates, &public_url, &secret)
+        })
+        .await
+        .map_err(|e| format!("digest task panicked: {}", e))??;
+
+        if queued > 0 {
+            self.email_queue.wake(EmailPriority::Bulk);
+            tracing::info!("Queued {} weekly digest(s)", queued);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsubscribe_tokens_only_verify_with_their_secret() {
+        let token = unsubscribe_token("secret", "user-1");
+        assert_eq!(verify_unsubscribe_token("secret", &token).as_deref(), Some("user-1"));
+        assert_eq!(verify_unsubscribe_token("other", &token), None);
+
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = format!("{}.{}", URL_SAFE_NO_PAD.encode("user-2"), signature);
+        assert_eq!(verify_unsubscribe_token("secret", &forged), None);
+    }
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use lettre::message::{Mailbox, MultiPart};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::Config;
+use crate::db::models::email_suppression::EmailSuppressions;
+use crate::errors::AuthError;
+use crate::state::DbPool;
+
+#[derive(Debug, Clone)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SendOutcome {
+    Sent,
+    Suppressed,
+}
+
+enum Transport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    /// Writes messages to the log instead of delivering them; used when no SMTP server is configured.
+    Log,
+}
+
+#[derive(Clone)]
+pub struct EmailService {
+    transport: Arc<Transport>,
+    from: Mailbox,
+    db_pool: DbPool,
+}
+
+impl EmailService {
+    pub fn new(config: &Config, db_pool: DbPool) -> Self {
+        let transport = match config.smtp_url() {
+            // Checked when the config was loaded.
+            Some(url) => Transport::Smtp(
+                AsyncSmtpTransport::<Tokio1Executor>::from_url(url)
+                    .expect("SMTP_URL is a valid SMTP connection url")
+                    .build(),
+            ),
+            None => {
+                tracing::warn!("SMTP_URL is not set, outgoing email will only be logged");
+                Transport::Log
+            }
+        };
+
+        // Checked when the config was loaded.
+        let from = config
+            .email_from_address()
+            .parse::<Mailbox>()
+            .expect("EMAIL_FROM is a valid mailbox");
+
+        Self {
+            transport: Arc::new(transport),
+            from,
+            db_pool,
+        }
+    }
+
+    /// Sends a message unless the recipient is on the suppression list.
+    pub async fn send(&self, message: EmailMessage) -> Result<SendOutcome, AuthError> {
+        let suppressed = {
+            let mut conn = self.db_pool.get()
+                .map_err(|e| AuthError::internal(format!("Database connection failed: {}", e)))?;
+            EmailSuppressions::is_suppressed(&mut conn, &message.to)
+                .map_err(|e| AuthError::database(format!("Failed to check suppression list: {}", e)))?
+        };
+
+        if suppressed {
+            tracing::info!("Skipping email to suppressed address: {}", message.to);
+            return Ok(SendOutcome::Suppressed);
+        }
+
+        match self.transport.as_ref() {
+            Transport::Smtp(smtp) => {
+                let email = self.build(&message)?;
+                smtp.send(email)
+                    .await
+                    .map_err(|e| AuthError::internal(format!("Failed to send email: {}", e)))?;
+            }
+            Transport::Log => {
+                tracing::info!(
+                    "Email to {} with subject '{}':\n{}",
+                    message.to,
+                    message.subject,
+                    message.text_body
+                );
+            }
+        }
+
+        Ok(SendOutcome::Sent)
+    }
+
+    fn build(&self, message: &EmailMessage) -> Result<Message, AuthError> {
+        let to = message.to
+            .parse::<Mailbox>()
+            .map_err(|e| AuthError::validation(format!("Invalid recipient address: {}", e)))?;
+
+        let builder = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&message.subject);
+
+        let email = match &message.html_body {
+            Some(html) => builder.multipart(MultiPart::alternative_plain_html(
+                message.text_body.clone(),
+                html.clone(),
+            )),
+            None => builder.body(message.text_body.clone()),
+        };
+
+        email.map_err(|e| AuthError::internal(format!("Failed to build email: {}", e)))
+    }
+}
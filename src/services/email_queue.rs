@@ -0,0 +1,275 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::{QueryResult, SqliteConnection};
+use tokio::sync::Notify;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::Config;
+use crate::db::models::email_outbox::{
+    EmailOutbox, EMAIL_OUTBOX_PENDING, EMAIL_OUTBOX_SENT, EMAIL_OUTBOX_SUPPRESSED,
+};
+use crate::errors::AuthError;
+use crate::services::email::{EmailMessage, EmailService, SendOutcome};
+use crate::services::job_locks::Leases;
+use crate::services::lifecycle::{Service, ServiceHealth, Shutdown, Tasks};
+use crate::state::DbPool;
+
+/// Messages a lane loads from the outbox at a time.
+const SEND_BATCH: i64 = 50;
+
+/// The first retry waits this long, and each after it twice as long as the last.
+const FIRST_RETRY_SECONDS: i64 = 60;
+const MAX_RETRY_SECONDS: i64 = 60 * 60;
+
+/// Delivery lane for an outgoing email.
+///
+/// Transactional mail (verification, password reset) and bulk mail (digests, newsletters)
+/// are drained by separate workers with independent rate limits, so a large bulk send
+/// never sits in front of a password reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailPriority {
+    Transactional,
+    Bulk,
+}
+
+impl EmailPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Transactional => "transactional",
+            Self::Bulk => "bulk",
+        }
+    }
+}
+
+/// Adds `message` to the outbox. Call it inside the transaction making the change the
+/// message is about, so the two are kept or rolled back together, then
+/// [`EmailQueue::wake`] the lane once it commits.
+pub fn enqueue(conn: &mut SqliteConnection, message: &EmailMessage, priority: EmailPriority) -> QueryResult<()> {
+    let now = Utc::now().naive_utc();
+    EmailOutbox::create(conn, &EmailOutbox {
+        id: uuid::Uuid::new_v4().to_string(),
+        recipient: message.to.clone(),
+        subject: message.subject.clone(),
+        text_body: message.text_body.clone(),
+        html_body: message.html_body.clone(),
+        priority: priority.as_str().to_string(),
+        status: EMAIL_OUTBOX_PENDING.to_string(),
+        attempts: 0,
+        next_attempt_at: now,
+        last_error: None,
+        created_at: now,
+        sent_at: None,
+    })
+    .map(|_| ())
+}
+
+/// How long to wait before the next attempt, after `attempts` have failed.
+fn backoff(attempts: i32) -> chrono::Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    chrono::Duration::seconds(FIRST_RETRY_SECONDS.saturating_mul(1 << doublings).min(MAX_RETRY_SECONDS))
+}
+
+/// Sends what's in the outbox. Failed sends are retried with exponential backoff until
+/// `EMAIL_MAX_ATTEMPTS` is reached, after which the message is marked failed. Of the servers
+/// sharing a database, only the one holding a lane's lock sends from it.
+#[derive(Clone)]
+pub struct EmailQueue {
+    workers: Arc<LaneWorkers>,
+}
+
+struct LaneWorkers {
+    mailer: EmailService,
+    pool: DbPool,
+    transactional_rate_per_minute: u32,
+    bulk_rate_per_minute: u32,
+    poll_interval: Duration,
+    max_attempts: i32,
+    leases: Leases,
+    transactional_wake: Notify,
+    bulk_wake: Notify,
+    tasks: Tasks,
+}
+
+impl LaneWorkers {
+    fn wake(&self, priority: EmailPriority) -> &Notify {
+        match priority {
+            EmailPriority::Transactional => &self.transactional_wake,
+            EmailPriority::Bulk => &self.bulk_wake,
+        }
+    }
+
+    /// The lane's lock in `job_locks`, so only one server sends from it.
+    fn lock_name(priority: EmailPriority) -> &'static str {
+        match priority {
+            EmailPriority::Transactional => "email-queue:transactional",
+            EmailPriority::Bulk => "email-queue:bulk",
+        }
+    }
+
+    fn rate_per_minute(&self, priority: EmailPriority) -> u32 {
+        match priority {
+            EmailPriority::Transactional => self.transactional_rate_per_minute,
+            EmailPriority::Bulk => self.bulk_rate_per_minute,
+        }
+    }
+}
+
+impl EmailQueue {
+    pub fn new(config: &Config, pool: DbPool, mailer: EmailService, leases: Leases) -> Self {
+        Self {
+            workers: Arc::new(LaneWorkers {
+                mailer,
+                pool,
+                transactional_rate_per_minute: config.email_transactional_rate_per_minute(),
+                bulk_rate_per_minute: config.email_bulk_rate_per_minute(),
+                poll_interval: Duration::from_secs(config.email_outbox_poll_interval_seconds().max(1)),
+                max_attempts: config.email_max_attempts(),
+                leases,
+                transactional_wake: Notify::new(),
+                bulk_wake: Notify::new(),
+                tasks: Tasks::new(),
+            }),
+        }
+    }
+
+    /// Has the lane check the outbox now rather than at its next poll.
+    pub fn wake(&self, priority: EmailPriority) {
+        self.workers.wake(priority).notify_one();
+    }
+}
+
+#[async_trait]
+impl Service for EmailQueue {
+    fn name(&self) -> &'static str {
+        "email-queue"
+    }
+
+    /// Spawns one worker per lane.
+    async fn start(&self) -> Result<(), AuthError> {
+        for priority in [EmailPriority::Transactional, EmailPriority::Bulk] {
+            let workers = self.workers.clone();
+            self.workers.tasks.spawn(move |shutdown| run_lane(priority, workers, shutdown));
+        }
+        Ok(())
+    }
+
+    /// Waits for the message being sent, if any. The rest stay in the outbox for next time.
+    async fn stop(&self) {
+        self.workers.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.workers.tasks.health()
+    }
+}
+
+async fn run_lane(priority: EmailPriority, workers: Arc<LaneWorkers>, mut shutdown: Shutdown) {
+    let mut limiter = tokio::time::interval(Duration::from_secs(60) / workers.rate_per_minute(priority).max(1));
+    limiter.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut lease = workers.leases.lease(LaneWorkers::lock_name(priority));
+
+    'lane: loop {
+        while lease.hold().await {
+            let due = match load_due(&workers.pool, priority).await {
+                Ok(due) => due,
+                Err(e) => {
+                    tracing::error!("Failed to load due {} email: {}", priority.as_str(), e);
+                    break;
+                }
+            };
+            let full = due.len() as i64 == SEND_BATCH;
+
+            for message in due {
+                tokio::select! {
+                    _ = limiter.tick() => {}
+                    _ = shutdown.wait() => break 'lane,
+                }
+                // A slow lane can outlast the lease; the rest of the batch is left to
+                // whichever server took it over.
+                if !lease.hold().await {
+                    break;
+                }
+                send(&workers, message).await;
+            }
+            // A short batch was the last of what's due; anything sent from it that failed
+            // waits for its backoff.
+            if !full {
+                break;
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(workers.poll_interval) => {}
+            _ = workers.wake(priority).notified() => {}
+            _ = shutdown.wait() => break,
+        }
+    }
+
+    lease.release().await;
+    tracing::info!("{} email queue stopped", priority.as_str());
+}
+
+async fn load_due(pool: &DbPool, priority: EmailPriority) -> Result<Vec<EmailOutbox>, String> {
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| e.to_string())?;
+        EmailOutbox::due(&mut conn, priority.as_str(), Utc::now().naive_utc(), SEND_BATCH).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Sends one message and records how it went.
+async fn send(workers: &LaneWorkers, message: EmailOutbox) {
+    let attempts = message.attempts + 1;
+    let result = workers.mailer.send(EmailMessage {
+        to: message.recipient.clone(),
+        subject: message.subject,
+        text_body: message.text_body,
+        html_body: message.html_body,
+    }).await;
+
+    let id = message.id;
+    let recipient = message.recipient;
+    let max_attempts = workers.max_attempts;
+    let pool = workers.pool.clone();
+    let recorded = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| e.to_string())?;
+        let now = Utc::now().naive_utc();
+        match result {
+            Ok(SendOutcome::Sent) => EmailOutbox::mark_done(&mut conn, &id, EMAIL_OUTBOX_SENT, attempts, now),
+            Ok(SendOutcome::Suppressed) => EmailOutbox::mark_done(&mut conn, &id, EMAIL_OUTBOX_SUPPRESSED, attempts, now),
+            Err(e) => {
+                let retry_at = (attempts < max_attempts).then(|| now + backoff(attempts));
+                match retry_at {
+                    Some(retry_at) => tracing::warn!("Failed to send email {} to {}, retrying at {}: {}", id, recipient, retry_at, e),
+                    None => tracing::error!("Giving up on email {} to {} after {} attempts: {}", id, recipient, attempts, e),
+                }
+                EmailOutbox::mark_attempt_failed(&mut conn, &id, attempts, &e.to_string(), retry_at)
+            }
+        }
+        .map_err(|e| format!("Failed to record email {}: {}", id, e))
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|recorded| recorded);
+
+    if let Err(e) = recorded {
+        tracing::error!("{}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_a_cap() {
+        assert_eq!(backoff(1), chrono::Duration::seconds(60));
+        assert_eq!(backoff(3), chrono::Duration::seconds(240));
+        assert_eq!(backoff(30), chrono::Duration::seconds(MAX_RETRY_SECONDS));
+    }
+}
@@ -0,0 +1,123 @@
+//! Translates bounce and complaint webhooks from email providers into suppression events.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::db::models::email_suppression::{SUPPRESSION_REASON_BOUNCE, SUPPRESSION_REASON_COMPLAINT};
+use crate::errors::AuthError;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SuppressionEvent {
+    pub email: String,
+    pub reason: &'static str,
+    pub details: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SnsEnvelope {
+    #[serde(rename = "Type")]
+    kind: String,
+    message: Option<String>,
+    subscribe_url: Option<String>,
+}
+
+/// Parses an SES notification delivered through SNS. Only permanent bounces and complaints
+/// produce events; subscription confirmations are logged so an operator can confirm them.
+pub fn parse_ses(body: &str) -> Result<Vec<SuppressionEvent>, AuthError> {
+    let envelope: SnsEnvelope = serde_json::from_str(body)
+        .map_err(|e| AuthError::validation(format!("Invalid SNS payload: {}", e)))?;
+
+    match envelope.kind.as_str() {
+        "SubscriptionConfirmation" => {
+            tracing::warn!(
+                "SES suppression webhook needs confirmation: {}",
+                envelope.subscribe_url.unwrap_or_default()
+            );
+            return Ok(Vec::new());
+        }
+        "Notification" => {}
+        other => {
+            tracing::debug!("Ignoring SNS message of type {}", other);
+            return Ok(Vec::new());
+        }
+    }
+
+    let message: Value = serde_json::from_str(&envelope.message.unwrap_or_default())
+        .map_err(|e| AuthError::validation(format!("Invalid SES notification: {}", e)))?;
+
+    let kind = message["notificationType"]
+        .as_str()
+        .or_else(|| message["eventType"].as_str())
+        .unwrap_or_default();
+
+    let events = match kind {
+        "Bounce" if message["bounce"]["bounceType"] == "Permanent" => {
+            let details = message["bounce"]["bounceSubType"].as_str().map(String::from);
+            recipients(&message["bounce"]["bouncedRecipients"], "emailAddress")
+                .map(|email| SuppressionEvent { email, reason: SUPPRESSION_REASON_BOUNCE, details: details.clone() })
+                .collect()
+        }
+        "Complaint" => {
+            let details = message["complaint"]["complaintFeedbackType"].as_str().map(String::from);
+            recipients(&message["complaint"]["complainedRecipients"], "emailAddress")
+                .map(|email| SuppressionEvent { email, reason: SUPPRESSION_REASON_COMPLAINT, details: details.clone() })
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    Ok(events)
+}
+
+/// Parses a Mailgun webhook. Only permanent failures and complaints produce events.
+pub fn parse_mailgun(body: &str) -> Result<Vec<SuppressionEvent>, AuthError> {
+    let payload: Value = serde_json::from_str(body)
+        .map_err(|e| AuthError::validation(format!("Invalid Mailgun payload: {}", e)))?;
+
+    let event = &payload["event-data"];
+    let Some(email) = event["recipient"].as_str() else {
+        return Ok(Vec::new());
+    };
+
+    let reason = match event["event"].as_str() {
+        Some("failed") if event["severity"] == "permanent" => SUPPRESSION_REASON_BOUNCE,
+        Some("complained") => SUPPRESSION_REASON_COMPLAINT,
+        _ => return Ok(Vec::new()),
+    };
+
+    Ok(vec![SuppressionEvent {
+        email: email.to_string(),
+        reason,
+        details: event["delivery-status"]["description"].as_str().map(String::from),
+    }])
+}
+
+/// Parses a Postmark bounce or spam complaint webhook.
+pub fn parse_postmark(body: &str) -> Result<Vec<SuppressionEvent>, AuthError> {
+    let payload: Value = serde_json::from_str(body)
+        .map_err(|e| AuthError::validation(format!("Invalid Postmark payload: {}", e)))?;
+
+    let Some(email) = payload["Email"].as_str() else {
+        return Ok(Vec::new());
+    };
+
+    let reason = match (payload["RecordType"].as_str(), payload["Type"].as_str()) {
+        (Some("Bounce"), Some("HardBounce" | "BadEmailAddress")) => SUPPRESSION_REASON_BOUNCE,
+        (Some("SpamComplaint"), _) => SUPPRESSION_REASON_COMPLAINT,
+        _ => return Ok(Vec::new()),
+    };
+
+    Ok(vec![SuppressionEvent {
+        email: email.to_string(),
+        reason,
+        details: payload["Description"].as_str().map(String::from),
+    }])
+}
+
+fn recipients<'a>(list: &'a Value, field: &'a str) -> impl Iterator<Item = String> + 'a {
+    list.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(move |recipient| recipient[field].as_str().map(String::from))
+}
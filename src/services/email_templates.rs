@@ -0,0 +1,177 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+use tera::Context;
+
+use crate::http::locale::Locale;
+use crate::services::email::EmailMessage;
+use crate::services::templates::Templates;
+
+/// The emails rendered from `templates/emails/`, each with an `.html` and a `.txt` part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTemplate {
+    Verification,
+    PasswordReset,
+    NewDevice,
+    Digest,
+}
+
+impl EmailTemplate {
+    pub const ALL: &'static [EmailTemplate] = &[Self::Verification, Self::PasswordReset, Self::NewDevice, Self::Digest];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Verification => "verification",
+            Self::PasswordReset => "password_reset",
+            Self::NewDevice => "new_device",
+            Self::Digest => "digest",
+        }
+    }
+
+    /// The email with made-up details, for previews and snapshot tests.
+    pub fn sample(self, templates: &Templates, locale: Locale) -> tera::Result<EmailMessage> {
+        let name = "ann";
+        let to = "ann@example.com";
+        match self {
+            Self::Verification => verification(templates, locale, name, to, "https://tsumi.example/api/v1/auth/verify-email?token=sample", 24),
+            Self::PasswordReset => password_reset(templates, locale, name, to, "https://tsumi.example/reset-password?token=sample", 60),
+            Self::NewDevice => new_device(templates, locale, name, to, &NewDevice {
+                device: "Firefox on Linux",
+                ip_address: Some("203.0.113.7"),
+                time: "2025-06-26 20:00 UTC",
+                login_url: "https://tsumi.example/login",
+            }),
+            Self::Digest => digest(templates, locale, name, to, &[
+                DigestPost {
+                    title: "Notes on <Rust> & SQLite".to_string(),
+                    author: "bob".to_string(),
+                    url: "https://tsumi.example/bob/notes-on-rust".to_string(),
+                },
+                DigestPost {
+                    title: "A week of walks".to_string(),
+                    author: "cat".to_string(),
+                    url: "https://tsumi.example/cat/a-week-of-walks".to_string(),
+                },
+            ], "https://tsumi.example/api/v1/digest/unsubscribe?token=sample"),
+        }
+    }
+}
+
+impl FromStr for EmailTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|template| template.name() == s)
+            .ok_or_else(|| format!("no email template named {}", s))
+    }
+}
+
+/// What the new-device alert says about the sign in.
+pub struct NewDevice<'a> {
+    pub device: &'a str,
+    pub ip_address: Option<&'a str>,
+    pub time: &'a str,
+    pub login_url: &'a str,
+}
+
+/// One post in a digest.
+#[derive(Debug, Serialize)]
+pub struct DigestPost {
+    pub title: String,
+    pub author: String,
+    pub url: String,
+}
+
+pub fn verification(templates: &Templates, locale: Locale, name: &str, to: &str, link: &str, hours: i64) -> tera::Result<EmailMessage> {
+    let mut ctx = Context::new();
+    ctx.insert("link", link);
+    ctx.insert("hours", &hours);
+    render(templates, EmailTemplate::Verification, locale, name, to, locale.text("email.verification.subject").to_string(), ctx)
+}
+
+pub fn password_reset(templates: &Templates, locale: Locale, name: &str, to: &str, link: &str, minutes: i64) -> tera::Result<EmailMessage> {
+    let mut ctx = Context::new();
+    ctx.insert("link", link);
+    ctx.insert("minutes", &minutes);
+    render(templates, EmailTemplate::PasswordReset, locale, name, to, locale.text("email.password_reset.subject").to_string(), ctx)
+}
+
+pub fn new_device(templates: &Templates, locale: Locale, name: &str, to: &str, sign_in: &NewDevice<'_>) -> tera::Result<EmailMessage> {
+    let mut ctx = Context::new();
+    ctx.insert("device", sign_in.device);
+    ctx.insert("ip_address", &sign_in.ip_address);
+    ctx.insert("time", sign_in.time);
+    ctx.insert("login_url", sign_in.login_url);
+    render(templates, EmailTemplate::NewDevice, locale, name, to, locale.text("email.new_device.subject").to_string(), ctx)
+}
+
+pub fn digest(
+    templates: &Templates,
+    locale: Locale,
+    name: &str,
+    to: &str,
+    posts: &[DigestPost],
+    unsubscribe_url: &str,
+) -> tera::Result<EmailMessage> {
+    let subject = match posts.len() {
+        1 => locale.text("email.digest.subject_one").to_string(),
+        count => locale.format("email.digest.subject_other", &[("count", &count.to_string())]),
+    };
+    let mut ctx = Context::new();
+    ctx.insert("posts", posts);
+    ctx.insert("unsubscribe_url", unsubscribe_url);
+    render(templates, EmailTemplate::Digest, locale, name, to, subject, ctx)
+}
+
+/// Renders both parts of `template` in `locale`. Every template gets `lang`, `name` (the
+/// recipient's) and `subject` on top of its own variables.
+fn render(
+    templates: &Templates,
+    template: EmailTemplate,
+    locale: Locale,
+    name: &str,
+    to: &str,
+    subject: String,
+    mut ctx: Context,
+) -> tera::Result<EmailMessage> {
+    ctx.insert("lang", locale.tag());
+    ctx.insert("name", name);
+    ctx.insert("subject", &subject);
+
+    Ok(EmailMessage {
+        to: to.to_string(),
+        text_body: templates.render(&format!("emails/{}.txt", template.name()), &ctx)?,
+        html_body: Some(templates.render(&format!("emails/{}.html", template.name()), &ctx)?),
+        subject,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::http::assets::AssetManifest;
+
+    #[test]
+    fn every_email_renders_as_it_did_in_every_locale() {
+        let templates = Templates::embedded(Arc::new(AssetManifest::default()));
+        for template in EmailTemplate::ALL {
+            for locale in Locale::ALL {
+                let message = template.sample(&templates, *locale).unwrap();
+                let html = message.html_body.as_deref().unwrap_or_default();
+                let snapshot = format!("Subject: {}\n\n{}\n\n{}", message.subject, message.text_body, html);
+                insta::assert_snapshot!(format!("{}_{}", template.name(), locale.tag()), snapshot);
+            }
+        }
+    }
+
+    #[test]
+    fn templates_are_named_by_their_files() {
+        assert_eq!("new_device".parse::<EmailTemplate>(), Ok(EmailTemplate::NewDevice));
+        assert!("welcome".parse::<EmailTemplate>().is_err());
+    }
+}
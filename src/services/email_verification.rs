@@ -0,0 +1,38 @@
+use diesel::{QueryResult, SqliteConnection};
+
+use crate::db::models::email_verification_token::EmailVerificationTokens;
+use crate::errors::AuthError;
+use crate::http::locale::Locale;
+use crate::services::email::EmailMessage;
+use crate::services::email_queue::{self, EmailPriority};
+use crate::services::email_templates;
+use crate::state::AppState;
+use crate::utils::generate_token;
+
+const VERIFICATION_TOKEN_HOURS: i64 = 24;
+
+/// A verification link for an address, and the email carrying it.
+pub struct VerificationEmail {
+    token: String,
+    message: EmailMessage,
+}
+
+impl VerificationEmail {
+    /// Renders the email for `name` at `email` with a fresh token. Rendered ahead of the
+    /// transaction that queues it, so a template error can't leave half a change behind.
+    pub fn render(state: &AppState, locale: Locale, name: &str, email: &str) -> Result<Self, AuthError> {
+        let token = generate_token();
+        let link = format!("{}/api/v1/auth/verify-email?token={}", state.config.load().public_url(), token);
+        let message = email_templates::verification(&state.templates, locale, name, email, &link, VERIFICATION_TOKEN_HOURS)
+            .map_err(|e| AuthError::internal(format!("Failed to render verification email: {}", e)))?;
+        Ok(Self { token, message })
+    }
+
+    /// Makes this the user's only verification token and queues the email. Run it in the
+    /// transaction that set the address, then wake the transactional lane.
+    pub fn queue(&self, conn: &mut SqliteConnection, user_id: &str) -> QueryResult<()> {
+        EmailVerificationTokens::delete_by_user(conn, user_id)?;
+        EmailVerificationTokens::create(conn, &self.token, user_id, VERIFICATION_TOKEN_HOURS)?;
+        email_queue::enqueue(conn, &self.message, EmailPriority::Transactional)
+    }
+}
@@ -0,0 +1,376 @@
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::config::Config;
+use crate::db::models::api_token::ApiTokens;
+use crate::db::models::comment::Comments;
+use crate::db::models::export_job::ExportJobs;
+use crate::db::models::follow::Follows;
+use crate::db::models::post::Posts;
+use crate::db::models::post_version::PostVersions;
+use crate::db::models::push_subscription::PushSubscriptions;
+use crate::db::models::tag::Tags;
+use crate::db::models::user_model::UserModel;
+use crate::db::models::user_preferences::UserPreferences;
+use crate::db::models::webhook::Webhooks;
+use crate::errors::AuthError;
+use crate::http::pagination::SortDir;
+use crate::services::job_locks::Leases;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+use crate::services::notifications::format_time_of_day;
+use crate::services::storage::Storage;
+use crate::state::DbPool;
+
+/// Expired archives deleted per sweep.
+const EXPIRY_BATCH: i64 = 50;
+
+pub fn storage_key(job: &ExportJobs) -> String {
+    format!("exports/{}/{}.zip", job.user_id, job.id)
+}
+
+/// Writes JSON and markdown files into an in-memory zip.
+struct Archive {
+    zip: ZipWriter<Cursor<Vec<u8>>>,
+}
+
+impl Archive {
+    fn new() -> Self {
+        Self { zip: ZipWriter::new(Cursor::new(Vec::new())) }
+    }
+
+    fn add(&mut self, path: &str, contents: &[u8]) -> Result<(), String> {
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        self.zip.start_file(path, options).map_err(|e| format!("Failed to add {}: {}", path, e))?;
+        self.zip.write_all(contents).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+
+    fn add_json(&mut self, path: &str, value: &impl Serialize) -> Result<(), String> {
+        let contents = serde_json::to_vec_pretty(value).map_err(|e| format!("Failed to serialize {}: {}", path, e))?;
+        self.add(path, &contents)
+    }
+
+    fn finish(self) -> Result<Vec<u8>, String> {
+        self.zip.finish().map(Cursor::into_inner).map_err(|e| format!("Failed to finish archive: {}", e))
+    }
+}
+
+fn profile(conn: &mut SqliteConnection, user: &UserModel) -> QueryResult<serde_json::Value> {
+    let preferences = UserPreferences::for_user(conn, &user.id)?;
+    let following: Vec<String> = Follows::following_page(conn, &user.id, SortDir::Asc, 0, i64::MAX)?
+        .into_iter()
+        .map(|(followee, _)| followee.name)
+        .collect();
+    let followers = Follows::count_followers(conn, &user.id)?;
+
+    Ok(json!({
+        "id": user.id,
+        "name": user.name,
+        "email": user.email,
+        "email_verified": user.email_verified,
+        "created_at": user.created_at,
+        "bio": user.bio,
+        "website": user.website,
+        "location": user.location,
+        "profile_private": user.profile_private,
+        "show_email": user.show_email,
+        "timezone": user.timezone,
+        "quiet_hours": user.quiet_hours_start.zip(user.quiet_hours_end).map(|(start, end)| {
+            format!("{}-{}", format_time_of_day(start), format_time_of_day(end))
+        }),
+        "canonicalize_links": user.canonicalize_links,
+        "weekly_digest": preferences.weekly_digest,
+        "following": following,
+        "follower_count": followers,
+    }))
+}
+
+/// Credentials are listed so the user can see what exists, never with their secrets.
+fn account(conn: &mut SqliteConnection, user: &UserModel) -> QueryResult<serde_json::Value> {
+    let api_tokens: Vec<serde_json::Value> = ApiTokens::by_user(conn, &user.id)?
+        .into_iter()
+        .map(|token| json!({
+            "name": token.name,
+            "token_prefix": token.token_prefix,
+            "scopes": token.scope_list(),
+            "last_used_at": token.last_used_at,
+            "expires_at": token.expires_at,
+            "created_at": token.created_at,
+        }))
+        .collect();
+    let webhooks: Vec<serde_json::Value> = Webhooks::for_user(conn, &user.id)?
+        .into_iter()
+        .map(|webhook| json!({
+            "url": webhook.url,
+            "events": webhook.event_list(),
+            "created_at": webhook.created_at,
+        }))
+        .collect();
+    let push_subscriptions: Vec<serde_json::Value> = PushSubscriptions::for_user(conn, &user.id)?
+        .into_iter()
+        .map(|subscription| json!({
+            "endpoint": subscription.endpoint,
+            "user_agent": subscription.user_agent,
+            "created_at": subscription.created_at,
+        }))
+        .collect();
+    let mut linked_accounts = Vec::new();
+    for provider in ["github"] {
+        if UserModel::linked_account_id(conn, &user.id, provider)?.is_some() {
+            linked_accounts.push(provider);
+        }
+    }
+
+    Ok(json!({
+        "is_admin": user.is_admin,
+        "updated_at": user.updated_at,
+        "linked_accounts": linked_accounts,
+        "api_tokens": api_tokens,
+        "webhooks": webhooks,
+        "push_subscriptions": push_subscriptions,
+    }))
+}
+
+/// Every post as it reads in the editor, at `posts/{slug}.md`, with its saved versions under
+/// `posts/{slug}/versions/`. `posts.json` indexes them with their metadata.
+fn add_posts(conn: &mut SqliteConnection, user: &UserModel, archive: &mut Archive) -> Result<(), String> {
+    let posts = Posts::all_by_user(conn, &user.id).map_err(|e| e.to_string())?;
+    let mut index = Vec::with_capacity(posts.len());
+    for post in posts {
+        let tags: Vec<String> = Tags::by_post(conn, &post.id)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        let versions = PostVersions::by_post(conn, &post.id).map_err(|e| e.to_string())?;
+
+        let file = format!("posts/{}.md", post.slug);
+        archive.add(&file, post.content.as_bytes())?;
+        let mut version_files = Vec::with_capacity(versions.len());
+        // Numbered oldest first, since two saves can share a timestamp and even a hash.
+        for (number, version) in versions.into_iter().rev().enumerate() {
+            let short_hash = version.commit_hash.get(..7).unwrap_or(&version.commit_hash);
+            let path = format!("posts/{}/versions/{:04}-{}.md", post.slug, number + 1, short_hash);
+            archive.add(&path, version.content.as_bytes())?;
+            version_files.push(json!({
+                "file": path,
+                "title": version.title,
+                "commit_hash": version.commit_hash,
+                "commit_message": version.commit_message,
+                "created_at": version.created_at,
+            }));
+        }
+
+        index.push(json!({
+            "id": post.id,
+            "file": file,
+            "title": post.title,
+            "description": post.description,
+            "slug": post.slug,
+            "status": post.status,
+            "tags": tags,
+            "created_at": post.created_at,
+            "updated_at": post.updated_at,
+            "published_at": post.published_at,
+            "versions": version_files,
+        }));
+    }
+    archive.add_json("posts.json", &index)
+}
+
+/// Assembles the user's export: `profile.json`, `account.json`, `posts.json` with the posts
+/// themselves under `posts/`, and `comments.json`.
+pub fn build_archive(conn: &mut SqliteConnection, user_id: &str) -> Result<Vec<u8>, String> {
+    let user = UserModel::by_id(conn, user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("User {} no longer exists", user_id))?;
+
+    let mut archive = Archive::new();
+    archive.add_json("profile.json", &profile(conn, &user).map_err(|e| e.to_string())?)?;
+    archive.add_json("account.json", &account(conn, &user).map_err(|e| e.to_string())?)?;
+    add_posts(conn, &user, &mut archive)?;
+
+    let comments: Vec<serde_json::Value> = Comments::by_user(conn, &user.id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|comment| json!({
+            "id": comment.id,
+            "post_id": comment.post_id,
+            "parent_id": comment.parent_id,
+            "body": comment.body,
+            "created_at": comment.created_at,
+            "updated_at": comment.updated_at,
+            "deleted_at": comment.deleted_at,
+        }))
+        .collect();
+    archive.add_json("comments.json", &comments)?;
+
+    archive.finish()
+}
+
+/// Assembles requested exports one at a time and deletes archives once they expire.
+pub struct ExportWorker {
+    period: Duration,
+    ttl: chrono::Duration,
+    pool: DbPool,
+    storage: Arc<dyn Storage>,
+    leases: Leases,
+    tasks: Tasks,
+}
+
+impl ExportWorker {
+    pub fn new(config: &Config, pool: DbPool, storage: Arc<dyn Storage>, leases: Leases) -> Self {
+        Self {
+            period: Duration::from_secs(config.export_poll_interval_seconds().max(1)),
+            ttl: chrono::Duration::hours(config.export_ttl_hours()),
+            pool,
+            storage,
+            leases,
+            tasks: Tasks::new(),
+        }
+    }
+}
+
+/// Assembles the next pending export, returning whether there was one.
+async fn run_next(pool: &DbPool, storage: &dyn Storage, ttl: chrono::Duration) -> Result<bool, String> {
+    let build_pool = pool.clone();
+    let built = tokio::task::spawn_blocking(move || {
+        let mut conn = build_pool.get().map_err(|e| e.to_string())?;
+        let Some(job) = ExportJobs::claim_next(&mut conn).map_err(|e| e.to_string())? else {
+            return Ok(None);
+        };
+        let archive = build_archive(&mut conn, &job.user_id);
+        Ok::<_, String>(Some((job, archive)))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let Some((job, archive)) = built else {
+        return Ok(false);
+    };
+
+    let key = storage_key(&job);
+    let outcome = match archive {
+        Ok(bytes) => {
+            let size = bytes.len() as i64;
+            storage.put(&key, "application/zip", bytes).await.map(|_| size).map_err(|e| e.to_string())
+        }
+        Err(e) => Err(e),
+    };
+
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| e.to_string())?;
+        match outcome {
+            Ok(size) => {
+                ExportJobs::mark_ready(&mut conn, &job.id, &key, size, Utc::now().naive_utc() + ttl).map_err(|e| e.to_string())?;
+                tracing::info!("Export {} for user {} is ready ({} bytes)", job.id, job.user_id, size);
+            }
+            Err(e) => {
+                tracing::error!("Export {} for user {} failed: {}", job.id, job.user_id, e);
+                ExportJobs::mark_failed(&mut conn, &job.id, "The export could not be assembled").map_err(|e| e.to_string())?;
+            }
+        }
+        Ok::<_, String>(true)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Deletes archives past their expiry, returning how many were removed.
+async fn expire(pool: &DbPool, storage: &dyn Storage) -> Result<usize, String> {
+    let load_pool = pool.clone();
+    let expired = tokio::task::spawn_blocking(move || {
+        let mut conn = load_pool.get().map_err(|e| e.to_string())?;
+        ExportJobs::expired(&mut conn, Utc::now().naive_utc(), EXPIRY_BATCH).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let mut removed = Vec::with_capacity(expired.len());
+    for job in expired {
+        if let Some(key) = &job.storage_key
+            && let Err(e) = storage.delete(key).await
+        {
+            tracing::warn!("Failed to delete expired export {}: {}", key, e);
+            continue;
+        }
+        removed.push(job.id);
+    }
+
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| e.to_string())?;
+        for id in &removed {
+            ExportJobs::mark_expired(&mut conn, id).map_err(|e| e.to_string())?;
+        }
+        Ok::<_, String>(removed.len())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[async_trait]
+impl Service for ExportWorker {
+    fn name(&self) -> &'static str {
+        "export-worker"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let (period, ttl) = (self.period, self.ttl);
+        let pool = self.pool.clone();
+        let storage = self.storage.clone();
+        let mut lease = self.leases.lease(self.name());
+
+        self.tasks.spawn(|mut shutdown| async move {
+            loop {
+                if !lease.hold().await {
+                    tokio::select! {
+                        _ = tokio::time::sleep(period) => continue,
+                        _ = shutdown.wait() => break,
+                    }
+                }
+
+                match expire(&pool, storage.as_ref()).await {
+                    Ok(0) => {}
+                    Ok(removed) => tracing::info!("Deleted {} expired export(s)", removed),
+                    Err(e) => tracing::error!("Failed to expire exports: {}", e),
+                }
+
+                // Queued exports run back to back; the worker only sleeps once the queue is empty.
+                let delay = match run_next(&pool, storage.as_ref(), ttl).await {
+                    Ok(true) => Duration::ZERO,
+                    Ok(false) => period,
+                    Err(e) => {
+                        tracing::error!("Export worker failed: {}", e);
+                        period
+                    }
+                };
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = shutdown.wait() => break,
+                }
+            }
+
+            lease.release().await;
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::db::models::feature_flag::{
+    FeatureFlags, FEATURE_COMMENTS, FEATURE_FLAGS, FEATURE_OAUTH_GITHUB, FEATURE_SIGNUP,
+};
+
+/// A flag known at compile time, for guarding handlers with `Enabled<F>`.
+pub trait Flag: Send + Sync + 'static {
+    const NAME: &'static str;
+    /// Sent with the 403 when the flag is off.
+    const DISABLED_MESSAGE: &'static str;
+}
+
+pub struct SignupFeature;
+
+impl Flag for SignupFeature {
+    const NAME: &'static str = FEATURE_SIGNUP;
+    const DISABLED_MESSAGE: &'static str = "Sign ups are currently disabled";
+}
+
+pub struct GithubOAuthFeature;
+
+impl Flag for GithubOAuthFeature {
+    const NAME: &'static str = FEATURE_OAUTH_GITHUB;
+    const DISABLED_MESSAGE: &'static str = "Signing in with GitHub is currently disabled";
+}
+
+pub struct CommentsFeature;
+
+impl Flag for CommentsFeature {
+    const NAME: &'static str = FEATURE_COMMENTS;
+    const DISABLED_MESSAGE: &'static str = "Commenting is currently disabled";
+}
+
+pub fn description(name: &str) -> &'static str {
+    match name {
+        FEATURE_SIGNUP => "New accounts can be created",
+        FEATURE_OAUTH_GITHUB => "Users can sign in with GitHub",
+        FEATURE_COMMENTS => "Users can comment on posts",
+        _ => "",
+    }
+}
+
+pub fn is_known(name: &str) -> bool {
+    FEATURE_FLAGS.contains(&name)
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FlagState {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub enabled: bool,
+    /// The configured value, which applies again once the override is removed.
+    pub default: bool,
+    pub overridden: bool,
+    pub updated_by: Option<String>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+fn states(default: impl Fn(&str) -> bool, overrides: Vec<FeatureFlags>) -> Vec<FlagState> {
+    let mut overrides: HashMap<String, FeatureFlags> =
+        overrides.into_iter().map(|flag| (flag.name.clone(), flag)).collect();
+
+    FEATURE_FLAGS
+        .into_iter()
+        .map(|name| {
+            let default = default(name);
+            let stored = overrides.remove(name);
+            FlagState {
+                name,
+                description: description(name),
+                enabled: stored.as_ref().map_or(default, |flag| flag.enabled),
+                default,
+                overridden: stored.is_some(),
+                updated_by: stored.as_ref().and_then(|flag| flag.updated_by.clone()),
+                updated_at: stored.map(|flag| flag.updated_at),
+            }
+        })
+        .collect()
+}
+
+/// Every flag with its current value: an admin's override when there is one, the configured
+/// default otherwise.
+pub fn list(conn: &mut SqliteConnection, config: &Config) -> QueryResult<Vec<FlagState>> {
+    Ok(states(|name| config.feature_flag_default(name), FeatureFlags::all(conn)?))
+}
+
+pub fn is_enabled(conn: &mut SqliteConnection, config: &Config, name: &str) -> QueryResult<bool> {
+    Ok(FeatureFlags::by_name(conn, name)?.map_or_else(|| config.feature_flag_default(name), |flag| flag.enabled))
+}
+
+/// Flag names to values, as handed to templates under `features`.
+pub fn template_values(states: &[FlagState]) -> BTreeMap<&'static str, bool> {
+    states.iter().map(|state| (state.name, state.enabled)).collect()
+}
+
+/// What templates see when the overrides can't be read.
+pub fn defaults(config: &Config) -> Vec<FlagState> {
+    states(|name| config.feature_flag_default(name), Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_win_over_configured_defaults() {
+        let overrides = vec![FeatureFlags {
+            name: FEATURE_COMMENTS.to_string(),
+            enabled: true,
+            updated_by: Some("admin".to_string()),
+            updated_at: NaiveDateTime::default(),
+        }];
+        let flags = states(|name| name != FEATURE_SIGNUP && name != FEATURE_COMMENTS, overrides);
+        let values = template_values(&flags);
+
+        assert!(!values[FEATURE_SIGNUP]);
+        assert!(values[FEATURE_OAUTH_GITHUB]);
+        assert!(values[FEATURE_COMMENTS]);
+
+        let comments = flags.iter().find(|flag| flag.name == FEATURE_COMMENTS).unwrap();
+        assert!(comments.overridden);
+        assert!(!comments.default);
+        assert_eq!(comments.updated_by.as_deref(), Some("admin"));
+    }
+}
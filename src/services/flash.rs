@@ -0,0 +1,95 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tower_cookies::Cookies;
+
+use crate::config::Config;
+use crate::services::cookies::{self, AuthCookie};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const FLASH_PURPOSE: &str = "flash";
+
+/// Messages queued at once beyond this are dropped, keeping the cookie small.
+const MAX_MESSAGES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlashLevel {
+    Notice,
+    Error,
+}
+
+/// A message shown once, on the next page rendered, such as after a redirect.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flash {
+    pub level: FlashLevel,
+    pub message: String,
+}
+
+fn flash_mac(secret: &str, payload: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(FLASH_PURPOSE.as_bytes());
+    mac.update(b":");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// The messages as a cookie value: their JSON and an HMAC of it, so a page never shows a
+/// message the server didn't queue.
+fn encode(secret: &str, messages: &[Flash]) -> String {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(messages).unwrap_or_default());
+    let signature = flash_mac(secret, &payload).finalize().into_bytes();
+    format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(signature))
+}
+
+fn decode(secret: &str, value: &str) -> Option<Vec<Flash>> {
+    let (payload, signature) = value.split_once('.')?;
+    flash_mac(secret, payload).verify_slice(&URL_SAFE_NO_PAD.decode(signature).ok()?).ok()?;
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()
+}
+
+/// The queued messages, without clearing them. Unsigned or garbled cookies hold none.
+pub fn peek(cookies: &Cookies, config: &Config) -> Vec<Flash> {
+    cookies::get(cookies, config, AuthCookie::Flash)
+        .and_then(|value| decode(config.access_token_secret(), &value))
+        .unwrap_or_default()
+}
+
+/// Queues `message` for the next page rendered.
+pub fn push(cookies: &Cookies, config: &Config, level: FlashLevel, message: impl Into<String>) {
+    let mut messages = peek(cookies, config);
+    if messages.len() < MAX_MESSAGES {
+        messages.push(Flash { level, message: message.into() });
+    }
+    cookies::set(cookies, config, AuthCookie::Flash, encode(config.access_token_secret(), &messages), None);
+}
+
+/// The queued messages, cleared so they're only shown once.
+pub fn take(cookies: &Cookies, config: &Config) -> Vec<Flash> {
+    let messages = peek(cookies, config);
+    if cookies::get(cookies, config, AuthCookie::Flash).is_some() {
+        cookies::clear(cookies, config, AuthCookie::Flash);
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_only_come_back_with_a_valid_signature() {
+        let messages = vec![Flash { level: FlashLevel::Notice, message: "Welcome back".to_string() }];
+        let value = encode("secret", &messages);
+        assert_eq!(decode("secret", &value), Some(messages));
+        assert_eq!(decode("other secret", &value), None);
+
+        let (_, signature) = value.split_once('.').unwrap();
+        let forged = format!("{}.{}", URL_SAFE_NO_PAD.encode(r#"[{"level":"error","message":"Call us"}]"#), signature);
+        assert_eq!(decode("secret", &forged), None);
+        assert_eq!(decode("secret", "garbage"), None);
+    }
+}
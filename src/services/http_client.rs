@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode};
+
+use crate::config::Config;
+
+/// Retries back off from `HTTP_RETRY_BASE_MS`, doubling each time, up to this.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// The one HTTP client every outbound integration shares, so connections are pooled and every
+/// call gets the configured timeouts and proxy. Redirects aren't followed: none of the
+/// integrations need them, and a webhook URL shouldn't be able to bounce a signed request
+/// somewhere else.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    retries: u32,
+    retry_base: Duration,
+}
+
+impl HttpClient {
+    pub fn new(config: &Config) -> Self {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(config.http_connect_timeout_seconds().max(1)))
+            .timeout(Duration::from_secs(config.http_timeout_seconds().max(1)))
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent(concat!("tsumi/", env!("CARGO_PKG_VERSION")));
+        if let Some(proxy_url) = config.http_proxy_url() {
+            // Checked when the config was loaded.
+            builder = builder.proxy(reqwest::Proxy::all(proxy_url).expect("HTTP_PROXY_URL is a valid proxy"));
+        }
+
+        Self {
+            client: builder.build().expect("Failed to build HTTP client"),
+            retries: config.http_retries(),
+            retry_base: Duration::from_millis(config.http_retry_base_ms()),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// Sends `request`, trying again after network errors, 5xx and 429 answers, with a
+    /// jittered backoff between tries. Only for calls that are safe to repeat: a one-time code
+    /// or token would be spent by the first try.
+    pub async fn send_idempotent(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let mut retry = 0;
+        loop {
+            // Streaming bodies can't be replayed.
+            let Some(attempt) = request.try_clone() else {
+                return request.send().await;
+            };
+
+            let result = attempt.send().await;
+            let retryable = match &result {
+                Ok(response) => is_retryable_status(response.status()),
+                Err(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+            };
+            if !retryable || retry >= self.retries {
+                return result;
+            }
+
+            retry += 1;
+            let delay = retry_delay(self.retry_base, retry);
+            match &result {
+                Ok(response) => tracing::warn!("{} answered {}, retrying in {:?}", response.url(), response.status(), delay),
+                Err(e) => tracing::warn!("Request failed, retrying in {:?}: {}", delay, e),
+            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// "Full jitter": anywhere up to the doubled delay, so clients that failed together don't
+/// retry together.
+fn retry_delay(base: Duration, retry: u32) -> Duration {
+    let ceiling = base.saturating_mul(1 << retry.saturating_sub(1).min(16)).min(MAX_RETRY_DELAY);
+    let millis = ceiling.as_millis() as u64;
+    Duration::from_millis(rand::rng().random_range(0..=millis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delays_stay_under_a_doubling_capped_ceiling() {
+        let base = Duration::from_millis(200);
+        for _ in 0..100 {
+            assert!(retry_delay(base, 1) <= Duration::from_millis(200));
+            assert!(retry_delay(base, 3) <= Duration::from_millis(800));
+            assert!(retry_delay(base, 30) <= MAX_RETRY_DELAY);
+        }
+    }
+
+    #[tokio::test]
+    async fn idempotent_calls_are_retried_until_they_succeed_or_run_out() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route("/", axum::routing::get(move || {
+            let counter = counter.clone();
+            async move {
+                match counter.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => StatusCode::SERVICE_UNAVAILABLE,
+                    _ => StatusCode::OK,
+                }
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = |retries| HttpClient { client: reqwest::Client::new(), retries, retry_base: Duration::from_millis(1) };
+        let response = client(1).send_idempotent(client(1).get(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        hits.store(0, Ordering::SeqCst);
+        let response = client(2).send_idempotent(client(2).get(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn only_server_errors_and_throttling_are_retried() {
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::OK));
+    }
+}
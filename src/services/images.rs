@@ -0,0 +1,16 @@
+/// Image formats accepted for uploads and proxied avatars, as `(content type, extension,
+/// magic bytes)`.
+pub const IMAGE_TYPES: &[(&str, &str, &[u8])] = &[
+    ("image/png", "png", b"\x89PNG\r\n\x1a\n"),
+    ("image/jpeg", "jpg", b"\xff\xd8\xff"),
+    ("image/gif", "gif", b"GIF8"),
+    ("image/webp", "webp", b"RIFF"),
+];
+
+/// Matches the declared content type against the file's magic bytes, so a file can't claim to
+/// be an image it isn't.
+pub fn sniff(declared: &str, bytes: &[u8]) -> Option<(&'static str, &'static str)> {
+    let (content_type, extension, magic) = IMAGE_TYPES.iter().find(|(content_type, _, _)| *content_type == declared)?;
+    let matches = bytes.starts_with(magic) && (*content_type != "image/webp" || bytes.get(8..12) == Some(b"WEBP"));
+    matches.then_some((*content_type, *extension))
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::config::Config;
+use crate::db::connection::run_blocking;
+use crate::db::models::job_lock::JobLocks;
+use crate::db::write::write;
+use crate::state::DbPool;
+
+/// This server's side of the `job_locks` table. Every server on a database picks its own
+/// holder id at startup, and background work that must happen on only one of them is done
+/// by whichever holds its lock. Locks are leased for `JOB_LEASE_SECONDS` and renewed while
+/// held, so when a server dies its work moves to another once the lease runs out.
+#[derive(Clone)]
+pub struct Leases {
+    holder: Arc<str>,
+    ttl: Duration,
+    pool: DbPool,
+}
+
+impl Leases {
+    pub fn new(config: &Config, pool: DbPool) -> Self {
+        Self {
+            holder: uuid::Uuid::new_v4().to_string().into(),
+            ttl: Duration::from_secs(config.job_lease_seconds().max(1)),
+            pool,
+        }
+    }
+
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// When a lease taken or renewed at `now` runs out.
+    pub fn expires_at(&self, now: DateTime<Utc>) -> NaiveDateTime {
+        (now + chrono::Duration::from_std(self.ttl).unwrap_or_default()).naive_utc()
+    }
+
+    /// A lease on `name` for a worker that holds it for as long as it runs.
+    pub fn lease(&self, name: &'static str) -> Lease {
+        Lease { leases: self.clone(), name, renew_at: None }
+    }
+
+    /// Takes or renews the lock on `name`. Returns false if another server holds it.
+    pub async fn acquire(&self, name: &'static str) -> Result<bool, String> {
+        let holder = self.holder.clone();
+        let expires_at = self.expires_at(Utc::now());
+        run_blocking(&self.pool, move |conn| {
+            write(conn, |conn| JobLocks::acquire(conn, name, &holder, Utc::now().naive_utc(), expires_at))
+        })
+        .await
+    }
+
+    /// Gives up the lock on `name`, so another server can take it without waiting out the
+    /// lease. Returns false if this server no longer held it.
+    pub async fn release(&self, name: &'static str) -> Result<bool, String> {
+        let holder = self.holder.clone();
+        run_blocking(&self.pool, move |conn| write(conn, |conn| JobLocks::release(conn, name, &holder))).await
+    }
+}
+
+/// A worker's hold on its lock. The worker asks [`Lease::hold`] before each unit of work and
+/// skips the work when another server has it.
+pub struct Lease {
+    leases: Leases,
+    name: &'static str,
+    /// Set while held: when it's next renewed.
+    renew_at: Option<Instant>,
+}
+
+impl Lease {
+    /// Whether this server holds the lock, taking it if it's free. It's renewed once a third
+    /// of the lease has gone by, so a worker can ask as often as it likes. A database error
+    /// counts as not holding it.
+    pub async fn hold(&mut self) -> bool {
+        if self.renew_at.is_some_and(|at| Instant::now() < at) {
+            return true;
+        }
+
+        match self.leases.acquire(self.name).await {
+            Ok(true) => {
+                if self.renew_at.is_none() {
+                    tracing::info!("Took the {} lock", self.name);
+                }
+                self.renew_at = Some(Instant::now() + self.leases.ttl / 3);
+                true
+            }
+            Ok(false) => {
+                if self.renew_at.take().is_some() {
+                    tracing::warn!("Lost the {} lock to another server", self.name);
+                }
+                false
+            }
+            Err(e) => {
+                tracing::error!("Failed to take the {} lock: {}", self.name, e);
+                self.renew_at = None;
+                false
+            }
+        }
+    }
+
+    /// Gives the lock up if it's held. Call it when the worker stops.
+    pub async fn release(&mut self) {
+        if self.renew_at.take().is_none() {
+            return;
+        }
+        if let Err(e) = self.leases.release(self.name).await {
+            tracing::warn!("Failed to release the {} lock: {}", self.name, e);
+        }
+    }
+}
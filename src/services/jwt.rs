@@ -1,103 +1,260 @@
+use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
 use chrono::Duration;
+use jsonwebtoken::errors::ErrorKind;
 use jsonwebtoken::{encode, decode, DecodingKey, EncodingKey, Header, TokenData, Validation};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use crate::config::config;
+use crate::config::Config;
 use crate::errors::AuthError;
+use crate::services::rollout::in_rollout;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub exp: usize,
     pub iat: usize,
     pub user_id: String,
+    /// What the token is for, `access` or `refresh`. Required, so a token of another kind
+    /// signed with the same key never decodes as this one.
+    pub typ: String,
+    /// The user's token version when the token was issued; only set on access tokens.
+    #[serde(default)]
+    pub ver: i32,
+    /// Random per token, so two tokens issued in the same second still differ.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// The admin acting as `user_id`, on access tokens issued for impersonation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imp: Option<String>,
+    /// The admin's token version when impersonation started, so signing the admin out
+    /// everywhere ends it too.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imp_ver: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
 }
 
-pub async fn create_access_token(user_id: &str) -> Result<String, AuthError> {
-    let config = config().await;
-    let secret = config.access_token_secret();
-    let now = chrono::Utc::now();
-    let expire = Duration::hours(config.access_token_expires_at());
-    let exp = (now + expire).timestamp() as usize;
-    let iat = now.timestamp() as usize;
-
-    let claim = Claims {
-        iat,
-        exp,
-        user_id: user_id.to_string(),
-    };
-
-    encode(&Header::default(), &claim, &EncodingKey::from_secret(secret.as_ref()))
-        .map_err(|e| AuthError::internal(format!("Failed to create access token: {}", e)))
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SudoClaims {
+    pub exp: usize,
+    pub iat: usize,
+    pub user_id: String,
+    /// Always `sudo`; see [`Claims::typ`].
+    pub typ: String,
+    pub scope: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
 }
 
-pub async fn create_refresh_token(user_id: &str) -> Result<String, AuthError> {
-    let config = config().await;
-    let secret = config.refresh_token_secret();
-    let now = chrono::Utc::now();
-    let expire = Duration::hours(config.refresh_token_expires_at());
-    let exp = (now + expire).timestamp() as usize;
-    let iat = now.timestamp() as usize;
-
-    let claim = Claims {
-        iat,
-        exp,
-        user_id: user_id.to_string(),
-    };
-
-    encode(&Header::default(), &claim, &EncodingKey::from_secret(secret.as_ref()))
-        .map_err(|e| AuthError::internal(format!("Failed to create refresh token: {}", e)))
+const SUDO_SCOPE: &str = "sudo";
+
+const ACCESS_TYPE: &str = "access";
+const REFRESH_TYPE: &str = "refresh";
+const SUDO_TYPE: &str = "sudo";
+
+/// Impersonation tokens last at most this long, however long access tokens do.
+const IMPERSONATION_MAX_MINUTES: i64 = 60;
+
+const OPAQUE_REFRESH_TOKEN_PREFIX: &str = "rt_";
+const OPAQUE_REFRESH_TOKENS_ROLLOUT: &str = "opaque-refresh-tokens";
+
+/// A signing secret, turned into keys once instead of on every token.
+struct Keys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
 }
 
-pub async fn decode_access_token(access_token: &str) -> Result<TokenData<Claims>, AuthError> {
-    let config = config().await;
-    let secret = config.access_token_secret();
-
-    let validation = Validation::default();
-
-    decode::<Claims>(
-        access_token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &validation,
-    )
-        .map_err(|e| {
-            match e.kind() {
-                jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
-                    AuthError::unauthorized("Access token has expired")
-                }
-                jsonwebtoken::errors::ErrorKind::InvalidToken => {
-                    AuthError::unauthorized("Invalid access token")
-                }
-                jsonwebtoken::errors::ErrorKind::InvalidSignature => {
-                    AuthError::unauthorized("Invalid token signature")
-                }
-                _ => AuthError::internal(format!("Failed to decode access token: {}", e))
-            }
-        })
+impl Keys {
+    fn new(secret: &str) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+        }
+    }
 }
 
-pub async fn decode_refresh_token(refresh_token: &str) -> Result<TokenData<Claims>, AuthError> {
-    let config = config().await;
-    let secret = config.refresh_token_secret();
-
-    let validation = Validation::default();
-    decode::<Claims>(
-        refresh_token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &validation,
-    )
-        .map_err(|e| {
-            match e.kind() {
-                jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
-                    AuthError::unauthorized("Refresh token has expired")
+/// Issues and checks the server's JWTs. Built once at startup from the token settings, which
+/// need a restart to change.
+pub struct JwtService {
+    access: Keys,
+    refresh: Keys,
+    access_expires: Duration,
+    refresh_expires: Duration,
+    sudo_expires: Duration,
+    issuer: Option<String>,
+    audience: Option<String>,
+    validation: Validation,
+}
+
+impl JwtService {
+    pub fn new(config: &Config) -> Self {
+        let mut validation = Validation::default();
+        validation.leeway = config.jwt_leeway_seconds();
+        let mut required = vec!["exp"];
+        if let Some(issuer) = config.jwt_issuer() {
+            validation.set_issuer(&[issuer]);
+            required.push("iss");
+        }
+        match config.jwt_audience() {
+            Some(audience) => {
+                validation.set_audience(&[audience]);
+                required.push("aud");
+            }
+            None => validation.validate_aud = false,
+        }
+        validation.set_required_spec_claims(&required);
+
+        Self {
+            access: Keys::new(config.access_token_secret()),
+            refresh: Keys::new(config.refresh_token_secret()),
+            access_expires: Duration::hours(config.access_token_expires_at()),
+            refresh_expires: Duration::hours(config.refresh_token_expires_at()),
+            sudo_expires: Duration::minutes(config.reauth_window_minutes()),
+            issuer: config.jwt_issuer().map(String::from),
+            audience: config.jwt_audience().map(String::from),
+            validation,
+        }
+    }
+
+    fn claims(&self, typ: &str, user_id: &str, version: i32, expires: Duration) -> Claims {
+        let now = chrono::Utc::now();
+        Claims {
+            iat: now.timestamp() as usize,
+            exp: (now + expires).timestamp() as usize,
+            user_id: user_id.to_string(),
+            typ: typ.to_string(),
+            ver: version,
+            jti: Some(uuid::Uuid::new_v4().to_string()),
+            imp: None,
+            imp_ver: None,
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+        }
+    }
+
+    /// An access token for the user at `version`, their current `token_version`.
+    pub fn create_access_token(&self, user_id: &str, version: i32) -> Result<String, AuthError> {
+        encode(&Header::default(), &self.claims(ACCESS_TYPE, user_id, version, self.access_expires), &self.access.encoding)
+            .map_err(|e| AuthError::internal(format!("Failed to create access token: {}", e)))
+    }
+
+    /// An access token for the user at `version` that says `impersonator`, at their own
+    /// `impersonator_version`, is acting as them. There's no refresh token to go with it; once it
+    /// expires the admin is back to their own session.
+    pub fn create_impersonation_token(
+        &self,
+        user_id: &str,
+        version: i32,
+        impersonator: &str,
+        impersonator_version: i32,
+    ) -> Result<(String, chrono::DateTime<chrono::Utc>), AuthError> {
+        let expires = self.access_expires.min(Duration::minutes(IMPERSONATION_MAX_MINUTES));
+        let mut claims = self.claims(ACCESS_TYPE, user_id, version, expires);
+        claims.imp = Some(impersonator.to_string());
+        claims.imp_ver = Some(impersonator_version);
+
+        let token = encode(&Header::default(), &claims, &self.access.encoding)
+            .map_err(|e| AuthError::internal(format!("Failed to create impersonation token: {}", e)))?;
+        let expires_at = chrono::DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_default();
+        Ok((token, expires_at))
+    }
+
+    pub fn create_refresh_token(&self, user_id: &str) -> Result<String, AuthError> {
+        encode(&Header::default(), &self.claims(REFRESH_TYPE, user_id, 0, self.refresh_expires), &self.refresh.encoding)
+            .map_err(|e| AuthError::internal(format!("Failed to create refresh token: {}", e)))
+    }
+
+    /// Issues the refresh token for a new session. Users inside the opaque refresh token rollout
+    /// get a random token that only means something to the `refresh_tokens` table; everyone else
+    /// gets a signed JWT. The refresh endpoint accepts both. The rollout is read from `config`
+    /// as it can change while the server runs.
+    pub fn issue_refresh_token(&self, config: &Config, user_id: &str) -> Result<String, AuthError> {
+        if in_rollout(OPAQUE_REFRESH_TOKENS_ROLLOUT, user_id, config.opaque_refresh_tokens_rollout_percent()) {
+            let bytes: [u8; 32] = rand::rng().random();
+            return Ok(format!("{}{}", OPAQUE_REFRESH_TOKEN_PREFIX, BASE64_URL_SAFE_NO_PAD.encode(bytes)));
+        }
+
+        self.create_refresh_token(user_id)
+    }
+
+    /// Decodes an access token, impersonation tokens included. Sudo tokens share the access key
+    /// but carry a different `typ`, so they're rejected here.
+    pub fn decode_access_token(&self, access_token: &str) -> Result<TokenData<Claims>, AuthError> {
+        let token = decode::<Claims>(access_token, &self.access.decoding, &self.validation)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::unauthorized("Access token has expired"),
+                ErrorKind::InvalidSignature => AuthError::unauthorized("Invalid token signature"),
+                ErrorKind::InvalidToken | ErrorKind::InvalidIssuer | ErrorKind::InvalidAudience | ErrorKind::MissingRequiredClaim(_) | ErrorKind::Json(_) => {
+                    AuthError::unauthorized("Invalid access token")
                 }
-                jsonwebtoken::errors::ErrorKind::InvalidToken => {
+                _ => AuthError::internal(format!("Failed to decode access token: {}", e)),
+            })?;
+
+        if token.claims.typ != ACCESS_TYPE {
+            return Err(AuthError::unauthorized("Invalid access token"));
+        }
+
+        Ok(token)
+    }
+
+    pub fn decode_refresh_token(&self, refresh_token: &str) -> Result<TokenData<Claims>, AuthError> {
+        let token = decode::<Claims>(refresh_token, &self.refresh.decoding, &self.validation)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::unauthorized("Refresh token has expired"),
+                ErrorKind::InvalidSignature => AuthError::unauthorized("Invalid token signature"),
+                ErrorKind::InvalidToken | ErrorKind::InvalidIssuer | ErrorKind::InvalidAudience | ErrorKind::MissingRequiredClaim(_) | ErrorKind::Json(_) => {
                     AuthError::unauthorized("Invalid refresh token")
                 }
-                jsonwebtoken::errors::ErrorKind::InvalidSignature => {
-                    AuthError::unauthorized("Invalid token signature")
-                }
-                _ => AuthError::internal(format!("Failed to decode refresh token: {}", e))
-            }
-        })
+                _ => AuthError::internal(format!("Failed to decode refresh token: {}", e)),
+            })?;
+
+        if token.claims.typ != REFRESH_TYPE {
+            return Err(AuthError::unauthorized("Invalid refresh token"));
+        }
+
+        Ok(token)
+    }
+
+    pub fn create_sudo_token(&self, user_id: &str) -> Result<(String, chrono::DateTime<chrono::Utc>), AuthError> {
+        let now = chrono::Utc::now();
+        let expires_at = now + self.sudo_expires;
+
+        let claim = SudoClaims {
+            iat: now.timestamp() as usize,
+            exp: expires_at.timestamp() as usize,
+            user_id: user_id.to_string(),
+            typ: SUDO_TYPE.to_string(),
+            scope: SUDO_SCOPE.to_string(),
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+        };
+
+        let token = encode(&Header::default(), &claim, &self.access.encoding)
+            .map_err(|e| AuthError::internal(format!("Failed to create sudo token: {}", e)))?;
+
+        Ok((token, expires_at))
+    }
+
+    pub fn decode_sudo_token(&self, sudo_token: &str) -> Result<TokenData<SudoClaims>, AuthError> {
+        let token = decode::<SudoClaims>(sudo_token, &self.access.decoding, &self.validation)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::reauth_required("Re-authentication has expired"),
+                _ => AuthError::reauth_required("Invalid re-authentication token"),
+            })?;
+
+        if token.claims.typ != SUDO_TYPE || token.claims.scope != SUDO_SCOPE {
+            return Err(AuthError::reauth_required("Invalid re-authentication token"));
+        }
+
+        Ok(token)
+    }
+}
+
+pub fn is_opaque_refresh_token(token: &str) -> bool {
+    token.starts_with(OPAQUE_REFRESH_TOKEN_PREFIX)
 }
 
 pub fn extract_user_id_from_claims(claims: &Claims) -> &str {
@@ -109,4 +266,88 @@ pub fn is_token_close_to_expiry(claims: &Claims, threshold_minutes: i64) -> bool
     let threshold_seconds = (threshold_minutes * 60) as usize;
 
     claims.exp.saturating_sub(now) <= threshold_seconds
-}
\ No newline at end of file
+}
+/// Decodes a token's claims for debugging tools. Expired tokens still decode; a token signed
+/// with a different secret decodes too, with the second value reporting the bad signature.
+pub fn inspect_token(token: &str, secret: &str) -> Result<(serde_json::Value, bool), AuthError> {
+    let mut validation = Validation::default();
+    validation.validate_exp = false;
+    validation.required_spec_claims.clear();
+
+    match decode::<serde_json::Value>(token, &DecodingKey::from_secret(secret.as_ref()), &validation) {
+        Ok(data) => Ok((data.claims, true)),
+        Err(e) if matches!(e.kind(), ErrorKind::InvalidSignature) => {
+            validation.insecure_disable_signature_validation();
+            decode::<serde_json::Value>(token, &DecodingKey::from_secret(&[]), &validation)
+                .map(|data| (data.claims, false))
+                .map_err(|e| AuthError::validation(format!("Malformed token: {}", e)))
+        }
+        Err(e) => Err(AuthError::validation(format!("Malformed token: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(extra: &[(&'static str, &'static str)]) -> JwtService {
+        let mut values = vec![
+            ("DATABASE_URL", "test.db"),
+            ("CORS_ORIGIN", "http://localhost"),
+            ("ACCESS_SECRET", "access"),
+            ("ACCESS_EXPIRES", "1"),
+            ("REFRESH_TOKEN", "refresh"),
+            ("REFRESH_EXPIRES", "24"),
+            ("GITHUB_OAUTH_CLIENT_ID", "id"),
+            ("GITHUB_OAUTH_CLIENT_SECRET", "secret"),
+        ];
+        values.extend_from_slice(extra);
+        JwtService::new(&Config::from_values(values).unwrap())
+    }
+
+    #[test]
+    fn tokens_carry_and_require_the_configured_issuer_and_audience() {
+        let jwt = service(&[("JWT_ISSUER", "tsumi"), ("JWT_AUDIENCE", "web")]);
+        let token = jwt.create_access_token("u1", 3).unwrap();
+        let claims = jwt.decode_access_token(&token).unwrap().claims;
+        assert_eq!(claims.user_id, "u1");
+        assert_eq!(claims.ver, 3);
+        assert_eq!(claims.iss.as_deref(), Some("tsumi"));
+        assert_eq!(claims.aud.as_deref(), Some("web"));
+
+        let elsewhere = service(&[("JWT_ISSUER", "tsumi"), ("JWT_AUDIENCE", "mobile")]);
+        assert!(elsewhere.decode_access_token(&token).is_err());
+        let unscoped = service(&[]).create_access_token("u1", 0).unwrap();
+        assert!(jwt.decode_access_token(&unscoped).is_err());
+    }
+
+    #[test]
+    fn access_and_refresh_tokens_are_not_interchangeable() {
+        let jwt = service(&[]);
+        let refresh = jwt.create_refresh_token("u1").unwrap();
+        assert!(jwt.decode_refresh_token(&refresh).is_ok());
+        assert!(jwt.decode_access_token(&refresh).is_err());
+        assert!(jwt.decode_sudo_token(&jwt.create_access_token("u1", 0).unwrap()).is_err());
+    }
+
+    #[test]
+    fn sudo_tokens_are_not_access_tokens() {
+        let jwt = service(&[]);
+        let (sudo, _) = jwt.create_sudo_token("u1").unwrap();
+        assert!(jwt.decode_sudo_token(&sudo).is_ok());
+        assert_eq!(jwt.decode_access_token(&sudo).unwrap_err().status_code(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn impersonation_tokens_name_the_admin_and_expire_within_the_hour() {
+        let jwt = service(&[("ACCESS_EXPIRES", "24")]);
+        let (token, expires_at) = jwt.create_impersonation_token("u1", 2, "admin", 5).unwrap();
+        let claims = jwt.decode_access_token(&token).unwrap().claims;
+        assert_eq!((claims.user_id.as_str(), claims.ver, claims.imp.as_deref()), ("u1", 2, Some("admin")));
+        assert_eq!(claims.imp_ver, Some(5));
+        assert!(expires_at <= chrono::Utc::now() + Duration::minutes(IMPERSONATION_MAX_MINUTES));
+
+        let own = jwt.decode_access_token(&jwt.create_access_token("u1", 2).unwrap()).unwrap().claims;
+        assert_eq!(own.imp, None);
+    }
+}
@@ -0,0 +1,184 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::errors::AuthError;
+
+/// How long a single service gets to stop before its tasks are abandoned.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum ServiceHealth {
+    NotStarted,
+    Running,
+    Stopped,
+    Failed(String),
+}
+
+/// A long-running background component with an explicit lifecycle.
+///
+/// Services are registered with a [`ServiceRegistry`], which starts them in registration
+/// order and stops them in reverse, so a service may rely on anything registered before it.
+#[async_trait]
+pub trait Service: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Spawns the service's tasks. Called once.
+    async fn start(&self) -> Result<(), AuthError>;
+
+    /// Asks the service's tasks to finish their current unit of work and waits for them.
+    async fn stop(&self);
+
+    fn health(&self) -> ServiceHealth;
+}
+
+/// Resolves once the owning service has been asked to stop.
+#[derive(Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub async fn wait(&mut self) {
+        // An error means the sender is gone, which is as good as a stop request.
+        let _ = self.0.wait_for(|stopping| *stopping).await;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TaskState {
+    Idle,
+    Running,
+    Stopped,
+}
+
+/// The tasks a service has spawned, and the signal that tells them to stop. Services hold one
+/// and delegate `stop` and `health` to it.
+pub struct Tasks {
+    shutdown: watch::Sender<bool>,
+    handles: Mutex<Vec<JoinHandle<()>>>,
+    state: Mutex<TaskState>,
+}
+
+impl Tasks {
+    pub fn new() -> Self {
+        Self {
+            shutdown: watch::Sender::new(false),
+            handles: Mutex::new(Vec::new()),
+            state: Mutex::new(TaskState::Idle),
+        }
+    }
+
+    /// Spawns a task that is handed a [`Shutdown`] to watch and marks the service running.
+    pub fn spawn<F, Fut>(&self, task: F)
+    where
+        F: FnOnce(Shutdown) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handle = tokio::spawn(task(Shutdown(self.shutdown.subscribe())));
+        self.handles.lock().unwrap_or_else(|e| e.into_inner()).push(handle);
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = TaskState::Running;
+    }
+
+    pub async fn stop(&self) {
+        self.shutdown.send_replace(true);
+
+        let handles = std::mem::take(&mut *self.handles.lock().unwrap_or_else(|e| e.into_inner()));
+        for handle in handles {
+            if let Err(e) = handle.await {
+                tracing::error!("Background task ended abnormally during shutdown: {}", e);
+            }
+        }
+
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = TaskState::Stopped;
+    }
+
+    pub fn health(&self) -> ServiceHealth {
+        match *self.state.lock().unwrap_or_else(|e| e.into_inner()) {
+            TaskState::Idle => ServiceHealth::NotStarted,
+            TaskState::Stopped => ServiceHealth::Stopped,
+            TaskState::Running => {
+                let handles = self.handles.lock().unwrap_or_else(|e| e.into_inner());
+                if handles.iter().any(JoinHandle::is_finished) {
+                    ServiceHealth::Failed("A worker task exited unexpectedly".to_string())
+                } else {
+                    ServiceHealth::Running
+                }
+            }
+        }
+    }
+}
+
+impl Default for Tasks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatus {
+    pub name: &'static str,
+    #[serde(flatten)]
+    pub health: ServiceHealth,
+}
+
+/// Owns every background service and manages their startup order, readiness and shutdown.
+#[derive(Default)]
+pub struct ServiceRegistry {
+    services: Vec<Arc<dyn Service>>,
+}
+
+impl ServiceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, service: Arc<dyn Service>) {
+        self.services.push(service);
+    }
+
+    /// Starts services in registration order. If one fails, those already started are stopped
+    /// again before the error is returned.
+    pub async fn start_all(&self) -> Result<(), AuthError> {
+        for (index, service) in self.services.iter().enumerate() {
+            if let Err(e) = service.start().await {
+                tracing::error!("Failed to start {}: {}", service.name(), e);
+                for started in self.services[..index].iter().rev() {
+                    stop_with_timeout(started.as_ref()).await;
+                }
+                return Err(e);
+            }
+            tracing::info!("Started {}", service.name());
+        }
+        Ok(())
+    }
+
+    /// Stops services in reverse registration order.
+    pub async fn stop_all(&self) {
+        for service in self.services.iter().rev() {
+            stop_with_timeout(service.as_ref()).await;
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<ServiceStatus> {
+        self.services
+            .iter()
+            .map(|service| ServiceStatus { name: service.name(), health: service.health() })
+            .collect()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.services.iter().all(|service| service.health() == ServiceHealth::Running)
+    }
+}
+
+async fn stop_with_timeout(service: &dyn Service) {
+    match tokio::time::timeout(STOP_TIMEOUT, service.stop()).await {
+        Ok(()) => tracing::info!("Stopped {}", service.name()),
+        Err(_) => tracing::warn!("{} did not stop within {:?}", service.name(), STOP_TIMEOUT),
+    }
+}
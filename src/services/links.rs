@@ -0,0 +1,177 @@
+use std::fs;
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::Deserialize;
+use url::Url;
+
+use crate::services::markdown::{parser_options, split_front_matter};
+
+const DEFAULT_STRIP_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid",
+    "_hsenc", "_hsmi", "mkt_tok", "ref_src", "ref_url",
+];
+
+const DEFAULT_STRIP_PARAM_PREFIXES: &[&str] = &["utm_"];
+
+/// A small slice of the HSTS preload list: whole preloaded TLDs plus popular preloaded hosts.
+/// Extend it with `https_hosts` in the rules file.
+const DEFAULT_HTTPS_HOSTS: &[&str] = &[
+    "app", "dev", "foo", "new", "page", "day", "google", "youtube", "android",
+    "google.com", "youtube.com", "github.com", "github.io", "gitlab.com", "twitter.com", "x.com",
+    "facebook.com", "instagram.com", "wikipedia.org", "mozilla.org", "paypal.com", "stripe.com",
+    "dropbox.com", "crates.io", "docs.rs", "rust-lang.org",
+];
+
+/// Rules applied to outbound links when a post is saved.
+///
+/// The built-in rules always apply; a JSON file named by `LINK_RULES_FILE` can add to them, e.g.
+/// `{"strip_params": ["ref"], "strip_param_prefixes": ["pk_"], "https_hosts": ["example.org"]}`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LinkRules {
+    /// Query parameters removed by exact (case-insensitive) name.
+    pub strip_params: Vec<String>,
+    /// Query parameters removed when their name starts with one of these.
+    pub strip_param_prefixes: Vec<String>,
+    /// Hosts whose `http://` links are upgraded to `https://`. An entry also covers its
+    /// subdomains, so `dev` covers every `.dev` domain.
+    pub https_hosts: Vec<String>,
+}
+
+impl LinkRules {
+    pub fn builtin() -> Self {
+        Self {
+            strip_params: DEFAULT_STRIP_PARAMS.iter().map(|s| s.to_string()).collect(),
+            strip_param_prefixes: DEFAULT_STRIP_PARAM_PREFIXES.iter().map(|s| s.to_string()).collect(),
+            https_hosts: DEFAULT_HTTPS_HOSTS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Built-in rules, extended by the rules file at `path` if one is configured. A file that
+    /// can't be read or parsed is logged and ignored rather than stopping the server.
+    pub fn load(path: Option<&str>) -> Self {
+        let mut rules = Self::builtin();
+        let Some(path) = path else {
+            return rules;
+        };
+
+        let extra = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| serde_json::from_str::<LinkRules>(&contents).map_err(|e| e.to_string()));
+
+        match extra {
+            Ok(extra) => {
+                rules.strip_params.extend(extra.strip_params);
+                rules.strip_param_prefixes.extend(extra.strip_param_prefixes);
+                rules.https_hosts.extend(extra.https_hosts);
+                tracing::info!("Loaded link rules from {}", path);
+            }
+            Err(e) => tracing::error!("Ignoring link rules file {}: {}", path, e),
+        }
+
+        for value in rules.strip_params.iter_mut()
+            .chain(rules.strip_param_prefixes.iter_mut())
+            .chain(rules.https_hosts.iter_mut())
+        {
+            *value = value.trim_start_matches('.').to_ascii_lowercase();
+        }
+
+        rules
+    }
+
+    /// The canonical form of an absolute http(s) URL, or `None` if no rule changes it.
+    pub fn canonicalize_url(&self, raw: &str) -> Option<String> {
+        let mut url = Url::parse(raw).ok()?;
+        let mut changed = false;
+
+        if url.scheme() == "http" && url.host_str().is_some_and(|host| self.is_https_host(host)) {
+            url.set_scheme("https").ok()?;
+            changed = true;
+        } else if url.scheme() != "https" && url.scheme() != "http" {
+            return None;
+        }
+
+        if url.query().is_some() {
+            let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+            let kept: Vec<&(String, String)> = pairs.iter().filter(|(name, _)| !self.is_tracking_param(name)).collect();
+
+            if kept.len() != pairs.len() {
+                if kept.is_empty() {
+                    url.set_query(None);
+                } else {
+                    url.query_pairs_mut().clear().extend_pairs(kept);
+                }
+                changed = true;
+            }
+        }
+
+        changed.then(|| url.to_string())
+    }
+
+    /// Rewrites link, image and reference-definition destinations in post markdown. Everything
+    /// else, including URLs inside code, is left byte-for-byte intact.
+    pub fn canonicalize_markdown(&self, source: &str) -> String {
+        let (_, body) = split_front_matter(source);
+        let body_offset = source.len() - body.len();
+
+        let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+        let mut replace = |span: Range<usize>, dest: &str, from_end: bool| {
+            let Some(canonical) = self.canonicalize_url(dest) else {
+                return;
+            };
+            let slice = &body[span.clone()];
+            let found = if from_end { slice.rfind(dest) } else { slice.find(dest) };
+            if let Some(pos) = found {
+                let start = body_offset + span.start + pos;
+                replacements.push((start..start + dest.len(), canonical));
+            }
+        };
+
+        let parser = Parser::new_ext(body, parser_options());
+        for (_, definition) in parser.reference_definitions().iter() {
+            replace(definition.span.clone(), &definition.dest, false);
+        }
+
+        for (event, span) in parser.into_offset_iter() {
+            match event {
+                // The destination follows the link text, so search from the end of the span.
+                Event::Start(Tag::Link { dest_url, .. }) | Event::Start(Tag::Image { dest_url, .. }) => {
+                    replace(span, &dest_url, true)
+                }
+                _ => {}
+            }
+        }
+
+        if replacements.is_empty() {
+            return source.to_string();
+        }
+
+        replacements.sort_by_key(|(range, _)| range.start);
+        let mut output = String::with_capacity(source.len());
+        let mut cursor = 0;
+        for (range, canonical) in replacements {
+            if range.start < cursor {
+                continue;
+            }
+            output.push_str(&source[cursor..range.start]);
+            output.push_str(&canonical);
+            cursor = range.end;
+        }
+        output.push_str(&source[cursor..]);
+        output
+    }
+
+    fn is_tracking_param(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.strip_params.contains(&name)
+            || self.strip_param_prefixes.iter().any(|prefix| name.starts_with(prefix.as_str()))
+    }
+
+    fn is_https_host(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.https_hosts.iter().any(|entry| {
+            host == *entry || host.strip_suffix(entry.as_str()).is_some_and(|rest| rest.ends_with('.'))
+        })
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Events buffered per user before a slow connection starts missing them.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Something pushed to a signed-in user's open `/ws` connections as it happens.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    NewComment {
+        post_id: String,
+        comment_id: String,
+        author: String,
+    },
+    NewReaction {
+        post_id: String,
+        reaction: String,
+        user: String,
+    },
+}
+
+/// Per-user broadcast channels for live events. A channel exists only while its user has a
+/// connection open; events for anyone else are dropped, since they're delivered through
+/// notifications anyway.
+#[derive(Default)]
+pub struct LiveHub {
+    channels: Mutex<HashMap<String, broadcast::Sender<LiveEvent>>>,
+}
+
+impl LiveHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn channels(&self) -> std::sync::MutexGuard<'_, HashMap<String, broadcast::Sender<LiveEvent>>> {
+        self.channels.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn subscribe(&self, user_id: &str) -> broadcast::Receiver<LiveEvent> {
+        self.channels()
+            .entry(user_id.to_string())
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Drops the user's channel once their last connection has gone. Call after dropping
+    /// the connection's receiver.
+    pub fn unsubscribe(&self, user_id: &str) {
+        let mut channels = self.channels();
+        if channels.get(user_id).is_some_and(|sender| sender.receiver_count() == 0) {
+            channels.remove(user_id);
+        }
+    }
+
+    pub fn publish(&self, user_id: &str, event: LiveEvent) {
+        if let Some(sender) = self.channels().get(user_id) {
+            // Only fails when every receiver has just gone away.
+            let _ = sender.send(event);
+        }
+    }
+
+    /// Users with at least one open connection.
+    pub fn connected_users(&self) -> usize {
+        self.channels().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn events_reach_only_the_users_own_connections() {
+        let hub = LiveHub::new();
+        let mut ann = hub.subscribe("ann");
+        let mut bob = hub.subscribe("bob");
+
+        hub.publish("ann", LiveEvent::NewReaction { post_id: "p".into(), reaction: "like".into(), user: "bob".into() });
+        assert!(matches!(ann.recv().await.unwrap(), LiveEvent::NewReaction { .. }));
+        assert!(bob.try_recv().is_err());
+
+        drop(ann);
+        hub.unsubscribe("ann");
+        assert_eq!(hub.connected_users(), 1);
+    }
+}
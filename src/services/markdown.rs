@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use serde::Deserialize;
+use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+
+static SANITIZER: Lazy<ammonia::Builder<'static>> = Lazy::new(|| {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_tag_attributes("span", &["class"])
+        .add_tag_attributes("code", &["class"])
+        .add_tag_attributes("pre", &["class"])
+        .add_tag_attributes("input", &["type", "checked", "disabled"])
+        .add_tags(&["input"]);
+    builder
+});
+
+/// Metadata block at the top of a markdown document, delimited by `---` lines.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub date: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_yaml::Value>,
+}
+
+/// Splits a leading front-matter block from the markdown body.
+///
+/// A block that fails to parse as YAML is left in the body so nothing the author wrote is lost.
+pub fn split_front_matter(source: &str) -> (Option<FrontMatter>, &str) {
+    let Some(rest) = source.strip_prefix("---\n").or_else(|| source.strip_prefix("---\r\n")) else {
+        return (None, source);
+    };
+
+    let Some(end) = rest.find("\n---") else {
+        return (None, source);
+    };
+
+    let yaml = &rest[..end];
+    let body = rest[end + 4..].trim_start_matches(['\r', '\n']);
+
+    match serde_yaml::from_str::<FrontMatter>(yaml) {
+        Ok(front_matter) => (Some(front_matter), body),
+        Err(e) => {
+            tracing::debug!("Ignoring invalid front matter: {}", e);
+            (None, source)
+        }
+    }
+}
+
+/// Markdown extensions enabled for posts. Anything that walks post markdown should parse with
+/// these so it sees the same structure the renderer does.
+pub fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+}
+
+/// Renders post markdown to sanitized HTML with highlighted code blocks. Front matter is
+/// stripped from the output.
+pub fn render(source: &str) -> String {
+    let (_, body) = split_front_matter(source);
+
+    let mut events = Vec::new();
+    let mut code_block: Option<(String, String)> = None;
+
+    for event in Parser::new_ext(body, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(lang) => lang.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code_block = Some((lang, String::new()));
+            }
+            Event::Text(text) if code_block.is_some() => {
+                if let Some((_, code)) = code_block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((lang, code)) = code_block.take() {
+                    events.push(Event::Html(CowStr::from(highlight(&lang, &code))));
+                }
+            }
+            event => events.push(event),
+        }
+    }
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, events.into_iter());
+
+    SANITIZER.clean(&unsafe_html).to_string()
+}
+
+fn highlight(lang: &str, code: &str) -> String {
+    let syntax = SYNTAX_SET
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, ClassStyle::Spaced);
+    for line in LinesWithEndings::from(code) {
+        if generator.parse_html_for_line_which_includes_newline(line).is_err() {
+            return format!("<pre><code>{}</code></pre>", ammonia::clean_text(code));
+        }
+    }
+
+    let class = if lang.is_empty() { String::new() } else { format!(" class=\"language-{}\"", ammonia::clean_text(lang)) };
+    format!("<pre><code{}>{}</code></pre>", class, generator.finalize())
+}
+
+/// Tera filter rendering a markdown string, for use as `{{ post.content | markdown | safe }}`.
+pub fn tera_filter(value: &tera::Value, _args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+    let source = tera::from_value::<String>(value.clone())?;
+    Ok(tera::to_value(render(&source))?)
+}
+
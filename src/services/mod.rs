@@ -1,2 +1,58 @@
 pub mod users;
 pub mod jwt;
+pub mod alt_text;
+pub mod api_tokens;
+pub mod audit;
+pub mod avatars;
+pub mod backfill;
+pub mod backups;
+pub mod blog_styles;
+pub mod cache;
+pub mod collab;
+pub mod digest;
+pub mod email;
+pub mod email_queue;
+pub mod email_templates;
+pub mod email_suppression;
+pub mod email_verification;
+pub mod exports;
+pub mod images;
+pub mod job_locks;
+pub mod lifecycle;
+pub mod links;
+pub mod live;
+pub mod markdown;
+pub mod nodeinfo;
+pub mod notifications;
+pub mod passwords;
+pub mod post_import;
+pub mod post_metadata;
+pub mod push;
+pub mod redis_cache;
+pub mod redis_sessions;
+pub mod retention;
+pub mod rollout;
+pub mod s3;
+pub mod scheduled_posts;
+pub mod scheduler;
+pub mod seed;
+pub mod sessions;
+pub mod simhash;
+pub mod sitemap;
+pub mod storage;
+pub mod webhooks;
+pub mod onboarding;
+pub mod config_watcher;
+pub mod feature_flags;
+pub mod captcha;
+pub mod password_reset;
+pub mod normalize;
+pub mod devices;
+pub mod cookies;
+pub mod templates;
+pub mod flash;
+pub mod slugs;
+pub mod views;
+pub mod spam;
+pub mod http_client;
+pub mod token_purge;
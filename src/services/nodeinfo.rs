@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDateTime};
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::comment::Comments;
+use crate::db::models::post::Posts;
+use crate::db::models::user_model::UserModel;
+
+pub const NODEINFO_SCHEMA: &str = "http://nodeinfo.diaspora.software/ns/schema/2.1";
+
+const SOFTWARE_NAME: &str = "tsumi";
+const SOFTWARE_REPOSITORY: &str = "https://github.com/namishh/tsumi";
+
+/// How much usage the NodeInfo document reveals, set with `NODEINFO_STATS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeInfoStats {
+    /// Exact counts.
+    Full,
+    /// Counts rounded to one significant digit, enough for a directory to size the instance.
+    Approximate,
+    /// No counts at all.
+    Hidden,
+}
+
+impl FromStr for NodeInfoStats {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Self::Full),
+            "approximate" => Ok(Self::Approximate),
+            "hidden" => Ok(Self::Hidden),
+            other => Err(format!("expected full, approximate or hidden, got {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Software {
+    pub name: String,
+    pub version: String,
+    pub repository: String,
+    pub homepage: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserUsage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_month: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_halfyear: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Usage {
+    pub users: UserUsage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_posts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_comments: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Services {
+    pub inbound: Vec<String>,
+    pub outbound: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+}
+
+/// A NodeInfo 2.1 document. tsumi doesn't federate, so `protocols` is empty.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeInfo {
+    pub version: String,
+    pub software: Software,
+    pub protocols: Vec<String>,
+    pub services: Services,
+    pub open_registrations: bool,
+    pub usage: Usage,
+    pub metadata: Metadata,
+}
+
+/// Rounds to one significant digit, so 1234 reads as 1000 and 87 as 90.
+fn approximate(count: i64) -> i64 {
+    if count < 10 {
+        return count;
+    }
+    let magnitude = 10_i64.pow(count.ilog10());
+    (count + magnitude / 2) / magnitude * magnitude
+}
+
+/// Counts users, posts and comments as of `now`, to the degree `stats` allows.
+pub fn usage(conn: &mut SqliteConnection, stats: NodeInfoStats, now: NaiveDateTime) -> diesel::QueryResult<Usage> {
+    if stats == NodeInfoStats::Hidden {
+        return Ok(Usage::default());
+    }
+
+    let round = |count: i64| match stats {
+        NodeInfoStats::Approximate => Some(approximate(count)),
+        _ => Some(count),
+    };
+
+    Ok(Usage {
+        users: UserUsage {
+            total: round(UserModel::count_active(conn)?),
+            active_month: round(UserModel::count_active_since(conn, now - Duration::days(30))?),
+            active_halfyear: round(UserModel::count_active_since(conn, now - Duration::days(180))?),
+        },
+        local_posts: round(Posts::count_published(conn)?),
+        local_comments: round(Comments::count_visible(conn)?),
+    })
+}
+
+pub fn document(base_url: &str, node_name: Option<&str>, usage: Usage) -> NodeInfo {
+    NodeInfo {
+        version: "2.1".to_string(),
+        software: Software {
+            name: SOFTWARE_NAME.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            repository: SOFTWARE_REPOSITORY.to_string(),
+            homepage: base_url.to_string(),
+        },
+        protocols: Vec::new(),
+        services: Services { inbound: Vec::new(), outbound: Vec::new() },
+        open_registrations: true,
+        usage,
+        metadata: Metadata { node_name: node_name.map(str::to_string) },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approximate_keeps_one_significant_digit() {
+        assert_eq!(approximate(0), 0);
+        assert_eq!(approximate(7), 7);
+        assert_eq!(approximate(14), 10);
+        assert_eq!(approximate(87), 90);
+        assert_eq!(approximate(1234), 1000);
+        assert_eq!(approximate(96_000), 100_000);
+    }
+}
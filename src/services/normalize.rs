@@ -0,0 +1,65 @@
+use unicode_normalization::UnicodeNormalization;
+
+use crate::errors::AuthError;
+
+/// Names that would shadow a page route or pass for the site itself. Compared without regard
+/// to case.
+pub const RESERVED_USERNAMES: &[&str] = &[
+    "admin", "administrator", "root", "system", "tsumi", "support", "help", "security",
+    "api", "auth", "login", "logout", "signin", "signout", "signup", "register",
+    "reset-password", "settings", "me", "posts", "p", "widgets", "static", "health", "ready",
+    "feed", "sitemap", "nodeinfo", "null", "undefined",
+];
+
+/// Request payloads that are cleaned up before they're validated or stored.
+pub trait Normalize: Sized {
+    fn normalize(self) -> Result<Self, AuthError>;
+}
+
+/// Trimmed and lowercased, so `Ann@Example.com ` and `ann@example.com` are one address.
+pub fn email(input: &str) -> String {
+    input.trim().to_lowercase()
+}
+
+/// The NFC form of a trimmed username; failures are reported against the `name` field. Only ascii letters, digits, `_` and `-` are allowed, so
+/// lookalike characters from other scripts can't imitate an existing name.
+pub fn username(input: &str) -> Result<String, AuthError> {
+    let name: String = input.trim().nfc().collect();
+
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(AuthError::invalid_field("name", "charset", "Username may only contain letters, digits, '_' and '-'"));
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return Err(AuthError::invalid_field("name", "charset", "Username must start with a letter or digit"));
+    }
+    if is_reserved(&name) {
+        return Err(AuthError::invalid_field("name", "reserved", format!("Username {} is reserved", name)));
+    }
+    Ok(name)
+}
+
+pub fn is_reserved(name: &str) -> bool {
+    RESERVED_USERNAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emails_are_trimmed_and_lowercased() {
+        assert_eq!(email("  Ann.Lee@Example.COM "), "ann.lee@example.com");
+    }
+
+    #[test]
+    fn usernames_keep_case_but_reject_lookalikes_and_reserved_names() {
+        assert_eq!(username(" Ann_Lee-2 ").unwrap(), "Ann_Lee-2");
+        // Cyrillic "а" in place of the latin one.
+        assert!(username("\u{430}nn").is_err());
+        // Decomposed "é" is composed first, then refused like the precomposed one.
+        assert!(username("jose\u{301}").is_err());
+        assert!(username("-ann").is_err());
+        assert!(username("Admin").is_err());
+        assert!(username("reset-password").is_err());
+    }
+}
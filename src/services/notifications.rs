@@ -0,0 +1,469 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use diesel::prelude::*;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::Config;
+use crate::db::models::follow::Follows;
+use crate::db::models::notification::{Notifications, NOTIFICATION_KIND_POST_PUBLISHED};
+use crate::db::models::notification_delivery::NotificationDeliveries;
+use crate::db::models::post::Posts;
+use crate::db::models::push_subscription::PushSubscriptions;
+use crate::db::models::user_model::UserModel;
+use crate::db::write::write;
+use crate::errors::AuthError;
+use crate::services::email::EmailMessage;
+use crate::services::email_queue::{self, EmailPriority, EmailQueue};
+use crate::services::job_locks::Leases;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+use crate::services::push::{PushNotification, PushOutcome, PushService};
+use crate::state::DbPool;
+
+pub const CHANNEL_EMAIL: &str = "email";
+pub const CHANNEL_PUSH: &str = "push";
+
+pub const KIND_REACTION: &str = "reaction";
+pub const KIND_COMMENT: &str = "comment";
+
+/// Deliveries handled per dispatcher tick.
+const DISPATCH_BATCH: i64 = 100;
+
+/// Something that happened to a user's post, worth telling them about.
+pub struct Event<'a> {
+    pub kind: &'static str,
+    pub recipient_id: &'a str,
+    pub actor_id: &'a str,
+    pub actor_name: &'a str,
+    pub post_id: &'a str,
+}
+
+/// Queues a notification about `event`, folding it into one already waiting for the same
+/// kind of event on the same post. The first event opens a batching window; later ones ride
+/// along without pushing it back, so a steady trickle can't hold a notification forever.
+/// People aren't notified about their own actions.
+///
+/// Notifications go out by email, and also by push to recipients with a subscribed browser.
+pub fn notify(conn: &mut SqliteConnection, config: &Config, event: &Event) -> QueryResult<()> {
+    if event.actor_id == event.recipient_id {
+        return Ok(());
+    }
+
+    queue(conn, config, event, CHANNEL_EMAIL)?;
+    if config.vapid_private_key().is_some() && PushSubscriptions::exists_for_user(conn, event.recipient_id)? {
+        queue(conn, config, event, CHANNEL_PUSH)?;
+    }
+    Ok(())
+}
+
+fn queue(conn: &mut SqliteConnection, config: &Config, event: &Event, channel: &str) -> QueryResult<()> {
+    let coalesce_key = format!("{}:{}", event.kind, event.post_id);
+    write(conn, |conn| {
+        match NotificationDeliveries::by_key(conn, event.recipient_id, channel, &coalesce_key)? {
+            Some(pending) => {
+                let mut actors = parse_actors(&pending.actors);
+                actors.retain(|name| name != event.actor_name);
+                actors.insert(0, event.actor_name.to_string());
+                NotificationDeliveries::fold(conn, &pending.id, &encode_actors(&actors), pending.event_count + 1)?;
+            }
+            None => {
+                let now = Utc::now().naive_utc();
+                NotificationDeliveries::create(conn, &NotificationDeliveries {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    user_id: event.recipient_id.to_string(),
+                    channel: channel.to_string(),
+                    coalesce_key,
+                    kind: event.kind.to_string(),
+                    post_id: event.post_id.to_string(),
+                    actors: encode_actors(&[event.actor_name.to_string()]),
+                    event_count: 1,
+                    deliver_after: now + chrono::Duration::seconds(config.notification_batch_window_seconds().max(0)),
+                    created_at: now,
+                })?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Adds a notification to the recipient's in-app list. People aren't notified about their
+/// own actions.
+pub fn record(
+    conn: &mut SqliteConnection,
+    recipient_id: &str,
+    kind: &str,
+    actor_id: &str,
+    post_id: Option<&str>,
+    comment_id: Option<&str>,
+) -> QueryResult<()> {
+    if actor_id == recipient_id {
+        return Ok(());
+    }
+
+    Notifications::create_many(conn, &[Notifications {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: recipient_id.to_string(),
+        kind: kind.to_string(),
+        actor_id: Some(actor_id.to_string()),
+        post_id: post_id.map(str::to_string),
+        comment_id: comment_id.map(str::to_string),
+        read_at: None,
+        created_at: Utc::now().naive_utc(),
+    }])?;
+    Ok(())
+}
+
+/// Tells the author's followers that `post` is out, returning how many were told. Only the
+/// first publication counts, so unpublishing and republishing doesn't notify anyone twice.
+pub fn record_published(conn: &mut SqliteConnection, post: &Posts) -> QueryResult<usize> {
+    if Notifications::exists_for_post(conn, NOTIFICATION_KIND_POST_PUBLISHED, &post.id)? {
+        return Ok(0);
+    }
+
+    let now = Utc::now().naive_utc();
+    let notifications: Vec<Notifications> = Follows::follower_ids(conn, &post.user_id)?
+        .into_iter()
+        .map(|follower_id| Notifications {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: follower_id,
+            kind: NOTIFICATION_KIND_POST_PUBLISHED.to_string(),
+            actor_id: Some(post.user_id.clone()),
+            post_id: Some(post.id.clone()),
+            comment_id: None,
+            read_at: None,
+            created_at: now,
+        })
+        .collect();
+    // Chunked to stay under SQLite's limit on bound parameters per statement.
+    let mut created = 0;
+    for chunk in notifications.chunks(500) {
+        created += Notifications::create_many(conn, chunk)?;
+    }
+    Ok(created)
+}
+
+fn parse_actors(actors: &str) -> Vec<String> {
+    serde_json::from_str(actors).unwrap_or_default()
+}
+
+fn encode_actors(actors: &[String]) -> String {
+    serde_json::to_string(actors).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Parses `HH:MM` into minutes past midnight.
+pub fn parse_time_of_day(value: &str) -> Option<i32> {
+    let time = NaiveTime::parse_from_str(value, "%H:%M").ok()?;
+    Some((time.hour() * 60 + time.minute()) as i32)
+}
+
+pub fn format_time_of_day(minutes: i32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// When the user's quiet hours end, in UTC, if `now` falls inside them. Quiet hours are
+/// minutes past local midnight in the user's time zone and may wrap past midnight; a window
+/// that starts and ends at the same minute is empty.
+pub fn quiet_until(timezone: &str, start: Option<i32>, end: Option<i32>, now: DateTime<Utc>) -> Option<NaiveDateTime> {
+    let (start, end) = (start?, end?);
+    if start == end {
+        return None;
+    }
+
+    let tz: Tz = timezone.parse().unwrap_or(Tz::UTC);
+    let local = now.with_timezone(&tz);
+    let minute = (local.hour() * 60 + local.minute()) as i32;
+    let quiet = if start < end {
+        start <= minute && minute < end
+    } else {
+        minute >= start || minute < end
+    };
+    if !quiet {
+        return None;
+    }
+
+    let end_time = NaiveTime::from_hms_opt((end / 60) as u32, (end % 60) as u32, 0)?;
+    let mut ends_at = local.date_naive().and_time(end_time);
+    if ends_at <= local.naive_local() {
+        ends_at += chrono::Duration::days(1);
+    }
+    // A window ending inside a daylight-saving gap ends when the clocks have jumped.
+    tz.from_local_datetime(&ends_at)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(ends_at + chrono::Duration::hours(1))).earliest())
+        .map(|ends_at| ends_at.naive_utc())
+}
+
+/// "ann", "ann and bob", "ann, bob and 3 others".
+fn describe_actors(actors: &[String]) -> String {
+    match actors {
+        [] => "Someone".to_string(),
+        [one] => one.clone(),
+        [first, second] => format!("{} and {}", first, second),
+        [first, second, rest @ ..] => {
+            let others = if rest.len() == 1 { "1 other".to_string() } else { format!("{} others", rest.len()) };
+            format!("{}, {} and {}", first, second, others)
+        }
+    }
+}
+
+/// A subject line and a one-sentence summary of what happened.
+fn describe(delivery: &NotificationDeliveries, post: &Posts) -> (String, String) {
+    let actors = describe_actors(&parse_actors(&delivery.actors));
+    match delivery.kind.as_str() {
+        KIND_COMMENT if delivery.event_count > 1 => (
+            format!("{} new comments on \"{}\"", delivery.event_count, post.title),
+            format!("{} left {} comments on your post \"{}\".", actors, delivery.event_count, post.title),
+        ),
+        KIND_COMMENT => (
+            format!("New comment on \"{}\"", post.title),
+            format!("{} commented on your post \"{}\".", actors, post.title),
+        ),
+        _ => (
+            format!("{} reacted to \"{}\"", actors, post.title),
+            format!("{} reacted to your post \"{}\".", actors, post.title),
+        ),
+    }
+}
+
+fn render_email(delivery: &NotificationDeliveries, user: &UserModel, post: &Posts, public_url: &str) -> EmailMessage {
+    let (subject, summary) = describe(delivery, post);
+    EmailMessage {
+        to: user.email.clone(),
+        subject,
+        text_body: format!(
+            "Hi {},\n\n{}\n\n{}/{}/{}\n\nYou can set quiet hours for these emails in your preferences.",
+            user.name, summary, public_url, user.name, post.slug
+        ),
+        html_body: None,
+    }
+}
+
+fn render_push(delivery: &NotificationDeliveries, user: &UserModel, post: &Posts, public_url: &str) -> PushNotification {
+    let (title, body) = describe(delivery, post);
+    PushNotification {
+        title,
+        body,
+        url: format!("{}/{}/{}", public_url, user.name, post.slug),
+    }
+}
+
+/// What to do with a delivery whose batching window has closed.
+enum Dispatch {
+    Email(EmailMessage),
+    Push(Vec<PushSubscriptions>, PushNotification),
+    Postpone(NaiveDateTime),
+    Drop,
+}
+
+fn prepare(conn: &mut SqliteConnection, delivery: &NotificationDeliveries, public_url: &str, now: DateTime<Utc>) -> QueryResult<Dispatch> {
+    let Some(user) = UserModel::by_id(conn, &delivery.user_id)?.filter(|user| user.deleted_at.is_none()) else {
+        return Ok(Dispatch::Drop);
+    };
+    if let Some(until) = quiet_until(&user.timezone, user.quiet_hours_start, user.quiet_hours_end, now) {
+        return Ok(Dispatch::Postpone(until));
+    }
+    let Some(post) = Posts::by_id(conn, &delivery.post_id)? else {
+        return Ok(Dispatch::Drop);
+    };
+    if delivery.channel == CHANNEL_PUSH {
+        let subscriptions = PushSubscriptions::for_user(conn, &user.id)?;
+        if subscriptions.is_empty() {
+            return Ok(Dispatch::Drop);
+        }
+        return Ok(Dispatch::Push(subscriptions, render_push(delivery, &user, &post, public_url)));
+    }
+    Ok(Dispatch::Email(render_email(delivery, &user, &post, public_url)))
+}
+
+/// Sends notifications once their batching window closes, holding them back through the
+/// recipient's quiet hours. Also prunes push subscriptions that have expired or that push
+/// services have stopped accepting.
+pub struct NotificationDispatcher {
+    period: Duration,
+    public_url: String,
+    pool: DbPool,
+    email_queue: EmailQueue,
+    push: PushService,
+    leases: Leases,
+    tasks: Tasks,
+}
+
+impl NotificationDispatcher {
+    pub fn new(config: &Config, pool: DbPool, email_queue: EmailQueue, push: PushService, leases: Leases) -> Self {
+        Self {
+            period: Duration::from_secs(config.notification_dispatch_interval_seconds().max(1)),
+            public_url: config.public_url().to_string(),
+            pool,
+            email_queue,
+            push,
+            leases,
+            tasks: Tasks::new(),
+        }
+    }
+}
+
+/// Works through the due deliveries, returning how many were sent or dropped.
+async fn dispatch_due(pool: &DbPool, email_queue: &EmailQueue, push: &PushService, public_url: &str) -> Result<usize, String> {
+    let prepare_pool = pool.clone();
+    let public_url = public_url.to_string();
+    let prepared = tokio::task::spawn_blocking(move || {
+        let mut conn = prepare_pool.get().map_err(|e| e.to_string())?;
+        let now = Utc::now();
+        match PushSubscriptions::delete_expired(&mut conn, now.naive_utc()) {
+            Ok(0) => {}
+            Ok(pruned) => tracing::info!("Pruned {} expired push subscription(s)", pruned),
+            Err(e) => tracing::error!("Failed to prune expired push subscriptions: {}", e),
+        }
+        let mut prepared = Vec::new();
+        for delivery in NotificationDeliveries::due(&mut conn, now.naive_utc(), DISPATCH_BATCH).map_err(|e| e.to_string())? {
+            match prepare(&mut conn, &delivery, &public_url, now) {
+                Ok(Dispatch::Postpone(until)) => {
+                    if let Err(e) = NotificationDeliveries::postpone(&mut conn, &delivery.id, until) {
+                        tracing::error!("Failed to postpone notification {}: {}", delivery.id, e);
+                    }
+                }
+                Ok(dispatch) => prepared.push((delivery.id, dispatch)),
+                Err(e) => tracing::error!("Failed to prepare notification {}: {}", delivery.id, e),
+            }
+        }
+        Ok::<_, String>(prepared)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let mut done = Vec::new();
+    let mut emails = Vec::new();
+    let mut gone = Vec::new();
+    for (id, dispatch) in prepared {
+        match dispatch {
+            Dispatch::Email(message) => emails.push(message),
+            // Push is best effort: a browser that misses one still gets the email.
+            Dispatch::Push(subscriptions, notification) => {
+                for subscription in subscriptions {
+                    match push.send(&subscription, &notification).await {
+                        Ok(PushOutcome::Sent) => {}
+                        Ok(PushOutcome::Gone) => gone.push(subscription.id),
+                        Err(e) => tracing::warn!("Failed to push notification {} to {}: {}", id, subscription.id, e),
+                    }
+                }
+            }
+            Dispatch::Postpone(_) | Dispatch::Drop => {}
+        }
+        done.push(id);
+    }
+    if done.is_empty() {
+        return Ok(0);
+    }
+
+    let queues_email = !emails.is_empty();
+    let pool = pool.clone();
+    let handled = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| e.to_string())?;
+        if !gone.is_empty() {
+            match PushSubscriptions::remove(&mut conn, &gone) {
+                Ok(removed) => tracing::info!("Removed {} push subscription(s) rejected by their push service", removed),
+                Err(e) => tracing::error!("Failed to remove rejected push subscriptions: {}", e),
+            }
+        }
+        // Emails are queued as their deliveries are removed, so each goes out once.
+        write(&mut conn, |conn| {
+            for message in &emails {
+                email_queue::enqueue(conn, message, EmailPriority::Bulk)?;
+            }
+            NotificationDeliveries::remove(conn, &done)
+        })
+        .map_err(|e: diesel::result::Error| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if queues_email {
+        email_queue.wake(EmailPriority::Bulk);
+    }
+    Ok(handled)
+}
+
+#[async_trait]
+impl Service for NotificationDispatcher {
+    fn name(&self) -> &'static str {
+        "notification-dispatcher"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let period = self.period;
+        let pool = self.pool.clone();
+        let email_queue = self.email_queue.clone();
+        let push = self.push.clone();
+        let public_url = self.public_url.clone();
+        let mut lease = self.leases.lease(self.name());
+
+        self.tasks.spawn(|mut shutdown| async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.wait() => break,
+                }
+                if !lease.hold().await {
+                    continue;
+                }
+
+                match dispatch_due(&pool, &email_queue, &push, &public_url).await {
+                    Ok(0) => {}
+                    Ok(handled) => tracing::info!("Dispatched {} notification(s)", handled),
+                    Err(e) => tracing::error!("Failed to dispatch notifications: {}", e),
+                }
+            }
+
+            lease.release().await;
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn quiet_hours_follow_the_users_time_zone() {
+        let (start, end) = (parse_time_of_day("22:00"), parse_time_of_day("07:30"));
+
+        // 23:00 in Tokyo is inside an overnight window that ends 07:30 local, 22:30 UTC.
+        let until = quiet_until("Asia/Tokyo", start, end, at("2025-06-26T14:00:00Z")).unwrap();
+        assert_eq!(until, at("2025-06-26T22:30:00Z").naive_utc());
+
+        // 03:00 the next morning is still inside it, ending the same day.
+        let until = quiet_until("Asia/Tokyo", start, end, at("2025-06-26T18:00:00Z")).unwrap();
+        assert_eq!(until, at("2025-06-26T22:30:00Z").naive_utc());
+
+        assert_eq!(quiet_until("Asia/Tokyo", start, end, at("2025-06-26T03:00:00Z")), None, "noon is not quiet");
+        assert_eq!(quiet_until("Asia/Tokyo", None, None, at("2025-06-26T14:00:00Z")), None);
+        assert_eq!(quiet_until("UTC", start, start, at("2025-06-26T22:10:00Z")), None, "an empty window");
+    }
+
+    #[test]
+    fn describes_who_acted() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(describe_actors(&names(&["ann"])), "ann");
+        assert_eq!(describe_actors(&names(&["ann", "bob"])), "ann and bob");
+        assert_eq!(describe_actors(&names(&["ann", "bob", "cy", "di"])), "ann, bob and 2 others");
+    }
+}
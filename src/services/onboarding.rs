@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::db::models::follow::Follows;
+use crate::db::models::onboarding_step::{
+    OnboardingSteps, ONBOARDING_STEP_FIRST_POST, ONBOARDING_STEP_FOLLOW_AUTHORS, ONBOARDING_STEP_SET_AVATAR,
+    ONBOARDING_STEP_VERIFY_EMAIL, ONBOARDING_STEPS,
+};
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct StepProgress {
+    pub current: i64,
+    pub target: i64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ChecklistStep {
+    pub step: &'static str,
+    pub title: String,
+    pub completed: bool,
+    pub completed_at: Option<NaiveDateTime>,
+    /// Set for steps that take more than one action.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<StepProgress>,
+}
+
+/// Records that the user finished `step`, returning whether it's new. Called from wherever
+/// the step's event happens; recording a step twice is harmless.
+pub fn complete(conn: &mut SqliteConnection, user_id: &str, step: &str) -> QueryResult<bool> {
+    let completed = OnboardingSteps::complete(conn, user_id, step, Utc::now().naive_utc())?;
+    if completed {
+        tracing::info!("User {} completed onboarding step {}", user_id, step);
+    }
+    Ok(completed)
+}
+
+/// Called after the user follows someone. Completes the follow step once they follow
+/// `ONBOARDING_FOLLOW_TARGET` authors.
+pub fn followed(conn: &mut SqliteConnection, config: &Config, user_id: &str) -> QueryResult<bool> {
+    if Follows::count_following(conn, user_id)? < config.onboarding_follow_target() {
+        return Ok(false);
+    }
+    complete(conn, user_id, ONBOARDING_STEP_FOLLOW_AUTHORS)
+}
+
+fn title(step: &str, follow_target: i64) -> String {
+    match step {
+        ONBOARDING_STEP_VERIFY_EMAIL => "Verify your email address".to_string(),
+        ONBOARDING_STEP_SET_AVATAR => "Set an avatar".to_string(),
+        ONBOARDING_STEP_FIRST_POST => "Publish your first post".to_string(),
+        ONBOARDING_STEP_FOLLOW_AUTHORS if follow_target == 1 => "Follow an author".to_string(),
+        ONBOARDING_STEP_FOLLOW_AUTHORS => format!("Follow {} authors", follow_target),
+        step => step.to_string(),
+    }
+}
+
+fn build_steps(
+    steps: &[&'static str],
+    completed: &HashMap<String, NaiveDateTime>,
+    following: i64,
+    follow_target: i64,
+) -> Vec<ChecklistStep> {
+    steps
+        .iter()
+        .map(|step| {
+            let completed_at = completed.get(*step).copied();
+            let progress = (*step == ONBOARDING_STEP_FOLLOW_AUTHORS).then(|| StepProgress {
+                current: if completed_at.is_some() { follow_target } else { following.min(follow_target) },
+                target: follow_target,
+            });
+            ChecklistStep {
+                step,
+                title: title(step, follow_target),
+                completed: completed_at.is_some(),
+                completed_at,
+                progress,
+            }
+        })
+        .collect()
+}
+
+/// The user's checklist, with the steps configured in `ONBOARDING_STEPS` in order.
+pub fn checklist(conn: &mut SqliteConnection, config: &Config, user_id: &str) -> QueryResult<Vec<ChecklistStep>> {
+    let completed: HashMap<String, NaiveDateTime> = OnboardingSteps::for_user(conn, user_id)?
+        .into_iter()
+        .map(|step| (step.step, step.completed_at))
+        .collect();
+    let following = Follows::count_following(conn, user_id)?;
+
+    let steps: Vec<&'static str> = config.onboarding_steps()
+        .into_iter()
+        .filter_map(|step| ONBOARDING_STEPS.into_iter().find(|known| *known == step))
+        .collect();
+    Ok(build_steps(&steps, &completed, following, config.onboarding_follow_target()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_follow_the_configured_order_and_track_follows() {
+        let at = NaiveDateTime::default();
+        let completed = HashMap::from([(ONBOARDING_STEP_VERIFY_EMAIL.to_string(), at)]);
+        let steps = build_steps(
+            &[ONBOARDING_STEP_FOLLOW_AUTHORS, ONBOARDING_STEP_VERIFY_EMAIL],
+            &completed,
+            2,
+            3,
+        );
+
+        assert_eq!(steps[0].step, ONBOARDING_STEP_FOLLOW_AUTHORS);
+        assert_eq!(steps[0].title, "Follow 3 authors");
+        assert!(!steps[0].completed);
+        assert_eq!(steps[0].progress, Some(StepProgress { current: 2, target: 3 }));
+        assert_eq!(steps[1].step, ONBOARDING_STEP_VERIFY_EMAIL);
+        assert_eq!(steps[1].completed_at, Some(at));
+        assert_eq!(steps[1].progress, None);
+    }
+
+    #[test]
+    fn completed_follow_step_stays_full_after_unfollowing() {
+        let completed = HashMap::from([(ONBOARDING_STEP_FOLLOW_AUTHORS.to_string(), NaiveDateTime::default())]);
+        let steps = build_steps(&[ONBOARDING_STEP_FOLLOW_AUTHORS], &completed, 1, 3);
+        assert_eq!(steps[0].progress, Some(StepProgress { current: 3, target: 3 }));
+    }
+}
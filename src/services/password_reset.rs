@@ -0,0 +1,46 @@
+use diesel::SqliteConnection;
+
+use crate::db::models::reset_token::ResetTokens;
+use crate::db::models::user_model::UserModel;
+use crate::errors::AuthError;
+use crate::http::locale::Locale;
+use crate::services::email_queue::{self, EmailPriority};
+use crate::services::email_templates;
+use crate::state::AppState;
+use crate::utils::generate_token;
+
+const RESET_TOKEN_MINUTES: i64 = 60;
+/// A new link isn't sent while the previous one is younger than this.
+const RESET_EMAIL_COOLDOWN_MINUTES: i64 = 2;
+
+/// Replaces any outstanding reset token for the user and emails a link to the new one. Returns
+/// `false` without sending anything when a link went out within the cooldown.
+pub async fn send_reset_email(
+    state: &AppState,
+    conn: &mut SqliteConnection,
+    user: &UserModel,
+    locale: Locale,
+) -> Result<bool, AuthError> {
+    let latest = ResetTokens::latest_for_user(conn, &user.id)
+        .map_err(|e| AuthError::database(format!("Failed to look up reset tokens: {}", e)))?;
+    let cooldown = chrono::Duration::minutes(RESET_EMAIL_COOLDOWN_MINUTES);
+    if latest.is_some_and(|token| token.created_at + cooldown > chrono::Utc::now().naive_utc()) {
+        return Ok(false);
+    }
+
+    let token = generate_token();
+    let link = format!("{}/reset-password?token={}", state.config.load().public_url(), token);
+    let message = email_templates::password_reset(&state.templates, locale, &user.name, &user.email, &link, RESET_TOKEN_MINUTES)
+        .map_err(|e| AuthError::internal(format!("Failed to render reset email: {}", e)))?;
+
+    state.writes.write(conn, |conn| {
+        ResetTokens::delete_by_user(conn, &user.id)?;
+        ResetTokens::create(conn, &token, &user.id, RESET_TOKEN_MINUTES)?;
+        email_queue::enqueue(conn, &message, EmailPriority::Transactional)
+    })
+    .await
+    .map_err(|e: diesel::result::Error| AuthError::database(format!("Failed to queue reset email: {}", e)))?;
+    state.email_queue.wake(EmailPriority::Transactional);
+
+    Ok(true)
+}
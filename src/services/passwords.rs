@@ -0,0 +1,51 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+use crate::config::Config;
+use crate::errors::AuthError;
+use crate::services::rollout::in_rollout;
+
+const ARGON2_ROLLOUT: &str = "argon2";
+
+/// Hashes a new password in the format the user's rollout bucket calls for: argon2id for users
+/// inside `ROLLOUT_ARGON2_PERCENT`, bcrypt for everyone else.
+pub fn hash_password(config: &Config, user_id: &str, password: &str) -> Result<String, AuthError> {
+    if uses_argon2(config, user_id) {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| AuthError::internal(format!("Password hashing failed: {}", e)))
+    } else {
+        bcrypt::hash(password, bcrypt::DEFAULT_COST)
+            .map_err(|e| AuthError::internal(format!("Password hashing failed: {}", e)))
+    }
+}
+
+/// Checks a password against a stored hash of either format, regardless of rollout.
+pub fn verify_password(password: &str, stored: &str) -> Result<bool, AuthError> {
+    if is_argon2(stored) {
+        let hash = PasswordHash::new(stored)
+            .map_err(|e| AuthError::internal(format!("Malformed password hash: {}", e)))?;
+        Ok(Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+    } else {
+        bcrypt::verify(password, stored)
+            .map_err(|e| AuthError::internal(format!("Password verification failed: {}", e)))
+    }
+}
+
+/// Whether a stored hash is in the other format from the one the user's bucket now calls for.
+/// Sign-in rehashes these while the plaintext is at hand, moving users in either direction as
+/// the rollout changes.
+pub fn needs_rehash(config: &Config, user_id: &str, stored: &str) -> bool {
+    is_argon2(stored) != uses_argon2(config, user_id)
+}
+
+fn uses_argon2(config: &Config, user_id: &str) -> bool {
+    in_rollout(ARGON2_ROLLOUT, user_id, config.argon2_rollout_percent())
+}
+
+fn is_argon2(stored: &str) -> bool {
+    stored.starts_with("$argon2")
+}
@@ -0,0 +1,184 @@
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use crate::services::markdown::split_front_matter;
+use crate::utils::slugify;
+
+/// A markdown file taken from an import, named by its path inside any archive it came in.
+/// `contents` is the error to report instead when the file couldn't be read.
+pub struct ImportFile {
+    pub name: String,
+    pub contents: Result<String, String>,
+}
+
+/// What an imported file says about the post it becomes. `content` is the whole file, front
+/// matter included, as it would be when written in the editor.
+#[derive(Debug, PartialEq)]
+pub struct ParsedPost {
+    pub title: String,
+    pub description: String,
+    pub slug: Option<String>,
+    pub tags: Vec<String>,
+    pub content: String,
+    /// `None` imports the post as a draft.
+    pub published_at: Option<NaiveDateTime>,
+}
+
+fn has_extension(name: &str, extensions: &[&str]) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extensions.iter().any(|wanted| extension.eq_ignore_ascii_case(wanted)))
+}
+
+pub fn is_markdown(name: &str) -> bool {
+    has_extension(name, &["md", "markdown"])
+}
+
+pub fn is_zip(name: &str) -> bool {
+    has_extension(name, &["zip"])
+}
+
+pub fn decode(name: &str, bytes: Vec<u8>) -> ImportFile {
+    ImportFile {
+        name: name.to_string(),
+        contents: String::from_utf8(bytes).map_err(|_| "File is not valid UTF-8".to_string()),
+    }
+}
+
+/// The markdown files in a zip archive. Other entries are skipped, as are hidden files and
+/// macOS resource forks. Entries are read no further than the `remaining` byte budget,
+/// whatever their headers claim, so a zip bomb fails instead of filling memory.
+pub fn expand_zip(archive_name: &str, bytes: &[u8], max_files: usize, remaining: &mut usize) -> Result<Vec<ImportFile>, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| format!("{} is not a readable zip archive: {}", archive_name, e))?;
+
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index)
+            .map_err(|e| format!("Failed to read {}: {}", archive_name, e))?;
+        let Some(path) = entry.enclosed_name() else {
+            continue;
+        };
+        let name = path.to_string_lossy().replace('\\', "/");
+        let hidden = name.split('/').any(|part| part.starts_with('.') || part == "__MACOSX");
+        if entry.is_dir() || hidden || !is_markdown(&name) {
+            continue;
+        }
+        if files.len() == max_files {
+            return Err(format!("{} holds more than {} markdown files", archive_name, max_files));
+        }
+
+        let mut contents = Vec::new();
+        entry.take(*remaining as u64 + 1).read_to_end(&mut contents)
+            .map_err(|e| format!("Failed to read {} from {}: {}", name, archive_name, e))?;
+        if contents.len() > *remaining {
+            return Err(format!("{} expands past the import size limit", archive_name));
+        }
+        *remaining -= contents.len();
+        files.push(decode(&format!("{}/{}", archive_name, name), contents));
+    }
+    Ok(files)
+}
+
+/// Reads a front matter `date` as written by common static site generators: a bare date,
+/// a local date and time, or RFC 3339. Times without an offset are taken as UTC.
+fn parse_date(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.naive_utc());
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0))
+}
+
+/// The first `# ` heading of the body, for files without a front matter title.
+fn first_heading(body: &str) -> Option<String> {
+    body.lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|heading| heading.trim().to_string())
+        .filter(|heading| !heading.is_empty())
+}
+
+/// "2024-01-05-my-first-post.md" reads as "my first post".
+fn title_from_name(name: &str) -> String {
+    let stem = Path::new(name).file_stem().and_then(|stem| stem.to_str()).unwrap_or(name);
+    let stem = stem.get(..11)
+        .filter(|prefix| prefix.ends_with('-') && NaiveDate::parse_from_str(&prefix[..10], "%Y-%m-%d").is_ok())
+        .map_or(stem, |prefix| &stem[prefix.len()..]);
+    stem.replace(['-', '_'], " ").trim().to_string()
+}
+
+/// Turns an imported file into a post. The title comes from front matter, then the first
+/// heading, then the file name. A post with a `date` keeps it as its publication time,
+/// unless front matter also says `draft: true`; one without a date is imported as a draft.
+pub fn parse(name: &str, source: String) -> Result<ParsedPost, String> {
+    let (front_matter, body) = split_front_matter(&source);
+    let front_matter = front_matter.unwrap_or_default();
+
+    let title = front_matter.title.clone()
+        .filter(|title| !title.trim().is_empty())
+        .or_else(|| first_heading(body))
+        .unwrap_or_else(|| title_from_name(name));
+    if title.is_empty() {
+        return Err("Could not find a title in front matter, a heading or the file name".to_string());
+    }
+
+    let draft = front_matter.extra.get("draft").and_then(|draft| draft.as_bool()).unwrap_or(false);
+    let published_at = match &front_matter.date {
+        Some(date) => Some(parse_date(date).ok_or_else(|| format!("Unrecognized date \"{}\"", date))?),
+        None => None,
+    };
+
+    Ok(ParsedPost {
+        slug: front_matter.slug.clone().or_else(|| Some(slugify(&title))).filter(|slug| !slug.is_empty()),
+        title,
+        description: front_matter.description.unwrap_or_default(),
+        tags: front_matter.tags,
+        published_at: published_at.filter(|_| !draft),
+        content: source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_front_matter_and_falls_back_to_headings_and_names() {
+        let post = parse("hello.md", "---\ntitle: Hello\nslug: hi-there\ntags: [rust, web]\ndate: 2024-01-05\n---\nBody".to_string()).unwrap();
+        assert_eq!(post.title, "Hello");
+        assert_eq!(post.slug.as_deref(), Some("hi-there"));
+        assert_eq!(post.tags, vec!["rust", "web"]);
+        assert_eq!(post.published_at, NaiveDate::from_ymd_opt(2024, 1, 5).unwrap().and_hms_opt(0, 0, 0));
+
+        let post = parse("notes.md", "Intro\n\n# A Heading\n\ntext".to_string()).unwrap();
+        assert_eq!(post.title, "A Heading");
+        assert_eq!(post.slug.as_deref(), Some("a-heading"));
+        assert_eq!(post.published_at, None);
+
+        let post = parse("posts/2024-01-05-my-first-post.md", "no heading".to_string()).unwrap();
+        assert_eq!(post.title, "my first post");
+    }
+
+    #[test]
+    fn drafts_and_bad_dates() {
+        let post = parse("a.md", "---\ntitle: A\ndate: 2024-01-05T10:00:00+02:00\ndraft: true\n---\n".to_string()).unwrap();
+        assert_eq!(post.published_at, None);
+
+        let error = parse("a.md", "---\ntitle: A\ndate: last tuesday\n---\n".to_string()).unwrap_err();
+        assert!(error.contains("last tuesday"));
+    }
+
+    #[test]
+    fn recognizes_file_types() {
+        assert!(is_markdown("post.MD"));
+        assert!(is_markdown("dir/post.markdown"));
+        assert!(!is_markdown("post.txt"));
+        assert!(is_zip("blog.zip"));
+    }
+}
@@ -0,0 +1,91 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+use crate::services::markdown::{parser_options, split_front_matter};
+
+/// Length a generated description is trimmed to, matching what search engines show.
+pub const DESCRIPTION_MAX_CHARS: usize = 160;
+
+/// The reading speed `reading_minutes` assumes.
+pub const WORDS_PER_MINUTE: i32 = 200;
+
+/// The readable text of a post body, without markup or front matter.
+pub fn plain_text(content: &str) -> String {
+    let (_, body) = split_front_matter(content);
+
+    let mut text = String::new();
+    for event in Parser::new_ext(body, parser_options()) {
+        match event {
+            Event::Text(chunk) | Event::Code(chunk) => text.push_str(&chunk),
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
+
+pub fn word_count(content: &str) -> i32 {
+    plain_text(content).split_whitespace().count().try_into().unwrap_or(i32::MAX)
+}
+
+/// How long `word_count` words take to read, in whole minutes rounded up. Never under one.
+pub fn reading_minutes(word_count: i32) -> i32 {
+    (word_count.saturating_add(WORDS_PER_MINUTE - 1) / WORDS_PER_MINUTE).max(1)
+}
+
+/// A description built from the first paragraph of the post, cut on a word boundary.
+pub fn description(content: &str) -> String {
+    let (_, body) = split_front_matter(content);
+
+    let mut paragraph = String::new();
+    let mut in_paragraph = false;
+    for event in Parser::new_ext(body, parser_options()) {
+        match event {
+            Event::Start(Tag::Paragraph) => in_paragraph = true,
+            Event::End(TagEnd::Paragraph) if !paragraph.trim().is_empty() => break,
+            Event::End(TagEnd::Paragraph) => in_paragraph = false,
+            Event::Text(chunk) | Event::Code(chunk) if in_paragraph => paragraph.push_str(&chunk),
+            Event::SoftBreak | Event::HardBreak if in_paragraph => paragraph.push(' '),
+            _ => {}
+        }
+    }
+
+    let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+    if paragraph.chars().count() <= DESCRIPTION_MAX_CHARS {
+        return paragraph;
+    }
+
+    let mut summary = String::new();
+    for word in paragraph.split(' ') {
+        if summary.chars().count() + word.chars().count() + 1 > DESCRIPTION_MAX_CHARS - 1 {
+            break;
+        }
+        if !summary.is_empty() {
+            summary.push(' ');
+        }
+        summary.push_str(word);
+    }
+    if summary.is_empty() {
+        summary = paragraph.chars().take(DESCRIPTION_MAX_CHARS - 1).collect();
+    }
+    summary.push('…');
+    summary
+}
+
+/// Lowercased, de-punctuated terms for the search index.
+pub fn search_terms(title: &str, description: &str, content: &str) -> String {
+    let text = format!("{} {} {}", title, description, plain_text(content)).to_lowercase();
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The first image in the post, used as its Open Graph image.
+pub fn og_image(content: &str) -> Option<String> {
+    let (_, body) = split_front_matter(content);
+
+    Parser::new_ext(body, parser_options()).find_map(|event| match event {
+        Event::Start(Tag::Image { dest_url, .. }) if !dest_url.is_empty() => Some(dest_url.to_string()),
+        _ => None,
+    })
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Serialize;
+use web_push::{
+    request_builder, ContentEncoding, PartialVapidSignatureBuilder, SubscriptionInfo, VapidSignatureBuilder,
+    WebPushMessageBuilder,
+};
+
+use crate::config::Config;
+use crate::db::models::push_subscription::PushSubscriptions;
+use crate::errors::AuthError;
+
+/// How long a push service holds a notification for a browser that's offline.
+const PUSH_TTL_SECONDS: u32 = 24 * 60 * 60;
+
+/// What a service worker receives in its `push` event, as JSON.
+#[derive(Debug, Clone, Serialize)]
+pub struct PushNotification {
+    pub title: String,
+    pub body: String,
+    pub url: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushOutcome {
+    Sent,
+    /// The push service no longer knows the subscription; it should be deleted.
+    Gone,
+}
+
+struct Vapid {
+    signer: PartialVapidSignatureBuilder,
+    public_key: String,
+    subject: String,
+}
+
+/// Sends Web Push messages, encrypted for each browser (RFC 8291) and signed with the
+/// server's VAPID key (RFC 8292). Without `VAPID_PRIVATE_KEY` push is off and browsers
+/// can't subscribe.
+#[derive(Clone)]
+pub struct PushService {
+    client: reqwest::Client,
+    vapid: Option<Arc<Vapid>>,
+}
+
+impl PushService {
+    pub fn new(config: &Config) -> Self {
+        let vapid = config.vapid_private_key().map(|key| {
+            // Checked when the config was loaded.
+            let signer = VapidSignatureBuilder::from_base64_no_sub(key)
+                .expect("VAPID_PRIVATE_KEY is a valid key");
+            Arc::new(Vapid {
+                public_key: URL_SAFE_NO_PAD.encode(signer.get_public_key()),
+                signer,
+                subject: config.vapid_subject().to_string(),
+            })
+        });
+        if vapid.is_none() {
+            tracing::info!("VAPID_PRIVATE_KEY is not set, push notifications are off");
+        }
+
+        Self { client: reqwest::Client::new(), vapid }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.vapid.is_some()
+    }
+
+    /// The key browsers pass as `applicationServerKey` when subscribing.
+    pub fn public_key(&self) -> Option<&str> {
+        self.vapid.as_ref().map(|vapid| vapid.public_key.as_str())
+    }
+
+    pub async fn send(&self, subscription: &PushSubscriptions, notification: &PushNotification) -> Result<PushOutcome, AuthError> {
+        let vapid = self.vapid.as_ref().ok_or_else(|| AuthError::internal("Push notifications are not configured"))?;
+        let payload = serde_json::to_vec(notification)
+            .map_err(|e| AuthError::internal(format!("Failed to serialize push notification: {}", e)))?;
+
+        let info = SubscriptionInfo::new(
+            subscription.endpoint.as_str(),
+            subscription.p256dh.as_str(),
+            subscription.auth.as_str(),
+        );
+        let mut signature = vapid.signer.clone().add_sub_info(&info);
+        signature.add_claim("sub", vapid.subject.as_str());
+        let signature = signature.build()
+            .map_err(|e| AuthError::internal(format!("Failed to sign push message: {:?}", e)))?;
+
+        let mut message = WebPushMessageBuilder::new(&info);
+        message.set_ttl(PUSH_TTL_SECONDS);
+        message.set_payload(ContentEncoding::Aes128Gcm, &payload);
+        message.set_vapid_signature(signature);
+        let message = message.build()
+            .map_err(|e| AuthError::internal(format!("Failed to encrypt push message: {:?}", e)))?;
+
+        // The crate builds an `http` 0.2 request, so it's copied over to reqwest by hand.
+        let request = request_builder::build_request::<Vec<u8>>(message);
+        let mut outgoing = self.client.post(request.uri().to_string());
+        for (name, value) in request.headers() {
+            outgoing = outgoing.header(name.as_str(), value.as_bytes());
+        }
+        let response = outgoing
+            .body(request.into_body())
+            .send()
+            .await
+            .map_err(|e| AuthError::internal(format!("Failed to reach push service: {}", e)))?;
+
+        match response.status().as_u16() {
+            200..=299 => Ok(PushOutcome::Sent),
+            404 | 410 => Ok(PushOutcome::Gone),
+            status => {
+                let body = response.text().await.unwrap_or_default();
+                Err(AuthError::internal(format!("Push service answered {}: {}", status, body)))
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use tokio::sync::OnceCell;
+
+use crate::config::Config;
+use crate::errors::AuthError;
+use crate::services::cache::Cache;
+
+/// Keeps our keys apart from anything else living in the same Redis database.
+pub(crate) const KEY_PREFIX: &str = "tsumi:";
+
+/// Keys fetched per SCAN round trip when invalidating by prefix.
+const SCAN_COUNT: usize = 200;
+
+/// A cache shared by every app instance pointed at the same Redis. The connection is opened
+/// on first use, so a Redis that's down at startup doesn't stop the app from booting; reads
+/// just miss until it comes back.
+pub struct RedisCache {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl RedisCache {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        // Checked when the config was loaded.
+        let client = redis::Client::open(config.cache_redis_url()?).expect("REDIS_URL is a valid Redis URL");
+        Some(Self { client, connection: OnceCell::new() })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, AuthError> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+            .map_err(|e| {
+                tracing::error!("Failed to connect to Redis: {}", e);
+                AuthError::internal("Cache unavailable")
+            })
+    }
+}
+
+fn redis_error(action: &str, key: &str, e: redis::RedisError) -> AuthError {
+    tracing::error!("Redis {} of {} failed: {}", action, key, e);
+    AuthError::internal("Cache unavailable")
+}
+
+/// Escapes the glob characters SCAN's MATCH understands, so a prefix is matched literally.
+fn glob_escape(prefix: &str) -> String {
+    let mut escaped = String::with_capacity(prefix.len());
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[async_trait]
+impl Cache for RedisCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, AuthError> {
+        let mut conn = self.connection().await?;
+        conn.get(format!("{}{}", KEY_PREFIX, key)).await.map_err(|e| redis_error("read", key, e))
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<(), AuthError> {
+        let mut conn = self.connection().await?;
+        conn.set_ex(format!("{}{}", KEY_PREFIX, key), value, ttl.as_secs().max(1))
+            .await
+            .map_err(|e| redis_error("write", key, e))
+    }
+
+    async fn invalidate(&self, key: &str) -> Result<(), AuthError> {
+        let mut conn = self.connection().await?;
+        conn.del(format!("{}{}", KEY_PREFIX, key)).await.map_err(|e| redis_error("delete", key, e))
+    }
+
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<(), AuthError> {
+        let mut conn = self.connection().await?;
+        let pattern = format!("{}{}*", glob_escape(KEY_PREFIX), glob_escape(prefix));
+
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(SCAN_COUNT)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| redis_error("scan", prefix, e))?;
+
+            if !keys.is_empty() {
+                let _: () = conn.del(keys).await.map_err(|e| redis_error("delete", prefix, e))?;
+            }
+            if next == 0 {
+                return Ok(());
+            }
+            cursor = next;
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use tokio::sync::OnceCell;
+
+use crate::config::Config;
+use crate::db::models::refresh_token::RefreshTokens;
+use crate::errors::AuthError;
+use crate::http::client::device_label;
+use crate::http::pagination::SortDir;
+use crate::services::redis_cache::KEY_PREFIX;
+use crate::services::sessions::SessionStore;
+
+/// Sessions in Redis, each under its token with a native TTL, so expired sessions disappear
+/// without a sweeper. Sorted sets of tokens per user and per IP address, scored by expiry,
+/// back listing, search and sign-out-everywhere.
+///
+/// A rotated session stays under its token, marked as replaced, but leaves the user and IP
+/// indexes. A set per token family tracks every token issued in one sign-in.
+///
+/// Redis drops a session as soon as it expires, so `include_expired` has nothing extra to show.
+pub struct RedisSessionStore {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl RedisSessionStore {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        // Checked when the config was loaded.
+        let client = redis::Client::open(config.session_redis_url()?).expect("REDIS_URL is a valid Redis URL");
+        Some(Self { client, connection: OnceCell::new() })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, AuthError> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+            .map_err(|e| {
+                tracing::error!("Failed to connect to Redis: {}", e);
+                AuthError::internal("Session store unavailable")
+            })
+    }
+
+    /// The sessions stored under `tokens`, skipping any that have expired since being indexed.
+    async fn load(&self, conn: &mut ConnectionManager, tokens: &[String]) -> Result<Vec<RefreshTokens>, AuthError> {
+        if tokens.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Vec<String> = tokens.iter().map(|token| session_key(token)).collect();
+        // Spelled out rather than `mget`, which sends a plain GET for a single key.
+        let values: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(conn)
+            .await
+            .map_err(|e| redis_error("read", "sessions", e))?;
+
+        Ok(values.into_iter().flatten().filter_map(|value| parse(&value)).collect())
+    }
+
+    /// Every live session in an index, dropping the entries of expired ones first.
+    async fn indexed(&self, conn: &mut ConnectionManager, index: &str) -> Result<Vec<RefreshTokens>, AuthError> {
+        let _: () = conn.zrembyscore(index, "-inf", Utc::now().timestamp())
+            .await
+            .map_err(|e| redis_error("prune", index, e))?;
+        let tokens: Vec<String> = conn.zrange(index, 0, -1).await.map_err(|e| redis_error("read", index, e))?;
+        self.load(conn, &tokens).await
+    }
+}
+
+fn session_key(token: &str) -> String {
+    format!("{}session:{}", KEY_PREFIX, token)
+}
+
+fn user_index(user_id: &str) -> String {
+    format!("{}sessions:user:{}", KEY_PREFIX, user_id)
+}
+
+fn ip_index(ip_address: &str) -> String {
+    format!("{}sessions:ip:{}", KEY_PREFIX, ip_address)
+}
+
+fn family_index(family_id: &str) -> String {
+    format!("{}sessions:family:{}", KEY_PREFIX, family_id)
+}
+
+/// Set once when a session is rotated, so that two refreshes racing with the same token
+/// can't both replace it.
+fn replaced_key(token: &str) -> String {
+    format!("{}session-replaced:{}", KEY_PREFIX, token)
+}
+
+fn new_session(
+    token: &str,
+    user_id: &str,
+    lifetime: chrono::Duration,
+    persistent: bool,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+    family_id: Option<&str>,
+) -> RefreshTokens {
+    let now = Utc::now();
+    let id = uuid::Uuid::new_v4().to_string();
+    RefreshTokens {
+        family_id: family_id.map_or_else(|| id.clone(), str::to_owned),
+        id,
+        token: token.to_owned(),
+        user_id: user_id.to_owned(),
+        expires_at: (now + lifetime).naive_utc(),
+        created_at: now.naive_utc(),
+        ip_address: ip_address.map(str::to_owned),
+        user_agent: user_agent.map(str::to_owned),
+        replaced_by: None,
+        device: Some(device_label(user_agent)),
+        persistent,
+    }
+}
+
+fn serialize(session: &RefreshTokens) -> Result<String, AuthError> {
+    serde_json::to_string(session).map_err(|e| AuthError::internal(format!("Failed to serialize session: {}", e)))
+}
+
+/// Seconds until `session` expires, at least one.
+fn ttl(session: &RefreshTokens) -> i64 {
+    (session.expires_at - Utc::now().naive_utc()).num_seconds().max(1)
+}
+
+/// Adds the commands storing `session` and indexing it to `pipe`.
+fn store(pipe: &mut redis::Pipeline, session: &RefreshTokens, value: String) {
+    let ttl = ttl(session);
+    let expires_at = session.expires_at.and_utc().timestamp();
+
+    // Every session lasts the same number of days, so the newest one in an index is also
+    // the last to expire and the index can simply take its TTL.
+    pipe.set_ex(session_key(&session.token), value, ttl as u64).ignore()
+        .zadd(user_index(&session.user_id), &session.token, expires_at).ignore()
+        .expire(user_index(&session.user_id), ttl).ignore()
+        .sadd(family_index(&session.family_id), &session.token).ignore()
+        .expire(family_index(&session.family_id), ttl).ignore();
+    if let Some(ip_address) = &session.ip_address {
+        pipe.zadd(ip_index(ip_address), &session.token, expires_at).ignore()
+            .expire(ip_index(ip_address), ttl).ignore();
+    }
+}
+
+/// Adds the commands dropping `session` from the user and IP indexes to `pipe`.
+fn unindex(pipe: &mut redis::Pipeline, session: &RefreshTokens) {
+    pipe.zrem(user_index(&session.user_id), &session.token).ignore();
+    if let Some(ip_address) = &session.ip_address {
+        pipe.zrem(ip_index(ip_address), &session.token).ignore();
+    }
+}
+
+/// Sessions stored before token families existed become families of one.
+fn parse(value: &str) -> Option<RefreshTokens> {
+    let mut session: RefreshTokens = serde_json::from_str(value)
+        .inspect_err(|e| tracing::warn!("Skipping unreadable session in Redis: {}", e))
+        .ok()?;
+    if session.family_id.is_empty() {
+        session.family_id = session.id.clone();
+    }
+    Some(session)
+}
+
+fn redis_error(action: &str, key: &str, e: redis::RedisError) -> AuthError {
+    tracing::error!("Redis {} of {} failed: {}", action, key, e);
+    AuthError::internal("Session store unavailable")
+}
+
+#[async_trait]
+impl SessionStore for RedisSessionStore {
+    async fn create(
+        &self,
+        token: &str,
+        user_id: &str,
+        lifetime: chrono::Duration,
+        persistent: bool,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<RefreshTokens, AuthError> {
+        let session = new_session(token, user_id, lifetime, persistent, ip_address, user_agent, None);
+        let mut pipe = redis::pipe();
+        store(pipe.atomic(), &session, serialize(&session)?);
+
+        let mut conn = self.connection().await?;
+        let _: () = pipe.query_async(&mut conn).await.map_err(|e| redis_error("write", "session", e))?;
+        Ok(session)
+    }
+
+    async fn rotate(
+        &self,
+        previous: &RefreshTokens,
+        token: &str,
+        lifetime: chrono::Duration,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<Option<RefreshTokens>, AuthError> {
+        let next = new_session(
+            token,
+            &previous.user_id,
+            lifetime,
+            previous.persistent,
+            ip_address,
+            user_agent,
+            Some(&previous.family_id),
+        );
+        let mut conn = self.connection().await?;
+
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(replaced_key(&previous.token))
+            .arg(&next.id)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl(previous))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| redis_error("claim", "session", e))?;
+        if claimed.is_none() {
+            return Ok(None);
+        }
+
+        let replaced = RefreshTokens { replaced_by: Some(next.id.clone()), ..previous.clone() };
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        store(&mut pipe, &next, serialize(&next)?);
+        unindex(&mut pipe, previous);
+        pipe.cmd("SET").arg(session_key(&previous.token)).arg(serialize(&replaced)?).arg("XX").arg("KEEPTTL").ignore();
+
+        let _: () = pipe.query_async(&mut conn).await.map_err(|e| redis_error("write", "session", e))?;
+        Ok(Some(next))
+    }
+
+    async fn by_token(&self, token: &str) -> Result<Option<RefreshTokens>, AuthError> {
+        let mut conn = self.connection().await?;
+        let value: Option<String> = conn.get(session_key(token)).await.map_err(|e| redis_error("read", "session", e))?;
+        Ok(value.as_deref().and_then(parse))
+    }
+
+    async fn delete_by_token(&self, token: &str) -> Result<bool, AuthError> {
+        let Some(session) = self.by_token(token).await? else {
+            return Ok(false);
+        };
+
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .del(session_key(token)).ignore()
+            .srem(family_index(&session.family_id), token).ignore();
+        unindex(&mut pipe, &session);
+
+        let mut conn = self.connection().await?;
+        let _: () = pipe.query_async(&mut conn).await.map_err(|e| redis_error("delete", "session", e))?;
+        Ok(true)
+    }
+
+    async fn delete_family(&self, family_id: &str) -> Result<usize, AuthError> {
+        let mut conn = self.connection().await?;
+        let index = family_index(family_id);
+        let tokens: Vec<String> = conn.smembers(&index).await.map_err(|e| redis_error("read", &index, e))?;
+        let sessions = self.load(&mut conn, &tokens).await?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(&index).ignore();
+        if !tokens.is_empty() {
+            pipe.del(tokens.iter().map(|token| session_key(token)).collect::<Vec<_>>()).ignore();
+        }
+        for session in &sessions {
+            unindex(&mut pipe, session);
+        }
+        let _: () = pipe.query_async(&mut conn).await.map_err(|e| redis_error("delete", &index, e))?;
+        Ok(sessions.len())
+    }
+
+    async fn delete_by_user(&self, user_id: &str) -> Result<usize, AuthError> {
+        let mut conn = self.connection().await?;
+        let index = user_index(user_id);
+        let tokens: Vec<String> = conn.zrange(&index, 0, -1).await.map_err(|e| redis_error("read", &index, e))?;
+        let sessions = self.load(&mut conn, &tokens).await?;
+
+        // Whole families go, so the tokens rotated out of these sessions go with them.
+        let families: HashSet<&str> = sessions.iter().map(|session| session.family_id.as_str()).collect();
+        for family_id in families {
+            self.delete_family(family_id).await?;
+        }
+        let _: () = conn.del(&index).await.map_err(|e| redis_error("delete", &index, e))?;
+        Ok(sessions.len())
+    }
+
+    async fn count_for_user(&self, user_id: &str, _include_expired: bool) -> Result<i64, AuthError> {
+        let mut conn = self.connection().await?;
+        let index = user_index(user_id);
+        let _: () = conn.zrembyscore(&index, "-inf", Utc::now().timestamp())
+            .await
+            .map_err(|e| redis_error("prune", &index, e))?;
+        conn.zcard(&index).await.map_err(|e| redis_error("count", &index, e))
+    }
+
+    async fn page_for_user(
+        &self,
+        user_id: &str,
+        _include_expired: bool,
+        sort: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<RefreshTokens>, AuthError> {
+        let mut conn = self.connection().await?;
+        let mut sessions = self.indexed(&mut conn, &user_index(user_id)).await?;
+
+        // A user has a handful of sessions, so they're sorted here rather than in Redis.
+        sessions.sort_by(|a, b| {
+            let order = match sort {
+                "expires_at" => a.expires_at.cmp(&b.expires_at),
+                _ => a.created_at.cmp(&b.created_at),
+            };
+            let order = if dir.is_asc() { order } else { order.reverse() };
+            order.then_with(|| a.id.cmp(&b.id))
+        });
+
+        Ok(sessions.into_iter().skip(offset.max(0) as usize).take(limit.max(0) as usize).collect())
+    }
+
+    async fn search(&self, term: &str, limit: i64) -> Result<Vec<RefreshTokens>, AuthError> {
+        let mut conn = self.connection().await?;
+        let mut found: HashMap<String, RefreshTokens> = HashMap::new();
+        for index in [ip_index(term), user_index(term)] {
+            for session in self.indexed(&mut conn, &index).await? {
+                found.insert(session.id.clone(), session);
+            }
+        }
+
+        let mut sessions: Vec<RefreshTokens> = found.into_values().collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.created_at));
+        sessions.truncate(limit.max(0) as usize);
+        Ok(sessions)
+    }
+}
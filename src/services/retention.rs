@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use diesel::{QueryResult, SqliteConnection};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::db::models::audit_log::AuditLogs;
+use crate::db::models::backfill_job::BackfillJobs;
+use crate::db::models::email_outbox::EmailOutbox;
+use crate::db::models::export_job::ExportJobs;
+use crate::db::models::idempotency_key::IdempotencyKeys;
+use crate::db::models::notification::Notifications;
+use crate::db::models::webhook_delivery::WebhookDeliveries;
+use crate::db::write::{write, WriteLock};
+use crate::services::lifecycle::Shutdown;
+use crate::services::scheduler::{Job, Schedule};
+use crate::state::DbPool;
+
+/// Breathing room between batches so requests waiting on the write lock get a turn.
+const BATCH_PAUSE: Duration = Duration::from_millis(100);
+
+type PruneFn = fn(&mut SqliteConnection, NaiveDateTime, i64) -> QueryResult<usize>;
+
+/// How long rows of one table are kept, and how to delete a batch of the expired ones.
+#[derive(Clone, Copy)]
+pub struct RetentionPolicy {
+    pub table: &'static str,
+    /// 0 keeps rows forever.
+    pub days: u32,
+    prune: PruneFn,
+}
+
+pub fn policies(config: &Config) -> Vec<RetentionPolicy> {
+    vec![
+        RetentionPolicy { table: "audit_logs", days: config.audit_log_retention_days(), prune: AuditLogs::delete_before },
+        RetentionPolicy {
+            table: "notifications",
+            days: config.notification_retention_days(),
+            prune: Notifications::delete_before,
+        },
+        RetentionPolicy {
+            table: "backfill_jobs",
+            days: config.job_history_retention_days(),
+            prune: BackfillJobs::delete_finished_before,
+        },
+        RetentionPolicy {
+            table: "webhook_deliveries",
+            days: config.job_history_retention_days(),
+            prune: WebhookDeliveries::delete_finished_before,
+        },
+        RetentionPolicy {
+            table: "export_jobs",
+            days: config.job_history_retention_days(),
+            prune: ExportJobs::delete_finished_before,
+        },
+        RetentionPolicy {
+            table: "email_outbox",
+            days: config.job_history_retention_days(),
+            prune: EmailOutbox::delete_finished_before,
+        },
+        // Keys carry their own expiry; this only clears out the expired ones a day later.
+        RetentionPolicy { table: "idempotency_keys", days: 1, prune: IdempotencyKeys::delete_expired_before },
+    ]
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TableReport {
+    pub retain_days: u32,
+    pub pruned_last_run: u64,
+    /// Rows pruned since the server started.
+    pub pruned_total: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionReport {
+    pub tables: BTreeMap<&'static str, TableReport>,
+}
+
+/// Shared between the pruner and the admin API: what the last runs pruned.
+#[derive(Default)]
+pub struct RetentionHandle {
+    report: Mutex<RetentionReport>,
+}
+
+impl RetentionHandle {
+    pub fn report(&self) -> RetentionReport {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RetentionReport> {
+        self.report.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Deletes rows past their table's retention once a night, in batches of
+/// `RETENTION_BATCH_SIZE` with a pause between them, so pruning a large backlog never holds
+/// SQLite's write lock for long.
+pub struct RetentionPruner {
+    policies: Vec<RetentionPolicy>,
+    batch_size: i64,
+    run_hour: u32,
+    pool: DbPool,
+    writes: WriteLock,
+    handle: Arc<RetentionHandle>,
+}
+
+impl RetentionPruner {
+    pub fn new(config: &Config, pool: DbPool, writes: WriteLock) -> Self {
+        let policies = policies(config);
+        let handle = Arc::new(RetentionHandle::default());
+        handle.lock().tables = policies
+            .iter()
+            .map(|policy| (policy.table, TableReport { retain_days: policy.days, ..TableReport::default() }))
+            .collect();
+
+        Self {
+            policies,
+            batch_size: config.retention_batch_size(),
+            run_hour: config.retention_run_hour(),
+            pool,
+            writes,
+            handle,
+        }
+    }
+
+    pub fn handle(&self) -> Arc<RetentionHandle> {
+        self.handle.clone()
+    }
+}
+
+/// Prunes one table until nothing expired is left, returning how many rows went, or `None`
+/// if shutdown interrupted it.
+async fn prune_table(
+    pool: &DbPool,
+    writes: &WriteLock,
+    policy: RetentionPolicy,
+    batch_size: i64,
+    shutdown: &mut Shutdown,
+) -> Option<Result<u64, String>> {
+    let cutoff = Utc::now().naive_utc() - chrono::Duration::days(i64::from(policy.days));
+    let mut pruned = 0;
+
+    loop {
+        let pool = pool.clone();
+        let turn = writes.acquire().await;
+        let result = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| e.to_string())?;
+            write(&mut conn, |conn| (policy.prune)(conn, cutoff, batch_size)).map_err(|e| e.to_string())
+        })
+        .await;
+        drop(turn);
+
+        let deleted = match result {
+            Ok(Ok(deleted)) => deleted,
+            Ok(Err(e)) => return Some(Err(e)),
+            Err(e) => return Some(Err(format!("prune task panicked: {}", e))),
+        };
+        pruned += deleted as u64;
+        if (deleted as i64) < batch_size {
+            return Some(Ok(pruned));
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(BATCH_PAUSE) => {}
+            _ = shutdown.wait() => return None,
+        }
+    }
+}
+
+#[async_trait]
+impl Job for RetentionPruner {
+    fn name(&self) -> &'static str {
+        "retention"
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::DailyAt(self.run_hour)
+    }
+
+    async fn run(&self, mut shutdown: Shutdown) -> Result<(), String> {
+        let mut failed = 0;
+        for policy in self.policies.iter().filter(|policy| policy.days > 0) {
+            let Some(result) = prune_table(&self.pool, &self.writes, *policy, self.batch_size, &mut shutdown).await else {
+                return Err("stopped by shutdown before every table was pruned".to_string());
+            };
+
+            let mut report = self.handle.lock();
+            let table = report.tables.entry(policy.table).or_default();
+            match result {
+                Ok(pruned) => {
+                    table.pruned_last_run = pruned;
+                    table.pruned_total += pruned;
+                    table.last_error = None;
+                    if pruned > 0 {
+                        tracing::info!(table = policy.table, pruned, "Pruned rows past retention");
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to prune {}: {}", policy.table, e);
+                    table.pruned_last_run = 0;
+                    table.last_error = Some(e);
+                    failed += 1;
+                }
+            }
+        }
+
+        match failed {
+            0 => Ok(()),
+            failed => Err(format!("failed to prune {} table(s)", failed)),
+        }
+    }
+}
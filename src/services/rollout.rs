@@ -0,0 +1,11 @@
+use sha2::{Digest, Sha256};
+
+/// Whether `subject` (usually a user id) falls inside a `percent` rollout of `feature`.
+///
+/// Buckets come from a hash of both values, so a subject stays enrolled as the percentage grows
+/// and separate features don't all land on the same unlucky users.
+pub fn in_rollout(feature: &str, subject: &str, percent: u8) -> bool {
+    let digest = Sha256::digest(format!("{}:{}", feature, subject).as_bytes());
+    let bucket = u16::from_be_bytes([digest[0], digest[1]]) % 100;
+    bucket < u16::from(percent)
+}
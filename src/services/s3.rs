@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::errors::AuthError;
+use crate::services::storage::Storage;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Storage in an S3-compatible bucket, addressed path-style (`{endpoint}/{bucket}/{key}`) so it
+/// works with MinIO, R2 and friends as well as AWS. Requests are signed with SigV4.
+pub struct S3Storage {
+    http: reqwest::Client,
+    endpoint: String,
+    host: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl S3Storage {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let endpoint = config.s3_endpoint()?.to_string();
+        // Checked when the config was loaded.
+        let host = url::Url::parse(&endpoint)
+            .ok()
+            .and_then(|url| {
+                let host = url.host_str()?.to_string();
+                Some(match url.port() {
+                    Some(port) => format!("{}:{}", host, port),
+                    None => host,
+                })
+            })
+            .expect("S3_ENDPOINT is a URL with a host");
+
+        Some(Self {
+            http: reqwest::Client::new(),
+            endpoint,
+            host,
+            bucket: config.s3_bucket()?.to_string(),
+            region: config.s3_region()?.to_string(),
+            access_key_id: config.s3_access_key_id()?.to_string(),
+            secret_access_key: config.s3_secret_access_key()?.to_string(),
+        })
+    }
+
+    fn request(&self, method: Method, key: &str, payload: &[u8]) -> reqwest::RequestBuilder {
+        let path = format!("/{}/{}", self.bucket, key);
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(payload));
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, self.host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [date.as_str(), self.region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .fold(format!("AWS4{}", self.secret_access_key).into_bytes(), |key, part| hmac(&key, part.as_bytes()));
+        let signature = hex::encode(hmac(&signing_key, string_to_sign.as_bytes()));
+
+        self.http
+            .request(method, format!("{}{}", self.endpoint, path))
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key_id, scope, signed_headers, signature
+                ),
+            )
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, content_type: &str, bytes: Vec<u8>) -> Result<(), AuthError> {
+        let response = self.request(Method::PUT, key, &bytes)
+            .header("content-type", content_type)
+            .body(bytes)
+            .send()
+            .await
+            .map_err(|e| AuthError::internal(format!("S3 upload of {} failed: {}", key, e)))?;
+
+        if !response.status().is_success() {
+            return Err(AuthError::internal(format!("S3 upload of {} returned {}", key, response.status())));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, AuthError> {
+        let response = self.request(Method::GET, key, b"")
+            .send()
+            .await
+            .map_err(|e| AuthError::internal(format!("S3 download of {} failed: {}", key, e)))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let bytes = response.bytes().await
+                    .map_err(|e| AuthError::internal(format!("S3 download of {} failed: {}", key, e)))?;
+                Ok(Some(bytes.to_vec()))
+            }
+            status => Err(AuthError::internal(format!("S3 download of {} returned {}", key, status))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AuthError> {
+        let response = self.request(Method::DELETE, key, b"")
+            .send()
+            .await
+            .map_err(|e| AuthError::internal(format!("S3 delete of {} failed: {}", key, e)))?;
+
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            return Err(AuthError::internal(format!("S3 delete of {} returned {}", key, response.status())));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::config::Config;
+use crate::db::models::onboarding_step::ONBOARDING_STEP_FIRST_POST;
+use crate::db::models::post::Posts;
+use crate::db::models::webhook::WEBHOOK_EVENT_POST_PUBLISHED;
+use crate::services::cache::{self, Cache};
+use crate::services::lifecycle::Shutdown;
+use crate::services::notifications;
+use crate::services::onboarding;
+use crate::services::scheduler::{Job, Schedule};
+use crate::services::webhooks;
+use crate::state::DbPool;
+
+/// Flips scheduled posts live once their publish time has passed.
+pub struct ScheduledPublisher {
+    period: Duration,
+    pool: DbPool,
+    cache: Arc<dyn Cache>,
+}
+
+impl ScheduledPublisher {
+    pub fn new(config: &Config, pool: DbPool, cache: Arc<dyn Cache>) -> Self {
+        Self {
+            period: Duration::from_secs(config.scheduled_publish_interval_seconds().max(1)),
+            pool,
+            cache,
+        }
+    }
+}
+
+#[async_trait]
+impl Job for ScheduledPublisher {
+    fn name(&self) -> &'static str {
+        "scheduled-publish"
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(self.period)
+    }
+
+    async fn run(&self, _shutdown: Shutdown) -> Result<(), String> {
+        let pool = self.pool.clone();
+        let published = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| e.to_string())?;
+            let published = Posts::publish_due(&mut conn, chrono::Utc::now().naive_utc()).map_err(|e| e.to_string())?;
+            for post in &published {
+                if let Err(e) = notifications::record_published(&mut conn, post) {
+                    tracing::warn!("Failed to notify followers about post {}: {}", post.id, e);
+                }
+                if let Err(e) = webhooks::emit(&mut conn, &post.user_id, WEBHOOK_EVENT_POST_PUBLISHED, webhooks::post_data(post)) {
+                    tracing::warn!("Failed to queue webhooks for post {}: {}", post.id, e);
+                }
+                if let Err(e) = onboarding::complete(&mut conn, &post.user_id, ONBOARDING_STEP_FIRST_POST) {
+                    tracing::warn!("Failed to update onboarding for user {}: {}", post.user_id, e);
+                }
+            }
+            Ok::<_, String>(published.len())
+        })
+        .await
+        .map_err(|e| format!("scheduled publish task panicked: {}", e))??;
+
+        if published > 0 {
+            tracing::info!("Published {} scheduled post(s)", published);
+            cache::invalidate_all_pages(self.cache.as_ref()).await;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::{QueryResult, SqliteConnection};
+use serde::Serialize;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+
+use crate::config::Config;
+use crate::db::connection::run_blocking;
+use crate::db::models::job::Jobs;
+use crate::db::models::job_lock::JobLocks;
+use crate::db::write::write;
+use crate::errors::AuthError;
+use crate::services::job_locks::Leases;
+use crate::services::lifecycle::{Service, ServiceHealth, Shutdown, Tasks};
+use crate::state::DbPool;
+
+/// How soon a failed run is tried again, unless the job's schedule comes round sooner.
+const RETRY_AFTER_FAILURE: chrono::Duration = chrono::Duration::minutes(5);
+
+/// When a job runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// This long after the last run finished, and straight away the first time.
+    Every(Duration),
+    /// Once a day at `hour`:00 UTC.
+    DailyAt(u32),
+    /// Only when asked for with [`Scheduler::run_now`].
+    OnDemand,
+}
+
+impl Schedule {
+    /// When a job that has never run first runs.
+    fn first_run(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Every(_) => Some(now),
+            _ => self.next_after(now),
+        }
+    }
+
+    /// When the run after one finishing at `now` is due.
+    fn next_after(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            // Capped at a century, which is as good as never and can't overflow.
+            Self::Every(interval) => {
+                Some(now + chrono::Duration::from_std(interval.min(Duration::from_secs(100 * 365 * 86400))).unwrap_or_default())
+            }
+            Self::DailyAt(hour) => {
+                let today = now.date_naive().and_hms_opt(hour, 0, 0).unwrap_or_default().and_utc();
+                Some(if today > now { today } else { today + chrono::Duration::days(1) })
+            }
+            Self::OnDemand => None,
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            Self::Every(interval) => format!("every {}s", interval.as_secs()),
+            Self::DailyAt(hour) => format!("daily at {:02}:00 UTC", hour),
+            Self::OnDemand => "on demand".to_string(),
+        }
+    }
+}
+
+/// A unit of background work the [`Scheduler`] runs on a [`Schedule`].
+///
+/// Runs are at least once: a run that doesn't finish, because its server died or it returned
+/// an error, is run again. Jobs should be safe to repeat.
+#[async_trait]
+pub trait Job: Send + Sync {
+    /// The job's row in the `jobs` table, and how admins refer to it.
+    fn name(&self) -> &'static str;
+
+    fn schedule(&self) -> Schedule;
+
+    /// Does one run. `shutdown` resolves when the server is stopping; a long job should stop
+    /// at the next safe point and return an error, so the rest is picked up next time.
+    async fn run(&self, shutdown: Shutdown) -> Result<(), String>;
+}
+
+/// Where a job stands, from its row in the `jobs` table.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub name: &'static str,
+    pub schedule: String,
+    pub next_run_at: Option<NaiveDateTime>,
+    /// Held by a server right now.
+    pub running: bool,
+    pub last_run_started_at: Option<NaiveDateTime>,
+    pub last_run_finished_at: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+}
+
+/// Runs the registered jobs when they're due. Each job's schedule and last run are kept in
+/// the `jobs` table, so a restart carries on where the last process left off.
+///
+/// A run holds the job's lock in `job_locks`, so servers sharing a database take turns rather
+/// than each running everything. The lease is renewed while the job runs; if the server dies
+/// mid-run it runs out and the job is run again, by whichever server gets to it first.
+pub struct Scheduler {
+    jobs: Vec<Arc<dyn Job>>,
+    poll_interval: Duration,
+    leases: Leases,
+    pool: DbPool,
+    wake: Arc<Notify>,
+    tasks: Tasks,
+}
+
+impl Scheduler {
+    pub fn new(config: &Config, pool: DbPool, leases: Leases) -> Self {
+        Self {
+            jobs: Vec::new(),
+            poll_interval: Duration::from_secs(config.scheduler_poll_interval_seconds().max(1)),
+            leases,
+            pool,
+            wake: Arc::new(Notify::new()),
+            tasks: Tasks::new(),
+        }
+    }
+
+    /// Adds a job. Names must be unique.
+    pub fn register(&mut self, job: Arc<dyn Job>) {
+        debug_assert!(self.job(job.name()).is_none(), "job {} registered twice", job.name());
+        self.jobs.push(job);
+    }
+
+    fn job(&self, name: &str) -> Option<&Arc<dyn Job>> {
+        self.jobs.iter().find(|job| job.name() == name)
+    }
+
+    /// Every registered job, in registration order.
+    pub fn statuses(&self, conn: &mut SqliteConnection) -> QueryResult<Vec<JobStatus>> {
+        let rows = Jobs::all(conn)?;
+        let locks = JobLocks::held(conn, Utc::now().naive_utc())?;
+        Ok(self
+            .jobs
+            .iter()
+            .map(|job| {
+                let row = rows.iter().find(|row| row.name == job.name());
+                status(job.as_ref(), row, locks.iter().any(|lock| lock.name == job.name()))
+            })
+            .collect())
+    }
+
+    /// The job called `name`, or `None` if there's no such job.
+    pub fn status(&self, conn: &mut SqliteConnection, name: &str) -> QueryResult<Option<JobStatus>> {
+        let Some(job) = self.job(name) else {
+            return Ok(None);
+        };
+        let row = Jobs::by_name(conn, name)?;
+        let running = JobLocks::held(conn, Utc::now().naive_utc())?.iter().any(|lock| lock.name == name);
+        Ok(Some(status(job.as_ref(), row.as_ref(), running)))
+    }
+
+    /// Makes the job due now instead of at its next scheduled time. Returns false if there's
+    /// no job called `name`.
+    pub fn run_now(&self, conn: &mut SqliteConnection, name: &str) -> QueryResult<bool> {
+        if self.job(name).is_none() {
+            return Ok(false);
+        }
+        write(conn, |conn| Jobs::request_run(conn, name, Utc::now().naive_utc()))?;
+        self.wake.notify_one();
+        Ok(true)
+    }
+}
+
+fn status(job: &dyn Job, row: Option<&Jobs>, running: bool) -> JobStatus {
+    JobStatus {
+        name: job.name(),
+        schedule: job.schedule().describe(),
+        next_run_at: row.and_then(|row| row.next_run_at),
+        running,
+        last_run_started_at: row.and_then(|row| row.last_started_at),
+        last_run_finished_at: row.and_then(|row| row.last_finished_at),
+        last_error: row.and_then(|row| row.last_error.clone()),
+    }
+}
+
+/// What the scheduler loop shares with each run it starts.
+#[derive(Clone)]
+struct Runner {
+    pool: DbPool,
+    leases: Leases,
+}
+
+impl Runner {
+    /// Adds rows for jobs that have none and brings the rest in line with their schedules.
+    async fn register(&self, jobs: &[Arc<dyn Job>]) -> Result<(), String> {
+        let now = Utc::now();
+        let schedules: Vec<_> = jobs
+            .iter()
+            .map(|job| {
+                let schedule = job.schedule();
+                (
+                    job.name(),
+                    schedule.first_run(now).map(|at| at.naive_utc()),
+                    schedule.next_after(now).map(|at| at.naive_utc()),
+                )
+            })
+            .collect();
+        run_blocking(&self.pool, move |conn| {
+            write(conn, |conn| {
+                for (name, first_run_at, latest) in &schedules {
+                    Jobs::register(conn, name, *first_run_at, *latest, now.naive_utc())?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    /// Takes the job's lock and starts a run if it's due and no server holds it.
+    async fn claim(&self, name: &'static str) -> Result<bool, String> {
+        let now = Utc::now();
+        let holder = self.leases.holder().to_string();
+        let expires_at = self.leases.expires_at(now);
+        run_blocking(&self.pool, move |conn| {
+            write(conn, |conn| {
+                let now = now.naive_utc();
+                if !JobLocks::acquire(conn, name, &holder, now, expires_at)? {
+                    return Ok(false);
+                }
+                // Another server may have run it since this one last looked.
+                if !Jobs::start(conn, name, now)? {
+                    JobLocks::release(conn, name, &holder)?;
+                    return Ok(false);
+                }
+                Ok(true)
+            })
+        })
+        .await
+    }
+
+    /// Runs a job this server has claimed, renewing the lease until it's done, then records
+    /// the outcome and when it's next due.
+    async fn run(self, job: Arc<dyn Job>, shutdown: Shutdown) -> &'static str {
+        let name = job.name();
+        let mut renew = tokio::time::interval(self.leases.ttl() / 3);
+        renew.tick().await;
+
+        let run = job.run(shutdown);
+        tokio::pin!(run);
+        let result = loop {
+            tokio::select! {
+                result = &mut run => break result,
+                _ = renew.tick() => match self.leases.acquire(name).await {
+                    Ok(true) => {}
+                    Ok(false) => tracing::warn!("Lost the lock on job {} while it was running", name),
+                    Err(e) => tracing::warn!("Failed to renew the lock on job {}: {}", name, e),
+                },
+            }
+        };
+
+        let now = Utc::now();
+        let next = job.schedule().next_after(now);
+        let (next, error) = match result {
+            Ok(()) => (next, None),
+            Err(e) => {
+                tracing::error!("Job {} failed: {}", name, e);
+                (next.map(|next| next.min(now + RETRY_AFTER_FAILURE)), Some(e))
+            }
+        };
+
+        let holder = self.leases.holder().to_string();
+        let finished = run_blocking(&self.pool, move |conn| {
+            write(conn, |conn| {
+                // A server that lost the lock leaves the record to the one that took it.
+                if !JobLocks::release(conn, name, &holder)? {
+                    return Ok(false);
+                }
+                Jobs::finish(conn, name, now.naive_utc(), next.map(|next| next.naive_utc()), error.as_deref())?;
+                Ok(true)
+            })
+        })
+        .await;
+        match finished {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!("Job {} finished after another server took it over", name),
+            Err(e) => tracing::error!("Failed to record the run of job {}: {}", name, e),
+        }
+        name
+    }
+}
+
+#[async_trait]
+impl Service for Scheduler {
+    fn name(&self) -> &'static str {
+        "scheduler"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let jobs = self.jobs.clone();
+        let poll_interval = self.poll_interval;
+        let wake = self.wake.clone();
+        let runner = Runner { pool: self.pool.clone(), leases: self.leases.clone() };
+
+        runner.register(&jobs).await.map_err(|e| {
+            tracing::error!("Failed to register scheduled jobs: {}", e);
+            AuthError::database("Failed to register scheduled jobs")
+        })?;
+
+        self.tasks.spawn(|mut shutdown| async move {
+            let mut running = JoinSet::new();
+            let mut busy = HashSet::new();
+
+            loop {
+                match run_blocking(&runner.pool, Jobs::all).await {
+                    Ok(rows) => {
+                        let now = Utc::now().naive_utc();
+                        for job in &jobs {
+                            if busy.contains(job.name()) {
+                                continue;
+                            }
+                            let due = rows
+                                .iter()
+                                .find(|row| row.name == job.name())
+                                .and_then(|row| row.next_run_at)
+                                .is_some_and(|at| at <= now);
+                            if !due {
+                                continue;
+                            }
+                            match runner.claim(job.name()).await {
+                                Ok(true) => {
+                                    busy.insert(job.name());
+                                    running.spawn(runner.clone().run(job.clone(), shutdown.clone()));
+                                }
+                                Ok(false) => {}
+                                Err(e) => tracing::error!("Failed to claim job {}: {}", job.name(), e),
+                            }
+                        }
+                    }
+                    Err(e) => tracing::error!("Failed to load scheduled jobs: {}", e),
+                }
+
+                // Runs finishing count as a wake up, as they've just set their next time.
+                tokio::select! {
+                    _ = tokio::time::sleep(poll_interval) => {}
+                    _ = wake.notified() => {}
+                    Some(finished) = running.join_next() => match finished {
+                        Ok(name) => { busy.remove(name); }
+                        Err(e) => tracing::error!("Scheduled job panicked: {}", e),
+                    },
+                    _ = shutdown.wait() => break,
+                }
+                while let Some(finished) = running.try_join_next() {
+                    if let Ok(name) = finished {
+                        busy.remove(name);
+                    }
+                }
+            }
+
+            // Jobs have seen the same shutdown and stop at their next safe point.
+            while running.join_next().await.is_some() {}
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn daily_jobs_run_at_the_coming_occurrence_of_the_hour() {
+        assert_eq!(Schedule::DailyAt(3).next_after(at("2025-06-26T01:30:00Z")), Some(at("2025-06-26T03:00:00Z")));
+        assert_eq!(Schedule::DailyAt(3).next_after(at("2025-06-26T03:00:00Z")), Some(at("2025-06-27T03:00:00Z")));
+        assert_eq!(Schedule::DailyAt(3).first_run(at("2025-06-26T23:59:00Z")), Some(at("2025-06-27T03:00:00Z")));
+    }
+
+    #[test]
+    fn interval_jobs_run_straight_away_then_an_interval_after_each_run() {
+        let now = at("2025-06-26T12:00:00Z");
+        let hourly = Schedule::Every(Duration::from_secs(3600));
+        assert_eq!(hourly.first_run(now), Some(now));
+        assert_eq!(hourly.next_after(now), Some(at("2025-06-26T13:00:00Z")));
+        assert!(Schedule::Every(Duration::MAX).next_after(now).is_some());
+    }
+
+    #[test]
+    fn on_demand_jobs_are_never_due_by_themselves() {
+        let now = at("2025-06-26T12:00:00Z");
+        assert_eq!(Schedule::OnDemand.first_run(now), None);
+        assert_eq!(Schedule::OnDemand.next_after(now), None);
+    }
+}
@@ -0,0 +1,261 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::db::models::comment::{Comments, NewComment};
+use crate::db::models::follow::Follows;
+use crate::db::models::onboarding_step::ONBOARDING_STEP_VERIFY_EMAIL;
+use crate::db::models::post::{NewPost, PostChanges, Posts, POST_STATUS_DRAFT, POST_STATUS_PUBLISHED};
+use crate::db::models::post_version::PostVersions;
+use crate::db::models::report::MODERATION_VISIBLE;
+use crate::db::models::tag::Tags;
+use crate::db::models::user_model::{NewUser, UserModel};
+use crate::db::schema::users;
+use crate::db::write::write;
+use crate::errors::AuthError;
+use crate::services::onboarding;
+use crate::services::passwords::hash_password;
+use crate::services::post_metadata;
+use crate::utils::slugify;
+
+/// Every seeded user signs in with this.
+pub const SEED_PASSWORD: &str = "tsumi-seed-password";
+
+/// Usernames, and whether they're an admin. Each signs in as `<name>@example.com`.
+const USERS: &[(&str, bool)] = &[("ada", true), ("grace", false), ("linus", false), ("margaret", false)];
+
+struct SeedPost {
+    /// Index into `USERS`.
+    author: usize,
+    title: &'static str,
+    tags: &'static [&'static str],
+    /// The content of each version, oldest first.
+    revisions: &'static [&'static str],
+    published: bool,
+}
+
+const POSTS: &[SeedPost] = &[
+    SeedPost {
+        author: 0,
+        title: "Notes on the Analytical Engine",
+        tags: &["history", "computing"],
+        revisions: &[
+            "The engine weaves algebraic patterns just as the Jacquard loom weaves flowers and leaves.",
+            "The engine weaves algebraic patterns just as the Jacquard loom weaves flowers and leaves.\n\n\
+             ## Beyond numbers\n\nIt might act upon other things besides number, were objects found \
+             whose mutual relations could be expressed by those of abstract science.",
+        ],
+        published: true,
+    },
+    SeedPost {
+        author: 0,
+        title: "Computing Bernoulli numbers",
+        tags: &["math", "computing"],
+        revisions: &["A step-by-step table of operations for computing the Bernoulli numbers.\n\n\
+                      ```\nV1 = 1\nV2 = 2\nV3 = n\n```"],
+        published: true,
+    },
+    SeedPost {
+        author: 1,
+        title: "It's easier to ask forgiveness",
+        tags: &["advice"],
+        revisions: &[
+            "It's easier to ask forgiveness than it is to get permission.",
+            "It's easier to ask forgiveness than it is to get permission.\n\n\
+             Ships are safe in harbor, but that's not what ships are built for.",
+            "It's easier to ask forgiveness than it is to get permission.\n\n\
+             A ship in port is safe, but that's not what ships are built for. Sail out to sea and do new things.",
+        ],
+        published: true,
+    },
+    SeedPost {
+        author: 1,
+        title: "Finding the first bug",
+        tags: &["history", "debugging"],
+        revisions: &["Relay #70, Panel F: a moth, taped into the logbook. First actual case of a bug being found."],
+        published: true,
+    },
+    SeedPost {
+        author: 2,
+        title: "Just a hobby, won't be big",
+        tags: &["kernels", "announcements"],
+        revisions: &["I'm doing a (free) operating system, just a hobby, won't be big and professional.\n\n\
+                      I'd like any feedback on things people like or dislike."],
+        published: true,
+    },
+    SeedPost {
+        author: 3,
+        title: "Software engineering, a draft",
+        tags: &["engineering"],
+        revisions: &[
+            "Nobody took software seriously at first.",
+            "Nobody took software seriously at first, so I started calling it engineering.\n\n\
+             - Plan for errors\n- Make the priorities explicit\n- Test like lives depend on it",
+        ],
+        published: false,
+    },
+];
+
+/// (commenter, post, body, reply to the comment at this index of `COMMENTS`).
+const COMMENTS: &[(usize, usize, &str, Option<usize>)] = &[
+    (1, 0, "Poetical science at its finest.", None),
+    (0, 0, "Thank you! The engine deserves the poetry.", Some(0)),
+    (2, 1, "This is basically the first program, right?", None),
+    (3, 2, "Printing this out for the office.", None),
+    (1, 2, "Please do.", Some(3)),
+    (0, 4, "Famous last words.", None),
+];
+
+/// (follower, followee), indexes into `USERS`.
+const FOLLOWS: &[(usize, usize)] = &[(1, 0), (2, 0), (3, 0), (0, 1), (2, 1), (3, 2)];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SeedReport {
+    pub users: usize,
+    pub posts: usize,
+    pub versions: usize,
+    pub comments: usize,
+    pub follows: usize,
+}
+
+/// The email a seeded user signs in with.
+pub fn seed_email(name: &str) -> String {
+    format!("{}@example.com", name)
+}
+
+/// Fills the database with sample users, posts with a few versions and tags, comments and
+/// follows, for working against realistic data. Returns `None` without touching anything when
+/// the database was already seeded.
+pub fn seed(conn: &mut SqliteConnection, config: &Config) -> Result<Option<SeedReport>, AuthError> {
+    let seeded = UserModel::by_name(conn, USERS[0].0).map_err(|e| {
+        tracing::error!("Failed to look up seed users: {}", e);
+        AuthError::database("Failed to look up seed users")
+    })?;
+    if seeded.is_some() {
+        return Ok(None);
+    }
+
+    // Hashing is slow on purpose, so it's done before the write begins.
+    let mut accounts = Vec::with_capacity(USERS.len());
+    for (name, admin) in USERS {
+        let id = uuid::Uuid::new_v4().to_string();
+        let password = hash_password(config, &id, SEED_PASSWORD)?;
+        accounts.push((id, *name, *admin, password));
+    }
+
+    write(conn, |conn| {
+        let now = Utc::now().naive_utc();
+        let mut report = SeedReport::default();
+
+        let mut user_ids = Vec::with_capacity(accounts.len());
+        for (id, name, admin, password) in accounts {
+            diesel::insert_into(users::table)
+                .values(&NewUser {
+                    id: id.clone(),
+                    name: name.to_string(),
+                    email: seed_email(name),
+                    password,
+                    email_verified: true,
+                    created_at: now - Duration::days(60),
+                })
+                .execute(conn)?;
+            if admin {
+                UserModel::set_admin(conn, &id, true)?;
+            }
+            onboarding::complete(conn, &id, ONBOARDING_STEP_VERIFY_EMAIL)?;
+            user_ids.push(id);
+            report.users += 1;
+        }
+
+        let mut post_ids = Vec::with_capacity(POSTS.len());
+        for (index, seed) in POSTS.iter().enumerate() {
+            let author = &user_ids[seed.author];
+            // Spread out over the last few weeks, oldest first.
+            let at = now - Duration::days(3 * (POSTS.len() - index) as i64);
+            let (post, versions) = seed_post(conn, seed, author, at)?;
+            post_ids.push(post.id);
+            report.posts += 1;
+            report.versions += versions;
+        }
+
+        let mut comment_ids: Vec<String> = Vec::with_capacity(COMMENTS.len());
+        for (index, (commenter, post, body, reply_to)) in COMMENTS.iter().enumerate() {
+            let parent = reply_to.map(|parent| comment_ids[parent].clone());
+            let at = now - Duration::hours((COMMENTS.len() - index) as i64);
+            let comment = Comments::create(conn, &NewComment {
+                id: uuid::Uuid::new_v4().to_string(),
+                post_id: post_ids[*post].clone(),
+                user_id: user_ids[*commenter].clone(),
+                root_id: parent.clone(),
+                parent_id: parent,
+                body: body.to_string(),
+                created_at: at,
+                updated_at: at,
+                moderation_status: MODERATION_VISIBLE.to_string(),
+            })?;
+            comment_ids.push(comment.id);
+            report.comments += 1;
+        }
+
+        for (follower, followee) in FOLLOWS {
+            let follow = Follows {
+                follower_id: user_ids[*follower].clone(),
+                followee_id: user_ids[*followee].clone(),
+                created_at: now - Duration::days(30),
+            };
+            if Follows::follow(conn, &follow)? {
+                report.follows += 1;
+            }
+        }
+
+        Ok(report)
+    })
+    .map(Some)
+    .map_err(|e: diesel::result::Error| {
+        tracing::error!("Failed to seed the database: {}", e);
+        AuthError::database("Failed to seed the database")
+    })
+}
+
+/// Creates a post from its first revision and records each later one as a new version.
+fn seed_post(conn: &mut SqliteConnection, seed: &SeedPost, author: &str, at: NaiveDateTime) -> QueryResult<(Posts, usize)> {
+    let first = seed.revisions[0];
+    let word_count = post_metadata::word_count(first);
+    let mut post = Posts::create(conn, &NewPost {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: author.to_string(),
+        title: seed.title.to_string(),
+        description: post_metadata::description(first),
+        slug: slugify(seed.title),
+        content: first.to_string(),
+        created_at: at,
+        updated_at: at,
+        status: if seed.published { POST_STATUS_PUBLISHED } else { POST_STATUS_DRAFT }.to_string(),
+        published_at: seed.published.then_some(at),
+        word_count: Some(word_count),
+        og_image_url: None,
+        cover_upload_id: None,
+        reading_minutes: Some(post_metadata::reading_minutes(word_count)),
+    })?;
+    PostVersions::record(conn, &post, author, "Initial version")?;
+
+    for (number, content) in seed.revisions.iter().enumerate().skip(1) {
+        let word_count = post_metadata::word_count(content);
+        post = Posts::update(conn, &post.id, &PostChanges {
+            content: Some(content.to_string()),
+            description: Some(post_metadata::description(content)),
+            word_count: Some(word_count),
+            reading_minutes: Some(post_metadata::reading_minutes(word_count)),
+            updated_at: Some(at + Duration::hours(number as i64)),
+            ..PostChanges::default()
+        })?;
+        PostVersions::record(conn, &post, author, &format!("Revision {}", number))?;
+    }
+
+    let terms = post_metadata::search_terms(&post.title, &post.description, &post.content);
+    Posts::index_search_terms(conn, &post.id, &terms)?;
+    let tags: Vec<String> = seed.tags.iter().map(|tag| tag.to_string()).collect();
+    Tags::set_for_post(conn, &post.id, &tags)?;
+    Ok((post, seed.revisions.len()))
+}
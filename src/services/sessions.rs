@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Duration;
+use diesel::prelude::*;
+
+use crate::config::Config;
+use crate::db::models::refresh_token::RefreshTokens;
+use crate::db::write::WriteLock;
+use crate::errors::AuthError;
+use crate::http::pagination::SortDir;
+use crate::services::redis_sessions::RedisSessionStore;
+use crate::state::DbPool;
+
+/// Where signed-in sessions (the records behind refresh tokens) are kept.
+///
+/// Every refresh rotates the token, so this sees an update and an insert per refresh. Rotated
+/// tokens are kept, marked as replaced, until they expire; listings leave them out. Methods
+/// log backend failures themselves; callers only need to pick the message the client sees.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Opens a session for `token` that expires after `lifetime`, starting a new token family.
+    /// `persistent` records whether the user asked to be remembered.
+    async fn create(
+        &self,
+        token: &str,
+        user_id: &str,
+        lifetime: Duration,
+        persistent: bool,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<RefreshTokens, AuthError>;
+
+    /// Replaces `previous` with `token`, in the same family and expiring after `lifetime`.
+    /// `None` when `previous` had already been replaced.
+    async fn rotate(
+        &self,
+        previous: &RefreshTokens,
+        token: &str,
+        lifetime: Duration,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<Option<RefreshTokens>, AuthError>;
+
+    /// The session behind `token`, including one that has since been replaced.
+    async fn by_token(&self, token: &str) -> Result<Option<RefreshTokens>, AuthError>;
+
+    /// Ends the session, returning whether there was one.
+    async fn delete_by_token(&self, token: &str) -> Result<bool, AuthError>;
+
+    /// Revokes every token in a family, returning how many there were.
+    async fn delete_family(&self, family_id: &str) -> Result<usize, AuthError>;
+
+    /// Ends every session the user has, returning how many there were.
+    async fn delete_by_user(&self, user_id: &str) -> Result<usize, AuthError>;
+
+    async fn count_for_user(&self, user_id: &str, include_expired: bool) -> Result<i64, AuthError>;
+
+    /// A page of the user's sessions, ordered by `sort` (`created_at` or `expires_at`).
+    async fn page_for_user(
+        &self,
+        user_id: &str,
+        include_expired: bool,
+        sort: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<RefreshTokens>, AuthError>;
+
+    /// Sessions opened from the given IP address or belonging to the given user id.
+    async fn search(&self, term: &str, limit: i64) -> Result<Vec<RefreshTokens>, AuthError>;
+}
+
+/// Redis when `SESSION_STORE=redis`, so refresh-token rotation stays off SQLite's single
+/// writer, and the `refresh_tokens` table otherwise.
+pub fn from_config(config: &Config, pool: DbPool, writes: WriteLock) -> Arc<dyn SessionStore> {
+    match RedisSessionStore::from_config(config) {
+        Some(redis) => {
+            tracing::info!("Storing sessions in Redis");
+            Arc::new(redis)
+        }
+        None => {
+            tracing::info!("Storing sessions in the database");
+            Arc::new(DbSessionStore::new(pool, writes))
+        }
+    }
+}
+
+pub struct DbSessionStore {
+    pool: DbPool,
+    writes: WriteLock,
+}
+
+impl DbSessionStore {
+    pub fn new(pool: DbPool, writes: WriteLock) -> Self {
+        Self { pool, writes }
+    }
+
+    /// [`run`](Self::run) in this process's turn at the write lock, for queries that write.
+    async fn write<T, F>(&self, action: &'static str, query: F) -> Result<T, AuthError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut SqliteConnection) -> QueryResult<T> + Send + 'static,
+    {
+        let _turn = self.writes.acquire().await;
+        self.run(action, query).await
+    }
+
+    async fn run<T, F>(&self, action: &'static str, query: F) -> Result<T, AuthError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut SqliteConnection) -> QueryResult<T> + Send + 'static,
+    {
+        let pool = self.pool.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| e.to_string())?;
+            query(&mut conn).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+
+        result.map_err(|e| {
+            tracing::error!("Failed to {} in the database: {}", action, e);
+            AuthError::database("Session store unavailable")
+        })
+    }
+}
+
+#[async_trait]
+impl SessionStore for DbSessionStore {
+    async fn create(
+        &self,
+        token: &str,
+        user_id: &str,
+        lifetime: Duration,
+        persistent: bool,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<RefreshTokens, AuthError> {
+        let (token, user_id) = (token.to_owned(), user_id.to_owned());
+        let (ip_address, user_agent) = (ip_address.map(str::to_owned), user_agent.map(str::to_owned));
+        self.write("create session", move |conn| {
+            RefreshTokens::create(conn, &token, &user_id, lifetime, persistent, ip_address.as_deref(), user_agent.as_deref())
+        })
+        .await
+    }
+
+    async fn rotate(
+        &self,
+        previous: &RefreshTokens,
+        token: &str,
+        lifetime: Duration,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<Option<RefreshTokens>, AuthError> {
+        let (previous, token) = (previous.clone(), token.to_owned());
+        let (ip_address, user_agent) = (ip_address.map(str::to_owned), user_agent.map(str::to_owned));
+        self.write("rotate session", move |conn| {
+            RefreshTokens::rotate(conn, &previous, &token, lifetime, ip_address.as_deref(), user_agent.as_deref())
+        })
+        .await
+    }
+
+    async fn by_token(&self, token: &str) -> Result<Option<RefreshTokens>, AuthError> {
+        let token = token.to_owned();
+        self.run("look up session", move |conn| RefreshTokens::by_token(conn, &token).optional()).await
+    }
+
+    async fn delete_by_token(&self, token: &str) -> Result<bool, AuthError> {
+        let token = token.to_owned();
+        self.write("delete session", move |conn| RefreshTokens::delete_by_token(conn, &token))
+            .await
+            .map(|deleted| deleted > 0)
+    }
+
+    async fn delete_family(&self, family_id: &str) -> Result<usize, AuthError> {
+        let family_id = family_id.to_owned();
+        self.write("delete session family", move |conn| RefreshTokens::delete_family(conn, &family_id)).await
+    }
+
+    async fn delete_by_user(&self, user_id: &str) -> Result<usize, AuthError> {
+        let user_id = user_id.to_owned();
+        self.write("delete sessions", move |conn| RefreshTokens::delete_by_user(conn, &user_id)).await
+    }
+
+    async fn count_for_user(&self, user_id: &str, include_expired: bool) -> Result<i64, AuthError> {
+        let user_id = user_id.to_owned();
+        self.run("count sessions", move |conn| RefreshTokens::count_for_user(conn, &user_id, include_expired)).await
+    }
+
+    async fn page_for_user(
+        &self,
+        user_id: &str,
+        include_expired: bool,
+        sort: &str,
+        dir: SortDir,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<RefreshTokens>, AuthError> {
+        let (user_id, sort) = (user_id.to_owned(), sort.to_owned());
+        self.run("list sessions", move |conn| {
+            RefreshTokens::page_for_user(conn, &user_id, include_expired, &sort, dir, offset, limit)
+        })
+        .await
+    }
+
+    async fn search(&self, term: &str, limit: i64) -> Result<Vec<RefreshTokens>, AuthError> {
+        let term = term.to_owned();
+        self.run("search sessions", move |conn| RefreshTokens::search(conn, &term, limit)).await
+    }
+}
@@ -0,0 +1,104 @@
+/// Posts whose fingerprints differ in at most this many of their 64 bits are treated as near
+/// duplicates. Kept below the number of bands so every match shares at least one band.
+pub const MAX_DISTANCE: u32 = 3;
+
+/// Words per shingle. Shingles rather than single words make the fingerprint sensitive to
+/// word order, so two posts about the same topic don't look alike just for sharing a vocabulary.
+const SHINGLE_WORDS: usize = 3;
+
+/// A 64-bit simhash of a post's title and body. Similar texts get fingerprints that differ in
+/// few bits, unlike a cryptographic hash where any edit changes everything.
+pub fn fingerprint(title: &str, content: &str) -> u64 {
+    let words: Vec<String> = title
+        .split(|c: char| !c.is_alphanumeric())
+        .chain(content.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut weights = [0i64; 64];
+    let mut add = |feature: &[String]| {
+        let hash = fnv1a(feature);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    };
+    if words.len() < SHINGLE_WORDS {
+        add(&words);
+    } else {
+        words.windows(SHINGLE_WORDS).for_each(&mut add);
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// Number of differing bits between two fingerprints.
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// The fingerprint split into four 16-bit bands for the lookup indexes.
+pub fn bands(hash: u64) -> [i32; 4] {
+    [0, 1, 2, 3].map(|band| (hash >> (band * 16) & 0xffff) as i32)
+}
+
+/// FNV-1a over the words of a shingle. Fingerprints are stored, so the hash has to be stable
+/// across builds and Rust versions, which the standard library's hasher doesn't promise.
+fn fnv1a(words: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in words.join(" ").bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POST: &str = "Rust's ownership model gives memory safety without a garbage collector. \
+        Every value has a single owner, and the value is dropped when the owner goes out of scope. \
+        Borrowing lets code use a value without taking ownership of it, and the borrow checker \
+        makes sure references never outlive the data they point to. Mutable references are \
+        exclusive: while one exists, no other reference to the same value may be used, which rules \
+        out data races at compile time. Shared references can be copied freely but only allow \
+        reading. Moving a value transfers ownership, so the previous binding can no longer be used, \
+        while types that implement Copy are duplicated instead. Lifetimes name the regions of code \
+        in which a reference is valid; most of the time the compiler infers them, and annotations \
+        are only needed when a function returns a reference derived from one of several inputs. \
+        Smart pointers such as Box, Rc and Arc build on these rules: Box owns heap data, Rc shares \
+        ownership within a thread through reference counting, and Arc does the same across threads \
+        using atomic operations. Interior mutability types like RefCell and Mutex move some of the \
+        borrow checking to run time when the static rules are too strict for a data structure. \
+        Together these pieces let programs manage memory and other resources predictably, with \
+        cleanup happening at well defined points instead of whenever a collector decides to run.";
+
+    #[test]
+    fn small_edits_stay_within_the_threshold() {
+        let original = fingerprint("Understanding ownership", POST);
+        let edited = fingerprint("Understanding ownership!", &POST.replace("single owner", "single, unique owner"));
+        assert!(distance(original, edited) <= MAX_DISTANCE, "distance {}", distance(original, edited));
+        assert_eq!(original, fingerprint("UNDERSTANDING OWNERSHIP", &POST.to_uppercase()));
+    }
+
+    #[test]
+    fn unrelated_posts_are_far_apart() {
+        let other = "Sourdough needs a lively starter, a long cold proof in the fridge overnight, \
+            and a very hot oven. Score the loaf just before baking so it can expand evenly.";
+        let distance = distance(fingerprint("Understanding ownership", POST), fingerprint("Baking bread", other));
+        assert!(distance > MAX_DISTANCE * 4, "distance {}", distance);
+    }
+
+    #[test]
+    fn near_duplicates_share_a_band() {
+        let hash = fingerprint("Understanding ownership", POST);
+        let nearby = hash ^ (1 << 3) ^ (1 << 20) ^ (1 << 40);
+        assert!(bands(hash).iter().zip(bands(nearby)).any(|(a, b)| *a == b));
+        assert!(bands(u64::MAX).iter().all(|band| *band == 0xffff));
+    }
+}
@@ -0,0 +1,300 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwapOption;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use diesel::SqliteConnection;
+use sha2::{Digest, Sha256};
+use tokio::time::MissedTickBehavior;
+
+use crate::config::Config;
+use crate::db::models::page::Pages;
+use crate::db::models::post::Posts;
+use crate::errors::AuthError;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+use crate::state::DbPool;
+
+/// The sitemap protocol caps a single file at 50,000 URLs.
+pub const URLS_PER_SITEMAP: usize = 50_000;
+
+const STATIC_PAGES: &[&str] = &["/", "/posts"];
+
+#[derive(Debug, Clone)]
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<NaiveDateTime>,
+}
+
+/// Every public URL on the site: static pages and published site pages, then each active
+/// author's page followed by their published posts. Author pages take the `updated_at` of their most recent post.
+pub fn entries(conn: &mut SqliteConnection, base_url: &str) -> diesel::QueryResult<Vec<SitemapEntry>> {
+    let mut entries: Vec<SitemapEntry> = STATIC_PAGES
+        .iter()
+        .map(|path| SitemapEntry { loc: format!("{}{}", base_url, path), lastmod: None })
+        .collect();
+    entries.extend(Pages::sitemap_entries(conn)?.into_iter().map(|(slug, updated_at)| SitemapEntry {
+        loc: format!("{}/p/{}", base_url, slug),
+        lastmod: Some(updated_at),
+    }));
+
+    let mut current_author: Option<(String, usize)> = None;
+    for (author, slug, updated_at) in Posts::sitemap_entries(conn)? {
+        let author_index = match &current_author {
+            Some((name, index)) if *name == author => *index,
+            _ => {
+                entries.push(SitemapEntry { loc: format!("{}/{}", base_url, author), lastmod: None });
+                let index = entries.len() - 1;
+                current_author = Some((author.clone(), index));
+                index
+            }
+        };
+
+        let author_page = &mut entries[author_index];
+        if author_page.lastmod.is_none_or(|lastmod| lastmod < updated_at) {
+            author_page.lastmod = Some(updated_at);
+        }
+
+        entries.push(SitemapEntry {
+            loc: format!("{}/{}/{}", base_url, author, slug),
+            lastmod: Some(updated_at),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Renders a `<urlset>` document.
+pub fn render_urlset(entries: &[SitemapEntry]) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str("\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for entry in entries {
+        xml.push_str("  <url>\n    <loc>");
+        xml.push_str(&xml_escape(&entry.loc));
+        xml.push_str("</loc>\n");
+        if let Some(lastmod) = entry.lastmod {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", format_lastmod(lastmod)));
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Renders a `<sitemapindex>` pointing at `/sitemaps/{n}.xml` for each chunk, with the newest
+/// `lastmod` found in that chunk.
+pub fn render_index(base_url: &str, entries: &[SitemapEntry]) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str("\n<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for (index, chunk) in entries.chunks(URLS_PER_SITEMAP).enumerate() {
+        xml.push_str("  <sitemap>\n    <loc>");
+        xml.push_str(&xml_escape(&format!("{}/sitemaps/{}.xml", base_url, index + 1)));
+        xml.push_str("</loc>\n");
+        if let Some(lastmod) = chunk.iter().filter_map(|entry| entry.lastmod).max() {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", format_lastmod(lastmod)));
+        }
+        xml.push_str("  </sitemap>\n");
+    }
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+fn format_lastmod(lastmod: NaiveDateTime) -> String {
+    lastmod.and_utc().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// What a sitemap was built from: how many posts and pages it lists and when the latest of
+/// each changed. Any write that touches the sitemap changes one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SiteVersion {
+    posts: (i64, Option<NaiveDateTime>),
+    pages: (i64, Option<NaiveDateTime>),
+}
+
+pub fn site_version(conn: &mut SqliteConnection) -> diesel::QueryResult<SiteVersion> {
+    Ok(SiteVersion { posts: Posts::sitemap_version(conn)?, pages: Pages::sitemap_version(conn)? })
+}
+
+/// One rendered sitemap document.
+#[derive(Debug)]
+pub struct SitemapFile {
+    pub xml: String,
+    /// A strong validator, from a hash of `xml`.
+    pub etag: String,
+}
+
+impl SitemapFile {
+    fn new(xml: String) -> Self {
+        let etag = format!("\"{}\"", &hex::encode(Sha256::digest(xml.as_bytes()))[..32]);
+        Self { xml, etag }
+    }
+}
+
+/// `/sitemap.xml` and, once there are more URLs than fit in one file, the
+/// `/sitemaps/{n}.xml` chunks it indexes.
+#[derive(Debug)]
+pub struct SitemapFiles {
+    version: SiteVersion,
+    pub root: SitemapFile,
+    pub chunks: Vec<SitemapFile>,
+}
+
+/// Reads the site and renders every sitemap file.
+pub fn build(conn: &mut SqliteConnection, base_url: &str) -> diesel::QueryResult<SitemapFiles> {
+    // Read first, so a write landing mid-build leaves the files looking out of date.
+    let version = site_version(conn)?;
+    let entries = entries(conn, base_url)?;
+
+    let (root, chunks) = if entries.len() <= URLS_PER_SITEMAP {
+        (render_urlset(&entries), Vec::new())
+    } else {
+        let chunks = entries.chunks(URLS_PER_SITEMAP).map(|chunk| SitemapFile::new(render_urlset(chunk))).collect();
+        (render_index(base_url, &entries), chunks)
+    };
+
+    Ok(SitemapFiles { version, root: SitemapFile::new(root), chunks })
+}
+
+/// The rendered sitemap, kept up to date by a worker so requests never read every post.
+/// The worker checks the site's [`SiteVersion`] every interval and rebuilds once a change
+/// has settled. Until it's running, requests check the version themselves and rebuild when
+/// it has moved.
+pub struct SitemapCache {
+    period: Duration,
+    store: Arc<SitemapStore>,
+    tasks: Tasks,
+}
+
+struct SitemapStore {
+    pool: DbPool,
+    base_url: String,
+    files: ArcSwapOption<SitemapFiles>,
+    worker_running: AtomicBool,
+}
+
+impl SitemapCache {
+    pub fn new(config: &Config, pool: DbPool) -> Self {
+        Self {
+            period: Duration::from_secs(config.sitemap_refresh_interval_seconds().max(1)),
+            store: Arc::new(SitemapStore {
+                pool,
+                base_url: config.canonical_url().to_string(),
+                files: ArcSwapOption::empty(),
+                worker_running: AtomicBool::new(false),
+            }),
+            tasks: Tasks::new(),
+        }
+    }
+
+    /// The sitemap to serve, built on the spot when there's none yet or, without the worker,
+    /// when it's out of date.
+    pub fn current(&self, conn: &mut SqliteConnection) -> diesel::QueryResult<Arc<SitemapFiles>> {
+        let store = &self.store;
+        if let Some(files) = store.files.load_full()
+            && (store.worker_running.load(Ordering::Relaxed) || site_version(conn)? == files.version)
+        {
+            return Ok(files);
+        }
+        store.rebuild(conn)
+    }
+}
+
+impl SitemapStore {
+    fn rebuild(&self, conn: &mut SqliteConnection) -> diesel::QueryResult<Arc<SitemapFiles>> {
+        let files = Arc::new(build(conn, &self.base_url)?);
+        self.files.store(Some(files.clone()));
+        Ok(files)
+    }
+
+    /// One check of the worker. `pending` is the version a previous check found changed; it's
+    /// only rebuilt once a check finds the site still at that version.
+    fn refresh(&self, conn: &mut SqliteConnection, pending: &mut Option<SiteVersion>) -> diesel::QueryResult<bool> {
+        let version = site_version(conn)?;
+        let built = self.files.load().as_ref().map(|files| files.version);
+        if built == Some(version) {
+            *pending = None;
+            return Ok(false);
+        }
+        if built.is_some() && *pending != Some(version) {
+            *pending = Some(version);
+            return Ok(false);
+        }
+        self.rebuild(conn)?;
+        *pending = None;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Service for SitemapCache {
+    fn name(&self) -> &'static str {
+        "sitemap-cache"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let period = self.period;
+        let store = self.store.clone();
+
+        self.tasks.spawn(|mut shutdown| async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut pending = None;
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.wait() => break,
+                }
+
+                let task_store = store.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let mut conn = task_store.pool.get().map_err(|e| e.to_string())?;
+                    let rebuilt = task_store.refresh(&mut conn, &mut pending).map_err(|e| e.to_string())?;
+                    Ok::<_, String>((rebuilt, pending))
+                })
+                .await;
+
+                match result {
+                    Ok(Ok((rebuilt, still_pending))) => {
+                        pending = still_pending;
+                        if rebuilt {
+                            tracing::debug!("Rebuilt the sitemap");
+                        }
+                        // Only trusted once the first check has built it.
+                        store.worker_running.store(true, Ordering::Relaxed);
+                    }
+                    Ok(Err(e)) => tracing::error!("Failed to refresh the sitemap: {}", e),
+                    Err(e) => tracing::error!("Sitemap refresh task panicked: {}", e),
+                }
+            }
+
+            store.worker_running.store(false, Ordering::Relaxed);
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
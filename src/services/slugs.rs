@@ -0,0 +1,65 @@
+use std::collections::HashSet;
+
+use diesel::{QueryResult, SqliteConnection};
+
+use crate::db::models::post_slug::PostSlugs;
+use crate::utils::slugify;
+
+/// Slugs no post can have: path segments the site's own routes use, so a post's link can
+/// never be mistaken for one of them.
+pub const RESERVED_SLUGS: &[&str] = &[
+    "static", "auth", "admin", "api", "activity", "feed", "rss", "atom", "new", "edit", "settings",
+];
+
+pub fn is_reserved(slug: &str) -> bool {
+    RESERVED_SLUGS.iter().any(|reserved| reserved.eq_ignore_ascii_case(slug))
+}
+
+/// Whether `slug` is the one `title` gives, or that with a number added to tell it apart, so
+/// it should change along with the title.
+pub fn follows_title(slug: &str, title: &str) -> bool {
+    let base = slugify(title);
+    !base.is_empty()
+        && (slug == base
+            || slug
+                .strip_prefix(base.as_str())
+                .and_then(|rest| rest.strip_prefix('-'))
+                .is_some_and(|n| n.parse::<u32>().is_ok_and(|n| n >= 2)))
+}
+
+/// `base`, or the first of `base-2`, `base-3` and so on that the author isn't using, either
+/// as a post's slug or an old one that still redirects. `post_id`'s own slugs are free to it.
+pub fn available(conn: &mut SqliteConnection, user_id: &str, base: &str, post_id: Option<&str>) -> QueryResult<String> {
+    let taken = PostSlugs::taken(conn, user_id, base, post_id)?;
+    Ok(first_free(base, &taken))
+}
+
+fn first_free(base: &str, taken: &HashSet<String>) -> String {
+    std::iter::once(base.to_string())
+        .chain((2..).map(|n| format!("{}-{}", base, n)))
+        .find(|slug| !taken.contains(slug) && !is_reserved(slug))
+        .expect("only finitely many slugs are taken")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_first_free_slug_skips_taken_and_reserved_ones() {
+        let taken: HashSet<String> = ["my-post", "my-post-2", "my-post-4"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(first_free("my-post", &taken), "my-post-3");
+        assert_eq!(first_free("other", &taken), "other");
+        assert_eq!(first_free("admin", &HashSet::new()), "admin-2");
+    }
+
+    #[test]
+    fn slugs_follow_the_title_they_came_from() {
+        assert!(follows_title("my-post", "My Post"));
+        assert!(follows_title("my-post-3", "My Post"));
+        assert!(!follows_title("my-post-1", "My Post"));
+        assert!(!follows_title("my-post-draft", "My Post"));
+        assert!(!follows_title("hand-picked", "My Post"));
+        assert!(is_reserved("API"));
+    }
+}
@@ -0,0 +1,280 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::db::models::report::{MODERATION_PENDING, MODERATION_SHADOWED, MODERATION_VISIBLE};
+use crate::errors::AuthError;
+use crate::services::http_client::HttpClient;
+use crate::state::AppState;
+
+const AKISMET_URL: &str = "https://rest.akismet.com/1.1/comment-check";
+/// Akismet only says spam or not; these stand in for a score. Spam it's sure enough of to
+/// suggest discarding outright scores the most.
+const AKISMET_SPAM_SCORE: f64 = 0.8;
+const AKISMET_DISCARD_SCORE: f64 = 1.0;
+
+/// Where spam checks get their verdicts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpamBackend {
+    /// Keywords from `SPAM_KEYWORDS`, links and shouting, scored locally.
+    Heuristic,
+    Akismet,
+}
+
+impl FromStr for SpamBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "heuristic" => Ok(Self::Heuristic),
+            "akismet" => Ok(Self::Akismet),
+            other => Err(format!("expected heuristic or akismet, got {}", other)),
+        }
+    }
+}
+
+/// What gets checked, each switched on with its own setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpamCheck {
+    Comment,
+    Signup,
+}
+
+/// What happens to checked content, from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    Allow,
+    /// Held back until a moderator lets it through.
+    Queue,
+    /// Quietly kept from everyone but its author.
+    Hide,
+    Reject,
+}
+
+impl Verdict {
+    /// The verdict a comment's `moderation_status` stands for.
+    pub fn from_moderation(status: &str) -> Self {
+        match status {
+            MODERATION_PENDING => Self::Queue,
+            MODERATION_SHADOWED => Self::Hide,
+            _ => Self::Allow,
+        }
+    }
+
+    /// The `moderation_status` content gets for this verdict. Rejected content isn't kept.
+    pub fn moderation_status(self) -> &'static str {
+        match self {
+            Self::Allow | Self::Reject => MODERATION_VISIBLE,
+            Self::Queue => MODERATION_PENDING,
+            Self::Hide => MODERATION_SHADOWED,
+        }
+    }
+}
+
+/// Content to check, with what's known about who sent it.
+#[derive(Debug)]
+pub struct Submission<'a> {
+    pub check: SpamCheck,
+    pub author: &'a str,
+    pub email: &'a str,
+    /// The comment; empty for signups.
+    pub content: &'a str,
+    pub ip_address: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+}
+
+/// Scores submissions from 0 (clean) to 1 (certainly spam). Implemented per backend so no
+/// particular vendor is baked in.
+#[async_trait]
+pub trait SpamChecker: Send + Sync {
+    async fn score(&self, submission: &Submission<'_>) -> Result<f64, AuthError>;
+}
+
+/// The configured backend, if any.
+pub fn from_config(config: &Config, http: HttpClient) -> Option<Arc<dyn SpamChecker>> {
+    match config.spam_backend()? {
+        SpamBackend::Heuristic => {
+            tracing::info!("Checking for spam with {} keywords", config.spam_keywords().len());
+            Some(Arc::new(HeuristicChecker::new(config.spam_keywords())))
+        }
+        SpamBackend::Akismet => {
+            let url = config.spam_akismet_url().unwrap_or(AKISMET_URL);
+            tracing::info!("Checking for spam with Akismet at {}", url);
+            Some(Arc::new(AkismetChecker::new(
+                http,
+                url,
+                config.spam_akismet_api_key().unwrap_or_default(),
+                config.public_url(),
+            )))
+        }
+    }
+}
+
+/// Scores by what spam tends to look like: configured keywords, a pile of links, or shouting.
+pub struct HeuristicChecker {
+    keywords: Vec<String>,
+}
+
+impl HeuristicChecker {
+    pub fn new(keywords: &[String]) -> Self {
+        Self { keywords: keywords.iter().map(|keyword| keyword.to_lowercase()).collect() }
+    }
+
+    fn score_text(&self, submission: &Submission<'_>) -> f64 {
+        // Names are only judged at signup, so an admin trusting an account is enough to clear it.
+        let text = match submission.check {
+            SpamCheck::Comment => submission.content.to_lowercase(),
+            SpamCheck::Signup => format!("{} {}", submission.author, submission.email).to_lowercase(),
+        };
+        let keyword_hits = self.keywords.iter().filter(|keyword| text.contains(keyword.as_str())).count();
+        let links = submission.content.matches("http://").count() + submission.content.matches("https://").count();
+
+        let letters: Vec<char> = submission.content.chars().filter(|c| c.is_alphabetic()).collect();
+        let upper = letters.iter().filter(|c| c.is_uppercase()).count();
+        let shouting = letters.len() >= 20 && upper * 10 >= letters.len() * 7;
+
+        let mut score = 0.4 * keyword_hits as f64;
+        if links > 2 {
+            score += 0.3;
+        }
+        if links > 5 {
+            score += 0.3;
+        }
+        if shouting {
+            score += 0.2;
+        }
+        score.min(1.0)
+    }
+}
+
+#[async_trait]
+impl SpamChecker for HeuristicChecker {
+    async fn score(&self, submission: &Submission<'_>) -> Result<f64, AuthError> {
+        Ok(self.score_text(submission))
+    }
+}
+
+/// Akismet's `comment-check`: a form POST answered with `true` for spam, plus an
+/// `X-akismet-pro-tip: discard` header for the blatant kind.
+pub struct AkismetChecker {
+    http: HttpClient,
+    url: String,
+    api_key: String,
+    blog: String,
+}
+
+impl AkismetChecker {
+    pub fn new(http: HttpClient, url: &str, api_key: &str, blog: &str) -> Self {
+        Self {
+            http,
+            url: url.to_string(),
+            api_key: api_key.to_string(),
+            blog: blog.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SpamChecker for AkismetChecker {
+    async fn score(&self, submission: &Submission<'_>) -> Result<f64, AuthError> {
+        let comment_type = match submission.check {
+            SpamCheck::Comment => "comment",
+            SpamCheck::Signup => "signup",
+        };
+        let form = [
+            ("api_key", self.api_key.as_str()),
+            ("blog", self.blog.as_str()),
+            ("user_ip", submission.ip_address.unwrap_or_default()),
+            ("user_agent", submission.user_agent.unwrap_or_default()),
+            ("comment_type", comment_type),
+            ("comment_author", submission.author),
+            ("comment_author_email", submission.email),
+            ("comment_content", submission.content),
+        ];
+
+        // Checking is safe to repeat.
+        let response = self.http
+            .send_idempotent(self.http.post(&self.url).form(&form))
+            .await
+            .map_err(|e| AuthError::internal(format!("Akismet request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AuthError::internal(format!("Akismet answered {}", response.status())));
+        }
+
+        let discard = response
+            .headers()
+            .get("x-akismet-pro-tip")
+            .is_some_and(|tip| tip.as_bytes() == b"discard");
+        let body = response.text().await
+            .map_err(|e| AuthError::internal(format!("Invalid Akismet response: {}", e)))?;
+        match body.trim() {
+            "true" if discard => Ok(AKISMET_DISCARD_SCORE),
+            "true" => Ok(AKISMET_SPAM_SCORE),
+            "false" => Ok(0.0),
+            other => Err(AuthError::internal(format!("Unexpected Akismet response: {}", other))),
+        }
+    }
+}
+
+/// The verdict on `submission` under the configured thresholds. Content is let through when
+/// checks are off for it or the backend can't be asked: a spam check that's down shouldn't
+/// stop people commenting.
+pub async fn check(state: &AppState, submission: &Submission<'_>) -> Verdict {
+    let Some(checker) = state.spam.as_ref().filter(|_| state.config.load().spam_checked(submission.check)) else {
+        return Verdict::Allow;
+    };
+
+    match checker.score(submission).await {
+        Ok(score) => {
+            let verdict = state.config.load().spam_verdict(score);
+            if verdict != Verdict::Allow {
+                tracing::info!("Spam check scored {:?} by {} at {:.2}: {:?}", submission.check, submission.author, score, verdict);
+            }
+            verdict
+        }
+        Err(e) => {
+            tracing::warn!("Failed to check {:?} by {} for spam: {}", submission.check, submission.author, e);
+            Verdict::Allow
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(content: &str) -> Submission<'_> {
+        Submission {
+            check: SpamCheck::Comment,
+            author: "bob",
+            email: "bob@example.com",
+            content,
+            ip_address: None,
+            user_agent: None,
+        }
+    }
+
+    #[test]
+    fn heuristic_scores_keywords_links_and_shouting() {
+        let checker = HeuristicChecker::new(&["Casino".to_string(), "cheap pills".to_string()]);
+        assert_eq!(checker.score_text(&comment("Nice post, thanks!")), 0.0);
+        assert!((checker.score_text(&comment("Best CASINO bonus here")) - 0.4).abs() < 1e-9);
+
+        let links = "see https://a.example https://b.example http://c.example";
+        assert!((checker.score_text(&comment(links)) - 0.3).abs() < 1e-9);
+        assert!(checker.score_text(&comment("THIS IS THE BEST THING I HAVE EVER READ")) > 0.0);
+        assert_eq!(checker.score_text(&comment(&format!("casino cheap pills {} {}", links, links))), 1.0);
+    }
+
+    #[test]
+    fn verdicts_map_to_moderation_statuses_and_back() {
+        for verdict in [Verdict::Allow, Verdict::Queue, Verdict::Hide] {
+            assert_eq!(Verdict::from_moderation(verdict.moderation_status()), verdict);
+        }
+        assert!(Verdict::Hide > Verdict::Queue);
+    }
+}
@@ -0,0 +1,88 @@
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::config::Config;
+use crate::errors::AuthError;
+use crate::services::s3::S3Storage;
+
+/// Where uploaded files are kept. Keys are relative, `/`-separated paths chosen by the caller.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, bytes: Vec<u8>) -> Result<(), AuthError>;
+
+    /// The stored bytes, or `None` if nothing is stored under `key`.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, AuthError>;
+
+    async fn delete(&self, key: &str) -> Result<(), AuthError>;
+}
+
+/// The S3 backend when a bucket is configured, local disk otherwise.
+pub fn from_config(config: &Config) -> Arc<dyn Storage> {
+    match S3Storage::from_config(config) {
+        Some(s3) => {
+            tracing::info!("Storing uploads in S3 bucket {}", config.s3_bucket().unwrap_or_default());
+            Arc::new(s3)
+        }
+        None => {
+            tracing::info!("Storing uploads under {}", config.uploads_dir());
+            Arc::new(DiskStorage::new(config.uploads_dir()))
+        }
+    }
+}
+
+pub struct DiskStorage {
+    root: PathBuf,
+}
+
+impl DiskStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, AuthError> {
+        let relative = Path::new(key);
+        if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+            return Err(AuthError::internal(format!("Invalid storage key: {}", key)));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl Storage for DiskStorage {
+    async fn put(&self, key: &str, _content_type: &str, bytes: Vec<u8>) -> Result<(), AuthError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await
+                .map_err(|e| AuthError::internal(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+
+        // Write beside the target and rename so readers never see a partial file.
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, bytes).await
+            .map_err(|e| AuthError::internal(format!("Failed to write {}: {}", partial.display(), e)))?;
+        tokio::fs::rename(&partial, &path).await
+            .map_err(|e| AuthError::internal(format!("Failed to move upload into {}: {}", path.display(), e)))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, AuthError> {
+        let path = self.path(key)?;
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(AuthError::internal(format!("Failed to read {}: {}", path.display(), e))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AuthError> {
+        let path = self.path(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AuthError::internal(format!("Failed to delete {}: {}", path.display(), e))),
+        }
+    }
+}
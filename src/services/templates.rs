@@ -0,0 +1,226 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use include_dir::{include_dir, Dir, DirEntry};
+use tera::{Context, Tera};
+
+use crate::config::Config;
+use crate::errors::AuthError;
+use crate::http::assets::{collect_files, AssetFunction, AssetManifest};
+use crate::http::locale::Translate;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+use crate::services::markdown;
+
+/// Where templates are read from in development.
+pub const TEMPLATE_DIR: &str = "templates";
+
+/// How often the watcher looks for edited templates.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The templates as they were at build time, so deployments don't need the directory.
+static EMBEDDED: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/templates");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Source {
+    Embedded,
+    Disk(PathBuf),
+}
+
+/// The page templates. In development they're read from `templates/`, and a
+/// [`TemplateWatcher`] swaps in a fresh set whenever one is edited. Otherwise they're the
+/// copies compiled into the binary.
+#[derive(Clone)]
+pub struct Templates {
+    tera: Arc<ArcSwap<Tera>>,
+    source: Source,
+    assets: Arc<AssetManifest>,
+}
+
+impl Templates {
+    /// Disk templates in development, the embedded ones otherwise.
+    pub fn from_config(config: &Config, assets: Arc<AssetManifest>) -> Self {
+        if config.is_development() {
+            Self::from_dir(TEMPLATE_DIR, assets)
+        } else {
+            Self::embedded(assets)
+        }
+    }
+
+    pub fn embedded(assets: Arc<AssetManifest>) -> Self {
+        // Every embedded template is parsed by the tests, so this can't fail in a release.
+        let tera = build(&Source::Embedded, &assets).expect("embedded templates are valid");
+        Self { tera: Arc::new(ArcSwap::from_pointee(tera)), source: Source::Embedded, assets }
+    }
+
+    /// The templates under `dir`. While they don't load, the embedded ones stand in until a
+    /// reload succeeds, so a typo doesn't stop the server from starting.
+    pub fn from_dir(dir: impl Into<PathBuf>, assets: Arc<AssetManifest>) -> Self {
+        let source = Source::Disk(dir.into());
+        let tera = build(&source, &assets).unwrap_or_else(|e| {
+            tracing::error!("Failed to load templates from disk, using the built-in ones: {}", e);
+            build(&Source::Embedded, &assets).expect("embedded templates are valid")
+        });
+        Self { tera: Arc::new(ArcSwap::from_pointee(tera)), source, assets }
+    }
+
+    pub fn render(&self, name: &str, ctx: &Context) -> tera::Result<String> {
+        self.tera.load().render(name, ctx)
+    }
+
+    /// Reads every template from disk again. The running set is kept if any fails to parse.
+    pub fn reload(&self) -> tera::Result<()> {
+        if self.source == Source::Embedded {
+            return Ok(());
+        }
+        self.tera.store(Arc::new(build(&self.source, &self.assets)?));
+        Ok(())
+    }
+
+    /// The directory the templates come from, when they aren't embedded.
+    pub fn dir(&self) -> Option<&Path> {
+        match &self.source {
+            Source::Disk(dir) => Some(dir),
+            Source::Embedded => None,
+        }
+    }
+}
+
+fn build(source: &Source, assets: &Arc<AssetManifest>) -> tera::Result<Tera> {
+    let mut tera = match source {
+        Source::Disk(dir) => Tera::new(&format!("{}/**/*", dir.display()))?,
+        Source::Embedded => {
+            let mut files = Vec::new();
+            embedded_files(&EMBEDDED, &mut files);
+            let mut tera = Tera::default();
+            tera.add_raw_templates(files)?;
+            tera
+        }
+    };
+    tera.register_filter("markdown", markdown::tera_filter);
+    tera.register_function("asset", AssetFunction(assets.clone()));
+    tera.register_function("t", Translate);
+    Ok(tera)
+}
+
+/// Every embedded template, named by its path under `templates/` as `Tera::new` would.
+fn embedded_files(dir: &'static Dir<'static>, files: &mut Vec<(String, &'static str)>) {
+    for entry in dir.entries() {
+        match entry {
+            DirEntry::Dir(dir) => embedded_files(dir, files),
+            DirEntry::File(file) => {
+                if let Some(contents) = file.contents_utf8() {
+                    files.push((file.path().to_string_lossy().replace('\\', "/"), contents));
+                }
+            }
+        }
+    }
+}
+
+/// What changes when a template is edited, added or removed: the file count and the newest
+/// modification time.
+fn snapshot(dir: &Path) -> (usize, Option<SystemTime>) {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files);
+    let newest = files
+        .iter()
+        .filter_map(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+        .max();
+    (files.len(), newest)
+}
+
+/// Reloads disk templates when the template directory changes, so edits show up without a
+/// restart. Only registered in development.
+pub struct TemplateWatcher {
+    templates: Templates,
+    tasks: Tasks,
+}
+
+impl TemplateWatcher {
+    pub fn new(templates: Templates) -> Self {
+        Self { templates, tasks: Tasks::new() }
+    }
+}
+
+#[async_trait]
+impl Service for TemplateWatcher {
+    fn name(&self) -> &'static str {
+        "template-watcher"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let Some(dir) = self.templates.dir().map(Path::to_path_buf) else {
+            return Ok(());
+        };
+        let templates = self.templates.clone();
+        let mut last = snapshot(&dir);
+
+        self.tasks.spawn(|mut shutdown| async move {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {
+                        let current = snapshot(&dir);
+                        if current == last {
+                            continue;
+                        }
+                        last = current;
+                        match templates.reload() {
+                            Ok(()) => tracing::info!("Reloaded templates from {}", dir.display()),
+                            Err(e) => tracing::error!("Kept the running templates, as they failed to reload: {}", e),
+                        }
+                    }
+                    _ = shutdown.wait() => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_embedded_template_parses_under_its_path() {
+        let templates = Templates::embedded(Arc::new(AssetManifest::default()));
+        let tera = templates.tera.load();
+        let names: Vec<&str> = tera.get_template_names().collect();
+        assert!(names.contains(&"login.html"), "{:?}", names);
+        assert!(names.contains(&"widgets/latest_posts.html"), "{:?}", names);
+        assert_eq!(templates.dir(), None);
+    }
+
+    #[test]
+    fn reloads_pick_up_edits_and_keep_the_old_set_on_errors() {
+        let dir = std::env::temp_dir().join(format!("tsumi-templates-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("hello.html"), "hello {{ name }}").unwrap();
+        let templates = Templates::from_dir(&dir, Arc::new(AssetManifest::default()));
+        let mut ctx = Context::new();
+        ctx.insert("name", "ann");
+        assert_eq!(templates.render("hello.html", &ctx).unwrap(), "hello ann");
+
+        fs::write(dir.join("hello.html"), "hi {{ name }}").unwrap();
+        templates.reload().unwrap();
+        assert_eq!(templates.render("hello.html", &ctx).unwrap(), "hi ann");
+
+        fs::write(dir.join("hello.html"), "hi {{ name").unwrap();
+        assert!(templates.reload().is_err());
+        assert_eq!(templates.render("hello.html", &ctx).unwrap(), "hi ann");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
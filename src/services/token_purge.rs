@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use diesel::{QueryResult, SqliteConnection};
+
+use crate::config::Config;
+use crate::db::models::email_verification_token::EmailVerificationTokens;
+use crate::db::models::refresh_token::RefreshTokens;
+use crate::db::models::user_model::UserModel;
+use crate::db::write::write;
+use crate::services::lifecycle::Shutdown;
+use crate::services::scheduler::{Job, Schedule};
+use crate::state::DbPool;
+
+/// How many of each kind of token a purge deleted.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PurgedTokens {
+    pub refresh: usize,
+    pub verification: usize,
+    pub reset: usize,
+}
+
+impl PurgedTokens {
+    pub fn total(&self) -> usize {
+        self.refresh + self.verification + self.reset
+    }
+}
+
+/// Deletes refresh tokens, email verification links and password reset links past their
+/// expiry. Sessions kept in Redis expire on their own and aren't touched.
+pub fn purge_expired(conn: &mut SqliteConnection, now: NaiveDateTime) -> QueryResult<PurgedTokens> {
+    write(conn, |conn| {
+        Ok(PurgedTokens {
+            refresh: RefreshTokens::delete_expired(conn, now)?,
+            verification: EmailVerificationTokens::delete_expired(conn, now)?,
+            reset: UserModel::delete_expired_reset_tokens(conn, now)?,
+        })
+    })
+}
+
+/// Purges expired tokens every `TOKEN_PURGE_INTERVAL_SECONDS`.
+pub struct TokenPurger {
+    period: Duration,
+    pool: DbPool,
+}
+
+impl TokenPurger {
+    pub fn new(config: &Config, pool: DbPool) -> Self {
+        Self { period: Duration::from_secs(config.token_purge_interval_seconds().max(1)), pool }
+    }
+}
+
+#[async_trait]
+impl Job for TokenPurger {
+    fn name(&self) -> &'static str {
+        "token-purge"
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(self.period)
+    }
+
+    async fn run(&self, _shutdown: Shutdown) -> Result<(), String> {
+        let pool = self.pool.clone();
+        let purged = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| e.to_string())?;
+            purge_expired(&mut conn, chrono::Utc::now().naive_utc()).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("token purge task panicked: {}", e))??;
+
+        if purged.total() > 0 {
+            tracing::info!(
+                refresh = purged.refresh,
+                verification = purged.verification,
+                reset = purged.reset,
+                "Purged expired tokens"
+            );
+        }
+        Ok(())
+    }
+}
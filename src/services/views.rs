@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use diesel::SqliteConnection;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::Config;
+use crate::db::models::post::Posts;
+use crate::db::models::post_view::PostViews;
+use crate::db::models::user_model::UserModel;
+use crate::db::write::{write, WriteLock};
+use crate::errors::AuthError;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+use crate::state::DbPool;
+
+/// Post page views waiting to be written, by author name and slug. Pages are served from the
+/// cache without loading the post, so the post is only looked up when the views are written.
+type Pending = HashMap<(String, String), i32>;
+
+/// Counts post page views in memory and writes them out in batches, so a busy post doesn't
+/// cost a write per reader. Views are written every interval, as soon as a batch fills up,
+/// and on shutdown.
+pub struct ViewCounter {
+    period: Duration,
+    buffer: Arc<ViewBuffer>,
+    tasks: Tasks,
+}
+
+struct ViewBuffer {
+    pool: DbPool,
+    writes: WriteLock,
+    batch_size: i64,
+    /// The views and how many there are in all.
+    pending: Mutex<(Pending, i64)>,
+}
+
+impl ViewCounter {
+    pub fn new(config: &Config, pool: DbPool, writes: WriteLock) -> Self {
+        Self {
+            period: Duration::from_secs(config.view_flush_interval_seconds().max(1)),
+            buffer: Arc::new(ViewBuffer {
+                pool,
+                writes,
+                batch_size: config.view_flush_batch_size().max(1),
+                pending: Mutex::new((HashMap::new(), 0)),
+            }),
+            tasks: Tasks::new(),
+        }
+    }
+
+    /// Counts a view of `author`'s post at `slug`. Callers leave out bots and pages that
+    /// weren't found.
+    pub async fn record(&self, author: &str, slug: &str) {
+        let full = {
+            let mut pending = self.buffer.pending.lock().unwrap_or_else(|e| e.into_inner());
+            *pending.0.entry((author.to_string(), slug.to_string())).or_default() += 1;
+            pending.1 += 1;
+            pending.1 >= self.buffer.batch_size
+        };
+        if full {
+            self.buffer.flush().await;
+        }
+    }
+}
+
+impl ViewBuffer {
+    /// Writes out every pending view. Views that fail to write are dropped, as the counts
+    /// are only approximate.
+    async fn flush(&self) {
+        let (pending, count) = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        if count == 0 {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let turn = self.writes.acquire().await;
+        let result = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| e.to_string())?;
+            write_views(&mut conn, pending).map_err(|e| e.to_string())
+        })
+        .await;
+        drop(turn);
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::error!("Failed to write {} post view(s): {}", count, e),
+            Err(e) => tracing::error!("Post view flush panicked: {}", e),
+        }
+    }
+}
+
+/// One transaction for the whole batch, so it costs SQLite a single commit.
+fn write_views(conn: &mut SqliteConnection, pending: Pending) -> diesel::QueryResult<()> {
+    write(conn, |conn| {
+        let today = chrono::Utc::now().date_naive();
+        let mut authors: HashMap<String, Option<String>> = HashMap::new();
+
+        for ((author, slug), views) in pending {
+            let author_id = match authors.get(&author) {
+                Some(author_id) => author_id.clone(),
+                None => {
+                    let author_id = UserModel::by_name(conn, &author)?.map(|user| user.id);
+                    authors.insert(author, author_id.clone());
+                    author_id
+                }
+            };
+            let Some(author_id) = author_id else {
+                continue;
+            };
+            // The post may have been renamed or unpublished since it was read.
+            if let Some(post) = Posts::published_by_slug(conn, &author_id, &slug)? {
+                PostViews::add(conn, &post.id, today, views)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[async_trait]
+impl Service for ViewCounter {
+    fn name(&self) -> &'static str {
+        "view-counter"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let period = self.period;
+        let buffer = self.buffer.clone();
+
+        self.tasks.spawn(|mut shutdown| async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => buffer.flush().await,
+                    _ = shutdown.wait() => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Stops the timer and writes out what's still pending.
+    async fn stop(&self) {
+        self.tasks.stop().await;
+        self.buffer.flush().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
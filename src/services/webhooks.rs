@@ -0,0 +1,422 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::Config;
+use crate::db::models::comment::Comments;
+use crate::db::models::post::Posts;
+use crate::db::models::post_reaction::PostReactions;
+use crate::db::models::webhook::Webhooks;
+use crate::db::models::webhook_delivery::{WebhookDeliveries, WEBHOOK_DELIVERY_PENDING};
+use crate::errors::AuthError;
+use crate::services::http_client::HttpClient;
+use crate::services::job_locks::Leases;
+use crate::services::lifecycle::{Service, ServiceHealth, Tasks};
+use crate::state::DbPool;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const WEBHOOK_SECRET_PREFIX: &str = "whsec_";
+
+/// `sha256=` and the hex HMAC of `{timestamp}.{body}` under the webhook's secret.
+pub const SIGNATURE_HEADER: &str = "x-tsumi-signature";
+/// Unix seconds when the attempt was signed, so receivers can refuse stale replays.
+pub const TIMESTAMP_HEADER: &str = "x-tsumi-timestamp";
+pub const EVENT_HEADER: &str = "x-tsumi-event";
+/// The same on every retry of a delivery, for receivers that dedupe.
+pub const DELIVERY_HEADER: &str = "x-tsumi-delivery";
+
+const USER_AGENT: &str = concat!("tsumi-webhooks/", env!("CARGO_PKG_VERSION"));
+
+/// Deliveries attempted per dispatcher tick.
+const DISPATCH_BATCH: i64 = 50;
+
+/// The first retry waits this long, and each after it twice as long as the last.
+const FIRST_RETRY_SECONDS: i64 = 30;
+const MAX_RETRY_SECONDS: i64 = 6 * 60 * 60;
+
+pub fn generate_secret() -> String {
+    let bytes: [u8; 24] = rand::rng().random();
+    format!("{}{}", WEBHOOK_SECRET_PREFIX, URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Checks that `url` points at a public host, so a webhook can't be aimed at the server's own
+/// network. Every address the host resolves to must be public. Development skips the check,
+/// for receivers on the same machine.
+pub async fn check_destination(url: &str, allow_local: bool) -> Result<(), String> {
+    if allow_local {
+        return Ok(());
+    }
+
+    let url = url::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let addresses: Vec<IpAddr> = match url.host() {
+        Some(url::Host::Ipv4(ip)) => vec![ip.into()],
+        Some(url::Host::Ipv6(ip)) => vec![ip.into()],
+        Some(url::Host::Domain(domain)) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|e| format!("Couldn't resolve {}: {}", domain, e))?
+                .map(|address| address.ip())
+                .collect()
+        }
+        None => return Err("URL has no host".to_string()),
+    };
+
+    if addresses.is_empty() {
+        return Err("URL's host has no addresses".to_string());
+    }
+    if let Some(ip) = addresses.iter().find(|ip| !is_public(**ip)) {
+        return Err(format!("URL points at {}, which isn't a public address", ip));
+    }
+    Ok(())
+}
+
+/// Loopback, private, link-local, unique local and other non-routable addresses aren't public.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            // 100.64.0.0/10 is carrier-grade NAT, private in all but name.
+            let shared = a == 100 && (b & 0xc0) == 64;
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(ip.into()),
+            None => {
+                let first = ip.segments()[0];
+                let unique_local = (first & 0xfe00) == 0xfc00;
+                let link_local = (first & 0xffc0) == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// How long to wait before the next attempt, after `attempts` have failed.
+fn backoff(attempts: i32) -> chrono::Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    chrono::Duration::seconds(FIRST_RETRY_SECONDS.saturating_mul(1 << doublings).min(MAX_RETRY_SECONDS))
+}
+
+#[derive(Debug, Serialize)]
+struct Envelope<'a> {
+    id: String,
+    event: &'a str,
+    created_at: NaiveDateTime,
+    data: serde_json::Value,
+}
+
+/// Queues `event` for each of the user's webhooks subscribed to it, returning how many
+/// deliveries were queued. Each delivery carries the same event id.
+pub fn emit(conn: &mut SqliteConnection, user_id: &str, event: &str, data: serde_json::Value) -> QueryResult<usize> {
+    let webhooks: Vec<Webhooks> = Webhooks::for_user(conn, user_id)?
+        .into_iter()
+        .filter(|webhook| webhook.subscribes_to(event))
+        .collect();
+    if webhooks.is_empty() {
+        return Ok(0);
+    }
+
+    let now = Utc::now().naive_utc();
+    let envelope = Envelope { id: uuid::Uuid::new_v4().to_string(), event, created_at: now, data };
+    let payload = serde_json::to_string(&envelope).unwrap_or_else(|_| "{}".to_string());
+    let deliveries: Vec<WebhookDeliveries> = webhooks
+        .into_iter()
+        .map(|webhook| WebhookDeliveries {
+            id: uuid::Uuid::new_v4().to_string(),
+            webhook_id: webhook.id,
+            event: event.to_string(),
+            payload: payload.clone(),
+            status: WEBHOOK_DELIVERY_PENDING.to_string(),
+            attempts: 0,
+            next_attempt_at: now,
+            response_status: None,
+            last_error: None,
+            created_at: now,
+            delivered_at: None,
+        })
+        .collect();
+    WebhookDeliveries::create_many(conn, &deliveries)
+}
+
+pub fn post_data(post: &Posts) -> serde_json::Value {
+    json!({
+        "post": {
+            "id": post.id,
+            "author_id": post.user_id,
+            "title": post.title,
+            "slug": post.slug,
+            "description": post.description,
+            "status": post.status,
+            "published_at": post.published_at,
+            "updated_at": post.updated_at,
+        }
+    })
+}
+
+pub fn comment_data(comment: &Comments, author_name: &str) -> serde_json::Value {
+    json!({
+        "comment": {
+            "id": comment.id,
+            "post_id": comment.post_id,
+            "parent_id": comment.parent_id,
+            "author_id": comment.user_id,
+            "author_name": author_name,
+            "body": comment.body,
+            "created_at": comment.created_at,
+        }
+    })
+}
+
+pub fn reaction_data(reaction: &PostReactions, user_name: &str) -> serde_json::Value {
+    json!({
+        "reaction": {
+            "post_id": reaction.post_id,
+            "user_id": reaction.user_id,
+            "user_name": user_name,
+            "kind": reaction.kind,
+            "created_at": reaction.created_at,
+        }
+    })
+}
+
+/// How one attempt went.
+enum Attempt {
+    Delivered(i32),
+    Failed(Option<i32>, String),
+}
+
+async fn attempt(client: &HttpClient, timeout: Duration, allow_local: bool, webhook: &Webhooks, delivery: &WebhookDeliveries) -> Attempt {
+    // Checked again here, as the host may have been pointed somewhere else since it was registered.
+    if let Err(e) = check_destination(&webhook.url, allow_local).await {
+        return Attempt::Failed(None, e);
+    }
+
+    let timestamp = Utc::now().timestamp();
+    let result = client
+        .post(&webhook.url)
+        .timeout(timeout)
+        .header(http::header::USER_AGENT, USER_AGENT)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, sign(&webhook.secret, timestamp, &delivery.payload))
+        .header(TIMESTAMP_HEADER, timestamp)
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, &delivery.id)
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => Attempt::Delivered(response.status().as_u16() as i32),
+        Ok(response) => {
+            let status = response.status();
+            Attempt::Failed(Some(status.as_u16() as i32), format!("Endpoint answered {}", status))
+        }
+        Err(e) => Attempt::Failed(None, e.to_string()),
+    }
+}
+
+/// Posts queued deliveries to their webhooks, signed with each webhook's secret. Failed
+/// attempts are retried with exponential backoff until `WEBHOOK_MAX_ATTEMPTS` is reached,
+/// after which the delivery is marked failed. Redirects aren't followed.
+pub struct WebhookDispatcher {
+    period: Duration,
+    timeout: Duration,
+    max_attempts: i32,
+    allow_local: bool,
+    client: HttpClient,
+    pool: DbPool,
+    leases: Leases,
+    tasks: Tasks,
+}
+
+impl WebhookDispatcher {
+    pub fn new(config: &Config, pool: DbPool, client: HttpClient, leases: Leases) -> Self {
+        Self {
+            period: Duration::from_secs(config.webhook_dispatch_interval_seconds().max(1)),
+            timeout: Duration::from_secs(config.webhook_timeout_seconds().max(1)),
+            max_attempts: config.webhook_max_attempts(),
+            allow_local: config.is_development(),
+            client,
+            pool,
+            leases,
+            tasks: Tasks::new(),
+        }
+    }
+}
+
+/// Attempts the due deliveries, returning how many were attempted.
+async fn dispatch_due(
+    pool: &DbPool,
+    client: &HttpClient,
+    timeout: Duration,
+    allow_local: bool,
+    max_attempts: i32,
+) -> Result<usize, String> {
+    let load_pool = pool.clone();
+    let due = tokio::task::spawn_blocking(move || {
+        let mut conn = load_pool.get().map_err(|e| e.to_string())?;
+        let mut due = Vec::new();
+        for delivery in WebhookDeliveries::due(&mut conn, Utc::now().naive_utc(), DISPATCH_BATCH).map_err(|e| e.to_string())? {
+            // Deleting a webhook takes its deliveries with it, so this only misses on a race.
+            if let Some(webhook) = Webhooks::by_id(&mut conn, &delivery.webhook_id).map_err(|e| e.to_string())? {
+                due.push((webhook, delivery));
+            }
+        }
+        Ok::<_, String>(due)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    let mut outcomes = Vec::with_capacity(due.len());
+    for (webhook, delivery) in &due {
+        let outcome = attempt(client, timeout, allow_local, webhook, delivery).await;
+        if let Attempt::Failed(_, error) = &outcome {
+            tracing::warn!("Webhook delivery {} to {} failed: {}", delivery.id, webhook.id, error);
+        }
+        outcomes.push((delivery.id.clone(), delivery.attempts + 1, outcome));
+    }
+
+    let attempted = outcomes.len();
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| e.to_string())?;
+        let now = Utc::now().naive_utc();
+        for (id, attempts, outcome) in outcomes {
+            let result = match outcome {
+                Attempt::Delivered(status) => WebhookDeliveries::mark_delivered(&mut conn, &id, attempts, status, now),
+                Attempt::Failed(status, error) => {
+                    let retry_at = (attempts < max_attempts).then(|| now + backoff(attempts));
+                    WebhookDeliveries::mark_attempt_failed(&mut conn, &id, attempts, status, &error, retry_at)
+                }
+            };
+            if let Err(e) = result {
+                tracing::error!("Failed to record webhook delivery {}: {}", id, e);
+            }
+        }
+        Ok::<_, String>(attempted)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[async_trait]
+impl Service for WebhookDispatcher {
+    fn name(&self) -> &'static str {
+        "webhook-dispatcher"
+    }
+
+    async fn start(&self) -> Result<(), AuthError> {
+        let period = self.period;
+        let timeout = self.timeout;
+        let max_attempts = self.max_attempts;
+        let allow_local = self.allow_local;
+        let client = self.client.clone();
+        let pool = self.pool.clone();
+        let mut lease = self.leases.lease(self.name());
+
+        self.tasks.spawn(|mut shutdown| async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.wait() => break,
+                }
+                if !lease.hold().await {
+                    continue;
+                }
+
+                match dispatch_due(&pool, &client, timeout, allow_local, max_attempts).await {
+                    Ok(0) => {}
+                    Ok(attempted) => tracing::info!("Attempted {} webhook delivery(ies)", attempted),
+                    Err(e) => tracing::error!("Failed to dispatch webhooks: {}", e),
+                }
+            }
+
+            lease.release().await;
+        });
+
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.tasks.stop().await;
+    }
+
+    fn health(&self) -> ServiceHealth {
+        self.tasks.health()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        let signature = sign("whsec_test", 1_750_000_000, r#"{"event":"post.published"}"#);
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature, sign("whsec_test", 1_750_000_000, r#"{"event":"post.published"}"#));
+        assert_ne!(signature, sign("whsec_test", 1_750_000_001, r#"{"event":"post.published"}"#));
+        assert_ne!(signature, sign("whsec_other", 1_750_000_000, r#"{"event":"post.published"}"#));
+    }
+
+    #[tokio::test]
+    async fn destinations_must_be_public() {
+        for url in [
+            "https://127.0.0.1/hook",
+            "https://10.1.2.3/hook",
+            "https://192.168.0.10/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://[::1]/hook",
+            "https://[fd00::1]/hook",
+            "https://[fe80::1]/hook",
+            "https://[::ffff:127.0.0.1]/hook",
+            "https://localhost/hook",
+        ] {
+            assert!(check_destination(url, false).await.is_err(), "{} was allowed", url);
+        }
+        assert!(check_destination("https://93.184.215.14/hook", false).await.is_ok());
+        assert!(check_destination("https://[2606:4700::1111]/hook", false).await.is_ok());
+        assert!(check_destination("http://localhost:9000/hook", true).await.is_ok());
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_cap() {
+        assert_eq!(backoff(1), chrono::Duration::seconds(30));
+        assert_eq!(backoff(2), chrono::Duration::seconds(60));
+        assert_eq!(backoff(5), chrono::Duration::seconds(480));
+        assert_eq!(backoff(30), chrono::Duration::seconds(MAX_RETRY_SECONDS));
+    }
+}
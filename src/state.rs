@@ -1,12 +1,60 @@
+use std::sync::Arc;
+
 use diesel::r2d2::{ConnectionManager, Pool};
 use diesel::SqliteConnection;
-use tera::Tera;
-use crate::config::Config;
+use crate::config::ConfigHandle;
+use crate::db::write::WriteLock;
+use crate::services::captcha::CaptchaVerifier;
+use crate::http::assets::AssetManifest;
+use crate::services::avatars::AvatarProxy;
+use crate::services::backups::BackupHandle;
+use crate::services::cache::Cache;
+use crate::services::collab::CollabHub;
+use crate::services::email_queue::EmailQueue;
+use crate::services::http_client::HttpClient;
+use crate::services::jwt::JwtService;
+use crate::services::lifecycle::ServiceRegistry;
+use crate::services::links::LinkRules;
+use crate::services::live::LiveHub;
+use crate::services::push::PushService;
+use crate::services::retention::RetentionHandle;
+use crate::services::scheduler::Scheduler;
+use crate::services::sessions::SessionStore;
+use crate::services::sitemap::SitemapCache;
+use crate::services::spam::SpamChecker;
+use crate::services::storage::Storage;
+use crate::services::templates::Templates;
+use crate::services::views::ViewCounter;
 
-type DbPool = Pool<ConnectionManager<SqliteConnection>>;
+pub type DbPool = Pool<ConnectionManager<SqliteConnection>>;
 #[derive(Clone)]
 pub struct AppState {
-    pub tera: Tera,
+    pub templates: Templates,
     pub db_pool: DbPool,
-    pub config: &'static Config
+    pub writes: WriteLock,
+    pub config: ConfigHandle,
+    pub jwt: Arc<JwtService>,
+    pub email_queue: EmailQueue,
+    pub link_rules: Arc<LinkRules>,
+    /// Shared by the outbound integrations: OAuth, webhooks, captcha and spam checks.
+    pub http: HttpClient,
+    pub storage: Arc<dyn Storage>,
+    pub cache: Arc<dyn Cache>,
+    pub avatars: Arc<AvatarProxy>,
+    /// Set when a captcha provider is configured.
+    pub captcha: Option<Arc<CaptchaVerifier>>,
+    /// Set when a spam check backend is configured.
+    pub spam: Option<Arc<dyn SpamChecker>>,
+    pub sessions: Arc<dyn SessionStore>,
+    pub collab: Arc<CollabHub>,
+    pub live: Arc<LiveHub>,
+    pub push: PushService,
+    pub retention: Arc<RetentionHandle>,
+    pub backups: Arc<BackupHandle>,
+    /// Runs the scheduled jobs: publishing, digests, token purging, retention and backups.
+    pub scheduler: Arc<Scheduler>,
+    pub views: Arc<ViewCounter>,
+    pub sitemaps: Arc<SitemapCache>,
+    pub services: Arc<ServiceRegistry>,
+    pub assets: Arc<AssetManifest>,
 }
@@ -27,22 +27,57 @@ pub async fn create_jwt(user_id: &str, state: &AppState) -> Result<String, Box<d
         iss: "tsumi".to_string(),
     };
 
-    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(state.config
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(state.config.load()
                                                                          .access_token_secret()
         .as_bytes()))?;
     Ok(token)
 }
 
-fn generate_csrf_token() -> String {
+/// The token double-submitted with state-changing requests; see `http::csrf`.
+pub fn generate_csrf_token() -> String {
+    generate_token()
+}
+
+/// A random, url-safe token suitable for emailed links.
+pub fn generate_token() -> String {
     use rand::Rng;
     let mut rng = rand::rng();
     let bytes: [u8; 32] = rng.random();
-    BASE64_URL_SAFE_NO_PAD.encode(&bytes)
+    BASE64_URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Lowercase, hyphen-separated ascii slug, e.g. "Hello, World!" -> "hello-world".
+pub fn slugify(input: &str) -> String {
+    let mut slug = String::with_capacity(input.len());
+    for c in input.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Escapes `%`, `_` and `\` so user input can be embedded in a `LIKE ... ESCAPE '\'` pattern.
+pub fn escape_like(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
 }
 
 
 pub fn get_db_conn(
     state: &AppState
 ) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, Box<dyn Error>> {
-    state.db_pool.get().map_err(|e| Box::<dyn Error>::from(e))
+    state.db_pool.get().map_err(Box::<dyn Error>::from)
 }
\ No newline at end of file
@@ -0,0 +1,118 @@
+mod common;
+
+use std::io::Cursor;
+
+use diesel::prelude::*;
+use http::{Method, StatusCode};
+use serde_json::json;
+use tsumi::db::schema::{reset_tokens, users};
+
+use common::TestApp;
+
+async fn admin_app(overrides: &[(&str, &str)]) -> (TestApp, String) {
+    let app = TestApp::with_settings(overrides).await;
+    let admin_id = app.sign_in_as("ann", "ann@example.com").await;
+    diesel::update(users::table.find(&admin_id))
+        .set(users::is_admin.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+    (app, admin_id)
+}
+
+fn files_in(dir: &std::path::Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else { return 0 };
+    entries
+        .flatten()
+        .map(|entry| if entry.path().is_dir() { files_in(&entry.path()) } else { 1 })
+        .sum()
+}
+
+#[tokio::test]
+async fn suspended_users_are_locked_out_until_unsuspended() {
+    let (app, _) = admin_app(&[]).await;
+    let bob_id = app.sign_in_as("bob", "bob@example.com").await;
+    let bob_token = app.cookie("access_token").unwrap();
+
+    app.sign_in("ann@example.com").await;
+    assert_eq!(app.post("/api/v1/admin/users/missing/suspend", json!({})).await.status, StatusCode::NOT_FOUND);
+    let suspended = app.post(&format!("/api/v1/admin/users/{}/suspend", bob_id), json!({})).await;
+    assert_eq!(suspended.status, StatusCode::OK, "{}", suspended.body);
+    assert_eq!(suspended.data()["status"], "suspended");
+
+    let listed = app.get("/api/v1/admin/users?status=suspended").await;
+    assert_eq!(listed.status, StatusCode::OK, "{}", listed.body);
+    let ids: Vec<_> = listed.data()["items"].as_array().unwrap().iter().map(|user| user["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(bob_id)]);
+
+    // Tokens issued before the suspension stop working, and signing in again is refused.
+    let me = app.get_with_headers("/api/v1/me", &[("authorization", &format!("Bearer {}", bob_token))]).await;
+    assert_eq!(me.status, StatusCode::FORBIDDEN, "{}", me.body);
+    let signin = app.post("/api/v1/auth/signin", json!({ "email": "bob@example.com", "password": "correct horse battery" })).await;
+    assert_eq!(signin.status, StatusCode::FORBIDDEN, "{}", signin.body);
+
+    let unsuspended = app.post(&format!("/api/v1/admin/users/{}/unsuspend", bob_id), json!({})).await;
+    assert_eq!(unsuspended.status, StatusCode::OK, "{}", unsuspended.body);
+    assert_eq!(unsuspended.data()["status"], "active");
+    app.sign_in("bob@example.com").await;
+    assert_eq!(app.get("/api/v1/me").await.data()["username"], "bob");
+}
+
+#[tokio::test]
+async fn admins_verify_emails_and_send_reset_links() {
+    let (app, admin_id) = admin_app(&[]).await;
+    let bob_id = app.sign_in_as("bob", "bob@example.com").await;
+    diesel::update(users::table.find(&bob_id))
+        .set(users::email_verified.eq(false))
+        .execute(&mut app.conn())
+        .unwrap();
+
+    app.sign_in("ann@example.com").await;
+    let own = app.post(&format!("/api/v1/admin/users/{}/suspend", admin_id), json!({})).await;
+    assert_eq!(own.status, StatusCode::FORBIDDEN);
+
+    let verified = app.post(&format!("/api/v1/admin/users/{}/verify-email", bob_id), json!({})).await;
+    assert_eq!(verified.status, StatusCode::OK, "{}", verified.body);
+    assert_eq!(verified.data()["email_verified"], true);
+
+    let reset = app.post(&format!("/api/v1/admin/users/{}/password-reset", bob_id), json!({})).await;
+    assert_eq!(reset.status, StatusCode::OK, "{}", reset.body);
+    assert_eq!(reset.data()["sent"], true);
+    let tokens: i64 = reset_tokens::table
+        .filter(reset_tokens::user_id.eq(&bob_id))
+        .count()
+        .get_result(&mut app.conn())
+        .unwrap();
+    assert_eq!(tokens, 1);
+}
+
+#[tokio::test]
+async fn purging_a_user_deletes_their_files() {
+    let uploads = std::env::temp_dir().join(format!("tsumi-purge-{}", uuid::Uuid::new_v4()));
+    let (app, _) = admin_app(&[("UPLOADS_DIR", uploads.to_str().unwrap())]).await;
+    let bob_id = app.sign_in_as("bob", "bob@example.com").await;
+
+    let mut png = Vec::new();
+    image::DynamicImage::new_rgb8(40, 40).write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+    let avatar = app.send_file(Method::PUT, "/api/v1/me/avatar", "image/png", &png).await;
+    assert_eq!(avatar.status, StatusCode::OK, "{}", avatar.body);
+    let mut body = b"--b\r\ncontent-disposition: form-data; name=\"file\"; filename=\"a.png\"\r\ncontent-type: image/png\r\n\r\n".to_vec();
+    body.extend_from_slice(&png);
+    body.extend_from_slice(b"\r\n--b--\r\n");
+    let upload = app.send_raw(Method::POST, "/api/v1/uploads", "multipart/form-data; boundary=b", &body).await;
+    assert_eq!(upload.status, StatusCode::OK, "{}", upload.body);
+    let post = app.post("/api/v1/posts", json!({ "title": "Hello", "content": "x" })).await;
+    assert_eq!(post.status, StatusCode::OK, "{}", post.body);
+    let avatar_url = avatar.data()["avatar_url"].as_str().unwrap().to_string();
+
+    app.sign_in("ann@example.com").await;
+    let purged = app.send(Method::DELETE, &format!("/api/v1/admin/users/{}", bob_id), None).await;
+    assert_eq!(purged.status, StatusCode::OK, "{}", purged.body);
+    assert_eq!(purged.data()["files_deleted"], 1);
+
+    assert_eq!(app.get(&avatar_url).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(files_in(&uploads), 0, "files left behind in {}", uploads.display());
+    let again = app.send(Method::DELETE, &format!("/api/v1/admin/users/{}", bob_id), None).await;
+    assert_eq!(again.status, StatusCode::NOT_FOUND);
+
+    std::fs::remove_dir_all(&uploads).ok();
+}
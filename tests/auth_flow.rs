@@ -0,0 +1,355 @@
+mod common;
+
+use diesel::prelude::*;
+use http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+use tsumi::db::schema::{audit_logs, email_verification_tokens, refresh_tokens};
+
+const EMAIL: &str = "ann@example.com";
+const PASSWORD: &str = "correct horse battery";
+
+fn verification_token(app: &TestApp) -> String {
+    email_verification_tokens::table
+        .select(email_verification_tokens::token)
+        .first(&mut app.conn())
+        .expect("signup issues a verification token")
+}
+
+async fn sign_in(app: &TestApp) -> common::TestResponse {
+    app.post("/api/v1/auth/signin", json!({ "email": EMAIL, "password": PASSWORD })).await
+}
+
+#[tokio::test]
+async fn signup_verify_signin_refresh_signout() {
+    let app = TestApp::new().await;
+
+    let signup = app.post("/api/v1/auth/signup", json!({ "name": "ann", "email": EMAIL, "password": PASSWORD })).await;
+    assert_eq!(signup.status, StatusCode::OK, "{}", signup.body);
+    assert_eq!(signup.data()["username"], "ann");
+    assert_eq!(signup.data()["email_verified"], false);
+    assert!(signup.data().get("password").is_none());
+
+    let unverified = sign_in(&app).await;
+    assert_eq!(unverified.status, StatusCode::UNAUTHORIZED);
+
+    let verify = app.get(&format!("/api/v1/auth/verify-email?token={}", verification_token(&app))).await;
+    assert_eq!(verify.status, StatusCode::OK, "{}", verify.body);
+
+    let signin = sign_in(&app).await;
+    assert_eq!(signin.status, StatusCode::OK, "{}", signin.body);
+    assert_eq!(signin.data()["user"]["email_verified"], true);
+    assert!(app.cookie("refresh_token").is_some());
+    assert!(app.cookie("access_token").is_some());
+
+    let refresh = app.send(Method::POST, "/api/v1/auth/refresh", None).await;
+    assert_eq!(refresh.status, StatusCode::OK, "{}", refresh.body);
+    assert!(refresh.data()["access_token"].as_str().is_some_and(|token| !token.is_empty()));
+    assert!(app.cookie("refresh_token").is_some());
+
+    let signout = app.send(Method::POST, "/api/v1/auth/signout", None).await;
+    assert_eq!(signout.status, StatusCode::OK, "{}", signout.body);
+    assert!(app.cookie("refresh_token").is_none());
+
+    let after_signout = app.send(Method::POST, "/api/v1/auth/refresh", None).await;
+    assert_eq!(after_signout.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn duplicate_signups_and_bad_passwords_are_rejected() {
+    let app = TestApp::new().await;
+    let signup = json!({ "name": "ann", "email": EMAIL, "password": PASSWORD });
+    assert_eq!(app.post("/api/v1/auth/signup", signup).await.status, StatusCode::OK);
+
+    let duplicate = app.post("/api/v1/auth/signup", json!({ "name": "bob", "email": "ANN@example.com", "password": PASSWORD })).await;
+    assert_eq!(duplicate.status, StatusCode::CONFLICT);
+    assert_eq!(duplicate.error_code(), "CONFLICT");
+
+    let invalid = app.post("/api/v1/auth/signup", json!({ "name": "bo", "email": "bob@example.com", "password": "short" })).await;
+    assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+    let fields: Vec<&str> = invalid.body["error"]["details"]["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["name", "password"]);
+
+    app.get(&format!("/api/v1/auth/verify-email?token={}", verification_token(&app))).await;
+    let wrong = app.post("/api/v1/auth/signin", json!({ "email": EMAIL, "password": "not the password" })).await;
+    assert_eq!(wrong.status, StatusCode::UNAUTHORIZED);
+    assert!(app.cookie("refresh_token").is_none());
+}
+
+async fn signed_up_and_verified(app: &TestApp) {
+    app.post("/api/v1/auth/signup", json!({ "name": "ann", "email": EMAIL, "password": PASSWORD })).await;
+    app.get(&format!("/api/v1/auth/verify-email?token={}", verification_token(app))).await;
+}
+
+#[tokio::test]
+async fn apps_with_different_configs_run_side_by_side() {
+    let first = TestApp::new().await;
+    let second = TestApp::with_settings(&[("ACCESS_SECRET", "another-access-secret")]).await;
+    signed_up_and_verified(&first).await;
+    signed_up_and_verified(&second).await;
+
+    assert_eq!(sign_in(&first).await.status, StatusCode::OK);
+    let token = first.cookie("access_token").unwrap();
+    assert_eq!(first.get("/api/v1/me/preferences").await.status, StatusCode::OK);
+
+    second.set_cookie("access_token", &token);
+    let rejected = second.get("/api/v1/me/preferences").await;
+    assert_eq!(rejected.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(rejected.body["error"]["message"], "Unauthorized: Invalid token signature");
+}
+
+#[tokio::test]
+async fn reusing_a_rotated_refresh_token_revokes_the_whole_session() {
+    let app = TestApp::new().await;
+    let user_id = app.sign_in_as("ann", EMAIL).await;
+    let first = app.cookie("refresh_token").unwrap();
+
+    assert_eq!(app.send(Method::POST, "/api/v1/auth/refresh", None).await.status, StatusCode::OK);
+    let second = app.cookie("refresh_token").unwrap();
+    assert_ne!(first, second);
+
+    app.set_cookie("refresh_token", &first);
+    let reused = app.send(Method::POST, "/api/v1/auth/refresh", None).await;
+    assert_eq!(reused.status, StatusCode::UNAUTHORIZED);
+    assert!(app.cookie("refresh_token").is_none());
+
+    app.set_cookie("refresh_token", &second);
+    let revoked = app.send(Method::POST, "/api/v1/auth/refresh", None).await;
+    assert_eq!(revoked.status, StatusCode::UNAUTHORIZED);
+
+    let flagged: i64 = audit_logs::table
+        .filter(audit_logs::event.eq("auth.refresh_token_reused"))
+        .filter(audit_logs::user_id.eq(&user_id))
+        .count()
+        .get_result(&mut app.conn())
+        .unwrap();
+    assert_eq!(flagged, 1);
+}
+
+#[tokio::test]
+async fn signing_in_from_a_new_device_is_recorded_and_flagged_once() {
+    let app = TestApp::new().await;
+    let user_id = app.sign_in_as("ann", EMAIL).await;
+    let new_device_events = || -> i64 {
+        audit_logs::table
+            .filter(audit_logs::event.eq("auth.new_device_sign_in"))
+            .filter(audit_logs::user_id.eq(&user_id))
+            .count()
+            .get_result(&mut app.conn())
+            .unwrap()
+    };
+    // The first device an account uses is only remembered.
+    assert_eq!(new_device_events(), 0);
+
+    app.set_user_agent("Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0");
+    assert_eq!(sign_in(&app).await.status, StatusCode::OK);
+    assert_eq!(sign_in(&app).await.status, StatusCode::OK);
+    assert_eq!(new_device_events(), 1);
+
+    let sessions = app.get("/api/v1/me/sessions").await;
+    assert_eq!(sessions.status, StatusCode::OK, "{}", sessions.body);
+    let session = &sessions.data()["items"][0];
+    assert_eq!(session["device"], json!("Firefox on Linux"));
+    assert_eq!(session["current"], json!(true));
+}
+
+/// The `Max-Age` of the refresh cookie a response sets, `None` for a browser-session cookie.
+fn refresh_cookie_max_age(response: &common::TestResponse) -> Option<i64> {
+    response.headers.get_all(http::header::SET_COOKIE)
+        .iter()
+        .filter_map(|header| cookie::Cookie::parse(header.to_str().ok()?.to_string()).ok())
+        .find(|cookie| cookie.name() == "refresh_token" && !cookie.value().is_empty())
+        .expect("a refresh cookie is set")
+        .max_age()
+        .map(|age| age.whole_seconds())
+}
+
+#[tokio::test]
+async fn remember_me_decides_how_long_the_session_lasts() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", EMAIL).await;
+    let expires_in_hours = || -> i64 {
+        let expires_at: chrono::NaiveDateTime = refresh_tokens::table
+            .filter(refresh_tokens::replaced_by.is_null())
+            .select(refresh_tokens::expires_at)
+            .first(&mut app.conn())
+            .unwrap();
+        (expires_at - chrono::Utc::now().naive_utc()).num_hours()
+    };
+
+    let short = app.post("/api/v1/auth/signin", json!({ "email": EMAIL, "password": PASSWORD })).await;
+    assert_eq!(refresh_cookie_max_age(&short), None);
+    assert!(expires_in_hours() < 12);
+    // Refreshing keeps the session as short as it started.
+    let refreshed = app.send(Method::POST, "/api/v1/auth/refresh", None).await;
+    assert_eq!(refresh_cookie_max_age(&refreshed), None);
+    assert!(expires_in_hours() < 12);
+
+    let remembered = app.post("/api/v1/auth/signin", json!({ "email": EMAIL, "password": PASSWORD, "remember_me": true })).await;
+    assert_eq!(refresh_cookie_max_age(&remembered), Some(24 * 24 * 60 * 60));
+    assert!(expires_in_hours() > 24 * 23);
+    let refreshed = app.send(Method::POST, "/api/v1/auth/refresh", None).await;
+    assert_eq!(refresh_cookie_max_age(&refreshed), Some(24 * 24 * 60 * 60));
+}
+
+#[tokio::test]
+async fn signing_out_everywhere_revokes_sessions_and_access_tokens() {
+    let app = TestApp::new().await;
+    let user_id = app.sign_in_as("ann", EMAIL).await;
+    let laptop_access = app.cookie("access_token").unwrap();
+    let laptop_refresh = app.cookie("refresh_token").unwrap();
+    // A second browser, which doesn't send the first one's cookies.
+    app.set_cookie("refresh_token", "");
+    assert_eq!(sign_in(&app).await.status, StatusCode::OK);
+
+    let signed_out = app.send(Method::POST, "/api/v1/auth/signout-all", None).await;
+    assert_eq!(signed_out.status, StatusCode::OK, "{}", signed_out.body);
+    assert_eq!(signed_out.data()["sessions_revoked"], json!(2));
+    assert!(app.cookie("access_token").is_none());
+    assert!(app.cookie("refresh_token").is_none());
+
+    app.set_cookie("access_token", &laptop_access);
+    let me = app.get("/api/v1/me/sessions").await;
+    assert_eq!(me.status, StatusCode::UNAUTHORIZED);
+    app.set_cookie("refresh_token", &laptop_refresh);
+    assert_eq!(app.send(Method::POST, "/api/v1/auth/refresh", None).await.status, StatusCode::UNAUTHORIZED);
+
+    // Signing in again issues tokens at the new version.
+    assert_eq!(sign_in(&app).await.status, StatusCode::OK);
+    assert_eq!(app.get("/api/v1/me/sessions").await.status, StatusCode::OK);
+
+    let recorded: i64 = audit_logs::table
+        .filter(audit_logs::event.eq("auth.sign_out_all"))
+        .filter(audit_logs::user_id.eq(&user_id))
+        .count()
+        .get_result(&mut app.conn())
+        .unwrap();
+    assert_eq!(recorded, 1);
+}
+
+#[tokio::test]
+async fn sudo_tokens_are_not_accepted_as_access_tokens() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", EMAIL).await;
+    let reauth = app.post("/api/v1/auth/reauth", json!({ "password": PASSWORD })).await;
+    assert_eq!(reauth.status, StatusCode::OK, "{}", reauth.body);
+    let sudo = reauth.data()["sudo_token"].as_str().unwrap().to_string();
+
+    app.clear_cookies();
+    let me = app.get_with_headers("/api/v1/me/sessions", &[("authorization", &format!("Bearer {}", sudo))]).await;
+    assert_eq!(me.status, StatusCode::UNAUTHORIZED, "{}", me.body);
+}
+
+#[tokio::test]
+async fn cookie_authenticated_mutations_need_the_csrf_token() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", EMAIL).await;
+
+    let forged = app.send_without_csrf(Method::POST, "/api/v1/auth/signout", None).await;
+    assert_eq!(forged.status, StatusCode::FORBIDDEN, "{}", forged.body);
+    assert!(app.cookie("refresh_token").is_some());
+
+    // The token stays the same for as long as the cookie does.
+    let issued = app.get("/api/v1/auth/csrf").await;
+    assert_eq!(issued.status, StatusCode::OK, "{}", issued.body);
+    assert_eq!(issued.data()["csrf_token"].as_str(), app.cookie("csrf_token").as_deref());
+    assert_eq!(issued.data()["header"], "x-csrf-token");
+
+    // Without our cookies there's nothing for another site to ride on.
+    let fresh = TestApp::new().await;
+    let signup = fresh
+        .send_without_csrf(Method::POST, "/api/v1/auth/signup", Some(json!({ "name": "bob", "email": "bob@example.com", "password": PASSWORD })))
+        .await;
+    assert_eq!(signup.status, StatusCode::OK, "{}", signup.body);
+
+    let signout = app.send(Method::POST, "/api/v1/auth/signout", None).await;
+    assert_eq!(signout.status, StatusCode::OK, "{}", signout.body);
+}
+
+#[tokio::test]
+async fn signing_in_returns_to_next_only_when_it_stays_on_the_site() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", EMAIL).await;
+    let sign_in_to = |next: &'static str| app.post("/api/v1/auth/signin", json!({ "email": EMAIL, "password": PASSWORD, "next": next }));
+
+    assert_eq!(sign_in(&app).await.data()["redirect_to"], "/");
+    assert_eq!(sign_in_to("/posts?page=2").await.data()["redirect_to"], "/posts?page=2");
+    assert_eq!(sign_in_to("https://evil.test/").await.data()["redirect_to"], "/");
+    assert_eq!(sign_in_to("//evil.test").await.data()["redirect_to"], "/");
+}
+
+#[tokio::test]
+async fn github_callbacks_need_the_state_their_sign_in_started_with() {
+    let app = TestApp::new().await;
+
+    let start = app.get("/auth/github?next=/posts").await;
+    let location = start.headers[http::header::LOCATION].to_str().unwrap().to_owned();
+    let state = location.split("state=").nth(1).expect("the authorize URL carries a state");
+    let cookie = app.cookie("oauth_state").expect("the state is kept in a cookie");
+    assert!(cookie.starts_with(&format!("{}.", state)));
+
+    // A forged callback is turned away before the code is exchanged with GitHub.
+    let forged = app.get("/auth/github/callback?code=abc&state=forged").await;
+    assert_eq!(forged.headers[http::header::LOCATION], "/login");
+    assert!(app.cookie("oauth_state").is_none());
+}
+
+#[tokio::test]
+async fn html_forms_redirect_on_success_and_show_errors_inline() {
+    let app = TestApp::new().await;
+
+    let invalid = app.post_form("/register", &[("name", "ann"), ("email", "not-an-email"), ("password", "short")]).await;
+    assert_eq!(invalid.status, StatusCode::BAD_REQUEST, "{}", invalid.text);
+    assert!(invalid.text.contains("Email must be a valid email."), "{}", invalid.text);
+    assert!(invalid.text.contains("Password must be between 8 and 128 characters"));
+    assert!(invalid.text.contains(r#"value="not-an-email""#));
+
+    let registered = app.post_form("/register", &[("name", "ann"), ("email", EMAIL), ("password", PASSWORD)]).await;
+    assert_eq!(registered.status, StatusCode::SEE_OTHER, "{}", registered.text);
+    assert_eq!(registered.headers[http::header::LOCATION], "/login");
+    let login = app.get("/login").await;
+    assert!(login.text.contains("Check your inbox"), "{}", login.text);
+    // Flash messages are shown once.
+    assert!(!app.get("/login").await.text.contains("Check your inbox"));
+    app.get(&format!("/api/v1/auth/verify-email?token={}", verification_token(&app))).await;
+
+    let wrong = app.post_form("/login", &[("email", EMAIL), ("password", "wrong password"), ("next", "/posts")]).await;
+    assert_eq!(wrong.status, StatusCode::UNAUTHORIZED);
+    assert!(wrong.text.contains("Invalid email or password"), "{}", wrong.text);
+    assert!(wrong.text.contains(r#"name="next" value="&#x2F;posts""#), "{}", wrong.text);
+    assert!(app.cookie("access_token").is_none());
+
+    let signed_in = app.post_form("/login", &[("email", EMAIL), ("password", PASSWORD), ("next", "/posts")]).await;
+    assert_eq!(signed_in.status, StatusCode::SEE_OTHER, "{}", signed_in.text);
+    assert_eq!(signed_in.headers[http::header::LOCATION], "/posts");
+    assert!(app.cookie("access_token").is_some());
+    assert!(app.get("/").await.text.contains("signed in as ann"));
+}
+
+#[tokio::test]
+async fn pages_speak_the_users_language_then_the_browsers() {
+    let app = TestApp::new().await;
+    app.set_accept_language("fr-CA,fr;q=0.9,en;q=0.5");
+
+    let login = app.get("/login").await;
+    assert!(login.text.contains("<h1>Connexion</h1>"), "{}", login.text);
+    let invalid = app.post_form("/register", &[("name", "ann"), ("email", "not-an-email"), ("password", PASSWORD)]).await;
+    assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+    assert!(!invalid.text.contains("Email must be a valid email."), "{}", invalid.text);
+
+    app.sign_in_as("ann", EMAIL).await;
+    assert!(app.get("/").await.text.contains("connecté en tant que ann"));
+
+    let unsupported = app.send(Method::PATCH, "/api/v1/me/preferences", Some(json!({ "locale": "ja" }))).await;
+    assert_eq!(unsupported.status, StatusCode::BAD_REQUEST);
+    let chosen = app.send(Method::PATCH, "/api/v1/me/preferences", Some(json!({ "locale": "de" }))).await;
+    assert_eq!(chosen.status, StatusCode::OK, "{}", chosen.body);
+    assert_eq!(chosen.data()["locale"], "de");
+    assert!(app.get("/").await.text.contains("angemeldet als ann"));
+}
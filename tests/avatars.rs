@@ -0,0 +1,48 @@
+mod common;
+
+use std::io::Cursor;
+
+use http::{Method, StatusCode};
+
+use common::TestApp;
+
+fn image(format: image::ImageFormat) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image::DynamicImage::new_rgb8(120, 80).write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn uploaded_avatars_are_resized_and_replace_the_fallback() {
+    let uploads = std::env::temp_dir().join(format!("tsumi-avatars-{}", uuid::Uuid::new_v4()));
+    let app = TestApp::with_settings(&[("UPLOADS_DIR", uploads.to_str().unwrap())]).await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let gif = app.send_file(Method::PUT, "/api/v1/me/avatar", "image/gif", b"GIF89a").await;
+    assert_eq!(gif.status, StatusCode::UNSUPPORTED_MEDIA_TYPE, "{}", gif.body);
+    let garbled = app.send_file(Method::PUT, "/api/v1/me/avatar", "image/png", b"\x89PNG\r\n\x1a\nnope").await;
+    assert_eq!(garbled.status, StatusCode::BAD_REQUEST, "{}", garbled.body);
+
+    let uploaded = app.send_file(Method::PUT, "/api/v1/me/avatar", "image/jpeg", &image(image::ImageFormat::Jpeg)).await;
+    assert_eq!(uploaded.status, StatusCode::OK, "{}", uploaded.body);
+    assert_eq!(uploaded.data()["uploaded"], true);
+    let avatar_url = uploaded.data()["avatar_url"].as_str().unwrap().to_string();
+
+    let served = app.get(&format!("{}?s=40", avatar_url)).await;
+    assert_eq!(served.status, StatusCode::OK);
+    assert_eq!(served.headers[http::header::CONTENT_TYPE], "image/png");
+    let decoded = image::load_from_memory(&served.bytes).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (64, 64));
+
+    let replaced = app.send_file(Method::PUT, "/api/v1/me/avatar", "image/png", &image(image::ImageFormat::Png)).await;
+    assert_eq!(replaced.status, StatusCode::OK, "{}", replaced.body);
+    assert_ne!(replaced.data()["avatar_url"], avatar_url.as_str());
+    assert_eq!(app.get(&avatar_url).await.status, StatusCode::NOT_FOUND);
+
+    let removed = app.send(Method::DELETE, "/api/v1/me/avatar", None).await;
+    assert_eq!(removed.status, StatusCode::OK, "{}", removed.body);
+    assert_eq!(removed.data()["uploaded"], false);
+    assert_ne!(removed.data()["avatar_url"], replaced.data()["avatar_url"]);
+
+    std::fs::remove_dir_all(&uploads).ok();
+}
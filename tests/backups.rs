@@ -0,0 +1,65 @@
+mod common;
+
+use std::sync::Arc;
+
+use http::StatusCode;
+use diesel::prelude::*;
+use tsumi::db::schema::users;
+use tsumi::services::backups::Backups;
+use tsumi::services::storage::DiskStorage;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn backups_snapshot_the_database_and_keep_the_newest() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let dir = std::env::temp_dir().join(format!("tsumi-backups-{}", uuid::Uuid::new_v4()));
+    let backups = Backups::new(Arc::new(DiskStorage::new(&dir)), 2);
+    let mut names = Vec::new();
+    for _ in 0..3 {
+        names.push(backups.create(&app.pool).await.unwrap().name);
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    let kept: Vec<_> = backups.list().await.unwrap().into_iter().map(|backup| backup.name).collect();
+    assert_eq!(kept, vec![names[2].clone(), names[1].clone()]);
+    assert!(!dir.join(&names[0]).exists());
+    assert!(backups.fetch(&names[0]).await.unwrap().is_none());
+
+    // The snapshot is a database of its own, with everything written before it.
+    let snapshot = dir.join(&names[2]);
+    let mut conn = SqliteConnection::establish(&snapshot.to_string_lossy()).unwrap();
+    let count: i64 = users::table.count().get_result(&mut conn).unwrap();
+    assert_eq!(count, 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn backups_are_for_admins() {
+    let dir = std::env::temp_dir().join(format!("tsumi-backups-{}", uuid::Uuid::new_v4()));
+    let app = TestApp::with_settings(&[("BACKUP_DIR", &dir.to_string_lossy())]).await;
+    let id = app.sign_in_as("ann", "ann@example.com").await;
+    diesel::update(users::table.find(&id))
+        .set(users::is_admin.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+
+    app.sign_in_as("bob", "bob@example.com").await;
+    let forbidden = app.get("/api/v1/admin/backups").await;
+    assert_eq!(forbidden.status, StatusCode::FORBIDDEN);
+
+    app.sign_in("ann@example.com").await;
+    let status = app.get("/api/v1/admin/backups").await;
+    assert_eq!(status.status, StatusCode::OK, "{}", status.body);
+    assert_eq!(status.data()["backups"], serde_json::json!([]));
+    assert_eq!(status.data()["interval_hours"], 24);
+    assert_eq!(status.data()["schedule"], "every 86400s");
+
+    let run = app.post("/api/v1/admin/backups/run", serde_json::json!({})).await;
+    assert_eq!(run.status, StatusCode::ACCEPTED, "{}", run.body);
+    let status = app.get("/api/v1/admin/backups").await;
+    assert!(status.data()["next_run_at"].is_string(), "{}", status.body);
+}
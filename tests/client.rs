@@ -0,0 +1,50 @@
+mod common;
+
+use diesel::prelude::*;
+use tsumi::db::schema::users;
+use tsumi_client::{Client, ClientError, CreatePostRequest, SignInRequest, SignUpRequest};
+
+use common::TestApp;
+
+#[tokio::test]
+async fn the_client_signs_in_and_round_trips_a_post() {
+    let app = TestApp::new().await;
+    let client = Client::new(&app.serve().await).unwrap();
+
+    let user = client
+        .sign_up(&SignUpRequest {
+            name: "ann".into(),
+            email: "ann@example.com".into(),
+            password: "correct horse battery".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    diesel::update(users::table.find(&user.id))
+        .set(users::email_verified.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+    client
+        .sign_in(&SignInRequest {
+            email: "ann@example.com".into(),
+            password: "correct horse battery".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let created = client
+        .create_post(&CreatePostRequest { title: "Hello".into(), content: "# Hello".into(), ..Default::default() })
+        .await
+        .unwrap();
+    let fetched = client.get_post(&created.id).await.unwrap();
+    assert_eq!((fetched.id.as_str(), fetched.title.as_str()), (created.id.as_str(), "Hello"));
+
+    // Ids are sent as one path segment, so they can't reach another endpoint.
+    for id in ["../me", "x/publish", "x?y=1"] {
+        match client.get_post(id).await {
+            Err(ClientError::Api { status, .. }) => assert_eq!(status, http::StatusCode::NOT_FOUND, "{}", id),
+            other => panic!("{} reached {:?}", id, other.map(|post| post.id)),
+        }
+    }
+}
@@ -0,0 +1,315 @@
+//! Shared setup for the integration tests: the real router over a private in-memory database,
+//! with a config that doesn't depend on the environment and a client that keeps cookies.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use axum::body::{to_bytes, Body};
+use axum::Router;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+use diesel_migrations::MigrationHarness;
+use http::header::{ACCEPT_LANGUAGE, CONTENT_TYPE, COOKIE, SET_COOKIE, USER_AGENT};
+use http::{HeaderMap, Method, Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use tsumi::config::Config;
+use tsumi::db::connection::SqliteCustomizer;
+use tsumi::db::schema::users;
+use tsumi::routes::app_router;
+use tsumi::state::DbPool;
+
+/// The password `sign_in_as` gives every user.
+const PASSWORD: &str = "correct horse battery";
+
+/// Settings every test app runs with. The database URL is unused; each app gets its own pool.
+const TEST_SETTINGS: &[(&str, &str)] = &[
+    ("DATABASE_URL", "unused.db"),
+    ("CORS_ORIGIN", "http://localhost:8000"),
+    ("ACCESS_SECRET", "test-access-secret"),
+    ("ACCESS_EXPIRES", "15"),
+    ("REFRESH_TOKEN", "test-refresh-secret"),
+    ("REFRESH_EXPIRES", "24"),
+    ("GITHUB_OAUTH_CLIENT_ID", "test-client"),
+    ("GITHUB_OAUTH_CLIENT_SECRET", "test-secret"),
+    ("APP_ENV", "test"),
+];
+
+/// The test settings with `overrides` applied on top.
+pub fn test_config(overrides: &[(&str, &str)]) -> Config {
+    let mut settings: Vec<(&str, &str)> = TEST_SETTINGS.to_vec();
+    settings.extend_from_slice(overrides);
+    Config::from_values(settings).expect("test config is valid")
+}
+
+/// A fresh in-memory database with every migration applied. The name keeps it private to one
+/// pool while letting the pool's connections share it.
+pub fn test_pool() -> DbPool {
+    let url = format!("file:tsumi-test-{}?mode=memory&cache=shared", uuid::Uuid::new_v4());
+    let pool = Pool::builder()
+        .max_size(4)
+        .connection_customizer(Box::new(SqliteCustomizer::default()))
+        .build(ConnectionManager::<SqliteConnection>::new(url))
+        .expect("failed to build test pool");
+    pool.get()
+        .expect("failed to get test connection")
+        .run_pending_migrations(tsumi::MIGRATIONS)
+        .expect("failed to run migrations");
+    pool
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+    /// The raw body, for responses that aren't JSON.
+    pub text: String,
+    pub bytes: Vec<u8>,
+}
+
+impl TestResponse {
+    /// The body's `data`, for successful API responses.
+    pub fn data(&self) -> &Value {
+        &self.body["data"]
+    }
+
+    /// The error code of a failed API response.
+    pub fn error_code(&self) -> &str {
+        self.body["error"]["code"].as_str().unwrap_or_default()
+    }
+}
+
+/// The app router backed by its own database, and a browser-like cookie jar.
+pub struct TestApp {
+    router: Router,
+    pub pool: DbPool,
+    cookies: Mutex<HashMap<String, String>>,
+    user_agent: Mutex<Option<String>>,
+    accept_language: Mutex<Option<String>>,
+}
+
+impl TestApp {
+    pub async fn new() -> Self {
+        Self::with_settings(&[]).await
+    }
+
+    /// An app whose config differs from the default test one by `overrides`.
+    pub async fn with_settings(overrides: &[(&str, &str)]) -> Self {
+        let config = test_config(overrides);
+        let pool = test_pool();
+        let state = tsumi::app::build_state(&config, pool.clone(), None);
+        Self {
+            router: app_router(state),
+            pool,
+            cookies: Mutex::new(HashMap::new()),
+            user_agent: Mutex::new(None),
+            accept_language: Mutex::new(None),
+        }
+    }
+
+    /// Serves the app on a local port for clients that speak real HTTP, returning its root URL.
+    /// The server runs until the test's runtime shuts down.
+    pub async fn serve(&self) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("failed to bind a test port");
+        let address = listener.local_addr().expect("listener has an address");
+        let app = self.router.clone().into_make_service_with_connect_info::<std::net::SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, app).await.expect("test server failed") });
+        format!("http://{}", address)
+    }
+
+    pub fn conn(&self) -> PooledConnection<ConnectionManager<SqliteConnection>> {
+        self.pool.get().expect("failed to get test connection")
+    }
+
+    pub fn cookie(&self, name: &str) -> Option<String> {
+        self.cookies.lock().unwrap().get(name).cloned()
+    }
+
+    /// Signs up a user with a verified email and signs them in, returning their id.
+    pub async fn sign_in_as(&self, name: &str, email: &str) -> String {
+        let password = PASSWORD;
+        let signup = self.post("/api/v1/auth/signup", serde_json::json!({ "name": name, "email": email, "password": password })).await;
+        assert_eq!(signup.status, StatusCode::OK, "{}", signup.body);
+        let id = signup.data()["id"].as_str().unwrap().to_string();
+        diesel::update(users::table.find(&id))
+            .set(users::email_verified.eq(true))
+            .execute(&mut self.conn())
+            .expect("failed to verify user");
+
+        self.sign_in(email).await;
+        id
+    }
+
+    /// Signs back in as a user `sign_in_as` created, replacing whoever is signed in.
+    pub async fn sign_in(&self, email: &str) {
+        let signin = self.post("/api/v1/auth/signin", serde_json::json!({ "email": email, "password": PASSWORD })).await;
+        assert_eq!(signin.status, StatusCode::OK, "{}", signin.body);
+    }
+
+    /// Forgets every cookie, as if the browser signed out.
+    pub fn clear_cookies(&self) {
+        self.cookies.lock().unwrap().clear();
+    }
+
+    pub fn set_cookie(&self, name: &str, value: &str) {
+        self.cookies.lock().unwrap().insert(name.to_string(), value.to_string());
+    }
+
+    /// Sends `user_agent` with every later request, as if the browser changed.
+    pub fn set_user_agent(&self, user_agent: &str) {
+        *self.user_agent.lock().unwrap() = Some(user_agent.to_string());
+    }
+
+    /// Sends `accept_language` with every later request, as a browser set to that language would.
+    pub fn set_accept_language(&self, accept_language: &str) {
+        *self.accept_language.lock().unwrap() = Some(accept_language.to_string());
+    }
+
+    pub async fn get(&self, path: &str) -> TestResponse {
+        self.send(Method::GET, path, None).await
+    }
+
+    /// A GET with extra request headers, such as `If-None-Match`.
+    pub async fn get_with_headers(&self, path: &str, headers: &[(&str, &str)]) -> TestResponse {
+        self.dispatch(Method::GET, path, None, None, headers).await
+    }
+
+    pub async fn post(&self, path: &str, body: Value) -> TestResponse {
+        self.send(Method::POST, path, Some(body)).await
+    }
+
+    /// Sends a request with the jar's cookies, then stores whatever cookies the response sets
+    /// and drops the ones it clears. State-changing requests carry the CSRF token like a page
+    /// of ours would, fetching one first if the jar has none.
+    pub async fn send(&self, method: Method, path: &str, body: Option<Value>) -> TestResponse {
+        let safe = matches!(method, Method::GET | Method::HEAD | Method::OPTIONS);
+        if !safe && self.cookie("csrf_token").is_none() {
+            self.send_without_csrf(Method::GET, "/api/v1/auth/csrf", None).await;
+        }
+        let csrf = if safe { None } else { self.cookie("csrf_token") };
+        self.dispatch(method, path, body.map(json_body), csrf, &[]).await
+    }
+
+    /// Like [`TestApp::post`], with extra request headers.
+    pub async fn post_with_headers(&self, path: &str, body: Value, headers: &[(&str, &str)]) -> TestResponse {
+        if self.cookie("csrf_token").is_none() {
+            self.send_without_csrf(Method::GET, "/api/v1/auth/csrf", None).await;
+        }
+        let csrf = self.cookie("csrf_token");
+        self.dispatch(Method::POST, path, Some(json_body(body)), csrf, headers).await
+    }
+
+    /// Sends `body` as is, for requests `post` can't express, like malformed JSON.
+    pub async fn send_raw(&self, method: Method, path: &str, content_type: &str, body: &[u8]) -> TestResponse {
+        if self.cookie("csrf_token").is_none() {
+            self.send_without_csrf(Method::GET, "/api/v1/auth/csrf", None).await;
+        }
+        let csrf = self.cookie("csrf_token");
+        self.dispatch(method, path, Some((content_type.to_string(), body.to_vec())), csrf, &[]).await
+    }
+
+    /// Submits an HTML form to `path`, with the CSRF token in its hidden field like our pages.
+    pub async fn post_form(&self, path: &str, fields: &[(&str, &str)]) -> TestResponse {
+        if self.cookie("csrf_token").is_none() {
+            self.send_without_csrf(Method::GET, "/api/v1/auth/csrf", None).await;
+        }
+        let csrf = self.cookie("csrf_token").unwrap_or_default();
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .append_pair("csrf_token", &csrf)
+            .finish();
+        self.dispatch(Method::POST, path, Some(("application/x-www-form-urlencoded".to_string(), body.into_bytes())), None, &[]).await
+    }
+
+    /// Sends `bytes` as the `file` field of a multipart form, like a file input would.
+    pub async fn send_file(&self, method: Method, path: &str, content_type: &str, bytes: &[u8]) -> TestResponse {
+        if self.cookie("csrf_token").is_none() {
+            self.send_without_csrf(Method::GET, "/api/v1/auth/csrf", None).await;
+        }
+        let boundary = uuid::Uuid::new_v4().simple().to_string();
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"file\"\r\nContent-Type: {content_type}\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+        let content_type = format!("multipart/form-data; boundary={}", boundary);
+        self.dispatch(method, path, Some((content_type, body)), self.cookie("csrf_token"), &[]).await
+    }
+
+    /// Sends a request the way a cross-site form would, without echoing the CSRF token.
+    pub async fn send_without_csrf(&self, method: Method, path: &str, body: Option<Value>) -> TestResponse {
+        self.dispatch(method, path, body.map(json_body), None, &[]).await
+    }
+
+    async fn dispatch(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<(String, Vec<u8>)>,
+        csrf: Option<String>,
+        headers: &[(&str, &str)],
+    ) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(path);
+        let cookie_header = self.cookies.lock().unwrap()
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if !cookie_header.is_empty() {
+            request = request.header(COOKIE, cookie_header);
+        }
+        if let Some(user_agent) = self.user_agent.lock().unwrap().as_deref() {
+            request = request.header(USER_AGENT, user_agent);
+        }
+        if let Some(accept_language) = self.accept_language.lock().unwrap().as_deref() {
+            request = request.header(ACCEPT_LANGUAGE, accept_language);
+        }
+        if let Some(csrf) = csrf {
+            request = request.header("x-csrf-token", csrf);
+        }
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = match body {
+            Some((content_type, body)) => request.header(CONTENT_TYPE, content_type).body(Body::from(body)),
+            None => request.body(Body::empty()),
+        }
+        .expect("valid request");
+
+        let response = self.router.clone().oneshot(request).await.expect("router is infallible");
+        let (parts, body) = response.into_parts();
+        self.store_cookies(&parts.headers);
+
+        let bytes = to_bytes(body, usize::MAX).await.expect("readable body");
+        TestResponse {
+            status: parts.status,
+            headers: parts.headers,
+            body: serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+            bytes: bytes.to_vec(),
+        }
+    }
+
+    fn store_cookies(&self, headers: &HeaderMap) {
+        let mut jar = self.cookies.lock().unwrap();
+        for header in headers.get_all(SET_COOKIE) {
+            let Some(cookie) = header.to_str().ok().and_then(|raw| cookie::Cookie::parse(raw.to_string()).ok()) else {
+                continue;
+            };
+            let expired = cookie.max_age().is_some_and(|age| age.is_zero() || age.is_negative());
+            if expired || cookie.value().is_empty() {
+                jar.remove(cookie.name());
+            } else {
+                jar.insert(cookie.name().to_string(), cookie.value().to_string());
+            }
+        }
+    }
+}
+
+fn json_body(body: Value) -> (String, Vec<u8>) {
+    ("application/json".to_string(), body.to_string().into_bytes())
+}
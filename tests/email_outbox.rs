@@ -0,0 +1,57 @@
+mod common;
+
+use diesel::prelude::*;
+use http::StatusCode;
+use serde_json::json;
+use tsumi::db::schema::{email_outbox, users};
+
+use common::TestApp;
+
+#[tokio::test]
+async fn signups_queue_their_verification_email_and_admins_can_retry_failures() {
+    let app = TestApp::with_settings(&[]).await;
+    let admin_id = app.sign_in_as("ann", "ann@example.com").await;
+    diesel::update(users::table.find(&admin_id))
+        .set(users::is_admin.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+
+    let queued: Vec<(String, String, String)> = email_outbox::table
+        .select((email_outbox::id, email_outbox::recipient, email_outbox::priority))
+        .load(&mut app.conn())
+        .unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].1, "ann@example.com");
+    assert_eq!(queued[0].2, "transactional");
+
+    // A refused signup queues nothing.
+    let taken = app.post("/api/v1/auth/signup", json!({ "name": "ann", "email": "ann@example.com", "password": "correct horse battery" })).await;
+    assert_eq!(taken.status, StatusCode::CONFLICT);
+    let total: i64 = email_outbox::table.count().get_result(&mut app.conn()).unwrap();
+    assert_eq!(total, 1);
+
+    let id = &queued[0].0;
+    diesel::update(email_outbox::table.find(id))
+        .set((email_outbox::status.eq("failed"), email_outbox::attempts.eq(8), email_outbox::last_error.eq("connection refused")))
+        .execute(&mut app.conn())
+        .unwrap();
+
+    let status = app.get("/api/v1/admin/email-outbox").await;
+    assert_eq!(status.status, StatusCode::OK, "{}", status.body);
+    assert_eq!(status.data()["transactional"]["failed"], 1);
+    assert_eq!(status.data()["transactional"]["pending"], 0);
+    assert_eq!(status.data()["failed"][0]["last_error"], "connection refused");
+    assert!(status.data()["failed"][0].get("text_body").is_none());
+
+    let retried = app.post(&format!("/api/v1/admin/email-outbox/{}/retry", id), json!({})).await;
+    assert_eq!(retried.status, StatusCode::OK, "{}", retried.body);
+    let again = app.post(&format!("/api/v1/admin/email-outbox/{}/retry", id), json!({})).await;
+    assert_eq!(again.status, StatusCode::NOT_FOUND);
+
+    let status = app.get("/api/v1/admin/email-outbox").await;
+    assert_eq!(status.data()["transactional"]["pending"], 1);
+    assert!(status.data()["oldest_pending_at"].is_string());
+
+    app.sign_in_as("bob", "bob@example.com").await;
+    assert_eq!(app.get("/api/v1/admin/email-outbox").await.status, StatusCode::FORBIDDEN);
+}
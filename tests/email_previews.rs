@@ -0,0 +1,44 @@
+mod common;
+
+use diesel::prelude::*;
+use http::StatusCode;
+use serde_json::json;
+use tsumi::db::schema::email_outbox;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn emails_can_be_previewed_in_development() {
+    let app = TestApp::with_settings(&[("APP_ENV", "development")]).await;
+
+    let html = app.get("/api/v1/dev/emails/password_reset").await;
+    assert_eq!(html.status, StatusCode::OK, "{}", html.text);
+    assert!(html.text.contains("<title>Reset your tsumi password</title>"), "{}", html.text);
+
+    let text = app.get("/api/v1/dev/emails/digest?lang=de&format=text").await;
+    assert_eq!(text.status, StatusCode::OK, "{}", text.text);
+    assert!(text.text.starts_with("Subject: 2 neue Beiträge"), "{}", text.text);
+    assert!(text.text.contains("- Notes on <Rust> & SQLite von bob"), "{}", text.text);
+
+    assert_eq!(app.get("/api/v1/dev/emails/welcome").await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.get("/api/v1/dev/emails/digest?lang=ja").await.status, StatusCode::BAD_REQUEST);
+
+    let production = TestApp::new().await;
+    assert_eq!(production.get("/api/v1/dev/emails/digest").await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn verification_emails_are_written_in_the_browsers_language() {
+    let app = TestApp::new().await;
+    app.set_accept_language("fr-CA,fr;q=0.9");
+    let signup = app.post("/api/v1/auth/signup", json!({ "name": "ann", "email": "ann@example.com", "password": "correct horse battery" })).await;
+    assert_eq!(signup.status, StatusCode::OK, "{}", signup.body);
+
+    let (subject, text_body, html_body): (String, String, Option<String>) = email_outbox::table
+        .select((email_outbox::subject, email_outbox::text_body, email_outbox::html_body))
+        .first(&mut app.conn())
+        .unwrap();
+    assert_eq!(subject, "Vérifiez votre adresse e-mail tsumi");
+    assert!(text_body.starts_with("Bonjour ann,"), "{}", text_body);
+    assert!(html_body.unwrap().contains("<html lang=\"fr\">"));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn retried_posts_replay_the_first_response() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let body = json!({ "title": "Once", "content": "only once" });
+    let first = app.post_with_headers("/api/v1/posts", body.clone(), &[("Idempotency-Key", "create-once")]).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+    assert!(first.headers.get("idempotent-replayed").is_none());
+
+    let retry = app.post_with_headers("/api/v1/posts", body, &[("Idempotency-Key", "create-once")]).await;
+    assert_eq!(retry.status, StatusCode::OK, "{}", retry.body);
+    assert_eq!(retry.headers["idempotent-replayed"], "true");
+    assert_eq!(retry.data()["id"], first.data()["id"]);
+
+    let posts = app.get("/api/v1/me/posts").await;
+    assert_eq!(posts.data()["total"], 1);
+
+    let reused = app
+        .post_with_headers("/api/v1/posts", json!({ "title": "Twice", "content": "x" }), &[("Idempotency-Key", "create-once")])
+        .await;
+    assert_eq!(reused.status, StatusCode::BAD_REQUEST);
+    assert_eq!(reused.body["error"]["details"]["fields"][0]["code"], "reused");
+
+    // Keys are per user.
+    app.sign_in_as("bob", "bob@example.com").await;
+    let other = app
+        .post_with_headers("/api/v1/posts", json!({ "title": "Twice", "content": "x" }), &[("Idempotency-Key", "create-once")])
+        .await;
+    assert_eq!(other.status, StatusCode::OK, "{}", other.body);
+    assert_ne!(other.data()["id"], first.data()["id"]);
+}
+
+#[tokio::test]
+async fn retried_signups_are_not_repeated() {
+    let app = TestApp::new().await;
+    let body = json!({ "name": "cat", "email": "cat@example.com", "password": "correct horse battery" });
+
+    let first = app.post_with_headers("/api/v1/auth/signup", body.clone(), &[("Idempotency-Key", "signup-1")]).await;
+    assert!(first.status.is_success(), "{}", first.body);
+
+    let retry = app.post_with_headers("/api/v1/auth/signup", body.clone(), &[("Idempotency-Key", "signup-1")]).await;
+    assert_eq!(retry.status, first.status);
+    assert_eq!(retry.headers["idempotent-replayed"], "true");
+    assert_eq!(retry.body, first.body);
+
+    // Another signed-out client can't replay it with the same key.
+    app.set_user_agent("another browser");
+    let elsewhere = app.post_with_headers("/api/v1/auth/signup", body.clone(), &[("Idempotency-Key", "signup-1")]).await;
+    assert!(elsewhere.headers.get("idempotent-replayed").is_none());
+    assert_eq!(elsewhere.status, StatusCode::CONFLICT, "{}", elsewhere.body);
+
+    // Without a key the duplicate is handled, and turned away, as usual.
+    let again = app.post("/api/v1/auth/signup", body).await;
+    assert_eq!(again.status, StatusCode::CONFLICT, "{}", again.body);
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use diesel::prelude::*;
+use http::{Method, StatusCode};
+use serde_json::json;
+use tsumi::db::schema::{audit_logs, users};
+
+use common::TestApp;
+
+async fn admin_app() -> (TestApp, String) {
+    let app = TestApp::new().await;
+    let admin_id = app.sign_in_as("ann", "ann@example.com").await;
+    diesel::update(users::table.find(&admin_id))
+        .set(users::is_admin.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+    (app, admin_id)
+}
+
+#[tokio::test]
+async fn admins_act_as_users_until_they_stop() {
+    let (app, admin_id) = admin_app().await;
+    let user_id = app.sign_in_as("bob", "bob@example.com").await;
+    app.sign_in("ann@example.com").await;
+
+    let started = app.post(&format!("/api/v1/admin/impersonate/{}", user_id), json!({})).await;
+    assert_eq!(started.status, StatusCode::OK, "{}", started.body);
+    assert_eq!(app.get("/api/v1/me").await.data()["username"], "bob");
+    assert!(app.get("/").await.text.contains("Viewing the site as"));
+
+    // Writing on their behalf works; deleting, sensitive account changes and admin routes don't.
+    let post = app.post("/api/v1/posts", json!({ "title": "Hello", "content": "x" })).await;
+    assert_eq!(post.status, StatusCode::OK, "{}", post.body);
+    let path = format!("/api/v1/posts/{}", post.data()["id"].as_str().unwrap());
+    assert_eq!(app.send(Method::DELETE, &path, None).await.status, StatusCode::FORBIDDEN);
+    assert_eq!(app.post("/api/v1/auth/signout-all", json!({})).await.status, StatusCode::FORBIDDEN);
+    assert_eq!(app.get("/api/v1/admin/users").await.status, StatusCode::FORBIDDEN);
+
+    let stopped = app.post("/api/v1/auth/impersonation/stop", json!({})).await;
+    assert_eq!(stopped.status, StatusCode::OK, "{}", stopped.body);
+    assert_eq!(app.get("/api/v1/me").await.data()["username"], "ann");
+    assert!(!app.get("/").await.text.contains("Viewing the site as"));
+
+    let events: Vec<(String, Option<String>, Option<String>)> = audit_logs::table
+        .filter(audit_logs::event.like("admin.impersonation%"))
+        .order(audit_logs::created_at.asc())
+        .select((audit_logs::event, audit_logs::user_id, audit_logs::actor_id))
+        .load(&mut app.conn())
+        .unwrap();
+    assert_eq!(events, vec![
+        ("admin.impersonation_started".to_string(), Some(user_id.clone()), Some(admin_id.clone())),
+        ("admin.impersonation_stopped".to_string(), Some(user_id), Some(admin_id)),
+    ]);
+}
+
+#[tokio::test]
+async fn admins_cannot_be_impersonated() {
+    let (app, _) = admin_app().await;
+    let other_id = app.sign_in_as("cat", "cat@example.com").await;
+    diesel::update(users::table.find(&other_id))
+        .set(users::is_admin.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+    app.sign_in("ann@example.com").await;
+
+    let refused = app.post(&format!("/api/v1/admin/impersonate/{}", other_id), json!({})).await;
+    assert_eq!(refused.status, StatusCode::FORBIDDEN);
+    let stop = app.post("/api/v1/auth/impersonation/stop", json!({})).await;
+    assert_eq!(stop.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn signing_the_admin_out_everywhere_ends_impersonation() {
+    let (app, _) = admin_app().await;
+    let user_id = app.sign_in_as("bob", "bob@example.com").await;
+    app.sign_in("ann@example.com").await;
+
+    let started = app.post(&format!("/api/v1/admin/impersonate/{}", user_id), json!({})).await;
+    assert_eq!(started.status, StatusCode::OK, "{}", started.body);
+    let impersonating = app.cookie("access_token").unwrap();
+
+    app.sign_in("ann@example.com").await;
+    assert_eq!(app.post("/api/v1/auth/signout-all", json!({})).await.status, StatusCode::OK);
+
+    app.set_cookie("access_token", &impersonating);
+    assert_eq!(app.get("/api/v1/me").await.status, StatusCode::UNAUTHORIZED);
+    let stop = app.post("/api/v1/auth/impersonation/stop", json!({})).await;
+    assert_eq!(stop.status, StatusCode::UNAUTHORIZED, "{}", stop.body);
+}
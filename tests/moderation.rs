@@ -0,0 +1,183 @@
+mod common;
+
+use diesel::prelude::*;
+use http::StatusCode;
+use serde_json::json;
+use tsumi::db::schema::users;
+
+use common::TestApp;
+
+async fn moderated_app() -> TestApp {
+    let app = TestApp::with_settings(&[]).await;
+    let admin_id = app.sign_in_as("ann", "ann@example.com").await;
+    diesel::update(users::table.find(&admin_id))
+        .set(users::is_admin.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+    app
+}
+
+#[tokio::test]
+async fn hiding_a_reported_post_takes_it_down_and_tells_the_author() {
+    let app = moderated_app().await;
+    app.sign_in_as("bob", "bob@example.com").await;
+    let created = app.post("/api/v1/posts", json!({ "title": "Spammy", "content": "x", "is_published": true })).await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    let post_id = created.data()["id"].as_str().unwrap().to_string();
+    let own = app.post(&format!("/api/v1/posts/{}/report", post_id), json!({ "reason": "spam" })).await;
+    assert_eq!(own.status, StatusCode::BAD_REQUEST);
+
+    app.sign_in_as("cat", "cat@example.com").await;
+    let bad = app.post(&format!("/api/v1/posts/{}/report", post_id), json!({ "reason": "boring" })).await;
+    assert_eq!(bad.status, StatusCode::BAD_REQUEST);
+    let report = app.post(&format!("/api/v1/posts/{}/report", post_id), json!({ "reason": "spam", "details": "ads" })).await;
+    assert_eq!(report.status, StatusCode::OK, "{}", report.body);
+    let again = app.post(&format!("/api/v1/posts/{}/report", post_id), json!({ "reason": "spam" })).await;
+    assert_eq!(again.status, StatusCode::CONFLICT);
+
+    app.sign_in("ann@example.com").await;
+    let queue = app.get("/api/v1/admin/reports").await;
+    assert_eq!(queue.status, StatusCode::OK, "{}", queue.body);
+    let items = queue.data()["items"].as_array().unwrap().clone();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["reporter_name"], "cat");
+    assert_eq!(items[0]["post_title"], "Spammy");
+
+    let report_id = items[0]["id"].as_str().unwrap();
+    let hidden = app.post(&format!("/api/v1/admin/reports/{}/hide", report_id), json!({})).await;
+    assert_eq!(hidden.status, StatusCode::OK, "{}", hidden.body);
+    assert_eq!(hidden.data()["resolved"], 1);
+    assert_eq!(app.get("/api/v1/admin/reports").await.data()["items"], json!([]));
+    let actioned = app.get("/api/v1/admin/reports?status=actioned").await;
+    assert_eq!(actioned.data()["items"][0]["status"], "actioned");
+
+    app.sign_in("cat@example.com").await;
+    assert_eq!(app.get(&format!("/api/v1/posts/{}", post_id)).await.status, StatusCode::NOT_FOUND);
+    let listed = app.get("/api/public/v1/posts").await;
+    assert_eq!(listed.data()["items"], json!([]));
+
+    app.sign_in("bob@example.com").await;
+    let notifications = app.get("/api/v1/notifications").await;
+    assert_eq!(notifications.status, StatusCode::OK, "{}", notifications.body);
+    assert_eq!(notifications.data()["items"][0]["kind"], "content_hidden");
+    assert_eq!(app.get(&format!("/api/v1/posts/{}", post_id)).await.status, StatusCode::OK);
+
+    app.sign_in("ann@example.com").await;
+    let restored = app.post(&format!("/api/v1/admin/reports/{}/restore", report_id), json!({})).await;
+    assert_eq!(restored.status, StatusCode::OK, "{}", restored.body);
+    app.sign_in("cat@example.com").await;
+    assert_eq!(app.get(&format!("/api/v1/posts/{}", post_id)).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn hidden_comments_keep_their_place_in_the_thread() {
+    let app = moderated_app().await;
+    app.sign_in_as("bob", "bob@example.com").await;
+    let created = app.post("/api/v1/posts", json!({ "title": "Thread", "content": "x", "is_published": true })).await;
+    let post_id = created.data()["id"].as_str().unwrap().to_string();
+
+    app.sign_in_as("cat", "cat@example.com").await;
+    let comment = app.post(&format!("/api/v1/posts/{}/comments", post_id), json!({ "body": "rude" })).await;
+    assert_eq!(comment.status, StatusCode::OK, "{}", comment.body);
+    let comment_id = comment.data()["id"].as_str().unwrap().to_string();
+
+    app.sign_in("bob@example.com").await;
+    let report = app.post(&format!("/api/v1/comments/{}/report", comment_id), json!({ "reason": "harassment" })).await;
+    assert_eq!(report.status, StatusCode::OK, "{}", report.body);
+    let report_id = report.data()["id"].as_str().unwrap().to_string();
+
+    app.sign_in("ann@example.com").await;
+    let dismissed = app.post(&format!("/api/v1/admin/reports/{}/dismiss", report_id), json!({})).await;
+    assert_eq!(dismissed.status, StatusCode::OK, "{}", dismissed.body);
+    let hidden = app.post(&format!("/api/v1/admin/reports/{}/hide", report_id), json!({})).await;
+    assert_eq!(hidden.status, StatusCode::OK, "{}", hidden.body);
+    assert_eq!(hidden.data()["resolved"], 0);
+
+    let comments = app.get(&format!("/api/v1/posts/{}/comments", post_id)).await;
+    assert_eq!(comments.status, StatusCode::OK, "{}", comments.body);
+    let shown = &comments.data()["comments"][0];
+    assert_eq!(shown["hidden"], true);
+    assert_eq!(shown["body"], json!(null));
+
+    app.sign_in("bob@example.com").await;
+    let again = app.post(&format!("/api/v1/comments/{}/report", comment_id), json!({ "reason": "spam" })).await;
+    assert_eq!(again.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn the_spam_check_queues_hides_or_rejects_comments_by_score() {
+    let app = TestApp::with_settings(&[
+        ("SPAM_CHECKER", "heuristic"),
+        ("SPAM_KEYWORDS", "casino, viagra, lottery"),
+        ("SPAM_QUEUE_THRESHOLD", "0.3"),
+    ])
+    .await;
+    let admin_id = app.sign_in_as("ann", "ann@example.com").await;
+    diesel::update(users::table.find(&admin_id))
+        .set(users::is_admin.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+    let created = app.post("/api/v1/posts", json!({ "title": "Open thread", "content": "x", "is_published": true })).await;
+    let post_id = created.data()["id"].as_str().unwrap().to_string();
+    let comments_path = format!("/api/v1/posts/{}/comments", post_id);
+
+    app.sign_in_as("bob", "bob@example.com").await;
+    let rejected = app.post(&comments_path, json!({ "body": "casino viagra lottery" })).await;
+    assert_eq!(rejected.status, StatusCode::BAD_REQUEST, "{}", rejected.body);
+    let shadowed = app.post(&comments_path, json!({ "body": "cheap viagra at the casino" })).await;
+    assert_eq!(shadowed.status, StatusCode::OK, "{}", shadowed.body);
+    assert_eq!(shadowed.data()["hidden"], false);
+    let queued = app.post(&comments_path, json!({ "body": "my casino story" })).await;
+    assert_eq!(queued.status, StatusCode::OK, "{}", queued.body);
+    assert_eq!(queued.data()["pending_review"], true);
+    let queued_id = queued.data()["id"].as_str().unwrap().to_string();
+
+    // Bob sees both as his; everyone else sees neither.
+    let own = app.get(&comments_path).await;
+    assert!(own.data()["comments"].as_array().unwrap().iter().all(|comment| comment["hidden"] == false));
+    app.sign_in("ann@example.com").await;
+    let others = app.get(&comments_path).await;
+    assert!(others.data()["comments"].as_array().unwrap().iter().all(|comment| comment["hidden"] == true));
+
+    let queue = app.get("/api/v1/admin/reports").await;
+    let items = queue.data()["items"].as_array().unwrap().clone();
+    assert_eq!(items.len(), 1, "{}", queue.body);
+    assert_eq!(items[0]["comment_id"], json!(queued_id));
+    assert_eq!(items[0]["reporter_name"], json!(null));
+    let dismissed = app.post(&format!("/api/v1/admin/reports/{}/dismiss", items[0]["id"].as_str().unwrap()), json!({})).await;
+    assert_eq!(dismissed.status, StatusCode::OK, "{}", dismissed.body);
+    let released = app.get(&comments_path).await;
+    let released = released.data()["comments"].as_array().unwrap().iter().find(|comment| comment["id"] == json!(queued_id)).cloned().unwrap();
+    assert_eq!(released["hidden"], false);
+}
+
+#[tokio::test]
+async fn flagged_signups_have_their_comments_held_until_trusted() {
+    let app = TestApp::with_settings(&[("SPAM_CHECKER", "heuristic"), ("SPAM_KEYWORDS", "casino, viagra")]).await;
+    let admin_id = app.sign_in_as("ann", "ann@example.com").await;
+    diesel::update(users::table.find(&admin_id))
+        .set(users::is_admin.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+    let created = app.post("/api/v1/posts", json!({ "title": "Open thread", "content": "x", "is_published": true })).await;
+    let comments_path = format!("/api/v1/posts/{}/comments", created.data()["id"].as_str().unwrap());
+
+    let spammer_id = app.sign_in_as("casinoking", "viagra@example.com").await;
+    let first = app.post(&comments_path, json!({ "body": "Hello there" })).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+
+    app.sign_in("ann@example.com").await;
+    let listed = app.get(&comments_path).await;
+    assert_eq!(listed.data()["comments"][0]["hidden"], true);
+    let trusted = app.post(&format!("/api/v1/admin/users/{}/trust", spammer_id), json!({})).await;
+    assert_eq!(trusted.status, StatusCode::OK, "{}", trusted.body);
+    assert_eq!(trusted.data()["comment_moderation"], "visible");
+
+    app.sign_in("viagra@example.com").await;
+    let second = app.post(&comments_path, json!({ "body": "Hello again" })).await;
+    assert_eq!(second.status, StatusCode::OK, "{}", second.body);
+    app.sign_in("ann@example.com").await;
+    let listed = app.get(&comments_path).await;
+    let shown = listed.data()["comments"].as_array().unwrap().iter().filter(|comment| comment["hidden"] == false).count();
+    assert_eq!(shown, 1);
+}
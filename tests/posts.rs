@@ -0,0 +1,200 @@
+mod common;
+
+use http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn post_failures_use_the_error_envelope() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let post = json!({ "title": "Hello", "slug": "hello", "content": "# Hello", "tags": ["rust"] });
+    let created = app.post("/api/v1/posts", post.clone()).await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    assert_eq!(created.data()["slug"], "hello");
+    assert_eq!(created.data()["tags"], json!(["rust"]));
+
+    let duplicate = app.post("/api/v1/posts", post).await;
+    assert_eq!(duplicate.status, StatusCode::CONFLICT);
+    assert_eq!(duplicate.error_code(), "CONFLICT");
+
+    let bad_tag = app.post("/api/v1/posts", json!({ "title": "Tags", "content": "x", "tags": [" "] })).await;
+    assert_eq!(bad_tag.status, StatusCode::BAD_REQUEST);
+    assert_eq!(bad_tag.body["error"]["details"]["fields"][0]["field"], "tags");
+
+    let missing = app.get("/api/v1/posts/no-such-post").await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    assert_eq!(missing.error_code(), "NOT_FOUND");
+
+    let id = created.data()["id"].as_str().unwrap();
+    let deleted = app.send(Method::DELETE, &format!("/api/v1/posts/{}", id), None).await;
+    assert_eq!(deleted.status, StatusCode::OK, "{}", deleted.body);
+    assert_eq!(app.get(&format!("/api/v1/posts/{}", id)).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn renamed_posts_keep_their_old_links() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let post = json!({ "title": "My Post", "content": "Body", "is_published": true });
+    let first = app.post("/api/v1/posts", post.clone()).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+    assert_eq!(first.data()["slug"], "my-post");
+    let second = app.post("/api/v1/posts", post).await;
+    assert_eq!(second.data()["slug"], "my-post-2");
+
+    let reserved = app.post("/api/v1/posts", json!({ "title": "Admin", "slug": "admin", "content": "x" })).await;
+    assert_eq!(reserved.status, StatusCode::BAD_REQUEST);
+    assert_eq!(reserved.body["error"]["details"]["fields"][0]["code"], "reserved");
+
+    // A slug from front matter has to be one the request could have sent.
+    let bad_slug = app.post("/api/v1/posts", json!({ "title": "Bad", "slug": "Hello World", "content": "x" })).await;
+    let bad_front_matter = app.post("/api/v1/posts", json!({ "title": "Bad", "content": "---\nslug: Hello World/../x\n---\nBody" })).await;
+    assert_eq!(bad_front_matter.status, bad_slug.status, "{}", bad_front_matter.body);
+    assert_eq!(bad_front_matter.status, StatusCode::BAD_REQUEST);
+    assert_eq!(bad_front_matter.body["error"]["details"]["fields"], bad_slug.body["error"]["details"]["fields"]);
+
+    let id = first.data()["id"].as_str().unwrap();
+    let renamed = app.send(Method::PATCH, &format!("/api/v1/posts/{}", id), Some(json!({ "title": "Better Title" }))).await;
+    assert_eq!(renamed.status, StatusCode::OK, "{}", renamed.body);
+    assert_eq!(renamed.data()["slug"], "better-title");
+
+    let old = app.get("/ann/my-post").await;
+    assert_eq!(old.status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(old.headers["location"], "/ann/better-title");
+    assert_eq!(app.get("/ann/better-title").await.status, StatusCode::OK);
+
+    // A new post can take the old slug back, and then it's that post's link.
+    let third = app.post("/api/v1/posts", json!({ "title": "Third", "slug": "my-post", "content": "x", "is_published": true })).await;
+    assert_eq!(third.status, StatusCode::OK, "{}", third.body);
+    assert_eq!(app.get("/ann/my-post").await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn collaborators_edit_drafts_but_only_the_author_publishes() {
+    let app = TestApp::new().await;
+    let bob_id = app.sign_in_as("bob", "bob@example.com").await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let created = app.post("/api/v1/posts", json!({ "title": "Together", "content": "Draft" })).await;
+    let id = created.data()["id"].as_str().unwrap().to_string();
+    let post_path = format!("/api/v1/posts/{}", id);
+    let collaborators_path = format!("{}/collaborators", post_path);
+
+    let invited = app.post(&collaborators_path, json!({ "username": "bob" })).await;
+    assert_eq!(invited.status, StatusCode::OK, "{}", invited.body);
+    assert_eq!(invited.data()["collaborators"][0]["role"], "editor");
+    let unknown_role = app.post(&collaborators_path, json!({ "username": "bob", "role": "owner" })).await;
+    assert_eq!(unknown_role.status, StatusCode::BAD_REQUEST);
+
+    app.sign_in("bob@example.com").await;
+    assert_eq!(app.get(&post_path).await.status, StatusCode::OK);
+    let edited = app.send(Method::PATCH, &post_path, Some(json!({ "content": "Draft, edited by bob" }))).await;
+    assert_eq!(edited.status, StatusCode::OK, "{}", edited.body);
+    let publish = app.post(&format!("{}/publish", post_path), json!({})).await;
+    assert_eq!(publish.status, StatusCode::FORBIDDEN);
+    assert_eq!(app.send(Method::DELETE, &post_path, None).await.status, StatusCode::FORBIDDEN);
+
+    app.sign_in("ann@example.com").await;
+    let published = app.post(&format!("{}/publish", post_path), json!({})).await;
+    assert_eq!(published.status, StatusCode::OK, "{}", published.body);
+    let demoted = app.post(&collaborators_path, json!({ "username": "bob", "role": "viewer" })).await;
+    assert_eq!(demoted.data()["collaborators"][0]["role"], "viewer");
+
+    // Once it's live the post is the author's alone, and viewers never get to change it.
+    app.sign_in("bob@example.com").await;
+    let edit = app.send(Method::PATCH, &post_path, Some(json!({ "title": "Mine now" }))).await;
+    assert_eq!(edit.status, StatusCode::FORBIDDEN);
+    assert_eq!(app.get(&format!("{}/versions", post_path)).await.status, StatusCode::OK);
+    let left = app.send(Method::DELETE, &format!("{}/{}", collaborators_path, bob_id), None).await;
+    assert_eq!(left.status, StatusCode::OK, "{}", left.body);
+    assert_eq!(app.get(&format!("{}/versions", post_path)).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn posts_carry_their_word_count_and_reading_time() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let created = app.post("/api/v1/posts", json!({ "title": "Short", "content": "A few words here" })).await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    assert_eq!(created.data()["word_count"], 4);
+    assert_eq!(created.data()["reading_minutes"], 1);
+
+    let id = created.data()["id"].as_str().unwrap();
+    let content = "word ".repeat(401);
+    let updated = app.send(Method::PATCH, &format!("/api/v1/posts/{}", id), Some(json!({ "content": content }))).await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+    assert_eq!(updated.data()["word_count"], 401);
+    assert_eq!(updated.data()["reading_minutes"], 3);
+}
+
+#[tokio::test]
+async fn post_reads_answer_conditional_requests() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let created = app.post("/api/v1/posts", json!({ "title": "Cached", "content": "x", "is_published": true })).await;
+    let id = created.data()["id"].as_str().unwrap().to_string();
+    let path = format!("/api/v1/posts/{}", id);
+
+    let first = app.get(&path).await;
+    assert_eq!(first.status, StatusCode::OK);
+    assert_eq!(first.headers[CACHE_CONTROL], "private, no-cache");
+    let etag = first.headers[ETAG].to_str().unwrap().to_string();
+    assert!(etag.starts_with("W/\""), "{}", etag);
+
+    let unchanged = app.get_with_headers(&path, &[(IF_NONE_MATCH.as_str(), &etag)]).await;
+    assert_eq!(unchanged.status, StatusCode::NOT_MODIFIED);
+    assert!(unchanged.text.is_empty());
+
+    let edited = app.send(Method::PATCH, &path, Some(json!({ "content": "y" }))).await;
+    assert_eq!(edited.status, StatusCode::OK, "{}", edited.body);
+    let changed = app.get_with_headers(&path, &[(IF_NONE_MATCH.as_str(), &etag)]).await;
+    assert_eq!(changed.status, StatusCode::OK);
+    assert_ne!(changed.headers[ETAG].to_str().unwrap(), etag);
+
+    app.clear_cookies();
+    let anonymous = app.get(&path).await;
+    assert_eq!(anonymous.headers[CACHE_CONTROL], "public, no-cache");
+    let public = app.get(&format!("/api/public/v1/posts/{}", id)).await;
+    let public_etag = public.headers[ETAG].to_str().unwrap().to_string();
+    let public_unchanged = app.get_with_headers(&format!("/api/public/v1/posts/{}", id), &[(IF_NONE_MATCH.as_str(), &public_etag)]).await;
+    assert_eq!(public_unchanged.status, StatusCode::NOT_MODIFIED);
+    assert_eq!(public_unchanged.headers[CACHE_CONTROL], "public, max-age=60");
+}
+
+#[tokio::test]
+async fn unreadable_bodies_use_the_error_envelope() {
+    let app = TestApp::with_settings(&[("POST_BODY_MAX_BYTES", "1024")]).await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let malformed = app.send_raw(Method::POST, "/api/v1/posts", "application/json", br#"{"title": "#).await;
+    assert_eq!(malformed.status, StatusCode::BAD_REQUEST, "{}", malformed.text);
+    assert_eq!(malformed.error_code(), "VALIDATION_ERROR");
+
+    let missing = app.post("/api/v1/posts", json!({ "content": "x" })).await;
+    assert_eq!(missing.status, StatusCode::BAD_REQUEST, "{}", missing.text);
+    assert_eq!(missing.body["error"]["details"]["fields"][0]["field"], "title");
+    assert_eq!(missing.body["error"]["details"]["fields"][0]["code"], "required");
+
+    let wrong_type = app.post("/api/v1/posts", json!({ "title": "Hi", "content": "x", "tags": [1] })).await;
+    assert_eq!(wrong_type.status, StatusCode::BAD_REQUEST, "{}", wrong_type.text);
+    assert_eq!(wrong_type.body["error"]["details"]["fields"][0]["field"], "tags[0]");
+
+    let not_json = app.send_raw(Method::POST, "/api/v1/posts", "text/plain", b"hello").await;
+    assert_eq!(not_json.status, StatusCode::UNSUPPORTED_MEDIA_TYPE, "{}", not_json.text);
+    assert_eq!(not_json.error_code(), "UNSUPPORTED_MEDIA_TYPE");
+
+    let huge = app.post("/api/v1/posts", json!({ "title": "Big", "content": "x".repeat(2048) })).await;
+    assert_eq!(huge.status, StatusCode::PAYLOAD_TOO_LARGE, "{}", huge.text);
+    assert_eq!(huge.error_code(), "PAYLOAD_TOO_LARGE");
+
+    // Other groups keep their own limit.
+    let profile = app.send(Method::PATCH, "/api/v1/me", Some(json!({ "bio": "x".repeat(2048) }))).await;
+    assert_ne!(profile.status, StatusCode::PAYLOAD_TOO_LARGE, "{}", profile.text);
+}
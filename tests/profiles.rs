@@ -0,0 +1,46 @@
+mod common;
+
+use http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn profiles_show_what_their_owner_allows() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let not_a_site = app.send(Method::PATCH, "/api/v1/me", Some(json!({ "website": "javascript:alert(1)" }))).await;
+    assert_eq!(not_a_site.status, StatusCode::BAD_REQUEST);
+    assert_eq!(not_a_site.body["error"]["details"]["fields"][0]["field"], "website");
+
+    let profile = json!({ "bio": " Writes about tea. ", "website": "https://ann.example", "location": "Kyoto", "show_email": true });
+    let updated = app.send(Method::PATCH, "/api/v1/me", Some(profile)).await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+    assert_eq!(updated.data()["bio"], "Writes about tea.");
+    assert_eq!(app.get("/api/v1/me").await.data()["location"], "Kyoto");
+
+    let public = app.get("/api/public/v1/users/ann").await;
+    assert_eq!(public.status, StatusCode::OK, "{}", public.body);
+    assert_eq!(public.data()["website"], "https://ann.example");
+    assert!(public.data().get("email").is_none());
+    assert_eq!(app.get("/api/v1/users/ann").await.data()["email"], "ann@example.com");
+
+    let private = app.send(Method::PATCH, "/api/v1/me", Some(json!({ "profile_private": true }))).await;
+    assert_eq!(private.status, StatusCode::OK, "{}", private.body);
+    assert_eq!(private.data()["bio"], "Writes about tea.");
+    assert_eq!(app.get("/api/public/v1/users/ann").await.status, StatusCode::NOT_FOUND);
+
+    app.sign_in_as("bob", "bob@example.com").await;
+    let seen_by_bob = app.get("/api/v1/users/ann").await;
+    assert_eq!(seen_by_bob.status, StatusCode::OK);
+    assert_eq!(seen_by_bob.data()["email"], "ann@example.com");
+    assert!(app.get("/api/v1/users/bob").await.data().get("email").is_some());
+
+    app.clear_cookies();
+    assert_eq!(app.get("/api/v1/users/ann").await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.get("/api/v1/users/ann/activity").await.status, StatusCode::NOT_FOUND);
+    let bob = app.get("/api/v1/users/bob").await;
+    assert_eq!(bob.status, StatusCode::OK);
+    assert!(bob.data().get("email").is_none());
+}
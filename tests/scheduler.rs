@@ -0,0 +1,201 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::prelude::*;
+use http::StatusCode;
+use tsumi::db::models::job::Jobs;
+use tsumi::db::schema::{job_locks, jobs, users};
+use tsumi::services::job_locks::Leases;
+use tsumi::services::lifecycle::{Service, Shutdown};
+use tsumi::services::scheduler::{Job, Schedule, Scheduler};
+
+use common::{test_config, test_pool, TestApp};
+
+/// Counts its runs, failing each one while `fail` is set.
+struct Counter {
+    runs: Arc<AtomicUsize>,
+    fail: bool,
+    takes: Duration,
+}
+
+#[async_trait]
+impl Job for Counter {
+    fn name(&self) -> &'static str {
+        "counter"
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::Every(Duration::from_secs(3600))
+    }
+
+    async fn run(&self, _shutdown: Shutdown) -> Result<(), String> {
+        self.runs.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.takes).await;
+        if self.fail { Err("out of cheese".to_string()) } else { Ok(()) }
+    }
+}
+
+/// A server's scheduler, with only the counter job, counting into `runs`.
+fn server(pool: &tsumi::state::DbPool, runs: &Arc<AtomicUsize>, fail: bool, takes: Duration) -> Scheduler {
+    let config = test_config(&[]);
+    let mut scheduler = Scheduler::new(&config, pool.clone(), Leases::new(&config, pool.clone()));
+    scheduler.register(Arc::new(Counter { runs: runs.clone(), fail, takes }));
+    scheduler
+}
+
+fn scheduler(pool: &tsumi::state::DbPool, fail: bool) -> (Scheduler, Arc<AtomicUsize>) {
+    let runs = Arc::new(AtomicUsize::new(0));
+    (server(pool, &runs, fail, Duration::ZERO), runs)
+}
+
+fn lock_count(pool: &tsumi::state::DbPool) -> i64 {
+    job_locks::table.count().get_result(&mut pool.get().unwrap()).unwrap()
+}
+
+/// Waits for the job's row to show a finished run.
+async fn finished_row(pool: &tsumi::state::DbPool) -> Jobs {
+    for _ in 0..200 {
+        let row = Jobs::by_name(&mut pool.get().unwrap(), "counter").unwrap();
+        if let Some(row) = row.filter(|row| row.last_finished_at.is_some()) {
+            return row;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the job never finished a run");
+}
+
+#[tokio::test]
+async fn due_jobs_run_once_and_are_scheduled_again() {
+    let pool = test_pool();
+    let (scheduler, runs) = scheduler(&pool, false);
+    scheduler.start().await.unwrap();
+
+    let row = finished_row(&pool).await;
+    scheduler.stop().await;
+
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    assert_eq!(lock_count(&pool), 0);
+    assert_eq!(row.last_error, None);
+    let next = row.next_run_at.unwrap() - Utc::now().naive_utc();
+    assert!(next > chrono::Duration::minutes(59), "{}", next);
+}
+
+#[tokio::test]
+async fn failed_runs_are_recorded_and_retried_before_the_next_interval() {
+    let pool = test_pool();
+    let (scheduler, runs) = scheduler(&pool, true);
+    scheduler.start().await.unwrap();
+
+    let row = finished_row(&pool).await;
+    scheduler.stop().await;
+
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    assert_eq!(row.last_error.as_deref(), Some("out of cheese"));
+    let next = row.next_run_at.unwrap() - Utc::now().naive_utc();
+    assert!(next <= chrono::Duration::minutes(5), "{}", next);
+}
+
+#[tokio::test]
+async fn jobs_held_by_another_server_wait_for_its_lease_to_run_out() {
+    let pool = test_pool();
+    let now = Utc::now().naive_utc();
+    diesel::insert_into(jobs::table)
+        .values((jobs::name.eq("counter"), jobs::next_run_at.eq(now)))
+        .execute(&mut pool.get().unwrap())
+        .unwrap();
+    diesel::insert_into(job_locks::table)
+        .values((
+            job_locks::name.eq("counter"),
+            job_locks::holder.eq("another-server"),
+            job_locks::acquired_at.eq(now),
+            job_locks::expires_at.eq(now + chrono::Duration::minutes(5)),
+        ))
+        .execute(&mut pool.get().unwrap())
+        .unwrap();
+
+    let (scheduler, runs) = scheduler(&pool, false);
+    scheduler.start().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 0);
+
+    // The other server died without finishing, so its run is taken over.
+    diesel::update(job_locks::table.find("counter"))
+        .set(job_locks::expires_at.eq(now - chrono::Duration::seconds(1)))
+        .execute(&mut pool.get().unwrap())
+        .unwrap();
+    assert!(scheduler.run_now(&mut pool.get().unwrap(), "counter").unwrap());
+
+    let row = finished_row(&pool).await;
+    scheduler.stop().await;
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    assert!(row.last_started_at.is_some());
+    assert_eq!(lock_count(&pool), 0);
+}
+
+#[tokio::test]
+async fn servers_sharing_a_database_run_a_due_job_once() {
+    let pool = test_pool();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let servers: Vec<_> = (0..3).map(|_| server(&pool, &runs, false, Duration::from_millis(100))).collect();
+    for server in &servers {
+        server.start().await.unwrap();
+    }
+
+    finished_row(&pool).await;
+    // Each server has had a look by now, and the job isn't due again for an hour.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    for server in &servers {
+        server.stop().await;
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn a_workers_lease_keeps_other_servers_out_until_released() {
+    let pool = test_pool();
+    let config = test_config(&[]);
+    let (one, two) = (Leases::new(&config, pool.clone()), Leases::new(&config, pool.clone()));
+    let (mut first, mut second) = (one.lease("webhook-dispatcher"), two.lease("webhook-dispatcher"));
+
+    assert!(first.hold().await);
+    assert!(!second.hold().await);
+    assert!(first.hold().await, "holding it again renews it");
+
+    first.release().await;
+    assert!(second.hold().await);
+    assert!(!one.acquire("webhook-dispatcher").await.unwrap());
+}
+
+#[tokio::test]
+async fn admins_can_list_jobs_and_run_them_now() {
+    let app = TestApp::new().await;
+    let id = app.sign_in_as("ann", "ann@example.com").await;
+    diesel::update(users::table.find(&id))
+        .set(users::is_admin.eq(true))
+        .execute(&mut app.conn())
+        .unwrap();
+
+    let list = app.get("/api/v1/admin/jobs").await;
+    assert_eq!(list.status, StatusCode::OK, "{}", list.body);
+    let names: Vec<_> = list.data().as_array().unwrap().iter().map(|job| job["name"].as_str().unwrap().to_string()).collect();
+    assert_eq!(names, ["scheduled-publish", "digests", "token-purge", "retention", "backups"]);
+    assert_eq!(list.data()[3]["schedule"], "daily at 03:00 UTC");
+
+    let run = app.post("/api/v1/admin/jobs/token-purge/run", serde_json::json!({})).await;
+    assert_eq!(run.status, StatusCode::ACCEPTED, "{}", run.body);
+    let row = Jobs::by_name(&mut app.conn(), "token-purge").unwrap().unwrap();
+    assert!(row.next_run_at.is_some_and(|at| at <= Utc::now().naive_utc()));
+
+    let missing = app.post("/api/v1/admin/jobs/nope/run", serde_json::json!({})).await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+
+    let retention = app.get("/api/v1/admin/retention").await;
+    assert_eq!(retention.status, StatusCode::OK, "{}", retention.body);
+    assert_eq!(retention.data()["name"], "retention");
+    assert!(retention.data()["tables"]["audit_logs"].is_object());
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use http::StatusCode;
+use serde_json::json;
+use tsumi::services::seed::SEED_PASSWORD;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn seeding_fills_a_development_database_once() {
+    let app = TestApp::with_settings(&[("APP_ENV", "development")]).await;
+
+    let seeded = app.post("/api/v1/dev/seed", json!({})).await;
+    assert_eq!(seeded.status, StatusCode::OK, "{}", seeded.body);
+    assert_eq!(seeded.data()["users"], 4);
+    assert_eq!(seeded.data()["posts"], 6);
+    assert!(seeded.data()["versions"].as_u64().unwrap() > 6);
+
+    let again = app.post("/api/v1/dev/seed", json!({})).await;
+    assert_eq!(again.status, StatusCode::CONFLICT);
+
+    let signin = app.post("/api/v1/auth/signin", json!({ "email": "ada@example.com", "password": SEED_PASSWORD })).await;
+    assert_eq!(signin.status, StatusCode::OK, "{}", signin.body);
+    let posts = app.get("/api/v1/me/posts").await;
+    assert_eq!(posts.data()["total"], 2);
+}
+
+#[tokio::test]
+async fn seeding_is_only_mounted_in_development() {
+    let app = TestApp::new().await;
+    let seeded = app.post("/api/v1/dev/seed", json!({})).await;
+    assert_eq!(seeded.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn series_link_their_published_parts_in_order() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let series = app.post("/api/v1/series", json!({ "title": "Learning Rust" })).await;
+    assert_eq!(series.status, StatusCode::OK, "{}", series.body);
+    assert_eq!(series.data()["slug"], "learning-rust");
+    let series_id = series.data()["id"].as_str().unwrap().to_string();
+
+    let mut ids = Vec::new();
+    for (title, published) in [("Ownership", true), ("Borrowing", false), ("Lifetimes", true)] {
+        let post = json!({ "title": title, "content": title, "is_published": published, "series_id": series_id });
+        let created = app.post("/api/v1/posts", post).await;
+        assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+        ids.push(created.data()["id"].as_str().unwrap().to_string());
+    }
+
+    let unknown = app.post("/api/v1/posts", json!({ "title": "Stray", "content": "x", "series_id": "nope" })).await;
+    assert_eq!(unknown.status, StatusCode::BAD_REQUEST);
+    assert_eq!(unknown.body["error"]["details"]["fields"][0]["field"], "series_id");
+
+    // The draft in the middle is skipped for readers.
+    let last = app.get(&format!("/api/v1/posts/{}", ids[2])).await;
+    assert_eq!(last.data()["series"]["part"], 2);
+    assert_eq!(last.data()["series"]["parts"], 2);
+    assert_eq!(last.data()["series"]["prev"]["slug"], "ownership");
+    assert!(last.data()["series"]["next"].is_null());
+
+    let page = app.get("/ann/ownership").await;
+    assert_eq!(page.status, StatusCode::OK);
+    assert!(page.text.contains(r#"href="/ann/lifetimes""#), "{}", page.text);
+
+    let reordered = app
+        .send(Method::PUT, &format!("/api/v1/series/{}/posts", series_id), Some(json!({ "post_ids": [ids[2], ids[0]] })))
+        .await;
+    assert_eq!(reordered.status, StatusCode::OK, "{}", reordered.body);
+    let titles: Vec<&str> = reordered.data()["posts"].as_array().unwrap().iter().map(|post| post["title"].as_str().unwrap()).collect();
+    assert_eq!(titles, ["Lifetimes", "Ownership"]);
+    assert!(app.get(&format!("/api/v1/posts/{}", ids[1])).await.data().get("series").is_none());
+
+    app.clear_cookies();
+    let public = app.get(&format!("/api/v1/series/{}", series_id)).await;
+    assert_eq!(public.status, StatusCode::OK);
+    assert_eq!(public.data()["posts"].as_array().unwrap().len(), 2);
+
+    app.sign_in("ann@example.com").await;
+    let deleted = app.send(Method::DELETE, &format!("/api/v1/series/{}", series_id), None).await;
+    assert_eq!(deleted.status, StatusCode::OK, "{}", deleted.body);
+    assert!(app.get(&format!("/api/v1/posts/{}", ids[0])).await.data().get("series").is_none());
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use http::header::{ETAG, IF_NONE_MATCH};
+use http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn the_sitemap_is_reused_until_posts_change() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+    let first = app.post("/api/v1/posts", json!({ "title": "First", "content": "x", "is_published": true })).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+
+    let sitemap = app.get("/sitemap.xml").await;
+    assert_eq!(sitemap.status, StatusCode::OK);
+    assert!(sitemap.text.contains("/ann/first</loc>"), "{}", sitemap.text);
+    let etag = sitemap.headers[ETAG].to_str().unwrap().to_string();
+
+    let unchanged = app.get_with_headers("/sitemap.xml", &[(IF_NONE_MATCH.as_str(), &etag)]).await;
+    assert_eq!(unchanged.status, StatusCode::NOT_MODIFIED);
+    assert!(unchanged.text.is_empty());
+
+    let draft = app.post("/api/v1/posts", json!({ "title": "Draft", "content": "x" })).await;
+    assert_eq!(draft.status, StatusCode::OK, "{}", draft.body);
+    let still_unchanged = app.get_with_headers("/sitemap.xml", &[(IF_NONE_MATCH.as_str(), &etag)]).await;
+    assert_eq!(still_unchanged.status, StatusCode::NOT_MODIFIED, "drafts aren't in the sitemap");
+
+    let id = draft.data()["id"].as_str().unwrap();
+    let published = app.send(Method::POST, &format!("/api/v1/posts/{}/publish", id), Some(json!({}))).await;
+    assert_eq!(published.status, StatusCode::OK, "{}", published.body);
+    let changed = app.get_with_headers("/sitemap.xml", &[(IF_NONE_MATCH.as_str(), &etag)]).await;
+    assert_eq!(changed.status, StatusCode::OK);
+    assert!(changed.text.contains("/ann/draft</loc>"), "{}", changed.text);
+    assert_ne!(changed.headers[ETAG].to_str().unwrap(), etag);
+
+    assert_eq!(app.get("/sitemaps/1.xml").await.status, StatusCode::OK);
+    assert_eq!(app.get("/sitemaps/2.xml").await.status, StatusCode::NOT_FOUND);
+}
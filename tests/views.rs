@@ -0,0 +1,50 @@
+mod common;
+
+use http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+const BROWSER: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0";
+
+#[tokio::test]
+async fn readers_are_counted_and_bots_are_not() {
+    let app = TestApp::with_settings(&[("VIEW_FLUSH_BATCH_SIZE", "1")]).await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    let quiet = app.post("/api/v1/posts", json!({ "title": "Quiet", "content": "x", "is_published": true })).await;
+    assert_eq!(quiet.status, StatusCode::OK, "{}", quiet.body);
+    let busy = app.post("/api/v1/posts", json!({ "title": "Busy", "content": "x", "is_published": true })).await;
+    let busy_id = busy.data()["id"].as_str().unwrap().to_string();
+    app.clear_cookies();
+
+    app.set_user_agent(BROWSER);
+    for _ in 0..3 {
+        assert_eq!(app.get("/ann/busy").await.status, StatusCode::OK);
+    }
+    app.get("/ann/quiet").await;
+    app.get("/ann/missing").await;
+
+    app.set_user_agent("Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)");
+    app.get("/ann/quiet").await;
+    app.get("/ann/quiet").await;
+
+    let post = app.get(&format!("/api/public/v1/posts/{}", busy_id)).await;
+    assert_eq!(post.status, StatusCode::OK, "{}", post.body);
+    assert_eq!(post.data()["views"], 3);
+
+    let popular = app.get("/api/public/v1/posts/popular?window=7d").await;
+    assert_eq!(popular.status, StatusCode::OK, "{}", popular.body);
+    let titles: Vec<&str> = popular.data().as_array().unwrap().iter().map(|post| post["title"].as_str().unwrap()).collect();
+    assert_eq!(titles, ["Busy", "Quiet"]);
+    assert_eq!(popular.data()[0]["window_views"], 3);
+    assert_eq!(popular.data()[1]["views"], 1);
+
+    let invalid = app.get("/api/public/v1/posts/popular?window=forever").await;
+    assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+    assert_eq!(invalid.body["error"]["details"]["fields"][0]["field"], "window");
+
+    app.sign_in("ann@example.com").await;
+    let own = app.get(&format!("/api/v1/posts/{}", busy_id)).await;
+    assert_eq!(own.data()["views"], 3);
+}
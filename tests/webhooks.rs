@@ -0,0 +1,20 @@
+mod common;
+
+use http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn webhooks_cannot_point_at_the_servers_own_network() {
+    let app = TestApp::new().await;
+    app.sign_in_as("ann", "ann@example.com").await;
+
+    for url in ["https://127.0.0.1/hook", "https://localhost/hook", "https://169.254.169.254/latest", "https://[fd00::1]/hook"] {
+        let created = app.post("/api/v1/me/webhooks", json!({ "url": url, "events": ["post.published"] })).await;
+        assert_eq!(created.status, StatusCode::BAD_REQUEST, "{}: {}", url, created.body);
+    }
+
+    let public = app.post("/api/v1/me/webhooks", json!({ "url": "https://93.184.215.14/hook", "events": ["post.published"] })).await;
+    assert_eq!(public.status, StatusCode::OK, "{}", public.body);
+}
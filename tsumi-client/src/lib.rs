@@ -0,0 +1,350 @@
+//! Typed bindings for the tsumi HTTP API.
+//!
+//! ```no_run
+//! # async fn run() -> tsumi_client::Result<()> {
+//! use tsumi_client::{Client, CreatePostRequest};
+//!
+//! let client = Client::new("https://tsumi.example")?.with_token("tsumi_...");
+//! let post = client
+//!     .create_post(&CreatePostRequest {
+//!         title: "Hello".into(),
+//!         content: "# Hello".into(),
+//!         ..Default::default()
+//!     })
+//!     .await?;
+//! client.publish_post(&post.id, None).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::{Arc, RwLock};
+
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::{Method, RequestBuilder, Url};
+use serde::de::DeserializeOwned;
+
+mod error;
+
+pub use error::{ClientError, Result};
+// Request and response bodies are the server's own, so the two never drift apart.
+pub use tsumi_types::*;
+
+/// Where the JSON API lives relative to the site root passed to [`Client::new`].
+const API_PATH: &str = "api/v1";
+
+/// An API client. Cloning is cheap and clones share the cookie jar and bearer token.
+///
+/// Signing in stores the session cookies like a browser would; alternatively a personal
+/// access token can be supplied with [`Client::with_token`] for bots and scripts. Changes
+/// made on the strength of the cookies carry the CSRF token, which is fetched when first needed.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: Url,
+    token: Arc<RwLock<Option<String>>>,
+    csrf: Arc<RwLock<Option<String>>>,
+}
+
+impl Client {
+    pub fn new(base_url: &str) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .cookie_store(true)
+            .user_agent(concat!("tsumi-client/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Self::with_http_client(base_url, http)
+    }
+
+    /// Uses a preconfigured `reqwest::Client`. Enable its cookie store if you intend to sign in
+    /// with a password rather than a token.
+    pub fn with_http_client(base_url: &str, http: reqwest::Client) -> Result<Self> {
+        let base_url = Url::parse(&format!("{}/{}/", base_url.trim_end_matches('/'), API_PATH))
+            .map_err(|e| ClientError::InvalidUrl(e.to_string()))?;
+
+        Ok(Self {
+            http,
+            base_url,
+            token: Arc::new(RwLock::new(None)),
+            csrf: Arc::new(RwLock::new(None)),
+        })
+    }
+
+    /// Sends `token` (an access token or a `tsumi_` personal access token) as a bearer token.
+    pub fn with_token(self, token: impl Into<String>) -> Self {
+        self.set_token(Some(token.into()));
+        self
+    }
+
+    pub fn set_token(&self, token: Option<String>) {
+        *self.token.write().unwrap_or_else(|e| e.into_inner()) = token;
+    }
+
+    // Auth
+
+    pub async fn sign_up(&self, request: &SignUpRequest) -> Result<UserDto> {
+        self.send(self.request(Method::POST, "auth/signup")?.json(request)).await
+    }
+
+    pub async fn sign_in(&self, request: &SignInRequest) -> Result<SignInResponse> {
+        self.send(self.request(Method::POST, "auth/signin")?.json(request)).await
+    }
+
+    /// Exchanges the refresh token cookie for a new access token, which is then used as the
+    /// bearer token for subsequent requests.
+    pub async fn refresh(&self) -> Result<RefreshResponse> {
+        let response: RefreshResponse = self.send(self.request(Method::POST, "auth/refresh")?).await?;
+        self.set_token(Some(response.access_token.clone()));
+        Ok(response)
+    }
+
+    pub async fn sign_out(&self) -> Result<SignOutResponse> {
+        let response = self.send(self.request(Method::POST, "auth/signout")?).await?;
+        self.set_token(None);
+        Ok(response)
+    }
+
+    /// Ends every session the user has and revokes the access tokens issued to them.
+    pub async fn sign_out_all(&self) -> Result<SignOutAllResponse> {
+        let response = self.send(self.request(Method::POST, "auth/signout-all")?).await?;
+        self.set_token(None);
+        Ok(response)
+    }
+
+    // Posts
+
+    pub async fn create_post(&self, request: &CreatePostRequest) -> Result<PostDto> {
+        self.send(self.request(Method::POST, "posts")?.json(request)).await
+    }
+
+    pub async fn get_post(&self, id: &str) -> Result<PostDto> {
+        self.send(self.request(Method::GET, &format!("posts/{}", segment(id)))?).await
+    }
+
+    pub async fn update_post(&self, id: &str, request: &UpdatePostRequest) -> Result<PostDto> {
+        self.send(self.request(Method::PATCH, &format!("posts/{}", segment(id)))?.json(request)).await
+    }
+
+    pub async fn delete_post(&self, id: &str) -> Result<DeletePostResponse> {
+        self.send(self.request(Method::DELETE, &format!("posts/{}", segment(id)))?).await
+    }
+
+    /// Publishes the post now, or schedules it when `publish_at` is in the future.
+    pub async fn publish_post(&self, id: &str, publish_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<PostDto> {
+        let request = PublishPostRequest { publish_at };
+        self.send(self.request(Method::POST, &format!("posts/{}/publish", segment(id)))?.json(&request)).await
+    }
+
+    pub async fn unpublish_post(&self, id: &str) -> Result<PostDto> {
+        self.send(self.request(Method::POST, &format!("posts/{}/unpublish", segment(id)))?).await
+    }
+
+    /// The signed-in user's posts. Sort by `updated_at`, `created_at`, `published_at` or `title`.
+    pub async fn my_posts(&self, options: &ListQuery, filter: &ListMyPostsQuery) -> Result<Paginated<PostDto>> {
+        self.send(self.request(Method::GET, "me/posts")?.query(options).query(filter)).await
+    }
+
+    pub async fn post_versions(&self, id: &str) -> Result<Vec<PostVersionDto>> {
+        let list: ListPostVersionsResponse = self.send(self.request(Method::GET, &format!("posts/{}/versions", segment(id)))?).await?;
+        Ok(list.versions)
+    }
+
+    pub async fn collaborators(&self, post_id: &str) -> Result<Vec<CollaboratorResponse>> {
+        let list: CollaboratorsResponse = self.send(self.request(Method::GET, &format!("posts/{}/collaborators", segment(post_id)))?).await?;
+        Ok(list.collaborators)
+    }
+
+    /// Invites `username` to a post as an `editor` or a `viewer`, or changes their role if
+    /// they're already on it. Returns everyone on the post.
+    pub async fn invite_collaborator(&self, post_id: &str, username: &str, role: &str) -> Result<Vec<CollaboratorResponse>> {
+        let request = InviteCollaboratorRequest { username: username.to_string(), role: Some(role.to_string()) };
+        let list: CollaboratorsResponse =
+            self.send(self.request(Method::POST, &format!("posts/{}/collaborators", segment(post_id)))?.json(&request)).await?;
+        Ok(list.collaborators)
+    }
+
+    pub async fn remove_collaborator(&self, post_id: &str, user_id: &str) -> Result<Vec<CollaboratorResponse>> {
+        let list: CollaboratorsResponse =
+            self.send(self.request(Method::DELETE, &format!("posts/{}/collaborators/{}", segment(post_id), segment(user_id)))?).await?;
+        Ok(list.collaborators)
+    }
+
+    /// Reacts to a published post, replacing any earlier reaction. Reactions are `like`,
+    /// `love`, `celebrate` and `insightful`.
+    pub async fn react(&self, post_id: &str, reaction: &str) -> Result<ReactionResponse> {
+        let request = ReactPostRequest { reaction: Some(reaction.to_string()) };
+        self.send(self.request(Method::POST, &format!("posts/{}/react", segment(post_id)))?.json(&request)).await
+    }
+
+    pub async fn unreact(&self, post_id: &str) -> Result<ReactionResponse> {
+        self.send(self.request(Method::DELETE, &format!("posts/{}/react", segment(post_id)))?).await
+    }
+
+    pub async fn reacted_posts(&self, page: i64) -> Result<ListReactedPostsResponse> {
+        self.send(self.request(Method::GET, "me/reactions")?.query(&[("page", page)])).await
+    }
+
+    pub async fn series_list(&self) -> Result<Vec<SeriesResponse>> {
+        let list: ListSeriesResponse = self.send(self.request(Method::GET, "series")?).await?;
+        Ok(list.series)
+    }
+
+    /// A series and its posts. Only its author sees the unpublished ones.
+    pub async fn series(&self, id: &str) -> Result<SeriesResponse> {
+        self.send(self.request(Method::GET, &format!("series/{}", segment(id)))?).await
+    }
+
+    pub async fn create_series(&self, request: &CreateSeriesRequest) -> Result<SeriesResponse> {
+        self.send(self.request(Method::POST, "series")?.json(request)).await
+    }
+
+    pub async fn update_series(&self, id: &str, request: &UpdateSeriesRequest) -> Result<SeriesResponse> {
+        self.send(self.request(Method::PATCH, &format!("series/{}", segment(id)))?.json(request)).await
+    }
+
+    /// Sets the series' posts, in reading order.
+    pub async fn set_series_posts(&self, id: &str, post_ids: Vec<String>) -> Result<SeriesResponse> {
+        let request = SetSeriesPostsRequest { post_ids };
+        self.send(self.request(Method::PUT, &format!("series/{}/posts", segment(id)))?.json(&request)).await
+    }
+
+    pub async fn delete_series(&self, id: &str) -> Result<DeleteSeriesResponse> {
+        self.send(self.request(Method::DELETE, &format!("series/{}", segment(id)))?).await
+    }
+
+    /// Tags in use on published posts, optionally limited to names starting with `prefix`.
+    /// Sort by `name` or `posts`.
+    pub async fn tags(&self, options: &ListQuery, prefix: Option<&str>) -> Result<Paginated<TagResponse>> {
+        let mut builder = self.request(Method::GET, "tags")?.query(options);
+        if let Some(prefix) = prefix {
+            builder = builder.query(&[("q", prefix)]);
+        }
+        self.send(builder).await
+    }
+
+    // Account
+
+    pub async fn profile(&self) -> Result<ProfileResponse> {
+        self.send(self.request(Method::GET, "me")?).await
+    }
+
+    pub async fn update_profile(&self, request: &UpdateProfileRequest) -> Result<ProfileResponse> {
+        self.send(self.request(Method::PATCH, "me")?.json(request)).await
+    }
+
+    /// A user's profile. Private ones are only found when signed in.
+    pub async fn user(&self, username: &str) -> Result<PublicProfileResponse> {
+        self.send(self.request(Method::GET, &format!("users/{}", segment(username)))?).await
+    }
+
+    /// The signed-in user's active sessions. Sort by `created_at` or `expires_at`.
+    pub async fn sessions(&self, options: &ListQuery) -> Result<Paginated<SessionDto>> {
+        self.send(self.request(Method::GET, "me/sessions")?.query(options)).await
+    }
+
+    /// Sets the signed-in user's avatar from a PNG, JPEG or WebP image.
+    pub async fn update_avatar(&self, content_type: &str, bytes: Vec<u8>) -> Result<AvatarResponse> {
+        let part = reqwest::multipart::Part::bytes(bytes).file_name("avatar").mime_str(content_type)?;
+        let form = reqwest::multipart::Form::new().part("file", part);
+        self.send(self.request(Method::PUT, "me/avatar")?.multipart(form)).await
+    }
+
+    /// Removes the uploaded avatar, going back to the GitHub avatar or Gravatar.
+    pub async fn delete_avatar(&self) -> Result<AvatarResponse> {
+        self.send(self.request(Method::DELETE, "me/avatar")?).await
+    }
+
+    // Uploads
+
+    /// Uploads an image for use as a post cover or inline in post content.
+    pub async fn upload(&self, file_name: &str, content_type: &str, bytes: Vec<u8>) -> Result<UploadResponse> {
+        let part = reqwest::multipart::Part::bytes(bytes)
+            .file_name(file_name.to_string())
+            .mime_str(content_type)?;
+        let form = reqwest::multipart::Form::new().part("file", part);
+        self.send(self.request(Method::POST, "uploads")?.multipart(form)).await
+    }
+
+    // Comments
+
+    pub async fn comments(&self, post_id: &str, page: i64) -> Result<ListCommentsResponse> {
+        let path = format!("posts/{}/comments", segment(post_id));
+        self.send(self.request(Method::GET, &path)?.query(&[("page", page)])).await
+    }
+
+    pub async fn create_comment(&self, post_id: &str, request: &CreateCommentRequest) -> Result<CommentResponse> {
+        self.send(self.request(Method::POST, &format!("posts/{}/comments", segment(post_id)))?.json(request)).await
+    }
+
+    pub async fn update_comment(&self, id: &str, request: &UpdateCommentRequest) -> Result<CommentResponse> {
+        self.send(self.request(Method::PATCH, &format!("comments/{}", segment(id)))?.json(request)).await
+    }
+
+    pub async fn delete_comment(&self, id: &str) -> Result<DeleteCommentResponse> {
+        self.send(self.request(Method::DELETE, &format!("comments/{}", segment(id)))?).await
+    }
+
+    /// `path` is relative to the API root; ids and names in it go through [`segment`].
+    fn request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        let url = self.base_url.join(path).map_err(|e| ClientError::InvalidUrl(e.to_string()))?;
+        let builder = self.http.request(method, url);
+
+        let token = self.token.read().unwrap_or_else(|e| e.into_inner()).clone();
+        Ok(match token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        })
+    }
+
+    /// The token cookie-authenticated changes echo back, from `auth/csrf` the first time.
+    pub async fn csrf_token(&self) -> Result<String> {
+        if let Some(token) = self.csrf.read().unwrap_or_else(|e| e.into_inner()).clone() {
+            return Ok(token);
+        }
+        let response: CsrfResponse = self.execute(self.request(Method::GET, "auth/csrf")?.build()?).await?;
+        *self.csrf.write().unwrap_or_else(|e| e.into_inner()) = Some(response.csrf_token.clone());
+        Ok(response.csrf_token)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, builder: RequestBuilder) -> Result<T> {
+        let mut request = builder.build()?;
+        if !request.method().is_safe() && !request.headers().contains_key(AUTHORIZATION) {
+            // A token that isn't a valid header value is left off and the server says no.
+            if let Ok(token) = HeaderValue::from_str(&self.csrf_token().await?) {
+                request.headers_mut().insert("x-csrf-token", token);
+            }
+        }
+        self.execute(request).await
+    }
+
+    async fn execute<T: DeserializeOwned>(&self, request: reqwest::Request) -> Result<T> {
+        let response = self.http.execute(request).await?;
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response.json::<ApiResponse<T>>().await?.data);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let (code, message) = match serde_json::from_str::<ErrorResponse>(&body) {
+            Ok(envelope) => (envelope.error.code, envelope.error.message),
+            Err(_) => (status.as_str().to_string(), body),
+        };
+
+        Err(ClientError::Api { status, code, message })
+    }
+}
+
+/// Percent-encodes `value` as a single path segment, so an id or username with `/`, `?`, `#`
+/// or a `..` in it can't send the request to another endpoint.
+fn segment(value: &str) -> String {
+    if value == "." || value == ".." {
+        return value.replace('.', "%2E");
+    }
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
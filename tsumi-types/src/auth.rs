@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::users::UserDto;
+
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct SignUpRequest {
+    #[validate(length(min = 3, max = 50, message = "Username must be between 3 and 50 characters."))]
+    pub name: String,
+
+    #[validate(email(message = "Email must be a valid email."))]
+    pub email: String,
+
+    #[validate(length(min = 8, max = 128, message = "Password must be between 8 and 128 characters"))]
+    pub password: String,
+
+    /// The solved captcha, when signups need one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captcha_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct SignInRequest {
+    #[validate(email(message = "Email must be a valid email."))]
+    pub email: String,
+
+    #[validate(length(min = 8, max = 128, message = "Password must be between 8 and 128 characters"))]
+    pub password: String,
+
+    /// Keep the session for the full refresh expiry. Otherwise it lasts a few hours and its
+    /// cookie is dropped when the browser closes.
+    #[serde(default)]
+    pub remember_me: bool,
+
+    /// The page to return to, echoed back as `redirect_to` when it's on the server's site.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignInResponse {
+    pub user: UserDto,
+    pub message: String,
+    pub signed_in_at: DateTime<Utc>,
+    /// Where to send the user now: the requested `next` page, or home.
+    pub redirect_to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshResponse {
+    pub access_token: String,
+    pub message: String,
+    pub refreshed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignOutResponse {
+    pub message: String,
+    pub signed_out_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignOutAllResponse {
+    pub message: String,
+    pub sessions_revoked: usize,
+    pub signed_out_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsrfResponse {
+    pub csrf_token: String,
+    /// Where state-changing requests send the token back.
+    pub header: String,
+}
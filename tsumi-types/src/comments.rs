@@ -0,0 +1,54 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct CreateCommentRequest {
+    #[validate(length(min = 1, max = 10000, message = "Comment must be between 1 and 10000 characters"))]
+    pub body: String,
+
+    /// The comment being replied to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct UpdateCommentRequest {
+    #[validate(length(min = 1, max = 10000, message = "Comment must be between 1 and 10000 characters"))]
+    pub body: String,
+}
+
+/// A comment and its replies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentResponse {
+    pub id: String,
+    pub post_id: String,
+    pub parent_id: Option<String>,
+    /// `None` when the comment or its author's account has been deleted.
+    pub author: Option<String>,
+    pub body: Option<String>,
+    pub body_html: Option<String>,
+    pub deleted: bool,
+    /// Hidden by a moderator or the spam check. Shown in place like a deleted comment, so replies
+    /// keep their thread.
+    pub hidden: bool,
+    /// Held by the spam check for a moderator to review. Only its author sees it meanwhile.
+    pub pending_review: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub replies: Vec<CommentResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListCommentsResponse {
+    pub comments: Vec<CommentResponse>,
+    pub page: i64,
+    pub total_pages: i64,
+    pub total_threads: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteCommentResponse {
+    pub message: String,
+    pub deleted_at: DateTime<Utc>,
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+/// The success envelope: every JSON API response is `{"data": ...}`, the counterpart of the
+/// `{"error": ...}` envelope errors are sent in. Documents with a format of their own, such as
+/// NodeInfo and the OpenAPI description, are sent as they are.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn new(data: T) -> Self {
+        Self { data }
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<T: Serialize> axum::response::IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> axum::response::Response {
+        axum::Json(self).into_response()
+    }
+}
+
+/// The error envelope every failed API request gets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: ErrorDetails,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    /// E.g. `NOT_FOUND` or `REAUTH_REQUIRED`.
+    pub code: String,
+    pub message: String,
+    /// For validation failures, `{"fields": [...]}` of [`FieldError`]s.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+/// One invalid request field, sent under `details.fields` so clients can point at it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// Dotted path to the field, with list indexes in brackets, e.g. `tags[2]`.
+    pub field: String,
+    /// The failed rule, e.g. `length` or `email`.
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), code: code.into(), message: message.into() }
+    }
+}
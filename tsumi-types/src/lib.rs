@@ -0,0 +1,23 @@
+//! Request and response bodies of the tsumi JSON API, shared by the server and `tsumi-client`
+//! so both always agree on the wire format. Requests carry their `validator` rules, which the
+//! server enforces and clients may check before sending.
+//!
+//! Only the bodies themselves live here; building them from database rows is the server's job.
+
+mod auth;
+mod comments;
+mod envelope;
+mod pagination;
+mod posts;
+mod series;
+mod uploads;
+mod users;
+
+pub use auth::*;
+pub use comments::*;
+pub use envelope::*;
+pub use pagination::*;
+pub use posts::*;
+pub use series::*;
+pub use uploads::*;
+pub use users::*;
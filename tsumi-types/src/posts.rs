@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+pub static SLUG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z0-9]+(?:-[a-z0-9]+)*$").unwrap());
+
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct CreatePostRequest {
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    pub title: String,
+
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+
+    #[validate(regex(path = *SLUG_REGEX, message = "Slug may only contain lowercase letters, digits and hyphens"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+
+    #[validate(length(max = 200000, message = "Content must be at most 200000 characters"))]
+    pub content: String,
+
+    #[validate(length(max = 10, message = "A post can have at most 10 tags"))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub is_published: bool,
+
+    /// Schedules the post instead of publishing it immediately when in the future.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<DateTime<Utc>>,
+
+    /// Id of one of the author's uploads to show as the cover image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_image_id: Option<String>,
+
+    /// Id of one of the author's series to add the post to, at the end.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct UpdatePostRequest {
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[validate(regex(path = *SLUG_REGEX, message = "Slug may only contain lowercase letters, digits and hyphens"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+
+    #[validate(length(max = 200000, message = "Content must be at most 200000 characters"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    #[validate(length(max = 10, message = "A post can have at most 10 tags"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+
+    /// An empty string removes the cover image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_image_id: Option<String>,
+
+    /// Moves the post to the end of another series. An empty string takes it out of its series.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PublishPostRequest {
+    /// Schedules the post when in the future; publishes it now otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReactPostRequest {
+    /// `like`, `love`, `celebrate` or `insightful`. Defaults to a like.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reaction: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InviteCollaboratorRequest {
+    pub username: String,
+    /// `editor` or `viewer`. Defaults to editor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+}
+
+/// Filters for the author's own post list, alongside a [`ListQuery`](crate::ListQuery).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListMyPostsQuery {
+    /// `draft`, `scheduled` or `published`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+/// A post as its author sees it, drafts included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostDto {
+    pub id: String,
+    pub author_id: String,
+    pub title: String,
+    pub description: String,
+    pub slug: String,
+    pub content: String,
+    pub content_html: String,
+    pub status: String,
+    pub published_at: Option<NaiveDateTime>,
+    pub tags: Vec<String>,
+    pub cover_image_url: Option<String>,
+    /// Left out until the post has been counted, as backfilled posts may not have been yet.
+    pub word_count: Option<i32>,
+    /// At 200 words a minute, rounded up.
+    pub reading_minutes: Option<i32>,
+    /// Reaction counts by kind.
+    pub reactions: BTreeMap<String, i64>,
+    pub reaction_count: i64,
+    /// Reads of the post's page by people, not bots. Written in batches, so it lags a little.
+    pub views: i64,
+    /// Existing posts this one nearly duplicates. Only filled in when publishing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub near_duplicates: Vec<NearDuplicate>,
+    /// Where the post sits in its series, when it's in one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series: Option<SeriesNav>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl PostDto {
+    pub fn with_reactions(mut self, counts: impl IntoIterator<Item = (String, i64)>) -> Self {
+        self.reactions = counts.into_iter().collect();
+        self.reaction_count = self.reactions.values().sum();
+        self
+    }
+
+    pub fn with_views(mut self, views: i64) -> Self {
+        self.views = views;
+        self
+    }
+
+    pub fn with_near_duplicates(mut self, near_duplicates: Vec<NearDuplicate>) -> Self {
+        self.near_duplicates = near_duplicates;
+        self
+    }
+
+    pub fn with_series(mut self, series: Option<SeriesNav>) -> Self {
+        self.series = series;
+        self
+    }
+}
+
+/// A post whose content is nearly the same as the one being published.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearDuplicate {
+    pub post_id: String,
+    pub author_id: String,
+    pub title: String,
+    pub status: String,
+    /// Differing fingerprint bits out of 64; 0 means the text is effectively identical.
+    pub distance: u32,
+}
+
+/// A post's place in its series, with the parts either side of it. Only published parts
+/// count, besides the post itself, so readers are never sent to a draft.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesNav {
+    pub id: String,
+    pub title: String,
+    pub slug: String,
+    /// Counted from 1.
+    pub part: usize,
+    pub parts: usize,
+    pub prev: Option<SeriesNavPost>,
+    pub next: Option<SeriesNavPost>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesNavPost {
+    pub id: String,
+    pub title: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletePostResponse {
+    pub message: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionResponse {
+    pub post_id: String,
+    /// The caller's reaction after the request, `None` once removed.
+    pub reaction: Option<String>,
+    pub reactions: BTreeMap<String, i64>,
+    pub reaction_count: i64,
+}
+
+impl ReactionResponse {
+    pub fn new(post_id: String, reaction: Option<String>, counts: Vec<(String, i64)>) -> Self {
+        let reactions: BTreeMap<String, i64> = counts.into_iter().collect();
+        Self {
+            post_id,
+            reaction,
+            reaction_count: reactions.values().sum(),
+            reactions,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactedPost {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub slug: String,
+    pub author: String,
+    pub published_at: Option<NaiveDateTime>,
+    pub reaction: String,
+    pub reacted_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListReactedPostsResponse {
+    pub posts: Vec<ReactedPost>,
+    pub page: i64,
+    pub total_pages: i64,
+}
+
+/// Someone the author invited to a post, as an `editor` or a `viewer`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollaboratorResponse {
+    pub user_id: String,
+    pub name: String,
+    pub role: String,
+    pub added_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollaboratorsResponse {
+    pub collaborators: Vec<CollaboratorResponse>,
+}
+
+/// A saved version of a post, recorded on creation and each commit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostVersionDto {
+    pub id: String,
+    pub post_id: String,
+    pub user_id: String,
+    pub title: String,
+    pub content: String,
+    pub description: String,
+    pub commit_hash: String,
+    pub commit_message: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListPostVersionsResponse {
+    pub versions: Vec<PostVersionDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagResponse {
+    pub name: String,
+    pub post_count: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn posts_read_back_without_their_optional_sections() {
+        let body = json!({
+            "id": "p1",
+            "author_id": "u1",
+            "title": "Hello",
+            "description": "",
+            "slug": "hello",
+            "content": "hi",
+            "content_html": "<p>hi</p>\n",
+            "status": "draft",
+            "published_at": null,
+            "tags": [],
+            "cover_image_url": null,
+            "word_count": 1,
+            "reading_minutes": 1,
+            "reactions": { "like": 2 },
+            "reaction_count": 2,
+            "views": 0,
+            "created_at": "1970-01-01T00:00:00",
+            "updated_at": "1970-01-01T00:00:00",
+        });
+
+        let post: PostDto = serde_json::from_value(body.clone()).unwrap();
+        assert!(post.near_duplicates.is_empty());
+        assert!(post.series.is_none());
+        assert_eq!(serde_json::to_value(&post).unwrap(), body);
+    }
+}
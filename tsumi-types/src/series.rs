@@ -0,0 +1,73 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::posts::SLUG_REGEX;
+
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct CreateSeriesRequest {
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    pub title: String,
+
+    /// Made from the title when left out.
+    #[validate(regex(path = *SLUG_REGEX, message = "Slug may only contain lowercase letters, digits and hyphens"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct UpdateSeriesRequest {
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[validate(regex(path = *SLUG_REGEX, message = "Slug may only contain lowercase letters, digits and hyphens"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct SetSeriesPostsRequest {
+    /// The series' posts, in reading order.
+    #[validate(length(max = 200, message = "A series can have at most 200 posts"))]
+    pub post_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesPostResponse {
+    pub id: String,
+    pub title: String,
+    pub slug: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesResponse {
+    pub id: String,
+    pub author_id: String,
+    pub title: String,
+    pub slug: String,
+    pub description: String,
+    /// In reading order. Only the author sees the parts that aren't published.
+    pub posts: Vec<SeriesPostResponse>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListSeriesResponse {
+    pub series: Vec<SeriesResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteSeriesResponse {
+    pub message: String,
+}
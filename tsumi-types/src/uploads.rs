@@ -0,0 +1,22 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// An uploaded image, for use as a post cover or inline in post content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadResponse {
+    pub id: String,
+    pub user_id: String,
+    pub content_type: String,
+    pub byte_size: i64,
+    pub sha256: String,
+    pub original_filename: Option<String>,
+    pub created_at: NaiveDateTime,
+    /// Alt text the author approved, possibly edited from the suggestion.
+    pub alt_text: Option<String>,
+    /// Machine-generated alt text awaiting the author's review.
+    pub alt_text_suggestion: Option<String>,
+    /// `None` when no suggestion was requested, otherwise `pending`, `suggested` or `failed`.
+    pub alt_text_status: Option<String>,
+    /// Where the file is served, relative to the site.
+    pub url: String,
+}
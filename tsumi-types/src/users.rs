@@ -0,0 +1,94 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// A user as the API shows them. Never carries the password hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDto {
+    pub id: String,
+    pub username: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub created_at: NaiveDateTime,
+}
+
+/// The signed-in user's own profile, with its privacy settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileResponse {
+    pub username: String,
+    pub email: String,
+    pub bio: String,
+    pub website: Option<String>,
+    pub location: Option<String>,
+    pub avatar_url: Option<String>,
+    pub profile_private: bool,
+    pub show_email: bool,
+    pub joined_at: NaiveDateTime,
+}
+
+/// Fields left out are kept as they are; an empty string clears a text field.
+#[derive(Debug, Clone, Default, Validate, Serialize, Deserialize)]
+pub struct UpdateProfileRequest {
+    #[validate(length(max = 500, message = "Bio must be at most 500 characters"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+
+    /// An `http` or `https` URL.
+    #[validate(length(max = 200, message = "Website must be at most 200 characters"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub website: Option<String>,
+
+    #[validate(length(max = 100, message = "Location must be at most 100 characters"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+
+    /// Hides the profile, email included, from signed-out viewers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_private: Option<bool>,
+
+    /// Shows the email on the profile to signed-in viewers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_email: Option<bool>,
+}
+
+/// Another user's profile, as they allow it to be seen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicProfileResponse {
+    pub name: String,
+    pub url: String,
+    /// Served by the site, never by an upstream host.
+    pub avatar_url: Option<String>,
+    pub bio: String,
+    pub website: Option<String>,
+    pub location: Option<String>,
+    /// Only sent to signed-in viewers the user shares it with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    pub joined_at: NaiveDateTime,
+    pub post_count: i64,
+    pub followers: i64,
+    pub following: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvatarResponse {
+    /// The avatar shown for the user, relative to the site.
+    pub avatar_url: String,
+    /// Whether it's one they uploaded, rather than their GitHub avatar or Gravatar.
+    pub uploaded: bool,
+}
+
+/// A signed-in session. The refresh token itself stays on the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDto {
+    pub id: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// A coarse label such as "Firefox on Linux". Missing for sessions opened before devices
+    /// were recorded.
+    pub device: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    /// Whether this is the session the request was made from.
+    pub current: bool,
+}